@@ -0,0 +1,25 @@
+//! Reading the process table the ways the table does: from scratch, refreshed in place with the
+//! usage figures, as a list with parents for a kill, and one process at a time.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use syskill::users;
+
+fn refresh(c: &mut Criterion) {
+    c.bench_function("System::new_all", |b| b.iter(System::new_all));
+
+    let mut system = System::new_all();
+    c.bench_function("refresh in place", |b| {
+        b.iter(|| system.refresh_processes_specifics(users::refresh_kind()))
+    });
+    c.bench_function("list with parents", |b| {
+        b.iter(|| system.refresh_processes_specifics(ProcessRefreshKind::new()))
+    });
+    let own = Pid::from_u32(std::process::id());
+    c.bench_function("single process", |b| {
+        b.iter(|| system.refresh_process_specifics(own, ProcessRefreshKind::new()))
+    });
+}
+
+criterion_group!(benches, refresh);
+criterion_main!(benches);
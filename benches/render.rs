@@ -0,0 +1,58 @@
+//! Drawing the table of a few thousand processes, of which only a screenful is in sight.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ratatui::{backend::TestBackend, Terminal};
+use syskill::snapshot::{ProcessSample, Snapshot};
+use syskill::App;
+
+const PROCESSES: u32 = 5000;
+
+fn snapshot() -> Snapshot {
+    let processes = (1..=PROCESSES)
+        .map(|pid| ProcessSample {
+            pid,
+            name: format!("worker-{}", pid % 97),
+            cpu_usage: (pid % 400) as f32 / 4.0,
+            memory: u64::from(pid) * 4096,
+            virtual_memory: u64::from(pid) * 65536,
+            shared_memory: None,
+            swap: None,
+            exe: format!("/usr/lib/worker/worker-{}", pid % 97),
+            command: format!("/usr/lib/worker/worker-{} --id {pid}", pid % 97),
+            parent: Some(pid / 10),
+            start_time: 1_760_000_000 + u64::from(pid),
+            user: "root".to_string(),
+            state: "Sleeping".to_string(),
+            sched: String::new(),
+            disk_read: 0,
+            disk_written: 0,
+            threads: 1 + pid % 8,
+            fds: None,
+            tty: String::new(),
+            session: None,
+            container: String::new(),
+            cpu_time: None,
+            window: String::new(),
+            gpu: None,
+            gpu_memory: None,
+            custom: Default::default(),
+        })
+        .collect();
+    Snapshot {
+        timestamp: 1_760_623_402,
+        processes,
+        without_disk: true,
+    }
+}
+
+fn render(c: &mut Criterion) {
+    let mut app = App::new();
+    app.show_snapshot(snapshot());
+    let mut terminal = Terminal::new(TestBackend::new(180, 45)).unwrap();
+    c.bench_function("render 5000 processes", |b| {
+        b.iter(|| app.render(&mut terminal).unwrap())
+    });
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+//! Screen-reader friendly linear mode.
+//!
+//! Linear mode renders without box-drawing characters, colors or padding, marks the selected row
+//! with a plain-text prefix and keeps a one-line announcement of the selection at the top of the
+//! screen. An optional speak command receives every announcement on its standard input.
+
+use crate::app::ProcessInfo;
+use crate::units::Units;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Describes the selected row as a single sentence.
+pub fn announcement(
+    data: Option<&ProcessInfo>,
+    index: usize,
+    total: usize,
+    units: Units,
+) -> String {
+    match data {
+        Some(data) => format!(
+            "Row {} of {}: {}, PID {}, CPU {}, memory {}",
+            index + 1,
+            total,
+            data.name,
+            data.pid,
+            units.cpu(data.cpu_usage),
+            units.memory(data.memory)
+        ),
+        None => "No processes".to_string(),
+    }
+}
+
+/// Pipes `text` to `command` (run through `sh -c`) without waiting for it to finish.
+pub fn speak(command: &str, text: &str) {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    if let Ok(mut child) = child {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "{text}");
+        }
+        // Reap the speaker in the background so it doesn't linger as a zombie.
+        std::thread::spawn(move || child.wait());
+    }
+}
@@ -0,0 +1,192 @@
+//! Which cores a process runs on, and changing its CPU affinity.
+//!
+//! The kernel reports the core every thread last ran on in `/proc/<pid>/task/<tid>/stat`.
+//! Sampling that frequently for the selected process gives a per-core heat map, which makes a
+//! process saturating a single core because of a too narrow affinity mask easy to spot.
+
+use std::collections::VecDeque;
+use std::io;
+
+/// Number of samples the heat map is computed from.
+const HEAT_SAMPLES: usize = 40;
+/// Share of observations on one core above which a process counts as confined to it.
+const DOMINANT_SHARE: f64 = 0.9;
+/// CPU usage (percent of one core) above which that core counts as saturated.
+const SATURATED_CPU: f32 = 90.0;
+
+/// Rolling record of the cores the threads of one process were last seen on.
+#[derive(Default)]
+pub struct CoreHeat {
+    pid: u32,
+    samples: VecDeque<Vec<usize>>,
+}
+
+impl CoreHeat {
+    /// Takes a sample for `pid`, starting over when the process changes.
+    pub fn sample(&mut self, pid: u32) {
+        if self.pid != pid {
+            self.pid = pid;
+            self.samples.clear();
+        }
+        let cores = last_cores(pid);
+        if cores.is_empty() {
+            return;
+        }
+        if self.samples.len() == HEAT_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(cores);
+    }
+
+    /// Share of observations per core, for `cores` cores.
+    pub fn heat(&self, cores: usize) -> Vec<f64> {
+        let mut counts = vec![0usize; cores];
+        let mut total = 0;
+        for &core in self.samples.iter().flatten() {
+            if let Some(count) = counts.get_mut(core) {
+                *count += 1;
+                total += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .map(|count| count as f64 / total.max(1) as f64)
+            .collect()
+    }
+
+    /// The single core the process is confined to, if its `cpu_usage` saturates it.
+    pub fn saturated_core(&self, cores: usize, cpu_usage: f32) -> Option<usize> {
+        if cpu_usage < SATURATED_CPU || self.samples.len() < HEAT_SAMPLES / 4 {
+            return None;
+        }
+        self.heat(cores)
+            .into_iter()
+            .position(|share| share >= DOMINANT_SHARE)
+    }
+}
+
+/// Cores the threads of `pid` last ran on, one entry per thread.
+#[cfg(target_os = "linux")]
+fn last_cores(pid: u32) -> Vec<usize> {
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{pid}/task")) else {
+        return Vec::new();
+    };
+    tasks
+        .flatten()
+        .filter_map(|task| std::fs::read_to_string(task.path().join("stat")).ok())
+        .filter_map(|stat| {
+            // The command name may contain spaces, so count fields from its closing parenthesis.
+            // `processor` is field 39; the remainder starts at field 3.
+            let rest = &stat[stat.rfind(')')? + 2..];
+            rest.split_whitespace().nth(39 - 3)?.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn last_cores(_pid: u32) -> Vec<usize> {
+    Vec::new()
+}
+
+/// Cores `pid` may run on, from `Cpus_allowed_list` (e.g. `0-3,8`).
+#[cfg(target_os = "linux")]
+pub fn allowed_cores(pid: u32) -> Option<Vec<usize>> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let list = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))?;
+    parse_cores(list)
+}
+
+/// Parses a kernel CPU list such as `0-3,8`.
+pub fn parse_cores(list: &str) -> Option<Vec<usize>> {
+    let mut cores = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => cores.extend(start.parse::<usize>().ok()?..=end.parse().ok()?),
+            None => cores.push(range.parse().ok()?),
+        }
+    }
+    Some(cores)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cores(_pid: u32) -> Option<Vec<usize>> {
+    None
+}
+
+/// Allows every thread of `pid` to run on all `cores` cores.
+pub fn spread(pid: u32, cores: usize) -> Result<(), String> {
+    let all: Vec<usize> = (0..cores).collect();
+    set_cores(pid, &all).map_err(|e| e.to_string())
+}
+
+/// Parses the cores typed into the priority editor, e.g. `0-3,8`, checking that `available`
+/// cores has them.
+pub fn parse_chosen_cores(input: &str, available: usize) -> Result<Vec<usize>, String> {
+    let cores = parse_cores(input)
+        .filter(|cores| !cores.is_empty())
+        .ok_or_else(|| format!("Not a list of cores: {}", input.trim()))?;
+    match cores.iter().find(|&&core| core >= available) {
+        Some(core) => Err(format!(
+            "There is no cpu{core}, this system has {available} cores"
+        )),
+        None => Ok(cores),
+    }
+}
+
+/// Restricts every thread of `pid` to `cores`, like `taskset -a -p`.
+#[cfg(target_os = "linux")]
+pub fn set_cores(pid: u32, cores: &[usize]) -> io::Result<()> {
+    // SAFETY: cpu_set_t is plain data, valid when zeroed.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &core in cores
+        .iter()
+        .filter(|&&core| core < libc::CPU_SETSIZE as usize)
+    {
+        // SAFETY: `core` is within the set.
+        unsafe { libc::CPU_SET(core, &mut set) };
+    }
+    let tids: Vec<libc::pid_t> = std::fs::read_dir(format!("/proc/{pid}/task"))?
+        .flatten()
+        .filter_map(|task| task.file_name().to_str()?.parse().ok())
+        .collect();
+    for tid in tids {
+        // SAFETY: `set` is a valid cpu_set_t of the given size.
+        let result =
+            unsafe { libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+        let error = io::Error::last_os_error();
+        // Threads may exit in between.
+        if result == -1 && error.raw_os_error() != Some(libc::ESRCH) {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_cores(_pid: u32, _cores: &[usize]) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Formats core numbers as a compact list like `0-3,8`.
+pub fn format_cores(cores: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &core in cores {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == core => *end = core,
+            _ => ranges.push((core, core)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,189 @@
+//! Alerts: CPU and memory thresholds for every process or those of a name, so a runaway process
+//! gets noticed while syskill runs in another pane.
+//!
+//! ```toml
+//! desktop_notifications = true
+//!
+//! [[alert]]
+//! max_cpu = 200
+//!
+//! [[alert]]
+//! process = "java*"
+//! max_cpu = 90
+//! max_memory_mib = 4096
+//! ```
+//!
+//! `process` is a name, or a prefix followed by `*`; without it a threshold applies to every
+//! process. Processes above a threshold are highlighted in the table, and each time one goes
+//! above it is added to the alert pane and, with `desktop_notifications`, announced on the
+//! desktop. Builds without the `notifications` feature only alert in the table.
+
+use crate::app::ProcessInfo;
+use crate::proc::ProcessKey;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// How many alerts the pane keeps, the latest last.
+const KEPT_ALERTS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Threshold {
+    pub process: Option<String>,
+    /// CPU usage in percent of one core.
+    pub max_cpu: Option<f32>,
+    pub max_memory_mib: Option<u64>,
+}
+
+impl Threshold {
+    /// Rejects thresholds that could never go off.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_cpu.is_none() && self.max_memory_mib.is_none() {
+            return Err(format!(
+                "{} needs max_cpu or max_memory_mib",
+                self.describe()
+            ));
+        }
+        Ok(())
+    }
+
+    /// E.g. `java*` or `all processes`.
+    pub fn describe(&self) -> String {
+        self.process
+            .clone()
+            .unwrap_or_else(|| "all processes".to_string())
+    }
+
+    /// E.g. `CPU above 90%, memory above 4096 MiB`.
+    pub fn limits(&self) -> String {
+        let cpu = self.max_cpu.map(|max| format!("CPU above {max}%"));
+        let memory = self
+            .max_memory_mib
+            .map(|max| format!("memory above {max} MiB"));
+        cpu.into_iter().chain(memory).collect::<Vec<_>>().join(", ")
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self.process.as_deref() {
+            None => true,
+            Some(pattern) => match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            },
+        }
+    }
+
+    /// What `data` is above, e.g. `153.2% CPU, limit 90%`; `None` if nothing.
+    fn exceeded(&self, data: &ProcessInfo) -> Option<String> {
+        if !self.matches(&data.name) {
+            return None;
+        }
+        let cpu = data.cpu_usage;
+        if let Some(max) = self.max_cpu.filter(|&max| cpu > max) {
+            return Some(format!("{cpu:.1}% CPU, limit {max}%"));
+        }
+        let mib = data.memory / (1024 * 1024);
+        self.max_memory_mib
+            .filter(|&max| mib > max)
+            .map(|max| format!("{mib} MiB, limit {max} MiB"))
+    }
+}
+
+/// A process that went above a threshold.
+pub struct Alert {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub key: ProcessKey,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Default)]
+pub struct Alerts {
+    pub thresholds: Vec<Threshold>,
+    /// Announce new alerts on the desktop as well.
+    pub notify: bool,
+    log: Vec<Alert>,
+    /// Processes above a threshold in the last check.
+    above: HashSet<ProcessKey>,
+}
+
+impl Alerts {
+    /// The processes that went above a threshold since the last check. A process alerts once
+    /// for as long as it stays above.
+    pub fn check(&mut self, processes: &[ProcessInfo], timestamp: u64) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        let mut above = HashSet::new();
+        for data in processes {
+            if data.exited.is_some() || data.members > 0 {
+                continue;
+            }
+            let Some(reason) = self
+                .thresholds
+                .iter()
+                .find_map(|threshold| threshold.exceeded(data))
+            else {
+                continue;
+            };
+            let key = data.key();
+            if !self.above.contains(&key) {
+                alerts.push(Alert {
+                    timestamp,
+                    key,
+                    name: data.name.clone(),
+                    reason,
+                });
+            }
+            above.insert(key);
+        }
+        self.above = above;
+        alerts
+    }
+
+    pub fn is_above(&self, key: &ProcessKey) -> bool {
+        self.above.contains(key)
+    }
+
+    /// Number of processes above a threshold.
+    pub fn active(&self) -> usize {
+        self.above.len()
+    }
+
+    pub fn record(&mut self, alert: Alert) {
+        if self.notify {
+            notify(&alert);
+        }
+        if self.log.len() == KEPT_ALERTS {
+            self.log.remove(0);
+        }
+        self.log.push(alert);
+    }
+
+    /// Oldest first.
+    pub fn log(&self) -> &[Alert] {
+        &self.log
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+}
+
+/// Shows `alert` as a desktop notification. It is sent from a thread of its own, as talking to
+/// the notification daemon can take a while; without one nothing is shown, the pane still has
+/// the alert.
+#[cfg(feature = "notifications")]
+fn notify(alert: &Alert) {
+    let summary = format!("{} ({})", alert.name, alert.key.pid);
+    let body = alert.reason.clone();
+    std::thread::spawn(move || {
+        let _ = notify_rust::Notification::new()
+            .appname("syskill")
+            .summary(&summary)
+            .body(&body)
+            .show();
+    });
+}
+
+#[cfg(not(feature = "notifications"))]
+fn notify(_alert: &Alert) {}
@@ -0,0 +1,5519 @@
+//! State of the interactive process table and the actions on it.
+
+use crate::config::{self, Column};
+use crate::connector;
+#[cfg(unix)]
+use crate::control;
+use crate::error::{self, Error};
+use crate::escalate;
+use crate::graphics::{self, GraphicsProtocol};
+use crate::plugin::ColumnProvider;
+use crate::proc::{self, KillError, KillRequest, KillSignal, ProcessKey};
+use crate::report::{Report, ReportFormat, WatchdogSummary};
+#[cfg(unix)]
+use crate::session;
+use crate::source::SystemSource;
+use crate::throttle::{Limit, Resource};
+use crate::ui::{Clickable, TableColors, Theme, PALETTES};
+use crate::units::Units;
+use crate::{
+    accessibility, affinity, alert, audit, breakdown, changes, clipboard, cmdline, collector,
+    consumers, container, crash, dependents, details, ephemeral, events, exits, export, group,
+    history::HistoryStore, hunt, i18n, ionice, keymap, limits, metrics, numa, oom, orphans,
+    outcomes, plugin, ports, pressure, query, queue, redact, renice, replay, respawn, sched,
+    signal, snapshot, summary, suspects, terminal, threads, throttle, tools, tree, update, users,
+    watchdog,
+};
+use ratatui::widgets::TableState;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{CpuRefreshKind, Pid, ProcessRefreshKind, RefreshKind, Signal, System, UpdateKind};
+use update::UpdateStatus;
+
+/// A row of the table: a process, or a group of them in the grouped view. The figures are kept
+/// as numbers, and only [`crate::ui`] turns them into text.
+#[derive(Clone)]
+pub(crate) struct ProcessInfo {
+    pub(crate) name: String,
+    /// 0 for a group.
+    pub(crate) pid: u32,
+    /// Percent of one core since the last refresh.
+    pub(crate) cpu_usage: f32,
+    /// Resident bytes.
+    pub(crate) memory: u64,
+    /// Bytes of address space; 0 if unknown.
+    pub(crate) virtual_memory: u64,
+    /// Resident bytes shared with other processes, and bytes swapped out; `None` if unknown.
+    pub(crate) shared_memory: Option<u64>,
+    pub(crate) swap: Option<u64>,
+    pub(crate) exe: String,
+    /// The arguments, with secrets hidden; empty if unknown or not read.
+    pub(crate) command: String,
+    /// NUMA node holding most of the process's memory; empty on single-node systems.
+    pub(crate) node: String,
+    pub(crate) parent: Option<u32>,
+    /// Seconds since the Unix epoch, 0 if unknown.
+    pub(crate) start_time: u64,
+    /// Name of the owner, empty if unknown.
+    pub(crate) user: String,
+    /// E.g. `Running` or `Zombie`; empty if unknown.
+    pub(crate) state: String,
+    /// Scheduling policy, e.g. `OTHER` or `FIFO 50`; empty if unknown.
+    pub(crate) sched: String,
+    /// Bytes read from and written to storage since the process started; the table shows the
+    /// rates between refreshes, see [`crate::history::History::io_rate`].
+    pub(crate) disk_read: u64,
+    pub(crate) disk_written: u64,
+    /// 0 if unknown.
+    pub(crate) threads: u32,
+    /// Open file descriptors; `None` if unknown.
+    pub(crate) fds: Option<u32>,
+    /// Controlling terminal, e.g. `pts/3`; empty if it has none or unknown.
+    pub(crate) tty: String,
+    /// Session ID; `None` if unknown.
+    pub(crate) session: Option<u32>,
+    /// CPU time used since the start, in milliseconds; `None` if unknown.
+    pub(crate) cpu_time: Option<u64>,
+    /// E.g. `docker:web` or `nginx.service`; empty if none or unknown.
+    pub(crate) container: String,
+    /// Title of the window of the process; empty if it has none, or off Windows.
+    pub(crate) window: String,
+    /// GPU utilization in percent and bytes of GPU memory; `None` if no GPU is used, or unknown.
+    pub(crate) gpu: Option<f32>,
+    pub(crate) gpu_memory: Option<u64>,
+    /// Values of the custom columns, by column name; empty if not read.
+    pub(crate) custom: BTreeMap<String, plugin::Value>,
+    /// When the process was found gone; it stays listed for `exited_linger`.
+    pub(crate) exited: Option<Instant>,
+    /// Number of processes a summary row of the grouped view stands for; 0 for a process.
+    pub(crate) members: usize,
+}
+
+impl From<snapshot::ProcessSample> for ProcessInfo {
+    fn from(sample: snapshot::ProcessSample) -> Self {
+        Self {
+            name: sample.name,
+            pid: sample.pid,
+            cpu_usage: sample.cpu_usage,
+            memory: sample.memory,
+            virtual_memory: sample.virtual_memory,
+            shared_memory: sample.shared_memory,
+            swap: sample.swap,
+            exe: sample.exe,
+            command: sample.command,
+            node: String::new(),
+            parent: sample.parent,
+            start_time: sample.start_time,
+            user: sample.user,
+            state: sample.state,
+            sched: sample.sched,
+            disk_read: sample.disk_read,
+            disk_written: sample.disk_written,
+            threads: sample.threads,
+            fds: sample.fds,
+            tty: sample.tty,
+            session: sample.session,
+            cpu_time: sample.cpu_time,
+            container: sample.container,
+            window: sample.window,
+            gpu: sample.gpu,
+            gpu_memory: sample.gpu_memory,
+            custom: sample.custom,
+            exited: None,
+            members: 0,
+        }
+    }
+}
+
+impl ProcessInfo {
+    pub(crate) fn key(&self) -> ProcessKey {
+        if self.members > 0 {
+            // Groups have no PID; their name keeps them selected across updates.
+            let mut hasher = DefaultHasher::new();
+            self.name.hash(&mut hasher);
+            return ProcessKey {
+                pid: 0,
+                start_time: hasher.finish(),
+            };
+        }
+        ProcessKey {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+}
+
+/// A process killed during this session.
+#[derive(Clone)]
+pub struct KillRecord {
+    pub timestamp: u64,
+    pub pid: u32,
+    pub name: String,
+}
+
+/// A signal sent during this session, shown in the detail view of its process.
+pub(crate) struct SentSignal {
+    /// E.g. `SIGTERM` or `signal 34`.
+    pub(crate) signal: String,
+    pub(crate) timestamp: u64,
+    pub(crate) at: Instant,
+    /// When an escalating kill follows up with SIGKILL, while it is pending.
+    pub(crate) kill_due: Option<Instant>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SortColumn {
+    Name,
+    Pid,
+    Cpu,
+    Memory,
+    User,
+    /// Time since the start.
+    Elapsed,
+    CpuTime,
+    /// A column added with [`crate::plugin`], as [`Column::Custom`].
+    Custom(u16),
+}
+
+impl SortColumn {
+    pub(crate) const ALL: [SortColumn; 7] = [
+        SortColumn::Name,
+        SortColumn::Pid,
+        SortColumn::Cpu,
+        SortColumn::Memory,
+        SortColumn::User,
+        SortColumn::Elapsed,
+        SortColumn::CpuTime,
+    ];
+
+    /// As written in `--sort` and `:sort`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Custom(index) => Column::Custom(index).name(),
+            Self::Name => "name",
+            Self::Pid => "pid",
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::User => "user",
+            Self::Elapsed => "elapsed",
+            Self::CpuTime => "cpu_time",
+        }
+    }
+
+    /// The action sorting by this column; custom columns are sorted by with `:sort` or a click.
+    pub(crate) fn action(self) -> Option<keymap::Action> {
+        Some(match self {
+            Self::Name => keymap::Action::SortName,
+            Self::Pid => keymap::Action::SortPid,
+            Self::Cpu => keymap::Action::SortCpu,
+            Self::Memory => keymap::Action::SortMemory,
+            Self::User => keymap::Action::SortUser,
+            Self::Elapsed => keymap::Action::SortElapsed,
+            Self::CpuTime => keymap::Action::SortCpuTime,
+            Self::Custom(_) => return None,
+        })
+    }
+}
+
+/// One level of the sort order; later keys break ties of earlier ones.
+#[derive(Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub(crate) column: SortColumn,
+    pub(crate) descending: bool,
+}
+
+impl SortKey {
+    pub(crate) fn new(column: SortColumn) -> Self {
+        Self {
+            column,
+            // Heaviest and oldest processes first.
+            descending: matches!(
+                column,
+                SortColumn::Cpu
+                    | SortColumn::Memory
+                    | SortColumn::Elapsed
+                    | SortColumn::CpuTime
+                    | SortColumn::Custom(_)
+            ),
+        }
+    }
+
+    /// Custom columns are found among `providers`, see [`plugin::providers`].
+    pub(crate) fn compare(
+        self,
+        a: &ProcessInfo,
+        b: &ProcessInfo,
+        providers: &[&dyn ColumnProvider],
+    ) -> Ordering {
+        let ordering = match self.column {
+            SortColumn::Name => a.name.cmp(&b.name),
+            SortColumn::Pid => a.pid.cmp(&b.pid),
+            SortColumn::Cpu => a.cpu_usage.total_cmp(&b.cpu_usage),
+            SortColumn::Memory => a.memory.cmp(&b.memory),
+            SortColumn::User => a.user.cmp(&b.user),
+            // Unknown start times go last, as the newest.
+            SortColumn::Elapsed => match (a.start_time, b.start_time) {
+                (0, 0) => Ordering::Equal,
+                (0, _) => Ordering::Less,
+                (_, 0) => Ordering::Greater,
+                (a, b) => b.cmp(&a),
+            },
+            SortColumn::CpuTime => a.cpu_time.cmp(&b.cpu_time),
+            // Processes without a value go last, as with CPU time.
+            SortColumn::Custom(index) => {
+                let name = providers
+                    .get(usize::from(index))
+                    .map_or("", |provider| provider.name());
+                match (a.custom.get(name), b.custom.get(name)) {
+                    (Some(a), Some(b)) => a.compare(b),
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                }
+            }
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+impl std::fmt::Display for SortKey {
+    /// As [`std::str::FromStr`] parses it, e.g. `cpu:desc`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = if self.descending { "desc" } else { "asc" };
+        write!(f, "{}:{direction}", self.column.name())
+    }
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    /// Parses `column[:asc|:desc]`, e.g. `cpu:desc`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (column, direction) = match s.split_once(':') {
+            Some((column, direction)) => (column, Some(direction)),
+            None => (s, None),
+        };
+        let custom = || match Column::named(column)? {
+            Column::Custom(index) => Some(SortColumn::Custom(index)),
+            _ => None,
+        };
+        let column = match column {
+            "mem" => SortColumn::Memory,
+            _ => SortColumn::ALL
+                .iter()
+                .copied()
+                .find(|candidate| candidate.name() == column)
+                .or_else(custom)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown column `{column}`, expected name, pid, cpu, memory, user, \
+                         elapsed, cpu_time or a custom column"
+                    )
+                })?,
+        };
+        let mut key = Self::new(column);
+        match direction {
+            None => {}
+            Some("asc") => key.descending = false,
+            Some("desc") => key.descending = true,
+            Some(direction) => {
+                return Err(format!(
+                    "unknown direction `{direction}`, expected asc or desc"
+                ))
+            }
+        }
+        Ok(key)
+    }
+}
+
+/// Ways to end or signal the selected process.
+pub(crate) enum KillAction {
+    /// SIGKILL, the `d` key.
+    Kill,
+    /// A signal chosen in the picker, by name or by number.
+    Signal(Signal),
+    SignalNumber(String),
+    /// SIGKILL to the process and all its descendants.
+    Subtree,
+    /// SIGKILL to every process in the process group of the process, e.g. a job of a shell.
+    ProcessGroup,
+    /// SIGKILL to every listed process with the name of the selected group.
+    Group,
+    /// SIGKILL to every process in the container of the selected process.
+    Container,
+    /// A signal to every process a search matched, whatever the table shows, by `:pkill`.
+    Matching {
+        pattern: String,
+        signal: Signal,
+        targets: Vec<ProcessKey>,
+    },
+    /// SIGTERM, then SIGKILL if the process is still running after the grace period.
+    Escalate(Duration),
+}
+
+impl KillAction {
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            KillAction::Kill
+            | KillAction::Subtree
+            | KillAction::ProcessGroup
+            | KillAction::Group
+            | KillAction::Container => "SIGKILL".to_string(),
+            KillAction::Signal(signal) | KillAction::Matching { signal, .. } => {
+                format!("SIG{}", signal::name(*signal))
+            }
+            KillAction::SignalNumber(number) => format!("signal {number}"),
+            KillAction::Escalate(grace) => {
+                format!("SIGTERM, then SIGKILL after {}s,", grace.as_secs_f64())
+            }
+        }
+    }
+
+    /// Pausing and resuming are harmless enough to skip the confirmation.
+    fn needs_confirmation(&self) -> bool {
+        !matches!(self, KillAction::Signal(Signal::Stop | Signal::Continue))
+    }
+}
+
+/// Labels and watch markers of a process, its own and those inherited from its ancestors.
+#[derive(Default)]
+pub(crate) struct Tags {
+    pub(crate) own: Vec<String>,
+    pub(crate) inherited: Vec<String>,
+}
+
+impl Tags {
+    /// Plain text for linear mode and copying.
+    pub(crate) fn describe(&self) -> String {
+        let mut text = self.own.join(" ");
+        if !self.inherited.is_empty() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("(from parent: {})", self.inherited.join(" ")));
+        }
+        text
+    }
+}
+
+/// The priority editor of the selected process.
+pub(crate) struct PriorityEdit {
+    /// Current nice value, or why it cannot be read.
+    pub(crate) from: Result<i32, String>,
+    pub(crate) nice: i32,
+    /// Cores the process may run on now; `None` where that is unknown and cannot be changed.
+    pub(crate) cores: Option<Vec<usize>>,
+}
+
+/// A kill waiting for the user to confirm it.
+pub(crate) struct PendingKill {
+    pub(crate) action: KillAction,
+    pub(crate) key: ProcessKey,
+    /// The PID, or how many processes a group or container kill takes.
+    pub(crate) pid: String,
+    pub(crate) name: String,
+    pub(crate) user: String,
+    /// Other processes that may be affected by the kill.
+    pub(crate) dependents: Vec<dependents::Dependent>,
+    /// The descendants or the rest of the process group a kill takes along, by PID and name.
+    pub(crate) affected: Vec<(u32, String)>,
+    /// The process group of the process, for a kill of it.
+    pub(crate) process_group: Option<u32>,
+}
+
+/// A signal the owner of a process refused, offered to send again as root.
+pub(crate) struct DeniedKill {
+    pub(crate) request: KillRequest,
+    pub(crate) name: String,
+    pub(crate) owner: String,
+    /// Why sudo could not send it either, e.g. because it wants a password.
+    pub(crate) sudo_error: Option<String>,
+}
+
+/// Panes shown to the right of the table.
+pub(crate) enum SidePane {
+    Chart,
+    Cores,
+    Numa,
+    Memory,
+    Runtime,
+    Power,
+    Log,
+}
+
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum AppState {
+    ProcessMode,
+    SearchMode,
+    PortMode,
+    JumpMode,
+    FindMode,
+    CommandMode,
+    CommandLineMode,
+    ReniceMode,
+    SignalMode,
+    SchedMode,
+    IoniceMode,
+    PriorityMode,
+    ThrottleMode,
+    ScheduleMode,
+    WatchdogMode,
+    ExitFilterMode,
+    /// Typing a search in the Environment tab of the detail view.
+    EnvSearchMode,
+    ColumnMode,
+    CopyMode,
+}
+
+pub struct App {
+    pub(crate) state: TableState,
+    pub(crate) items: Vec<ProcessInfo>,
+    pub(crate) ctx: System,
+    pub(crate) colors: TableColors,
+    pub(crate) theme: Theme,
+    /// Custom themes by name, from the config file.
+    pub(crate) themes: BTreeMap<String, config::ThemeSpec>,
+    pub(crate) units: Units,
+    pub(crate) previous_cpu: HashMap<ProcessKey, f32>,
+    pub(crate) show_popup: bool,
+    pub(crate) mode: AppState,
+    /// Row highlighted in the signal picker.
+    pub(crate) signal_choice: usize,
+    /// Index into `sched::PICKER` of the policy highlighted in the scheduling picker.
+    pub(crate) sched_choice: usize,
+    /// Index into `ionice::PICKER` of the class highlighted in the I/O priority picker.
+    pub(crate) ionice_choice: usize,
+    /// Ask before killing (`--no-confirm` turns this off).
+    pub(crate) confirm_kills: bool,
+    /// Refuse to signal, renice or otherwise change processes, only browsing and exporting.
+    pub(crate) read_only: bool,
+    /// Read-only mode was asked for with `--read-only`, so it cannot be turned off.
+    pub(crate) read_only_locked: bool,
+    pub(crate) pending_kill: Option<PendingKill>,
+    pub(crate) denied_kill: Option<DeniedKill>,
+    pub(crate) config: config::Config,
+    /// Escalating kills waiting for their process to exit, with the id of their SIGKILL in
+    /// `queue`.
+    pub(crate) escalations: Vec<(u64, Receiver<escalate::Outcome>)>,
+    /// Actions waiting to be carried out.
+    pub(crate) queue: queue::ActionQueue,
+    /// Whether the panel listing `queue` is open.
+    pub(crate) show_queue: bool,
+    /// Index into `queue` of the panel cursor.
+    pub(crate) queue_choice: usize,
+    /// A confirmed kill waiting for the delay typed into the input popup.
+    pub(crate) scheduling: Option<PendingKill>,
+    /// Fields the live collector reads, following the columns shown.
+    pub(crate) collected: Arc<Mutex<snapshot::Fields>>,
+    /// The disk totals of the processes shown were not read, see [`snapshot::Fields`].
+    pub(crate) without_disk: bool,
+    /// Rules signalling processes on their own, and what they did.
+    pub(crate) watchdog: watchdog::Watchdog,
+    /// Whether the panel with the watchdog rules and their log is open.
+    pub(crate) show_watchdog: bool,
+    /// Index into the watchdog rules of the panel cursor.
+    pub(crate) watchdog_choice: usize,
+    /// Index of the watchdog rule being edited in the input popup; `None` for a new one.
+    pub(crate) watchdog_editing: Option<usize>,
+    /// CPU and memory thresholds, the processes above them and the alerts they raised.
+    pub(crate) alerts: alert::Alerts,
+    /// Whether the alert pane is open.
+    pub(crate) show_alerts: bool,
+    /// Processes that exited while the table was open.
+    pub(crate) exits: exits::Exits,
+    /// Whether the exit log is open, its order, search and how far it is scrolled.
+    pub(crate) show_exits: bool,
+    pub(crate) exits_sort: exits::ExitSort,
+    pub(crate) exits_filter: String,
+    pub(crate) exits_scroll: u16,
+    /// What changed from the baseline to the latest refresh, and whether the panel is open.
+    pub(crate) changes: changes::Changes,
+    pub(crate) show_changes: bool,
+    /// The processes most likely behind trouble, and whether the panel is open.
+    pub(crate) suspects: suspects::Suspects,
+    pub(crate) show_suspects: bool,
+    pub(crate) consumers: consumers::Consumers,
+    pub(crate) show_consumers: bool,
+    /// The processes too short-lived for a refresh to see, and whether the panel is open.
+    pub(crate) ephemeral: ephemeral::Ephemeral,
+    pub(crate) show_ephemeral: bool,
+    /// Whether the state and user counts are shown above the table.
+    pub(crate) show_breakdown: bool,
+    /// Whether the name column shows full command lines instead of names.
+    pub(crate) full_commands: bool,
+    /// Characters of every command line scrolled out of sight on the left.
+    pub(crate) command_offset: usize,
+    /// The key bindings of the table, and the keys typed so far of a sequence.
+    pub(crate) keys: keymap::Keys,
+    /// The last search through names, command lines, environments, files and connections.
+    pub(crate) hunt: Option<hunt::Hunt>,
+    /// Hides secrets in the command lines and environments shown.
+    pub(crate) redactor: redact::Redactor,
+    /// Whether the generated list of keys is open, and how far it is scrolled.
+    pub(crate) show_help: bool,
+    pub(crate) help_scroll: u16,
+    /// Index of the count highlighted while picking one with the keyboard.
+    pub(crate) breakdown_choice: Option<usize>,
+    /// Index into `COPY_COLUMNS` of the copy mode cursor.
+    pub(crate) copy_column: usize,
+    pub(crate) input: String,
+    /// Searches submitted this session, the latest last.
+    pub(crate) search_history: Vec<String>,
+    /// While going through `search_history` in the search box, the index shown and the search
+    /// typed before.
+    history_choice: Option<(usize, String)>,
+    pub(crate) character_index: usize,
+    pub(crate) kills: Vec<KillRecord>,
+    /// Killed processes watched for coming back.
+    respawns: respawn::Respawns,
+    /// System-wide figures shown above the table; only of this machine, so not when attached.
+    pub(crate) summary: Option<summary::Summary>,
+    summary_sampler: summary::Sampler,
+    /// Flags of the processes holding a large share of the open files or threads, by PID.
+    heavy: HashMap<u32, Vec<String>>,
+    /// Every signal sent this session, by process.
+    pub(crate) sent_signals: HashMap<ProcessKey, Vec<SentSignal>>,
+    pub(crate) notice: Option<String>,
+    /// Shown instead of the notice until the next key press.
+    pub(crate) error: Option<Error>,
+    /// Every notice and error shown on the status line, and whether the history of them is open
+    /// and how far it is scrolled.
+    pub(crate) outcomes: outcomes::Outcomes,
+    pub(crate) show_outcomes: bool,
+    pub(crate) outcomes_scroll: u16,
+    /// Where the signals sent are logged for auditing, by whom, and the entries of the log while
+    /// it is open and how far it is scrolled.
+    pub(crate) audit_path: Option<std::path::PathBuf>,
+    pub(crate) audit_sender: (String, String),
+    pub(crate) audit_entries: Option<Vec<audit::Entry>>,
+    pub(crate) audit_scroll: u16,
+    pub(crate) show_about: bool,
+    /// Priority change of the searched processes, waiting for confirmation or showing failures.
+    pub(crate) renice: Option<renice::RenicePlan>,
+    /// Nice value and cores being chosen for the selected process, with the core list typed into
+    /// `input`.
+    pub(crate) priority: Option<PriorityEdit>,
+    /// Detail view of one process, opened with Enter.
+    pub(crate) details: Option<details::Details>,
+    /// Lines scrolled down in the detail view.
+    pub(crate) details_scroll: u16,
+    pub(crate) details_tab: details::Tab,
+    /// Search of the Environment tab, and whether its secrets are shown.
+    pub(crate) env_search: String,
+    pub(crate) env_revealed: bool,
+    /// TID of the thread of the detail view the signal picker is open for.
+    pub(crate) signal_thread: Option<u32>,
+    pub(crate) update_status: UpdateStatus,
+    pub(crate) update_rx: Option<Receiver<UpdateStatus>>,
+    pub(crate) linear: bool,
+    pub(crate) speak_command: Option<String>,
+    pub(crate) hyperlinks: bool,
+    pub(crate) exe_url: String,
+    pub(crate) history: HistoryStore,
+    pub(crate) show_chart: bool,
+    pub(crate) show_cores: bool,
+    pub(crate) core_heat: affinity::CoreHeat,
+    pub(crate) show_numa: bool,
+    pub(crate) show_memory: bool,
+    pub(crate) show_runtime: bool,
+    pub(crate) show_power: bool,
+    pub(crate) show_log: bool,
+    pub(crate) tool_rx: Option<Receiver<tools::ToolOutput>>,
+    pub(crate) tool_output: Option<tools::ToolOutput>,
+    /// Last command run at the command prompt, offered again when it is reopened.
+    pub(crate) last_command: String,
+    pub(crate) command_rx: Option<Receiver<tools::ToolOutput>>,
+    /// Output of the command run at the prompt, shown in a popup.
+    pub(crate) command_output: Option<tools::ToolOutput>,
+    /// Lines scrolled down in the command output.
+    pub(crate) command_scroll: u16,
+    pub(crate) orphan_watch: Option<orphans::OrphanWatch>,
+    /// Children that outlived a killed parent, awaiting confirmation to kill them too.
+    pub(crate) orphans: Vec<(ProcessKey, String)>,
+    /// Only processes of this user are listed (`--user`/`--mine`).
+    pub(crate) user: Option<users::UserFilter>,
+    /// Snapshots from the background collector.
+    pub(crate) live: Option<collector::Live>,
+    /// Between the collector's snapshots; zero when they are only made on refresh.
+    pub(crate) refresh_interval: Duration,
+    /// A manual refresh asked the collector for a snapshot, shown even while paused.
+    refresh_asked: bool,
+    /// Why the last update failed; the previous data stays on screen meanwhile.
+    pub(crate) collect_error: Option<String>,
+    /// Timestamp of the last remote snapshot shown.
+    #[cfg(unix)]
+    pub(crate) remote_timestamp: u64,
+    pub(crate) paused: bool,
+    /// When the selection was last moved, and the newest snapshot held back since then; see
+    /// `navigation_hold`.
+    moved_at: Option<Instant>,
+    pub(crate) held: Option<snapshot::Snapshot>,
+    /// Slows the collector down while the table is left alone.
+    pub(crate) idle: collector::Idle,
+    /// How long the table has to be left alone for that; `None` without live updates or when
+    /// turned off.
+    pub(crate) idle_timeout: Option<Duration>,
+    /// Seconds between snapshots once idle; zero for none.
+    pub(crate) idle_refresh: Duration,
+    pub(crate) last_input: Instant,
+    /// Whether the terminal reported having the focus; terminals that do not report it count as
+    /// unfocused.
+    pub(crate) focused: bool,
+    /// Set when the terminal lost the focus and `draw_unfocused` is off; nothing is drawn until
+    /// the focus is back.
+    pub(crate) hidden: bool,
+    /// Sort by CPU or memory, whichever the system is under more pressure for.
+    pub(crate) focus: bool,
+    /// The process kept in the middle of the table and set apart as the rows move around it,
+    /// with its name to announce its exit.
+    pub(crate) follow: Option<(ProcessKey, String)>,
+    /// Processes running under a CPU cap or memory limit, removed again when the table quits.
+    pub(crate) throttles: Vec<throttle::Throttle>,
+    /// Whether the prompt being shown is for a CPU cap or a memory limit.
+    pub(crate) throttle_resource: Resource,
+    /// Whether the cap being entered also applies to the children of the selected process.
+    pub(crate) throttle_tree: bool,
+    /// Draw the table on the whole alternate screen instead of inline below the prompt.
+    pub(crate) fullscreen: bool,
+    /// What the last frame drew where, for the mouse.
+    pub(crate) clickable: Clickable,
+    /// Every column of the table in display order, with whether it is shown.
+    pub(crate) columns: Vec<(Column, bool)>,
+    /// Index into `columns` of the column picker cursor.
+    pub(crate) column_choice: usize,
+    /// Processes marked with `w`.
+    pub(crate) watched: HashSet<ProcessKey>,
+    /// Sort keys, most significant first.
+    pub(crate) sort: Vec<SortKey>,
+    /// Set in tree mode.
+    pub(crate) tree: Option<tree::TreeView>,
+    /// Tree details of each row of `items` in tree mode.
+    pub(crate) tree_rows: Vec<tree::TreeRow>,
+    /// Descendants of collapsed processes and members of collapsed groups, not listed in
+    /// `items`.
+    pub(crate) tree_hidden: Vec<ProcessInfo>,
+    /// Set in grouped mode.
+    pub(crate) groups: Option<group::GroupView>,
+    /// Place of each row of `items` in grouped mode.
+    pub(crate) group_rows: Vec<group::GroupRow>,
+    /// Banner explaining what `/proc` hides, see [`crate::procfs::restriction`].
+    pub(crate) restriction: Option<String>,
+    /// When the displayed data was collected.
+    pub(crate) updated_at: Instant,
+    /// How often new data is expected; `None` when only refreshed manually.
+    pub(crate) expected_interval: Option<Duration>,
+    pub(crate) events: Option<events::EventLog>,
+    /// Session log every snapshot shown and every action is appended to, see [`replay`].
+    pub(crate) recording: Option<replay::SessionLog>,
+    /// Metrics served to Prometheus, updated with every snapshot shown.
+    pub(crate) metrics: Option<metrics::Exporter>,
+    /// The session log shown instead of the processes of this machine (`syskill replay`).
+    pub(crate) replay: Option<replay::Replay>,
+    /// Kills by the kernel's OOM killer, when the kernel log is readable.
+    pub(crate) oom_rx: Option<Receiver<oom::OomKill>>,
+    /// Processes the OOM killer killed, tagged while they linger in the table.
+    oom_killed: HashSet<ProcessKey>,
+    /// Search filter kept across live updates; cleared by a manual refresh.
+    pub(crate) filter: String,
+    /// Earlier searches `filter` narrows down, the first first; the table shows the processes
+    /// matching all of them.
+    pub(crate) filters: Vec<String>,
+    /// Only processes of this user are shown, picked with `U`. Unlike `user`, the others are
+    /// still collected.
+    pub(crate) owner_filter: Option<String>,
+    /// Only zombie, stopped and other processes in a state worth a look are shown (`Z`).
+    pub(crate) problems_only: bool,
+    /// Only processes started from the terminal syskill runs in are shown (`h`).
+    pub(crate) terminal_filter: Option<terminal::Terminal>,
+    /// Why the filter is not a valid expression; nothing is listed meanwhile.
+    pub(crate) filter_error: Option<String>,
+    /// Every process of the last update, before the search filter.
+    pub(crate) unfiltered: Vec<ProcessInfo>,
+    /// Result of the last port lookup while its popup is open.
+    pub(crate) port_holders: Option<(ports::PortQuery, Vec<ports::Holder>)>,
+    pub(crate) numa: Option<numa::Topology>,
+    pub(crate) graphics: Option<GraphicsProtocol>,
+    pub(crate) image: graphics::Placement,
+    #[cfg(unix)]
+    pub(crate) remote: Option<session::RemoteSession>,
+    #[cfg(unix)]
+    pub(crate) control: Option<Receiver<control::ControlRequest>>,
+}
+
+/// Percentage points by which the other resource must be under more pressure before focus mode
+/// switches to it.
+const FOCUS_MARGIN: f64 = 2.0;
+/// How many submitted searches `Up` in the search box goes back through.
+const KEPT_SEARCHES: usize = 100;
+/// Characters command lines scroll by at a time.
+const COMMAND_SCROLL: usize = 10;
+/// What read-only mode answers when asked to change a process.
+/// Values that can be copied in copy mode; the first four are the table columns.
+pub(crate) const COPY_COLUMNS: [&str; 6] = ["Name", "PID", "CPU usage", "Memory", "Path", "User"];
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            state: TableState::default().with_selected(0),
+            items: Vec::new(),
+            // Only the CPU list, which is cheap; processes are read by `quick_load` and the
+            // collector.
+            ctx: System::new_with_specifics(RefreshKind::new().with_cpu(CpuRefreshKind::new())),
+            colors: TableColors::new(&PALETTES[0]),
+            theme: Theme::default(),
+            themes: BTreeMap::new(),
+            units: Units::default(),
+            previous_cpu: HashMap::new(),
+            show_popup: false,
+            mode: AppState::ProcessMode,
+            signal_choice: 0,
+            sched_choice: 0,
+            ionice_choice: 0,
+            confirm_kills: true,
+            read_only: false,
+            read_only_locked: false,
+            pending_kill: None,
+            denied_kill: None,
+            config: config::Config::default(),
+            escalations: Vec::new(),
+            queue: queue::ActionQueue::default(),
+            show_queue: false,
+            queue_choice: 0,
+            scheduling: None,
+            collected: Arc::new(Mutex::new(snapshot::Fields::ALL)),
+            without_disk: false,
+            watchdog: watchdog::Watchdog::default(),
+            show_watchdog: false,
+            watchdog_choice: 0,
+            watchdog_editing: None,
+            alerts: alert::Alerts::default(),
+            show_alerts: false,
+            exits: exits::Exits::default(),
+            show_exits: false,
+            exits_sort: exits::ExitSort::default(),
+            exits_filter: String::new(),
+            exits_scroll: 0,
+            changes: changes::Changes::default(),
+            show_changes: false,
+            suspects: suspects::Suspects::default(),
+            show_suspects: false,
+            consumers: consumers::Consumers::default(),
+            show_consumers: false,
+            ephemeral: ephemeral::Ephemeral::default(),
+            show_ephemeral: false,
+            show_breakdown: false,
+            full_commands: false,
+            command_offset: 0,
+            keys: keymap::Keys::default(),
+            redactor: redact::Redactor::default(),
+            hunt: None,
+            show_help: false,
+            help_scroll: 0,
+            breakdown_choice: None,
+            copy_column: 0,
+            input: String::new(),
+            search_history: Vec::new(),
+            history_choice: None,
+            character_index: 0,
+            kills: Vec::new(),
+            respawns: respawn::Respawns::default(),
+            sent_signals: HashMap::new(),
+            summary: None,
+            summary_sampler: summary::Sampler::new(),
+            heavy: HashMap::new(),
+            notice: None,
+            error: None,
+            outcomes: outcomes::Outcomes::default(),
+            show_outcomes: false,
+            outcomes_scroll: 0,
+            audit_path: None,
+            audit_sender: Default::default(),
+            audit_entries: None,
+            audit_scroll: 0,
+            show_about: false,
+            renice: None,
+            priority: None,
+            details: None,
+            details_scroll: 0,
+            details_tab: details::Tab::default(),
+            env_search: String::new(),
+            env_revealed: false,
+            signal_thread: None,
+            update_status: UpdateStatus::NotChecked,
+            update_rx: None,
+            linear: false,
+            speak_command: None,
+            hyperlinks: false,
+            exe_url: String::new(),
+            history: HistoryStore::default(),
+            show_chart: false,
+            show_cores: false,
+            core_heat: affinity::CoreHeat::default(),
+            show_numa: false,
+            show_memory: false,
+            show_runtime: false,
+            show_power: false,
+            show_log: false,
+            tool_rx: None,
+            tool_output: None,
+            last_command: String::new(),
+            command_rx: None,
+            command_output: None,
+            command_scroll: 0,
+            orphan_watch: None,
+            orphans: Vec::new(),
+            port_holders: None,
+            user: None,
+            live: None,
+            refresh_asked: false,
+            #[cfg(unix)]
+            remote_timestamp: 0,
+            paused: false,
+            moved_at: None,
+            held: None,
+            idle: collector::Idle::default(),
+            idle_timeout: None,
+            idle_refresh: Duration::ZERO,
+            last_input: Instant::now(),
+            focused: false,
+            hidden: false,
+            focus: false,
+            follow: None,
+            throttles: Vec::new(),
+            throttle_resource: Resource::Cpu,
+            throttle_tree: false,
+            fullscreen: false,
+            clickable: Clickable::default(),
+            columns: config::Config::default().layout(),
+            column_choice: 0,
+            watched: HashSet::new(),
+            sort: vec![SortKey::new(SortColumn::Pid)],
+            tree: None,
+            tree_rows: Vec::new(),
+            tree_hidden: Vec::new(),
+            groups: None,
+            group_rows: Vec::new(),
+            updated_at: Instant::now(),
+            expected_interval: None,
+            refresh_interval: Duration::ZERO,
+            collect_error: None,
+            events: None,
+            recording: None,
+            metrics: None,
+            replay: None,
+            restriction: None,
+            oom_rx: None,
+            oom_killed: HashSet::new(),
+            filter: String::new(),
+            filters: Vec::new(),
+            owner_filter: None,
+            problems_only: false,
+            terminal_filter: None,
+            filter_error: None,
+            unfiltered: Vec::new(),
+            numa: numa::Topology::detect(),
+            graphics: None,
+            image: graphics::Placement::default(),
+            #[cfg(unix)]
+            remote: None,
+            #[cfg(unix)]
+            control: None,
+        }
+    }
+
+    pub(crate) fn clean(&mut self) {
+        self.items = Vec::new();
+        self.tree_hidden.clear();
+    }
+
+    pub(crate) fn move_cursor_left(&mut self) {
+        let cursor_moved_left = self.character_index.saturating_sub(1);
+        self.character_index = self.clamp_cursor(cursor_moved_left);
+    }
+
+    pub(crate) fn move_cursor_right(&mut self) {
+        let cursor_moved_right = self.character_index.saturating_add(1);
+        self.character_index = self.clamp_cursor(cursor_moved_right);
+    }
+
+    pub(crate) fn enter_char(&mut self, new_char: char) {
+        let index = self.byte_index();
+        self.input.insert(index, new_char);
+        self.move_cursor_right();
+    }
+
+    /// Returns the byte index based on the character position.
+    ///
+    /// Since each character in a string can be contain multiple bytes, it's necessary to calculate
+    /// the byte index based on the index of the character.
+    pub(crate) fn byte_index(&mut self) -> usize {
+        self.input
+            .char_indices()
+            .map(|(i, _)| i)
+            .nth(self.character_index)
+            .unwrap_or(self.input.len())
+    }
+
+    pub(crate) fn delete_char(&mut self) {
+        let is_not_cursor_leftmost = self.character_index != 0;
+        if is_not_cursor_leftmost {
+            // Method "remove" is not used on the saved text for deleting the selected char.
+            // Reason: Using remove on String works on bytes instead of the chars.
+            // Using remove would require special care because of char boundaries.
+
+            let current_index = self.character_index;
+            let from_left_to_current_index = current_index - 1;
+
+            // Getting all characters before the selected character.
+            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
+            // Getting all characters after selected character.
+            let after_char_to_delete = self.input.chars().skip(current_index);
+
+            // Put all characters together except the selected one.
+            // By leaving the selected one out, it is forgotten and therefore deleted.
+            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
+            self.move_cursor_left();
+        }
+    }
+
+    pub(crate) fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.input.chars().count())
+    }
+
+    pub(crate) fn reset_cursor(&mut self) {
+        self.character_index = 0;
+    }
+
+    /// Opens an empty search input that narrows down the current results, if any.
+    pub(crate) fn start_search(&mut self) {
+        if !self.filter.is_empty() {
+            self.filters.push(std::mem::take(&mut self.filter));
+        }
+        self.input.clear();
+        self.reset_cursor();
+        self.history_choice = None;
+    }
+
+    /// Takes the last of the searches the current one narrows down back into the empty search
+    /// input, to be edited. Returns whether there was one.
+    pub(crate) fn edit_previous_filter(&mut self) -> bool {
+        let Some(previous) = self.filters.pop() else {
+            return false;
+        };
+        self.input = previous;
+        self.character_index = self.input.chars().count();
+        self.update_search();
+        true
+    }
+
+    /// The searches the table is filtered by, the first first.
+    pub(crate) fn filter_chain(&self) -> impl Iterator<Item = &str> {
+        self.filters
+            .iter()
+            .chain([&self.filter])
+            .map(String::as_str)
+            .filter(|filter| !filter.is_empty())
+    }
+
+    /// Drops the last search, showing the results of the ones before it.
+    pub(crate) fn pop_filter(&mut self) {
+        if self.filter.is_empty() {
+            return;
+        }
+        self.input = self.filters.pop().unwrap_or_default();
+        self.update_search();
+        self.input.clear();
+        self.record_filter();
+    }
+
+    /// Drops every search, showing all processes again.
+    pub(crate) fn clear_filters(&mut self) {
+        if self.filter.is_empty() {
+            return;
+        }
+        self.filters.clear();
+        self.input.clear();
+        self.update_search();
+        self.record_filter();
+    }
+
+    pub(crate) fn submit_message(&mut self) {
+        let search = std::mem::take(&mut self.input);
+        if !search.is_empty() {
+            self.search_history.retain(|earlier| *earlier != search);
+            if self.search_history.len() == KEPT_SEARCHES {
+                self.search_history.remove(0);
+            }
+            self.search_history.push(search);
+        }
+        self.history_choice = None;
+        self.reset_cursor();
+        // An empty search narrows nothing down; the one before is the last again.
+        if self.filter.is_empty() {
+            self.filter = self.filters.pop().unwrap_or_default();
+        }
+        self.record_filter();
+    }
+
+    /// Shows an earlier search in the search box, or a later one, ending with what was typed
+    /// before, and filters the table with it.
+    pub(crate) fn recall_search(&mut self, earlier: bool) {
+        let latest = self.search_history.len();
+        let (index, draft) = match self.history_choice.take() {
+            Some(choice) => choice,
+            None => (latest, self.input.clone()),
+        };
+        let index = if earlier {
+            index.saturating_sub(1)
+        } else {
+            (index + 1).min(latest)
+        };
+        self.input = match self.search_history.get(index) {
+            Some(search) => search.clone(),
+            None => draft.clone(),
+        };
+        self.history_choice = (index < latest).then_some((index, draft));
+        self.character_index = self.input.chars().count();
+        self.update_search();
+    }
+
+    /// Searches with the filter saved as `name` in the `[filters]` config table.
+    pub(crate) fn apply_saved_filter(&mut self, name: &str) -> error::Result<()> {
+        let Some(filter) = self.config.filters.get(name) else {
+            let names: Vec<_> = self.config.filters.keys().map(String::as_str).collect();
+            return Err(Error::Invalid(match names.is_empty() {
+                true => {
+                    format!("No filter named {name}; save some as [filters] in the config file")
+                }
+                false => format!("No filter named {name}, expected {}", names.join(", ")),
+            }));
+        };
+        self.input = filter.search.clone();
+        self.update_search();
+        self.submit_message();
+        self.notice = Some(format!("Filter {name}: {}", self.filter));
+        Ok(())
+    }
+
+    /// Drops the search being typed, keeping the ones it narrowed down.
+    pub(crate) fn cancel_search(&mut self) {
+        self.input = self.filters.pop().unwrap_or_default();
+        self.reset_cursor();
+        self.update_search();
+        self.input.clear();
+        self.record_filter();
+    }
+
+    /// Filters the table again as the search input is typed, keeping the selected process.
+    pub(crate) fn update_search(&mut self) {
+        self.filter = self.input.clone();
+        self.update_collected();
+        let selected_pid = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|data| data.pid);
+        self.items = std::mem::take(&mut self.unfiltered);
+        self.tree_hidden.clear();
+        self.filter_items();
+        self.sort_items();
+        let index = selected_pid
+            .and_then(|pid| self.items.iter().position(|data| data.pid == pid))
+            .unwrap_or(0);
+        self.state.select(Some(index));
+    }
+
+    /// Remembers the processes just loaded into the table and keeps those matching the filter.
+    fn filter_items(&mut self) {
+        let previous = std::mem::take(&mut self.unfiltered);
+        self.keep_exited(previous);
+        self.unfiltered = std::mem::take(&mut self.items);
+        let queries: Result<Vec<_>, _> = self.filter_chain().map(query::Query::parse).collect();
+        self.items = match &queries {
+            Ok(queries) => self
+                .unfiltered
+                .iter()
+                .filter(|data| {
+                    self.owner_filter
+                        .as_ref()
+                        .is_none_or(|user| data.user == *user)
+                })
+                .filter(|data| !self.problems_only || proc::is_problem_state(&data.state))
+                .filter(|data| {
+                    self.terminal_filter
+                        .is_none_or(|terminal| terminal.started(data.pid))
+                })
+                .filter(|data| queries.iter().all(|query| query.matches(data, self.units)))
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        self.filter_error = queries.err();
+    }
+
+    /// Hides secrets in the command lines of the processes just read, before anything shows or
+    /// matches them.
+    fn redact_commands(&mut self) {
+        for data in &mut self.items {
+            if !data.command.is_empty() {
+                data.command = self.redactor.apply(&data.command);
+            }
+        }
+    }
+
+    /// Adds the processes of `previous` that are gone from the new `items` back to them, greyed
+    /// out, until they have been gone for `exited_linger`.
+    fn keep_exited(&mut self, previous: Vec<ProcessInfo>) {
+        let linger = self.config.exited_linger();
+        let now = Instant::now();
+        self.items
+            .retain(|data| data.exited.is_none_or(|exited| now - exited < linger));
+        if linger.is_zero() {
+            return;
+        }
+        let listed: HashSet<ProcessKey> = self.items.iter().map(ProcessInfo::key).collect();
+        for mut data in previous {
+            if listed.contains(&data.key()) {
+                continue;
+            }
+            let exited = *data.exited.get_or_insert(now);
+            if now - exited < linger {
+                data.cpu_usage = 0.0;
+                self.items.push(data);
+            }
+        }
+    }
+
+    /// Looks up the processes listening on the port typed into the input popup.
+    pub(crate) fn submit_port(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        match input.parse() {
+            Ok(query) => self.look_up_port(query),
+            Err(reason) => self.show_result(Err(Error::Invalid(reason))),
+        }
+    }
+
+    /// Opens the popup listing the processes on `query`, from which they can be signalled.
+    pub(crate) fn look_up_port(&mut self, query: ports::PortQuery) {
+        let mut holders = ports::holders(&mut self.ctx, query);
+        for holder in &mut holders {
+            holder.command = self.redactor.apply(&holder.command);
+        }
+        if holders.is_empty() {
+            self.notice = Some(format!("Nothing is listening on port {query}"));
+        } else {
+            self.port_holders = Some((query, holders));
+        }
+    }
+
+    /// Sends `signal` to every process holding the looked up port.
+    pub(crate) fn signal_port_holders(&mut self, signal: Signal) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let Some((port, holders)) = self.port_holders.take() else {
+            return;
+        };
+        let mut pids: Vec<u32> = holders.iter().map(|holder| holder.listener.pid).collect();
+        pids.dedup();
+        let sent = pids
+            .iter()
+            .filter(|&&pid| self.send_signal(pid, signal).is_ok())
+            .count();
+        self.notice = Some(format!(
+            "Sent {signal} to {sent} of {} processes on port {port}",
+            pids.len()
+        ));
+        self.refresh();
+    }
+
+    pub(crate) fn next(&mut self) {
+        if self.items.is_empty() {
+            self.state.select(None);
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i >= self.items.len().saturating_sub(1) {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+        self.speak_selection();
+    }
+
+    pub(crate) fn previous(&mut self) {
+        if self.items.is_empty() {
+            self.state.select(None);
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.items.len().saturating_sub(1)
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+        self.speak_selection();
+    }
+
+    /// Selects row `i`, as clicked or scrolled to with the mouse; unlike `j` and `k` the
+    /// selection does not wrap around.
+    pub(crate) fn select_row(&mut self, i: usize) {
+        if i >= self.items.len() {
+            return;
+        }
+        self.state.select(Some(i));
+        self.speak_selection();
+    }
+
+    /// Moves the selection a screenful of rows down, or up, stopping at either end.
+    pub(crate) fn move_page(&mut self, down: bool) {
+        if self.items.is_empty() {
+            return;
+        }
+        // As many rows as the last frame showed.
+        let rows =
+            usize::from(self.clickable.rows.height / self.clickable.row_height.max(1)).max(1);
+        let i = self.state.selected().unwrap_or(0);
+        self.select_row(if down {
+            (i + rows).min(self.items.len() - 1)
+        } else {
+            i.saturating_sub(rows)
+        });
+    }
+
+    /// Selects the process with the PID typed into the jump prompt.
+    pub(crate) fn submit_jump(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let pid = input.trim();
+        let result = match pid.parse() {
+            Ok(pid) => self.select_pid(pid),
+            Err(_) => Err(Error::InvalidPid(pid.to_string())),
+        };
+        self.show_result(result);
+    }
+
+    /// Selects the row of `pid`.
+    pub(crate) fn select_pid(&mut self, pid: u32) -> error::Result<()> {
+        if let Some(i) = self.items.iter().position(|data| data.pid == pid) {
+            self.select_row(i);
+            Ok(())
+        } else if self.unfiltered.iter().any(|data| data.pid == pid) {
+            Err(Error::Invalid(format!(
+                "PID {pid} is hidden by the search, a filter or a collapsed node"
+            )))
+        } else {
+            Err(Error::Invalid(format!("No process with PID {pid}")))
+        }
+    }
+
+    /// Selects the parent of the selected process.
+    pub(crate) fn select_parent(&mut self) -> error::Result<()> {
+        let data = self.selected_data()?;
+        let Some(parent) = data.parent else {
+            return Err(Error::Invalid(format!(
+                "{} ({}) has no parent",
+                data.name, data.pid
+            )));
+        };
+        self.select_pid(parent)
+    }
+
+    /// Selects the first listed child of the selected process, saying how many it has.
+    pub(crate) fn select_child(&mut self) -> error::Result<()> {
+        let data = self.selected_data()?;
+        let (pid, name) = (data.pid, data.name.clone());
+        let is_child = |child: &ProcessInfo| child.parent == Some(pid) && child.members == 0;
+        let Some(first) = self.items.iter().position(is_child) else {
+            let hidden = "hidden by the search, a filter or a collapsed node";
+            return Err(Error::Invalid(if self.unfiltered.iter().any(is_child) {
+                format!("The children of {name} ({pid}) are {hidden}")
+            } else {
+                format!("{name} ({pid}) has no children")
+            }));
+        };
+        let count = self.items.iter().filter(|child| is_child(child)).count();
+        self.select_row(first);
+        if count > 1 {
+            self.notice = Some(format!("First of {count} children of {name} ({pid})"));
+        }
+        Ok(())
+    }
+
+    /// Starts looking for the string typed into the find prompt everywhere in the processes.
+    pub(crate) fn submit_find(&mut self) {
+        let needle = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        if needle.is_empty() {
+            return;
+        }
+        self.hunt = Some(hunt::Hunt::start(needle, self.redactor.clone()));
+    }
+
+    pub(crate) fn poll_hunt(&mut self) {
+        if let Some(hunt) = &mut self.hunt {
+            hunt.poll();
+        }
+    }
+
+    /// Closes the find results and selects the process of the chosen hit.
+    pub(crate) fn jump_to_hit(&mut self) {
+        let Some(pid) = self
+            .hunt
+            .as_ref()
+            .and_then(|hunt| hunt.chosen())
+            .map(|hit| hit.pid)
+        else {
+            return;
+        };
+        self.hunt = None;
+        let result = self.select_pid(pid);
+        self.show_result(result);
+    }
+
+    /// Closes the changes panel and selects the process of the chosen change.
+    pub(crate) fn jump_to_change(&mut self) {
+        let Some(change) = self.changes.chosen() else {
+            return;
+        };
+        let pid = change.key.pid;
+        if change.kind == changes::Kind::Exited {
+            self.notice = Some(format!("{} ({pid}) has exited", change.name));
+            return;
+        }
+        self.show_changes = false;
+        let result = self.select_pid(pid);
+        self.show_result(result);
+    }
+
+    /// Closes the suspects and selects the chosen one.
+    pub(crate) fn jump_to_suspect(&mut self) {
+        let Some(pid) = self.suspects.chosen().map(|suspect| suspect.key.pid) else {
+            return;
+        };
+        self.show_suspects = false;
+        let result = self.select_pid(pid);
+        self.show_result(result);
+    }
+
+    /// Closes the top consumers and searches for the processes of the chosen user or name.
+    pub(crate) fn search_consumer(&mut self) {
+        let Some(search) = self.consumers.search() else {
+            return;
+        };
+        self.show_consumers = false;
+        self.input = search;
+        self.update_search();
+        self.submit_message();
+    }
+
+    /// Sums up the short-lived processes the proc connector reported up to now.
+    pub(crate) fn observe_ephemeral(&mut self) {
+        let redactor = &self.redactor;
+        self.ephemeral.observe(
+            &connector::short_lived(),
+            connector::listening_since(),
+            Instant::now(),
+            |command| redactor.apply(command),
+        );
+    }
+
+    /// Compares the next refreshes with this one, or again each with the one before.
+    pub(crate) fn toggle_changes_baseline(&mut self) {
+        if self.changes.baseline == changes::Baseline::Previous {
+            self.changes.pin(&self.unfiltered, snapshot::unix_now());
+            self.notice = Some("Comparing with this refresh".to_string());
+        } else {
+            self.changes.unpin(&self.unfiltered);
+            self.notice = Some("Comparing each refresh with the one before".to_string());
+        }
+    }
+
+    /// Whether a popup covers the table.
+    pub(crate) fn popup_open(&self) -> bool {
+        self.show_popup
+            || self.show_about
+            || self.details.is_some()
+            || self.hunt.is_some()
+            || self.renice.is_some()
+            || !self.orphans.is_empty()
+            || self.port_holders.is_some()
+            || self.command_output.is_some()
+            || self.pending_kill.is_some()
+            || self.denied_kill.is_some()
+            || self.show_queue
+            || self.show_watchdog
+            || self.show_alerts
+            || self.show_exits
+            || self.show_outcomes
+            || self.audit_entries.is_some()
+            || self.show_changes
+            || self.show_suspects
+            || self.show_consumers
+            || self.show_ephemeral
+            || self.breakdown_choice.is_some()
+            || self.show_help
+    }
+
+    pub(crate) fn announcement(&self) -> String {
+        let index = self.state.selected().unwrap_or(0);
+        accessibility::announcement(self.items.get(index), index, self.items.len(), self.units)
+    }
+
+    /// Hands the current selection to the speak command, if one is configured.
+    pub(crate) fn speak_selection(&self) {
+        if let Some(command) = &self.speak_command {
+            accessibility::speak(command, &self.announcement());
+        }
+    }
+
+    /// Fills the table with what is quick to read, such as names and PIDs, so it shows right
+    /// away even with thousands of processes. CPU and memory usage, owners and executables follow
+    /// with the first snapshot of the collector (see [`collector::spawn`]).
+    pub(crate) fn quick_load(&mut self) {
+        if self.replay.is_some() {
+            self.get_proc();
+            return;
+        }
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            self.get_proc();
+            return;
+        }
+        let mut kind = ProcessRefreshKind::new();
+        if self.user.is_some() {
+            // Needed to tell whose processes they are.
+            kind = kind.with_user(UpdateKind::OnlyIfNotSet);
+        }
+        self.ctx.refresh_processes_specifics(kind);
+        self.get_proc();
+    }
+
+    pub(crate) fn get_proc(&mut self) {
+        if let Some(replay) = &self.replay {
+            let processes = replay.current().processes.clone();
+            self.without_disk = replay.current().without_disk;
+            self.items
+                .extend(processes.into_iter().map(ProcessInfo::from));
+            self.redact_commands();
+            // Stepping back would leave processes that only start later behind as exited.
+            self.unfiltered.clear();
+            self.filter_items();
+            self.sort_items();
+            return;
+        }
+        #[cfg(unix)]
+        if let Some(remote) = &self.remote {
+            let processes = remote.latest().map(|s| s.processes).unwrap_or_default();
+            self.items
+                .extend(processes.into_iter().map(ProcessInfo::from));
+            self.redact_commands();
+            self.filter_items();
+            self.sort_items();
+            self.history.record(&self.unfiltered, true);
+            return;
+        }
+
+        self.ctx.refresh_cpu();
+        self.sample_summary();
+        let system = &self.ctx;
+        let processes = system.processes();
+        let owners = sysinfo::Users::new_with_refreshed_list();
+
+        for (pid, process) in processes.iter() {
+            if self
+                .user
+                .as_ref()
+                .is_some_and(|user| !user.matches(process))
+            {
+                continue;
+            }
+            let pid = pid.as_u32();
+            self.items.push(ProcessInfo {
+                name: process.name().to_string(),
+                pid,
+                cpu_usage: process.cpu_usage(),
+                memory: process.memory(),
+                virtual_memory: process.virtual_memory(),
+                shared_memory: None,
+                swap: None,
+                exe: process
+                    .exe()
+                    .map(|exe| exe.display().to_string())
+                    .unwrap_or_default(),
+                command: self.redactor.apply(&process.cmd().join(" ")),
+                node: match &self.numa {
+                    Some(_) => numa::home_node(&numa::memory_per_node(pid))
+                        .map_or_else(|| "-".to_string(), |node| node.to_string()),
+                    None => String::new(),
+                },
+                parent: process.parent().map(Pid::as_u32),
+                start_time: process.start_time(),
+                user: users::name(&owners, process),
+                state: proc::state_name(process.status()),
+                sched: sched::describe(pid),
+                disk_read: process.disk_usage().total_read_bytes,
+                disk_written: process.disk_usage().total_written_bytes,
+                threads: proc::thread_count(process),
+                // Left to the collector, like the other fields read per process.
+                fds: None,
+                tty: String::new(),
+                session: None,
+                cpu_time: None,
+                container: String::new(),
+                window: String::new(),
+                gpu: None,
+                gpu_memory: None,
+                custom: BTreeMap::new(),
+                exited: None,
+                members: 0,
+            });
+        }
+
+        self.filter_items();
+        self.sort_items();
+        self.history.record(&self.unfiltered, true);
+    }
+
+    /// The row under the cursor.
+    pub(crate) fn selected_data(&self) -> error::Result<&ProcessInfo> {
+        self.selected_process().ok_or(Error::NoSelection)
+    }
+
+    /// The selected row, unless it is the summary row of a group.
+    pub(crate) fn selected_process(&self) -> Option<&ProcessInfo> {
+        self.state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .filter(|data| data.members == 0)
+    }
+
+    /// Shows the error of a failed action on the status line.
+    pub(crate) fn show_result(&mut self, result: error::Result<()>) {
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    /// Adds what the status line says to the history, unless it said so already.
+    pub(crate) fn note_outcome(&mut self) {
+        let error = self.error.as_ref().map(Error::to_string);
+        self.outcomes
+            .observe(self.notice.as_deref(), error, snapshot::unix_now());
+    }
+
+    pub(crate) fn delete_proc(&mut self) -> error::Result<()> {
+        let row = self.selected_data()?;
+        let (pid, name) = (row.pid, row.name.clone());
+        #[cfg(unix)]
+        let start_time = row.start_time;
+        #[cfg(unix)]
+        if let Some(remote) = &mut self.remote {
+            // The server checks the start time and reports back; see `poll_remote`.
+            return remote.kill(pid, start_time).map_err(Error::Remote);
+        }
+        // The children, to tell whether they outlive it.
+        let watch = orphans::OrphanWatch::start(self.current_processes(), Pid::from_u32(pid));
+        let request = KillRequest::new(pid, Signal::Kill);
+        let result = self.deliver(request);
+        if let Ok(message) = &result {
+            self.orphan_watch = watch;
+            self.notice = Some(message.clone());
+        }
+        self.refresh();
+        result
+            .map(|_| ())
+            .map_err(|e| self.refused(request, &name, e))
+    }
+
+    /// Opens the signal picker for the selected process.
+    pub(crate) fn open_signal_picker(&mut self) {
+        if self.selected_process().is_some() {
+            self.signal_choice = 0;
+            self.input.clear();
+            self.reset_cursor();
+            self.mode = AppState::SignalMode;
+            self.show_popup = true;
+        }
+    }
+
+    /// Opens the signal picker for the thread chosen in the detail view.
+    pub(crate) fn open_thread_signal_picker(&mut self) {
+        let Some(thread) = self
+            .details
+            .as_ref()
+            .and_then(|details| details.threads.chosen())
+        else {
+            return;
+        };
+        self.signal_thread = Some(thread.tid);
+        self.signal_choice = 0;
+        self.input.clear();
+        self.reset_cursor();
+        self.mode = AppState::SignalMode;
+        self.show_popup = true;
+    }
+
+    /// The signals the picker offers, fewer for a single thread.
+    pub(crate) fn picker_signals(&self) -> &'static [(Signal, &'static str)] {
+        match self.signal_thread {
+            Some(_) => signal::THREAD_PICKER,
+            None => signal::PICKER,
+        }
+    }
+
+    /// Asks for the priority change of the processes matching the search.
+    pub(crate) fn start_renice(&mut self) {
+        if self.is_remote() {
+            self.notice = Some("Renicing is not supported when attached".to_string());
+        } else if self.filter_chain().next().is_none() {
+            self.notice = Some("Search first, renicing applies to the search results".to_string());
+        } else if !self.items.is_empty() {
+            self.mode = AppState::ReniceMode;
+            self.show_popup = true;
+        }
+    }
+
+    /// Previews the priority change typed into the input popup.
+    pub(crate) fn submit_renice(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        match renice::parse_delta(&input) {
+            Ok(delta) => {
+                let processes = self.items.iter().map(|data| (data.pid, data.name.clone()));
+                self.renice = Some(renice::RenicePlan::new(delta, processes));
+            }
+            Err(e) => self.notice = Some(e),
+        }
+    }
+
+    /// Applies the previewed priority change, keeping the plan open if some processes failed.
+    pub(crate) fn apply_renice(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let Some(plan) = &mut self.renice else {
+            return;
+        };
+        let reniced = plan.apply();
+        self.notice = Some(format!(
+            "Reniced {reniced} of {} processes by {:+}",
+            plan.entries.len(),
+            plan.delta
+        ));
+        if plan.failures().next().is_none() {
+            self.renice = None;
+        }
+    }
+
+    /// Opens the scheduling picker for the selected process, on its current policy.
+    pub(crate) fn open_sched_picker(&mut self) {
+        if self.is_remote() {
+            self.notice = Some("Scheduling is not supported when attached".to_string());
+            return;
+        }
+        let current = match self.selected_data() {
+            Ok(data) => sched::get(data.pid).ok(),
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        self.sched_choice = sched::PICKER
+            .iter()
+            .position(|&(policy, _)| current.is_some_and(|(current, _)| current == policy))
+            .unwrap_or(0);
+        self.input = current
+            .filter(|(policy, _)| policy.realtime())
+            .map(|(_, priority)| priority.to_string())
+            .unwrap_or_default();
+        self.reset_cursor();
+        self.mode = AppState::SchedMode;
+        self.show_popup = true;
+    }
+
+    /// Applies the policy chosen in the scheduling picker, with the typed priority.
+    pub(crate) fn submit_sched(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let result = self.change_sched(&input);
+        self.show_result(result);
+    }
+
+    fn change_sched(&mut self, input: &str) -> error::Result<()> {
+        let policy = sched::PICKER[self.sched_choice].0;
+        let priority = sched::parse_priority(policy, input).map_err(Error::Invalid)?;
+        let data = self.selected_data()?;
+        let pid = data.pid;
+        let name = data.name.clone();
+        sched::set(pid, policy, priority).map_err(Error::Scheduling)?;
+        let now = sched::describe(pid);
+        self.notice = Some(format!("{name} ({pid}) now runs as {now}"));
+        if let Some(data) = self.state.selected().and_then(|i| self.items.get_mut(i)) {
+            data.sched = now;
+        }
+        Ok(())
+    }
+
+    /// Opens the I/O priority picker for the selected process, on its current class and level.
+    pub(crate) fn open_ionice_picker(&mut self) {
+        if self.is_remote() {
+            self.notice = Some("I/O priorities are not supported when attached".to_string());
+            return;
+        }
+        let current = match self.selected_data() {
+            Ok(data) => ionice::get(data.pid).ok(),
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        self.ionice_choice = ionice::PICKER
+            .iter()
+            .position(|&(class, _)| current.is_some_and(|(current, _)| current == class))
+            .unwrap_or(0);
+        self.input = current
+            .filter(|(class, _)| class.has_level())
+            .map(|(_, level)| level.to_string())
+            .unwrap_or_default();
+        self.reset_cursor();
+        self.character_index = self.input.chars().count();
+        self.mode = AppState::IoniceMode;
+        self.show_popup = true;
+    }
+
+    /// Applies the class chosen in the I/O priority picker, with the typed level.
+    pub(crate) fn submit_ionice(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let result = self.change_ionice(&input);
+        self.show_result(result);
+    }
+
+    fn change_ionice(&mut self, input: &str) -> error::Result<()> {
+        let class = ionice::PICKER[self.ionice_choice].0;
+        let level = ionice::parse_level(class, input).map_err(Error::Invalid)?;
+        let data = self.selected_data()?;
+        let pid = data.pid;
+        let name = data.name.clone();
+        ionice::set(pid, class, level).map_err(Error::IoPriority)?;
+        let now = ionice::get(pid).unwrap_or((class, level));
+        self.notice = Some(format!(
+            "{name} ({pid}) now does I/O as {}",
+            ionice::describe(now)
+        ));
+        Ok(())
+    }
+
+    /// Opens the priority editor for the selected process, on its current nice value and cores.
+    pub(crate) fn open_priority_editor(&mut self) {
+        if self.is_remote() {
+            self.notice = Some("Changing priorities is not supported when attached".to_string());
+            return;
+        }
+        let pid = match self.selected_data() {
+            Ok(data) => data.pid,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        let from = renice::nice(pid).map_err(|e| e.to_string());
+        let cores = affinity::allowed_cores(pid);
+        self.input = cores
+            .as_deref()
+            .map(affinity::format_cores)
+            .unwrap_or_default();
+        self.reset_cursor();
+        self.character_index = self.input.chars().count();
+        self.priority = Some(PriorityEdit {
+            nice: *from.as_ref().unwrap_or(&0),
+            from,
+            cores,
+        });
+        self.mode = AppState::PriorityMode;
+        self.show_popup = true;
+    }
+
+    /// Moves the nice value chosen in the priority editor by `delta`, negative for a higher
+    /// priority.
+    pub(crate) fn adjust_nice(&mut self, delta: i32) {
+        if let Some(edit) = &mut self.priority {
+            edit.nice = renice::step(edit.nice, delta);
+        }
+    }
+
+    /// Applies the nice value and cores chosen in the priority editor.
+    pub(crate) fn submit_priority(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let result = match self.priority.take() {
+            Some(edit) => self.change_priority(&edit, &input),
+            None => Ok(()),
+        };
+        self.show_result(result);
+    }
+
+    fn change_priority(&mut self, edit: &PriorityEdit, input: &str) -> error::Result<()> {
+        // Check the cores before changing anything.
+        let cores = match &edit.cores {
+            Some(current) if input.trim() != affinity::format_cores(current) => Some(
+                affinity::parse_chosen_cores(input, self.ctx.cpus().len())
+                    .map_err(Error::Invalid)?,
+            ),
+            _ => None,
+        };
+        let data = self.selected_data()?;
+        let pid = data.pid;
+        let name = data.name.clone();
+        let mut changes = Vec::new();
+        if edit.from.as_ref().ok() != Some(&edit.nice) {
+            renice::set_nice(pid, edit.nice).map_err(Error::Priority)?;
+            changes.push(format!(
+                "runs at {} {}",
+                renice::LABEL.to_lowercase(),
+                renice::describe(edit.nice)
+            ));
+        }
+        if let Some(cores) = cores {
+            affinity::set_cores(pid, &cores).map_err(Error::Affinity)?;
+            changes.push(format!(
+                "may run on cores {}",
+                affinity::format_cores(&cores)
+            ));
+        }
+        self.notice = Some(if changes.is_empty() {
+            format!("{name} ({pid}) is unchanged")
+        } else {
+            format!("{name} ({pid}) now {}", changes.join(" and "))
+        });
+        Ok(())
+    }
+
+    /// Opens the prompt for a CPU cap or memory limit on the selected process, or removes the one
+    /// it is under.
+    pub(crate) fn start_throttle(&mut self, resource: Resource) {
+        if self.is_remote() {
+            self.notice = Some("CPU and memory caps are not supported when attached".to_string());
+            return;
+        }
+        let pid = match self.selected_data() {
+            Ok(data) => data.pid,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        let limited = self.throttle_of(pid).filter(|&i| {
+            let throttle = &self.throttles[i];
+            match resource {
+                Resource::Cpu => throttle.percent.is_some(),
+                Resource::Memory => throttle.memory.is_some(),
+            }
+        });
+        match limited {
+            Some(i) => {
+                let result = self.unthrottle(i, resource);
+                self.show_result(result);
+            }
+            None => {
+                self.throttle_resource = resource;
+                self.throttle_tree = false;
+                self.mode = AppState::ThrottleMode;
+                self.show_popup = true;
+            }
+        }
+    }
+
+    /// Index of the throttle `pid` was moved into, if any.
+    pub(crate) fn throttle_of(&self, pid: u32) -> Option<usize> {
+        self.throttles
+            .iter()
+            .position(|throttle| throttle.moved.contains(&pid))
+    }
+
+    /// Limits the selected process, and its children if chosen, to what was typed into the prompt.
+    /// A process already under the other limit keeps the processes it was put on with.
+    pub(crate) fn submit_throttle(&mut self) -> error::Result<()> {
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let (limit, failed, key): (_, fn(std::io::Error) -> Error, _) = match self.throttle_resource
+        {
+            Resource::Cpu => (
+                Limit::Cpu(throttle::parse_percent(&input).map_err(Error::Invalid)?),
+                Error::Throttling,
+                'T',
+            ),
+            Resource::Memory => (
+                Limit::Memory(throttle::parse_memory(&input).map_err(Error::Invalid)?),
+                Error::MemoryLimit,
+                'B',
+            ),
+        };
+        let data = self.selected_data()?;
+        let pid = data.pid;
+        let name = data.name.clone();
+        let amount = match limit {
+            Limit::Cpu(percent) => format!("{percent}% of a CPU"),
+            Limit::Memory(bytes) => format!("{} of memory", self.units.memory(bytes)),
+        };
+        if let Some(i) = self.throttle_of(pid) {
+            self.throttles[i].set(limit).map_err(failed)?;
+            self.notice = Some(format!(
+                "Capped {name} ({pid}) at {amount}, {key} again to remove"
+            ));
+            return Ok(());
+        }
+        let pids: Vec<u32> = if self.throttle_tree {
+            // Parents first, so the process itself is moved before its children.
+            let subtree = tree::subtree(&self.ctx, Pid::from_u32(pid));
+            subtree.into_iter().rev().map(|pid| pid.as_u32()).collect()
+        } else {
+            vec![pid]
+        };
+        let throttle = throttle::apply(&pids, limit).map_err(failed)?;
+        self.notice = Some(match throttle.moved.len() {
+            1 => format!("Capped {name} ({pid}) at {amount}, {key} again to remove"),
+            moved => format!(
+                "Capped {name} ({pid}) and {} children at {amount}, {key} again to remove",
+                moved - 1
+            ),
+        });
+        self.throttles.push(throttle);
+        Ok(())
+    }
+
+    fn unthrottle(&mut self, i: usize, resource: Resource) -> error::Result<()> {
+        let throttle = &mut self.throttles[i];
+        let pid = throttle.pid;
+        let (limit, failed): (_, fn(std::io::Error) -> Error) = match resource {
+            Resource::Cpu => (
+                format!("{}% CPU cap", throttle.percent.unwrap_or_default()),
+                Error::Throttling,
+            ),
+            Resource::Memory => (
+                format!(
+                    "{} memory cap",
+                    self.units.memory(throttle.memory.unwrap_or_default())
+                ),
+                Error::MemoryLimit,
+            ),
+        };
+        let result = throttle.lift(resource);
+        if throttle.is_unlimited() {
+            self.throttles.remove(i);
+        }
+        result.map_err(failed)?;
+        self.notice = Some(format!("Removed the {limit} of {pid}"));
+        Ok(())
+    }
+
+    /// Deletes the cgroups of caps whose processes have all exited.
+    pub(crate) fn poll_throttles(&mut self) {
+        let (gone, kept) = std::mem::take(&mut self.throttles)
+            .into_iter()
+            .partition(throttle::Throttle::is_empty);
+        self.throttles = kept;
+        for throttle in gone {
+            let _ = throttle::remove(throttle);
+        }
+    }
+
+    /// Removes every CPU cap and memory limit, so none outlives the table.
+    pub(crate) fn release_throttles(&mut self) {
+        for throttle in self.throttles.drain(..) {
+            let _ = throttle::remove(throttle);
+        }
+    }
+
+    /// Sends the signal chosen in the picker, or the number typed into it, to the selected process.
+    pub(crate) fn submit_signal(&mut self) {
+        let number = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        if let Some(tid) = self.signal_thread.take() {
+            return self.submit_thread_signal(tid, &number);
+        }
+        let action = if number.is_empty() {
+            KillAction::Signal(signal::PICKER[self.signal_choice].0)
+        } else {
+            KillAction::SignalNumber(number)
+        };
+        self.request_kill(action, false);
+    }
+
+    /// Sends the signal chosen in the picker, or the number typed into it, to the thread `tid` of
+    /// the process in the detail view alone.
+    fn submit_thread_signal(&mut self, tid: u32, number: &str) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let Some(details) = &self.details else {
+            return;
+        };
+        let (pid, name, start_time) = (details.pid, details.name.clone(), details.start_time);
+        let (number, what) = if number.is_empty() {
+            let signal = signal::THREAD_PICKER[self.signal_choice].0;
+            (
+                signal::number(signal),
+                format!("SIG{}", signal::name(signal)),
+            )
+        } else {
+            (
+                number.parse().ok().filter(|&number| number > 0),
+                format!("signal {number}"),
+            )
+        };
+        let Some(number) = number else {
+            self.notice = Some(format!("Not a signal number: {what}"));
+            return;
+        };
+        #[cfg(unix)]
+        if [libc::SIGKILL, libc::SIGSTOP].contains(&number) {
+            self.notice = Some(format!(
+                "Not sent: {what} acts on the whole process, send it from the table"
+            ));
+            return;
+        }
+        // The PID may have been given to another process since the details were read.
+        let same = self.ctx.refresh_process(pid)
+            && self
+                .ctx
+                .process(pid)
+                .is_some_and(|process| process.start_time() == start_time);
+        if !same {
+            self.notice = Some(format!("{name} ({pid}) has exited, nothing was sent"));
+            return;
+        }
+        self.notice = Some(match threads::signal(pid.as_u32(), tid, number) {
+            Ok(()) => {
+                self.log_signal(pid.as_u32(), format!("{what} to thread {tid}"));
+                format!("Sent {what} to thread {tid} of {name} ({pid})")
+            }
+            Err(e) => {
+                let what = format!("{what} to thread {tid}");
+                self.audit(pid.as_u32(), &what, &e.to_string());
+                format!("Cannot send {what} of {name} ({pid}): {e}")
+            }
+        });
+    }
+
+    /// Says that read-only mode keeps processes from being changed, if it is on.
+    pub(crate) fn refuse_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.notice = Some(i18n::text("status.read_only").to_string());
+        }
+        self.read_only
+    }
+
+    /// Turns read-only mode on, calling off the kills waiting in the queue, or off again unless
+    /// it was started with `--read-only`.
+    pub(crate) fn toggle_read_only(&mut self) {
+        if self.read_only_locked {
+            self.notice = Some("Started with --read-only, which stays on".to_string());
+            return;
+        }
+        self.read_only = !self.read_only;
+        if self.read_only {
+            let cancelled = self.cancel_all_pending();
+            self.notice = Some(match cancelled {
+                0 => i18n::text("status.read_only").to_string(),
+                1 => "Read-only, called off the pending kill".to_string(),
+                n => format!("Read-only, called off {n} pending kills"),
+            });
+        } else {
+            self.notice = Some("Processes can be signalled again".to_string());
+        }
+    }
+
+    /// Asks for confirmation before `action` on the selected process, unless forced or turned off.
+    pub(crate) fn request_kill(&mut self, action: KillAction, force: bool) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if matches!(action, KillAction::Group) {
+            return self.request_group_kill(force);
+        }
+        if matches!(action, KillAction::Container) {
+            return self.request_container_kill(force);
+        }
+        let data = match self.selected_data() {
+            Ok(data) => data,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        if force || !self.confirm_kills || !action.needs_confirmation() {
+            self.perform_kill(action);
+            return;
+        }
+        let (key, pid, name) = (data.key(), data.pid, data.name.clone());
+        let user = users::owner(Pid::from_u32(pid)).unwrap_or_else(|| "unknown".to_string());
+        // The server's processes are not necessarily visible here.
+        let (mut dependents, affected) = if self.is_remote() {
+            (Vec::new(), Vec::new())
+        } else {
+            self.ctx.refresh_processes_specifics(users::refresh_kind());
+            let dependents = dependents::find(&self.ctx, Pid::from_u32(pid));
+            (dependents, self.taken_along(&action, pid))
+        };
+        // Those are part of the kill already.
+        dependents.retain(|dependent| {
+            !affected
+                .iter()
+                .any(|&(pid, _)| pid == dependent.pid.as_u32())
+        });
+        self.pending_kill = Some(PendingKill {
+            action,
+            key,
+            pid: pid.to_string(),
+            name,
+            user,
+            dependents,
+            affected,
+            process_group: signal::process_group(pid),
+        });
+    }
+
+    /// The processes besides `pid` that `action` kills, by PID, from the last refresh of
+    /// `self.ctx`: its descendants, deepest first, or the rest of its process group.
+    fn taken_along(&self, action: &KillAction, pid: u32) -> Vec<(u32, String)> {
+        let name = |pid: Pid| {
+            let process = self.ctx.process(pid)?;
+            Some((pid.as_u32(), process.name().to_string()))
+        };
+        match action {
+            KillAction::Subtree => tree::subtree(&self.ctx, Pid::from_u32(pid))
+                .into_iter()
+                .filter(|&descendant| ![1, pid].contains(&descendant.as_u32()))
+                .filter_map(name)
+                .collect(),
+            KillAction::ProcessGroup => {
+                let Some(group) = signal::process_group(pid) else {
+                    return Vec::new();
+                };
+                let mut members: Vec<_> = self
+                    .ctx
+                    .processes()
+                    .keys()
+                    .filter(|member| {
+                        member.as_u32() != pid
+                            && signal::process_group(member.as_u32()) == Some(group)
+                    })
+                    .filter_map(|&member| name(member))
+                    .collect();
+                members.sort();
+                members
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Widens the kill awaiting confirmation from the process to its descendants, then to its
+    /// process group where there are any, and back.
+    pub(crate) fn cycle_kill_scope(&mut self) {
+        let Some(action) = self.pending_kill.as_ref().map(|pending| &pending.action) else {
+            return;
+        };
+        let next = match action {
+            KillAction::Kill => KillAction::Subtree,
+            KillAction::Subtree if cfg!(unix) => KillAction::ProcessGroup,
+            KillAction::Subtree | KillAction::ProcessGroup => KillAction::Kill,
+            _ => return,
+        };
+        if self.is_remote() {
+            self.notice = Some("Only the process itself can be killed when attached".to_string());
+            return;
+        }
+        self.pending_kill = None;
+        self.request_kill(next, false);
+    }
+
+    /// Pauses the selected process with SIGSTOP, or resumes it with SIGCONT if it is stopped.
+    pub(crate) fn toggle_suspend(&mut self) {
+        let signal = match self.selected_process() {
+            Some(data) if data.state == "Stopped" => Signal::Continue,
+            _ => Signal::Stop,
+        };
+        self.request_kill(KillAction::Signal(signal), false);
+    }
+
+    /// Requests an escalating kill of the selected process with the grace period of its name.
+    pub(crate) fn escalate_selected(&mut self) {
+        if let Some(data) = self.selected_process() {
+            let grace = self.config.grace_period(&data.name);
+            self.request_kill(KillAction::Escalate(grace), false);
+        }
+    }
+
+    fn start_escalation(&mut self, grace: Duration) {
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        let (pid, name, key) = (data.pid, data.name.clone(), data.key());
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            self.notice = Some("Escalating kills are not supported when attached".to_string());
+            return;
+        }
+        // SIGTERM is recorded once the escalation reports it delivered, see `poll_escalations`.
+        self.queue_escalation(key, name.clone(), grace, false);
+        self.save_escalations();
+        let process = format!("{name} ({pid})");
+        self.notice = Some(i18n::fill("status.sending_term", &[("process", &process)]));
+    }
+
+    /// Starts the escalating kill of the process of `key`, with SIGTERM unless `term_sent`, and
+    /// queues its SIGKILL.
+    fn queue_escalation(
+        &mut self,
+        key: ProcessKey,
+        name: String,
+        grace: Duration,
+        term_sent: bool,
+    ) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let id = self.queue.push(
+            key,
+            key.pid.to_string(),
+            name.clone(),
+            Instant::now() + grace,
+            queue::Job::Escalation(Arc::clone(&cancelled)),
+        );
+        self.escalations
+            .push((id, escalate::start(key, name, grace, term_sent, cancelled)));
+    }
+
+    /// Keeps the escalating kills waiting for their SIGKILL where the next session finds them,
+    /// should this one end first.
+    fn save_escalations(&self) {
+        let Some(path) = escalate::pending_path() else {
+            return;
+        };
+        let now = Instant::now();
+        let pending: Vec<_> = self
+            .queue
+            .entries()
+            .iter()
+            .filter(|entry| matches!(entry.job, queue::Job::Escalation(_)))
+            // Without a start time, the next session could not tell the process from another
+            // given its PID since.
+            .filter(|entry| entry.key.start_time != 0)
+            .map(|entry| escalate::Pending {
+                pid: entry.key.pid,
+                start_time: entry.key.start_time,
+                name: entry.name.clone(),
+                due: snapshot::unix_now() + entry.due.saturating_duration_since(now).as_secs(),
+            })
+            .collect();
+        if let Err(e) = escalate::save_pending(&path, &pending) {
+            tracing::warn!("cannot save pending kills to {}: {e}", path.display());
+        }
+    }
+
+    /// Takes up the escalating kills earlier sessions left waiting for their SIGKILL, e.g.
+    /// because they crashed, whose processes are still running.
+    pub(crate) fn resume_escalations(&mut self) {
+        let Some(directory) = crate::logging::directory() else {
+            return;
+        };
+        let mut system = System::new();
+        let mut pending = Vec::new();
+        for path in escalate::abandoned(&mut system, &directory) {
+            match escalate::take_over(&path) {
+                Ok(taken) => pending.extend(taken),
+                Err(e) => self.notice = Some(format!("Pending kills {}: {e}", path.display())),
+            }
+        }
+        let now = snapshot::unix_now();
+        let mut resumed = 0;
+        for pending in pending {
+            let overdue = Duration::from_secs(now.saturating_sub(pending.due));
+            if overdue > escalate::RESUMED_WITHIN
+                || pending.start_time == 0
+                || !escalate::is_running(&mut system, pending.key())
+            {
+                continue;
+            }
+            let grace = Duration::from_secs(pending.due.saturating_sub(now));
+            tracing::info!(
+                "resuming the graceful kill of {} ({}), SIGKILL in {}s",
+                pending.name,
+                pending.pid,
+                grace.as_secs()
+            );
+            self.queue_escalation(pending.key(), pending.name, grace, true);
+            resumed += 1;
+        }
+        self.save_escalations();
+        if resumed > 0 {
+            let kills = if resumed == 1 { "kill" } else { "kills" };
+            self.notice = Some(format!(
+                "Resumed {resumed} graceful {kills} the last session left waiting for SIGKILL; Q lists them"
+            ));
+        }
+    }
+
+    /// Records the SIGTERMs of escalating kills as they are delivered, and reports the kills
+    /// that have finished.
+    pub(crate) fn poll_escalations(&mut self) {
+        let mut outcomes = Vec::new();
+        self.escalations.retain(|(id, rx)| loop {
+            match rx.try_recv() {
+                Ok(outcome) => {
+                    let done = outcome.is_final();
+                    outcomes.push((*id, outcome));
+                    if done {
+                        break false;
+                    }
+                }
+                Err(e) => break e == std::sync::mpsc::TryRecvError::Empty,
+            }
+        });
+        if outcomes.is_empty() {
+            return;
+        }
+        let mut finished = false;
+        for (id, outcome) in &outcomes {
+            if outcome.is_final() {
+                self.queue.remove(*id);
+                finished = true;
+            }
+        }
+        if finished {
+            self.save_escalations();
+        }
+        for (id, outcome) in outcomes {
+            if let escalate::Outcome::Exited { pid, .. }
+            | escalate::Outcome::Killed { pid, .. }
+            | escalate::Outcome::Survived { pid, .. }
+            | escalate::Outcome::Cancelled { pid, .. }
+            | escalate::Outcome::Failed { pid, .. } = &outcome
+            {
+                let pending = self
+                    .sent_signals
+                    .iter_mut()
+                    .filter(|(key, _)| key.pid == pid.as_u32());
+                for sent in pending.flat_map(|(_, sent)| sent) {
+                    sent.kill_due = None;
+                }
+            }
+            self.notice = Some(match outcome {
+                escalate::Outcome::TermSent { pid, name } => {
+                    self.record_kill(pid.as_u32(), name.clone(), Signal::Term);
+                    let entry = self.queue.entries().iter().find(|entry| entry.id == id);
+                    let due = entry.map(|entry| entry.due);
+                    let key = self.key_of(pid.as_u32());
+                    if let Some(sent) = self
+                        .sent_signals
+                        .get_mut(&key)
+                        .and_then(|sent| sent.last_mut())
+                    {
+                        sent.kill_due = due;
+                    }
+                    let grace = due.map_or(0.0, |due| {
+                        due.saturating_duration_since(Instant::now()).as_secs_f64()
+                    });
+                    let (process, grace) = (format!("{name} ({pid})"), format!("{grace:.0}"));
+                    let args: [(&str, &dyn Display); 2] =
+                        [("process", &process), ("seconds", &grace)];
+                    i18n::fill("status.term_sent", &args)
+                }
+                escalate::Outcome::Exited { pid, name, after } => {
+                    let (process, after) = (
+                        format!("{name} ({pid})"),
+                        format!("{:.1}", after.as_secs_f64()),
+                    );
+                    let args: [(&str, &dyn Display); 2] =
+                        [("process", &process), ("seconds", &after)];
+                    i18n::fill("status.exited_on_term", &args)
+                }
+                escalate::Outcome::Killed { pid, name } => {
+                    self.record_kill(pid.as_u32(), name.clone(), Signal::Kill);
+                    let process = format!("{name} ({pid})");
+                    i18n::fill("status.killed_after_term", &[("process", &process)])
+                }
+                escalate::Outcome::Survived { pid, name } => {
+                    self.record_kill(pid.as_u32(), name.clone(), Signal::Kill);
+                    let process = format!("{name} ({pid})");
+                    i18n::fill("status.survived_kill", &[("process", &process)])
+                }
+                escalate::Outcome::Cancelled { pid, name } => {
+                    let process = format!("{name} ({pid})");
+                    i18n::fill("status.kill_called_off", &[("process", &process)])
+                }
+                escalate::Outcome::Failed {
+                    pid,
+                    signal,
+                    message,
+                } => {
+                    self.audit(
+                        pid.as_u32(),
+                        &format!("SIG{}", signal::name(signal)),
+                        &message,
+                    );
+                    message
+                }
+            });
+        }
+    }
+
+    /// Puts the kill waiting for confirmation off until a delay typed into the input popup.
+    pub(crate) fn start_scheduling(&mut self) {
+        self.scheduling = self.pending_kill.take();
+        if self.scheduling.is_some() {
+            self.input.clear();
+            self.reset_cursor();
+            self.mode = AppState::ScheduleMode;
+            self.show_popup = true;
+        }
+    }
+
+    /// Queues the kill being scheduled after the typed delay.
+    pub(crate) fn submit_schedule(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let Some(pending) = self.scheduling.take() else {
+            return;
+        };
+        match queue::parse_delay(&input) {
+            Ok(delay) => {
+                let id = self.queue.push(
+                    pending.key,
+                    pending.pid,
+                    pending.name,
+                    Instant::now() + delay,
+                    queue::Job::Scheduled(pending.action),
+                );
+                let entry = self.queue.entries().iter().find(|entry| entry.id == id);
+                self.notice = entry.map(|entry| {
+                    format!(
+                        "{}; Q lists pending actions",
+                        entry.describe(Instant::now())
+                    )
+                });
+            }
+            Err(reason) => self.show_result(Err(Error::Invalid(reason))),
+        }
+    }
+
+    /// Carries out the scheduled actions whose time has come, on their process if it is still
+    /// listed.
+    pub(crate) fn poll_queue(&mut self) {
+        for entry in self.queue.take_due(Instant::now()) {
+            let queue::Job::Scheduled(action) = entry.job else {
+                continue;
+            };
+            let Some(i) = self.items.iter().position(|data| data.key() == entry.key) else {
+                self.notice = Some(format!(
+                    "Skipped {} to {} ({}): it is no longer listed",
+                    action.describe().trim_end_matches(','),
+                    entry.name,
+                    entry.pid
+                ));
+                continue;
+            };
+            // Act on the process without taking the selection away from where the user left it.
+            let selection = self.selection();
+            self.state.select(Some(i));
+            // Kills that report nothing themselves still say what happened.
+            self.notice = Some(format!(
+                "Sent the scheduled {} to {} ({})",
+                action.describe().trim_end_matches(','),
+                entry.name,
+                entry.pid
+            ));
+            self.perform_kill(action);
+            self.restore_selection(selection);
+        }
+    }
+
+    /// Calls off the action under the cursor of the queue panel.
+    pub(crate) fn cancel_queued(&mut self) {
+        if let Some(entry) = self.queue.cancel(self.queue_choice) {
+            self.notice = Some(format!("Called off {}", entry.describe(Instant::now())));
+            self.save_escalations();
+        }
+        self.queue_choice = self.queue_choice.min(self.queue.len().saturating_sub(1));
+    }
+
+    /// Calls off every queued action, the kill waiting for confirmation and the one waiting to
+    /// be sent with sudo, returning how many.
+    fn cancel_all_pending(&mut self) -> usize {
+        let mut cancelled = usize::from(self.pending_kill.take().is_some());
+        cancelled += usize::from(self.denied_kill.take().is_some());
+        while self.queue.cancel(0).is_some() {
+            cancelled += 1;
+        }
+        self.queue_choice = 0;
+        self.save_escalations();
+        cancelled
+    }
+
+    /// Performs the confirmed kill on the process it was requested for, if that is still listed.
+    pub(crate) fn confirm_kill(&mut self) {
+        let Some(pending) = self.pending_kill.take() else {
+            return;
+        };
+        // Not about the selected process, which may not even be listed.
+        if let KillAction::Matching {
+            pattern,
+            signal,
+            targets,
+        } = pending.action
+        {
+            return self.kill_matching(&pattern, signal, &targets);
+        }
+        match self.items.iter().position(|data| data.key() == pending.key) {
+            Some(i) => {
+                self.state.select(Some(i));
+                self.perform_kill(pending.action);
+            }
+            None => {
+                self.notice = Some(format!(
+                    "{} ({}) is no longer listed",
+                    pending.name, pending.pid
+                ))
+            }
+        }
+    }
+
+    fn perform_kill(&mut self, action: KillAction) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if let Err(e) = self.verify_selected() {
+            self.notice = Some(e);
+            self.refresh();
+            return;
+        }
+        let (signal, number) = match action {
+            KillAction::Kill => {
+                let result = self.delete_proc();
+                return self.show_result(result);
+            }
+            KillAction::Subtree => return self.kill_subtree(),
+            KillAction::ProcessGroup => return self.kill_process_group(),
+            KillAction::Group => return self.kill_group(),
+            KillAction::Container => return self.kill_container(),
+            KillAction::Matching {
+                pattern,
+                signal,
+                targets,
+            } => return self.kill_matching(&pattern, signal, &targets),
+            KillAction::Escalate(grace) => return self.start_escalation(grace),
+            KillAction::Signal(signal) => (signal, None),
+            KillAction::SignalNumber(number) => (Signal::Kill, Some(number)),
+        };
+        let Some(pid) = self.selected_process().map(|data| data.pid) else {
+            return;
+        };
+
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            if number.is_none() && signal == Signal::Kill {
+                let result = self.delete_proc();
+                self.show_result(result);
+            } else {
+                self.notice = Some("Only SIGKILL can be sent through a server".to_string());
+            }
+            return;
+        }
+
+        let request = match number {
+            None => KillRequest::new(pid, signal),
+            Some(number) => match KillRequest::number(pid, &number) {
+                Ok(request) => request,
+                Err(e) => {
+                    self.notice = Some(e);
+                    return;
+                }
+            },
+        };
+        let watch = if matches!(
+            request.signal,
+            KillSignal::Named(Signal::Kill | Signal::Term)
+        ) {
+            self.ctx.refresh_processes_specifics(users::refresh_kind());
+            orphans::OrphanWatch::start(&self.ctx, Pid::from_u32(pid))
+        } else {
+            None
+        };
+        match self.deliver(request) {
+            Ok(message) => {
+                self.orphan_watch = watch;
+                self.notice = Some(message);
+            }
+            Err(e) => {
+                let name = self
+                    .selected_process()
+                    .map(|data| data.name.clone())
+                    .unwrap_or_default();
+                self.error = Some(self.refused(request, &name, e));
+            }
+        }
+        self.refresh();
+    }
+
+    /// Turns a signal that was not delivered into the error for the status line, and offers to
+    /// send it again with sudo if the owner of the process refused it.
+    fn refused(&mut self, request: KillRequest, name: &str, e: KillError) -> Error {
+        // There is no sudo to offer on Windows.
+        if e.denied && cfg!(unix) {
+            self.denied_kill = Some(DeniedKill {
+                request: request.expecting(&self.key_of(request.pid)),
+                name: name.to_string(),
+                owner: users::owner(Pid::from_u32(request.pid)).unwrap_or_default(),
+                sudo_error: None,
+            });
+        }
+        Error::Signal(e)
+    }
+
+    /// Sends the refused signal again through `sudo -n`, if the process is still the one it was
+    /// meant for.
+    pub(crate) fn send_with_sudo(&mut self) {
+        if self.refuse_read_only() {
+            self.denied_kill = None;
+            return;
+        }
+        let Some(denied) = &mut self.denied_kill else {
+            return;
+        };
+        let request = denied.request;
+        let pid = Pid::from_u32(request.pid);
+        let current = self
+            .ctx
+            .refresh_process_specifics(pid, ProcessRefreshKind::new())
+            .then(|| self.ctx.process(pid))
+            .flatten()
+            .is_some_and(|process| {
+                request
+                    .start_time
+                    .is_none_or(|start_time| start_time == 0 || start_time == process.start_time())
+            });
+        if !current {
+            self.notice = Some(format!(
+                "{} ({pid}) has exited, nothing was sent",
+                denied.name
+            ));
+            self.denied_kill = None;
+            return;
+        }
+        if let Err(e) = request.send_with_sudo() {
+            denied.sudo_error = Some(e);
+            return;
+        }
+        let Some(denied) = self.denied_kill.take() else {
+            return;
+        };
+        self.remember_signal(request, denied.name.clone());
+        self.notice = Some(format!(
+            "Sent {} to {} ({pid}) with sudo",
+            signal::describe(request.signal),
+            denied.name
+        ));
+        self.refresh();
+    }
+
+    /// Copies the command sending the refused signal as root, to run it in another terminal.
+    pub(crate) fn copy_sudo_command(&mut self) {
+        let Some(denied) = self.denied_kill.take() else {
+            return;
+        };
+        let command = denied.request.sudo_command();
+        self.notice = Some(match clipboard::copy(&command) {
+            Ok(()) => format!("Copied {command}"),
+            Err(e) => format!("Cannot copy {command}: {e}"),
+        });
+    }
+
+    /// Makes sure the PID of the selected row still belongs to the process shown, and not to a
+    /// process started since that was given the PID of the one that exited.
+    fn verify_selected(&mut self) -> Result<(), String> {
+        let Some(data) = self.selected_process().cloned() else {
+            return Ok(());
+        };
+        // The server's processes are not visible here, it checks the start time itself; old
+        // recordings lack it.
+        if self.is_remote() || data.start_time == 0 {
+            return Ok(());
+        }
+        let pid = Pid::from_u32(data.pid);
+        if !self.ctx.refresh_process(pid) {
+            return Err(format!(
+                "{} ({pid}) has exited, nothing was sent",
+                data.name
+            ));
+        }
+        match self.ctx.process(pid) {
+            Some(process)
+                if process.start_time() == data.start_time && process.name() == data.name =>
+            {
+                Ok(())
+            }
+            Some(process) => Err(format!(
+                "PID {pid} now belongs to {}, started after {} exited; nothing was sent",
+                process.name(),
+                data.name
+            )),
+            None => Err(format!(
+                "{} ({pid}) has exited, nothing was sent",
+                data.name
+            )),
+        }
+    }
+
+    /// Value of copy mode column `column` for the selected process.
+    fn copy_cell(&self, column: usize) -> Option<String> {
+        let data = self.selected_process()?;
+        Some(match column {
+            0 => data.name.clone(),
+            1 => data.pid.to_string(),
+            2 => self.units.cpu(data.cpu_usage),
+            3 => self.units.memory(data.memory),
+            4 => data.exe.clone(),
+            _ => data.user.clone(),
+        })
+    }
+
+    /// Shows the cell under the copy mode cursor, which may not be a table column.
+    pub(crate) fn show_copy_cell(&mut self) {
+        let value = self.copy_cell(self.copy_column).unwrap_or_default();
+        self.notice = Some(format!(
+            " {}: {value} (y to copy, V to copy the row, Esc to leave) ",
+            COPY_COLUMNS[self.copy_column]
+        ));
+    }
+
+    /// Copies the cell under the cursor, or the whole row as tab-separated values.
+    pub(crate) fn copy_selection(&mut self, whole_row: bool) {
+        let text = if whole_row {
+            (0..COPY_COLUMNS.len())
+                .filter_map(|column| self.copy_cell(column))
+                .collect::<Vec<_>>()
+                .join("\t")
+        } else {
+            self.copy_cell(self.copy_column).unwrap_or_default()
+        };
+        self.notice = Some(match clipboard::copy(&text) {
+            Ok(()) => format!(" Copied {} ", text.replace('\t', " ")),
+            Err(e) => format!(" Cannot copy: {e} "),
+        });
+    }
+
+    /// Opens the audit log of the signals sent, from this session and the ones before.
+    pub(crate) fn open_audit(&mut self) {
+        let Some(path) = &self.audit_path else {
+            self.notice = Some("No audit log without a home directory".to_string());
+            return;
+        };
+        match audit::read(path) {
+            Ok(entries) => {
+                self.audit_entries = Some(entries);
+                self.audit_scroll = 0;
+            }
+            Err(e) => self.notice = Some(format!("Audit log {}: {e}", path.display())),
+        }
+    }
+
+    /// Announces kills by the kernel's OOM killer, so they are not taken for a person's doing.
+    pub(crate) fn poll_oom(&mut self) {
+        let kills: Vec<_> = match &self.oom_rx {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return,
+        };
+        for kill in kills {
+            self.oom_killed.insert(self.key_of(kill.pid));
+            self.note_signal(kill.pid, "SIGKILL by the kernel's OOM killer".to_string());
+            self.notice = Some(match kill.anon_rss {
+                Some(rss) => format!(
+                    "Out of memory: the kernel killed {} ({}), which held {}",
+                    kill.name,
+                    kill.pid,
+                    self.units.memory(rss)
+                ),
+                None => format!(
+                    "Out of memory: the kernel killed {} ({})",
+                    kill.name, kill.pid
+                ),
+            });
+            if let Some(events) = &mut self.events {
+                if let Err(e) = events.oom_killed(kill.pid, &kill.name, kill.anon_rss) {
+                    self.notice = Some(format!("Cannot write event: {e}"));
+                }
+            }
+        }
+    }
+
+    /// Remembers a kill for the incident report and the event stream.
+    fn record_kill(&mut self, pid: u32, name: String, signal: Signal) {
+        if let Some(events) = &mut self.events {
+            if let Err(e) = events.killed(pid, &name, signal) {
+                self.notice = Some(format!("Cannot write event: {e}"));
+            }
+        }
+        self.log_signal(pid, format!("SIG{}", signal::name(signal)));
+        if let Some(killed) = self
+            .unfiltered
+            .iter()
+            .chain(&self.tree_hidden)
+            .find(|data| data.pid == pid)
+        {
+            self.respawns
+                .watch(killed, &self.unfiltered, snapshot::unix_now());
+        }
+        self.kills.push(KillRecord {
+            timestamp: snapshot::unix_now(),
+            pid,
+            name,
+        });
+    }
+
+    /// Remembers a signal sent from here, also in the audit log.
+    fn log_signal(&mut self, pid: u32, signal: String) {
+        self.audit(pid, &signal, audit::SENT);
+        self.note_signal(pid, signal);
+    }
+
+    /// Adds a signal sent to `pid`, or not sent for the reason of `outcome`, to the audit log.
+    fn audit(&self, pid: u32, signal: &str, outcome: &str) {
+        let Some(path) = &self.audit_path else {
+            return;
+        };
+        let process = self
+            .unfiltered
+            .iter()
+            .chain(&self.tree_hidden)
+            .find(|data| data.pid == pid);
+        let (user, sudo_user) = self.audit_sender.clone();
+        let entry = audit::Entry {
+            timestamp: snapshot::unix_now(),
+            pid,
+            name: process.map(|data| data.name.clone()).unwrap_or_default(),
+            signal: signal.to_string(),
+            outcome: outcome.to_string(),
+            user,
+            sudo_user,
+            owner: process.map(|data| data.user.clone()).unwrap_or_default(),
+        };
+        if let Err(e) = audit::append(path, &entry) {
+            tracing::warn!("cannot write to the audit log {}: {e}", path.display());
+        }
+    }
+
+    /// Remembers a signal for the session log and the detail view of its process.
+    fn note_signal(&mut self, pid: u32, signal: String) {
+        if self.recording.is_some() {
+            let action = replay::Action::Signal {
+                timestamp: snapshot::unix_now(),
+                pid,
+                name: self
+                    .unfiltered
+                    .iter()
+                    .chain(&self.tree_hidden)
+                    .find(|data| data.pid == pid)
+                    .map(|data| data.name.clone())
+                    .unwrap_or_default(),
+                signal: signal.clone(),
+            };
+            self.write_recording(|log| log.action(&action));
+        }
+        self.sent_signals
+            .entry(self.key_of(pid))
+            .or_default()
+            .push(SentSignal {
+                signal,
+                timestamp: snapshot::unix_now(),
+                at: Instant::now(),
+                kill_due: None,
+            });
+    }
+
+    /// Key of the listed process with `pid`, with an unknown start time if it is not listed.
+    fn key_of(&self, pid: u32) -> ProcessKey {
+        self.unfiltered
+            .iter()
+            .chain(&self.tree_hidden)
+            .find(|data| data.pid == pid)
+            .map_or(ProcessKey { pid, start_time: 0 }, ProcessInfo::key)
+    }
+
+    /// Asks about children that survived a killed parent, once it has exited.
+    pub(crate) fn poll_orphans(&mut self) {
+        let Some(watch) = &self.orphan_watch else {
+            return;
+        };
+        if let Some(survivors) = watch.poll(&mut self.ctx) {
+            self.orphans = survivors;
+            self.orphan_watch = None;
+        } else if watch.expired() {
+            self.orphan_watch = None;
+        }
+    }
+
+    pub(crate) fn kill_orphans(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let orphans = std::mem::take(&mut self.orphans);
+        let mut killed = 0;
+        for (key, name) in &orphans {
+            let request = KillRequest::new(key.pid, Signal::Kill);
+            if request.expecting(key).send(&mut self.ctx).is_ok() {
+                killed += 1;
+                self.record_kill(key.pid, name.clone(), Signal::Kill);
+            }
+        }
+        let args: [(&str, &dyn Display); 2] = [("killed", &killed), ("total", &orphans.len())];
+        self.notice = Some(i18n::fill("status.killed_orphans", &args));
+        self.refresh();
+    }
+
+    pub(crate) fn start_update_check(&mut self) {
+        self.update_status = UpdateStatus::Checking;
+        self.update_rx = Some(update::check_in_background());
+    }
+
+    /// Reloads the full process list and keeps only processes whose name matches `pattern`.
+    pub fn apply_filter(&mut self, pattern: &str) {
+        self.refresh();
+        self.filter = pattern.to_string();
+        self.record_filter();
+        self.items = std::mem::take(&mut self.unfiltered);
+        self.tree_hidden.clear();
+        self.filter_items();
+        self.sort_items();
+        self.state.select(Some(0));
+    }
+
+    pub fn send_signal(&mut self, pid: u32, signal: Signal) -> Result<String, String> {
+        Ok(self.deliver(KillRequest::new(pid, signal))?)
+    }
+
+    /// Sends `request` and remembers it for the detail view, and kills for the incident report.
+    fn deliver(&mut self, request: KillRequest) -> Result<String, KillError> {
+        if self.read_only {
+            return Err(KillError {
+                message: i18n::text("status.read_only").to_string(),
+                denied: false,
+            });
+        }
+        let killed = match request.send(&mut self.ctx) {
+            Ok(killed) => killed,
+            Err(e) => {
+                self.audit(request.pid, &signal::describe(request.signal), &e.message);
+                return Err(e);
+            }
+        };
+        self.remember_signal(request, killed.name);
+        Ok(killed.message)
+    }
+
+    fn remember_signal(&mut self, request: KillRequest, name: String) {
+        let pid = request.pid;
+        match request.signal {
+            KillSignal::Named(signal @ (Signal::Kill | Signal::Term)) => {
+                self.record_kill(pid, name, signal);
+            }
+            signal => self.log_signal(pid, signal::describe(signal)),
+        }
+    }
+
+    /// The displayed (possibly filtered) table, without the processes that have exited. Collapsed
+    /// subtrees and groups are included, groups as their processes.
+    pub fn snapshot(&self) -> snapshot::Snapshot {
+        snapshot::Snapshot {
+            timestamp: snapshot::unix_now(),
+            processes: self
+                .items
+                .iter()
+                .chain(&self.tree_hidden)
+                .filter(|data| data.exited.is_none() && data.members == 0)
+                .map(|data| snapshot::ProcessSample {
+                    pid: data.pid,
+                    name: data.name.clone(),
+                    cpu_usage: data.cpu_usage,
+                    memory: data.memory,
+                    virtual_memory: data.virtual_memory,
+                    shared_memory: data.shared_memory,
+                    swap: data.swap,
+                    exe: data.exe.clone(),
+                    command: data.command.clone(),
+                    parent: data.parent,
+                    start_time: data.start_time,
+                    user: data.user.clone(),
+                    state: data.state.clone(),
+                    sched: data.sched.clone(),
+                    disk_read: data.disk_read,
+                    disk_written: data.disk_written,
+                    threads: data.threads,
+                    fds: data.fds,
+                    tty: data.tty.clone(),
+                    session: data.session,
+                    cpu_time: data.cpu_time,
+                    container: data.container.clone(),
+                    window: data.window.clone(),
+                    gpu: data.gpu,
+                    gpu_memory: data.gpu_memory,
+                    custom: data.custom.clone(),
+                })
+                .collect(),
+            without_disk: self.without_disk,
+        }
+    }
+
+    /// Writes the displayed (possibly filtered) table to `path` as JSON.
+    pub fn write_snapshot(&self, path: &std::path::Path) -> io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(&self.snapshot())?)
+    }
+
+    /// Writes the displayed table to a file in the format set as `export_format` in the config.
+    pub(crate) fn export_table(&mut self) {
+        let format = self.config.export_format;
+        let path = format!(
+            "syskill-export-{}.{}",
+            snapshot::unix_now(),
+            format.extension()
+        );
+        self.export_to(&path, format);
+    }
+
+    /// Writes the displayed table to `path`.
+    fn export_to(&mut self, path: &str, format: export::ExportFormat) {
+        let snapshot = self.snapshot();
+        self.notice = Some(
+            match export::render(&snapshot, format)
+                .and_then(|rendered| std::fs::write(path, rendered).map_err(|e| e.to_string()))
+            {
+                Ok(()) => format!("Exported {} processes to {path}", snapshot.processes.len()),
+                Err(e) => format!("Failed to write {path}: {e}"),
+            },
+        );
+    }
+
+    /// Executes commands received on the control socket.
+    #[cfg(unix)]
+    pub(crate) fn poll_control(&mut self) {
+        use control::ControlCommand;
+
+        let requests: Vec<_> = match &self.control {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return,
+        };
+        for request in requests {
+            let result = match &request.command {
+                ControlCommand::List => {
+                    request.reply_processes(&self.snapshot().processes);
+                    continue;
+                }
+                ControlCommand::Filter(pattern) => {
+                    self.apply_filter(pattern);
+                    Ok(format!("{} processes", self.items.len()))
+                }
+                ControlCommand::Kill {
+                    pid,
+                    signal,
+                    start_time,
+                } => {
+                    let mut request = KillRequest::new(*pid, *signal);
+                    if let Some(start_time) = *start_time {
+                        let pid = *pid;
+                        request = request.expecting(&ProcessKey { pid, start_time });
+                    }
+                    let result = self.deliver(request).map_err(String::from);
+                    self.refresh();
+                    result
+                }
+                ControlCommand::Snapshot(path) => self
+                    .write_snapshot(path)
+                    .map(|()| format!("wrote {}", path.display()))
+                    .map_err(|e| e.to_string()),
+                ControlCommand::Refresh => {
+                    self.refresh();
+                    Ok(String::new())
+                }
+            };
+            if let Err(e) = &result {
+                self.notice = Some(format!("Control: {e}"));
+            }
+            request.reply(result);
+        }
+    }
+
+    /// Records the outcome of kills carried out by the server of an attached session.
+    #[cfg(unix)]
+    pub(crate) fn poll_remote(&mut self) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+        if remote.take_closed() {
+            self.notice =
+                Some("Lost the connection to the server; its last snapshot stays".to_string());
+        }
+        let results = remote.take_kill_results();
+        for (pid, name, ok, read_only, error) in results {
+            if ok {
+                self.record_kill(pid, name, Signal::Kill);
+                self.refresh();
+            } else if read_only {
+                self.notice = Some(format!("The server is read-only, {pid} was not killed"));
+            } else if let Some(error) = error {
+                self.notice = Some(format!("Server failed to kill {pid}: {error}"));
+            } else {
+                self.notice = Some(format!("Server failed to kill {pid}"));
+            }
+        }
+    }
+
+    /// Picks up the result of a background update check, announcing new releases.
+    pub(crate) fn poll_update(&mut self) {
+        let Some(status) = self.update_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        if let UpdateStatus::Available(_) = status {
+            self.notice = Some(status.describe());
+        }
+        self.update_status = status;
+        self.update_rx = None;
+    }
+
+    /// Summarises the application state for the crash reporter.
+    #[cfg(unix)]
+    pub(crate) fn is_remote(&self) -> bool {
+        self.remote.is_some()
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn is_remote(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn crash_state(&self) -> crash::StateSummary {
+        let selected = self.state.selected();
+        crash::StateSummary {
+            mode: format!("{:?}", self.mode),
+            items: self.items.len(),
+            selected,
+            kills: self.kills.len(),
+            search_len: self.input.chars().count(),
+            popup_open: self.show_popup
+                || self.show_about
+                || self.details.is_some()
+                || self.renice.is_some()
+                || self.pending_kill.is_some(),
+            selected_name: selected
+                .and_then(|i| self.items.get(i))
+                .map(|data| data.name.clone()),
+            killed_names: self.kills.iter().map(|kill| kill.name.clone()).collect(),
+        }
+    }
+
+    /// Writes an incident report for the current snapshot to the working directory.
+    /// Writes a report in `format`, or with the template set as `report_template` in the config.
+    pub(crate) fn export_report(&mut self, format: ReportFormat) {
+        if !self.is_remote() {
+            // Only names and PIDs were read at startup, see `quick_load`.
+            self.ctx.refresh_cpu();
+            self.ctx.refresh_memory();
+            self.ctx.refresh_processes_specifics(users::refresh_kind());
+        }
+        let report = Report::collect(
+            &self.ctx,
+            &self.kills,
+            Some(WatchdogSummary::collect(&self.watchdog)),
+            10,
+            self.user.as_ref(),
+        );
+        let rendered = match &self.config.report_template {
+            Some(name) => self
+                .config
+                .template(name)
+                .and_then(|(template, extension)| {
+                    let rendered = report.render_template(&template, self.units)?;
+                    Ok((rendered, extension.to_string()))
+                }),
+            None => Ok((
+                report.render(format, self.units),
+                format.extension().to_string(),
+            )),
+        };
+        self.notice = Some(match rendered {
+            Ok((rendered, extension)) => {
+                let path = format!("syskill-report-{}.{extension}", report.generated_at);
+                match std::fs::write(&path, rendered) {
+                    Ok(()) => format!("Report written to {path}"),
+                    Err(e) => format!("Failed to write {path}: {e}"),
+                }
+            }
+            Err(e) => format!("Failed to write report: {e}"),
+        });
+    }
+
+    /// Shows the newest snapshot from the collector (or the attached server) unless paused, or
+    /// once the selection has stopped moving.
+    pub(crate) fn poll_live(&mut self) {
+        let mut snapshot = match self.live.as_ref().and_then(collector::Live::latest) {
+            Some(Err(e)) => {
+                self.collect_error = Some(e);
+                None
+            }
+            update => update.and_then(Result::ok),
+        };
+        #[cfg(unix)]
+        if let Some(remote) = &self.remote {
+            snapshot = remote
+                .latest()
+                .filter(|latest| latest.timestamp != self.remote_timestamp);
+        }
+        let Some(snapshot) = snapshot else {
+            if !self.paused && !self.holding() {
+                if let Some(held) = self.held.take() {
+                    self.show_snapshot(held);
+                }
+            }
+            return;
+        };
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            // The server's interval is unknown; expect updates at the pace they have been coming.
+            if self.remote_timestamp != 0 {
+                let gap = snapshot
+                    .timestamp
+                    .saturating_sub(self.remote_timestamp)
+                    .max(1);
+                self.expected_interval = Some(Duration::from_secs(gap));
+            }
+            self.remote_timestamp = snapshot.timestamp;
+        }
+        self.collect_error = None;
+        if std::mem::take(&mut self.refresh_asked) {
+            self.held = None;
+            self.show_snapshot(snapshot);
+        } else if self.paused {
+            self.held = None;
+        } else if self.holding() {
+            self.held = Some(snapshot);
+        } else {
+            self.held = None;
+            self.show_snapshot(snapshot);
+        }
+    }
+
+    /// Notes that the selection moved, holding live updates back for `navigation_hold`.
+    pub(crate) fn note_move(&mut self) {
+        self.moved_at = Some(Instant::now());
+    }
+
+    /// Whether live updates are held back because the selection moved a moment ago.
+    fn holding(&self) -> bool {
+        self.moved_at
+            .is_some_and(|moved_at| moved_at.elapsed() < self.config.navigation_hold())
+    }
+
+    /// Appends every snapshot shown and every action taken from now on to the session log at
+    /// `path`.
+    pub(crate) fn start_recording(&mut self, path: &std::path::Path) {
+        match replay::SessionLog::open(path) {
+            Ok(log) => {
+                self.recording = Some(log);
+                self.notice = Some(format!("Recording to {}", path.display()));
+            }
+            Err(e) => self.notice = Some(format!("Cannot record to {}: {e}", path.display())),
+        }
+    }
+
+    /// Writes to the session log, if there is one, and stops logging if that fails.
+    fn write_recording(&mut self, write: impl FnOnce(&mut replay::SessionLog) -> io::Result<()>) {
+        if let Some(recording) = &mut self.recording {
+            if let Err(e) = write(recording) {
+                self.notice = Some(format!("Cannot record: {e}; stopped recording"));
+                self.recording = None;
+            }
+        }
+    }
+
+    /// Logs the searches, once one is entered or dropped.
+    fn record_filter(&mut self) {
+        let filter = self.filter_chain().collect::<Vec<_>>().join(" › ");
+        self.write_recording(|log| log.filter(&filter));
+    }
+
+    /// Replaces the table with `snapshot`, keeping the search filter and the selected process.
+    pub fn show_snapshot(&mut self, snapshot: snapshot::Snapshot) {
+        let start = Instant::now();
+        if let Some(events) = &mut self.events {
+            if let Err(e) = events.observe(&snapshot) {
+                self.notice = Some(format!("Cannot write event: {e}"));
+            }
+        }
+        self.write_recording(|log| log.snapshot(&snapshot));
+        let selection = self.selection();
+        self.previous_cpu = self
+            .items
+            .iter()
+            .map(|data| (data.key(), data.cpu_usage))
+            .collect();
+
+        if !self.is_remote() {
+            self.sample_summary();
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.update(&snapshot, self.summary.as_ref());
+        }
+        self.tree_hidden.clear();
+        self.without_disk = snapshot.without_disk;
+        self.items = snapshot
+            .processes
+            .into_iter()
+            .map(ProcessInfo::from)
+            .collect();
+        self.redact_commands();
+        if self.numa.is_some() {
+            for data in &mut self.items {
+                data.node = numa::home_node(&numa::memory_per_node(data.pid))
+                    .map_or_else(|| "-".to_string(), |node| node.to_string());
+            }
+        }
+        self.filter_items();
+        if self.focus {
+            if let Some(notice) = self.update_focus() {
+                self.notice = Some(notice);
+            }
+        }
+        self.sort_items();
+        self.history.record(&self.unfiltered, !self.without_disk);
+        self.record_exits();
+        self.check_respawns();
+        self.changes.observe(&self.unfiltered);
+        self.suspects.observe(
+            &self.unfiltered,
+            &self.history,
+            &self.exits,
+            snapshot::unix_now(),
+            self.units,
+        );
+        self.consumers.observe(&self.unfiltered);
+        if self.show_ephemeral {
+            self.observe_ephemeral();
+        }
+        self.updated_at = Instant::now();
+
+        self.restore_selection(selection);
+        self.check_followed();
+        if !self.is_remote() {
+            self.run_watchdog();
+        }
+        self.check_alerts();
+        tracing::debug!(
+            shown = self.items.len(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "showed a snapshot"
+        );
+    }
+
+    /// Adds the processes gone since the last snapshot to the exit log, with how they ended.
+    fn record_exits(&mut self) {
+        for mut exit in self.exits.observe(&self.unfiltered, snapshot::unix_now()) {
+            // The connector and signals sent from here are of this machine.
+            if !self.is_remote() {
+                exit.reason = self.exit_reason(exit.key);
+            }
+            self.exits.record(exit);
+        }
+    }
+
+    /// Warns about killed processes that came back, as killing them again will not help.
+    fn check_respawns(&mut self) {
+        let respawns = self.respawns.observe(&self.unfiltered);
+        let Some(first) = respawns.first() else {
+            return;
+        };
+        for respawn in &respawns {
+            tracing::info!("{}", respawn.describe());
+        }
+        let more = match respawns.len() {
+            1 => String::new(),
+            count => format!(" (and {} more)", count - 1),
+        };
+        self.notice = Some(format!("{}{more}", first.describe()));
+    }
+
+    /// Asks for a name to narrow the exit log down to, offering the current one.
+    pub(crate) fn start_exits_filter(&mut self) {
+        self.input = self.exits_filter.clone();
+        self.character_index = self.input.chars().count();
+        self.mode = AppState::ExitFilterMode;
+        self.show_popup = true;
+    }
+
+    pub(crate) fn submit_exits_filter(&mut self) {
+        self.exits_filter = std::mem::take(&mut self.input).trim().to_string();
+        self.reset_cursor();
+        self.exits_scroll = 0;
+    }
+
+    /// Adds the processes that went above a threshold to the alert pane, and tells about the
+    /// first on the status line.
+    fn check_alerts(&mut self) {
+        let alerts = self.alerts.check(&self.unfiltered, snapshot::unix_now());
+        if let Some(first) = alerts.first() {
+            let more = match alerts.len() {
+                1 => String::new(),
+                count => format!(" and {} more (G)", count - 1),
+            };
+            self.notice = Some(format!(
+                "Alert: {} ({}) at {}{more}",
+                first.name, first.key.pid, first.reason
+            ));
+        }
+        for alert in alerts {
+            self.alerts.record(alert);
+        }
+    }
+
+    /// Signals the processes the watchdog rules went off for, or only logs it in dry-run or
+    /// read-only mode.
+    fn run_watchdog(&mut self) {
+        let due = self
+            .watchdog
+            .check(&self.unfiltered, self.units, Instant::now());
+        for (rule, key, name) in due {
+            let signal = self.watchdog.rules[rule].signal;
+            let signal_name = format!("SIG{}", signal::name(signal));
+            let outcome = if self.watchdog.dry_run {
+                format!("would have sent {signal_name} (dry run)")
+            } else if self.read_only {
+                format!("would have sent {signal_name} (read-only)")
+            } else {
+                match KillRequest::new(key.pid, signal)
+                    .expecting(&key)
+                    .send(&mut self.ctx)
+                {
+                    Ok(_) => {
+                        if matches!(signal, Signal::Kill | Signal::Term) {
+                            self.record_kill(key.pid, name.clone(), signal);
+                        } else {
+                            self.log_signal(key.pid, signal_name.clone());
+                        }
+                        format!("sent {signal_name}")
+                    }
+                    Err(e) => e.message,
+                }
+            };
+            let rule = self.watchdog.rules[rule].describe();
+            self.notice = Some(format!(
+                "Watchdog, {rule}: {outcome} to {name} ({})",
+                key.pid
+            ));
+            self.watchdog.record(watchdog::Trigger {
+                timestamp: snapshot::unix_now(),
+                rule,
+                pid: key.pid,
+                name,
+                outcome,
+            });
+        }
+    }
+
+    /// Opens the input popup for a new watchdog rule.
+    pub(crate) fn start_watchdog_rule(&mut self) {
+        self.input.clear();
+        self.reset_cursor();
+        self.watchdog_editing = None;
+        self.mode = AppState::WatchdogMode;
+        self.show_popup = true;
+    }
+
+    /// Opens the input popup with the watchdog rule under the cursor of the panel.
+    pub(crate) fn edit_watchdog_rule(&mut self) {
+        let Some(rule) = self.watchdog.rules.get(self.watchdog_choice) else {
+            return;
+        };
+        self.input = rule.typed();
+        self.character_index = self.input.chars().count();
+        self.watchdog_editing = Some(self.watchdog_choice);
+        self.mode = AppState::WatchdogMode;
+        self.show_popup = true;
+    }
+
+    /// Adds the watchdog rule typed into the input popup, or puts it in the place of the one
+    /// edited, and saves the rules.
+    pub(crate) fn submit_watchdog_rule(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        let rule = match watchdog::Rule::parse(&input) {
+            Ok(rule) => rule,
+            Err(reason) => return self.show_result(Err(Error::Invalid(reason))),
+        };
+        let described = rule.describe();
+        match self.watchdog_editing.take() {
+            Some(index) => {
+                let name = self.watchdog.rules[index].name().map(str::to_string);
+                self.watchdog.replace(index, rule.named(name));
+            }
+            None => {
+                self.watchdog.rules.push(rule);
+                self.watchdog_choice = self.watchdog.rules.len() - 1;
+            }
+        }
+        self.update_collected();
+        let result = self.save_watchdog(format!("Watching for {described}"));
+        self.show_result(result);
+    }
+
+    /// Removes the watchdog rule under the cursor of the panel, and saves the rules.
+    pub(crate) fn remove_watchdog_rule(&mut self) {
+        let Some(rule) = self.watchdog.rules.get(self.watchdog_choice) else {
+            return;
+        };
+        let removed = format!("Removed the rule {}", rule.describe());
+        self.watchdog.remove(self.watchdog_choice);
+        self.update_collected();
+        self.watchdog_choice = self
+            .watchdog_choice
+            .min(self.watchdog.rules.len().saturating_sub(1));
+        let result = self.save_watchdog(removed);
+        self.show_result(result);
+    }
+
+    /// Writes the watchdog rules to the settings file, saying `done` and where. The rules apply
+    /// all the same if that fails.
+    fn save_watchdog(&mut self, done: String) -> error::Result<()> {
+        let rules: Vec<watchdog::RuleSpec> = self
+            .watchdog
+            .rules
+            .iter()
+            .map(watchdog::Rule::spec)
+            .collect();
+        self.notice = Some(done.clone());
+        let path = self
+            .config
+            .save_watchdog(&rules)
+            .map_err(Error::Settings)?
+            .display()
+            .to_string();
+        self.config.watchdog = rules;
+        self.notice = Some(format!("{done}; saved to {path}"));
+        Ok(())
+    }
+
+    /// Stops following the followed process once it has exited, saying how it ended if that is
+    /// known.
+    fn check_followed(&mut self) {
+        let Some((key, name)) = &self.follow else {
+            return;
+        };
+        let running = self
+            .items
+            .iter()
+            .chain(&self.tree_hidden)
+            .any(|data| data.key() == *key && data.exited.is_none());
+        if running {
+            return;
+        }
+        let reason = self.exit_reason(*key);
+        self.notice = Some(match reason {
+            Some(reason) => format!("{name} ({}) {reason}; stopped following", key.pid),
+            None => format!("{name} ({}) has exited; stopped following", key.pid),
+        });
+        self.follow = None;
+    }
+
+    /// How the process of `key` ended: killed by the kernel for lack of memory, or its exit
+    /// status as the proc connector reported it, or after a signal sent from here.
+    fn exit_reason(&self, key: ProcessKey) -> Option<String> {
+        if self.oom_killed.contains(&key) {
+            return Some("was killed by the kernel, out of memory".to_string());
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(status) = connector::exit_status(key.pid) {
+            return Some(connector::describe_exit(status));
+        }
+        let sent = self.sent_signals.get(&key)?.last()?;
+        Some(format!(
+            "has exited {}s after {} sent from here",
+            sent.at.elapsed().as_secs(),
+            sent.signal
+        ))
+    }
+
+    /// The selected process and its row, to select it again once the table is rebuilt.
+    fn selection(&self) -> (Option<ProcessKey>, usize) {
+        let index = self.state.selected().unwrap_or(0);
+        (self.items.get(index).map(ProcessInfo::key), index)
+    }
+
+    /// Selects the process of `selection` again or, if it is gone, its neighbor that moved up into
+    /// its row. Returns whether the process was found.
+    fn restore_selection(&mut self, (key, index): (Option<ProcessKey>, usize)) -> bool {
+        let found = key.and_then(|key| self.items.iter().position(|data| data.key() == key));
+        let index = found
+            .unwrap_or(index)
+            .min(self.items.len().saturating_sub(1));
+        self.state.select(Some(index));
+        found.is_some()
+    }
+
+    pub(crate) fn sort_items(&mut self) {
+        self.items.append(&mut self.tree_hidden);
+        // Groups are summed up again from their members below.
+        self.items.retain(|data| data.members == 0);
+        let keys = &self.sort;
+        let providers = plugin::providers();
+        let compare = |a: &ProcessInfo, b: &ProcessInfo| {
+            keys.iter()
+                .fold(Ordering::Equal, |ordering, key| {
+                    ordering.then_with(|| key.compare(a, b, &providers))
+                })
+                // Rows equal in every key still get a fixed place instead of jumping around.
+                .then_with(|| SortKey::new(SortColumn::Pid).compare(a, b, &providers))
+        };
+        self.items.sort_by(compare);
+        self.tree_rows.clear();
+        self.group_rows.clear();
+        if let Some(tree) = &self.tree {
+            (self.items, self.tree_rows, self.tree_hidden) =
+                tree.arrange(std::mem::take(&mut self.items));
+        } else if let Some(groups) = &self.groups {
+            (self.items, self.group_rows, self.tree_hidden) =
+                groups.arrange(std::mem::take(&mut self.items), compare);
+        }
+    }
+
+    /// Rearranges the table after the order or the tree changed, keeping the selected process.
+    fn rearrange(&mut self) {
+        let selection = self.selection();
+        self.sort_items();
+        self.restore_selection(selection);
+    }
+
+    pub(crate) fn toggle_tree(&mut self) {
+        self.tree = match self.tree {
+            Some(_) => None,
+            None => Some(tree::TreeView::default()),
+        };
+        self.groups = None;
+        self.rearrange();
+    }
+
+    pub(crate) fn toggle_groups(&mut self) {
+        self.groups = match self.groups {
+            Some(_) => None,
+            None => Some(group::GroupView::default()),
+        };
+        self.tree = None;
+        self.rearrange();
+    }
+
+    /// Collapses or expands the selected process in tree mode, or the group of the selected row
+    /// in grouped mode.
+    pub(crate) fn toggle_collapsed(&mut self) {
+        let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) else {
+            return;
+        };
+        if let Some(groups) = &mut self.groups {
+            let name = data.name.clone();
+            groups.toggle(&name);
+            self.rearrange();
+            // Collapsing from a member selects the group's row, which takes its place.
+            if let Some(i) = self.items.iter().position(|data| data.name == name) {
+                self.state.select(Some(i));
+            }
+            return;
+        }
+        if let Some(tree) = &mut self.tree {
+            tree.toggle(data.key());
+            self.rearrange();
+        }
+    }
+
+    /// The process table with every process and its parent as they are now, refreshed in place
+    /// without the usage figures, which the table has from its own refreshes.
+    fn current_processes(&mut self) -> &System {
+        self.ctx
+            .refresh_processes_specifics(ProcessRefreshKind::new());
+        &self.ctx
+    }
+
+    /// Kills the selected process together with all its descendants, children first.
+    pub(crate) fn kill_subtree(&mut self) {
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        let (name, root) = (data.name.clone(), data.key());
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            self.notice = Some("Killing a process tree is not supported when attached".to_string());
+            return;
+        }
+        let system = self.current_processes();
+        let subtree = tree::subtree(system, Pid::from_u32(root.pid));
+        let Some(pids) = tree::killable(
+            subtree.into_iter().map(Pid::as_u32).collect(),
+            std::process::id(),
+        ) else {
+            self.notice = Some(format!(
+                "syskill runs in the tree of {name} ({}), kill the process alone",
+                root.pid
+            ));
+            return;
+        };
+        // Each as found now, the root as listed, so a PID given to another process by the time
+        // its turn comes is left alone.
+        let keys: Vec<ProcessKey> = pids
+            .iter()
+            .filter_map(|&pid| system.process(Pid::from_u32(pid)))
+            .map(|process| ProcessKey {
+                pid: process.pid().as_u32(),
+                start_time: process.start_time(),
+            })
+            .map(|key| if key.pid == root.pid { root } else { key })
+            .collect();
+        let killed = self.kill_keys(&keys);
+        let process = format!("{name} ({})", root.pid);
+        let args: [(&str, &dyn Display); 3] = [
+            ("killed", &killed),
+            ("total", &pids.len()),
+            ("process", &process),
+        ];
+        self.notice = Some(i18n::fill("status.killed_tree", &args));
+        self.refresh();
+    }
+
+    /// Sends SIGKILL to the process of each of `keys` that still has its start time, if known;
+    /// how many were killed.
+    fn kill_keys(&mut self, keys: &[ProcessKey]) -> usize {
+        keys.iter()
+            .filter(|key| {
+                let request = KillRequest::new(key.pid, Signal::Kill);
+                let request = match key.start_time {
+                    0 => request,
+                    _ => request.expecting(key),
+                };
+                self.deliver(request).is_ok()
+            })
+            .count()
+    }
+
+    /// Kills every process in the process group of the selected process at once, including
+    /// those started since the confirmation.
+    pub(crate) fn kill_process_group(&mut self) {
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        let (name, pid) = (data.name.clone(), data.pid);
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            self.notice =
+                Some("Killing a process group is not supported when attached".to_string());
+            return;
+        }
+        let Some(group) = signal::process_group(pid) else {
+            self.notice = Some(format!("{name} ({pid}) is in no process group"));
+            return;
+        };
+        // Init's group holds processes it never meant to be one job with, and syskill's own
+        // would take syskill along.
+        if group == 1 || signal::process_group(std::process::id()) == Some(group) {
+            self.notice = Some(format!(
+                "Process group {group} of {name} ({pid}) is not a job of its own, kill the \
+                 process alone or with its descendants"
+            ));
+            return;
+        }
+        self.ctx.refresh_processes_specifics(users::refresh_kind());
+        let mut members = self.taken_along(&KillAction::ProcessGroup, pid);
+        members.push((pid, name.clone()));
+        #[cfg(unix)]
+        let result = signal::send_to_group(group, libc::SIGKILL);
+        #[cfg(not(unix))]
+        let result: io::Result<()> = Err(io::ErrorKind::Unsupported.into());
+        self.notice = Some(match result {
+            Ok(()) => {
+                let killed = members.len();
+                for (pid, name) in members {
+                    self.record_kill(pid, name, Signal::Kill);
+                }
+                format!("Killed process group {group} of {name} ({pid}), {killed} processes")
+            }
+            Err(e) => format!("Cannot kill process group {group} of {name} ({pid}): {e}"),
+        });
+        self.refresh();
+    }
+
+    /// Listed processes of the group `name`, including those of a collapsed group.
+    fn group_members(&self, name: &str) -> Vec<&ProcessInfo> {
+        self.items
+            .iter()
+            .chain(&self.tree_hidden)
+            .filter(|data| data.members == 0 && data.exited.is_none() && data.name == name)
+            .collect()
+    }
+
+    /// Asks for confirmation before killing the group of the selected row, unless forced or
+    /// turned off.
+    fn request_group_kill(&mut self, force: bool) {
+        let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) else {
+            self.error = Some(Error::NoSelection);
+            return;
+        };
+        if force || !self.confirm_kills {
+            return self.kill_group();
+        }
+        let members = self.group_members(&data.name);
+        let user = match members.first() {
+            Some(first) if members.iter().all(|member| member.user == first.user) => {
+                first.user.clone()
+            }
+            _ => "several".to_string(),
+        };
+        self.pending_kill = Some(PendingKill {
+            action: KillAction::Group,
+            key: data.key(),
+            pid: format!("{} processes", members.len()),
+            name: data.name.clone(),
+            user,
+            dependents: Vec::new(),
+            affected: Vec::new(),
+            process_group: None,
+        });
+    }
+
+    /// Kills every listed process with the name of the selected row.
+    pub(crate) fn kill_group(&mut self) {
+        let Some(name) = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|data| data.name.clone())
+        else {
+            return;
+        };
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            self.notice = Some("Killing a group is not supported when attached".to_string());
+            return;
+        }
+        let keys: Vec<ProcessKey> = self
+            .group_members(&name)
+            .into_iter()
+            .map(ProcessInfo::key)
+            .filter(|key| key.pid != 0)
+            .collect();
+        let mut killed = 0;
+        for key in &keys {
+            let pid = Pid::from_u32(key.pid);
+            // Not a process that was given the PID of a member since.
+            let Some(process) = self
+                .ctx
+                .refresh_process_specifics(pid, ProcessRefreshKind::new())
+                .then(|| self.ctx.process(pid))
+                .flatten()
+                .filter(|process| key.start_time == 0 || process.start_time() == key.start_time)
+            else {
+                continue;
+            };
+            if process.kill() {
+                killed += 1;
+                self.record_kill(key.pid, name.clone(), Signal::Kill);
+            }
+        }
+        let args: [(&str, &dyn Display); 3] =
+            [("killed", &killed), ("total", &keys.len()), ("name", &name)];
+        self.notice = Some(i18n::fill("status.killed_group", &args));
+        self.refresh();
+    }
+
+    /// The container of the selected process, e.g. `docker:web`; systemd units do not count.
+    fn selected_container(&self) -> Result<String, String> {
+        let data = self.selected_process().ok_or("Nothing is selected")?;
+        let container = match data.container.as_str() {
+            "" => container::of(data.pid),
+            container => container.to_string(),
+        };
+        // Containers go by `engine:name`, units by their own name.
+        match container.contains(':') {
+            true => Ok(container),
+            false => Err(format!(
+                "{} ({}) does not run in a container",
+                data.name, data.pid
+            )),
+        }
+    }
+
+    /// The running processes in `container`, whatever the search.
+    fn container_members(&self, container: &str) -> Vec<&ProcessInfo> {
+        self.unfiltered
+            .iter()
+            .filter(|data| data.members == 0 && data.exited.is_none())
+            .filter(|data| match data.container.as_str() {
+                "" => container::of(data.pid) == container,
+                known => known == container,
+            })
+            .collect()
+    }
+
+    /// Asks for confirmation before killing every process in the container of the selected
+    /// process, unless forced or turned off.
+    fn request_container_kill(&mut self, force: bool) {
+        if self.is_remote() {
+            self.notice = Some("Killing a container is not supported when attached".to_string());
+            return;
+        }
+        let container = match self.selected_container() {
+            Ok(container) => container,
+            Err(e) => {
+                self.notice = Some(e);
+                return;
+            }
+        };
+        if force || !self.confirm_kills {
+            return self.kill_container();
+        }
+        let Some(key) = self.selected_process().map(ProcessInfo::key) else {
+            return;
+        };
+        let members = self.container_members(&container);
+        let user = match members.first() {
+            Some(first) if members.iter().all(|member| member.user == first.user) => {
+                first.user.clone()
+            }
+            _ => "several".to_string(),
+        };
+        self.pending_kill = Some(PendingKill {
+            action: KillAction::Container,
+            key,
+            pid: format!("{} processes", members.len()),
+            name: container,
+            user,
+            dependents: Vec::new(),
+            affected: Vec::new(),
+            process_group: None,
+        });
+    }
+
+    /// Kills every process in the container of the selected process.
+    pub(crate) fn kill_container(&mut self) {
+        let container = match self.selected_container() {
+            Ok(container) => container,
+            Err(e) => {
+                self.notice = Some(e);
+                return;
+            }
+        };
+        let members: Vec<(ProcessKey, String)> = self
+            .container_members(&container)
+            .into_iter()
+            .map(|data| (data.key(), data.name.clone()))
+            .collect();
+        let mut killed = 0;
+        for (key, name) in &members {
+            let pid = Pid::from_u32(key.pid);
+            // Not a process that was given the PID of a member since.
+            let Some(process) = self
+                .ctx
+                .refresh_process_specifics(pid, ProcessRefreshKind::new())
+                .then(|| self.ctx.process(pid))
+                .flatten()
+                .filter(|process| key.start_time == 0 || process.start_time() == key.start_time)
+            else {
+                continue;
+            };
+            if process.kill() {
+                killed += 1;
+                self.record_kill(key.pid, name.clone(), Signal::Kill);
+            }
+        }
+        let args: [(&str, &dyn Display); 3] = [
+            ("killed", &killed),
+            ("total", &members.len()),
+            ("container", &container),
+        ];
+        self.notice = Some(i18n::fill("status.killed_container", &args));
+        self.refresh();
+    }
+
+    /// The processes `pattern` matches as a search, listed or not, apart from syskill itself.
+    fn matching_processes(&self, pattern: &str) -> Result<Vec<&ProcessInfo>, String> {
+        let query = query::Query::parse(pattern)?;
+        let own = std::process::id();
+        Ok(self
+            .unfiltered
+            .iter()
+            .filter(|data| data.members == 0 && data.exited.is_none())
+            .filter(|data| data.pid != 0 && data.pid != own)
+            .filter(|data| query.matches(data, self.units))
+            .collect())
+    }
+
+    /// Lists every process `pattern` matches for confirmation before `signal` is sent to all of
+    /// them, unless turned off.
+    pub(crate) fn request_pkill(&mut self, pattern: &str, signal: Signal) -> Result<(), String> {
+        if self.read_only {
+            return Err(i18n::text("status.read_only").to_string());
+        }
+        let members = self.matching_processes(pattern)?;
+        let Some(first) = members.first() else {
+            return Err(format!("No process matches {pattern}"));
+        };
+        let user = if members.iter().all(|member| member.user == first.user) {
+            first.user.clone()
+        } else {
+            "several".to_string()
+        };
+        let key = first.key();
+        let targets: Vec<ProcessKey> = members.iter().map(|data| data.key()).collect();
+        let affected = members
+            .iter()
+            .map(|data| (data.pid, data.name.clone()))
+            .collect();
+        if !self.confirm_kills {
+            self.kill_matching(pattern, signal, &targets);
+            return Ok(());
+        }
+        self.pending_kill = Some(PendingKill {
+            action: KillAction::Matching {
+                pattern: pattern.to_string(),
+                signal,
+                targets,
+            },
+            key,
+            pid: format!("{} processes", members.len()),
+            name: pattern.to_string(),
+            user,
+            dependents: Vec::new(),
+            affected,
+            process_group: None,
+        });
+        Ok(())
+    }
+
+    /// Sends `signal` to each of `targets` still running, the processes `pattern` matched.
+    fn kill_matching(&mut self, pattern: &str, signal: Signal, targets: &[ProcessKey]) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let mut sent = 0;
+        let mut failure = None;
+        for key in targets {
+            let request = KillRequest::new(key.pid, signal);
+            // Old recordings lack the start time.
+            let request = if key.start_time == 0 {
+                request
+            } else {
+                request.expecting(key)
+            };
+            match self.deliver(request) {
+                Ok(_) => sent += 1,
+                Err(e) => {
+                    failure.get_or_insert(e.message);
+                }
+            }
+        }
+        let mut notice = format!(
+            "Sent SIG{} to {sent} of {} processes matching {pattern}",
+            signal::name(signal),
+            targets.len()
+        );
+        if let Some(failure) = failure {
+            notice.push_str(&format!("; {failure}"));
+        }
+        self.notice = Some(notice);
+        self.refresh();
+    }
+
+    /// Sorts by `column` first, using the previous order to break ties, or reverses the order if
+    /// the table is already sorted by it.
+    pub(crate) fn sort_by(&mut self, column: SortColumn) {
+        if self.focus {
+            self.focus = false;
+            self.notice = Some("Focus mode off".to_string());
+        }
+        if self.sort.first().is_some_and(|key| key.column == column) {
+            self.sort[0].descending = !self.sort[0].descending;
+        } else {
+            self.sort.retain(|key| key.column != column);
+            self.sort.insert(0, SortKey::new(column));
+        }
+        self.update_collected();
+        self.rearrange();
+    }
+
+    /// Switches between the processes of all users, only the own ones and only root's.
+    pub(crate) fn cycle_owner_filter(&mut self) {
+        let own = users::UserFilter::from_cli(None, true)
+            .ok()
+            .flatten()
+            .map(|user| user.name);
+        self.owner_filter = match self.owner_filter.take() {
+            None => own.or_else(|| Some("root".to_string())),
+            Some(user) if user != "root" => Some("root".to_string()),
+            Some(_) => None,
+        };
+        self.notice = Some(match &self.owner_filter {
+            Some(user) => format!("Showing the processes of {user}"),
+            None => "Showing the processes of all users".to_string(),
+        });
+        self.refilter();
+    }
+
+    /// Switches between all processes and those in a state worth a look, such as zombies.
+    pub(crate) fn toggle_problems_only(&mut self) {
+        self.problems_only = !self.problems_only;
+        self.notice = Some(
+            if self.problems_only {
+                "Showing zombie, stopped, traced and blocked processes"
+            } else {
+                "Showing processes in every state"
+            }
+            .to_string(),
+        );
+        self.refilter();
+    }
+
+    /// Switches between all processes and those started from this terminal session, e.g. to
+    /// clean up what was just launched from it.
+    pub(crate) fn toggle_terminal_filter(&mut self) {
+        if self.terminal_filter.take().is_some() {
+            self.notice = Some("Showing the processes of every terminal".to_string());
+            return self.refilter();
+        }
+        if self.is_remote() {
+            self.notice = Some("Not available when attached to a session".to_string());
+            return;
+        }
+        let Some(terminal) = terminal::Terminal::of_parent() else {
+            self.notice = Some("Cannot tell which terminal session syskill runs in".to_string());
+            return;
+        };
+        self.notice = Some(format!(
+            "Showing the processes started from this terminal (session {})",
+            terminal.session()
+        ));
+        self.terminal_filter = Some(terminal);
+        self.refilter();
+    }
+
+    /// Applies the filters again to the current data, keeping the selected process.
+    fn refilter(&mut self) {
+        let selection = self.selection();
+        self.items = std::mem::take(&mut self.unfiltered);
+        self.tree_hidden.clear();
+        self.filter_items();
+        self.sort_items();
+        self.restore_selection(selection);
+    }
+
+    pub(crate) fn toggle_focus(&mut self) {
+        self.focus = !self.focus;
+        self.notice = Some(if self.focus {
+            self.update_focus()
+                .unwrap_or_else(|| "Focus mode on".to_string())
+        } else {
+            "Focus mode off".to_string()
+        });
+        self.rearrange();
+    }
+
+    /// Puts CPU or memory first in the sort order, depending on which the system is under more
+    /// pressure for. Returns a notice if the order changed.
+    fn update_focus(&mut self) -> Option<String> {
+        let pressure = pressure::read(&mut self.ctx);
+        let current = self.sort.first().map(|key| key.column);
+        let column = match current {
+            // Only switch for a clear difference so the order does not flap.
+            Some(SortColumn::Cpu) if pressure.memory > pressure.cpu + FOCUS_MARGIN => {
+                SortColumn::Memory
+            }
+            Some(SortColumn::Memory) if pressure.cpu > pressure.memory + FOCUS_MARGIN => {
+                SortColumn::Cpu
+            }
+            Some(column @ (SortColumn::Cpu | SortColumn::Memory)) => column,
+            _ if pressure.memory > pressure.cpu => SortColumn::Memory,
+            _ => SortColumn::Cpu,
+        };
+        if current == Some(column) && self.sort[0].descending {
+            return None;
+        }
+        self.sort.retain(|key| key.column != column);
+        self.sort.insert(0, SortKey::new(column));
+        let ((resource, value), (other, other_value)) = match column {
+            SortColumn::Memory => (("memory", pressure.memory), ("CPU", pressure.cpu)),
+            _ => (("CPU", pressure.cpu), ("memory", pressure.memory)),
+        };
+        let measure = if pressure.stall { "stalled" } else { "in use" };
+        Some(format!(
+            "Focus: sorting by {resource} ({resource} {value:.1}% {measure}, {other} {other_value:.1}%)"
+        ))
+    }
+
+    /// Opens the detail view of the selected process.
+    pub(crate) fn open_details(&mut self) {
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        if self.is_remote() {
+            self.notice = Some("Process details are not available when attached".to_string());
+            return;
+        }
+        let (pid, name) = (data.pid, data.name.clone());
+        self.details = details::Details::collect(&mut self.ctx, Pid::from_u32(pid), self.units);
+        if let Some(details) = &mut self.details {
+            details.redact(&self.redactor);
+        }
+        self.details_scroll = 0;
+        self.details_tab = details::Tab::default();
+        self.env_search.clear();
+        self.env_revealed = false;
+        if self.details.is_none() {
+            self.notice = Some(format!("{name} ({pid}) has exited"));
+        }
+    }
+
+    /// Shows or hides the disk read and write rate columns, which widen the table.
+    pub(crate) fn toggle_io_columns(&mut self) {
+        let show = !self.column_chosen(Column::Read);
+        for (column, shown) in &mut self.columns {
+            if matches!(column, Column::Read | Column::Write) {
+                *shown = show;
+            }
+        }
+        self.update_collected();
+        self.notice = Some(if show {
+            "Showing disk I/O rates".to_string()
+        } else {
+            "Hiding disk I/O rates".to_string()
+        });
+    }
+
+    /// Shows full command lines in place of process names, or the names again.
+    pub(crate) fn toggle_full_commands(&mut self) {
+        self.full_commands = !self.full_commands;
+        self.command_offset = 0;
+        self.update_collected();
+        self.notice = Some(if self.full_commands {
+            "Showing command lines, Left and Right to scroll".to_string()
+        } else {
+            "Showing process names".to_string()
+        });
+    }
+
+    /// Scrolls the command lines to the right to see the end of long ones, or back to the left.
+    pub(crate) fn scroll_commands(&mut self, right: bool) {
+        if !self.full_commands && !self.column_chosen(Column::Command) {
+            self.notice = Some("No command lines shown to scroll, Ctrl-e shows them".to_string());
+            return;
+        }
+        let longest = self
+            .items
+            .iter()
+            .map(|data| data.command.chars().count())
+            .max()
+            .unwrap_or(0);
+        self.command_offset = if right {
+            (self.command_offset + COMMAND_SCROLL).min(longest.saturating_sub(1))
+        } else {
+            self.command_offset.saturating_sub(COMMAND_SCROLL)
+        };
+    }
+
+    /// Whether `column` is picked to be shown; some only appear once they have something to show.
+    pub(crate) fn column_chosen(&self, column: Column) -> bool {
+        self.columns
+            .iter()
+            .any(|&(listed, shown)| listed == column && shown)
+    }
+
+    /// Tells the live collector which of the costly fields are needed: the disk totals for the
+    /// I/O rate columns, and the scheduling policy for its column or a search or watchdog rule on
+    /// it.
+    pub(crate) fn update_collected(&self) {
+        let searched = |field: &str| {
+            self.filter_chain().any(|filter| filter.contains(field))
+                || self
+                    .watchdog
+                    .rules
+                    .iter()
+                    .any(|rule| rule.condition().contains(field))
+        };
+        let fields = snapshot::Fields {
+            disk: self.column_chosen(Column::Read)
+                || self.column_chosen(Column::Write)
+                || self.metrics.is_some(),
+            sched: self.column_chosen(Column::Sched) || searched("sched"),
+            fds: self.column_chosen(Column::Fds),
+            memory_detail: self.column_chosen(Column::Shared) || self.column_chosen(Column::Swap),
+            session: self.column_chosen(Column::Tty)
+                || self.column_chosen(Column::Session)
+                || searched("tty")
+                || searched("session")
+                || searched("sid"),
+            container: self.column_chosen(Column::Container) || searched("container"),
+            window: self.column_chosen(Column::Window) || searched("window"),
+            command: self.column_chosen(Column::Command)
+                || self.full_commands
+                || searched("cmd")
+                || searched("command"),
+            gpu: self.column_chosen(Column::Gpu) || self.column_chosen(Column::GpuMemory),
+            cpu_time: self.column_chosen(Column::CpuTime)
+                || self
+                    .sort
+                    .iter()
+                    .any(|key| key.column == SortColumn::CpuTime),
+            custom: self
+                .columns
+                .iter()
+                .any(|&(column, shown)| shown && matches!(column, Column::Custom(_)))
+                || self
+                    .sort
+                    .iter()
+                    .any(|key| matches!(key.column, SortColumn::Custom(_))),
+        };
+        *self.collected.lock().unwrap_or_else(|e| e.into_inner()) = fields;
+    }
+
+    pub(crate) fn open_column_picker(&mut self) {
+        self.column_choice = 0;
+        self.mode = AppState::ColumnMode;
+        self.show_popup = true;
+    }
+
+    /// Shows or hides the column under the picker cursor.
+    pub(crate) fn toggle_column(&mut self) {
+        if let Some((_, shown)) = self.columns.get_mut(self.column_choice) {
+            *shown = !*shown;
+        }
+        self.update_collected();
+    }
+
+    /// Moves the column under the picker cursor one place to the left (`up`) or right, taking
+    /// the cursor along.
+    pub(crate) fn move_column(&mut self, up: bool) {
+        let from = self.column_choice;
+        let to = if up {
+            from.checked_sub(1)
+        } else {
+            Some(from + 1).filter(|&to| to < self.columns.len())
+        };
+        if let Some(to) = to {
+            self.columns.swap(from, to);
+            self.column_choice = to;
+        }
+    }
+
+    /// Writes the shown columns, in their order, to the settings file.
+    pub(crate) fn save_columns(&mut self) -> error::Result<()> {
+        let shown: Vec<Column> = self
+            .columns
+            .iter()
+            .filter(|&&(_, shown)| shown)
+            .map(|&(column, _)| column)
+            .collect();
+        let path = self
+            .config
+            .save_columns(&shown)
+            .map_err(Error::Settings)?
+            .display()
+            .to_string();
+        self.notice = Some(format!("Saved the columns to {path}"));
+        Ok(())
+    }
+
+    /// Starts collecting the processes of this machine every `interval`, or only on refresh with
+    /// a zero interval, in place of the collector so far, which stops once it is dropped.
+    pub(crate) fn start_collector(&mut self, interval: Duration) {
+        let every = (!interval.is_zero()).then_some(interval);
+        self.live = Some(collector::spawn(
+            SystemSource::new(self.user.clone()),
+            every,
+            Arc::clone(&self.collected),
+            self.idle.clone(),
+        ));
+        self.refresh_interval = interval;
+        self.expected_interval =
+            every.map(|interval| interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL));
+    }
+
+    /// Filters the table by `searches` in place of the current ones, each narrowing down the one
+    /// before.
+    pub(crate) fn apply_searches(&mut self, searches: &[String]) {
+        self.filters.clear();
+        self.input.clear();
+        self.update_search();
+        for search in searches {
+            self.start_search();
+            self.input = search.clone();
+            self.update_search();
+            self.submit_message();
+        }
+    }
+
+    /// Switches to the search, sort order, view, columns, palette and refresh interval of the
+    /// profile called `name`, keeping what it leaves out.
+    pub(crate) fn switch_profile(&mut self, name: &str) -> error::Result<()> {
+        let Some(profile) = self.config.profiles.get(name).cloned() else {
+            let names: Vec<_> = self.config.profiles.keys().map(String::as_str).collect();
+            return Err(Error::Invalid(match names.is_empty() {
+                true => format!(
+                    "No profile named {name}; save one with :save_profile or in the config file"
+                ),
+                false => format!("No profile named {name}, expected {}", names.join(", ")),
+            }));
+        };
+        if let Some(palette) = profile.palette {
+            self.set_theme(palette).map_err(Error::Invalid)?;
+        }
+        if !profile.columns.is_empty() {
+            self.columns = config::arranged(&profile.columns);
+            self.column_choice = 0;
+        }
+        if !profile.sort.is_empty() {
+            self.sort = profile.sort;
+        }
+        let local = !self.is_remote() && self.replay.is_none();
+        if let Some(seconds) = profile.refresh_interval.filter(|_| local) {
+            let interval = Duration::try_from_secs_f64(seconds).unwrap_or_default();
+            if interval != self.refresh_interval {
+                self.start_collector(interval);
+            }
+        }
+        self.update_collected();
+        self.apply_searches(&profile.filter);
+        if profile.tree != self.tree.is_some() {
+            self.tree = profile.tree.then(tree::TreeView::default);
+        }
+        if profile.group != self.groups.is_some() {
+            self.groups = profile.group.then(group::GroupView::default);
+        }
+        self.rearrange();
+        if let Some(path) = profile.record.filter(|_| self.recording.is_none()) {
+            self.start_recording(&path);
+        }
+        self.notice = Some(format!("Switched to the {name} profile"));
+        Ok(())
+    }
+
+    /// Writes the search, sort order, view, shown columns, palette and refresh interval of the
+    /// table to the settings file as the profile called `name`.
+    pub(crate) fn save_profile(&mut self, name: &str) -> error::Result<()> {
+        let profile = config::Profile {
+            filter: self.filter_chain().map(str::to_string).collect(),
+            sort: self.sort.clone(),
+            tree: self.tree.is_some(),
+            group: self.groups.is_some(),
+            columns: self
+                .columns
+                .iter()
+                .filter(|&&(_, shown)| shown)
+                .map(|&(column, _)| column)
+                .collect(),
+            palette: Some(self.theme.clone()),
+            refresh_interval: (!self.is_remote() && self.replay.is_none())
+                .then_some(self.refresh_interval.as_secs_f64()),
+            record: self
+                .config
+                .profiles
+                .get(name)
+                .and_then(|profile| profile.record.clone()),
+        };
+        let path = self
+            .config
+            .save_profile(name, &profile)
+            .map_err(Error::Settings)?
+            .display()
+            .to_string();
+        self.config.profiles.insert(name.to_string(), profile);
+        self.notice = Some(format!("Saved the {name} profile to {path}"));
+        Ok(())
+    }
+
+    pub(crate) fn toggle_follow(&mut self) {
+        if self.follow.take().is_some() {
+            self.notice = Some("Stopped following".to_string());
+            return;
+        }
+        let Some(data) = self.selected_process().cloned() else {
+            return self.show_result(Err(Error::NoSelection));
+        };
+        self.notice = Some(format!("Following {} ({})", data.name, data.pid));
+        self.follow = Some((data.key(), data.name));
+    }
+
+    /// Marks or unmarks the selected process as watched.
+    pub(crate) fn toggle_watch(&mut self) {
+        let Some(data) = self.selected_process().cloned() else {
+            return;
+        };
+        let watching = !self.watched.remove(&data.key());
+        if watching {
+            self.watched.insert(data.key());
+        }
+        self.notice = Some(format!(
+            "{} {} ({})",
+            if watching {
+                "Watching"
+            } else {
+                "Stopped watching"
+            },
+            data.name,
+            data.pid
+        ));
+    }
+
+    /// Updates the summary line and the processes flagged for holding much of a system limit.
+    fn sample_summary(&mut self) {
+        let summary = self.summary_sampler.sample();
+        self.heavy = summary
+            .limits
+            .as_ref()
+            .map(limits::heavy_processes)
+            .unwrap_or_default();
+        self.summary = Some(summary);
+    }
+
+    /// Whether any process has labels, is watched or holds much of a system limit, so the tags
+    /// column is worth showing.
+    pub(crate) fn has_tags(&self) -> bool {
+        !self.config.labels.is_empty()
+            || !self.watched.is_empty()
+            || !self.heavy.is_empty()
+            || !self.oom_killed.is_empty()
+            || !self.throttles.is_empty()
+    }
+
+    fn own_tags(&self, data: &ProcessInfo) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .config
+            .labels(&data.name)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        if self.watched.contains(&data.key()) {
+            tags.push("watched".to_string());
+        }
+        if self.oom_killed.contains(&data.key()) {
+            tags.push("oom-killed".to_string());
+        }
+        tags
+    }
+
+    /// Tags of the displayed processes by PID. Ancestors are looked up among all processes, so
+    /// workers keep the tags of a service even when the service itself is filtered out.
+    pub(crate) fn tags(&self) -> HashMap<u32, Tags> {
+        if !self.has_tags() {
+            return HashMap::new();
+        }
+        let by_pid: HashMap<u32, &ProcessInfo> = self
+            .unfiltered
+            .iter()
+            .map(|data| (data.pid, data))
+            .collect();
+        self.items
+            .iter()
+            .map(|data| {
+                let mut own = self.own_tags(data);
+                // Children do not share the files and threads of their parent.
+                own.extend(self.heavy.get(&data.pid).into_iter().flatten().cloned());
+                // Children started later are capped along, but only listed ones are known.
+                if let Some(throttle) = self.throttle_of(data.pid).map(|i| &self.throttles[i]) {
+                    own.extend(
+                        throttle
+                            .percent
+                            .map(|percent| format!("cpu cap {percent}%")),
+                    );
+                    own.extend(
+                        throttle
+                            .memory
+                            .map(|bytes| format!("memory cap {}", self.units.memory(bytes))),
+                    );
+                }
+                let mut inherited = Vec::new();
+                let mut seen = HashSet::from([data.pid]);
+                let mut parent = data.parent;
+                // PID reuse can make parent links loop.
+                while let Some(ancestor) = parent.and_then(|pid| by_pid.get(&pid)) {
+                    if !seen.insert(ancestor.pid) {
+                        break;
+                    }
+                    for tag in self.own_tags(ancestor) {
+                        if !own.contains(&tag) && !inherited.contains(&tag) {
+                            inherited.push(tag);
+                        }
+                    }
+                    parent = ancestor.parent;
+                }
+                (data.pid, Tags { own, inherited })
+            })
+            .collect()
+    }
+
+    pub(crate) fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.notice = Some(
+            if self.paused {
+                "Live updates paused, press p to resume"
+            } else {
+                "Live updates resumed"
+            }
+            .to_string(),
+        );
+    }
+
+    /// Notes that the table is in use, bringing live updates back to speed if they slowed down.
+    pub(crate) fn note_input(&mut self) {
+        self.last_input = Instant::now();
+        if self.idle.is_idle() {
+            self.idle.set(None);
+            self.notice = Some("Live updates back to speed".to_string());
+        }
+    }
+
+    /// Slows live updates down once the table has been left alone for `idle_timeout`.
+    pub(crate) fn check_idle(&mut self) {
+        let Some(timeout) = self.idle_timeout else {
+            return;
+        };
+        if self.focused || self.idle.is_idle() || self.last_input.elapsed() < timeout {
+            return;
+        }
+        self.idle.set(Some(self.idle_refresh));
+    }
+
+    pub fn refresh(&mut self) {
+        self.filter.clear();
+        self.filters.clear();
+        self.record_filter();
+        if self.replay.is_some() {
+            return self.show_replayed();
+        }
+        let selection = self.selection();
+        #[cfg(unix)]
+        if self.remote.is_some() {
+            self.previous_cpu = self
+                .items
+                .iter()
+                .map(|data| (data.key(), data.cpu_usage))
+                .collect();
+            self.clean();
+            self.get_proc();
+            self.restore_selection(selection);
+            return;
+        }
+        if let Some(live) = &self.live {
+            // The collector makes the snapshot, so the table does not wait for it; until then it
+            // shows everything it has again.
+            live.refresh();
+            self.refresh_asked = true;
+            self.items = std::mem::take(&mut self.unfiltered);
+            self.tree_hidden.clear();
+            self.filter_items();
+            self.sort_items();
+            self.restore_selection(selection);
+            return;
+        }
+        self.previous_cpu = self
+            .items
+            .iter()
+            .map(|data| (data.key(), data.cpu_usage))
+            .collect();
+        // The same table refreshed in place, which also measures CPU usage since the last refresh.
+        let start = Instant::now();
+        let ctx = &mut self.ctx;
+        let readable = collector::with_retry(|| {
+            ctx.refresh_processes_specifics(users::refresh_kind());
+            collector::readable(ctx).then_some(())
+        });
+        if readable.is_none() {
+            tracing::warn!("refresh failed: cannot read the process table");
+            self.collect_error = Some("Cannot read the process table".to_string());
+            return;
+        }
+        self.collect_error = None;
+        self.clean();
+        self.get_proc();
+        self.restore_selection(selection);
+        self.updated_at = Instant::now();
+        tracing::debug!(
+            processes = self.unfiltered.len(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "refreshed the table"
+        );
+    }
+
+    /// Shows the snapshot of the replayed session log the replay is at, keeping the search
+    /// filter and the selected process.
+    fn show_replayed(&mut self) {
+        let selection = self.selection();
+        self.previous_cpu = self
+            .items
+            .iter()
+            .map(|data| (data.key(), data.cpu_usage))
+            .collect();
+        self.clean();
+        self.get_proc();
+        self.changes.observe(&self.unfiltered);
+        self.restore_selection(selection);
+    }
+
+    /// Steps through the replayed session log, `steps` snapshots at a time, or to the next or
+    /// previous snapshot with actions before it.
+    pub(crate) fn step_replay(&mut self, steps: isize, to_action: bool) {
+        let Some(replay) = &mut self.replay else {
+            self.notice = Some("Not replaying a session log, see syskill replay".to_string());
+            return;
+        };
+        let moved = if to_action {
+            replay.step_to_action(steps > 0)
+        } else {
+            replay.step(steps)
+        };
+        if moved {
+            self.show_replayed();
+        } else {
+            self.notice = Some(
+                match (to_action, steps > 0) {
+                    (true, true) => "No actions after this snapshot",
+                    (true, false) => "No actions before this snapshot",
+                    (false, true) => "This is the last snapshot",
+                    (false, false) => "This is the first snapshot",
+                }
+                .to_string(),
+            );
+        }
+    }
+
+    /// Whether the detail view is open on its Threads tab.
+    pub(crate) fn threads_shown(&self) -> bool {
+        self.details.is_some() && self.details_tab == details::Tab::Threads
+    }
+
+    /// Whether the detail view is open on its Environment tab.
+    pub(crate) fn environment_shown(&self) -> bool {
+        self.details.is_some() && self.details_tab == details::Tab::Environment
+    }
+
+    pub(crate) fn start_env_search(&mut self) {
+        self.input = self.env_search.clone();
+        self.character_index = self.input.chars().count();
+        self.mode = AppState::EnvSearchMode;
+    }
+
+    pub(crate) fn submit_env_search(&mut self) {
+        self.env_search = std::mem::take(&mut self.input).trim().to_string();
+        self.reset_cursor();
+        self.mode = AppState::ProcessMode;
+    }
+
+    /// Samples the threads of the process in the detail view while they are shown.
+    pub(crate) fn sample_threads(&mut self) {
+        match &mut self.details {
+            Some(open) if self.details_tab == details::Tab::Threads => open.threads.sample(),
+            _ => {}
+        }
+    }
+
+    /// Samples the cores the selected process is running on.
+    pub(crate) fn sample_cores(&mut self) {
+        if let Some(data) = self.selected_process().cloned() {
+            self.core_heat.sample(data.pid);
+        }
+    }
+
+    /// Lets the selected process run on every core again.
+    pub(crate) fn spread_affinity(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        let cores = self.ctx.cpus().len();
+        let notice = match affinity::allowed_cores(data.pid) {
+            Some(allowed) if allowed.len() >= cores => {
+                format!("{} ({}) may already run on all cores", data.name, data.pid)
+            }
+            _ => match affinity::spread(data.pid, cores) {
+                Ok(()) => format!(
+                    "{} ({}) may now run on all {cores} cores",
+                    data.name, data.pid
+                ),
+                Err(e) => format!("Cannot change affinity of {}: {e}", data.pid),
+            },
+        };
+        self.notice = Some(notice);
+    }
+
+    /// Runs the `index`th runtime action available for the selected process.
+    pub(crate) fn run_tool(&mut self, index: usize) {
+        let Some(data) = self.selected_process().cloned() else {
+            return;
+        };
+        let Some(runtime) = tools::Runtime::detect(&data.name, &data.exe) else {
+            return;
+        };
+        if let Some(action) = tools::available(runtime).get(index) {
+            self.tool_rx = Some(action.run(data.pid));
+            self.tool_output = Some(tools::ToolOutput {
+                title: format!(
+                    "{} of {}: running {}...",
+                    action.label, data.pid, action.program
+                ),
+                text: String::new(),
+            });
+        }
+    }
+
+    pub(crate) fn poll_tools(&mut self) {
+        if let Some(output) = self.tool_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.tool_output = Some(output);
+            self.tool_rx = None;
+        }
+        if let Some(output) = self.command_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.command_output = Some(output);
+            self.command_rx = None;
+        }
+    }
+
+    /// Opens the command prompt with the last command, ready to be run again for another process.
+    pub(crate) fn start_command(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        if self.is_remote() {
+            self.notice = Some("Commands are not supported when attached".to_string());
+            return;
+        }
+        self.input = self.last_command.clone();
+        self.character_index = self.input.chars().count();
+        self.mode = AppState::CommandMode;
+        self.show_popup = true;
+    }
+
+    /// Runs the command typed at the prompt in the background; its output opens in a popup.
+    pub(crate) fn submit_command(&mut self) -> error::Result<()> {
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        let command = std::mem::take(&mut self.input).trim().to_string();
+        self.reset_cursor();
+        if command.is_empty() {
+            return Ok(());
+        }
+        self.last_command = command.clone();
+        let (pid, name) = if command.contains("{pid}") || command.contains("{name}") {
+            let data = self.selected_data()?;
+            (data.pid, data.name.clone())
+        } else {
+            Default::default()
+        };
+        self.command_rx = Some(tools::run_command(&command, pid, &name));
+        self.command_output = Some(tools::ToolOutput {
+            title: format!("$ {command}"),
+            text: "Running...".to_string(),
+        });
+        self.command_scroll = 0;
+        Ok(())
+    }
+
+    /// Opens the `:` command line.
+    pub(crate) fn start_command_line(&mut self) {
+        self.input.clear();
+        self.reset_cursor();
+        self.mode = AppState::CommandLineMode;
+        self.show_popup = true;
+    }
+
+    /// The state and user counts of every process, whatever the search.
+    pub(crate) fn breakdown(&self) -> breakdown::Breakdown {
+        breakdown::Breakdown::of(&self.unfiltered)
+    }
+
+    /// Shows the state and user counts and moves the keyboard to them; once there, hides them
+    /// again.
+    pub(crate) fn toggle_breakdown(&mut self) {
+        if self.breakdown_choice.is_some() {
+            self.show_breakdown = false;
+            self.breakdown_choice = None;
+        } else {
+            self.show_breakdown = true;
+            self.breakdown_choice = Some(0);
+        }
+    }
+
+    pub(crate) fn move_breakdown_choice(&mut self, step: isize) {
+        let count = self.breakdown().segments.len();
+        if let Some(choice) = &mut self.breakdown_choice {
+            *choice = choice
+                .saturating_add_signed(step)
+                .min(count.saturating_sub(1));
+        }
+    }
+
+    /// Searches for the processes of the `index`th count, or shows every process again if that
+    /// is the search already.
+    pub(crate) fn pick_breakdown(&mut self, index: usize) {
+        let Some((segment, _)) = self.breakdown().segments.into_iter().nth(index) else {
+            return;
+        };
+        let search = segment.search();
+        self.input = if self.filter == search {
+            String::new()
+        } else {
+            search
+        };
+        self.update_search();
+        if !self.filter.is_empty() {
+            self.submit_message();
+        }
+    }
+
+    /// Runs the command typed at the `:` command line. Returns the action it names, to be run as
+    /// if its key was pressed.
+    pub(crate) fn submit_command_line(&mut self) -> error::Result<Option<keymap::Action>> {
+        use cmdline::Command;
+
+        let line = std::mem::take(&mut self.input);
+        self.reset_cursor();
+        match cmdline::parse(&line).map_err(Error::Invalid)? {
+            Command::Signal { pid, signal } => {
+                if self.replay.is_some() {
+                    return Err(Error::Invalid(
+                        "Signals are not sent while replaying a session log".to_string(),
+                    ));
+                }
+                if self.is_remote() {
+                    return Err(Error::Invalid(
+                        "Signals are not supported when attached".to_string(),
+                    ));
+                }
+                let pid = match pid {
+                    Some(pid) => pid,
+                    None => self.selected_data()?.pid,
+                };
+                self.notice = Some(self.send_signal(pid, signal).map_err(Error::Invalid)?);
+            }
+            Command::Pkill { pattern, signal } => {
+                if self.replay.is_some() {
+                    return Err(Error::Invalid(
+                        "Signals are not sent while replaying a session log".to_string(),
+                    ));
+                }
+                if self.is_remote() {
+                    return Err(Error::Invalid(
+                        "Signals are not supported when attached".to_string(),
+                    ));
+                }
+                self.request_pkill(&pattern, signal)
+                    .map_err(Error::Invalid)?;
+            }
+            Command::Sort(keys) => {
+                self.sort = keys;
+                self.update_collected();
+                self.sort_items();
+            }
+            Command::SavedFilter(name) => self.apply_saved_filter(&name)?,
+            Command::Filter(search) => {
+                self.input = search;
+                self.update_search();
+                if !self.filter.is_empty() {
+                    self.submit_message();
+                }
+            }
+            Command::Export(path) => {
+                let format = export::ExportFormat::from_path(&path);
+                self.export_to(&path.display().to_string(), format);
+            }
+            Command::Theme(theme) => self.set_theme(theme).map_err(Error::Invalid)?,
+            Command::Profile(Some(name)) => self.switch_profile(&name)?,
+            Command::Profile(None) => {
+                let names: Vec<_> = self.config.profiles.keys().map(String::as_str).collect();
+                self.notice = Some(match names.is_empty() {
+                    true => "No profiles yet; save one with :save_profile NAME".to_string(),
+                    false => format!("Profiles: {}", names.join(", ")),
+                });
+            }
+            Command::SaveProfile(name) => self.save_profile(&name)?,
+            Command::Action(action) => return Ok(Some(action)),
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::{Fields, ProcessSample};
+    use crate::source::{MemorySource, ProcessSource};
+
+    /// Above the highest PID Linux hands out, so nothing here can reach a real process.
+    const FIRST_PID: u32 = 5_000_000;
+
+    /// Processes of the given names and CPU usage, with PIDs in that order.
+    fn snapshot(processes: &[(&str, f32)]) -> snapshot::Snapshot {
+        let processes = processes
+            .iter()
+            .zip(FIRST_PID..)
+            .map(|(&(name, cpu_usage), pid)| ProcessSample {
+                pid,
+                name: name.to_string(),
+                cpu_usage,
+                start_time: 1_760_000_000,
+                user: "root".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        snapshot::Snapshot {
+            timestamp: 1_760_623_402,
+            processes,
+            without_disk: true,
+        }
+    }
+
+    fn app(processes: &[(&str, f32)]) -> App {
+        let mut source = MemorySource::new([snapshot(processes)]);
+        let mut app = App::new();
+        app.show_snapshot(source.snapshot(Fields::ALL).unwrap());
+        app
+    }
+
+    fn names(app: &App) -> Vec<&str> {
+        app.items.iter().map(|data| data.name.as_str()).collect()
+    }
+
+    fn selected_name(app: &App) -> Option<&str> {
+        app.selected_process().map(|data| data.name.as_str())
+    }
+
+    fn search(app: &mut App, input: &str) {
+        app.input = input.to_string();
+        app.update_search();
+    }
+
+    #[test]
+    fn search_keeps_the_matching_processes() {
+        let mut app = app(&[("firefox", 0.0), ("bash", 0.0), ("Xorg", 0.0)]);
+        search(&mut app, "fire");
+        assert_eq!(names(&app), ["firefox"]);
+        search(&mut app, "");
+        assert_eq!(names(&app), ["firefox", "bash", "Xorg"]);
+    }
+
+    #[test]
+    fn search_by_field() {
+        let mut app = app(&[("firefox", 0.0), ("bash", 0.0), ("Xorg", 0.0)]);
+        search(&mut app, &format!("pid:{}", FIRST_PID + 1));
+        assert_eq!(names(&app), ["bash"]);
+    }
+
+    #[test]
+    fn search_keeps_the_selected_process() {
+        let mut app = app(&[("cargo", 0.0), ("bash", 0.0), ("rustc", 0.0)]);
+        app.state.select(Some(2));
+        search(&mut app, "r");
+        assert_eq!(names(&app), ["cargo", "rustc"]);
+        assert_eq!(selected_name(&app), Some("rustc"));
+    }
+
+    #[test]
+    fn invalid_search_shows_nothing() {
+        let mut app = app(&[("bash", 0.0)]);
+        search(&mut app, "cpu>");
+        assert!(app.items.is_empty());
+        assert!(app.filter_error.is_some());
+    }
+
+    /// Searches for `input` like typing it after `/` and pressing Enter.
+    fn submit_search(app: &mut App, input: &str) {
+        app.start_search();
+        search(app, input);
+        app.submit_message();
+    }
+
+    #[test]
+    fn searches_narrow_down_the_results() {
+        let mut app = app(&[
+            ("cargo", 0.0),
+            ("rustc", 0.0),
+            ("rustdoc", 0.0),
+            ("bash", 0.0),
+        ]);
+        submit_search(&mut app, "r");
+        assert_eq!(names(&app), ["cargo", "rustc", "rustdoc"]);
+        submit_search(&mut app, "rust");
+        assert_eq!(names(&app), ["rustc", "rustdoc"]);
+        submit_search(&mut app, "doc");
+        assert_eq!(names(&app), ["rustdoc"]);
+        assert_eq!(app.filter_chain().collect::<Vec<_>>(), ["r", "rust", "doc"]);
+    }
+
+    #[test]
+    fn dropping_searches_brings_the_results_back() {
+        let mut app = app(&[
+            ("cargo", 0.0),
+            ("rustc", 0.0),
+            ("rustdoc", 0.0),
+            ("bash", 0.0),
+        ]);
+        submit_search(&mut app, "r");
+        submit_search(&mut app, "rust");
+        submit_search(&mut app, "doc");
+        app.pop_filter();
+        assert_eq!(names(&app), ["rustc", "rustdoc"]);
+        app.clear_filters();
+        assert_eq!(names(&app), ["cargo", "rustc", "rustdoc", "bash"]);
+        assert_eq!(app.filter_chain().count(), 0);
+        app.pop_filter();
+        assert_eq!(names(&app), ["cargo", "rustc", "rustdoc", "bash"]);
+    }
+
+    #[test]
+    fn cancelled_search_keeps_the_earlier_ones() {
+        let mut app = app(&[("cargo", 0.0), ("rustc", 0.0), ("bash", 0.0)]);
+        submit_search(&mut app, "r");
+        app.start_search();
+        search(&mut app, "rust");
+        assert_eq!(names(&app), ["rustc"]);
+        app.cancel_search();
+        assert_eq!(names(&app), ["cargo", "rustc"]);
+        // An empty search does not stack either.
+        submit_search(&mut app, "");
+        app.pop_filter();
+        assert_eq!(names(&app), ["cargo", "rustc", "bash"]);
+    }
+
+    #[test]
+    fn earlier_search_can_be_edited() {
+        let mut app = app(&[("cargo", 0.0), ("rustc", 0.0), ("bash", 0.0)]);
+        submit_search(&mut app, "r");
+        app.start_search();
+        assert!(app.edit_previous_filter());
+        assert_eq!(app.input, "r");
+        search(&mut app, "ba");
+        app.submit_message();
+        assert_eq!(names(&app), ["bash"]);
+        assert_eq!(app.filter_chain().collect::<Vec<_>>(), ["ba"]);
+    }
+
+    #[test]
+    fn sorting_by_a_column_and_back() {
+        let mut app = app(&[("b", 10.0), ("c", 50.0), ("a", 30.0)]);
+        assert_eq!(names(&app), ["b", "c", "a"]);
+        app.sort_by(SortColumn::Cpu);
+        assert_eq!(names(&app), ["c", "a", "b"]);
+        app.sort_by(SortColumn::Cpu);
+        assert_eq!(names(&app), ["b", "a", "c"]);
+        app.sort_by(SortColumn::Name);
+        assert_eq!(names(&app), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn sorting_keeps_the_selected_process() {
+        let mut app = app(&[("b", 10.0), ("c", 50.0), ("a", 30.0)]);
+        app.state.select(Some(0));
+        app.sort_by(SortColumn::Name);
+        assert_eq!(selected_name(&app), Some("b"));
+    }
+
+    #[test]
+    fn sorting_breaks_ties_by_pid() {
+        let mut app = app(&[("a", 5.0), ("b", 5.0), ("c", 5.0)]);
+        app.sort_by(SortColumn::Cpu);
+        assert_eq!(names(&app), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut app = app(&[("a", 0.0), ("b", 0.0), ("c", 0.0)]);
+        app.previous();
+        assert_eq!(selected_name(&app), Some("c"));
+        app.next();
+        assert_eq!(selected_name(&app), Some("a"));
+        app.next();
+        assert_eq!(selected_name(&app), Some("b"));
+    }
+
+    #[test]
+    fn empty_table_selects_nothing() {
+        let mut app = app(&[("a", 0.0)]);
+        search(&mut app, "nothing like it");
+        app.next();
+        assert_eq!(app.state.selected(), None);
+        app.previous();
+        assert_eq!(app.state.selected(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tree_kills_skip_reused_pids() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let mut app = app(&[]);
+        let pid = Pid::from_u32(child.id());
+        app.ctx
+            .refresh_process_specifics(pid, ProcessRefreshKind::new());
+        let start_time = app.ctx.process(pid).unwrap().start_time();
+        // As if the descendant seen when the tree was collected had exited and its PID had gone
+        // to this one since.
+        let reused = ProcessKey {
+            pid: child.id(),
+            start_time: start_time + 1,
+        };
+        assert_eq!(app.kill_keys(&[reused]), 0);
+        assert!(child.try_wait().unwrap().is_none());
+        let key = ProcessKey {
+            start_time,
+            ..reused
+        };
+        assert_eq!(app.kill_keys(&[key]), 1);
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn signals_wait_for_the_selected_process_to_match() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let mut app = app(&[]);
+        let pid = Pid::from_u32(child.id());
+        app.ctx
+            .refresh_process_specifics(pid, ProcessRefreshKind::new());
+        let process = app.ctx.process(pid).unwrap();
+        let row = ProcessSample {
+            pid: child.id(),
+            name: process.name().to_string(),
+            start_time: process.start_time(),
+            ..Default::default()
+        };
+        // As if the row had been read before its PID went to the sleep started here.
+        let stale = ProcessSample {
+            start_time: row.start_time - 1,
+            ..row.clone()
+        };
+        for (sample, matches) in [(stale, false), (row, true)] {
+            app.show_snapshot(snapshot::Snapshot {
+                timestamp: 1_760_623_402,
+                processes: vec![sample],
+                without_disk: true,
+            });
+            app.state.select(Some(0));
+            assert_eq!(app.verify_selected().is_ok(), matches);
+        }
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn kill_targets_the_selected_process() {
+        let mut app = app(&[("b", 10.0), ("c", 50.0), ("a", 30.0)]);
+        app.sort_by(SortColumn::Cpu);
+        search(&mut app, "a");
+        app.next();
+        app.request_kill(KillAction::Kill, false);
+        let pending = app
+            .pending_kill
+            .as_ref()
+            .expect("the kill awaits confirmation");
+        assert_eq!(pending.name, "a");
+        assert_eq!(pending.pid, (FIRST_PID + 2).to_string());
+        assert_eq!(
+            Some(pending.key),
+            app.selected_process().map(ProcessInfo::key)
+        );
+    }
+
+    #[test]
+    fn kill_follows_the_process_to_its_new_row() {
+        let mut app = app(&[("b", 10.0), ("c", 50.0), ("a", 30.0)]);
+        app.sort_by(SortColumn::Cpu);
+        app.state.select(Some(0));
+        assert_eq!(selected_name(&app), Some("c"));
+        app.show_snapshot(snapshot(&[("b", 10.0), ("c", 0.0), ("a", 30.0)]));
+        assert_eq!(names(&app), ["a", "b", "c"]);
+        app.request_kill(KillAction::Kill, false);
+        assert_eq!(
+            app.pending_kill.map(|pending| pending.name),
+            Some("c".to_string())
+        );
+    }
+
+    #[test]
+    fn read_only_refuses_kills_and_calls_off_pending_ones() {
+        let mut app = app(&[("a", 0.0)]);
+        app.state.select(Some(0));
+        app.request_kill(KillAction::Kill, false);
+        assert!(app.pending_kill.is_some());
+        let denied = || DeniedKill {
+            request: KillRequest::new(FIRST_PID, Signal::Kill),
+            name: "a".to_string(),
+            owner: "root".to_string(),
+            sudo_error: None,
+        };
+        app.denied_kill = Some(denied());
+        app.toggle_read_only();
+        assert!(app.read_only);
+        assert!(app.pending_kill.is_none());
+        assert!(app.denied_kill.is_none());
+        app.request_kill(KillAction::Kill, false);
+        assert!(app.pending_kill.is_none());
+        assert_eq!(
+            app.send_signal(FIRST_PID, Signal::Kill),
+            Err(i18n::text("status.read_only").to_string())
+        );
+        app.denied_kill = Some(denied());
+        app.send_with_sudo();
+        assert!(app.denied_kill.is_none());
+        assert_eq!(app.notice.as_deref(), Some(i18n::text("status.read_only")));
+        app.toggle_read_only();
+        assert!(!app.read_only);
+
+        // Unless started with `--read-only`.
+        app.read_only = true;
+        app.read_only_locked = true;
+        app.toggle_read_only();
+        assert!(app.read_only);
+    }
+
+    #[test]
+    fn kill_without_a_selection_fails() {
+        let mut app = app(&[("a", 0.0)]);
+        search(&mut app, "nothing like it");
+        app.request_kill(KillAction::Kill, false);
+        assert!(app.pending_kill.is_none());
+        assert!(matches!(app.error, Some(Error::NoSelection)));
+    }
+
+    #[test]
+    fn parent_and_children_are_found_by_ppid() {
+        let mut family = snapshot(&[("init", 0.0), ("bash", 0.0), ("vim", 0.0), ("make", 0.0)]);
+        let parents = [
+            None,
+            Some(FIRST_PID),
+            Some(FIRST_PID + 1),
+            Some(FIRST_PID + 1),
+        ];
+        for (sample, parent) in family.processes.iter_mut().zip(parents) {
+            sample.parent = parent;
+        }
+        let mut app = App::new();
+        app.show_snapshot(family);
+        app.select_pid(FIRST_PID + 1).unwrap();
+        app.select_child().unwrap();
+        assert!(matches!(selected_name(&app), Some("vim" | "make")));
+        assert_eq!(
+            app.notice.as_deref(),
+            Some(format!("First of 2 children of bash ({})", FIRST_PID + 1).as_str())
+        );
+        assert!(app.select_child().is_err());
+        app.select_parent().unwrap();
+        assert_eq!(selected_name(&app), Some("bash"));
+        app.select_parent().unwrap();
+        assert_eq!(selected_name(&app), Some("init"));
+        assert!(app.select_parent().is_err());
+    }
+
+    #[test]
+    fn pkill_lists_every_match_whatever_the_table_shows() {
+        let mut app = app(&[("rustc", 0.0), ("cargo", 0.0), ("rustdoc", 0.0)]);
+        submit_search(&mut app, "cargo");
+        app.request_pkill("rust", Signal::Hangup).unwrap();
+        let pending = app.pending_kill.as_ref().unwrap();
+        assert_eq!(
+            pending.affected,
+            [
+                (FIRST_PID, "rustc".to_string()),
+                (FIRST_PID + 2, "rustdoc".to_string())
+            ]
+        );
+        assert_eq!(pending.pid, "2 processes");
+        assert!(matches!(
+            &pending.action,
+            KillAction::Matching { signal: Signal::Hangup, targets, .. } if targets.len() == 2
+        ));
+        // The search in place is left alone.
+        assert_eq!(names(&app), ["cargo"]);
+    }
+
+    #[test]
+    fn pkill_without_a_match_fails() {
+        let mut app = app(&[("cargo", 0.0)]);
+        assert!(app.request_pkill("rust", Signal::Term).is_err());
+        assert!(app.request_pkill("cpu >", Signal::Term).is_err());
+        assert!(app.pending_kill.is_none());
+    }
+
+    #[test]
+    fn profiles_replace_the_searches_and_keep_what_they_leave_out() {
+        let mut app = app(&[("rustc", 9.0), ("cargo", 1.0), ("rustdoc", 5.0)]);
+        submit_search(&mut app, "cargo");
+        app.config.profiles.insert(
+            "rust".to_string(),
+            config::Profile {
+                filter: vec!["rust".to_string(), "cpu > 6".to_string()],
+                tree: true,
+                ..Default::default()
+            },
+        );
+        app.switch_profile("rust").unwrap();
+        assert_eq!(app.filter_chain().collect::<Vec<_>>(), ["rust", "cpu > 6"]);
+        assert_eq!(names(&app), ["rustc"]);
+        assert!(app.tree.is_some());
+        app.config.profiles.insert(
+            "all".to_string(),
+            config::Profile {
+                sort: vec!["cpu".parse().unwrap()],
+                ..Default::default()
+            },
+        );
+        app.switch_profile("all").unwrap();
+        assert!(app.tree.is_none());
+        assert_eq!(names(&app), ["rustc", "rustdoc", "cargo"]);
+        assert!(app.switch_profile("missing").is_err());
+    }
+}
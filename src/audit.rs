@@ -0,0 +1,211 @@
+//! The audit log of the signals sent from syskill: when, to which process, by whom and whether
+//! they arrived, to find out on a shared server who killed what.
+//!
+//! Every signal is appended as a line of JSON to `kills.jsonl` in the state directory (see
+//! [`crate::logging::directory`]), where it stays across sessions. `syskill history` prints the
+//! log, and the table lists it too.
+
+use crate::details;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The outcome of a signal that was delivered.
+pub const SENT: &str = "sent";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub pid: u32,
+    pub name: String,
+    /// E.g. `SIGTERM` or `signal 34`.
+    pub signal: String,
+    /// `sent`, or why the signal was not delivered.
+    pub outcome: String,
+    /// Who sent it.
+    pub user: String,
+    /// Who ran syskill as `user` through sudo, if anyone did.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sudo_user: String,
+    /// Who the process belonged to; empty if unknown.
+    #[serde(default)]
+    pub owner: String,
+}
+
+impl Entry {
+    pub fn sent(&self) -> bool {
+        self.outcome == SENT
+    }
+
+    /// The user who sent it, with the one behind sudo.
+    pub fn sender(&self) -> String {
+        match self.sudo_user.as_str() {
+            "" => self.user.clone(),
+            sudo_user => format!("{} (sudo by {sudo_user})", self.user),
+        }
+    }
+}
+
+/// Which entries `syskill history` prints.
+#[derive(Default)]
+pub struct Filter {
+    /// Seconds since the Unix epoch from which on.
+    pub since: Option<u64>,
+    pub pid: Option<u32>,
+    /// Part of the name of the process.
+    pub name: Option<String>,
+    /// The user the process belonged to.
+    pub owner: Option<String>,
+    /// Only the signals that were not delivered.
+    pub failed: bool,
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.pid.is_none_or(|pid| entry.pid == pid)
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|name| entry.name.contains(name.as_str()))
+            && self
+                .owner
+                .as_ref()
+                .is_none_or(|owner| entry.owner == *owner)
+            && !(self.failed && entry.sent())
+    }
+}
+
+/// Who signals are sent by: the owner of this process, and the user who ran it with sudo.
+pub fn sender() -> (String, String) {
+    let user = sysinfo::get_current_pid()
+        .ok()
+        .and_then(crate::users::owner)
+        .unwrap_or_default();
+    let sudo_user = std::env::var("SUDO_USER")
+        .unwrap_or_default()
+        .replace(|c: char| c.is_control(), "");
+    (user, sudo_user)
+}
+
+/// Adds a signal sent to `pid` by this process, or not sent for the reason of `outcome`, to the
+/// log; for the signals sent without the table, which keeps its own [`sender`].
+pub fn record(pid: u32, name: &str, owner: &str, signal: sysinfo::Signal, outcome: &str) {
+    let Some(path) = path() else {
+        return;
+    };
+    let (user, sudo_user) = sender();
+    let entry = Entry {
+        timestamp: crate::snapshot::unix_now(),
+        pid,
+        name: name.to_string(),
+        signal: format!("SIG{}", crate::signal::name(signal)),
+        outcome: outcome.to_string(),
+        user,
+        sudo_user,
+        owner: owner.to_string(),
+    };
+    if let Err(e) = append(&path, &entry) {
+        tracing::warn!("cannot write to the audit log {}: {e}", path.display());
+    }
+}
+
+/// Where the audit log is kept; `None` without a home directory.
+pub fn path() -> Option<PathBuf> {
+    Some(crate::logging::directory()?.join("kills.jsonl"))
+}
+
+/// Adds `entry` to the end of the log at `path`.
+pub fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    // In one write, so the entries of sessions running at the same time do not interleave.
+    File::options()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)
+}
+
+/// The entries of the log at `path`, the oldest first; none if there is no such file. A line cut
+/// short by a crash is left out rather than keeping the others from being read.
+pub fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// `entry` on one line, as the table and `syskill history` list it.
+pub fn describe(entry: &Entry) -> String {
+    let owner = match entry.owner.as_str() {
+        "" => String::new(),
+        owner => format!(", owned by {owner}"),
+    };
+    let outcome = match entry.sent() {
+        true => String::new(),
+        false => format!(", not sent: {}", entry.outcome),
+    };
+    format!(
+        "{}  {} to {} ({}{owner}) by {}{outcome}",
+        details::format_timestamp(entry.timestamp),
+        entry.signal,
+        entry.name,
+        entry.pid,
+        entry.sender()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_appended_and_read_back() {
+        let path = std::env::temp_dir().join(format!("syskill-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(read(&path).unwrap().is_empty());
+        let sent = Entry {
+            timestamp: 1_760_623_402,
+            pid: 5_000_000,
+            name: "worker".to_string(),
+            signal: "SIGTERM".to_string(),
+            outcome: SENT.to_string(),
+            user: "root".to_string(),
+            sudo_user: "alice".to_string(),
+            owner: "www".to_string(),
+        };
+        let refused = Entry {
+            pid: 5_000_001,
+            outcome: "Operation not permitted".to_string(),
+            sudo_user: String::new(),
+            ..sent.clone()
+        };
+        append(&path, &sent).unwrap();
+        append(&path, &refused).unwrap();
+        // A line cut short, as by a crash while writing it.
+        File::options()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"timestamp\":")
+            .unwrap();
+        assert_eq!(read(&path).unwrap(), [sent.clone(), refused.clone()]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(describe(&sent)
+            .ends_with("SIGTERM to worker (5000000, owned by www) by root (sudo by alice)"));
+        assert!(describe(&refused).ends_with("by root, not sent: Operation not permitted"));
+    }
+}
@@ -0,0 +1,79 @@
+//! How many processes there are in each state and of each user, shown above the table as e.g.
+//! `312 total: 287 Sleeping, 14 Running, 3 Zombie · root 120, alice 96, …`. Each count is also a
+//! search, picked by clicking it or with `V`.
+
+use crate::app::ProcessInfo;
+use std::collections::HashMap;
+
+/// How many users are listed; the others are summed up as `…`.
+const LISTED_USERS: usize = 5;
+
+#[derive(Clone, PartialEq)]
+pub enum Segment {
+    State(String),
+    User(String),
+}
+
+impl Segment {
+    /// The search for the processes counted in this segment.
+    pub fn search(&self) -> String {
+        match self {
+            Self::State(state) => format!("state == \"{state}\""),
+            Self::User(user) => format!("user == \"{user}\""),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            Self::State(label) | Self::User(label) => label,
+        }
+    }
+}
+
+pub struct Breakdown {
+    pub total: usize,
+    /// States, most common first, then users.
+    pub segments: Vec<(Segment, usize)>,
+    /// Processes of users left out of `segments`.
+    pub other_users: usize,
+}
+
+impl Breakdown {
+    /// Counts the processes still running in `processes`.
+    pub fn of(processes: &[ProcessInfo]) -> Self {
+        let running: Vec<_> = processes
+            .iter()
+            .filter(|data| data.exited.is_none())
+            .collect();
+        let mut segments: Vec<_> = ranked(running.iter().map(|data| &data.state))
+            .into_iter()
+            .map(|(state, count)| (Segment::State(state), count))
+            .collect();
+        let mut users = ranked(running.iter().map(|data| &data.user)).into_iter();
+        segments.extend(
+            users
+                .by_ref()
+                .take(LISTED_USERS)
+                .map(|(user, count)| (Segment::User(user), count)),
+        );
+        Self {
+            total: running.len(),
+            segments,
+            other_users: users.map(|(_, count)| count).sum(),
+        }
+    }
+}
+
+/// How often each value occurs, most often first and then by name.
+fn ranked<'a>(values: impl Iterator<Item = &'a String>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for value in values.filter(|value| !value.is_empty()) {
+        *counts.entry(value).or_default() += 1;
+    }
+    let mut ranked: Vec<_> = counts
+        .into_iter()
+        .map(|(value, count)| (value.to_string(), count))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
@@ -0,0 +1,241 @@
+//! What changed between two snapshots of the table: the processes started and exited in between
+//! and those whose CPU or memory usage moved a lot, for catching fork bombs, crash loops and
+//! runaway processes as they happen.
+//!
+//! Each refresh is compared with the one before, or with a baseline: a refresh pinned in the
+//! panel or the last snapshot of a recording given with `--baseline`.
+
+use crate::app::ProcessInfo;
+use crate::proc::ProcessKey;
+use crate::snapshot::{self, Snapshot};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A change in CPU usage of at least this many percentage points is listed, as by `syskill diff`.
+const MIN_CPU_DELTA: f32 = 5.0;
+/// A change in memory is listed from this many percent of the larger of the two values, and from
+/// at least [`MIN_MEMORY_DELTA`] bytes.
+const MIN_MEMORY_DELTA_PCT: f64 = 20.0;
+const MIN_MEMORY_DELTA: u64 = 1024 * 1024;
+
+struct Seen {
+    name: String,
+    user: String,
+    cpu: f32,
+    memory: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Started,
+    Exited,
+    Changed,
+}
+
+impl Kind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Exited => "exited",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+pub struct Change {
+    pub key: ProcessKey,
+    pub name: String,
+    pub user: String,
+    pub kind: Kind,
+    /// In percent of one core, before and after; 0 where the process was not running.
+    pub cpu: (f32, f32),
+    /// In bytes, before and after.
+    pub memory: (u64, u64),
+}
+
+/// What the refreshes are compared with.
+#[derive(Clone, Default, PartialEq)]
+pub enum Baseline {
+    #[default]
+    Previous,
+    /// A refresh pinned in the panel, taken at this many seconds since the Unix epoch.
+    Pinned(u64),
+    /// The last snapshot of a recording, taken at this many seconds since the Unix epoch.
+    File(PathBuf, u64),
+}
+
+#[derive(Default)]
+pub struct Changes {
+    pub baseline: Baseline,
+    /// The processes of the baseline.
+    base: HashMap<ProcessKey, Seen>,
+    /// Started first, then exited, then changed by how much their CPU usage moved.
+    pub list: Vec<Change>,
+    /// Index into `list` of the chosen change.
+    pub choice: usize,
+}
+
+impl Changes {
+    /// Compares with the last snapshot of the recording at `path`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let Some(last) = snapshot::read_session(path)?.pop() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no snapshot", path.display()),
+            ));
+        };
+        Ok(Self {
+            baseline: Baseline::File(path.to_path_buf(), last.timestamp),
+            base: of_snapshot(&last),
+            ..Self::default()
+        })
+    }
+
+    /// Lists what changed from the baseline to `processes`, which become the baseline of the next
+    /// refresh unless one is pinned or loaded.
+    pub fn observe(&mut self, processes: &[ProcessInfo]) {
+        let current = of_table(processes);
+        if self.baseline == Baseline::Previous && self.base.is_empty() {
+            // Nothing to compare the first refresh with.
+            self.base = current;
+            return;
+        }
+        self.list = compare(&self.base, &current);
+        if self.baseline == Baseline::Previous {
+            self.base = current;
+        }
+        self.choice = self.choice.min(self.list.len().saturating_sub(1));
+    }
+
+    /// Compares the next refreshes with `processes` instead of with the one before them.
+    pub fn pin(&mut self, processes: &[ProcessInfo], now: u64) {
+        self.base = of_table(processes);
+        self.baseline = Baseline::Pinned(now);
+        self.list.clear();
+        self.choice = 0;
+    }
+
+    /// Goes back to comparing each refresh with the one before.
+    pub fn unpin(&mut self, processes: &[ProcessInfo]) {
+        self.base = of_table(processes);
+        self.baseline = Baseline::Previous;
+        self.list.clear();
+        self.choice = 0;
+    }
+
+    pub fn count(&self, kind: Kind) -> usize {
+        self.list
+            .iter()
+            .filter(|change| change.kind == kind)
+            .count()
+    }
+
+    pub fn chosen(&self) -> Option<&Change> {
+        self.list.get(self.choice)
+    }
+
+    pub fn move_choice(&mut self, step: isize) {
+        self.choice = self
+            .choice
+            .saturating_add_signed(step)
+            .min(self.list.len().saturating_sub(1));
+    }
+}
+
+/// The running processes of the table, without summary rows and those that already exited.
+fn of_table(processes: &[ProcessInfo]) -> HashMap<ProcessKey, Seen> {
+    processes
+        .iter()
+        .filter(|data| data.exited.is_none() && data.members == 0)
+        .map(|data| {
+            let seen = Seen {
+                name: data.name.clone(),
+                user: data.user.clone(),
+                cpu: data.cpu_usage,
+                memory: data.memory,
+            };
+            (data.key(), seen)
+        })
+        .collect()
+}
+
+fn of_snapshot(snapshot: &Snapshot) -> HashMap<ProcessKey, Seen> {
+    snapshot
+        .processes
+        .iter()
+        .map(|sample| {
+            let seen = Seen {
+                name: sample.name.clone(),
+                user: sample.user.clone(),
+                cpu: sample.cpu_usage,
+                memory: sample.memory,
+            };
+            (sample.key(), seen)
+        })
+        .collect()
+}
+
+fn compare(before: &HashMap<ProcessKey, Seen>, after: &HashMap<ProcessKey, Seen>) -> Vec<Change> {
+    let change = |key: ProcessKey, seen: &Seen, kind, cpu, memory| Change {
+        key,
+        name: seen.name.clone(),
+        user: seen.user.clone(),
+        kind,
+        cpu,
+        memory,
+    };
+    let mut list = Vec::new();
+    for (&key, now) in after {
+        match before.get(&key) {
+            None => list.push(change(
+                key,
+                now,
+                Kind::Started,
+                (0.0, now.cpu),
+                (0, now.memory),
+            )),
+            Some(then) if moved(then, now) => list.push(change(
+                key,
+                now,
+                Kind::Changed,
+                (then.cpu, now.cpu),
+                (then.memory, now.memory),
+            )),
+            Some(_) => {}
+        }
+    }
+    for (&key, then) in before {
+        if !after.contains_key(&key) {
+            list.push(change(
+                key,
+                then,
+                Kind::Exited,
+                (then.cpu, 0.0),
+                (then.memory, 0),
+            ));
+        }
+    }
+    list.sort_by(|a, b| {
+        a.kind
+            .cmp(&b.kind)
+            .then_with(|| {
+                let moved = |change: &Change| (change.cpu.1 - change.cpu.0).abs();
+                match a.kind {
+                    Kind::Changed => moved(b).total_cmp(&moved(a)),
+                    _ => a.name.cmp(&b.name),
+                }
+            })
+            .then(a.key.cmp(&b.key))
+    });
+    list
+}
+
+/// Whether the CPU or memory usage of a process moved enough to be listed.
+fn moved(then: &Seen, now: &Seen) -> bool {
+    let memory_delta = now.memory.abs_diff(then.memory);
+    let memory_base = then.memory.max(now.memory) as f64;
+    (now.cpu - then.cpu).abs() >= MIN_CPU_DELTA
+        || (memory_delta >= MIN_MEMORY_DELTA
+            && memory_delta as f64 * 100.0 / memory_base >= MIN_MEMORY_DELTA_PCT)
+}
@@ -0,0 +1,406 @@
+use crate::app::SortKey;
+use crate::config::Config;
+use crate::export::ExportFormat;
+use crate::graphics::GraphicsMode;
+use crate::hyperlink::HyperlinkMode;
+use crate::i18n::Numbers;
+use crate::metrics::MetricsAddress;
+use crate::ports::PortQuery;
+use crate::report::ReportFormat;
+use crate::ui::Theme;
+use crate::units::{MemoryUnit, Units};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+use sysinfo::Signal;
+
+/// Find and terminate processes. Runs the interactive process table when no subcommand is given.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Check GitHub for a newer release. Nothing is ever downloaded.
+    #[arg(long, global = true)]
+    pub check_updates: bool,
+
+    /// Include process names in crash reports. They are redacted by default.
+    #[arg(long, global = true)]
+    pub crash_include_names: bool,
+
+    /// How much goes into the log file: off, error, warn, info, debug or trace. Defaults to the
+    /// config file's `log_level`, or info.
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = crate::logging::parse_level)]
+    pub log_level: Option<tracing::level_filters::LevelFilter>,
+
+    /// Settings file; defaults to `$XDG_CONFIG_HOME/syskill/config.toml` if it exists.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Only show and record processes owned by this user.
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "mine")]
+    pub user: Option<String>,
+
+    /// Only show and record your own processes.
+    #[arg(long, global = true)]
+    pub mine: bool,
+
+    /// Color palette. `colorblind` is safe for deuteranopia and protanopia, `high-contrast` also
+    /// marks rising (▲), falling (▼) and critical (!) CPU usage with symbols. Defaults to the
+    /// config file's `palette`, or purple (`monochrome` with `NO_COLOR` set). `monochrome` uses
+    /// no colors at all; custom themes of the config file's `[themes]` table work too.
+    #[arg(long, alias = "theme", value_name = "NAME")]
+    pub palette: Option<Theme>,
+
+    /// Unit of memory sizes in the table and in reports. Defaults to the config file's
+    /// `memory_unit`, or the largest unit that fits.
+    #[arg(long, value_enum, global = true)]
+    pub memory_unit: Option<MemoryUnit>,
+
+    /// Show CPU usage as a share of all cores, so a process using every core shows 100%.
+    #[arg(long, global = true)]
+    pub per_core: bool,
+
+    /// Only browse, search and export: refuse to signal, renice or otherwise change processes,
+    /// whether from the table, the control socket, JSON-RPC or the clients of `serve`. The
+    /// watchdog only logs what it would do. `ctrl-w` turns this on while the table runs.
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Render executable paths as clickable OSC 8 hyperlinks.
+    #[arg(long, value_enum, default_value_t = HyperlinkMode::Auto)]
+    pub hyperlinks: HyperlinkMode,
+
+    /// Link target for executables; `{path}` and `{host}` are substituted.
+    #[arg(long, value_name = "TEMPLATE", default_value = "file://{host}{path}")]
+    pub exe_url: String,
+
+    /// Sort order of the table as comma-separated `column[:asc|:desc]` keys, where later keys break
+    /// ties, e.g. `cpu:desc,memory:desc,name`. Columns are name, pid, cpu, memory, user, elapsed,
+    /// cpu_time and the custom ones such as oom_score.
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    pub sort: Vec<SortKey>,
+
+    /// Seconds between live updates of the table, or a duration such as `500ms` or `1m`; 0 to
+    /// only update when pressing `r`. Defaults to the config file's `refresh_interval`, or 2.
+    #[arg(long, alias = "refresh", value_name = "SECONDS", value_parser = parse_seconds)]
+    pub refresh_interval: Option<f64>,
+
+    /// Minutes without a key pressed, while the terminal is not focused, after which live updates
+    /// slow down to the config file's `idle_refresh` until the table is used again; 0 never.
+    /// Defaults to the config file's `idle_timeout`, or 0.
+    #[arg(long, value_name = "MINUTES")]
+    pub idle_timeout: Option<f64>,
+
+    /// Append process events (started, exited, threshold crossed, killed) as JSON Lines to this
+    /// file. `-` streams them to stdout instead of showing the table.
+    #[arg(long, value_name = "PATH")]
+    pub events: Option<PathBuf>,
+
+    /// Append every snapshot of the table and every signal sent and search entered to this
+    /// session log, to step through later with `syskill replay`.
+    #[arg(long, value_name = "PATH")]
+    pub session_log: Option<PathBuf>,
+
+    /// Serve the metrics of the processes and the system in Prometheus format at /metrics on
+    /// this port of 127.0.0.1, or on ADDRESS:PORT, while the table runs.
+    #[arg(long, value_name = "PORT")]
+    pub metrics_port: Option<MetricsAddress>,
+
+    /// Draw history charts as kitty/iTerm2 images instead of braille characters.
+    #[arg(long, value_enum, default_value_t = GraphicsMode::Auto)]
+    pub graphics: GraphicsMode,
+
+    /// Listen for text or JSON commands (`list`, `filter`, `kill`, `snapshot`, `refresh`) on this
+    /// Unix socket.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    pub control: Option<PathBuf>,
+
+    /// Show and kill the processes of another machine, e.g. `admin@db1`, by running
+    /// `syskill serve --stdio` there over SSH.
+    #[cfg(unix)]
+    #[arg(long, value_name = "USER@SERVER")]
+    pub host: Option<String>,
+
+    /// Serve JSON-RPC on stdin/stdout for editor integrations instead of running the table.
+    #[arg(long)]
+    pub rpc: bool,
+
+    /// Kill without asking for confirmation first.
+    #[arg(long)]
+    pub no_confirm: bool,
+
+    /// Only log what the watchdog rules would do, without sending anything.
+    #[arg(long)]
+    pub watchdog_dry_run: bool,
+
+    /// Signal every process whose name matches PATTERN (like the search of the table) and exit,
+    /// without showing the table. Asks first unless `--yes` is given.
+    #[arg(long, value_name = "PATTERN")]
+    pub kill: Option<String>,
+
+    /// Signal sent by `--kill`, e.g. `TERM`, `HUP` or `KILL`.
+    #[arg(long, value_name = "NAME", default_value = "TERM", value_parser = parse_signal, requires = "kill")]
+    pub signal: Signal,
+
+    /// List the processes `--kill` would signal without sending anything.
+    #[arg(long, requires = "kill")]
+    pub dry_run: bool,
+
+    /// Do not ask before `--kill` sends the signal; required when stdin is not a terminal.
+    #[arg(long, requires = "kill")]
+    pub yes: bool,
+
+    /// Write the process table to PATH (`-` for stdout) and exit, without showing the table.
+    #[arg(long, value_name = "PATH")]
+    pub export: Option<PathBuf>,
+
+    /// Format of `--export`. Defaults to CSV for paths ending in `.csv` and JSON otherwise.
+    #[arg(long, value_enum, value_name = "FORMAT", requires = "export")]
+    pub export_format: Option<ExportFormat>,
+
+    /// Start the table with this search, e.g. `firefox` or `cpu > 5 && user == www`, instead of
+    /// the profile's. With `--export`, only export the processes matching it.
+    #[arg(long, value_name = "SEARCH")]
+    pub filter: Option<String>,
+
+    /// Start the table in the tree view.
+    #[arg(long)]
+    pub tree: bool,
+
+    /// Start with the processes listening on PORT (`8080`, or `53/udp` for one protocol), ready
+    /// to be reloaded, stopped or killed.
+    #[arg(long, value_name = "PORT")]
+    pub port: Option<PortQuery>,
+
+    /// Draw the table on the whole terminal (the alternate screen) instead of inline below the
+    /// prompt. `z` switches between the two.
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Leave the mouse to the terminal, e.g. for selecting text, instead of clicking and
+    /// scrolling through the table with it.
+    #[arg(long)]
+    pub no_mouse: bool,
+
+    /// Start in screen-reader friendly linear mode (no box drawing, plain-text selection line).
+    #[arg(long)]
+    pub linear: bool,
+
+    /// Compare the table with the last snapshot of this recording or JSON export, listing the
+    /// processes started, exited or changed since, instead of those since the last refresh.
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+
+    /// Shell command that receives an announcement of the selected row on stdin, e.g. `espeak`.
+    #[arg(long, value_name = "COMMAND")]
+    pub speak_command: Option<String>,
+
+    /// Start the table with the searches, sort order, view, columns, palette, refresh interval and
+    /// recording of this profile from the config file.
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print version and platform information.
+    Version,
+    /// Record snapshots of the process table to a JSON Lines file.
+    Record {
+        /// File to append snapshots to.
+        output: PathBuf,
+        /// Seconds between snapshots, or a duration such as `500ms` or `1m`.
+        #[arg(long, value_name = "SECONDS", default_value = "1", value_parser = parse_interval)]
+        interval: Duration,
+        /// Stop after this many snapshots instead of running until interrupted.
+        #[arg(long)]
+        count: Option<usize>,
+    },
+    /// Open the process table on a session log or recording, stepping through its snapshots
+    /// with `[` and `]` and to the signals sent and searches entered with `{` and `}`.
+    Replay {
+        /// Session log written with `--session-log`, or any other recording.
+        log: PathBuf,
+    },
+    /// Compare the average CPU and memory usage of processes between two recordings.
+    Diff {
+        /// Baseline recording.
+        before: PathBuf,
+        /// Recording to compare against the baseline.
+        after: PathBuf,
+        /// Print the report as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+        /// Minimum change in average CPU usage (percentage points) to report.
+        #[arg(long, default_value_t = 5.0)]
+        min_cpu_delta: f32,
+        /// Minimum relative change in average memory (percent) to report.
+        #[arg(long, default_value_t = 20.0)]
+        min_memory_delta: f64,
+    },
+    /// Write an incident report (system summary and top consumers) for the current snapshot.
+    Report {
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+        format: ReportFormat,
+        /// File to write the report to instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Number of processes to list per consumer table.
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Render with this template from the config file instead of `--format`.
+        #[arg(long)]
+        template: Option<String>,
+    },
+    /// Print the audit log of the signals sent from syskill, the oldest first. `--user` and
+    /// `--mine` only list those sent to the processes of that user.
+    History {
+        /// Only those sent within this long, e.g. `30m`, `12h` or `7d`.
+        #[arg(long, value_name = "AGE", value_parser = parse_age)]
+        since: Option<u64>,
+        /// Only those sent to this PID.
+        #[arg(long)]
+        pid: Option<u32>,
+        /// Only those sent to processes whose name contains this.
+        #[arg(long, value_name = "TEXT")]
+        name: Option<String>,
+        /// Only the signals that were not delivered.
+        #[arg(long)]
+        failed: bool,
+        /// Print the entries as JSON Lines, as they are logged.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Evaluate health rules once, print the violations and exit non-zero if there are any.
+    Check {
+        /// TOML file with the rules.
+        #[arg(long)]
+        rules: PathBuf,
+    },
+    /// Run a headless collector that attached TUIs share over a Unix socket.
+    #[cfg(unix)]
+    Serve {
+        /// Socket to listen on. Defaults to `$XDG_RUNTIME_DIR/syskill.sock`.
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// Seconds between snapshots, or a duration such as `500ms` or `1m`.
+        #[arg(long, value_name = "SECONDS", default_value = "1", value_parser = parse_interval)]
+        interval: Duration,
+        /// Also append every snapshot to this recording.
+        #[arg(long)]
+        record: Option<PathBuf>,
+        /// Serve the one client on stdin and stdout instead of a socket, as `--host` runs it.
+        #[arg(long, conflicts_with = "socket")]
+        stdio: bool,
+    },
+    /// Open the process table on the snapshots of a running `syskill serve`.
+    #[cfg(unix)]
+    Attach {
+        /// Socket of the server. Defaults to `$XDG_RUNTIME_DIR/syskill.sock`.
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    /// Number formatting from the flags, falling back to the config file. `cores` is left for
+    /// the caller to fill in from the system.
+    pub fn units(&self, config: &Config) -> Units {
+        Units {
+            memory: self.memory_unit.unwrap_or(config.memory_unit),
+            per_core: self.per_core || config.cpu_per_core,
+            numbers: Numbers::of_locale(config.locale.as_deref()),
+            ..Units::default()
+        }
+    }
+}
+
+fn parse_signal(name: &str) -> Result<Signal, String> {
+    crate::signal::parse(name).ok_or_else(|| format!("unknown signal {name}"))
+}
+
+/// Parses an age in whole seconds, either bare (`90`) or with a unit of `s`, `m`, `h` or `d`.
+fn parse_age(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (number, unit) = match input.char_indices().last() {
+        Some((i, unit @ ('s' | 'm' | 'h' | 'd'))) => (&input[..i], unit),
+        _ => (input, 's'),
+    };
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => 1,
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(seconds))
+        .ok_or_else(|| format!("expected an age such as 90, 30m, 12h or 7d, not {input:?}"))
+}
+
+/// Parses seconds, either bare (`2`, `0.5`) or with a unit of `ms`, `s` or `m`.
+fn parse_seconds(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    let (number, scale) = if let Some(number) = input.strip_suffix("ms") {
+        (number, 0.001)
+    } else if let Some(number) = input.strip_suffix('s') {
+        (number, 1.0)
+    } else if let Some(number) = input.strip_suffix('m') {
+        (number, 60.0)
+    } else {
+        (input, 1.0)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok(value * scale),
+        _ => Err(format!(
+            "expected seconds such as 2, 500ms or 1m, not {input:?}"
+        )),
+    }
+}
+
+/// Parses the time between two snapshots as [`parse_seconds`] does, which has to be more than 0.
+fn parse_interval(input: &str) -> Result<Duration, String> {
+    parse_seconds(input)
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| format!("expected seconds above 0 such as 1, 500ms or 1m, not {input:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_take_an_optional_unit() {
+        assert_eq!(parse_seconds("2"), Ok(2.0));
+        assert_eq!(parse_seconds("2s"), Ok(2.0));
+        assert_eq!(parse_seconds("250ms"), Ok(0.25));
+        assert_eq!(parse_seconds("1.5m"), Ok(90.0));
+        assert!(parse_seconds("-1").is_err());
+        assert!(parse_seconds("2h").is_err());
+    }
+
+    #[test]
+    fn snapshot_intervals_are_above_0() {
+        assert_eq!(parse_interval("500ms"), Ok(Duration::from_millis(500)));
+        for input in ["-1", "0", "nan", "inf", "1e300", ""] {
+            assert!(parse_interval(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn ages_take_an_optional_unit() {
+        assert_eq!(parse_age("90"), Ok(90));
+        assert_eq!(parse_age("30m"), Ok(1800));
+        assert_eq!(parse_age("7d"), Ok(604_800));
+        assert!(parse_age("1.5h").is_err());
+        assert!(parse_age("soon").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! The `:` command line, for reaching everything the table does by name instead of by key:
+//!
+//! ```text
+//! :kill 1234 [SIGNAL]     send SIGNAL (default TERM) to PID 1234; without a PID, ask to kill the
+//!                         selected process as `d` does
+//! :signal TERM [PID]      send a signal to PID, or to the selected process
+//! :pkill [-HUP] nginx     list every process a search matches, hidden or not, and send them all
+//!                         the signal (default TERM) once confirmed
+//! :sort cpu:desc,name     sort as for `--sort`
+//! :filter user == rob     search as after `/`; without a search, show everything again
+//! :filter @java           search with the filter saved as `java` in the `[filters]` config table
+//! :export top.csv         write the table as CSV, or JSON for other file names
+//! :theme emerald          switch the palette, as for `--palette`; without one, to the next
+//! :profile db-server      switch to the search, sort order, view, columns, palette and refresh
+//!                         interval of a profile of the `[profiles]` config table; without a name,
+//!                         list them
+//! :save_profile db-server save those of the table as that profile
+//! :tree                   any action of the `[keys]` config table, as if its key was pressed
+//! ```
+
+use crate::app::SortKey;
+use crate::keymap::Action;
+use crate::ui::Theme;
+use std::path::PathBuf;
+use sysinfo::Signal;
+
+pub enum Command {
+    /// A signal to the process `pid`, or to the selected one.
+    Signal {
+        pid: Option<u32>,
+        signal: Signal,
+    },
+    /// A signal to every process the search `pattern` matches.
+    Pkill {
+        pattern: String,
+        signal: Signal,
+    },
+    Sort(Vec<SortKey>),
+    Filter(String),
+    SavedFilter(String),
+    Export(PathBuf),
+    Theme(Theme),
+    /// The profile to switch to; `None` to list them.
+    Profile(Option<String>),
+    SaveProfile(String),
+    Action(Action),
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    match verb {
+        "kill" if !rest.is_empty() => {
+            let mut args = rest.split_whitespace();
+            let pid = args
+                .next()
+                .and_then(|pid| pid.parse().ok())
+                .ok_or("usage: kill [PID [SIGNAL]]")?;
+            let signal = match args.next() {
+                Some(name) => parse_signal(name)?,
+                None => Signal::Term,
+            };
+            Ok(Command::Signal {
+                pid: Some(pid),
+                signal,
+            })
+        }
+        "signal" if !rest.is_empty() => {
+            let mut args = rest.split_whitespace();
+            let signal = parse_signal(args.next().unwrap_or_default())?;
+            let pid = match args.next() {
+                Some(pid) => Some(pid.parse().map_err(|_| "usage: signal [SIGNAL [PID]]")?),
+                None => None,
+            };
+            Ok(Command::Signal { pid, signal })
+        }
+        "pkill" if !rest.is_empty() => {
+            let (signal, pattern) = match rest.strip_prefix('-') {
+                Some(flagged) => {
+                    let (name, pattern) = flagged.split_once(' ').unwrap_or((flagged, ""));
+                    (parse_signal(name)?, pattern.trim())
+                }
+                None => (Signal::Term, rest),
+            };
+            if pattern.is_empty() {
+                return Err("usage: pkill [-SIGNAL] SEARCH".to_string());
+            }
+            Ok(Command::Pkill {
+                pattern: pattern.to_string(),
+                signal,
+            })
+        }
+        "pkill" => Err("usage: pkill [-SIGNAL] SEARCH".to_string()),
+        "sort" if !rest.is_empty() => rest
+            .split(',')
+            .map(|key| key.trim().parse())
+            .collect::<Result<_, _>>()
+            .map(Command::Sort),
+        "sort" => Err("usage: sort KEYS, e.g. sort cpu:desc,name".to_string()),
+        "filter" => Ok(match rest.strip_prefix('@') {
+            Some(name) => Command::SavedFilter(name.to_string()),
+            None => Command::Filter(rest.to_string()),
+        }),
+        "export" if !rest.is_empty() => Ok(Command::Export(PathBuf::from(rest))),
+        "export" => Err("usage: export PATH".to_string()),
+        "theme" | "palette" if rest.is_empty() => Ok(Command::Action(Action::Theme)),
+        // Whether a custom theme exists is only known to the config.
+        "theme" | "palette" => Ok(Command::Theme(rest.parse().unwrap_or_default())),
+        "profile" => Ok(Command::Profile(
+            (!rest.is_empty()).then(|| rest.to_string()),
+        )),
+        "save_profile" if !rest.is_empty() && !rest.contains(char::is_whitespace) => {
+            Ok(Command::SaveProfile(rest.to_string()))
+        }
+        "save_profile" => Err("usage: save_profile NAME, without spaces".to_string()),
+        "" => Err("empty command".to_string()),
+        name => Action::from_name(name)
+            .map(Command::Action)
+            .ok_or_else(|| format!("unknown command {name}")),
+    }
+}
+
+fn parse_signal(name: &str) -> Result<Signal, String> {
+    crate::signal::parse(name).ok_or_else(|| format!("unknown signal {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkill_takes_a_search_and_an_optional_signal() {
+        let Ok(Command::Pkill { pattern, signal }) = parse("pkill java user:alex") else {
+            panic!("not a pkill");
+        };
+        assert_eq!((pattern.as_str(), signal), ("java user:alex", Signal::Term));
+        let Ok(Command::Pkill { pattern, signal }) = parse("pkill -hup nginx") else {
+            panic!("not a pkill");
+        };
+        assert_eq!((pattern.as_str(), signal), ("nginx", Signal::Hangup));
+        assert!(parse("pkill").is_err());
+        assert!(parse("pkill -KILL").is_err());
+        assert!(parse("pkill -NOPE nginx").is_err());
+    }
+}
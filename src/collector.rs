@@ -0,0 +1,246 @@
+//! Background collection of process snapshots, so the table stays live without blocking input.
+//!
+//! The collector thread reads the process table, which takes hundreds of milliseconds on a busy
+//! host, into a snapshot of its own while the table keeps showing the last one. A finished
+//! snapshot is left in [`Live`] for the table to swap in whole, replacing one it has not taken
+//! yet, so the table never waits for a refresh nor falls behind.
+
+use crate::connector;
+use crate::snapshot::{Fields, Snapshot};
+use crate::source::ProcessSource;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+/// Delays between attempts when reading the process table fails.
+const BACKOFF: [Duration; 3] = [
+    Duration::from_millis(100),
+    Duration::from_millis(300),
+    Duration::from_millis(900),
+];
+
+/// Shortest time between snapshots prompted by processes starting or exiting, so a burst of
+/// them (e.g. a build) makes one snapshot, and CPU usage is measured over a usable period.
+const SETTLE: Duration = Duration::from_millis(250);
+
+/// Calls `attempt` until it succeeds, backing off between tries; `None` if every try failed.
+pub fn with_retry<T>(mut attempt: impl FnMut() -> Option<T>) -> Option<T> {
+    for delay in BACKOFF {
+        if let Some(value) = attempt() {
+            return Some(value);
+        }
+        thread::sleep(delay);
+    }
+    attempt()
+}
+
+/// Whether `system` holds a real process table. Reading `/proc` can fail transiently (e.g.
+/// under memory pressure or while the process table churns); sysinfo then silently keeps the old
+/// list or returns an empty one, although there is always at least syskill itself.
+pub fn readable(system: &System) -> bool {
+    proc_readable() && !system.processes().is_empty()
+}
+
+#[cfg(target_os = "linux")]
+fn proc_readable() -> bool {
+    std::fs::read_dir("/proc").is_ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn proc_readable() -> bool {
+    true
+}
+
+/// Whether the table has been left alone, shared with the collector so it slows down until the
+/// table is used again.
+#[derive(Clone, Default)]
+pub struct Idle(Arc<(Mutex<Option<Duration>>, Condvar)>);
+
+impl Idle {
+    /// Slows the collector down to a snapshot every `interval`, or none at all for a zero one;
+    /// `None` brings it back to speed right away.
+    pub fn set(&self, interval: Option<Duration>) {
+        let (state, changed) = &*self.0;
+        *state.lock().unwrap_or_else(|e| e.into_inner()) = interval;
+        changed.notify_all();
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.0
+             .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Waits out an idle interval, returning early once the table is used again; returns at once
+    /// while it is in use.
+    fn wait(&self) {
+        let (state, changed) = &*self.0;
+        let guard = state.lock().unwrap_or_else(|e| e.into_inner());
+        match *guard {
+            None => {}
+            Some(interval) if interval.is_zero() => {
+                drop(changed.wait_while(guard, |idle| idle.is_some()));
+            }
+            Some(interval) => {
+                drop(changed.wait_timeout_while(guard, interval, |idle| idle.is_some()));
+            }
+        }
+    }
+}
+
+/// A snapshot, or the error reading the process table even after retrying.
+type Update = Result<Snapshot, String>;
+
+/// The handle of a collector thread, which stops once it is dropped.
+pub struct Live {
+    /// The latest snapshot, until the table takes it.
+    ready: Arc<(Mutex<Option<Update>>, Condvar)>,
+    asks: Sender<()>,
+}
+
+impl Live {
+    /// The snapshot made since the last one was taken, if any.
+    pub fn latest(&self) -> Option<Update> {
+        self.ready
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    /// Waits for the next snapshot.
+    pub fn next(&self) -> Update {
+        let (ready, made) = &*self.ready;
+        let guard = ready.lock().unwrap_or_else(|e| e.into_inner());
+        made.wait_while(guard, |ready| ready.is_none())
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .unwrap_or_else(|| Err("No snapshot was made".to_string()))
+    }
+
+    /// Asks for a snapshot now rather than at the next interval, dropping one made before.
+    pub fn refresh(&self) {
+        self.latest();
+        let _ = self.asks.send(());
+    }
+}
+
+/// Makes a snapshot of `source` right away and then every `interval` until the returned handle
+/// is dropped, or only when asked with [`Live::refresh`] without an interval. Where the kernel
+/// reports processes starting and exiting (see [`connector`]), those prompt a snapshot sooner.
+/// Only the costly fields currently in `fields` are read, so a refresh costs what the table
+/// shows. While `idle` is set, snapshots follow its interval instead.
+pub fn spawn(
+    mut source: impl ProcessSource + Send + 'static,
+    interval: Option<Duration>,
+    fields: Arc<Mutex<Fields>>,
+    idle: Idle,
+) -> Live {
+    let ready = Arc::new((Mutex::new(None), Condvar::new()));
+    let (asks, wakes) = mpsc::channel();
+    let interval = interval.map(|interval| interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL));
+    if interval.is_some() {
+        if let Some(events) = connector::watch() {
+            let asks = asks.clone();
+            thread::spawn(move || {
+                for () in events {
+                    if asks.send(()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+    let wanted = move || *fields.lock().unwrap_or_else(|e| e.into_inner());
+    let live = Live {
+        ready: Arc::clone(&ready),
+        asks,
+    };
+    thread::spawn(move || {
+        let mut fields = Fields::ALL;
+        loop {
+            let start = Instant::now();
+            let snapshot = source.snapshot(fields);
+            match &snapshot {
+                Ok(snapshot) => tracing::debug!(
+                    processes = snapshot.processes.len(),
+                    elapsed_ms = start.elapsed().as_millis() as u64,
+                    "refreshed"
+                ),
+                Err(e) => tracing::warn!("refresh failed: {e}"),
+            }
+            let (latest, made) = &*ready;
+            *latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot);
+            made.notify_all();
+            // Nobody left to take it.
+            if Arc::strong_count(&ready) == 1 {
+                break;
+            }
+            if idle.is_idle() {
+                idle.wait();
+            } else if !wait(&wakes, interval) {
+                break;
+            }
+            fields = wanted();
+        }
+    });
+    live
+}
+
+/// Waits `interval`, or until a process started or exited or a snapshot was asked for, but at
+/// least [`SETTLE`]; without an interval, only for the latter. `false` once the handle is gone.
+fn wait(wakes: &Receiver<()>, interval: Option<Duration>) -> bool {
+    let start = Instant::now();
+    let woken = match interval {
+        Some(interval) => wakes.recv_timeout(interval),
+        None => wakes.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
+    match woken {
+        Ok(()) => {
+            thread::sleep(SETTLE.saturating_sub(start.elapsed()));
+            while wakes.try_recv().is_ok() {}
+            true
+        }
+        Err(RecvTimeoutError::Timeout) => true,
+        Err(RecvTimeoutError::Disconnected) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    fn snapshot(timestamp: u64) -> Snapshot {
+        Snapshot {
+            timestamp,
+            processes: Vec::new(),
+            without_disk: false,
+        }
+    }
+
+    /// A collector that only makes snapshots when asked, after the first.
+    fn on_demand(source: MemorySource) -> Live {
+        let fields = Arc::new(Mutex::new(Fields::NONE));
+        spawn(source, None, fields, Idle::default())
+    }
+
+    #[test]
+    fn snapshots_are_made_when_asked() {
+        let live = on_demand(MemorySource::new([snapshot(1), snapshot(2)]));
+        assert_eq!(live.next().unwrap().timestamp, 1);
+        assert!(live.latest().is_none());
+        live.refresh();
+        assert_eq!(live.next().unwrap().timestamp, 2);
+    }
+
+    #[test]
+    fn errors_are_passed_on() {
+        let live = on_demand(MemorySource::default());
+        assert!(live.next().is_err());
+    }
+}
@@ -0,0 +1,829 @@
+//! Optional settings file, `$XDG_CONFIG_HOME/syskill/config.toml` (`~/.config/syskill/config.toml`
+//! when unset), or the file given with `--config`. Command line flags take precedence.
+//!
+//! ```toml
+//! refresh_interval = 2
+//! sort = "cpu:desc,memory:desc"
+//! palette = "colorblind"
+//! memory_unit = "mib"
+//! cpu_per_core = true
+//! locale = "de_DE"
+//! hidden_columns = ["pid"]
+//! columns = ["name", "pid", "ppid", "cpu", "memory", "threads"]
+//! fullscreen = true
+//! mouse = false
+//! trend_column = true
+//! io_columns = true
+//! breakdown = true
+//! exited_linger = 5
+//! idle_timeout = 10
+//! idle_refresh = 60
+//! navigation_hold = 1
+//! log_level = "debug"
+//! draw_unfocused = false
+//! confirm_kills = true
+//! default_grace = 10
+//! report_template = "csv"
+//! export_format = "csv"
+//! watchdog_dry_run = false
+//! desktop_notifications = true
+//! redact = ['--password[= ](\S+)', 'token=(\w+)']
+//!
+//! [grace_periods]
+//! postgres = 60
+//! "firefox*" = 5
+//!
+//! [labels]
+//! postgres = "db"
+//! "gunicorn*" = "web"
+//!
+//! [templates.csv]
+//! file = "top.csv.j2"
+//! extension = "csv"
+//!
+//! [keys]
+//! kill = "x"
+//! report = "d"
+//!
+//! [[watchdog]]
+//! when = "name ~ java && cpu > 90"
+//! for = "30s"
+//! signal = "TERM"
+//!
+//! [[alert]]
+//! process = "java*"
+//! max_cpu = 90
+//!
+//! [themes.solarized]
+//! base = "indigo"
+//! buffer_bg = "#002b36"
+//! row_fg = "#93a1a1"
+//!
+//! [filters.my-java-stuff]
+//! search = "name ~ java && user == alice"
+//! key = "alt-j"
+//!
+//! [profiles.incident]
+//! filter = "cpu > 5"
+//! sort = "cpu:desc"
+//! tree = true
+//! record = "incident.jsonl"
+//!
+//! [profiles.db-server]
+//! filter = ["postgres", "cpu > 1"]
+//! columns = ["name", "pid", "cpu", "memory", "read", "write"]
+//! palette = "emerald"
+//! refresh_interval = 5
+//! ```
+
+use crate::alert::Threshold;
+use crate::app::SortKey;
+use crate::export::ExportFormat;
+use crate::keymap::KeyMap;
+use crate::logging;
+use crate::plugin;
+use crate::redact::{self, Redactor};
+use crate::template::Template;
+use crate::ui::{Palette, Theme};
+use crate::units::MemoryUnit;
+use crate::watchdog::{self, RuleSpec};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How the table starts with `--profile`, or looks after `:profile`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Searches, as typed after `/`; several are stacked, each narrowing down the one before.
+    #[serde(deserialize_with = "searches")]
+    pub filter: Vec<String>,
+    /// Sort order, as for `--sort`, which takes precedence.
+    #[serde(deserialize_with = "sort_keys")]
+    pub sort: Vec<SortKey>,
+    /// Start in the process tree.
+    pub tree: bool,
+    /// Start with processes grouped by name.
+    pub group: bool,
+    /// Columns in display order, as for `columns`; those of the config file when empty.
+    pub columns: Vec<Column>,
+    /// A palette or custom theme, as for `palette`; `--palette` takes precedence.
+    pub palette: Option<Theme>,
+    /// Seconds between live updates, as for `refresh_interval`; `--refresh-interval` takes
+    /// precedence.
+    pub refresh_interval: Option<f64>,
+    /// Append every snapshot of the table to this recording, as `syskill record` does.
+    pub record: Option<PathBuf>,
+}
+
+/// A report template, see [`crate::template`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateFile {
+    /// Relative to the directory of the config file.
+    pub file: PathBuf,
+    /// Of the files written with the template.
+    #[serde(default = "default_extension")]
+    pub extension: String,
+}
+
+/// A search kept under a name in the `[filters]` table, applied with `:filter @name` or a key.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedFilter {
+    /// As typed after `/`.
+    pub search: String,
+    /// Keys applying the filter, written like those of `[keys]`.
+    pub key: Option<String>,
+}
+
+/// A custom palette in the `[themes]` table, chosen by its name like the built-in ones. Colors
+/// are `#rrggbb`, a name such as `lightred` or an index of the 256-color palette; those left out
+/// are the `base` palette's.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSpec {
+    pub base: Palette,
+    /// Behind the table.
+    pub buffer_bg: Option<String>,
+    pub header_bg: Option<String>,
+    pub header_fg: Option<String>,
+    pub row_fg: Option<String>,
+    /// Of the selected row, the keys of the help line and other highlights.
+    pub selected_style_fg: Option<String>,
+    /// Of the rows, alternately.
+    pub normal_row_color: Option<String>,
+    pub alt_row_color: Option<String>,
+    pub footer_border_color: Option<String>,
+    /// Of CPU usage rising sharply between refreshes.
+    pub rising_fg: Option<String>,
+    /// Of CPU usage over 80%.
+    pub critical_fg: Option<String>,
+    /// Also mark severity with ▲/▼/!.
+    pub severity_symbols: Option<bool>,
+}
+
+fn default_extension() -> String {
+    "txt".to_string()
+}
+
+/// Columns of the process table, in their default order.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Column {
+    Name,
+    Pid,
+    Cpu,
+    Memory,
+    Node,
+    /// Labels and watch markers.
+    Tags,
+    /// Recent CPU usage as a small graph, shown with `trend_column`.
+    Trend,
+    /// Owner of the process.
+    User,
+    /// E.g. `Sleeping` or `Zombie`.
+    State,
+    /// Scheduling policy, e.g. `OTHER` or `FIFO 50`.
+    Sched,
+    /// Disk read and write rates, shown with `io_columns`.
+    Read,
+    Write,
+    /// Bytes of address space.
+    Virtual,
+    /// Resident memory shared with other processes.
+    Shared,
+    /// Memory swapped out.
+    Swap,
+    /// Parent PID.
+    Ppid,
+    /// Controlling terminal, e.g. `pts/3`.
+    Tty,
+    /// Session ID.
+    Session,
+    Threads,
+    /// Start time, in UTC.
+    Started,
+    /// Time since the start.
+    Elapsed,
+    /// CPU time used since the start.
+    CpuTime,
+    /// Open file descriptors.
+    Fds,
+    /// Container or systemd unit.
+    Container,
+    /// Window title, on Windows.
+    Window,
+    /// GPU utilization.
+    Gpu,
+    /// GPU memory.
+    GpuMemory,
+    /// Full command line.
+    Command,
+    /// Added with [`crate::plugin::register`], by its place among those.
+    Custom(u16),
+}
+
+impl Column {
+    pub const ALL: [Column; 28] = [
+        Column::Name,
+        Column::Pid,
+        Column::Cpu,
+        Column::Memory,
+        Column::Node,
+        Column::Tags,
+        Column::Trend,
+        Column::User,
+        Column::State,
+        Column::Sched,
+        Column::Read,
+        Column::Write,
+        Column::Virtual,
+        Column::Shared,
+        Column::Swap,
+        Column::Ppid,
+        Column::Tty,
+        Column::Session,
+        Column::Threads,
+        Column::Started,
+        Column::Elapsed,
+        Column::CpuTime,
+        Column::Fds,
+        Column::Container,
+        Column::Window,
+        Column::Gpu,
+        Column::GpuMemory,
+        Column::Command,
+    ];
+
+    /// The built-in columns followed by the custom ones.
+    pub fn all() -> Vec<Column> {
+        let custom = (0..plugin::count()).map(|index| Column::Custom(index as u16));
+        Column::ALL.into_iter().chain(custom).collect()
+    }
+
+    /// The column called `name` in the config file.
+    pub fn named(name: &str) -> Option<Column> {
+        Column::all()
+            .into_iter()
+            .find(|column| column.name() == name)
+    }
+
+    /// Place among [`Column::all`], which the table indexes its columns by.
+    pub fn index(self) -> usize {
+        match self {
+            Column::Custom(index) => Column::ALL.len() + usize::from(index),
+            column => Column::ALL
+                .iter()
+                .position(|&built_in| built_in == column)
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn from_index(index: usize) -> Column {
+        match Column::ALL.get(index) {
+            Some(&column) => column,
+            None => Column::Custom((index - Column::ALL.len()) as u16),
+        }
+    }
+
+    /// As written in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Pid => "pid",
+            Column::Cpu => "cpu",
+            Column::Memory => "memory",
+            Column::Node => "node",
+            Column::Tags => "tags",
+            Column::Trend => "trend",
+            Column::User => "user",
+            Column::State => "state",
+            Column::Sched => "sched",
+            Column::Read => "read",
+            Column::Write => "write",
+            Column::Virtual => "virtual",
+            Column::Shared => "shared",
+            Column::Swap => "swap",
+            Column::Ppid => "ppid",
+            Column::Tty => "tty",
+            Column::Session => "session",
+            Column::Threads => "threads",
+            Column::Started => "started",
+            Column::Elapsed => "elapsed",
+            Column::CpuTime => "cpu_time",
+            Column::Fds => "fds",
+            Column::Container => "container",
+            Column::Window => "window",
+            Column::Gpu => "gpu",
+            Column::GpuMemory => "gpu_memory",
+            Column::Command => "command",
+            Column::Custom(index) => plugin::provider(index).map_or("", |provider| provider.name()),
+        }
+    }
+}
+
+impl TryFrom<String> for Column {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Column::named(&name).ok_or_else(|| {
+            let names: Vec<_> = Column::all().into_iter().map(Column::name).collect();
+            format!(
+                "unknown column `{name}`, expected one of {}",
+                names.join(", ")
+            )
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Seconds between live updates of the table; 0 to only update on request.
+    pub refresh_interval: f64,
+    /// Initial sort order, as for `--sort`.
+    #[serde(deserialize_with = "sort_keys")]
+    pub sort: Vec<SortKey>,
+    /// A palette or custom theme; `None` for purple, or none at all with `NO_COLOR` set.
+    pub palette: Option<Theme>,
+    /// Custom palettes by name.
+    pub themes: BTreeMap<String, ThemeSpec>,
+    pub memory_unit: MemoryUnit,
+    /// Show CPU usage as a share of all cores instead of one.
+    pub cpu_per_core: bool,
+    /// Language and number format, e.g. `de_DE`; `C` for English and plain numbers. Defaults to
+    /// the environment's.
+    pub locale: Option<String>,
+    /// Columns left out of the table.
+    pub hidden_columns: Vec<Column>,
+    /// Columns of the table in display order, as arranged with the column picker. Overrides
+    /// `hidden_columns`, `trend_column` and `io_columns`.
+    pub columns: Vec<Column>,
+    /// Draw the table full screen instead of inline below the prompt, like `--fullscreen`.
+    pub fullscreen: bool,
+    /// Click, scroll and sort with the mouse; off leaves the mouse to the terminal, like
+    /// `--no-mouse`.
+    pub mouse: bool,
+    /// Add a column with the recent CPU usage of each process as a small graph.
+    pub trend_column: bool,
+    /// Add columns with the disk read and write rates of each process.
+    pub io_columns: bool,
+    /// Show how many processes there are in each state and of each user above the table.
+    pub breakdown: bool,
+    /// Seconds exited processes stay in the table, greyed out; 0 removes them right away.
+    pub exited_linger: f64,
+    /// Minutes without input, while the terminal is not focused, after which live updates slow
+    /// down to `idle_refresh`; 0 keeps them going.
+    pub idle_timeout: f64,
+    /// Seconds between live updates once idle; 0 pauses them until the table is used again.
+    pub idle_refresh: f64,
+    /// Seconds live updates wait after the selection last moved, so rows do not shift under it
+    /// on the way to the process it is headed for; 0 lets them through right away.
+    pub navigation_hold: f64,
+    /// How much goes into the log, see [`crate::logging`]: `off`, `error`, `warn`, `info`,
+    /// `debug` or `trace`.
+    pub log_level: String,
+    /// Keep drawing the table while the terminal reports not having the focus; when off, the
+    /// table is only drawn again once the focus is back.
+    pub draw_unfocused: bool,
+    /// Ask before killing a process.
+    pub confirm_kills: bool,
+    /// Seconds between SIGTERM and SIGKILL for processes without a grace period of their own.
+    pub default_grace: f64,
+    /// Seconds between SIGTERM and SIGKILL by process name; a trailing `*` matches a prefix.
+    pub grace_periods: BTreeMap<String, f64>,
+    /// Labels by process name; a trailing `*` matches a prefix.
+    pub labels: BTreeMap<String, String>,
+    /// Report templates by name, for `report --template` and `report_template`.
+    pub templates: BTreeMap<String, TemplateFile>,
+    /// Template the `x` key writes reports with instead of Markdown.
+    pub report_template: Option<String>,
+    /// Format of the table exports written with the `X` key.
+    pub export_format: ExportFormat,
+    /// Keys by action name, see [`crate::keymap`].
+    pub keys: BTreeMap<String, String>,
+    /// Patterns of secrets hidden in command lines and environment variables, see
+    /// [`crate::redact`]; `None` for the built-in ones.
+    pub redact: Option<Vec<String>>,
+    /// Rules signalling processes on their own, see [`crate::watchdog`].
+    pub watchdog: Vec<RuleSpec>,
+    /// Only log what the watchdog rules would do, like `--watchdog-dry-run`.
+    pub watchdog_dry_run: bool,
+    /// CPU and memory thresholds to alert on, see [`crate::alert`].
+    pub alert: Vec<Threshold>,
+    /// Announce alerts as desktop notifications too.
+    pub desktop_notifications: bool,
+    /// Startup settings by name, for `--profile`.
+    pub profiles: BTreeMap<String, Profile>,
+    /// Searches by name.
+    pub filters: BTreeMap<String, SavedFilter>,
+    /// The file the settings were read from, or would be; `None` without a home directory.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval: 2.0,
+            sort: Vec::new(),
+            palette: None,
+            themes: BTreeMap::new(),
+            memory_unit: MemoryUnit::default(),
+            cpu_per_core: false,
+            locale: None,
+            hidden_columns: Vec::new(),
+            columns: Vec::new(),
+            fullscreen: false,
+            mouse: true,
+            trend_column: false,
+            io_columns: false,
+            breakdown: false,
+            exited_linger: 5.0,
+            idle_timeout: 0.0,
+            idle_refresh: 60.0,
+            navigation_hold: 1.0,
+            log_level: "info".to_string(),
+            draw_unfocused: true,
+            confirm_kills: true,
+            default_grace: 10.0,
+            grace_periods: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            templates: BTreeMap::new(),
+            report_template: None,
+            export_format: ExportFormat::default(),
+            keys: BTreeMap::new(),
+            redact: None,
+            watchdog: Vec::new(),
+            watchdog_dry_run: false,
+            alert: Vec::new(),
+            desktop_notifications: false,
+            profiles: BTreeMap::new(),
+            filters: BTreeMap::new(),
+            path: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `path`, or the default file if there is one.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path: Some(path),
+                    ..Self::default()
+                })
+            }
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        let mut config: Self =
+            toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        config.path = Some(path.clone());
+        config
+            .keymap()
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        config
+            .redactor()
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        logging::parse_level(&config.log_level)
+            .map_err(|e| format!("{}: log_level: {e}", path.display()))?;
+        if let Some(dir) = path.parent() {
+            for template in config.templates.values_mut() {
+                template.file = dir.join(&template.file);
+            }
+        }
+        for name in config.templates.keys().chain(&config.report_template) {
+            config
+                .template(name)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        for theme in config
+            .palette
+            .iter()
+            .cloned()
+            .chain(config.themes.keys().map(|name| Theme::Custom(name.clone())))
+        {
+            theme
+                .colors(&config.themes)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        if let Some(name) = config
+            .themes
+            .keys()
+            .find(|name| matches!(name.parse(), Ok(Theme::Palette(_))))
+        {
+            return Err(format!(
+                "{}: theme {name} has the name of a built-in palette",
+                path.display()
+            ));
+        }
+        config
+            .watchdog_rules()
+            .map_err(|e| format!("{}: watchdog: {e}", path.display()))?;
+        for threshold in &config.alert {
+            threshold
+                .validate()
+                .map_err(|e| format!("{}: alert: {e}", path.display()))?;
+        }
+        for (name, profile) in &config.profiles {
+            if profile.tree && profile.group {
+                return Err(format!(
+                    "{}: profile `{name}` cannot start in both the tree and groups",
+                    path.display()
+                ));
+            }
+            if let Some(palette) = &profile.palette {
+                palette
+                    .colors(&config.themes)
+                    .map_err(|e| format!("{}: profile `{name}`: {e}", path.display()))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Every column in display order, with whether it is shown.
+    pub fn layout(&self) -> Vec<(Column, bool)> {
+        if !self.columns.is_empty() {
+            return arranged(&self.columns);
+        }
+        Column::all()
+            .into_iter()
+            .map(|column| {
+                let shown = match column {
+                    Column::Trend => self.trend_column,
+                    Column::Read | Column::Write => self.io_columns,
+                    Column::Virtual
+                    | Column::Shared
+                    | Column::Swap
+                    | Column::Ppid
+                    | Column::Tty
+                    | Column::Session
+                    | Column::Threads
+                    | Column::Started
+                    | Column::Elapsed
+                    | Column::CpuTime
+                    | Column::Fds
+                    | Column::Container
+                    | Column::Window
+                    | Column::Gpu
+                    | Column::GpuMemory
+                    | Column::Command => false,
+                    Column::Custom(index) => {
+                        plugin::provider(index).is_some_and(|provider| provider.shown())
+                    }
+                    _ => true,
+                };
+                (column, shown && !self.hidden_columns.contains(&column))
+            })
+            .collect()
+    }
+
+    /// Writes `columns` to the settings file as its `columns` setting, keeping the rest of the
+    /// file as it is, comments included. Creates the file if there is none yet.
+    pub fn save_columns(&self, columns: &[Column]) -> Result<&Path, String> {
+        let names: toml_edit::Array = columns.iter().map(|column| column.name()).collect();
+        self.edit(|document| document["columns"] = toml_edit::value(names))
+    }
+
+    /// Writes `profile` to the settings file as the `[profiles]` table called `name`, keeping the
+    /// rest of the file as it is, including a `record` setting of the profile.
+    pub fn save_profile(&self, name: &str, profile: &Profile) -> Result<&Path, String> {
+        self.edit(|document| {
+            let profiles = document.entry("profiles").or_insert_with(|| {
+                let mut profiles = toml_edit::Table::new();
+                profiles.set_implicit(true);
+                toml_edit::Item::Table(profiles)
+            });
+            // Anything else would not have loaded.
+            let Some(profiles) = profiles.as_table_like_mut() else {
+                return;
+            };
+            let table = profiles.entry(name).or_insert(toml_edit::table());
+            let mut set = |key: &str, value: Option<toml_edit::Value>| match value {
+                Some(value) => table[key] = toml_edit::Item::Value(value),
+                None => {
+                    if let Some(table) = table.as_table_like_mut() {
+                        table.remove(key);
+                    }
+                }
+            };
+            set(
+                "filter",
+                match profile.filter.as_slice() {
+                    [] => None,
+                    [search] => Some(search.as_str().into()),
+                    searches => Some(searches.iter().collect::<toml_edit::Array>().into()),
+                },
+            );
+            let sort: Vec<String> = profile.sort.iter().map(SortKey::to_string).collect();
+            set("sort", (!sort.is_empty()).then(|| sort.join(",").into()));
+            set("tree", profile.tree.then(|| true.into()));
+            set("group", profile.group.then(|| true.into()));
+            let columns: toml_edit::Array = profile.columns.iter().map(|c| c.name()).collect();
+            set("columns", (!columns.is_empty()).then(|| columns.into()));
+            set(
+                "palette",
+                profile
+                    .palette
+                    .as_ref()
+                    .map(|palette| palette.to_string().into()),
+            );
+            set(
+                "refresh_interval",
+                profile
+                    .refresh_interval
+                    .map(|seconds| match seconds.fract() == 0.0 {
+                        true => (seconds as i64).into(),
+                        false => seconds.into(),
+                    }),
+            );
+        })
+    }
+
+    /// Writes `rules` to the settings file as its `[[watchdog]]` tables, keeping the rest of the
+    /// file as it is.
+    pub fn save_watchdog(&self, rules: &[RuleSpec]) -> Result<&Path, String> {
+        let mut tables = toml_edit::ArrayOfTables::new();
+        for rule in rules {
+            let mut table = toml_edit::Table::new();
+            let fields = [
+                ("name", rule.name.as_ref()),
+                ("when", Some(&rule.when)),
+                ("for", rule.sustain.as_ref()),
+                ("signal", rule.signal.as_ref()),
+            ];
+            for (key, value) in fields {
+                if let Some(value) = value {
+                    table[key] = toml_edit::value(value.as_str());
+                }
+            }
+            tables.push(table);
+        }
+        self.edit(|document| {
+            if tables.is_empty() {
+                document.remove("watchdog");
+            } else {
+                document["watchdog"] = toml_edit::Item::ArrayOfTables(tables);
+            }
+        })
+    }
+
+    /// Changes the settings file with `change`, creating it if there is none yet.
+    fn edit(&self, change: impl FnOnce(&mut toml_edit::DocumentMut)) -> Result<&Path, String> {
+        let path = self
+            .path
+            .as_deref()
+            .ok_or("no home directory for the settings file")?;
+        let error = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(error(&e)),
+        };
+        let mut document: toml_edit::DocumentMut = text.parse().map_err(|e| error(&e))?;
+        change(&mut document);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| error(&e))?;
+        }
+        std::fs::write(path, document.to_string()).map_err(|e| error(&e))?;
+        Ok(path)
+    }
+
+    /// Reads and parses the template called `name`, with the extension of its files.
+    pub fn template(&self, name: &str) -> Result<(Template, &str), String> {
+        let template = self
+            .templates
+            .get(name)
+            .ok_or_else(|| format!("no template `{name}` in [templates]"))?;
+        let source = std::fs::read_to_string(&template.file)
+            .map_err(|e| format!("template {name}: {}: {e}", template.file.display()))?;
+        let parsed = Template::parse(&source).map_err(|e| format!("template {name}: {e}"))?;
+        Ok((parsed, &template.extension))
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, String> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| format!("unknown profile `{name}`"))
+    }
+
+    pub fn watchdog_rules(&self) -> Result<Vec<watchdog::Rule>, String> {
+        self.watchdog
+            .iter()
+            .map(watchdog::Rule::from_spec)
+            .collect()
+    }
+
+    pub fn exited_linger(&self) -> Duration {
+        Duration::try_from_secs_f64(self.exited_linger).unwrap_or_default()
+    }
+
+    pub fn idle_refresh(&self) -> Duration {
+        Duration::try_from_secs_f64(self.idle_refresh).unwrap_or_default()
+    }
+
+    pub fn navigation_hold(&self) -> Duration {
+        Duration::try_from_secs_f64(self.navigation_hold).unwrap_or_default()
+    }
+
+    /// How long a process called `name` gets to exit after SIGTERM. An exact name wins over a
+    /// prefix, and a longer prefix over a shorter one.
+    pub fn grace_period(&self, name: &str) -> Duration {
+        let seconds = self.grace_periods.get(name).copied().or_else(|| {
+            self.grace_periods
+                .iter()
+                .filter_map(|(pattern, &seconds)| {
+                    let prefix = pattern.strip_suffix('*')?;
+                    name.starts_with(prefix).then_some((prefix.len(), seconds))
+                })
+                .max_by_key(|&(len, _)| len)
+                .map(|(_, seconds)| seconds)
+        });
+        Duration::try_from_secs_f64(seconds.unwrap_or(self.default_grace)).unwrap_or_default()
+    }
+
+    /// Labels of the processes called `name`, from every matching pattern.
+    pub fn labels(&self, name: &str) -> Vec<&str> {
+        self.labels
+            .iter()
+            .filter(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern.as_str(),
+            })
+            .map(|(_, label)| label.as_str())
+            .collect()
+    }
+
+    /// The key bindings of the process table, the `[keys]` table's before the defaults.
+    pub(crate) fn keymap(&self) -> Result<KeyMap, String> {
+        let filter_keys: Vec<_> = self
+            .filters
+            .iter()
+            .map(|(name, filter)| (name.as_str(), filter.key.as_deref()))
+            .collect();
+        KeyMap::new(&self.keys, &filter_keys)
+    }
+
+    pub(crate) fn redactor(&self) -> Result<Redactor, String> {
+        match &self.redact {
+            Some(patterns) => Redactor::new(patterns),
+            None => Redactor::new(&redact::DEFAULT_PATTERNS),
+        }
+    }
+}
+
+/// Reads a sort order written like `--sort`, e.g. `"cpu:desc,memory"`.
+/// Every column in the order of `columns`, the others hidden after them.
+pub fn arranged(columns: &[Column]) -> Vec<(Column, bool)> {
+    let mut layout: Vec<(Column, bool)> = Vec::new();
+    for &column in columns {
+        if !layout.iter().any(|&(listed, _)| listed == column) {
+            layout.push((column, true));
+        }
+    }
+    let rest = Column::all()
+        .into_iter()
+        .filter(|column| !columns.contains(column));
+    layout.extend(rest.map(|column| (column, false)));
+    layout
+}
+
+/// One search, or a list of them.
+fn searches<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Searches {
+        One(String),
+        Stacked(Vec<String>),
+    }
+    Ok(match Searches::deserialize(deserializer)? {
+        Searches::One(search) => vec![search],
+        Searches::Stacked(searches) => searches,
+    })
+}
+
+fn sort_keys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<SortKey>, D::Error> {
+    String::deserialize(deserializer)?
+        .split(',')
+        .map(|key| key.trim().parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("syskill").join("config.toml"))
+}
@@ -0,0 +1,359 @@
+//! Process starts and exits as the kernel reports them through the proc connector (a netlink
+//! socket), so the live table updates right away instead of at the next refresh. The exit
+//! statuses it reports are kept for a while, to tell how a followed process ended, and so are the
+//! processes that lived too short for a refresh to see them. Linux only; listening takes
+//! `CAP_NET_ADMIN` in the initial network namespace, and without it the table keeps polling as
+//! before.
+
+#[cfg(target_os = "linux")]
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::Receiver;
+#[cfg(target_os = "linux")]
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How many exit statuses are kept, the latest last.
+#[cfg(target_os = "linux")]
+const KEPT_EXITS: usize = 1024;
+
+/// PIDs and wait statuses of the processes that exited last.
+#[cfg(target_os = "linux")]
+static EXITS: Mutex<VecDeque<(u32, u32)>> = Mutex::new(VecDeque::new());
+
+/// Processes living shorter than this count as short-lived: as long as the default refresh
+/// interval, so polling most likely misses them.
+pub const SHORT_LIVED: Duration = Duration::from_secs(2);
+
+/// How many short-lived processes are kept, the latest last.
+#[cfg(target_os = "linux")]
+const KEPT_SHORT_LIVED: usize = 4096;
+
+/// How many started processes are followed before those too old to be short-lived are dropped.
+#[cfg(target_os = "linux")]
+const FOLLOWED_STARTS: usize = 16384;
+
+/// A process that exited within [`SHORT_LIVED`] of starting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShortLived {
+    pub exited: Instant,
+    /// Its command line as it exec'd, or its name if it could not be read in time.
+    pub command: String,
+    pub lifetime: Duration,
+}
+
+/// When the processes started since listening did, and their commands.
+#[cfg(target_os = "linux")]
+static STARTS: Mutex<Option<HashMap<u32, (Instant, String)>>> = Mutex::new(None);
+
+#[cfg(target_os = "linux")]
+static SHORT_LIVED_EXITS: Mutex<VecDeque<ShortLived>> = Mutex::new(VecDeque::new());
+
+/// When the connector was first listened to.
+#[cfg(target_os = "linux")]
+static LISTENING_SINCE: OnceLock<Instant> = OnceLock::new();
+
+/// The wait status of the process `pid` if the kernel reported its exit, in the form of
+/// `waitpid(2)`.
+#[cfg(target_os = "linux")]
+pub fn exit_status(pid: u32) -> Option<u32> {
+    let exits = EXITS.lock().unwrap_or_else(|e| e.into_inner());
+    exits
+        .iter()
+        .rev()
+        .find(|&&(exited, _)| exited == pid)
+        .map(|&(_, status)| status)
+}
+
+/// How a process ended according to its wait status, e.g. `killed by SIGSEGV`.
+#[cfg(target_os = "linux")]
+pub fn describe_exit(status: u32) -> String {
+    let signal = status & 0x7f;
+    if signal == 0 {
+        return format!("exited with status {}", (status >> 8) & 0xff);
+    }
+    let core = if status & 0x80 != 0 {
+        ", core dumped"
+    } else {
+        ""
+    };
+    format!(
+        "killed by SIG{}{core}",
+        crate::signal::name_of_number(signal as i32)
+    )
+}
+
+/// The short-lived processes seen since listening, the latest last.
+#[cfg(target_os = "linux")]
+pub fn short_lived() -> Vec<ShortLived> {
+    let exits = SHORT_LIVED_EXITS.lock().unwrap_or_else(|e| e.into_inner());
+    exits.iter().cloned().collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn short_lived() -> Vec<ShortLived> {
+    Vec::new()
+}
+
+/// Since when short-lived processes are seen; `None` without the connector.
+#[cfg(target_os = "linux")]
+pub fn listening_since() -> Option<Instant> {
+    LISTENING_SINCE.get().copied()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn listening_since() -> Option<Instant> {
+    None
+}
+
+/// Follows process starts, execs and exits in the background, or `None` if the connector cannot
+/// be subscribed to. Threads starting and exiting are left out.
+#[cfg(target_os = "linux")]
+pub fn watch() -> Option<Receiver<()>> {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    // SAFETY: socket(2) has no memory safety requirements.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_CONNECTOR,
+        )
+    };
+    if fd == -1 {
+        return None;
+    }
+    // SAFETY: `fd` is a new socket owned by nothing else.
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // SAFETY: sockaddr_nl is plain data, valid when zeroed.
+    let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_groups = CN_IDX_PROC;
+    // SAFETY: `address` is a valid sockaddr_nl of the given size.
+    let bound = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            std::ptr::addr_of!(address).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if bound == -1 {
+        return None;
+    }
+    let request = listen_request();
+    // SAFETY: `request` is a valid buffer of the given length.
+    let sent = unsafe {
+        libc::send(
+            socket.as_raw_fd(),
+            request.as_ptr().cast(),
+            request.len(),
+            0,
+        )
+    };
+    if sent == -1 {
+        return None;
+    }
+    LISTENING_SINCE.get_or_init(Instant::now);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 4096];
+        loop {
+            // SAFETY: `buffer` is valid for writes of its length.
+            let received = unsafe {
+                libc::recv(
+                    socket.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    0,
+                )
+            };
+            let changed = if received == -1 {
+                match io::Error::last_os_error().raw_os_error() {
+                    Some(libc::EINTR) => false,
+                    // Events were dropped because we fell behind; there were changes all the same.
+                    Some(libc::ENOBUFS) => true,
+                    _ => break,
+                }
+            } else {
+                let datagram = &buffer[..received as usize];
+                follow_lifetime(datagram);
+                if let Some(exit) = process_exit(datagram) {
+                    let mut exits = EXITS.lock().unwrap_or_else(|e| e.into_inner());
+                    if exits.len() == KEPT_EXITS {
+                        exits.pop_front();
+                    }
+                    exits.push_back(exit);
+                }
+                is_process_event(datagram)
+            };
+            if changed && sender.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    Some(receiver)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch() -> Option<Receiver<()>> {
+    None
+}
+
+/// Multicast group and value of the proc connector, from `linux/connector.h`.
+#[cfg(target_os = "linux")]
+const CN_IDX_PROC: u32 = 1;
+#[cfg(target_os = "linux")]
+const CN_VAL_PROC: u32 = 1;
+
+/// Size of `struct nlmsghdr` and of `struct cn_msg` without its payload.
+#[cfg(target_os = "linux")]
+const NLMSG_HEADER: usize = 16;
+#[cfg(target_os = "linux")]
+const CN_MSG_HEADER: usize = 20;
+
+/// `what` of a `struct proc_event`, from `linux/cn_proc.h`.
+#[cfg(target_os = "linux")]
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+#[cfg(target_os = "linux")]
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+#[cfg(target_os = "linux")]
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+/// A netlink message asking the connector for process events (`PROC_CN_MCAST_LISTEN`).
+#[cfg(target_os = "linux")]
+fn listen_request() -> Vec<u8> {
+    const PROC_CN_MCAST_LISTEN: u32 = 1;
+    let length = NLMSG_HEADER + CN_MSG_HEADER + 4;
+    let mut message = Vec::with_capacity(length);
+    // struct nlmsghdr: length, type, flags, sequence number, port
+    message.extend((length as u32).to_ne_bytes());
+    message.extend((libc::NLMSG_DONE as u16).to_ne_bytes());
+    message.extend(0u16.to_ne_bytes());
+    message.extend(0u32.to_ne_bytes());
+    message.extend(0u32.to_ne_bytes());
+    // struct cn_msg: index, value, sequence number, acknowledgement, payload length, flags
+    message.extend(CN_IDX_PROC.to_ne_bytes());
+    message.extend(CN_VAL_PROC.to_ne_bytes());
+    message.extend(0u32.to_ne_bytes());
+    message.extend(0u32.to_ne_bytes());
+    message.extend(4u16.to_ne_bytes());
+    message.extend(0u16.to_ne_bytes());
+    message.extend(PROC_CN_MCAST_LISTEN.to_ne_bytes());
+    message
+}
+
+/// The 32-bit word at `offset` of a received datagram.
+#[cfg(target_os = "linux")]
+fn read_word(datagram: &[u8], offset: usize) -> Option<u32> {
+    datagram
+        .get(offset..offset + 4)
+        .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+        .map(u32::from_ne_bytes)
+}
+
+/// The PID and wait status of a process, not a thread, whose exit a received datagram reports.
+#[cfg(target_os = "linux")]
+fn process_exit(datagram: &[u8]) -> Option<(u32, u32)> {
+    let event = NLMSG_HEADER + CN_MSG_HEADER;
+    let data = event + 16;
+    if read_word(datagram, event)? != PROC_EVENT_EXIT {
+        return None;
+    }
+    // PID, TGID and exit code
+    let pid = read_word(datagram, data)?;
+    (read_word(datagram, data + 4)? == pid).then_some((pid, read_word(datagram, data + 8)?))
+}
+
+/// Notes when a process starts and what it runs, and keeps it as short-lived if it exits soon
+/// after. The command is read as it exec's, which is the only chance to for the shortest lived.
+#[cfg(target_os = "linux")]
+fn follow_lifetime(datagram: &[u8]) {
+    let event = NLMSG_HEADER + CN_MSG_HEADER;
+    let data = event + 16;
+    let now = Instant::now();
+    let mut starts = STARTS.lock().unwrap_or_else(|e| e.into_inner());
+    let starts = starts.get_or_insert_with(HashMap::new);
+    match read_word(datagram, event) {
+        Some(PROC_EVENT_FORK) if is_process_event(datagram) => {
+            let (Some(parent), Some(pid)) =
+                (read_word(datagram, data), read_word(datagram, data + 8))
+            else {
+                return;
+            };
+            if starts.len() >= FOLLOWED_STARTS {
+                starts.retain(|_, (started, _)| now.duration_since(*started) < SHORT_LIVED);
+            }
+            // Until it exec's, a child runs what its parent does; it may be gone already.
+            let mut forked = command(pid);
+            if forked.is_empty() {
+                forked = command(parent);
+            }
+            starts.insert(pid, (now, forked));
+        }
+        Some(PROC_EVENT_EXEC) => {
+            let Some(pid) = read_word(datagram, data) else {
+                return;
+            };
+            let exec = command(pid);
+            if let Some((_, started_command)) = starts.get_mut(&pid).filter(|_| !exec.is_empty()) {
+                *started_command = exec;
+            }
+        }
+        Some(PROC_EVENT_EXIT) => {
+            let Some((pid, _)) = process_exit(datagram) else {
+                return;
+            };
+            let Some((started, command)) = starts.remove(&pid) else {
+                return;
+            };
+            let lifetime = now.duration_since(started);
+            if lifetime < SHORT_LIVED {
+                let mut exits = SHORT_LIVED_EXITS.lock().unwrap_or_else(|e| e.into_inner());
+                if exits.len() == KEPT_SHORT_LIVED {
+                    exits.pop_front();
+                }
+                exits.push_back(ShortLived {
+                    exited: now,
+                    command,
+                    lifetime,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The command line of `pid`, or its name for a process without one such as a kernel thread;
+/// empty if it is gone.
+#[cfg(target_os = "linux")]
+fn command(pid: u32) -> String {
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).unwrap_or_default();
+    let words: Vec<_> = cmdline
+        .split(|&byte| byte == 0)
+        .filter(|word| !word.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect();
+    if !words.is_empty() {
+        return words.join(" ");
+    }
+    std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .map(|name| name.trim_end().to_string())
+        .unwrap_or_default()
+}
+
+/// Whether a received datagram reports a process, not a thread, starting, exec'ing or exiting.
+#[cfg(target_os = "linux")]
+fn is_process_event(datagram: &[u8]) -> bool {
+    let word = |offset: usize| read_word(datagram, offset);
+    // struct proc_event: what, cpu, timestamp, then the event data.
+    let event = NLMSG_HEADER + CN_MSG_HEADER;
+    let data = event + 16;
+    match word(event) {
+        // parent PID and TGID, then child PID and TGID, which differ for a new thread
+        Some(PROC_EVENT_FORK) => word(data + 8).is_some() && word(data + 8) == word(data + 12),
+        Some(PROC_EVENT_EXEC) => true,
+        Some(PROC_EVENT_EXIT) => process_exit(datagram).is_some(),
+        _ => false,
+    }
+}
@@ -0,0 +1,154 @@
+//! The processes of the machine summed up by user and by name: their CPU usage, resident memory
+//! and how many there are, to tell on a shared machine which user or service is eating it.
+//!
+//! The users and names are ranked by CPU usage, then by memory, and the top few of each listed.
+//! Picking one searches for its processes.
+
+use crate::app::ProcessInfo;
+use std::collections::HashMap;
+
+/// How many users and how many names are listed.
+const LISTED: usize = 10;
+
+/// The processes of one user or of one name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Total {
+    pub label: String,
+    pub cpu: f32,
+    pub memory: u64,
+    pub count: usize,
+}
+
+#[derive(Default)]
+pub struct Consumers {
+    /// The heaviest user first.
+    pub users: Vec<Total>,
+    /// The heaviest name first.
+    pub names: Vec<Total>,
+    /// Index of the chosen one, counting the users and then the names.
+    pub choice: usize,
+}
+
+impl Consumers {
+    /// Sums up the processes still running in `processes`.
+    pub fn observe(&mut self, processes: &[ProcessInfo]) {
+        let running: Vec<&ProcessInfo> = processes
+            .iter()
+            .filter(|data| data.exited.is_none() && data.members == 0)
+            .collect();
+        self.users = totals(&running, |data| &data.user);
+        self.names = totals(&running, |data| &data.name);
+        self.choice = self.choice.min(self.len().saturating_sub(1));
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len() + self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn move_choice(&mut self, step: isize) {
+        self.choice = self
+            .choice
+            .saturating_add_signed(step)
+            .min(self.len().saturating_sub(1));
+    }
+
+    /// The search for the processes of the chosen user or name.
+    pub fn search(&self) -> Option<String> {
+        match self.users.get(self.choice) {
+            Some(user) => Some(format!("user == \"{}\"", user.label)),
+            None => self
+                .names
+                .get(self.choice - self.users.len())
+                .map(|name| format!("name == \"{}\"", name.label)),
+        }
+    }
+}
+
+/// The processes summed up by `label`, ranked and cut down to the listed ones.
+fn totals<'a>(
+    processes: &[&'a ProcessInfo],
+    label: impl Fn(&'a ProcessInfo) -> &'a str,
+) -> Vec<Total> {
+    let mut sums: HashMap<&str, Total> = HashMap::new();
+    for &data in processes {
+        let label = label(data);
+        if label.is_empty() {
+            continue;
+        }
+        let total = sums.entry(label).or_insert_with(|| Total {
+            label: label.to_string(),
+            cpu: 0.0,
+            memory: 0,
+            count: 0,
+        });
+        total.cpu += data.cpu_usage;
+        total.memory = total.memory.saturating_add(data.memory);
+        total.count += 1;
+    }
+    let mut totals: Vec<Total> = sums.into_values().collect();
+    totals.sort_by(|a, b| {
+        b.cpu
+            .total_cmp(&a.cpu)
+            .then(b.memory.cmp(&a.memory))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    totals.truncate(LISTED);
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::ProcessSample;
+
+    fn process(name: &str, user: &str, cpu: f32, memory: u64) -> ProcessInfo {
+        ProcessInfo::from(ProcessSample {
+            name: name.to_string(),
+            user: user.to_string(),
+            cpu_usage: cpu,
+            memory,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn processes_are_summed_up_by_user_and_name() {
+        let mut consumers = Consumers::default();
+        consumers.observe(&[
+            process("java", "alice", 50.0, 300),
+            process("java", "bob", 20.0, 100),
+            process("postgres", "bob", 40.0, 500),
+            process("bash", "alice", 0.0, 10),
+        ]);
+        let summary = |totals: &[Total]| -> Vec<(String, f32, u64, usize)> {
+            totals
+                .iter()
+                .map(|total| (total.label.clone(), total.cpu, total.memory, total.count))
+                .collect()
+        };
+        assert_eq!(
+            summary(&consumers.users),
+            [
+                ("bob".to_string(), 60.0, 600, 2),
+                ("alice".to_string(), 50.0, 310, 2)
+            ]
+        );
+        assert_eq!(
+            summary(&consumers.names),
+            [
+                ("java".to_string(), 70.0, 400, 2),
+                ("postgres".to_string(), 40.0, 500, 1),
+                ("bash".to_string(), 0.0, 10, 1)
+            ]
+        );
+        assert_eq!(consumers.search().as_deref(), Some("user == \"bob\""));
+        consumers.move_choice(3);
+        assert_eq!(consumers.search().as_deref(), Some("name == \"postgres\""));
+        consumers.move_choice(10);
+        assert_eq!(consumers.search().as_deref(), Some("name == \"bash\""));
+    }
+}
@@ -0,0 +1,139 @@
+//! The container or systemd unit a process runs in, from its cgroup path in
+//! `/proc/<pid>/cgroup`, e.g. `docker:web` for `/system.slice/docker-3f2a….scope` or
+//! `nginx.service`. Linux only.
+//!
+//! Docker and Podman containers are named as their engine names them, when its state can be
+//! read; other containers go by the first 12 characters of their ID, like `docker ps` shows them.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Labels of the containers looked up so far, by engine and ID; a container keeps its name.
+static LABELS: Mutex<BTreeMap<(&str, String), String>> = Mutex::new(BTreeMap::new());
+
+/// Characters of a container ID shown where there is no name.
+const SHORT_ID: usize = 12;
+
+/// The container `pid` runs in, or failing that its systemd service or slice; empty for neither
+/// and where there is no `/proc`.
+pub fn of(pid: u32) -> String {
+    let Ok(cgroup) = std::fs::read_to_string(format!("/proc/{pid}/cgroup")) else {
+        return String::new();
+    };
+    // `hierarchy:controllers:path`, one line per hierarchy; cgroup v2 has just `0::path`.
+    let paths: Vec<&str> = cgroup
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .collect();
+    if let Some((engine, id)) = paths.iter().find_map(|path| container(path)) {
+        return label(engine, id);
+    }
+    paths.iter().find_map(|path| unit(path)).unwrap_or_default()
+}
+
+/// The engine and ID, or name for LXC, of the container a cgroup path belongs to.
+fn container(path: &str) -> Option<(&'static str, String)> {
+    let components: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    for (i, part) in components.iter().enumerate().rev() {
+        let scope = |prefix: &str| {
+            part.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(".scope"))
+                .filter(|id| is_id(id))
+                .map(str::to_string)
+        };
+        if let Some(id) = scope("docker-") {
+            return Some(("docker", id));
+        }
+        if let Some(id) = scope("libpod-") {
+            return Some(("podman", id));
+        }
+        if let Some(id) = scope("cri-containerd-").or_else(|| scope("crio-")) {
+            return Some(("k8s", id));
+        }
+        if let Some(name) = part.strip_prefix("lxc.payload.") {
+            return Some(("lxc", name.to_string()));
+        }
+        let parent = i.checked_sub(1).map(|parent| components[parent]);
+        if is_id(part) {
+            match parent {
+                Some("docker") => return Some(("docker", part.to_string())),
+                Some(_) if path.contains("kubepods") => return Some(("k8s", part.to_string())),
+                _ => {}
+            }
+        }
+        if parent == Some("lxc") {
+            return Some(("lxc", part.to_string()));
+        }
+    }
+    None
+}
+
+/// The innermost systemd service of a cgroup path, or its innermost slice but the root one.
+fn unit(path: &str) -> Option<String> {
+    let components = || path.split('/').rev();
+    components()
+        .find(|part| part.ends_with(".service"))
+        .or_else(|| components().find(|part| part.ends_with(".slice") && *part != "-.slice"))
+        .map(str::to_string)
+}
+
+/// Whether `text` looks like a container ID: 64 hexadecimal digits.
+fn is_id(text: &str) -> bool {
+    text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// `engine:name`, with the short ID for a name where the engine's state cannot be read.
+fn label(engine: &'static str, id: String) -> String {
+    let mut labels = LABELS.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (engine, id);
+    if let Some(label) = labels.get(&key) {
+        return label.clone();
+    }
+    let (engine, id) = &key;
+    let name = match *engine {
+        "docker" => docker_name(id),
+        "podman" => podman_name(id),
+        _ => None,
+    }
+    .unwrap_or_else(|| id.chars().take(SHORT_ID).collect());
+    let label = format!("{engine}:{name}");
+    labels.insert(key, label.clone());
+    label
+}
+
+fn docker_name(id: &str) -> Option<String> {
+    let path = format!("/var/lib/docker/containers/{id}/config.v2.json");
+    let config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    let name = config.get("Name")?.as_str()?;
+    Some(name.trim_start_matches('/').to_string())
+}
+
+/// From the container list of the system's storage, or the user's for rootless containers.
+fn podman_name(id: &str) -> Option<String> {
+    const LIST: &str = "containers/storage/overlay-containers/containers.json";
+    let user = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".local/share")));
+    let lists = [Some(PathBuf::from("/var/lib")), user]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(LIST));
+    for list in lists {
+        let Ok(text) = std::fs::read_to_string(list) else {
+            continue;
+        };
+        let Ok(serde_json::Value::Array(containers)) = serde_json::from_str(&text) else {
+            continue;
+        };
+        let name = containers
+            .iter()
+            .find(|container| container.get("id").and_then(|id| id.as_str()) == Some(id))
+            .and_then(|container| container.get("names")?.get(0)?.as_str());
+        if let Some(name) = name {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
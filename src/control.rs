@@ -0,0 +1,294 @@
+//! Control socket for driving a running TUI from scripts and editors.
+//!
+//! Started with `--control <path>`. Every connection may send any number of newline-separated
+//! commands and receives one reply line (`ok ...` or `error: ...`) per command:
+//!
+//! ```text
+//! list                    the displayed table, as a JSON array on the reply line
+//! filter nginx            show only processes whose name contains "nginx"
+//! filter                  clear the filter
+//! kill 1234 [SIGNAL [START]]
+//!                         send SIGNAL (default TERM) to PID 1234, if it started at START
+//! snapshot /tmp/a.json    write the displayed table as JSON
+//! refresh                 reload the process list
+//! ```
+//!
+//! A command may also be a JSON object, such as `{"command": "kill", "pid": 1234, "signal":
+//! "HUP", "start_time": 1700000000}` or `{"command": "filter", "search": "nginx"}`, for tools that would rather not format
+//! and parse text. Its reply is one too: `{"ok": true, "message": ...}`, `{"ok": true,
+//! "processes": [...]}` for `list`, or `{"ok": false, "error": ...}`.
+
+use crate::snapshot::ProcessSample;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use sysinfo::Signal;
+
+#[derive(Debug)]
+pub enum ControlCommand {
+    Filter(String),
+    Kill {
+        pid: u32,
+        signal: Signal,
+        /// Start time the process must have, as listed, so a process that got its PID since is
+        /// not signalled.
+        start_time: Option<u64>,
+    },
+    Snapshot(PathBuf),
+    Refresh,
+    List,
+}
+
+/// A command as a JSON object, named by its `command` field.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+enum JsonCommand {
+    Filter {
+        #[serde(default)]
+        search: String,
+    },
+    Kill {
+        pid: u32,
+        signal: Option<String>,
+        start_time: Option<u64>,
+    },
+    Snapshot {
+        path: PathBuf,
+    },
+    Refresh,
+    List,
+}
+
+/// A command together with the connection its reply goes to.
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    /// Whether the command came as JSON, and so wants its reply in JSON.
+    json: bool,
+    reply: UnixStream,
+}
+
+impl ControlRequest {
+    pub fn reply(mut self, result: Result<String, String>) {
+        let _ = writeln!(self.reply, "{}", reply_line(self.json, result));
+    }
+
+    /// Replies to `list` with `processes`.
+    pub fn reply_processes(mut self, processes: &[ProcessSample]) {
+        let line = match self.json {
+            true => json!({ "ok": true, "processes": processes }).to_string(),
+            false => format!("ok {}", json!(processes)),
+        };
+        let _ = writeln!(self.reply, "{line}");
+    }
+}
+
+fn reply_line(json: bool, result: Result<String, String>) -> String {
+    match (json, result) {
+        (true, Ok(message)) => json!({ "ok": true, "message": message }).to_string(),
+        (true, Err(e)) => json!({ "ok": false, "error": e }).to_string(),
+        (false, Ok(message)) if message.is_empty() => "ok".to_string(),
+        (false, Ok(message)) => format!("ok {message}"),
+        (false, Err(e)) => format!("error: {e}"),
+    }
+}
+
+/// Whether `line` is a command in JSON rather than text.
+fn is_json(line: &str) -> bool {
+    line.trim_start().starts_with('{')
+}
+
+const KILL_USAGE: &str = "usage: kill <pid> [signal [start time]]";
+
+pub fn parse(line: &str) -> Result<ControlCommand, String> {
+    if is_json(line) {
+        return parse_json(line);
+    }
+    let line = line.trim();
+    let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    match verb {
+        "filter" => Ok(ControlCommand::Filter(rest.to_string())),
+        "refresh" => Ok(ControlCommand::Refresh),
+        "list" => Ok(ControlCommand::List),
+        "snapshot" if !rest.is_empty() => Ok(ControlCommand::Snapshot(PathBuf::from(rest))),
+        "snapshot" => Err("usage: snapshot <path>".to_string()),
+        "kill" => {
+            let mut args = rest.split_whitespace();
+            let pid = args
+                .next()
+                .and_then(|pid| pid.parse().ok())
+                .ok_or(KILL_USAGE)?;
+            let signal = match args.next() {
+                Some(name) => parse_signal(name)?,
+                None => Signal::Term,
+            };
+            let start_time = match args.next() {
+                Some(start_time) => Some(start_time.parse().map_err(|_| KILL_USAGE)?),
+                None => None,
+            };
+            Ok(ControlCommand::Kill {
+                pid,
+                signal,
+                start_time,
+            })
+        }
+        "" => Err("empty command".to_string()),
+        other => Err(format!("unknown command {other}")),
+    }
+}
+
+fn parse_json(line: &str) -> Result<ControlCommand, String> {
+    Ok(
+        match serde_json::from_str(line).map_err(|e| e.to_string())? {
+            JsonCommand::Filter { search } => ControlCommand::Filter(search),
+            JsonCommand::Kill {
+                pid,
+                signal,
+                start_time,
+            } => ControlCommand::Kill {
+                pid,
+                signal: match signal {
+                    Some(name) => parse_signal(&name)?,
+                    None => Signal::Term,
+                },
+                start_time,
+            },
+            JsonCommand::Snapshot { path } => ControlCommand::Snapshot(path),
+            JsonCommand::Refresh => ControlCommand::Refresh,
+            JsonCommand::List => ControlCommand::List,
+        },
+    )
+}
+
+fn parse_signal(name: &str) -> Result<Signal, String> {
+    crate::signal::parse(name).ok_or(format!("unknown signal {name}"))
+}
+
+/// Binds `path` and forwards parsed commands to the returned receiver.
+pub fn listen(path: &Path) -> io::Result<Receiver<ControlRequest>> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        // A live TUI answers connections; a socket nobody answers is a leftover from a crash, and
+        // anything else is not ours to remove.
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a syskill is already listening on {}", path.display()),
+            ));
+        }
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            thread::spawn(move || serve_connection(stream, sender));
+        }
+    });
+    Ok(receiver)
+}
+
+fn serve_connection(stream: UnixStream, sender: Sender<ControlRequest>) {
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else { break };
+        let Ok(mut reply) = stream.try_clone() else {
+            break;
+        };
+        let json = is_json(&line);
+        match parse(&line) {
+            Ok(command) => {
+                let request = ControlRequest {
+                    command,
+                    json,
+                    reply,
+                };
+                if sender.send(request).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(reply, "{}", reply_line(json, Err(e)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_stale_sockets_are_replaced() {
+        let directory =
+            std::env::temp_dir().join(format!("syskill-control-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("control.sock");
+
+        std::fs::write(&path, "not a socket").unwrap();
+        let error = listen(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+        std::fs::remove_file(&path).unwrap();
+
+        // Left behind by a crash: bound, but nobody listens any more.
+        drop(UnixListener::bind(&path).unwrap());
+        let _receiver = listen(&path).unwrap();
+        let error = listen(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn commands_come_as_text_or_json() {
+        assert!(matches!(parse("list"), Ok(ControlCommand::List)));
+        assert!(matches!(
+            parse(r#" {"command": "kill", "pid": 1234, "signal": "HUP"}"#),
+            Ok(ControlCommand::Kill {
+                pid: 1234,
+                signal: Signal::Hangup,
+                start_time: None,
+            })
+        ));
+        assert!(matches!(
+            parse("kill 1234 KILL 1700000000"),
+            Ok(ControlCommand::Kill {
+                pid: 1234,
+                signal: Signal::Kill,
+                start_time: Some(1_700_000_000),
+            })
+        ));
+        assert!(parse("kill 1234 KILL soon").is_err());
+        assert!(matches!(
+            parse(r#"{"command": "filter"}"#),
+            Ok(ControlCommand::Filter(search)) if search.is_empty()
+        ));
+        assert!(parse(r#"{"command": "kill", "pid": 1, "signal": "NOPE"}"#).is_err());
+        assert!(parse(r#"{"command": "reboot"}"#).is_err());
+    }
+
+    #[test]
+    fn json_commands_get_json_replies() {
+        assert_eq!(reply_line(false, Ok(String::new())), "ok");
+        assert_eq!(reply_line(false, Err("no".to_string())), "error: no");
+        assert_eq!(
+            reply_line(true, Err("no".to_string())),
+            r#"{"error":"no","ok":false}"#
+        );
+    }
+}
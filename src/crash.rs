@@ -0,0 +1,245 @@
+//! Crash reporter.
+//!
+//! On panic a diagnostic bundle is written next to the log files and its path is printed, so
+//! bug reports come with the panic message, a backtrace, platform details and a summary of the
+//! application state. Secrets in the panic message and keys typed outside the table are left out,
+//! and process names are redacted unless the user opted in with `--crash-include-names`.
+
+use crate::redact::{Redactor, DEFAULT_PATTERNS};
+use regex::Regex;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use sysinfo::System;
+
+const MAX_ACTIONS: usize = 32;
+
+static INCLUDE_NAMES: AtomicBool = AtomicBool::new(false);
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    state: None,
+    actions: VecDeque::new(),
+    redactor: None,
+});
+
+/// Sizes and flags describing the application at the time of the crash.
+#[derive(Clone, Debug, Default)]
+pub struct StateSummary {
+    pub mode: String,
+    pub items: usize,
+    pub selected: Option<usize>,
+    pub kills: usize,
+    pub search_len: usize,
+    pub popup_open: bool,
+    /// Only written to the bundle when process names were opted in.
+    pub selected_name: Option<String>,
+    /// Only written to the bundle when process names were opted in.
+    pub killed_names: Vec<String>,
+}
+
+struct CrashContext {
+    state: Option<StateSummary>,
+    actions: VecDeque<String>,
+    /// Hides secrets in the panic message; the default patterns until the config file is read.
+    redactor: Option<Redactor>,
+}
+
+pub fn install(include_process_names: bool) {
+    INCLUDE_NAMES.store(include_process_names, Ordering::Relaxed);
+    panic::set_hook(Box::new(|info| {
+        // Restore the terminal first so the message below is readable.
+        crate::input::restore_terminal();
+        tracing::error!("crashed: {info}");
+        match write_bundle(info) {
+            Ok(path) => eprintln!(
+                "\nsyskill crashed: {info}\nA diagnostic report was written to {}",
+                path.display()
+            ),
+            Err(e) => {
+                eprintln!("\nsyskill crashed: {info}\n(failed to write diagnostic report: {e})")
+            }
+        }
+    }));
+}
+
+pub fn record_action(action: String) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        if context.actions.len() == MAX_ACTIONS {
+            context.actions.pop_front();
+        }
+        context.actions.push_back(action);
+    }
+}
+
+/// Hides what `redactor` finds in the panic messages of crash reports, as in the table.
+pub fn set_redactor(redactor: Redactor) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.redactor = Some(redactor);
+    }
+}
+
+/// `message` with the secrets `redactor` finds hidden, and `names` too, as whole words.
+fn redact_message(message: &str, redactor: &Redactor, names: &[&str]) -> String {
+    let mut message = redactor.apply(message);
+    for name in names.iter().filter(|name| !name.is_empty()) {
+        if let Ok(name) = Regex::new(&format!(r"\b{}\b", regex::escape(name))) {
+            message = name.replace_all(&message, "<process>").into_owned();
+        }
+    }
+    message
+}
+
+pub fn update_state(state: StateSummary) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.state = Some(state);
+    }
+}
+
+fn write_bundle(info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+    let timestamp = crate::snapshot::unix_now();
+    let directory = crate::logging::directory()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no home directory"))?;
+    std::fs::create_dir_all(&directory)?;
+    let path = directory.join(format!("crash-{timestamp}-{}.txt", std::process::id()));
+    let include_names = INCLUDE_NAMES.load(Ordering::Relaxed);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "syskill crash report");
+    let _ = writeln!(out, "====================\n");
+    let _ = writeln!(out, "time: {timestamp} (Unix)");
+    for line in crate::update::about_lines() {
+        let _ = writeln!(out, "{line}");
+    }
+    let _ = writeln!(
+        out,
+        "os: {} / kernel {}",
+        System::long_os_version().unwrap_or_default(),
+        System::kernel_version().unwrap_or_default()
+    );
+
+    // The hook may run while the lock is held by the panicking thread; never block on it here.
+    let context = CONTEXT.try_lock();
+    let message = match &context {
+        Ok(context) => {
+            let default = || Redactor::new(&DEFAULT_PATTERNS).unwrap_or_default();
+            let redactor = context.redactor.clone().unwrap_or_else(default);
+            let names: Vec<&str> = match &context.state {
+                Some(state) if !include_names => {
+                    let selected = state.selected_name.iter().map(String::as_str);
+                    selected
+                        .chain(state.killed_names.iter().map(String::as_str))
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            redact_message(&info.to_string(), &redactor, &names)
+        }
+        // Nothing to tell the names by, so only where it happened.
+        Err(_) => match info.location() {
+            Some(location) => format!("panicked at {location} (message withheld)"),
+            None => "panicked (message withheld)".to_string(),
+        },
+    };
+    let _ = writeln!(out, "\npanic\n-----\n{message}");
+    if let Some(directory) = crate::logging::file_directory() {
+        let _ = writeln!(out, "log: {}", directory.display());
+    }
+
+    match context {
+        Ok(context) => {
+            let _ = writeln!(out, "\nstate\n-----");
+            match &context.state {
+                Some(state) => {
+                    let _ = writeln!(out, "mode: {}", state.mode);
+                    let _ = writeln!(out, "items: {}", state.items);
+                    let _ = writeln!(out, "selected row: {:?}", state.selected);
+                    let _ = writeln!(out, "kills this session: {}", state.kills);
+                    let _ = writeln!(out, "search input length: {}", state.search_len);
+                    let _ = writeln!(out, "popup open: {}", state.popup_open);
+                    if include_names {
+                        let _ = writeln!(out, "selected process: {:?}", state.selected_name);
+                        let _ = writeln!(out, "killed processes: {:?}", state.killed_names);
+                    } else {
+                        let _ = writeln!(out, "process names: redacted");
+                    }
+                }
+                None => {
+                    let _ = writeln!(out, "not initialised");
+                }
+            }
+
+            let _ = writeln!(
+                out,
+                "\nlast actions (oldest first)\n---------------------------"
+            );
+            for action in &context.actions {
+                let _ = writeln!(out, "{action}");
+            }
+        }
+        Err(_) => {
+            let _ = writeln!(out, "\nstate unavailable");
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "\nbacktrace\n---------\n{}",
+        Backtrace::force_capture()
+    );
+
+    create(&path)?.write_all(out.as_bytes())?;
+    Ok(path)
+}
+
+/// Creates the file at `path` for its owner alone, failing if anything is there already, a link
+/// to another file included.
+fn create(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_messages_keep_secrets_and_names_out() {
+        let redactor = Redactor::new(&DEFAULT_PATTERNS).unwrap();
+        let message = "panicked at src/app.rs:1:2:\nno worker for backup --password=hunter2";
+        assert_eq!(
+            redact_message(message, &redactor, &["backup", "app"]),
+            "panicked at src/<process>.rs:1:2:\nno worker for <process> --password=***"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bundles_are_new_files_of_their_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = std::env::temp_dir().join(format!("syskill-crash-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("crash.txt");
+        create(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(create(&path).is_err());
+
+        // A link planted where the bundle goes is not followed.
+        let target = directory.join("target.txt");
+        std::fs::write(&target, "kept").unwrap();
+        let link = directory.join("link.txt");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        assert!(create(&link).is_err());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "kept");
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
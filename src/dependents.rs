@@ -0,0 +1,157 @@
+//! Processes that may stop working when another one is killed: its children, the processes
+//! connected to it over TCP and, on Linux, the other processes of its systemd service.
+//!
+//! Connections are found by pairing the established sockets in `/proc/net/{tcp,tcp6}` and
+//! matching them to processes through the socket inodes in `/proc/<pid>/fd`; the service comes
+//! from `/proc/<pid>/cgroup`.
+
+use sysinfo::{Pid, System};
+
+#[derive(PartialEq)]
+pub enum Reason {
+    Child,
+    Connected,
+    /// Runs in the same systemd service.
+    SameUnit(String),
+}
+
+impl Reason {
+    pub fn describe(&self) -> String {
+        match self {
+            Reason::Child => "child".to_string(),
+            Reason::Connected => "connected over TCP".to_string(),
+            Reason::SameUnit(unit) => format!("same unit {unit}"),
+        }
+    }
+}
+
+pub struct Dependent {
+    pub pid: Pid,
+    pub name: String,
+    pub reason: Reason,
+}
+
+/// Processes in `system` that appear to depend on `target`, ordered by PID.
+pub fn find(system: &System, target: Pid) -> Vec<Dependent> {
+    let mut dependents: Vec<Dependent> = Vec::new();
+    let mut add = |pid: Pid, reason: Reason| {
+        if pid == target || dependents.iter().any(|dependent| dependent.pid == pid) {
+            return;
+        }
+        if let Some(process) = system.process(pid) {
+            dependents.push(Dependent {
+                pid,
+                name: process.name().to_string(),
+                reason,
+            });
+        }
+    };
+
+    for (&pid, process) in system.processes() {
+        if process.parent() == Some(target) {
+            add(pid, Reason::Child);
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        for pid in tcp_peers(target) {
+            add(pid, Reason::Connected);
+        }
+        if let Some(unit) = service(target) {
+            for &pid in system.processes().keys() {
+                if service(pid).as_ref() == Some(&unit) {
+                    add(pid, Reason::SameUnit(unit.clone()));
+                }
+            }
+        }
+    }
+
+    dependents.sort_by_key(|dependent| dependent.pid);
+    dependents
+}
+
+/// Inodes of the sockets `pid` has open.
+#[cfg(target_os = "linux")]
+fn socket_inodes(pid: Pid) -> std::collections::HashSet<String> {
+    let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+        return Default::default();
+    };
+    fds.flatten()
+        .filter_map(|fd| {
+            let target = std::fs::read_link(fd.path()).ok()?;
+            let target = target.to_string_lossy();
+            let inode = target.strip_prefix("socket:[")?.strip_suffix(']')?;
+            Some(inode.to_string())
+        })
+        .collect()
+}
+
+/// Processes at the other end of `target`'s established TCP connections on this host.
+#[cfg(target_os = "linux")]
+fn tcp_peers(target: Pid) -> Vec<Pid> {
+    use std::collections::HashSet;
+
+    const TCP_ESTABLISHED: &str = "01";
+
+    let own = socket_inodes(target);
+    if own.is_empty() {
+        return Vec::new();
+    }
+    // (local address, remote address, inode) of every established connection.
+    let mut connections = Vec::new();
+    for file in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(table) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let (Some(&local), Some(&remote), Some(&TCP_ESTABLISHED), Some(&inode)) =
+                (fields.get(1), fields.get(2), fields.get(3), fields.get(9))
+            {
+                connections.push((local.to_string(), remote.to_string(), inode.to_string()));
+            }
+        }
+    }
+    let ends: HashSet<(&str, &str)> = connections
+        .iter()
+        .filter(|(_, _, inode)| own.contains(inode))
+        .map(|(local, remote, _)| (local.as_str(), remote.as_str()))
+        .collect();
+    // The peer's socket is the same connection seen from the other side.
+    let peers: HashSet<&str> = connections
+        .iter()
+        .filter(|(local, remote, inode)| {
+            !own.contains(inode) && ends.contains(&(remote.as_str(), local.as_str()))
+        })
+        .map(|(_, _, inode)| inode.as_str())
+        .collect();
+    if peers.is_empty() {
+        return Vec::new();
+    }
+
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    processes
+        .flatten()
+        .filter_map(|process| process.file_name().to_str()?.parse::<usize>().ok())
+        .map(Pid::from)
+        .filter(|&pid| pid != target)
+        .filter(|&pid| {
+            socket_inodes(pid)
+                .iter()
+                .any(|inode| peers.contains(inode.as_str()))
+        })
+        .collect()
+}
+
+/// The systemd service `pid` runs in, from its cgroup path.
+#[cfg(target_os = "linux")]
+fn service(pid: Pid) -> Option<String> {
+    let cgroup = std::fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+    cgroup.lines().find_map(|line| {
+        let (_, path) = line.rsplit_once(':')?;
+        let unit = path.rsplit('/').next()?;
+        unit.ends_with(".service").then(|| unit.to_string())
+    })
+}
@@ -0,0 +1,235 @@
+//! Everything sysinfo knows about one process, for the detail view.
+//!
+//! The table only keeps what it displays and sorts by, so [`Details`] refreshes the process on its
+//! own, including the command line and environment that are too expensive to read for all of them.
+
+use crate::fds::{self, Descriptor};
+use crate::proc;
+use crate::procfs::{self, UNAVAILABLE};
+use crate::redact::Redactor;
+use crate::report;
+use crate::snapshot::ProcessKey;
+use crate::threads::Threads;
+use crate::units::Units;
+use sysinfo::{Pid, ProcessRefreshKind, System, UpdateKind, Users};
+
+pub struct Details {
+    pub pid: Pid,
+    pub start_time: u64,
+    pub name: String,
+    /// Labeled values, in display order.
+    pub fields: Vec<(&'static str, String)>,
+    /// Empty if the environment cannot be read (other users' processes).
+    pub environ: Vec<Variable>,
+    /// Open file descriptors; `None` if they cannot be read.
+    pub files: Option<Vec<Descriptor>>,
+    /// Sampled again while the Threads tab is shown.
+    pub threads: Threads,
+}
+
+/// An environment variable of the process.
+pub struct Variable {
+    /// `NAME=value`, as read.
+    pub text: String,
+    /// The same with its secrets hidden, if it has any.
+    pub hidden: Option<String>,
+}
+
+impl Variable {
+    /// The variable as shown, its secrets hidden unless `revealed`.
+    pub fn shown(&self, revealed: bool) -> &str {
+        match &self.hidden {
+            Some(hidden) if !revealed => hidden,
+            _ => &self.text,
+        }
+    }
+}
+
+/// The tabs of the detail view.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Tab {
+    #[default]
+    Overview,
+    Environment,
+    Files,
+    Threads,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 4] = [Tab::Overview, Tab::Environment, Tab::Files, Tab::Threads];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Overview => "Overview",
+            Self::Environment => "Environment",
+            Self::Files => "Files",
+            Self::Threads => "Threads",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Overview => Self::Environment,
+            Self::Environment => Self::Files,
+            Self::Files => Self::Threads,
+            Self::Threads => Self::Overview,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            Self::Overview => Self::Threads,
+            Self::Environment => Self::Overview,
+            Self::Files => Self::Environment,
+            Self::Threads => Self::Files,
+        }
+    }
+}
+
+impl Details {
+    /// Reads the details of `pid`, with memory sizes in `units`; `None` if it is gone.
+    pub fn collect(system: &mut System, pid: Pid, units: Units) -> Option<Self> {
+        system.refresh_process_specifics(
+            pid,
+            ProcessRefreshKind::new()
+                .with_memory()
+                .with_disk_usage()
+                .with_user(UpdateKind::Always)
+                .with_cmd(UpdateKind::Always)
+                .with_exe(UpdateKind::Always)
+                .with_cwd(UpdateKind::Always)
+                .with_environ(UpdateKind::Always),
+        );
+        let process = system.process(pid)?;
+        let unknown = || UNAVAILABLE.to_string();
+        let path = |path: Option<&std::path::Path>| {
+            path.map_or_else(unknown, |path| path.display().to_string())
+        };
+        let user = process.user_id().map_or_else(unknown, |uid| {
+            Users::new_with_refreshed_list()
+                .get_user_by_id(uid)
+                .map_or_else(|| uid.to_string(), |user| user.name().to_string())
+        });
+        let disk = process.disk_usage();
+        // Unreadable counters come back as zeros.
+        let disk_readable = procfs::readable(pid.as_u32(), "io");
+        let disk_total = |bytes: u64| match disk_readable {
+            true => format!("{} KiB total", bytes / 1024),
+            false => unknown(),
+        };
+        let (shared, swap) = match proc::shared_and_swap(pid.as_u32()) {
+            Some((shared, swap)) => (units.memory(shared), units.memory(swap)),
+            None => (unknown(), unknown()),
+        };
+        let command = match process.cmd() {
+            [] => unknown(),
+            cmd => cmd.join(" "),
+        };
+        let fields = vec![
+            ("Command", command),
+            ("Executable", path(process.exe())),
+            ("Working dir", path(process.cwd())),
+            ("User", user),
+            ("Status", process.status().to_string()),
+            (
+                "Parent PID",
+                process.parent().map_or_else(unknown, |pid| pid.to_string()),
+            ),
+            ("Started", format_timestamp(process.start_time())),
+            ("Run time", report::format_duration(process.run_time())),
+            (
+                "Threads",
+                process
+                    .tasks()
+                    .map_or_else(unknown, |tasks| tasks.len().max(1).to_string()),
+            ),
+            ("Resident mem", units.memory(process.memory())),
+            ("Virtual mem", units.memory(process.virtual_memory())),
+            ("Shared mem", shared),
+            ("Swap", swap),
+            ("Disk read", disk_total(disk.total_read_bytes)),
+            ("Disk written", disk_total(disk.total_written_bytes)),
+        ];
+        Some(Self {
+            pid,
+            start_time: process.start_time(),
+            name: process.name().to_string(),
+            fields,
+            environ: process
+                .environ()
+                .iter()
+                .map(|variable| Variable {
+                    text: variable.clone(),
+                    hidden: None,
+                })
+                .collect(),
+            files: fds::open(pid.as_u32(), &fds::sockets()),
+            threads: Threads::new(pid.as_u32()),
+        })
+    }
+
+    /// Hides the secrets `redactor` finds in the command line, and notes those of the environment
+    /// for the Environment tab to hide until they are revealed.
+    pub fn redact(&mut self, redactor: &Redactor) {
+        for (label, value) in &mut self.fields {
+            if *label == "Command" {
+                *value = redactor.apply(value);
+            }
+        }
+        for variable in &mut self.environ {
+            let hidden = redactor.apply_variable(&variable.text);
+            variable.hidden = (hidden != variable.text).then_some(hidden);
+        }
+    }
+
+    pub fn key(&self) -> ProcessKey {
+        ProcessKey {
+            pid: self.pid.as_u32(),
+            start_time: self.start_time,
+        }
+    }
+}
+
+/// Seconds since the epoch as a UTC date and time.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (date, seconds) = utc(timestamp);
+    format!(
+        "{date} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Seconds since the epoch as a UTC date and time to the minute, for the table.
+pub fn format_minute(timestamp: u64) -> String {
+    let (date, seconds) = utc(timestamp);
+    format!("{date} {:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+/// A duration by its two largest units, e.g. `3d 4h`, `5h 12m`, `7m 30s` or `42s`.
+pub fn format_elapsed(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86_400, seconds / 3600 % 24, seconds / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{seconds}s"),
+        (0, 0, _) => format!("{minutes}m {}s", seconds % 60),
+        (0, _, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+/// The UTC date of `timestamp`, and the seconds since midnight.
+fn utc(timestamp: u64) -> (String, u64) {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (format!("{year}-{month:02}-{day:02}"), seconds)
+}
@@ -0,0 +1,138 @@
+//! Comparison of two session recordings (`syskill diff`).
+//!
+//! PIDs are not stable between sessions, so processes are matched by name. For every name the
+//! CPU and memory of all its instances are summed per snapshot and averaged over the session.
+
+use crate::snapshot::Snapshot;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+const MIB: u64 = 1024 * 1024;
+
+pub struct DiffOptions {
+    /// Minimum change in average CPU usage, in percentage points.
+    pub min_cpu_delta: f32,
+    /// Minimum change in average memory, as a percentage of the larger of the two averages.
+    pub min_memory_delta_pct: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProcessDelta {
+    pub name: String,
+    /// Average CPU usage in the first recording, `None` if the process never appeared.
+    pub cpu_a: Option<f32>,
+    pub cpu_b: Option<f32>,
+    pub memory_a: Option<u64>,
+    pub memory_b: Option<u64>,
+    pub cpu_delta: f32,
+    pub memory_delta: i64,
+}
+
+#[derive(Default)]
+struct Average {
+    cpu: f32,
+    memory: u64,
+}
+
+fn averages(session: &[Snapshot]) -> BTreeMap<String, Average> {
+    let mut totals: BTreeMap<String, (f64, u128)> = BTreeMap::new();
+    for snapshot in session {
+        for process in &snapshot.processes {
+            let total = totals.entry(process.name.clone()).or_default();
+            total.0 += f64::from(process.cpu_usage);
+            total.1 += u128::from(process.memory);
+        }
+    }
+
+    // Snapshots in which a process is absent count as zero usage.
+    let samples = session.len().max(1);
+    totals
+        .into_iter()
+        .map(|(name, (cpu, memory))| {
+            let average = Average {
+                cpu: (cpu / samples as f64) as f32,
+                memory: (memory / samples as u128) as u64,
+            };
+            (name, average)
+        })
+        .collect()
+}
+
+/// Returns the processes whose average usage differs materially between `a` and `b`, largest
+/// CPU change first.
+pub fn compare(a: &[Snapshot], b: &[Snapshot], options: &DiffOptions) -> Vec<ProcessDelta> {
+    let averages_a = averages(a);
+    let averages_b = averages(b);
+    let names: BTreeSet<&String> = averages_a.keys().chain(averages_b.keys()).collect();
+
+    let mut deltas: Vec<ProcessDelta> = names
+        .into_iter()
+        .filter_map(|name| {
+            let before = averages_a.get(name);
+            let after = averages_b.get(name);
+            let cpu_before = before.map_or(0.0, |avg| avg.cpu);
+            let cpu_after = after.map_or(0.0, |avg| avg.cpu);
+            let memory_before = before.map_or(0, |avg| avg.memory);
+            let memory_after = after.map_or(0, |avg| avg.memory);
+
+            let cpu_delta = cpu_after - cpu_before;
+            let memory_delta = memory_after as i64 - memory_before as i64;
+            let memory_base = memory_before.max(memory_after) as f64;
+
+            let cpu_changed = cpu_delta.abs() >= options.min_cpu_delta;
+            let memory_changed = memory_delta.unsigned_abs() >= MIB
+                && memory_delta.unsigned_abs() as f64 * 100.0 / memory_base
+                    >= options.min_memory_delta_pct;
+
+            (cpu_changed || memory_changed).then(|| ProcessDelta {
+                name: name.clone(),
+                cpu_a: before.map(|avg| avg.cpu),
+                cpu_b: after.map(|avg| avg.cpu),
+                memory_a: before.map(|avg| avg.memory),
+                memory_b: after.map(|avg| avg.memory),
+                cpu_delta,
+                memory_delta,
+            })
+        })
+        .collect();
+
+    deltas.sort_by(|x, y| {
+        y.cpu_delta
+            .abs()
+            .total_cmp(&x.cpu_delta.abs())
+            .then(y.memory_delta.abs().cmp(&x.memory_delta.abs()))
+    });
+    deltas
+}
+
+fn format_mib(bytes: Option<u64>) -> String {
+    bytes.map_or("-".to_string(), |b| format!("{:.1}", b as f64 / MIB as f64))
+}
+
+fn format_cpu(cpu: Option<f32>) -> String {
+    cpu.map_or("-".to_string(), |c| format!("{c:.1}"))
+}
+
+pub fn print_table(deltas: &[ProcessDelta]) {
+    if deltas.is_empty() {
+        println!("No material differences between the two recordings.");
+        return;
+    }
+
+    println!(
+        "{:<25} {:>8} {:>8} {:>8} {:>11} {:>11} {:>11}",
+        "NAME", "CPU A", "CPU B", "ΔCPU", "MEM A MiB", "MEM B MiB", "ΔMEM MiB"
+    );
+    for delta in deltas {
+        println!(
+            "{:<25} {:>8} {:>8} {:>+8.1} {:>11} {:>11} {:>+11.1}",
+            delta.name,
+            format_cpu(delta.cpu_a),
+            format_cpu(delta.cpu_b),
+            delta.cpu_delta,
+            format_mib(delta.memory_a),
+            format_mib(delta.memory_b),
+            delta.memory_delta as f64 / MIB as f64,
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! The processes that started and exited between two refreshes, as the proc connector reported
+//! them (see [`crate::connector`]): crash loops and fork storms that polling never sees.
+//!
+//! They are summed up by command over the last minute, the most frequent first, with how often
+//! they ran and how long they lived.
+
+use crate::connector::ShortLived;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How far back the short-lived processes are summed up.
+pub const WINDOW: Duration = Duration::from_secs(60);
+
+/// The short-lived processes of one command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Command {
+    pub command: String,
+    pub count: usize,
+    /// Runs per second over the window, or over the time since listening if that is shorter.
+    pub rate: f64,
+    pub average_lifetime: Duration,
+    pub last_exited: Instant,
+}
+
+#[derive(Default)]
+pub struct Ephemeral {
+    /// `None` where the connector is not listened to, so nothing short-lived can be seen.
+    pub since: Option<Instant>,
+    /// The most frequent first.
+    pub commands: Vec<Command>,
+    /// How many short-lived processes there were in the window.
+    pub total: usize,
+    pub scroll: usize,
+}
+
+impl Ephemeral {
+    /// Sums up the processes in `exits` that exited within the window before `now`, with each
+    /// command passed through `redact`.
+    pub fn observe(
+        &mut self,
+        exits: &[ShortLived],
+        since: Option<Instant>,
+        now: Instant,
+        redact: impl Fn(&str) -> String,
+    ) {
+        self.since = since;
+        let span = since.map_or(WINDOW, |since| now.duration_since(since).min(WINDOW));
+        let recent = exits
+            .iter()
+            .filter(|exit| now.duration_since(exit.exited) < WINDOW);
+        let mut sums: HashMap<&str, (usize, Duration, Instant)> = HashMap::new();
+        self.total = 0;
+        for exit in recent {
+            let (count, lifetimes, last) =
+                sums.entry(&exit.command)
+                    .or_insert((0, Duration::ZERO, exit.exited));
+            *count += 1;
+            *lifetimes += exit.lifetime;
+            *last = (*last).max(exit.exited);
+            self.total += 1;
+        }
+        self.commands = sums
+            .into_iter()
+            .map(|(command, (count, lifetimes, last_exited))| Command {
+                command: redact(command),
+                count,
+                rate: count as f64 / span.as_secs_f64().max(1.0),
+                average_lifetime: lifetimes / count as u32,
+                last_exited,
+            })
+            .collect();
+        self.commands.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.last_exited.cmp(&a.last_exited))
+                .then_with(|| a.command.cmp(&b.command))
+        });
+        self.scroll = self.scroll.min(self.commands.len().saturating_sub(1));
+    }
+
+    pub fn scroll_by(&mut self, step: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(step)
+            .min(self.commands.len().saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_lived_processes_are_summed_up_by_command() {
+        let start = Instant::now();
+        let now = start + Duration::from_secs(100);
+        let exit = |command: &str, ago: u64, lifetime_ms: u64| ShortLived {
+            exited: now - Duration::from_secs(ago),
+            command: command.to_string(),
+            lifetime: Duration::from_millis(lifetime_ms),
+        };
+        let mut ephemeral = Ephemeral::default();
+        ephemeral.observe(
+            &[
+                // Before the window.
+                exit("cron-job", 90, 10),
+                exit("curl --token=abc https://example.com", 50, 300),
+                exit("sh -c true", 40, 4),
+                exit("sh -c true", 30, 6),
+                exit("sh -c true", 20, 2),
+                exit("curl --token=abc https://example.com", 10, 100),
+            ],
+            Some(start),
+            now,
+            |command| command.replace("abc", "***"),
+        );
+        assert_eq!(ephemeral.total, 5);
+        let summary: Vec<_> = ephemeral
+            .commands
+            .iter()
+            .map(|command| {
+                (
+                    command.command.as_str(),
+                    command.count,
+                    command.average_lifetime.as_millis(),
+                    now.duration_since(command.last_exited).as_secs(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("sh -c true", 3, 4, 20),
+                ("curl --token=*** https://example.com", 2, 200, 10)
+            ]
+        );
+        assert_eq!(ephemeral.commands[0].rate, 3.0 / 60.0);
+
+        // Listening only for the last 30 seconds.
+        ephemeral.observe(
+            &[exit("sh -c true", 5, 1)],
+            Some(now - Duration::from_secs(30)),
+            now,
+            str::to_string,
+        );
+        assert_eq!(ephemeral.commands[0].rate, 1.0 / 30.0);
+    }
+}
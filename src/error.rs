@@ -0,0 +1,103 @@
+//! Errors of the interactive table. They are shown on the status line below the table instead of
+//! crashing out of raw mode.
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The table is empty, e.g. after a search without matches.
+    NoSelection,
+    /// A PID in the table that is not a number, e.g. from a damaged recording.
+    InvalidPid(String),
+    /// The connection to the server of an attached session.
+    Remote(io::Error),
+    /// Input that was rejected, or a process that is no longer the one shown, with the reason.
+    Invalid(String),
+    /// Changing the scheduling policy failed, typically for lack of permissions.
+    Scheduling(io::Error),
+    /// Changing the I/O scheduling class failed, typically for lack of permissions.
+    IoPriority(io::Error),
+    /// Capping the CPU usage failed, typically for lack of permissions or cgroup support.
+    Throttling(io::Error),
+    /// Limiting the memory failed, typically for lack of permissions or cgroup support.
+    MemoryLimit(io::Error),
+    /// Changing the nice value failed, typically for lack of permissions.
+    Priority(io::Error),
+    /// Changing the CPU affinity failed, typically for lack of permissions.
+    Affinity(io::Error),
+    /// Writing the settings file failed, with the reason.
+    Settings(String),
+    /// A signal was not delivered, e.g. because the process belongs to another user.
+    Signal(crate::proc::KillError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoSelection => write!(f, "No process selected"),
+            Error::InvalidPid(pid) => write!(f, "Invalid PID {pid:?}"),
+            Error::Remote(e) => write!(f, "Lost connection to server: {e}"),
+            Error::Invalid(reason) => write!(f, "{reason}"),
+            Error::Scheduling(e) => write!(f, "Cannot change the scheduling policy: {e}"),
+            Error::IoPriority(e) => {
+                write!(f, "Cannot change the I/O priority: {e}")?;
+                if e.kind() == io::ErrorKind::PermissionDenied {
+                    write!(
+                        f,
+                        " (the realtime class, or another user's process, takes root or \
+                         CAP_SYS_NICE)"
+                    )?;
+                }
+                Ok(())
+            }
+            Error::Throttling(e) => write!(f, "Cannot cap the CPU usage: {e}"),
+            Error::MemoryLimit(e) => write!(f, "Cannot limit the memory: {e}"),
+            Error::Priority(e) => {
+                write!(f, "Cannot change the priority: {e}")?;
+                let denied = e.kind() == io::ErrorKind::PermissionDenied;
+                if denied && cfg!(windows) {
+                    write!(
+                        f,
+                        " (the realtime class, or another user's process, takes an administrator)"
+                    )?;
+                } else if denied {
+                    write!(
+                        f,
+                        " (raising it, or changing another user's process, takes root or \
+                         CAP_SYS_NICE)"
+                    )?;
+                }
+                Ok(())
+            }
+            Error::Affinity(e) => {
+                write!(f, "Cannot change the CPU affinity: {e}")?;
+                if e.kind() == io::ErrorKind::PermissionDenied {
+                    write!(
+                        f,
+                        " (changing another user's process takes root or CAP_SYS_NICE)"
+                    )?;
+                }
+                Ok(())
+            }
+            Error::Settings(e) => write!(f, "Cannot save the settings: {e}"),
+            Error::Signal(e) => {
+                // Lowercase for the command line, where it follows `syskill: `.
+                let mut message = e.message.chars();
+                if let Some(first) = message.next() {
+                    write!(f, "{}{}", first.to_uppercase(), message.as_str())?;
+                }
+                if e.denied && cfg!(windows) {
+                    write!(f, " (ending another user's process takes an administrator)")?;
+                } else if e.denied {
+                    write!(f, " (signalling another user's process takes root)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
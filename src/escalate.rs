@@ -0,0 +1,428 @@
+//! Escalating kill: SIGTERM first, SIGKILL if the process is still running after its grace
+//! period (see [`crate::config::Config::grace_period`]).
+//!
+//! The SIGKILL is sent again for a few seconds while the process holds out, and the kills still
+//! waiting for it are kept in a file next to the log files, one per running syskill, so that one
+//! cut short by syskill quitting or crashing is taken up again when it starts next.
+
+use crate::snapshot::ProcessKey;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, Signal, System};
+
+/// How long SIGKILL is sent again while the process is still running.
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pending kills of an earlier session are dropped once their SIGKILL is this overdue.
+pub const RESUMED_WITHIN: Duration = Duration::from_secs(60 * 60);
+
+/// What became of an escalating kill: [`Outcome::TermSent`] once SIGTERM is delivered, then how
+/// it ended.
+pub enum Outcome {
+    /// SIGTERM was delivered; one of the other outcomes follows.
+    TermSent { pid: Pid, name: String },
+    /// The process exited on SIGTERM.
+    Exited {
+        pid: Pid,
+        name: String,
+        after: Duration,
+    },
+    /// The process ignored SIGTERM and was killed.
+    Killed { pid: Pid, name: String },
+    /// The process was still running [`KILL_TIMEOUT`] after SIGKILL, typically stuck in
+    /// uninterruptible I/O.
+    Survived { pid: Pid, name: String },
+    /// SIGKILL was called off before the grace period ran out.
+    Cancelled { pid: Pid, name: String },
+    /// `signal` could not be sent, so the escalation ended there.
+    Failed {
+        pid: Pid,
+        signal: Signal,
+        message: String,
+    },
+}
+
+impl Outcome {
+    /// Whether the escalation is over.
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Outcome::TermSent { .. })
+    }
+}
+
+/// Sends SIGTERM to the process of `key`, unless `term_sent_before` says an earlier session did,
+/// and SIGKILL once `grace` has passed, unless `cancelled` is set by then, on a background thread
+/// reporting each step.
+pub fn start(
+    key: ProcessKey,
+    name: String,
+    grace: Duration,
+    term_sent_before: bool,
+    cancelled: Arc<AtomicBool>,
+) -> Receiver<Outcome> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut system = System::new();
+        let term_sent = |pid, name| {
+            let _ = sender.send(Outcome::TermSent { pid, name });
+        };
+        let outcome = escalate(
+            &mut system,
+            key,
+            name,
+            grace,
+            (!term_sent_before).then_some(&term_sent),
+            &cancelled,
+        );
+        let _ = sender.send(outcome);
+    });
+    receiver
+}
+
+/// Whether the process of `key` is running, refreshed in `system`.
+pub fn is_running(system: &mut System, key: ProcessKey) -> bool {
+    let pid = Pid::from_u32(key.pid);
+    // An exited process lingers as a zombie until its parent reaps it, and a process started
+    // since may have been given its PID.
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new())
+        && system.process(pid).is_some_and(|process| {
+            process.status() != ProcessStatus::Zombie
+                && (key.start_time == 0 || process.start_time() == key.start_time)
+        })
+}
+
+/// Escalates the kill of the process of `key`, sending SIGTERM first and telling `term_sent` it
+/// was delivered, unless there is no `term_sent` since an earlier session sent it.
+fn escalate(
+    system: &mut System,
+    key: ProcessKey,
+    name: String,
+    grace: Duration,
+    term_sent: Option<&dyn Fn(Pid, String)>,
+    cancelled: &AtomicBool,
+) -> Outcome {
+    let pid = Pid::from_u32(key.pid);
+    let running = |system: &mut System| is_running(system, key);
+    let failed = |signal, message| Outcome::Failed {
+        pid,
+        signal,
+        message,
+    };
+    if !running(system) {
+        // Resumed, the SIGTERM was recorded by the session that sent it; only SIGKILL is left.
+        let signal = if term_sent.is_some() {
+            Signal::Term
+        } else {
+            Signal::Kill
+        };
+        return failed(signal, format!("no process with PID {pid}"));
+    }
+    if let Some(term_sent) = term_sent {
+        match system.process(pid).and_then(|p| p.kill_with(Signal::Term)) {
+            Some(true) => term_sent(pid, name.clone()),
+            Some(false) => {
+                return failed(
+                    Signal::Term,
+                    format!("failed to send TERM to {pid} ({name})"),
+                )
+            }
+            None => {
+                return failed(
+                    Signal::Term,
+                    "TERM is not supported on this platform".to_string(),
+                )
+            }
+        }
+    }
+
+    let started = Instant::now();
+    while started.elapsed() < grace {
+        thread::sleep(Duration::from_millis(200));
+        if cancelled.load(Ordering::SeqCst) {
+            return Outcome::Cancelled { pid, name };
+        }
+        if !running(system) {
+            return Outcome::Exited {
+                pid,
+                name,
+                after: started.elapsed(),
+            };
+        }
+    }
+    if cancelled.load(Ordering::SeqCst) {
+        return Outcome::Cancelled { pid, name };
+    }
+    if !running(system) {
+        return Outcome::Exited {
+            pid,
+            name,
+            after: started.elapsed(),
+        };
+    }
+    if !system.process(pid).is_some_and(|process| process.kill()) {
+        return failed(Signal::Kill, format!("failed to kill {pid} ({name})"));
+    }
+    // The signal is only acted on once the process leaves the kernel, so one stuck in a system
+    // call may take a while, and the next attempt may catch it.
+    let killed = Instant::now();
+    let mut sent = killed;
+    while running(system) {
+        if killed.elapsed() >= KILL_TIMEOUT {
+            return Outcome::Survived { pid, name };
+        }
+        if sent.elapsed() >= Duration::from_secs(1) {
+            system.process(pid).map(|process| process.kill());
+            sent = Instant::now();
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    Outcome::Killed { pid, name }
+}
+
+/// A kill waiting for its SIGKILL, as kept across sessions.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pending {
+    pub pid: u32,
+    pub start_time: u64,
+    pub name: String,
+    /// Seconds since the Unix epoch when SIGKILL is due.
+    pub due: u64,
+}
+
+impl Pending {
+    pub fn key(&self) -> ProcessKey {
+        ProcessKey {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+}
+
+/// Where the pending kills of this syskill are kept; `None` without a home directory. Each
+/// running syskill has a file of its own, so that they do not overwrite each other's kills.
+pub fn pending_path() -> Option<PathBuf> {
+    let directory = crate::logging::directory()?;
+    Some(directory.join(format!("pending-kills-{}.json", std::process::id())))
+}
+
+/// The PID of the syskill that keeps its pending kills in `path`.
+fn kept_by(path: &Path) -> Option<Pid> {
+    let name = path.file_name()?.to_str()?;
+    let pid = name.strip_prefix("pending-kills-")?.strip_suffix(".json")?;
+    pid.parse().ok().map(Pid::from_u32)
+}
+
+/// The files of pending kills in `directory` left by syskills that are no longer running, but
+/// for which a process of another name may now run under their PID.
+pub fn abandoned(system: &mut System, directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let own = Pid::from_u32(std::process::id());
+    system.refresh_process_specifics(own, ProcessRefreshKind::new());
+    let own_name = system
+        .process(own)
+        .map(|process| process.name().to_string());
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let Some(pid) = kept_by(path) else {
+                return false;
+            };
+            pid != own
+                && !(system.refresh_process_specifics(pid, ProcessRefreshKind::new())
+                    && system
+                        .process(pid)
+                        .map(|process| process.name().to_string())
+                        == own_name)
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Takes the pending kills out of `path`, left by another syskill, unless another one took them
+/// first.
+pub fn take_over(path: &Path) -> io::Result<Vec<Pending>> {
+    // Moved aside first, so that two syskills starting together do not both resume them.
+    let claimed = path.with_extension(format!("json.{}", std::process::id()));
+    match std::fs::rename(path, &claimed) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        result => result?,
+    }
+    let pending = load_pending(&claimed);
+    std::fs::remove_file(&claimed)?;
+    pending
+}
+
+/// Writes `pending` to `path`, or removes the file once nothing is pending.
+pub fn save_pending(path: &Path, pending: &[Pending]) -> io::Result<()> {
+    if pending.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        };
+    }
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let json = serde_json::to_string_pretty(pending)?;
+    // Replaced in one go, so a crash while writing leaves the previous list.
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, json)?;
+    std::fs::rename(partial, path)
+}
+
+/// The pending kills written to `path`; none if there is no such file.
+pub fn load_pending(path: &Path) -> io::Result<Vec<Pending>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sigterm_is_reported_only_once_delivered() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let key = ProcessKey {
+            pid: 5_000_000,
+            start_time: 1,
+        };
+        let outcomes = start(key, "gone".to_string(), Duration::ZERO, false, cancelled);
+        assert!(matches!(
+            outcomes.recv().unwrap(),
+            Outcome::Failed {
+                signal: Signal::Term,
+                ..
+            }
+        ));
+        assert!(outcomes.recv().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sigterm_is_reported_before_the_process_exits() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = Pid::from_u32(child.id());
+        let mut system = System::new();
+        system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+        let key = ProcessKey {
+            pid: child.id(),
+            start_time: system.process(pid).unwrap().start_time(),
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let grace = Duration::from_secs(30);
+        let outcomes = start(key, "sleep".to_string(), grace, false, cancelled);
+        assert!(matches!(
+            outcomes.recv().unwrap(),
+            Outcome::TermSent { pid: sent, .. } if sent == pid
+        ));
+        child.wait().unwrap();
+        assert!(matches!(outcomes.recv().unwrap(), Outcome::Exited { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resumed_kills_send_no_sigterm() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = Pid::from_u32(child.id());
+        let mut system = System::new();
+        system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+        let key = ProcessKey {
+            pid: child.id(),
+            start_time: system.process(pid).unwrap().start_time(),
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let grace = Duration::from_secs(30);
+        let outcomes = start(
+            key,
+            "sleep".to_string(),
+            grace,
+            true,
+            Arc::clone(&cancelled),
+        );
+        thread::sleep(Duration::from_millis(300));
+        cancelled.store(true, Ordering::SeqCst);
+        assert!(matches!(
+            outcomes.recv().unwrap(),
+            Outcome::Cancelled { .. }
+        ));
+        assert!(child.try_wait().unwrap().is_none());
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let gone = ProcessKey {
+            pid: 5_000_000,
+            start_time: 1,
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let outcomes = start(gone, "gone".to_string(), grace, true, cancelled);
+        assert!(matches!(
+            outcomes.recv().unwrap(),
+            Outcome::Failed {
+                signal: Signal::Kill,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn pending_kills_are_kept_until_none_is_left() {
+        let path =
+            std::env::temp_dir().join(format!("syskill-pending-{}.json", std::process::id()));
+        let pending = vec![Pending {
+            pid: 5_000_000,
+            start_time: 1_760_623_402,
+            name: "worker".to_string(),
+            due: 1_760_623_412,
+        }];
+        save_pending(&path, &pending).unwrap();
+        assert_eq!(load_pending(&path).unwrap(), pending);
+        save_pending(&path, &[]).unwrap();
+        assert!(!path.exists());
+        assert!(load_pending(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pending_kills_of_ended_sessions_are_taken_over() {
+        let directory =
+            std::env::temp_dir().join(format!("syskill-abandoned-{}", std::process::id()));
+        let pending = |pid| Pending {
+            pid,
+            start_time: 1_760_623_402,
+            name: "worker".to_string(),
+            due: 1_760_623_412,
+        };
+        let own = directory.join(format!("pending-kills-{}.json", std::process::id()));
+        let ended = directory.join("pending-kills-5000001.json");
+        save_pending(&own, &[pending(5_000_000)]).unwrap();
+        save_pending(&ended, &[pending(5_000_002)]).unwrap();
+        save_pending(&directory.join("other.json"), &[pending(5_000_003)]).unwrap();
+
+        let mut system = System::new();
+        assert_eq!(
+            abandoned(&mut system, &directory),
+            std::slice::from_ref(&ended)
+        );
+        assert_eq!(take_over(&ended).unwrap(), [pending(5_000_002)]);
+        assert!(take_over(&ended).unwrap().is_empty());
+        assert!(abandoned(&mut system, &directory).is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
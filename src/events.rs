@@ -0,0 +1,185 @@
+//! Machine-readable event stream (`--events`).
+//!
+//! Every observation syskill makes is written as one JSON object per line, tagged with `event`:
+//! `process_started`, `process_exited`, `threshold_crossed` (CPU usage rising above or falling
+//! below the critical threshold), `killed` and `oom_killed` (by the kernel, when it ran out of
+//! memory).
+
+use crate::snapshot::{unix_now, Fields, ProcessKey, ProcessSample, Snapshot};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    ProcessStarted {
+        timestamp: u64,
+        pid: u32,
+        name: &'a str,
+    },
+    ProcessExited {
+        timestamp: u64,
+        pid: u32,
+        name: &'a str,
+    },
+    ThresholdCrossed {
+        timestamp: u64,
+        pid: u32,
+        name: &'a str,
+        metric: &'static str,
+        direction: &'static str,
+        value: f32,
+        threshold: f32,
+    },
+    Killed {
+        timestamp: u64,
+        pid: u32,
+        name: &'a str,
+        signal: String,
+    },
+    OomKilled {
+        timestamp: u64,
+        pid: u32,
+        name: &'a str,
+        /// Bytes of anonymous memory the process held.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        anon_rss: Option<u64>,
+    },
+}
+
+/// Turns consecutive snapshots into events and writes them out.
+pub struct EventLog {
+    writer: Box<dyn Write + Send>,
+    cpu_threshold: f32,
+    /// Processes of the previous snapshot: name and whether CPU usage was above the threshold.
+    /// A PID reused in between counts as one process exiting and another starting.
+    known: Option<HashMap<ProcessKey, (String, bool)>>,
+}
+
+impl EventLog {
+    /// Appends to `path`, or writes to stdout for `-`.
+    pub fn open(path: &Path, cpu_threshold: f32) -> io::Result<Self> {
+        let writer: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(BufWriter::new(
+                File::options().create(true).append(true).open(path)?,
+            ))
+        };
+        Ok(Self {
+            writer,
+            cpu_threshold,
+            known: None,
+        })
+    }
+
+    fn emit(&mut self, event: &Event) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    /// Compares `snapshot` with the previous one. The first snapshot only sets the baseline.
+    pub fn observe(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let timestamp = snapshot.timestamp;
+        let previous = self.known.take();
+        let mut known = HashMap::with_capacity(snapshot.processes.len());
+
+        for process in &snapshot.processes {
+            let ProcessSample {
+                pid,
+                name,
+                cpu_usage,
+                ..
+            } = process;
+            let above = *cpu_usage >= self.cpu_threshold;
+            known.insert(process.key(), (name.clone(), above));
+            let Some(previous) = &previous else {
+                continue;
+            };
+            let (pid, name) = (*pid, name.as_str());
+            match previous.get(&process.key()) {
+                None => self.emit(&Event::ProcessStarted {
+                    timestamp,
+                    pid,
+                    name,
+                })?,
+                Some(&(_, was_above)) if was_above != above => {
+                    self.emit(&Event::ThresholdCrossed {
+                        timestamp,
+                        pid,
+                        name,
+                        metric: "cpu",
+                        direction: if above { "above" } else { "below" },
+                        value: *cpu_usage,
+                        threshold: self.cpu_threshold,
+                    })?
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (key, (name, _)) in previous.iter().flatten() {
+            if !known.contains_key(key) {
+                self.emit(&Event::ProcessExited {
+                    timestamp,
+                    pid: key.pid,
+                    name,
+                })?;
+            }
+        }
+        self.known = Some(known);
+        Ok(())
+    }
+
+    pub fn killed(&mut self, pid: u32, name: &str, signal: sysinfo::Signal) -> io::Result<()> {
+        self.emit(&Event::Killed {
+            timestamp: unix_now(),
+            pid,
+            name,
+            signal: crate::signal::name(signal),
+        })
+    }
+
+    pub fn oom_killed(&mut self, pid: u32, name: &str, anon_rss: Option<u64>) -> io::Result<()> {
+        self.emit(&Event::OomKilled {
+            timestamp: unix_now(),
+            pid,
+            name,
+            anon_rss,
+        })
+    }
+}
+
+/// Streams events without the interactive table until interrupted.
+pub fn run(
+    path: &Path,
+    interval: std::time::Duration,
+    cpu_threshold: f32,
+    user: Option<crate::users::UserFilter>,
+) -> io::Result<()> {
+    let mut log = EventLog::open(path, cpu_threshold)?;
+    let oom_kills = crate::oom::watch();
+    // Events are about processes coming and going and their CPU usage, nothing costly.
+    let fields = std::sync::Arc::new(std::sync::Mutex::new(Fields::NONE));
+    let source = crate::source::SystemSource::new(user);
+    let live = crate::collector::spawn(
+        source,
+        Some(interval),
+        fields,
+        crate::collector::Idle::default(),
+    );
+    loop {
+        let update = live.next();
+        for kill in oom_kills.iter().flat_map(|receiver| receiver.try_iter()) {
+            log.oom_killed(kill.pid, &kill.name, kill.anon_rss)?;
+        }
+        match update {
+            Ok(snapshot) => log.observe(&snapshot)?,
+            Err(e) => eprintln!("syskill: {e}, retrying"),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! The processes that exited while the table was open, with how long they lived and the most CPU
+//! and memory they were seen using, so a short-lived culprit can still be looked at once it is
+//! gone from the table.
+
+use crate::app::ProcessInfo;
+use crate::fuzzy;
+use crate::proc::ProcessKey;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// How many exits the log keeps, the latest last.
+const KEPT_EXITS: usize = 1000;
+
+/// What is known of a running process by the time it exits.
+struct Seen {
+    name: String,
+    user: String,
+    start_time: u64,
+    peak_cpu: f32,
+    peak_memory: u64,
+}
+
+pub struct Exit {
+    pub key: ProcessKey,
+    pub name: String,
+    pub user: String,
+    /// Seconds from the start of the process to when it was found gone; `None` if its start
+    /// time is unknown.
+    pub lifetime: Option<u64>,
+    /// In percent of one core.
+    pub peak_cpu: f32,
+    /// In bytes.
+    pub peak_memory: u64,
+    /// Seconds since the Unix epoch when it was found gone.
+    pub detected: u64,
+    /// How it ended, if known, e.g. `killed by SIGSEGV`.
+    pub reason: Option<String>,
+}
+
+/// The order of the exit log.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum ExitSort {
+    /// The latest first.
+    #[default]
+    Detected,
+    Name,
+    Lifetime,
+    Cpu,
+    Memory,
+}
+
+impl ExitSort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Detected => Self::Name,
+            Self::Name => Self::Lifetime,
+            Self::Lifetime => Self::Cpu,
+            Self::Cpu => Self::Memory,
+            Self::Memory => Self::Detected,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Detected => "latest first",
+            Self::Name => "by name",
+            Self::Lifetime => "shortest-lived first",
+            Self::Cpu => "by peak CPU",
+            Self::Memory => "by peak memory",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Exits {
+    running: HashMap<ProcessKey, Seen>,
+    log: Vec<Exit>,
+}
+
+impl Exits {
+    /// Takes note of the processes in a new snapshot, and returns those of the last one that are
+    /// gone from it, for the caller to add with how they ended.
+    pub fn observe(&mut self, processes: &[ProcessInfo], now: u64) -> Vec<Exit> {
+        let mut running = HashMap::with_capacity(processes.len());
+        for data in processes {
+            if data.exited.is_some() || data.members > 0 {
+                continue;
+            }
+            let key = data.key();
+            let (cpu, memory) = (data.cpu_usage, data.memory);
+            let seen = match self.running.remove(&key) {
+                Some(seen) => Seen {
+                    peak_cpu: seen.peak_cpu.max(cpu),
+                    peak_memory: seen.peak_memory.max(memory),
+                    ..seen
+                },
+                None => Seen {
+                    name: data.name.clone(),
+                    user: data.user.clone(),
+                    start_time: data.start_time,
+                    peak_cpu: cpu,
+                    peak_memory: memory,
+                },
+            };
+            running.insert(key, seen);
+        }
+        let gone = std::mem::replace(&mut self.running, running);
+        gone.into_iter()
+            .map(|(key, seen)| Exit {
+                key,
+                name: seen.name,
+                user: seen.user,
+                lifetime: (seen.start_time > 0).then(|| now.saturating_sub(seen.start_time)),
+                peak_cpu: seen.peak_cpu,
+                peak_memory: seen.peak_memory,
+                detected: now,
+                reason: None,
+            })
+            .collect()
+    }
+
+    pub fn record(&mut self, exit: Exit) {
+        if self.log.len() == KEPT_EXITS {
+            self.log.remove(0);
+        }
+        self.log.push(exit);
+    }
+
+    /// The exits whose name matches `filter` like a plain search, in the order of `sort`.
+    pub fn listed(&self, filter: &str, sort: ExitSort) -> Vec<&Exit> {
+        let mut listed: Vec<_> = self
+            .log
+            .iter()
+            .filter(|exit| filter.is_empty() || fuzzy::find(filter, &exit.name).is_some())
+            .collect();
+        match sort {
+            ExitSort::Detected => listed.reverse(),
+            ExitSort::Name => listed.sort_by(|a, b| a.name.cmp(&b.name)),
+            ExitSort::Lifetime => listed.sort_by_key(|exit| exit.lifetime.unwrap_or(u64::MAX)),
+            ExitSort::Cpu => listed.sort_by(|a, b| b.peak_cpu.total_cmp(&a.peak_cpu)),
+            ExitSort::Memory => listed.sort_by_key(|exit| Reverse(exit.peak_memory)),
+        }
+        listed
+    }
+
+    /// The exits found from `since` on, in seconds since the Unix epoch.
+    pub fn since(&self, since: u64) -> impl Iterator<Item = &Exit> {
+        self.log.iter().filter(move |exit| exit.detected >= since)
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+}
+
+/// E.g. `3s`, `4m 2s`, `2h 5m` or `3d 4h`.
+pub fn format_lifetime(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        3600..=86_399 => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+        _ => format!("{}d {}h", seconds / 86_400, seconds % 86_400 / 3600),
+    }
+}
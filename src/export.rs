@@ -0,0 +1,88 @@
+//! Exports of the process table as JSON or CSV, e.g. to attach to an incident ticket.
+//!
+//! A JSON export is a recording with a single snapshot, so two exports can be compared with
+//! `syskill diff`.
+
+use crate::app::ProcessInfo;
+use crate::query::Query;
+use crate::snapshot::Snapshot;
+use crate::units::Units;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// CSV for paths ending in `.csv`, JSON otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => ExportFormat::Csv,
+            _ => ExportFormat::Json,
+        }
+    }
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Columns of a CSV export, in order.
+const CSV_HEADER: &str = "pid,name,user,state,sched,cpu_usage,memory,parent,start_time,exe";
+
+pub fn render(snapshot: &Snapshot, format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string(snapshot)
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string()),
+        ExportFormat::Csv => {
+            let mut csv = format!("{CSV_HEADER}\n");
+            for p in &snapshot.processes {
+                let parent = p
+                    .parent
+                    .map(|parent| parent.to_string())
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},{},{},{},{},{parent},{},{}",
+                    p.pid,
+                    escape_csv(&p.name),
+                    escape_csv(&p.user),
+                    escape_csv(&p.state),
+                    escape_csv(&p.sched),
+                    p.cpu_usage,
+                    p.memory,
+                    p.start_time,
+                    escape_csv(&p.exe),
+                );
+            }
+            Ok(csv)
+        }
+    }
+}
+
+/// Drops the processes that do not match `search`, which is read like the search of the table.
+pub fn retain_matching(snapshot: &mut Snapshot, search: &str, units: Units) -> Result<(), String> {
+    let query = Query::parse(search)?;
+    snapshot
+        .processes
+        .retain(|sample| query.matches(&ProcessInfo::from(sample.clone()), units));
+    Ok(())
+}
+
+fn escape_csv(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
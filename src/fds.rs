@@ -0,0 +1,147 @@
+//! The open file descriptors of a process, from `/proc/<pid>/fd`, with its sockets described by
+//! what they connect as `/proc/net` lists them, like a small `lsof -p`. Linux only.
+
+use std::collections::HashMap;
+
+/// What a file descriptor points to.
+pub enum Target {
+    /// A path, e.g. `/var/log/syslog`.
+    File(String),
+    /// What the socket connects, e.g. `tcp 10.0.0.2:40312 -> 10.0.0.7:5432`, or `socket:[1234]`
+    /// for kinds `/proc/net` has no table of.
+    Socket(String),
+    /// E.g. `pipe:[5678]`.
+    Pipe(String),
+    /// Anonymous inodes such as `anon_inode:[eventfd]`, and the rest.
+    Other(String),
+}
+
+impl Target {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::File(_) => "file",
+            Self::Socket(_) => "socket",
+            Self::Pipe(_) => "pipe",
+            Self::Other(_) => "other",
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            Self::File(text) | Self::Socket(text) | Self::Pipe(text) | Self::Other(text) => text,
+        }
+    }
+}
+
+pub struct Descriptor {
+    pub fd: u32,
+    pub target: Target,
+}
+
+/// The descriptors `pid` has open, by number, with sockets looked up in `sockets`; `None` if
+/// they cannot be read, as for other users' processes without root.
+#[cfg(target_os = "linux")]
+pub fn open(pid: u32, sockets: &HashMap<String, String>) -> Option<Vec<Descriptor>> {
+    let fds = std::fs::read_dir(format!("/proc/{pid}/fd")).ok()?;
+    let mut descriptors = Vec::new();
+    for fd in fds.flatten() {
+        let Some(number) = fd.file_name().to_str().and_then(|name| name.parse().ok()) else {
+            continue;
+        };
+        let target = match std::fs::read_link(fd.path()) {
+            Ok(target) => target.to_string_lossy().into_owned(),
+            // The descriptors can be listed where what they point to cannot be read.
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => return None,
+            // Closed since the listing.
+            Err(_) => continue,
+        };
+        let target = match target.strip_prefix("socket:[") {
+            Some(rest) => Target::Socket(
+                rest.strip_suffix(']')
+                    .and_then(|inode| sockets.get(inode))
+                    .cloned()
+                    .unwrap_or(target),
+            ),
+            None if target.starts_with('/') => Target::File(target),
+            None if target.starts_with("pipe:") => Target::Pipe(target),
+            None => Target::Other(target),
+        };
+        descriptors.push(Descriptor { fd: number, target });
+    }
+    descriptors.sort_by_key(|descriptor| descriptor.fd);
+    Some(descriptors)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open(_pid: u32, _sockets: &HashMap<String, String>) -> Option<Vec<Descriptor>> {
+    None
+}
+
+/// How many descriptors `pid` has open, without reading where they point; `None` if unknown.
+#[cfg(target_os = "linux")]
+pub fn count(pid: u32) -> Option<u32> {
+    let fds = std::fs::read_dir(format!("/proc/{pid}/fd")).ok()?;
+    Some(fds.count() as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn count(_pid: u32) -> Option<u32> {
+    None
+}
+
+/// Socket inodes with what the sockets connect, e.g. `tcp 10.0.0.2:40312 -> 10.0.0.7:5432`,
+/// `tcp 0.0.0.0:80 listening` or `unix /run/docker.sock`.
+#[cfg(target_os = "linux")]
+pub fn sockets() -> HashMap<String, String> {
+    use crate::ports::decode_address;
+
+    const TCP_LISTEN: &str = "0A";
+
+    let endpoint = |hex: &str| match hex.split_once(':') {
+        Some((address, port)) => format!(
+            "{}:{}",
+            decode_address(address),
+            u16::from_str_radix(port, 16).unwrap_or_default()
+        ),
+        None => hex.to_string(),
+    };
+    let mut sockets = HashMap::new();
+    for (protocol, file) in [
+        ("tcp", "/proc/net/tcp"),
+        ("tcp6", "/proc/net/tcp6"),
+        ("udp", "/proc/net/udp"),
+        ("udp6", "/proc/net/udp6"),
+    ] {
+        let Ok(table) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (Some(local), Some(remote), Some(&state), Some(inode)) =
+                (fields.get(1), fields.get(2), fields.get(3), fields.get(9))
+            else {
+                continue;
+            };
+            let description = if protocol.starts_with("tcp") && state == TCP_LISTEN {
+                format!("{protocol} {} listening", endpoint(local))
+            } else {
+                format!("{protocol} {} -> {}", endpoint(local), endpoint(remote))
+            };
+            sockets.insert(inode.to_string(), description);
+        }
+    }
+    if let Ok(table) = std::fs::read_to_string("/proc/net/unix") {
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let (Some(inode), Some(path)) = (fields.get(6), fields.get(7)) {
+                sockets.insert(inode.to_string(), format!("unix {path}"));
+            }
+        }
+    }
+    sockets
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sockets() -> HashMap<String, String> {
+    HashMap::new()
+}
@@ -0,0 +1,78 @@
+//! fzf-style fuzzy matching for the search filter.
+//!
+//! A pattern matches when its characters appear in the text in order, not necessarily next to
+//! each other. Matching ignores case unless the pattern contains an uppercase letter. Among the
+//! possible alignments the one with the most consecutive characters and word starts is chosen,
+//! so the highlighted characters are the ones a user would expect.
+
+/// Bonus for every matched character.
+const MATCH: i32 = 16;
+/// Bonus for a character directly following the previous match.
+const CONSECUTIVE: i32 = 8;
+/// Bonus for a character at the start of the text or of a word.
+const WORD_START: i32 = 8;
+/// Penalty for every skipped character between two matches.
+const GAP: i32 = 1;
+
+/// Character positions in `text` matched by `pattern`, or `None` if it does not match. An empty
+/// pattern matches everything.
+pub fn find(pattern: &str, text: &str) -> Option<Vec<usize>> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let normalize = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let pattern: Vec<char> = pattern.chars().map(normalize).collect();
+    let text: Vec<char> = text.chars().collect();
+    let Some(&first) = pattern.first() else {
+        return Some(Vec::new());
+    };
+
+    let mut best: Option<(i32, Vec<usize>)> = None;
+    // Try every occurrence of the first character as the start and match greedily from there.
+    for start in (0..text.len()).filter(|&i| normalize(text[i]) == first) {
+        let mut positions = vec![start];
+        let mut i = start + 1;
+        for &wanted in &pattern[1..] {
+            while i < text.len() && normalize(text[i]) != wanted {
+                i += 1;
+            }
+            if i == text.len() {
+                break;
+            }
+            positions.push(i);
+            i += 1;
+        }
+        if positions.len() < pattern.len() {
+            // Later starts leave even less text to match.
+            break;
+        }
+        let score = score(&text, &positions);
+        if best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((score, positions));
+        }
+    }
+    best.map(|(_, positions)| positions)
+}
+
+fn score(text: &[char], positions: &[usize]) -> i32 {
+    let mut score = 0;
+    for (n, &i) in positions.iter().enumerate() {
+        score += MATCH;
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += WORD_START;
+        }
+        if n > 0 {
+            let previous = positions[n - 1];
+            if i == previous + 1 {
+                score += CONSECUTIVE;
+            } else {
+                score -= GAP * (i - previous - 1) as i32;
+            }
+        }
+    }
+    score
+}
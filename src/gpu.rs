@@ -0,0 +1,221 @@
+//! GPU usage per process: NVIDIA cards through NVML, with the `gpu` feature, and AMD cards from
+//! the DRM entries amdgpu adds to `/proc/<pid>/fdinfo` on Linux, such as
+//! `drm-engine-gfx: 3052810 ns` and `drm-memory-vram: 81920 KiB`.
+//!
+//! Utilization is the share of the last refresh a GPU spent on the process, so the first sample
+//! only sets the baseline.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use std::time::Instant;
+
+/// What a process uses of the GPUs, summed over all of them.
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+    /// Percent of the time a GPU was busy with the process; `None` until there are two samples.
+    pub utilization: Option<f32>,
+    /// Bytes of GPU memory.
+    pub memory: u64,
+}
+
+/// Kept from one sample to the next.
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+struct State {
+    #[cfg(feature = "gpu")]
+    nvml: Option<nvml_wrapper::Nvml>,
+    /// Timestamp of the newest NVML utilization sample read, in microseconds.
+    #[cfg(feature = "gpu")]
+    last_seen: u64,
+    /// Nanoseconds each engine was busy with each amdgpu client, by PID, client and engine.
+    #[cfg(target_os = "linux")]
+    busy: HashMap<(u32, String), u64>,
+    #[cfg(target_os = "linux")]
+    sampled: Option<Instant>,
+}
+
+/// The GPU usage of every process using a GPU, by PID; empty without GPUs to read.
+pub fn usage() -> HashMap<u32, Usage> {
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let state = state.get_or_insert_with(|| State {
+        // Fails where the NVIDIA driver is not installed.
+        #[cfg(feature = "gpu")]
+        nvml: nvml_wrapper::Nvml::init().ok(),
+        #[cfg(feature = "gpu")]
+        last_seen: 0,
+        #[cfg(target_os = "linux")]
+        busy: HashMap::new(),
+        #[cfg(target_os = "linux")]
+        sampled: None,
+    });
+    let mut usage = HashMap::new();
+    state.nvidia(&mut usage);
+    state.amd(&mut usage);
+    usage
+}
+
+impl State {
+    #[cfg(feature = "gpu")]
+    fn nvidia(&mut self, usage: &mut HashMap<u32, Usage>) {
+        use nvml_wrapper::enums::device::UsedGpuMemory;
+
+        let Some(nvml) = &self.nvml else {
+            return;
+        };
+        let mut newest = self.last_seen;
+        for index in 0..nvml.device_count().unwrap_or(0) {
+            let Ok(device) = nvml.device_by_index(index) else {
+                continue;
+            };
+            // A process can be both, with the same memory.
+            let mut memory = HashMap::new();
+            let processes = device
+                .running_compute_processes()
+                .unwrap_or_default()
+                .into_iter()
+                .chain(device.running_graphics_processes().unwrap_or_default());
+            for process in processes {
+                if let UsedGpuMemory::Used(bytes) = process.used_gpu_memory {
+                    memory.insert(process.pid, bytes);
+                }
+            }
+            for (pid, bytes) in memory {
+                usage.entry(pid).or_default().memory += bytes;
+            }
+            // Every sample since the last one read, several per process; the newest counts. None
+            // is an error.
+            let mut latest: HashMap<u32, (u64, u32)> = HashMap::new();
+            for sample in device
+                .process_utilization_stats(self.last_seen)
+                .unwrap_or_default()
+            {
+                let entry = latest.entry(sample.pid).or_default();
+                if sample.timestamp >= entry.0 {
+                    *entry = (sample.timestamp, sample.sm_util);
+                }
+                newest = newest.max(sample.timestamp);
+            }
+            for (pid, (_, percent)) in latest {
+                *usage.entry(pid).or_default().utilization.get_or_insert(0.0) += percent as f32;
+            }
+        }
+        self.last_seen = newest;
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    fn nvidia(&mut self, _usage: &mut HashMap<u32, Usage>) {}
+
+    #[cfg(target_os = "linux")]
+    fn amd(&mut self, usage: &mut HashMap<u32, Usage>) {
+        use std::fs;
+
+        let now = Instant::now();
+        let elapsed = self
+            .sampled
+            .map(|sampled| (now - sampled).as_nanos() as f32);
+        let mut busy = HashMap::new();
+        let Ok(processes) = fs::read_dir("/proc") else {
+            return;
+        };
+        for process in processes.flatten() {
+            let Some(pid) = process.file_name().to_str().and_then(|n| n.parse().ok()) else {
+                continue;
+            };
+            // Other users' descriptors take root to read.
+            let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+                continue;
+            };
+            // Descriptors opened by the same client, e.g. through dup, have the same figures.
+            let mut clients = HashMap::new();
+            for fd in fds.flatten() {
+                let is_gpu =
+                    fs::read_link(fd.path()).is_ok_and(|path| path.starts_with("/dev/dri"));
+                if !is_gpu {
+                    continue;
+                }
+                let info = process.path().join("fdinfo").join(fd.file_name());
+                if let Some((id, client)) = fs::read_to_string(info).ok().and_then(|i| client(&i)) {
+                    clients.insert(id, client);
+                }
+            }
+            for (id, client) in clients {
+                let entry = usage.entry(pid).or_default();
+                entry.memory += client.vram;
+                // The busiest engine, as the other ones may run at the same time.
+                let mut percent = None;
+                for (engine, nanoseconds) in client.engines {
+                    let key = (pid, format!("{id} {engine}"));
+                    if let (Some(elapsed), Some(&before)) = (elapsed, self.busy.get(&key)) {
+                        let engine_percent = nanoseconds.saturating_sub(before) as f32 / elapsed;
+                        percent = Some(f32::max(percent.unwrap_or(0.0), engine_percent * 100.0));
+                    }
+                    busy.insert(key, nanoseconds);
+                }
+                if let Some(percent) = percent {
+                    *entry.utilization.get_or_insert(0.0) += percent.min(100.0);
+                }
+            }
+        }
+        self.busy = busy;
+        self.sampled = Some(now);
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn amd(&mut self, _usage: &mut HashMap<u32, Usage>) {}
+}
+
+/// An amdgpu client as its fdinfo describes it.
+#[cfg(target_os = "linux")]
+struct Client {
+    /// Nanoseconds each engine, e.g. `gfx` or `compute`, was busy with it.
+    engines: Vec<(String, u64)>,
+    /// Bytes of video memory.
+    vram: u64,
+}
+
+/// The device and client ID of an amdgpu fdinfo, which tell the clients apart, and the client.
+#[cfg(target_os = "linux")]
+fn client(info: &str) -> Option<(String, Client)> {
+    let (mut driver, mut device, mut id) = ("", "", "");
+    let mut client = Client {
+        engines: Vec::new(),
+        vram: 0,
+    };
+    for line in info.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "drm-driver" => driver = value,
+            "drm-pdev" => device = value,
+            "drm-client-id" => id = value,
+            // The second is the name of newer kernels.
+            "drm-memory-vram" | "drm-resident-vram" => client.vram = bytes(value),
+            // `drm-engine-capacity-*` counts the engines of a kind instead.
+            _ if key.starts_with("drm-engine-capacity-") => {}
+            _ => {
+                if let Some(engine) = key.strip_prefix("drm-engine-") {
+                    let nanoseconds = value.trim_end_matches(" ns").parse().unwrap_or(0);
+                    client.engines.push((engine.to_string(), nanoseconds));
+                }
+            }
+        }
+    }
+    (driver == "amdgpu" && !id.is_empty()).then(|| (format!("{device}/{id}"), client))
+}
+
+/// `81920 KiB` in bytes.
+#[cfg(target_os = "linux")]
+fn bytes(value: &str) -> u64 {
+    let mut parts = value.split_whitespace();
+    let number: u64 = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    let factor = match parts.next() {
+        Some("KiB") => 1024,
+        Some("MiB") => 1024 * 1024,
+        Some("GiB") => 1024 * 1024 * 1024,
+        _ => 1,
+    };
+    number * factor
+}
@@ -0,0 +1,323 @@
+//! Pixel-based chart rendering through the kitty and iTerm2 inline image protocols.
+//!
+//! Charts are rasterized into an RGBA buffer and written directly to the terminal on top of an
+//! area that ratatui leaves blank. Terminals without image support use the braille chart instead,
+//! as do builds without the `graphics` feature.
+
+#[cfg(feature = "graphics")]
+use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+    style::Print,
+};
+use ratatui::layout::Rect;
+use std::env;
+use std::io;
+#[cfg(feature = "graphics")]
+use std::io::Write;
+
+/// Assumed size of a terminal cell in pixels; terminals scale the image to the cell area anyway.
+#[cfg(feature = "graphics")]
+const CELL_WIDTH_PX: u32 = 10;
+#[cfg(feature = "graphics")]
+const CELL_HEIGHT_PX: u32 = 20;
+
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum GraphicsMode {
+    /// Use an image protocol when the terminal is known to support one.
+    #[default]
+    Auto,
+    Kitty,
+    Iterm,
+    /// Always draw charts with braille characters.
+    Off,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm,
+}
+
+impl GraphicsMode {
+    /// The protocol to draw charts with; always `None` when built without the `graphics`
+    /// feature.
+    pub fn protocol(self) -> Option<GraphicsProtocol> {
+        if !cfg!(feature = "graphics") {
+            return None;
+        }
+        match self {
+            GraphicsMode::Kitty => Some(GraphicsProtocol::Kitty),
+            GraphicsMode::Iterm => Some(GraphicsProtocol::Iterm),
+            GraphicsMode::Off => None,
+            GraphicsMode::Auto => detect(),
+        }
+    }
+}
+
+fn detect() -> Option<GraphicsProtocol> {
+    let term = env::var("TERM").unwrap_or_default();
+    if env::var_os("KITTY_WINDOW_ID").is_some() || term.contains("kitty") {
+        return Some(GraphicsProtocol::Kitty);
+    }
+    match env::var("TERM_PROGRAM").unwrap_or_default().as_str() {
+        "iTerm.app" | "WezTerm" => Some(GraphicsProtocol::Iterm),
+        _ => None,
+    }
+}
+
+/// The chart image on the screen, to be replaced or removed.
+#[derive(Default)]
+pub struct Placement {
+    #[cfg(feature = "graphics")]
+    shown: bool,
+}
+
+impl Placement {
+    /// Draws a chart of `samples` over `area`, or clears old kitty placements when there is none.
+    #[cfg(feature = "graphics")]
+    pub fn update(
+        &mut self,
+        protocol: GraphicsProtocol,
+        area: Option<Rect>,
+        samples: &[f64],
+        color: [u8; 3],
+    ) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        if protocol == GraphicsProtocol::Kitty && self.shown {
+            queue!(stdout, Print(KITTY_CLEAR))?;
+        }
+        self.shown = false;
+
+        if let Some(area) = area.filter(|area| !area.is_empty()) {
+            let max = samples.iter().copied().fold(100.0, f64::max);
+            let image = plot(samples, max, area.width, area.height, color);
+            queue!(
+                stdout,
+                SavePosition,
+                MoveTo(area.x, area.y),
+                Print(escape(protocol, &image, area)),
+                RestorePosition
+            )?;
+            self.shown = true;
+        }
+        stdout.flush()
+    }
+
+    #[cfg(not(feature = "graphics"))]
+    pub fn update(
+        &mut self,
+        _protocol: GraphicsProtocol,
+        _area: Option<Rect>,
+        _samples: &[f64],
+        _color: [u8; 3],
+    ) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An RGBA image.
+#[cfg(feature = "graphics")]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+#[cfg(feature = "graphics")]
+impl Image {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    fn put(&mut self, x: i64, y: i64, color: [u8; 4]) {
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return;
+        }
+        let offset = ((y as u32 * self.width + x as u32) * 4) as usize;
+        self.pixels[offset..offset + 4].copy_from_slice(&color);
+    }
+
+    /// Bresenham line, two pixels thick so it survives downscaling.
+    fn line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), color: [u8; 4]) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            self.put(x, y, color);
+            self.put(x, y + 1, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+}
+
+/// Rasterizes `samples` as a line chart filling a `cols` x `rows` cell area.
+#[cfg(feature = "graphics")]
+pub fn plot(samples: &[f64], max: f64, cols: u16, rows: u16, color: [u8; 3]) -> Image {
+    let mut image = Image::new(
+        u32::from(cols) * CELL_WIDTH_PX,
+        u32::from(rows) * CELL_HEIGHT_PX,
+    );
+    let (w, h) = (i64::from(image.width), i64::from(image.height));
+    let color = [color[0], color[1], color[2], 255];
+    let grid = [color[0] / 4, color[1] / 4, color[2] / 4, 255];
+
+    for quarter in 1..4 {
+        let y = h * quarter / 4;
+        image.line((0, y), (w - 1, y), grid);
+    }
+
+    let max = max.max(f64::EPSILON);
+    let points: Vec<(i64, i64)> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let x = i as i64 * (w - 1) / (samples.len().max(2) as i64 - 1);
+            let y = (h - 2) - ((value / max).clamp(0.0, 1.0) * (h - 2) as f64) as i64;
+            (x, y)
+        })
+        .collect();
+    for pair in points.windows(2) {
+        image.line(pair[0], pair[1], color);
+    }
+
+    image
+}
+
+/// Escape sequence placing `image` at the current cursor position, scaled to `area`.
+#[cfg(feature = "graphics")]
+pub fn escape(protocol: GraphicsProtocol, image: &Image, area: Rect) -> String {
+    match protocol {
+        GraphicsProtocol::Kitty => {
+            let payload = base64(&image.pixels);
+            let chunks: Vec<&str> = payload
+                .as_bytes()
+                .chunks(4096)
+                .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+                .collect();
+            let mut out = String::new();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                if i == 0 {
+                    out.push_str(&format!(
+                        "\x1b_Ga=T,f=32,s={},v={},c={},r={},C=1,q=2,m={more};{chunk}\x1b\\",
+                        image.width, image.height, area.width, area.height
+                    ));
+                } else {
+                    out.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+                }
+            }
+            out
+        }
+        GraphicsProtocol::Iterm => format!(
+            "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=0:{}\x07",
+            area.width,
+            area.height,
+            base64(&encode_png(image))
+        ),
+    }
+}
+
+/// Removes every kitty image placement from the screen.
+#[cfg(feature = "graphics")]
+pub const KITTY_CLEAR: &str = "\x1b_Ga=d,d=a,q=2\x1b\\";
+
+#[cfg(feature = "graphics")]
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Minimal PNG encoder using uncompressed (stored) deflate blocks, as required by iTerm2.
+#[cfg(feature = "graphics")]
+fn encode_png(image: &Image) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(image.pixels.len() + image.height as usize);
+    for row in image.pixels.chunks(image.width as usize * 4) {
+        raw.push(0); // no filter
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(65_535).peekable();
+    while let Some(block) = blocks.next() {
+        zlib.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    for (kind, data) in [(b"IHDR", &header), (b"IDAT", &zlib), (b"IEND", &Vec::new())] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    png
+}
+
+#[cfg(feature = "graphics")]
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(feature = "graphics")]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
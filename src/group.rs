@@ -0,0 +1,182 @@
+//! Grouped view: processes sharing a name folded into one row with their count and their summed
+//! CPU and memory usage, e.g. the dozens of renderers of a browser.
+//!
+//! Like [`crate::tree::TreeView`], [`GroupView`] only remembers which groups are expanded. Each
+//! time the list changes it is grouped again, the groups taking their place in the sort order by
+//! their sums, and the members of collapsed groups are set aside.
+
+use crate::app::ProcessInfo;
+use crate::plugin::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// Place of a displayed row in the grouped view.
+#[derive(Clone, Copy)]
+pub enum GroupRow {
+    /// The only process with its name.
+    Single,
+    /// The summary row of a group.
+    Header { expanded: bool },
+    /// A process of an expanded group, below its header.
+    Member,
+}
+
+impl GroupRow {
+    /// Indentation and expand/collapse marker to put in front of the name.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            GroupRow::Single => "  ",
+            GroupRow::Header { expanded: false } => "▸ ",
+            GroupRow::Header { expanded: true } => "▾ ",
+            GroupRow::Member => "    ",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GroupView {
+    expanded: HashSet<String>,
+}
+
+impl GroupView {
+    pub fn toggle(&mut self, name: &str) {
+        if !self.expanded.remove(name) {
+            self.expanded.insert(name.to_string());
+        }
+    }
+
+    /// Groups the sorted `items` by name, ordering the groups with `compare`. Returns the
+    /// displayed rows, with a header row for each name shared by several processes, and the
+    /// hidden members of collapsed groups.
+    pub fn arrange(
+        &self,
+        items: Vec<ProcessInfo>,
+        compare: impl Fn(&ProcessInfo, &ProcessInfo) -> Ordering,
+    ) -> (Vec<ProcessInfo>, Vec<GroupRow>, Vec<ProcessInfo>) {
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut groups: Vec<Vec<ProcessInfo>> = Vec::new();
+        for data in items {
+            match index.get(&data.name) {
+                Some(&i) => groups[i].push(data),
+                None => {
+                    index.insert(data.name.clone(), groups.len());
+                    groups.push(vec![data]);
+                }
+            }
+        }
+        let mut groups: Vec<(Option<ProcessInfo>, Vec<ProcessInfo>)> = groups
+            .into_iter()
+            .map(|members| match members.len() {
+                1 => (None, members),
+                _ => (Some(header(&members)), members),
+            })
+            .collect();
+        // Members stay sorted; groups move to where their sums put them.
+        groups.sort_by(|(a_header, a), (b_header, b)| {
+            compare(
+                a_header.as_ref().unwrap_or(&a[0]),
+                b_header.as_ref().unwrap_or(&b[0]),
+            )
+        });
+
+        let (mut shown, mut rows, mut hidden) = (Vec::new(), Vec::new(), Vec::new());
+        for (header, members) in groups {
+            let Some(header) = header else {
+                shown.extend(members);
+                rows.push(GroupRow::Single);
+                continue;
+            };
+            let expanded = self.expanded.contains(&header.name);
+            shown.push(header);
+            rows.push(GroupRow::Header { expanded });
+            if expanded {
+                rows.extend(std::iter::repeat_n(GroupRow::Member, members.len()));
+                shown.extend(members);
+            } else {
+                hidden.extend(members);
+            }
+        }
+        (shown, rows, hidden)
+    }
+}
+
+/// Summary row of `members`: their count and summed usage, and whatever they all have in common.
+fn header(members: &[ProcessInfo]) -> ProcessInfo {
+    let first = &members[0];
+    let shared = |value: fn(&ProcessInfo) -> &String| {
+        let first = value(first);
+        if members.iter().all(|data| value(data) == first) {
+            first.clone()
+        } else {
+            String::new()
+        }
+    };
+    ProcessInfo {
+        name: first.name.clone(),
+        pid: 0,
+        cpu_usage: members.iter().map(|data| data.cpu_usage).sum(),
+        memory: members.iter().map(|data| data.memory).sum(),
+        virtual_memory: members.iter().map(|data| data.virtual_memory).sum(),
+        shared_memory: members
+            .iter()
+            .filter_map(|data| data.shared_memory)
+            .reduce(|a, b| a + b),
+        swap: members
+            .iter()
+            .filter_map(|data| data.swap)
+            .reduce(|a, b| a + b),
+        exe: shared(|data| &data.exe),
+        command: shared(|data| &data.command),
+        node: shared(|data| &data.node),
+        parent: None,
+        start_time: 0,
+        user: shared(|data| &data.user),
+        state: String::new(),
+        sched: shared(|data| &data.sched),
+        disk_read: members.iter().map(|data| data.disk_read).sum(),
+        disk_written: members.iter().map(|data| data.disk_written).sum(),
+        threads: members.iter().map(|data| data.threads).sum(),
+        fds: members
+            .iter()
+            .filter_map(|data| data.fds)
+            .reduce(|a, b| a + b),
+        tty: shared(|data| &data.tty),
+        session: members
+            .iter()
+            .all(|data| data.session == first.session)
+            .then_some(first.session)
+            .flatten(),
+        cpu_time: members
+            .iter()
+            .filter_map(|data| data.cpu_time)
+            .reduce(|a, b| a + b),
+        container: shared(|data| &data.container),
+        window: String::new(),
+        gpu: members
+            .iter()
+            .filter_map(|data| data.gpu)
+            .reduce(|a, b| a + b),
+        gpu_memory: members
+            .iter()
+            .filter_map(|data| data.gpu_memory)
+            .reduce(|a, b| a + b),
+        custom: first
+            .custom
+            .keys()
+            .filter_map(|name| Some((name.clone(), custom_total(members, name)?)))
+            .collect(),
+        exited: None,
+        members: members.len(),
+    }
+}
+
+/// The custom column `name` of a group: the sum of numbers, or text all members share.
+fn custom_total(members: &[ProcessInfo], name: &str) -> Option<Value> {
+    let mut values = members.iter().map(|data| data.custom.get(name));
+    let first = values.next()??;
+    values.try_fold(first.clone(), |total, value| match (total, value?) {
+        (Value::Number(total), Value::Number(number)) => Some(Value::Number(total + number)),
+        (Value::Text(total), Value::Text(text)) if total == *text => Some(Value::Text(total)),
+        _ => None,
+    })
+}
@@ -0,0 +1,94 @@
+//! Rolling per-process history of CPU and memory samples, taken on every refresh, and the disk
+//! I/O rates between the last two refreshes.
+//!
+//! Every process is sampled, not only those matching the search, so narrowing the search does not
+//! throw away what was recorded.
+
+use crate::app::ProcessInfo;
+use crate::snapshot::ProcessKey;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Samples closer together than this do not update the I/O rates, as a refresh right after
+/// another (e.g. on `r`) would make them jump.
+const MIN_RATE_INTERVAL: f64 = 0.5;
+
+/// Number of samples kept per process.
+pub const HISTORY_LEN: usize = 60;
+/// Block characters of increasing height for text sparklines.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Default)]
+pub struct History {
+    pub cpu: VecDeque<f32>,
+    pub memory: VecDeque<u64>,
+    /// When the disk totals were last sampled, with the bytes read and written until then.
+    io_totals: Option<(Instant, u64, u64)>,
+    /// Bytes per second read and written between the last two samples; `None` until a process
+    /// has been sampled twice.
+    pub io_rate: Option<(u64, u64)>,
+}
+
+impl History {
+    fn push(&mut self, cpu: f32, memory: u64) {
+        if self.cpu.len() == HISTORY_LEN {
+            self.cpu.pop_front();
+            self.memory.pop_front();
+        }
+        self.cpu.push_back(cpu);
+        self.memory.push_back(memory);
+    }
+
+    fn sample_io(&mut self, now: Instant, read: u64, written: u64) {
+        if let Some((then, last_read, last_written)) = self.io_totals {
+            let elapsed = now.duration_since(then).as_secs_f64();
+            if elapsed < MIN_RATE_INTERVAL {
+                return;
+            }
+            let rate = |total: u64, last: u64| (total.saturating_sub(last) as f64 / elapsed) as u64;
+            self.io_rate = Some((rate(read, last_read), rate(written, last_written)));
+        }
+        self.io_totals = Some((now, read, written));
+    }
+
+    /// The last `width` CPU samples as a line of block characters, relative to their maximum so
+    /// the shape shows even for light processes.
+    pub fn cpu_trend(&self, width: usize) -> String {
+        let samples = self.cpu.iter().skip(self.cpu.len().saturating_sub(width));
+        let max = samples.clone().fold(1.0f32, |max, &cpu| max.max(cpu));
+        samples
+            .map(|&cpu| BARS[((cpu / max * 7.0).round() as usize).min(7)])
+            .collect()
+    }
+}
+
+/// Histories by process, so a process that got the PID of an exited one starts afresh.
+#[derive(Default)]
+pub struct HistoryStore {
+    histories: HashMap<ProcessKey, History>,
+}
+
+impl HistoryStore {
+    /// Appends the current sample of every listed process and forgets processes that are gone.
+    /// Without `disk` totals in the samples, the disk rates start over.
+    pub fn record(&mut self, items: &[ProcessInfo], disk: bool) {
+        let mut histories = HashMap::with_capacity(items.len());
+        let now = Instant::now();
+        for data in items.iter().filter(|data| data.exited.is_none()) {
+            let mut history = self.histories.remove(&data.key()).unwrap_or_default();
+            history.push(data.cpu_usage, data.memory);
+            if disk {
+                history.sample_io(now, data.disk_read, data.disk_written);
+            } else {
+                history.io_totals = None;
+                history.io_rate = None;
+            }
+            histories.insert(data.key(), history);
+        }
+        self.histories = histories;
+    }
+
+    pub fn get(&self, key: &ProcessKey) -> Option<&History> {
+        self.histories.get(key)
+    }
+}
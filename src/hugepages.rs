@@ -0,0 +1,77 @@
+//! Transparent and explicit (hugetlbfs) huge page usage, per process and system wide (Linux only).
+
+use std::fs;
+
+const KIB: u64 = 1024;
+
+/// Value of a `Name:   123 kB` line, in bytes.
+fn field(text: &str, name: &str) -> Option<u64> {
+    text.lines().find_map(|line| {
+        let value = line.strip_prefix(name)?.strip_prefix(':')?;
+        let number = value.split_whitespace().next()?.parse::<u64>().ok()?;
+        if value.trim_end().ends_with("kB") {
+            Some(number * KIB)
+        } else {
+            Some(number)
+        }
+    })
+}
+
+pub struct ProcessHugePages {
+    /// Memory backed by transparent huge pages.
+    pub transparent: u64,
+    /// Memory backed by pages from the hugetlbfs pool.
+    pub explicit: u64,
+}
+
+impl ProcessHugePages {
+    pub fn read(pid: u32) -> Option<Self> {
+        let rollup = fs::read_to_string(format!("/proc/{pid}/smaps_rollup")).ok()?;
+        let sum = |names: &[&str]| names.iter().filter_map(|name| field(&rollup, name)).sum();
+        Some(Self {
+            transparent: sum(&["AnonHugePages", "ShmemPmdMapped", "FilePmdMapped"]),
+            explicit: sum(&["Shared_Hugetlb", "Private_Hugetlb"]),
+        })
+    }
+}
+
+/// The system's hugetlbfs pool and transparent huge page settings.
+pub struct Pool {
+    /// Pool sizes are in pages of `page_size` bytes.
+    pub total: u64,
+    pub free: u64,
+    pub reserved: u64,
+    pub surplus: u64,
+    pub page_size: u64,
+    /// Anonymous memory currently backed by transparent huge pages, in bytes.
+    pub transparent: u64,
+    /// Active transparent huge page mode: `always`, `madvise` or `never`.
+    pub transparent_mode: Option<String>,
+}
+
+impl Pool {
+    pub fn read() -> Option<Self> {
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let transparent_mode = fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+            .ok()
+            .and_then(|modes| {
+                let start = modes.find('[')? + 1;
+                let end = modes[start..].find(']')? + start;
+                Some(modes[start..end].to_string())
+            });
+        Some(Self {
+            total: field(&meminfo, "HugePages_Total")?,
+            free: field(&meminfo, "HugePages_Free")?,
+            reserved: field(&meminfo, "HugePages_Rsvd").unwrap_or(0),
+            surplus: field(&meminfo, "HugePages_Surp").unwrap_or(0),
+            page_size: field(&meminfo, "Hugepagesize")?,
+            transparent: field(&meminfo, "AnonHugePages").unwrap_or(0),
+            transparent_mode,
+        })
+    }
+
+    /// Bytes of pool pages in use.
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free) * self.page_size
+    }
+}
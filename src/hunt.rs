@@ -0,0 +1,158 @@
+//! Looking for a string anywhere in the processes: their names, command lines, environments,
+//! open files and connections, for hunts such as "which process has this file open" or "what
+//! talks to 10.0.0.7".
+//!
+//! The search runs on a background thread with a `System` of its own. Open files and connections
+//! come from `/proc`, so they are only searched on Linux.
+
+use crate::fds::{self, Target};
+use crate::redact::Redactor;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use sysinfo::{ProcessRefreshKind, System, ThreadKind, UpdateKind};
+
+/// How many hits are listed; those of a search matching more are left out.
+const MAX_HITS: usize = 500;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Place {
+    Name,
+    Command,
+    Environment,
+    File,
+    Connection,
+}
+
+impl Place {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Command => "command",
+            Self::Environment => "environment",
+            Self::File => "open file",
+            Self::Connection => "connection",
+        }
+    }
+}
+
+pub struct Hit {
+    pub pid: u32,
+    pub name: String,
+    pub place: Place,
+    /// What matched, with secrets hidden.
+    pub text: String,
+}
+
+pub struct Hunt {
+    pub needle: String,
+    /// `None` while the search is still running.
+    pub hits: Option<Vec<Hit>>,
+    /// Whether hits beyond [`MAX_HITS`] were left out.
+    pub truncated: bool,
+    /// Index into `hits` of the chosen one.
+    pub choice: usize,
+    receiver: Receiver<Vec<Hit>>,
+}
+
+impl Hunt {
+    /// Starts looking for `needle`, ignoring case unless it has an uppercase letter.
+    pub fn start(needle: String, redactor: Redactor) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let pattern = needle.clone();
+        thread::spawn(move || {
+            let _ = sender.send(search(&pattern, &redactor));
+        });
+        Self {
+            needle,
+            hits: None,
+            truncated: false,
+            choice: 0,
+            receiver,
+        }
+    }
+
+    /// Takes the hits once the search is done.
+    pub fn poll(&mut self) {
+        if self.hits.is_none() {
+            if let Ok(mut hits) = self.receiver.try_recv() {
+                self.truncated = hits.len() > MAX_HITS;
+                hits.truncate(MAX_HITS);
+                self.hits = Some(hits);
+            }
+        }
+    }
+
+    pub fn chosen(&self) -> Option<&Hit> {
+        self.hits.as_ref()?.get(self.choice)
+    }
+
+    pub fn move_choice(&mut self, step: isize) {
+        let len = self.hits.as_ref().map_or(0, Vec::len);
+        self.choice = self
+            .choice
+            .saturating_add_signed(step)
+            .min(len.saturating_sub(1));
+    }
+}
+
+fn search(needle: &str, redactor: &Redactor) -> Vec<Hit> {
+    let case_sensitive = needle.chars().any(char::is_uppercase);
+    let needle = if case_sensitive {
+        needle.to_string()
+    } else {
+        needle.to_lowercase()
+    };
+    let matches = |text: &str| {
+        if case_sensitive {
+            text.contains(&needle)
+        } else {
+            text.to_lowercase().contains(&needle)
+        }
+    };
+
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessRefreshKind::new()
+            .with_cmd(UpdateKind::Always)
+            .with_environ(UpdateKind::Always),
+    );
+    let sockets = fds::sockets();
+    let mut hits = Vec::new();
+    for (pid, process) in system.processes() {
+        // Threads share the command line, environment and files of their process.
+        if process.thread_kind() == Some(ThreadKind::Userland) {
+            continue;
+        }
+        let pid = pid.as_u32();
+        let name = process.name();
+        let mut hit = |place, text: &str| {
+            if matches(text) {
+                hits.push(Hit {
+                    pid,
+                    name: name.to_string(),
+                    place,
+                    text: match place {
+                        Place::Environment => redactor.apply_variable(text),
+                        _ => redactor.apply(text),
+                    },
+                });
+            }
+        };
+        hit(Place::Name, name);
+        hit(Place::Command, &process.cmd().join(" "));
+        for variable in process.environ() {
+            hit(Place::Environment, variable);
+        }
+        for descriptor in fds::open(pid, &sockets).unwrap_or_default() {
+            match &descriptor.target {
+                Target::File(path) => hit(Place::File, path),
+                Target::Socket(socket) => hit(Place::Connection, socket),
+                Target::Pipe(_) | Target::Other(_) => {}
+            }
+        }
+    }
+    hits.sort_by(|a, b| (a.pid, a.place, &a.text).cmp(&(b.pid, b.place, &b.text)));
+    // A file or socket open several times is one place.
+    hits.dedup_by(|a, b| a.pid == b.pid && a.place == b.place && a.text == b.text);
+    hits
+}
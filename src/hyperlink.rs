@@ -0,0 +1,94 @@
+//! OSC 8 terminal hyperlinks.
+//!
+//! Ratatui has no notion of hyperlinks, so [`Hyperlink`] writes the escape sequences straight
+//! into the buffer. The text is emitted in two-character chunks, each wrapped in its own link,
+//! because the backend measures the width of a cell symbol including the escape sequence.
+
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use std::env;
+
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum HyperlinkMode {
+    /// Emit links when the terminal is known to support them.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl HyperlinkMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            HyperlinkMode::Auto => terminal_supports_hyperlinks(),
+            HyperlinkMode::Always => true,
+            HyperlinkMode::Never => false,
+        }
+    }
+}
+
+/// Best-effort detection based on the environment variables set by common terminals.
+fn terminal_supports_hyperlinks() -> bool {
+    let var = |name: &str| env::var(name).unwrap_or_default();
+
+    if matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper" | "Tabby"
+    ) {
+        return true;
+    }
+    if ["KITTY_WINDOW_ID", "WT_SESSION", "KONSOLE_VERSION"]
+        .iter()
+        .any(|name| env::var_os(name).is_some())
+    {
+        return true;
+    }
+    if var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000) {
+        return true;
+    }
+    let term = var("TERM");
+    ["kitty", "foot", "alacritty", "wezterm"]
+        .iter()
+        .any(|name| term.contains(name))
+}
+
+/// Builds a link for `path` from `template`, replacing `{path}` and `{host}`.
+pub fn url_for_path(template: &str, path: &str) -> String {
+    let host = sysinfo::System::host_name().unwrap_or_default();
+    template
+        .replace("{host}", &encode(&host))
+        .replace("{path}", &encode(path))
+}
+
+/// Percent-encodes everything except unreserved characters and `/`.
+fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+pub struct Hyperlink<'a> {
+    pub text: &'a str,
+    pub url: String,
+}
+
+impl Widget for Hyperlink<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let chars: Vec<char> = self.text.chars().collect();
+        for (i, chunk) in chars.chunks(2).enumerate() {
+            let x = area.x + i as u16 * 2;
+            if x >= area.right() {
+                break;
+            }
+            let text: String = chunk.iter().collect();
+            let link = format!("\x1B]8;;{}\x07{}\x1B]8;;\x07", self.url, text);
+            buf.get_mut(x, area.y).set_symbol(&link);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! The language of the table and how it writes numbers.
+//!
+//! Text is looked up by key, such as `popup.help`, in a message catalog: the English one in
+//! `src/locales/en.toml`, which lists every key, overlaid with the translation of the chosen
+//! language where there is one, with `{placeholders}` filled in by [`fill`]. So far the column
+//! headers, the hint line, the titles of popups, the kill confirmation, the orphan and permission
+//! popups with their buttons, and the status messages of kills come from it. The rest, other
+//! popup bodies, the other status messages and errors, the help text and the command-line
+//! interface, is still written in place and moves to the catalog in later changes.
+//!
+//! The language and the number format follow `locale` in the config file, or else the
+//! environment as other programs read it: `LC_ALL`, then `LC_MESSAGES` for text and
+//! `LC_NUMERIC` for numbers, then `LANG`.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// The bundled catalogs by language, English first.
+const CATALOGS: [(&str, &str); 1] = [("en", include_str!("locales/en.toml"))];
+
+static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Picks the catalog of `locale`, e.g. `de_DE.UTF-8`, or of the environment when `None`. Text
+/// looked up before is English.
+pub fn init(locale: Option<&str>) {
+    let locale = locale.map_or_else(|| from_environment("LC_MESSAGES"), str::to_string);
+    let translation = CATALOGS
+        .iter()
+        .find(|(name, _)| *name == language(&locale))
+        .map_or("", |(_, catalog)| catalog);
+    let _ = CATALOG.set(catalog(CATALOGS[0].1, translation));
+}
+
+/// The text of `key` in the chosen language, or `key` itself if no catalog has it.
+pub fn text(key: &'static str) -> &'static str {
+    CATALOG
+        .get_or_init(|| catalog(CATALOGS[0].1, ""))
+        .get(key)
+        .map_or(key, String::as_str)
+}
+
+/// The text of `key` with each `{name}` of `args` replaced by its value, e.g.
+/// `fill("status.killed_group", &[("killed", &2), ("total", &3), ("name", &"java")])`.
+pub fn fill(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(text(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+/// `english` with the keys of `translation` replaced. A translation that does not parse is left
+/// out rather than keeping the table from starting.
+fn catalog(english: &str, translation: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    for source in [english, translation] {
+        if let Ok(table) = source.parse::<toml::Table>() {
+            flatten("", &table, &mut messages);
+        }
+    }
+    messages
+}
+
+/// Adds the strings of `table` to `messages` under their dotted keys.
+fn flatten(prefix: &str, table: &toml::Table, messages: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = format!("{prefix}{key}");
+        match value {
+            toml::Value::String(text) => {
+                messages.insert(key, text.clone());
+            }
+            toml::Value::Table(table) => flatten(&format!("{key}."), table, messages),
+            _ => {}
+        }
+    }
+}
+
+/// The locale the environment sets for `category`, e.g. `LC_NUMERIC`; `C` when unset.
+pub fn from_environment(category: &str) -> String {
+    ["LC_ALL", category, "LANG"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .map(|value| value.to_string_lossy().into_owned())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "C".to_string())
+}
+
+/// The language of `locale`, e.g. `de` for `de_DE.UTF-8`; `en` for the `C` and `POSIX` locales.
+fn language(locale: &str) -> &str {
+    match locale.split(['_', '.', '@']).next().unwrap_or_default() {
+        "" | "C" | "POSIX" => "en",
+        language => language,
+    }
+}
+
+/// How numbers are written: the decimal mark and what separates groups of thousands.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Numbers {
+    pub decimal: char,
+    pub thousands: Option<char>,
+}
+
+impl Numbers {
+    /// As Rust writes them, e.g. `12345.6`.
+    pub const PLAIN: Numbers = Numbers {
+        decimal: '.',
+        thousands: None,
+    };
+
+    /// The format of `locale`, e.g. `1.234,5` for `de_DE`, or of the environment when `None`.
+    /// Locales not known here, and the `C` locale, write plain numbers.
+    pub fn of_locale(locale: Option<&str>) -> Self {
+        let locale = locale.map_or_else(|| from_environment("LC_NUMERIC"), str::to_string);
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        let (language, country) = name.split_once('_').unwrap_or((name, ""));
+        let (thousands, decimal) = match (language, country) {
+            ("de" | "it", "CH") => ('’', '.'),
+            ("en" | "ja" | "ko" | "zh" | "he" | "th", _) => (',', '.'),
+            ("de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro", _) => ('.', ','),
+            ("fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "hu" | "bg", _) => {
+                ('\u{a0}', ',')
+            }
+            _ => return Numbers::PLAIN,
+        };
+        Numbers {
+            decimal,
+            thousands: Some(thousands),
+        }
+    }
+
+    /// `value` with `decimals` digits after the decimal mark.
+    pub fn format(self, value: f64, decimals: usize) -> String {
+        let text = format!("{value:.decimals$}");
+        let (sign, text) = match text.strip_prefix('-') {
+            Some(text) => ("-", text),
+            None => ("", text.as_str()),
+        };
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let mut formatted = format!("{sign}{}", self.group(integer));
+        if !fraction.is_empty() {
+            formatted.push(self.decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// `value`, a whole number.
+    pub fn integer(self, value: u64) -> String {
+        self.group(&value.to_string())
+    }
+
+    /// `digits` with the thousands separated.
+    fn group(self, digits: &str) -> String {
+        let Some(separator) = self.thousands else {
+            return digits.to_string();
+        };
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_follow_the_locale() {
+        let english = Numbers::of_locale(Some("en_US.UTF-8"));
+        assert_eq!(english.format(1234567.891, 1), "1,234,567.9");
+        assert_eq!(english.format(-999.0, 1), "-999.0");
+        assert_eq!(english.integer(1000), "1,000");
+        let german = Numbers::of_locale(Some("de_DE.UTF-8"));
+        assert_eq!(german.format(1234.5, 1), "1.234,5");
+        assert_eq!(
+            Numbers::of_locale(Some("de_CH")).format(1234.5, 1),
+            "1’234.5"
+        );
+        assert_eq!(Numbers::of_locale(Some("C")), Numbers::PLAIN);
+        assert_eq!(Numbers::of_locale(Some("C.UTF-8")), Numbers::PLAIN);
+        assert_eq!(Numbers::PLAIN.format(1234.5, 1), "1234.5");
+    }
+
+    #[test]
+    fn translations_fall_back_to_english() {
+        let messages = catalog(CATALOGS[0].1, "[popup]\nhelp = \"Hilfe\"\n");
+        assert_eq!(messages["popup.help"], "Hilfe");
+        assert_eq!(messages["popup.about"], "About");
+        assert_eq!(language("de_DE.UTF-8"), "de");
+        assert_eq!(language("POSIX"), "en");
+        // Every translation only has keys the English catalog has.
+        let english = catalog(CATALOGS[0].1, "");
+        for (name, translation) in &CATALOGS[1..] {
+            for key in catalog("", translation).keys() {
+                assert!(english.contains_key(key), "{name}: unknown key {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let args: [(&str, &dyn Display); 3] = [("killed", &2), ("total", &3), ("name", &"java")];
+        assert_eq!(
+            fill("status.killed_group", &args),
+            "Killed 2 of 3 processes named java"
+        );
+        assert_eq!(fill("confirm.more", &[]), "and {count} more");
+    }
+}
@@ -0,0 +1,1617 @@
+//! The interactive session: terminal setup, the event loop and the key bindings.
+
+use crate::app::{App, AppState, KillAction, SortColumn, COPY_COLUMNS};
+use crate::cli::Cli;
+use crate::keymap::{Action, Key, Keys};
+use crate::report::ReportFormat;
+use crate::throttle::Resource;
+use crate::ui::Theme;
+use crate::units::Units;
+use crate::{
+    audit, changes, config, crash, events, i18n, ionice, metrics, oom, procfs, replay, sched,
+    users, CPU_CRITICAL,
+};
+#[cfg(unix)]
+use crate::{control, session};
+use crossterm::{
+    cursor::{MoveTo, Show},
+    event::{
+        poll, read, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
+    execute,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use ratatui::{prelude::*, Terminal};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::Signal;
+
+/// Starts the interactive table on the terminal and runs it until the user quits.
+#[cfg(unix)]
+pub type Remote = session::RemoteSession;
+#[cfg(not(unix))]
+pub type Remote = std::convert::Infallible;
+
+pub fn run(
+    cli: &Cli,
+    remote: Option<Remote>,
+    replay: Option<replay::Replay>,
+    user: Option<users::UserFilter>,
+    mut config: config::Config,
+) -> io::Result<()> {
+    let raw_mode = RawMode::enable()?;
+    let fullscreen = cli.fullscreen || config.fullscreen;
+    let mut terminal = open_terminal(fullscreen)?;
+    if config.mouse && !cli.no_mouse {
+        execute!(io::stdout(), EnableMouseCapture)?;
+        MOUSE_CAPTURE.store(true, Ordering::SeqCst);
+    }
+    // Focus changes tell when the table is watched without keys being pressed.
+    execute!(io::stdout(), EnableFocusChange)?;
+    FOCUS_CHANGE.store(true, Ordering::SeqCst);
+
+    let mut app = App::new();
+    app.fullscreen = fullscreen;
+    #[cfg(unix)]
+    {
+        app.remote = remote;
+    }
+    #[cfg(not(unix))]
+    let _ = remote;
+    app.replay = replay;
+    #[cfg(unix)]
+    if let Some(path) = &cli.control {
+        match control::listen(path) {
+            Ok(receiver) => app.control = Some(receiver),
+            Err(e) => app.notice = Some(format!("Control socket {}: {e}", path.display())),
+        }
+    }
+    app.linear = cli.linear;
+    i18n::init(config.locale.as_deref());
+    // Checked when the config file was loaded.
+    let profile = cli
+        .profile
+        .as_deref()
+        .and_then(|name| config.profile(name).ok())
+        .cloned()
+        .unwrap_or_default();
+    app.columns = match profile.columns.is_empty() {
+        true => config.layout(),
+        false => config::arranged(&profile.columns),
+    };
+    app.confirm_kills = config.confirm_kills && !cli.no_confirm;
+    app.read_only = cli.read_only;
+    app.read_only_locked = cli.read_only;
+    app.audit_path = audit::path();
+    app.audit_sender = audit::sender();
+    app.watchdog.rules = config.watchdog_rules().unwrap_or_default();
+    app.watchdog.dry_run = config.watchdog_dry_run || cli.watchdog_dry_run;
+    app.alerts.thresholds = std::mem::take(&mut config.alert);
+    app.alerts.notify = config.desktop_notifications;
+    app.show_breakdown = config.breakdown;
+    if let Some(path) = &cli.baseline {
+        match changes::Changes::load(path) {
+            Ok(changes) => {
+                app.changes = changes;
+                app.show_changes = true;
+            }
+            Err(e) => app.notice = Some(format!("Baseline {}: {e}", path.display())),
+        }
+    }
+    app.update_collected();
+    let sort = if !cli.sort.is_empty() {
+        &cli.sort
+    } else if !profile.sort.is_empty() {
+        &profile.sort
+    } else {
+        &config.sort
+    };
+    if !sort.is_empty() {
+        app.sort = sort.clone();
+        app.update_collected();
+        app.sort_items();
+    }
+    app.speak_command = cli.speak_command.clone();
+    app.themes = std::mem::take(&mut config.themes);
+    let theme = cli
+        .palette
+        .clone()
+        .or(profile.palette)
+        .or_else(|| config.palette.clone())
+        .unwrap_or_else(Theme::from_environment);
+    if let Err(e) = app.set_theme(theme) {
+        app.notice = Some(e);
+    }
+    app.units = Units {
+        cores: app.ctx.cpus().len(),
+        ..cli.units(&config)
+    };
+    app.hyperlinks = cli.hyperlinks.enabled();
+    app.exe_url = cli.exe_url.clone();
+    app.graphics = cli.graphics.protocol();
+    if let Some(user) = &user {
+        app.notice = Some(format!("Processes of {}", user.name));
+    }
+    if let Some(path) = &cli.events {
+        match events::EventLog::open(path, CPU_CRITICAL) {
+            Ok(log) => app.events = Some(log),
+            Err(e) => app.notice = Some(format!("Event log {}: {e}", path.display())),
+        }
+    }
+    let interval = cli
+        .refresh_interval
+        .or(profile.refresh_interval)
+        .unwrap_or(config.refresh_interval);
+    let interval = Duration::try_from_secs_f64(interval).unwrap_or_default();
+    let idle_timeout = cli.idle_timeout.unwrap_or(config.idle_timeout) * 60.0;
+    let idle_timeout = Duration::try_from_secs_f64(idle_timeout).unwrap_or_default();
+    app.idle_refresh = config.idle_refresh();
+    // Validated when the config file was loaded.
+    app.keys = Keys::new(config.keymap().unwrap_or_default());
+    app.redactor = config.redactor().unwrap_or_default();
+    crash::set_redactor(app.redactor.clone());
+    app.config = config;
+    // Attached sessions are updated by the server instead, and replays step through their log.
+    let local = !app.is_remote() && app.replay.is_none();
+    app.user = user;
+    if local {
+        // Without live updates, one snapshot still fills in what `quick_load` leaves out, and
+        // the next ones are made when refreshing.
+        app.start_collector(interval);
+        if !interval.is_zero() && !idle_timeout.is_zero() {
+            app.idle_timeout = Some(idle_timeout);
+        }
+    }
+    // The server's processes may run on another machine.
+    if local {
+        app.oom_rx = oom::watch();
+        app.restriction = procfs::restriction();
+        if let Some(metrics::MetricsAddress(address)) = cli.metrics_port {
+            match metrics::Exporter::serve(address) {
+                Ok(exporter) => {
+                    app.metrics = Some(exporter);
+                    // Scrapers get the disk counters whether or not the table shows them.
+                    app.update_collected();
+                }
+                Err(e) => app.notice = Some(format!("Metrics on {address}: {e}")),
+            }
+        }
+    }
+    app.quick_load();
+    if cli.check_updates {
+        app.start_update_check();
+    }
+    if let Some(search) = &cli.filter {
+        app.apply_searches(std::slice::from_ref(search));
+    } else if !profile.filter.is_empty() {
+        app.apply_searches(&profile.filter);
+    }
+    if cli.tree || profile.tree {
+        app.toggle_tree();
+    } else if profile.group {
+        app.toggle_groups();
+    }
+    if let Some(path) = cli.session_log.as_ref().or(profile.record.as_ref()) {
+        app.start_recording(path);
+    }
+    if let Some(query) = cli.port {
+        app.look_up_port(query);
+    }
+    // A read-only session leaves them to the next one that may kill.
+    if local && !app.read_only {
+        app.resume_escalations();
+    }
+
+    let events = EventReader::spawn();
+    'session: loop {
+        app.poll_update();
+        #[cfg(unix)]
+        app.poll_remote();
+        #[cfg(unix)]
+        app.poll_control();
+        app.sample_cores();
+        app.sample_threads();
+        app.poll_tools();
+        app.poll_hunt();
+        app.poll_oom();
+        app.poll_throttles();
+        app.poll_orphans();
+        app.poll_live();
+        app.poll_escalations();
+        app.poll_queue();
+        app.check_idle();
+        app.note_outcome();
+        crash::update_state(app.crash_state());
+        if !app.hidden {
+            app.render(&mut terminal)?;
+            VIEWPORT_TOP.store(terminal.get_frame().size().y, Ordering::SeqCst);
+        }
+
+        let tick = if app.idle.is_idle() { IDLE_TICK } else { TICK };
+        let first = events.receiver.recv_timeout(tick).ok();
+        // A key sequence left unfinished runs what its keys so far run, e.g. `g` without a second
+        // `g`.
+        let expired = app.keys.expire(Instant::now());
+        if first.is_none() && expired.is_none() {
+            continue;
+        }
+        // Everything typed while the last frame was drawn or the last key handled, before the
+        // next frame, so keys do not each wait for a redraw behind slow work.
+        let mut queue: VecDeque<_> = expired
+            .map(Input::Action)
+            .into_iter()
+            .chain(
+                first
+                    .into_iter()
+                    .chain(events.receiver.try_iter())
+                    .map(Input::Event),
+            )
+            .collect();
+        while let Some(input) = queue.pop_front() {
+            // Actions of the table, run once the key map has placed the key.
+            let (event, mut actions) = match input {
+                Input::Action(action) => (None, vec![action]),
+                Input::Event(event) => (Some(event), Vec::new()),
+            };
+            if matches!(
+                event,
+                Some(Ok(Event::Key(_) | Event::Mouse(_) | Event::FocusGained))
+            ) {
+                app.note_input();
+                if std::mem::take(&mut app.hidden) {
+                    // Whatever covered the table while it was away may still be on the screen.
+                    terminal.clear()?;
+                }
+            }
+            let event = match event {
+                Some(Ok(Event::FocusGained)) => {
+                    app.focused = true;
+                    continue;
+                }
+                Some(Ok(Event::FocusLost)) => {
+                    app.focused = false;
+                    app.hidden = !app.config.draw_unfocused;
+                    continue;
+                }
+                Some(Ok(Event::Resize(..))) => {
+                    terminal.autoresize()?;
+                    continue;
+                }
+                // Buttons stand for keys, which are handled as if typed.
+                Some(Ok(Event::Mouse(mouse))) => match mouse_key(&mut app, mouse) {
+                    Some(code) => Some(Ok(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))),
+                    None => continue,
+                },
+                event => event,
+            };
+            if let Some(Ok(Event::Key(key_event))) = event {
+                if key_event.kind == KeyEventKind::Press {
+                    app.error = None;
+                    crash::record_action(match (&app.mode, key_event.code) {
+                        // Whatever is typed in popups, searches, rules and environment values
+                        // included, may name processes or hold secrets, so only the keys of the
+                        // table make it into crash reports.
+                        (AppState::ProcessMode, code) => format!("ProcessMode: {code:?}"),
+                        (mode, KeyCode::Char(_)) => format!("{mode:?}: <input>"),
+                        (mode, code) => format!("{mode:?}: {code:?}"),
+                    });
+                    match app.mode {
+                        // Popups have keys of their own, which are not rebound.
+                        AppState::ProcessMode => match key_event.code {
+                            KeyCode::Char('y') if app.pending_kill.is_some() => {
+                                app.confirm_kill();
+                            }
+                            KeyCode::Char('n') | KeyCode::Esc if app.pending_kill.is_some() => {
+                                app.pending_kill = None;
+                            }
+                            KeyCode::Char('l') if app.pending_kill.is_some() => {
+                                app.start_scheduling();
+                            }
+                            KeyCode::Char('a') if app.pending_kill.is_some() => {
+                                app.cycle_kill_scope();
+                            }
+                            _ if app.pending_kill.is_some() => {}
+                            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')
+                                if app.details.is_some() =>
+                            {
+                                app.details = None;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.threads_shown() => {
+                                if let Some(details) = &mut app.details {
+                                    details.threads.move_choice(1);
+                                }
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.threads_shown() => {
+                                if let Some(details) = &mut app.details {
+                                    details.threads.move_choice(-1);
+                                }
+                            }
+                            KeyCode::Char('s') if app.threads_shown() => {
+                                app.open_thread_signal_picker();
+                            }
+                            KeyCode::Char('/') if app.environment_shown() => {
+                                app.start_env_search();
+                            }
+                            KeyCode::Char('r') if app.environment_shown() => {
+                                app.env_revealed = !app.env_revealed;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.details.is_some() => {
+                                app.details_scroll = app.details_scroll.saturating_add(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.details.is_some() => {
+                                app.details_scroll = app.details_scroll.saturating_sub(1);
+                            }
+                            KeyCode::Tab | KeyCode::Char('l') if app.details.is_some() => {
+                                app.details_tab = app.details_tab.next();
+                                app.details_scroll = 0;
+                            }
+                            KeyCode::BackTab | KeyCode::Char('h') if app.details.is_some() => {
+                                app.details_tab = app.details_tab.previous();
+                                app.details_scroll = 0;
+                            }
+                            _ if app.details.is_some() => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.hunt.is_some() => {
+                                app.hunt = None;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.hunt.is_some() => {
+                                if let Some(hunt) = &mut app.hunt {
+                                    hunt.move_choice(1);
+                                }
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.hunt.is_some() => {
+                                if let Some(hunt) = &mut app.hunt {
+                                    hunt.move_choice(-1);
+                                }
+                            }
+                            KeyCode::Enter if app.hunt.is_some() => app.jump_to_hit(),
+                            KeyCode::Char('/') if app.hunt.is_some() => {
+                                app.input =
+                                    app.hunt.take().map(|hunt| hunt.needle).unwrap_or_default();
+                                app.character_index = app.input.chars().count();
+                                app.mode = AppState::FindMode;
+                                app.show_popup = true;
+                            }
+                            _ if app.hunt.is_some() => {}
+                            KeyCode::Char('y')
+                                if app.renice.as_ref().is_some_and(|plan| !plan.applied) =>
+                            {
+                                app.apply_renice();
+                            }
+                            KeyCode::Char('n') | KeyCode::Esc if app.renice.is_some() => {
+                                app.renice = None;
+                            }
+                            _ if app.renice.is_some() => {}
+                            KeyCode::Char('y') if !app.orphans.is_empty() => {
+                                app.kill_orphans();
+                            }
+                            KeyCode::Char('n') | KeyCode::Esc if !app.orphans.is_empty() => {
+                                app.orphans.clear();
+                            }
+                            KeyCode::Char('y') if app.denied_kill.is_some() => {
+                                app.send_with_sudo();
+                            }
+                            KeyCode::Char('c') if app.denied_kill.is_some() => {
+                                app.copy_sudo_command();
+                            }
+                            KeyCode::Char('n') | KeyCode::Esc if app.denied_kill.is_some() => {
+                                app.denied_kill = None;
+                            }
+                            _ if app.denied_kill.is_some() => {}
+                            KeyCode::Char('h') if app.port_holders.is_some() => {
+                                app.signal_port_holders(Signal::Hangup);
+                            }
+                            KeyCode::Char('t') if app.port_holders.is_some() => {
+                                app.signal_port_holders(Signal::Term);
+                            }
+                            KeyCode::Char('K') if app.port_holders.is_some() => {
+                                app.signal_port_holders(Signal::Kill);
+                            }
+                            KeyCode::Esc if app.port_holders.is_some() => {
+                                app.port_holders = None;
+                            }
+                            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')
+                                if app.command_output.is_some() =>
+                            {
+                                app.command_output = None;
+                                app.command_rx = None;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.command_output.is_some() => {
+                                app.command_scroll = app.command_scroll.saturating_add(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.command_output.is_some() => {
+                                app.command_scroll = app.command_scroll.saturating_sub(1);
+                            }
+                            KeyCode::Char('!') if app.command_output.is_some() => {
+                                app.command_output = None;
+                                app.command_rx = None;
+                                app.start_command();
+                            }
+                            _ if app.command_output.is_some() => {}
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q')
+                                if app.show_queue =>
+                            {
+                                app.show_queue = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_queue => {
+                                app.queue_choice =
+                                    (app.queue_choice + 1).min(app.queue.len().saturating_sub(1));
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_queue => {
+                                app.queue_choice = app.queue_choice.saturating_sub(1);
+                            }
+                            KeyCode::Char('x') | KeyCode::Delete if app.show_queue => {
+                                app.cancel_queued();
+                            }
+                            _ if app.show_queue => {}
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('W')
+                                if app.show_watchdog =>
+                            {
+                                app.show_watchdog = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_watchdog => {
+                                app.watchdog_choice = (app.watchdog_choice + 1)
+                                    .min(app.watchdog.rules.len().saturating_sub(1));
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_watchdog => {
+                                app.watchdog_choice = app.watchdog_choice.saturating_sub(1);
+                            }
+                            KeyCode::Char('a') if app.show_watchdog => {
+                                app.start_watchdog_rule();
+                            }
+                            KeyCode::Char('e') | KeyCode::Enter if app.show_watchdog => {
+                                app.edit_watchdog_rule();
+                            }
+                            KeyCode::Char('x') | KeyCode::Delete if app.show_watchdog => {
+                                app.remove_watchdog_rule();
+                            }
+                            KeyCode::Char('d') if app.show_watchdog => {
+                                app.watchdog.toggle_dry_run();
+                            }
+                            _ if app.show_watchdog => {}
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('G')
+                                if app.show_alerts =>
+                            {
+                                app.show_alerts = false;
+                            }
+                            KeyCode::Char('c') if app.show_alerts => {
+                                app.alerts.clear();
+                            }
+                            _ if app.show_alerts => {}
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('l')
+                                if app.show_exits =>
+                            {
+                                app.show_exits = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_exits => {
+                                app.exits_scroll = app.exits_scroll.saturating_add(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_exits => {
+                                app.exits_scroll = app.exits_scroll.saturating_sub(1);
+                            }
+                            KeyCode::Char('s') if app.show_exits => {
+                                app.exits_sort = app.exits_sort.next();
+                                app.exits_scroll = 0;
+                            }
+                            KeyCode::Char('/') if app.show_exits => {
+                                app.start_exits_filter();
+                            }
+                            KeyCode::Char('c') if app.show_exits => {
+                                app.exits.clear();
+                            }
+                            _ if app.show_exits => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.show_outcomes => {
+                                app.show_outcomes = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_outcomes => {
+                                app.outcomes_scroll = app.outcomes_scroll.saturating_add(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_outcomes => {
+                                app.outcomes_scroll = app.outcomes_scroll.saturating_sub(1);
+                            }
+                            KeyCode::Char('c') if app.show_outcomes => {
+                                app.outcomes.clear();
+                                app.outcomes_scroll = 0;
+                            }
+                            _ if app.show_outcomes => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.audit_entries.is_some() => {
+                                app.audit_entries = None;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.audit_entries.is_some() => {
+                                app.audit_scroll = app.audit_scroll.saturating_add(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.audit_entries.is_some() => {
+                                app.audit_scroll = app.audit_scroll.saturating_sub(1);
+                            }
+                            _ if app.audit_entries.is_some() => {}
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('K')
+                                if app.show_changes =>
+                            {
+                                app.show_changes = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_changes => {
+                                app.changes.move_choice(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_changes => {
+                                app.changes.move_choice(-1);
+                            }
+                            KeyCode::Enter if app.show_changes => app.jump_to_change(),
+                            KeyCode::Char('b') if app.show_changes => {
+                                app.toggle_changes_baseline();
+                            }
+                            _ if app.show_changes => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.show_suspects => {
+                                app.show_suspects = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_suspects => {
+                                app.suspects.move_choice(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_suspects => {
+                                app.suspects.move_choice(-1);
+                            }
+                            KeyCode::Enter if app.show_suspects => app.jump_to_suspect(),
+                            _ if app.show_suspects => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.show_consumers => {
+                                app.show_consumers = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_consumers => {
+                                app.consumers.move_choice(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_consumers => {
+                                app.consumers.move_choice(-1);
+                            }
+                            KeyCode::Enter if app.show_consumers => app.search_consumer(),
+                            _ if app.show_consumers => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.show_ephemeral => {
+                                app.show_ephemeral = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_ephemeral => {
+                                app.ephemeral.scroll_by(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_ephemeral => {
+                                app.ephemeral.scroll_by(-1);
+                            }
+                            _ if app.show_ephemeral => {}
+                            KeyCode::Esc | KeyCode::Char('q') if app.breakdown_choice.is_some() => {
+                                app.breakdown_choice = None;
+                            }
+                            KeyCode::Char('V') if app.breakdown_choice.is_some() => {
+                                app.toggle_breakdown();
+                            }
+                            KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab
+                                if app.breakdown_choice.is_some() =>
+                            {
+                                app.move_breakdown_choice(1);
+                            }
+                            KeyCode::Char('h') | KeyCode::Left | KeyCode::BackTab
+                                if app.breakdown_choice.is_some() =>
+                            {
+                                app.move_breakdown_choice(-1);
+                            }
+                            KeyCode::Enter | KeyCode::Char(' ')
+                                if app.breakdown_choice.is_some() =>
+                            {
+                                if let Some(index) = app.breakdown_choice.take() {
+                                    app.pick_breakdown(index);
+                                }
+                            }
+                            _ if app.breakdown_choice.is_some() => {}
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?')
+                                if app.show_help =>
+                            {
+                                app.show_help = false;
+                            }
+                            KeyCode::Char('j') | KeyCode::Down if app.show_help => {
+                                app.help_scroll = app.help_scroll.saturating_add(1);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up if app.show_help => {
+                                app.help_scroll = app.help_scroll.saturating_sub(1);
+                            }
+                            _ if app.show_help => {}
+                            KeyCode::Char(digit @ '1'..='9') if app.show_runtime => {
+                                app.run_tool(digit as usize - '1' as usize);
+                            }
+                            _ => {
+                                actions =
+                                    app.keys.press(Key::from_event(&key_event), Instant::now());
+                            }
+                        },
+                        AppState::SearchMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Char('/') | KeyCode::Enter => {
+                                    app.submit_message();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = !app.show_popup
+                                }
+                                KeyCode::Esc => {
+                                    app.cancel_search();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                    app.update_search();
+                                }
+                                KeyCode::Backspace
+                                    if app.input.is_empty() && app.edit_previous_filter() => {}
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                    app.update_search();
+                                }
+                                KeyCode::Up => app.recall_search(true),
+                                KeyCode::Down => app.recall_search(false),
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::SearchMode => {}
+                        AppState::PortMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_port();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::PortMode => {}
+                        AppState::JumpMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_jump();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::JumpMode => {}
+                        AppState::FindMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_find();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::FindMode => {}
+                        AppState::CommandLineMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                    match app.submit_command_line() {
+                                        Ok(Some(action)) => actions.push(action),
+                                        result => app.show_result(result.map(|_| ())),
+                                    }
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => {}
+                            }
+                        }
+                        AppState::CommandLineMode => {}
+                        AppState::CommandMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    let result = app.submit_command();
+                                    app.show_result(result);
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::CommandMode => {}
+                        AppState::ReniceMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_renice();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::ReniceMode => {}
+                        AppState::SignalMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.signal_thread = None;
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                    app.submit_signal();
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => {
+                                    app.signal_choice =
+                                        (app.signal_choice + 1).min(app.picker_signals().len() - 1);
+                                }
+                                KeyCode::Char('k') | KeyCode::Up => {
+                                    app.signal_choice = app.signal_choice.saturating_sub(1);
+                                }
+                                KeyCode::Char(digit) if digit.is_ascii_digit() => {
+                                    app.enter_char(digit);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::SignalMode => {}
+                        AppState::SchedMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                    app.submit_sched();
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => {
+                                    app.sched_choice =
+                                        (app.sched_choice + 1).min(sched::PICKER.len() - 1);
+                                }
+                                KeyCode::Char('k') | KeyCode::Up => {
+                                    app.sched_choice = app.sched_choice.saturating_sub(1);
+                                }
+                                KeyCode::Char(digit) if digit.is_ascii_digit() => {
+                                    app.enter_char(digit);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::SchedMode => {}
+                        AppState::IoniceMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                    app.submit_ionice();
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => {
+                                    app.ionice_choice =
+                                        (app.ionice_choice + 1).min(ionice::PICKER.len() - 1);
+                                }
+                                KeyCode::Char('k') | KeyCode::Up => {
+                                    app.ionice_choice = app.ionice_choice.saturating_sub(1);
+                                }
+                                KeyCode::Char(digit) if digit.is_ascii_digit() => {
+                                    app.enter_char(digit);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::IoniceMode => {}
+                        AppState::PriorityMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.priority = None;
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                    app.submit_priority();
+                                }
+                                KeyCode::Up => {
+                                    app.adjust_nice(-1);
+                                }
+                                KeyCode::Down => {
+                                    app.adjust_nice(1);
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::PriorityMode => {}
+                        AppState::ThrottleMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    let result = app.submit_throttle();
+                                    app.show_result(result);
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Tab => {
+                                    app.throttle_tree = !app.throttle_tree;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::ThrottleMode => {}
+                        AppState::ScheduleMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.scheduling = None;
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_schedule();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::ScheduleMode => {}
+                        AppState::WatchdogMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_watchdog_rule();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::WatchdogMode => {}
+                        AppState::ExitFilterMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.reset_cursor();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Enter => {
+                                    app.submit_exits_filter();
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::ExitFilterMode => {}
+                        // The tab narrows down as the search is typed.
+                        AppState::EnvSearchMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc => {
+                                    app.input.clear();
+                                    app.submit_env_search();
+                                }
+                                KeyCode::Enter => app.submit_env_search(),
+                                KeyCode::Char(to_insert) => {
+                                    app.enter_char(to_insert);
+                                    app.details_scroll = 0;
+                                }
+                                KeyCode::Backspace => {
+                                    app.delete_char();
+                                    app.details_scroll = 0;
+                                }
+                                KeyCode::Left => {
+                                    app.move_cursor_left();
+                                }
+                                KeyCode::Right => {
+                                    app.move_cursor_right();
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::EnvSearchMode => {}
+                        AppState::ColumnMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('o') => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.show_popup = false;
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => {
+                                    app.column_choice =
+                                        (app.column_choice + 1).min(app.columns.len() - 1);
+                                }
+                                KeyCode::Char('k') | KeyCode::Up => {
+                                    app.column_choice = app.column_choice.saturating_sub(1);
+                                }
+                                KeyCode::Char(' ') => {
+                                    app.toggle_column();
+                                }
+                                KeyCode::Char('J') => {
+                                    app.move_column(false);
+                                }
+                                KeyCode::Char('K') => {
+                                    app.move_column(true);
+                                }
+                                KeyCode::Char('w') => {
+                                    let result = app.save_columns();
+                                    app.show_result(result);
+                                }
+                                _ => (),
+                            }
+                        }
+                        AppState::ColumnMode => {}
+                        AppState::CopyMode if key_event.kind == KeyEventKind::Press => {
+                            match key_event.code {
+                                KeyCode::Esc | KeyCode::Char('Y') => {
+                                    app.mode = AppState::ProcessMode;
+                                    app.notice = None;
+                                    continue;
+                                }
+                                KeyCode::Char('y') => {
+                                    app.copy_selection(false);
+                                    continue;
+                                }
+                                KeyCode::Char('V') => {
+                                    app.copy_selection(true);
+                                    continue;
+                                }
+                                KeyCode::Char('h') | KeyCode::Left => {
+                                    app.copy_column = app.copy_column.saturating_sub(1);
+                                }
+                                KeyCode::Char('l') | KeyCode::Right => {
+                                    app.copy_column =
+                                        (app.copy_column + 1).min(COPY_COLUMNS.len() - 1);
+                                }
+                                KeyCode::Char('k') | KeyCode::Up => {
+                                    app.previous();
+                                }
+                                KeyCode::Char('j') | KeyCode::Down => {
+                                    app.next();
+                                }
+                                _ => continue,
+                            }
+                            app.show_copy_cell();
+                        }
+                        AppState::CopyMode => {}
+                    }
+                }
+            }
+            for action in actions {
+                if !run_action(&mut app, action, &mut terminal)? {
+                    break 'session;
+                }
+            }
+            if app.fullscreen != ALTERNATE_SCREEN.load(Ordering::SeqCst) {
+                terminal = switch_screen(app.fullscreen)?;
+            }
+        }
+    }
+
+    app.release_throttles();
+    drop(events);
+    drop(raw_mode);
+    #[cfg(unix)]
+    if let Some(path) = &cli.control {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+/// A terminal event, or an action to run as if its keys were pressed.
+enum Input {
+    Event(io::Result<Event>),
+    Action(Action),
+}
+
+/// Runs `action` on the table; `false` to quit.
+fn run_action(
+    app: &mut App,
+    action: Action,
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+) -> io::Result<bool> {
+    if matches!(
+        action,
+        Action::Up
+            | Action::Down
+            | Action::Top
+            | Action::Bottom
+            | Action::PageUp
+            | Action::PageDown
+    ) {
+        app.note_move();
+    }
+    tracing::debug!(?action, "action");
+    match action {
+        Action::Quit => return Ok(false),
+        Action::Refresh => app.refresh(),
+        Action::Up => app.previous(),
+        Action::Down => app.next(),
+        Action::Top => app.select_row(0),
+        Action::Bottom => app.select_row(app.items.len().saturating_sub(1)),
+        Action::PageUp => app.move_page(false),
+        Action::PageDown => app.move_page(true),
+        Action::Jump => {
+            app.mode = AppState::JumpMode;
+            app.show_popup = true;
+        }
+        Action::Parent => {
+            let result = app.select_parent();
+            app.show_result(result);
+        }
+        Action::Children => {
+            let result = app.select_child();
+            app.show_result(result);
+        }
+        // The processes of a replay are long gone, or no longer what they were.
+        Action::Kill
+        | Action::KillNow
+        | Action::Signal
+        | Action::Escalate
+        | Action::Suspend
+        | Action::KillTree
+        | Action::KillContainer
+        | Action::Find
+        | Action::Details
+        | Action::Report
+        | Action::Cores
+        | Action::Runtime
+        | Action::Memory
+        | Action::Numa
+        | Action::SpreadAffinity
+        | Action::Terminal
+        | Action::Renice
+        | Action::Sched
+        | Action::Ionice
+        | Action::Priority
+        | Action::Throttle
+        | Action::MemoryCap
+        | Action::Port
+        | Action::Command
+            if app.replay.is_some() =>
+        {
+            app.notice = Some("Not available while replaying a session log".to_string());
+        }
+        // Browsing, searching and exporting stay available.
+        Action::Kill
+        | Action::KillNow
+        | Action::Signal
+        | Action::Escalate
+        | Action::Suspend
+        | Action::KillTree
+        | Action::KillContainer
+        | Action::SpreadAffinity
+        | Action::Renice
+        | Action::Sched
+        | Action::Ionice
+        | Action::Priority
+        | Action::Throttle
+        | Action::MemoryCap
+        | Action::Command
+            if app.read_only =>
+        {
+            app.refuse_read_only();
+        }
+        Action::Find if app.is_remote() => {
+            app.notice = Some("Finding is not available when attached".to_string());
+        }
+        Action::Find => {
+            app.mode = AppState::FindMode;
+            app.show_popup = true;
+        }
+        Action::Details => app.open_details(),
+        Action::Kill => app.request_kill(KillAction::Kill, false),
+        Action::KillNow => app.request_kill(KillAction::Kill, true),
+        Action::Signal => app.open_signal_picker(),
+        Action::Escalate => app.escalate_selected(),
+        Action::Suspend => app.toggle_suspend(),
+        Action::Queue => {
+            app.show_queue = true;
+            app.queue_choice = 0;
+        }
+        Action::Watchdog => app.show_watchdog = true,
+        Action::Alerts => app.show_alerts = true,
+        Action::Exits => {
+            app.show_exits = true;
+            app.exits_scroll = 0;
+        }
+        Action::Audit => app.open_audit(),
+        Action::Outcomes => {
+            app.show_outcomes = true;
+            app.outcomes_scroll = 0;
+        }
+        Action::Changes => {
+            app.show_changes = true;
+            app.changes.choice = 0;
+        }
+        Action::Suspects => {
+            app.show_suspects = true;
+            app.suspects.choice = 0;
+        }
+        Action::Consumers => {
+            app.show_consumers = true;
+            app.consumers.choice = 0;
+        }
+        Action::ShortLived => {
+            app.show_ephemeral = true;
+            app.ephemeral.scroll = 0;
+            app.observe_ephemeral();
+        }
+        Action::Breakdown => app.toggle_breakdown(),
+        Action::Search => {
+            app.mode = AppState::SearchMode;
+            app.show_popup = !app.show_popup;
+            app.start_search();
+        }
+        Action::PopFilter => app.pop_filter(),
+        Action::ClearFilters => app.clear_filters(),
+        Action::Tree => app.toggle_tree(),
+        Action::Collapse if app.tree.is_some() || app.groups.is_some() => {
+            app.toggle_collapsed();
+        }
+        Action::KillTree if app.groups.is_some() => {
+            app.request_kill(KillAction::Group, false);
+        }
+        Action::KillTree => app.request_kill(KillAction::Subtree, false),
+        Action::Collapse => {}
+        Action::KillContainer => app.request_kill(KillAction::Container, false),
+        Action::Group => app.toggle_groups(),
+        Action::Copy => {
+            app.mode = AppState::CopyMode;
+            app.show_copy_cell();
+        }
+        Action::Report => app.export_report(ReportFormat::Markdown),
+        Action::Export => app.export_table(),
+        Action::About => app.show_about = !app.show_about,
+        Action::Help => {
+            app.show_help = true;
+            app.help_scroll = 0;
+        }
+        Action::Chart => {
+            app.show_chart = !app.show_chart;
+            // Redraw everything so no image remnants stay behind.
+            terminal.clear()?;
+        }
+        Action::Cores => app.show_cores = !app.show_cores,
+        Action::Runtime => app.show_runtime = !app.show_runtime,
+        Action::Memory => app.show_memory = !app.show_memory,
+        Action::Numa => app.show_numa = !app.show_numa,
+        Action::Battery => app.show_power = !app.show_power,
+        Action::Log => app.show_log = !app.show_log,
+        Action::SpreadAffinity => app.spread_affinity(),
+        Action::Linear => app.linear = !app.linear,
+        Action::Fullscreen => app.fullscreen = !app.fullscreen,
+        Action::Theme => app.next_theme(),
+        Action::Announce => app.speak_selection(),
+        Action::SortName => app.sort_by(SortColumn::Name),
+        Action::SortPid => app.sort_by(SortColumn::Pid),
+        Action::SortCpu => app.sort_by(SortColumn::Cpu),
+        Action::SortMemory => app.sort_by(SortColumn::Memory),
+        Action::SortUser => app.sort_by(SortColumn::User),
+        Action::SortElapsed => app.sort_by(SortColumn::Elapsed),
+        Action::SortCpuTime => app.sort_by(SortColumn::CpuTime),
+        Action::Owner => app.cycle_owner_filter(),
+        Action::Problems => app.toggle_problems_only(),
+        Action::Terminal => app.toggle_terminal_filter(),
+        Action::Pause => app.toggle_pause(),
+        Action::ReplayBack => app.step_replay(-1, false),
+        Action::ReplayForward => app.step_replay(1, false),
+        Action::ReplayToPrevious => app.step_replay(-1, true),
+        Action::ReplayToNext => app.step_replay(1, true),
+        Action::Focus => app.toggle_focus(),
+        Action::Watch => app.toggle_watch(),
+        Action::Follow => app.toggle_follow(),
+        Action::Io => app.toggle_io_columns(),
+        Action::Columns => app.open_column_picker(),
+        Action::FullCommand => app.toggle_full_commands(),
+        Action::ScrollLeft => app.scroll_commands(false),
+        Action::ScrollRight => app.scroll_commands(true),
+        Action::Renice => app.start_renice(),
+        Action::Sched => app.open_sched_picker(),
+        Action::Ionice => app.open_ionice_picker(),
+        Action::Priority => app.open_priority_editor(),
+        Action::Throttle => app.start_throttle(Resource::Cpu),
+        Action::MemoryCap => app.start_throttle(Resource::Memory),
+        Action::Port => {
+            app.mode = AppState::PortMode;
+            app.show_popup = true;
+        }
+        Action::Command => app.start_command(),
+        Action::CommandLine => app.start_command_line(),
+        Action::ReadOnly => app.toggle_read_only(),
+        Action::SavedFilter(index) => {
+            if let Some(name) = app.config.filters.keys().nth(index).cloned() {
+                let result = app.apply_saved_filter(&name);
+                app.show_result(result);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// What the mouse did: a click on a popup button gives its key, to be handled like a typed one;
+/// clicks on the table and scrolling it are handled here. Scrolling a popup gives arrow keys.
+fn mouse_key(app: &mut App, mouse: MouseEvent) -> Option<KeyCode> {
+    let hit = |area: &Rect| {
+        (area.left()..area.right()).contains(&mouse.column)
+            && (area.top()..area.bottom()).contains(&mouse.row)
+    };
+    let selected = app.state.selected().unwrap_or(0);
+    match mouse.kind {
+        MouseEventKind::ScrollDown if app.popup_open() => Some(KeyCode::Down),
+        MouseEventKind::ScrollUp if app.popup_open() => Some(KeyCode::Up),
+        MouseEventKind::ScrollDown => {
+            app.select_row(selected + 1);
+            None
+        }
+        MouseEventKind::ScrollUp => {
+            app.select_row(selected.saturating_sub(1));
+            None
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            let clickable = &app.clickable;
+            if let Some(&(_, code)) = clickable.buttons.iter().find(|(area, _)| hit(area)) {
+                return Some(code);
+            }
+            if let Some(&(_, index)) = clickable.segments.iter().find(|(area, _)| hit(area)) {
+                if !app.popup_open() || app.breakdown_choice.is_some() {
+                    app.breakdown_choice = None;
+                    app.pick_breakdown(index);
+                }
+                return None;
+            }
+            if app.popup_open() {
+                return None;
+            }
+            if let Some(&(_, column)) = clickable.headers.iter().find(|(area, _)| hit(area)) {
+                app.sort_by(column);
+            } else if hit(&clickable.rows) {
+                let row = usize::from((mouse.row - clickable.rows.y) / clickable.row_height.max(1));
+                app.select_row(clickable.first_row + row);
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Terminal events, read on a thread of their own so that keys queue up while the table is busy,
+/// e.g. reading a large process table or writing an export.
+struct EventReader {
+    receiver: Receiver<io::Result<Event>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl EventReader {
+    fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                // Waiting in `poll` rather than `read` lets go of crossterm's event reader now and
+                // then, which the inline viewport needs to query the cursor position.
+                let event = match poll(Duration::from_millis(50)) {
+                    Ok(false) => continue,
+                    Ok(true) => read(),
+                    Err(e) => Err(e),
+                };
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            receiver,
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for EventReader {
+    /// Stops reading, so that input after the table has closed is left to the shell.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Whether the table has put the terminal into raw mode, see [`restore_terminal`].
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+/// Whether the table has taken over the mouse.
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(false);
+/// Whether the terminal reports focus changes to the table.
+static FOCUS_CHANGE: AtomicBool = AtomicBool::new(false);
+/// Whether the table is drawn full screen on the alternate screen.
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+/// First line of the inline viewport the table is drawn in.
+static VIEWPORT_TOP: AtomicU16 = AtomicU16::new(0);
+/// Lines of the inline viewport below the prompt.
+const INLINE_HEIGHT: u16 = 16;
+/// Longest the session waits for a key before drawing again. Snapshots are taken on the
+/// collector's thread and keys read on the [`EventReader`]'s, so neither waits for the other; the
+/// tick only bounds how late a new snapshot, a finished background job or a control command
+/// shows up when no key is pressed.
+const TICK: Duration = Duration::from_millis(250);
+
+/// The tick while live updates are slowed down for the table being left alone, so that drawing
+/// slows down with them.
+const IDLE_TICK: Duration = Duration::from_secs(5);
+
+/// A terminal drawing full screen on the alternate screen, or inline below the prompt.
+fn open_terminal(fullscreen: bool) -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    let viewport = if fullscreen {
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+        Viewport::Fullscreen
+    } else {
+        Viewport::Inline(INLINE_HEIGHT)
+    };
+    Terminal::with_options(
+        CrosstermBackend::new(io::stdout()),
+        TerminalOptions { viewport },
+    )
+}
+
+/// Moves the table to the alternate screen or back below the prompt.
+fn switch_screen(fullscreen: bool) -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    if fullscreen {
+        // Leave nothing of the inline table behind for when the alternate screen is left.
+        execute!(
+            io::stdout(),
+            MoveTo(0, VIEWPORT_TOP.load(Ordering::SeqCst)),
+            Clear(ClearType::FromCursorDown)
+        )?;
+    } else {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        ALTERNATE_SCREEN.store(false, Ordering::SeqCst);
+    }
+    open_terminal(fullscreen)
+}
+
+/// Clears the table off the screen, takes the terminal out of raw mode and shows the cursor
+/// again. Does nothing unless the table changed the terminal, so that the output of other
+/// commands stays free of escape sequences. Called on the way out of the table, whether it ends
+/// normally, with an error or in a panic.
+pub fn restore_terminal() {
+    if RAW_MODE.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        if MOUSE_CAPTURE.swap(false, Ordering::SeqCst) {
+            let _ = execute!(io::stdout(), DisableMouseCapture);
+        }
+        if FOCUS_CHANGE.swap(false, Ordering::SeqCst) {
+            let _ = execute!(io::stdout(), DisableFocusChange);
+        }
+        let _ = if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+            execute!(io::stdout(), LeaveAlternateScreen, Show)
+        } else {
+            execute!(
+                io::stdout(),
+                MoveTo(0, VIEWPORT_TOP.load(Ordering::SeqCst)),
+                Clear(ClearType::FromCursorDown),
+                Show
+            )
+        };
+    }
+}
+
+/// Restores the terminal when dropped, so that an error does not leave it unusable.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        enable_raw_mode()?;
+        RAW_MODE.store(true, Ordering::SeqCst);
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
@@ -0,0 +1,168 @@
+//! I/O scheduling classes, changed like `ionice` does, to keep a disk-heavy background job from
+//! starving everything else without killing it.
+//!
+//! The realtime and best-effort classes come with a level from 0, served first, to 7; a process
+//! left at `none` is served as best-effort at a level that follows its nice value. The classes
+//! only matter to I/O schedulers that honor them, such as BFQ. Linux only.
+
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Class {
+    None,
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+/// Classes offered by the picker, with a description.
+pub const PICKER: [(Class, &str); 4] = [
+    (Class::None, "follow the nice value"),
+    (Class::BestEffort, "share the disk by level"),
+    (Class::Idle, "only use the disk when nothing else does"),
+    (Class::Realtime, "served before everything else"),
+];
+
+/// Levels of the realtime and best-effort classes, the highest priority first.
+pub const LEVELS: std::ops::RangeInclusive<u32> = 0..=7;
+
+/// Bits of an I/O priority below the class, as `ioprio_set(2)` packs them.
+const CLASS_SHIFT: u32 = 13;
+
+impl Class {
+    /// As `ionice` names it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Class::None => "none",
+            Class::Realtime => "realtime",
+            Class::BestEffort => "best-effort",
+            Class::Idle => "idle",
+        }
+    }
+
+    pub const fn has_level(self) -> bool {
+        matches!(self, Class::Realtime | Class::BestEffort)
+    }
+
+    const fn number(self) -> u32 {
+        match self {
+            Class::None => 0,
+            Class::Realtime => 1,
+            Class::BestEffort => 2,
+            Class::Idle => 3,
+        }
+    }
+}
+
+/// The class and level packed into one I/O priority.
+fn encode(class: Class, level: u32) -> u32 {
+    class.number() << CLASS_SHIFT | if class.has_level() { level } else { 0 }
+}
+
+/// The class and level of a packed I/O priority; `None` for a class the kernel added since.
+fn decode(priority: u32) -> Option<(Class, u32)> {
+    let class = match priority >> CLASS_SHIFT {
+        0 => Class::None,
+        1 => Class::Realtime,
+        2 => Class::BestEffort,
+        3 => Class::Idle,
+        _ => return None,
+    };
+    Some((class, priority & ((1 << CLASS_SHIFT) - 1)))
+}
+
+/// A class and level as `ionice` prints them, e.g. `best-effort 4` or `idle`.
+pub fn describe((class, level): (Class, u32)) -> String {
+    if class.has_level() {
+        format!("{} {level}", class.name())
+    } else {
+        class.name().to_string()
+    }
+}
+
+/// Parses the level typed into the picker. Only the realtime and best-effort classes have one;
+/// for the others the input is not shown and ignored.
+pub fn parse_level(class: Class, input: &str) -> Result<u32, String> {
+    if !class.has_level() {
+        return Ok(0);
+    }
+    input
+        .trim()
+        .parse()
+        .ok()
+        .filter(|level| LEVELS.contains(level))
+        .ok_or_else(|| format!("{} needs a level from 0 to 7", class.name()))
+}
+
+/// `IOPRIO_WHO_PROCESS`: the `who` of `ioprio_get(2)` and `ioprio_set(2)` is a PID.
+#[cfg(target_os = "linux")]
+const WHO_PROCESS: libc::c_int = 1;
+
+#[cfg(target_os = "linux")]
+pub fn get(pid: u32) -> io::Result<(Class, u32)> {
+    // SAFETY: ioprio_get(2) takes two integers and has no memory safety requirements.
+    let priority = unsafe { libc::syscall(libc::SYS_ioprio_get, WHO_PROCESS, pid as libc::pid_t) };
+    if priority == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    decode(priority as u32).ok_or_else(|| io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(target_os = "linux")]
+pub fn set(pid: u32, class: Class, level: u32) -> io::Result<()> {
+    let priority = encode(class, level) as libc::c_int;
+    // SAFETY: ioprio_set(2) takes three integers and has no memory safety requirements.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            WHO_PROCESS,
+            pid as libc::pid_t,
+            priority,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get(_pid: u32) -> io::Result<(Class, u32)> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set(_pid: u32, _class: Class, _level: u32) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priorities_are_packed_as_the_kernel_does() {
+        assert_eq!(encode(Class::BestEffort, 7), 0x4007);
+        assert_eq!(encode(Class::Idle, 5), 0x6000);
+        for (class, _) in PICKER {
+            let level = if class.has_level() { 3 } else { 0 };
+            assert_eq!(decode(encode(class, level)), Some((class, level)));
+        }
+        assert_eq!(decode(7 << CLASS_SHIFT), None);
+    }
+
+    #[test]
+    fn only_classes_with_levels_need_one() {
+        assert_eq!(parse_level(Class::Realtime, " 0 "), Ok(0));
+        assert!(parse_level(Class::BestEffort, "8").is_err());
+        assert!(parse_level(Class::BestEffort, "").is_err());
+        assert_eq!(parse_level(Class::Idle, "junk"), Ok(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn own_priority_can_be_read() {
+        assert!(get(std::process::id()).is_ok());
+    }
+}
@@ -0,0 +1,734 @@
+//! Key bindings of the process table: which keys, with modifiers and in sequences such as `g g`,
+//! run which action. The `[keys]` config table binds actions to other keys:
+//!
+//! ```toml
+//! [keys]
+//! kill = "x"
+//! report = "d"
+//! top = "ctrl-home"
+//! quit = "Z Q"
+//! ```
+//!
+//! Keys are single characters, `space`, names such as `enter`, `tab`, `up` or `pagedown`, and
+//! either of these after `ctrl-` or `alt-`; several of them separated by spaces make a sequence.
+//! Keys that are not rebound keep their meaning, so two actions can swap keys. Popups have keys
+//! of their own, which stay as they are.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long a key that both runs an action and starts a longer sequence, like `g` and `g g`,
+/// waits for the rest of the sequence.
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Refresh,
+    Up,
+    Down,
+    Top,
+    Bottom,
+    PageUp,
+    PageDown,
+    Jump,
+    Parent,
+    Children,
+    Find,
+    Details,
+    Kill,
+    KillNow,
+    Signal,
+    Escalate,
+    Suspend,
+    Queue,
+    Watchdog,
+    Alerts,
+    Exits,
+    Outcomes,
+    Audit,
+    Changes,
+    Suspects,
+    Consumers,
+    ShortLived,
+    Breakdown,
+    Search,
+    PopFilter,
+    ClearFilters,
+    Tree,
+    Collapse,
+    KillTree,
+    KillContainer,
+    Group,
+    Copy,
+    Report,
+    Export,
+    About,
+    Help,
+    Chart,
+    Cores,
+    Runtime,
+    Memory,
+    Numa,
+    Battery,
+    Log,
+    SpreadAffinity,
+    Linear,
+    Fullscreen,
+    Theme,
+    Announce,
+    SortName,
+    SortPid,
+    SortCpu,
+    SortMemory,
+    SortUser,
+    SortElapsed,
+    SortCpuTime,
+    Owner,
+    Problems,
+    Terminal,
+    Pause,
+    ReplayBack,
+    ReplayForward,
+    ReplayToPrevious,
+    ReplayToNext,
+    Focus,
+    Watch,
+    Follow,
+    Io,
+    Columns,
+    FullCommand,
+    ScrollLeft,
+    ScrollRight,
+    Renice,
+    Sched,
+    Ionice,
+    Priority,
+    Throttle,
+    MemoryCap,
+    Port,
+    Command,
+    CommandLine,
+    ReadOnly,
+    /// The saved filter with this index in the `[filters]` config table.
+    SavedFilter(usize),
+}
+
+/// Every action with its name in `[keys]`, its default keys (alternatives separated by `|`) and
+/// what it does, in the order of the help screen.
+pub const ACTIONS: [(Action, &str, &str, &str); 87] = [
+    (Action::Quit, "quit", "q", "Quit"),
+    (Action::Refresh, "refresh", "r", "Reload the process list"),
+    (Action::Up, "up", "k|up", "Select the previous process"),
+    (Action::Down, "down", "j|down", "Select the next process"),
+    (Action::Top, "top", "g g|home", "Select the first process"),
+    (Action::Bottom, "bottom", "end", "Select the last process"),
+    (
+        Action::PageUp,
+        "page_up",
+        "pageup",
+        "Select the process a screen up",
+    ),
+    (
+        Action::PageDown,
+        "page_down",
+        "pagedown",
+        "Select the process a screen down",
+    ),
+    (Action::Jump, "jump", "J", "Select a process by PID"),
+    (
+        Action::Parent,
+        "parent",
+        "g p",
+        "Select the parent of the process",
+    ),
+    (
+        Action::Children,
+        "children",
+        "g c",
+        "Select the first child of the process",
+    ),
+    (
+        Action::Find,
+        "find",
+        "ctrl-f",
+        "Find a string in every process's command line, environment, files and connections",
+    ),
+    (
+        Action::Details,
+        "details",
+        "enter",
+        "Show details of the selection",
+    ),
+    (
+        Action::Kill,
+        "kill",
+        "d",
+        "Kill the selection, asking first",
+    ),
+    (
+        Action::KillNow,
+        "kill_now",
+        "ctrl-d",
+        "Kill the selection without asking",
+    ),
+    (Action::Signal, "signal", "s", "Pick a signal to send"),
+    (
+        Action::Escalate,
+        "escalate",
+        "e",
+        "SIGTERM, then SIGKILL after the grace period",
+    ),
+    (
+        Action::Suspend,
+        "suspend",
+        "b",
+        "Suspend or resume the selection",
+    ),
+    (Action::Queue, "queue", "Q", "Open the scheduled signals"),
+    (Action::Watchdog, "watchdog", "W", "Open the watchdog rules"),
+    (Action::Alerts, "alerts", "G", "Open the alerts"),
+    (
+        Action::Exits,
+        "exits",
+        "l",
+        "Open the log of exited processes",
+    ),
+    (
+        Action::Outcomes,
+        "history",
+        "ctrl-r",
+        "Open the history of what the status line said",
+    ),
+    (
+        Action::Audit,
+        "audit",
+        "ctrl-g",
+        "List the signals sent, in this session and the ones before",
+    ),
+    (
+        Action::Changes,
+        "changes",
+        "K",
+        "Show what started, exited or changed since the last refresh",
+    ),
+    (
+        Action::Suspects,
+        "suspects",
+        "ctrl-s",
+        "Rank the processes most likely behind trouble",
+    ),
+    (
+        Action::Consumers,
+        "consumers",
+        "ctrl-u",
+        "Sum up CPU and memory by user and by name",
+    ),
+    (
+        Action::ShortLived,
+        "short_lived",
+        "ctrl-n",
+        "List the processes too short-lived for a refresh to see",
+    ),
+    (
+        Action::Breakdown,
+        "breakdown",
+        "V",
+        "Count processes by state and user",
+    ),
+    (
+        Action::Search,
+        "search",
+        "/",
+        "Search, within the results of the last search if any",
+    ),
+    (
+        Action::PopFilter,
+        "pop_filter",
+        "esc",
+        "Drop the last search",
+    ),
+    (
+        Action::ClearFilters,
+        "clear_filters",
+        "ctrl-x",
+        "Drop every search",
+    ),
+    (
+        Action::Tree,
+        "tree",
+        "t",
+        "Switch to the tree view and back",
+    ),
+    (
+        Action::Collapse,
+        "collapse",
+        "space",
+        "Collapse or expand a tree node or group",
+    ),
+    (
+        Action::KillTree,
+        "kill_tree",
+        "D",
+        "Kill the selected process with its descendants, or the selected group",
+    ),
+    (
+        Action::KillContainer,
+        "kill_container",
+        "ctrl-k",
+        "Kill every process in the container of the selection",
+    ),
+    (
+        Action::Group,
+        "group",
+        "g",
+        "Group processes by name and back",
+    ),
+    (Action::Copy, "copy", "Y", "Copy a cell of the selection"),
+    (
+        Action::Report,
+        "report",
+        "x",
+        "Write a report on the selection",
+    ),
+    (Action::Export, "export", "X", "Export the table"),
+    (Action::About, "about", "v", "About syskill"),
+    (Action::Help, "help", "?", "Show these keys"),
+    (Action::Chart, "chart", "H", "Show the CPU history chart"),
+    (Action::Cores, "cores", "C", "Show core usage"),
+    (
+        Action::Runtime,
+        "runtime",
+        "I",
+        "Inspect JVM and Python runtimes",
+    ),
+    (Action::Memory, "memory", "M", "Show memory details"),
+    (Action::Numa, "numa", "N", "Show NUMA placement"),
+    (
+        Action::Battery,
+        "battery",
+        "ctrl-b",
+        "Show the battery and what drains it",
+    ),
+    (
+        Action::Log,
+        "log",
+        "ctrl-l",
+        "Show the latest lines of the log",
+    ),
+    (
+        Action::SpreadAffinity,
+        "spread_affinity",
+        "A",
+        "Spread the selection over all cores",
+    ),
+    (
+        Action::Linear,
+        "linear",
+        "L",
+        "Switch to the screen reader layout and back",
+    ),
+    (
+        Action::Fullscreen,
+        "fullscreen",
+        "z",
+        "Switch to full screen and back",
+    ),
+    (
+        Action::Theme,
+        "theme",
+        "ctrl-t",
+        "Switch to the next palette",
+    ),
+    (Action::Announce, "announce", "a", "Announce the selection"),
+    (Action::SortName, "sort_name", "n", "Sort by name"),
+    (Action::SortPid, "sort_pid", "i", "Sort by PID"),
+    (Action::SortCpu, "sort_cpu", "c", "Sort by CPU usage"),
+    (Action::SortMemory, "sort_memory", "m", "Sort by memory"),
+    (Action::SortUser, "sort_user", "u", "Sort by user"),
+    (
+        Action::SortElapsed,
+        "sort_elapsed",
+        "ctrl-a",
+        "Sort by time since the start",
+    ),
+    (
+        Action::SortCpuTime,
+        "sort_cpu_time",
+        "ctrl-p",
+        "Sort by CPU time used",
+    ),
+    (
+        Action::Owner,
+        "owner",
+        "U",
+        "Show the processes of one user after another",
+    ),
+    (
+        Action::Problems,
+        "problems",
+        "Z",
+        "Show only processes in problem states",
+    ),
+    (
+        Action::Terminal,
+        "terminal",
+        "h",
+        "Show only processes started from this terminal",
+    ),
+    (Action::Pause, "pause", "p", "Pause live updates"),
+    (
+        Action::ReplayBack,
+        "replay_back",
+        "[",
+        "Show the previous snapshot of the replayed session",
+    ),
+    (
+        Action::ReplayForward,
+        "replay_forward",
+        "]",
+        "Show the next snapshot of the replayed session",
+    ),
+    (
+        Action::ReplayToPrevious,
+        "replay_previous_action",
+        "{",
+        "Go back to the last action of the replayed session",
+    ),
+    (
+        Action::ReplayToNext,
+        "replay_next_action",
+        "}",
+        "Go on to the next action of the replayed session",
+    ),
+    (
+        Action::Focus,
+        "focus",
+        "f",
+        "Show only the selection and its relatives",
+    ),
+    (Action::Watch, "watch", "w", "Mark the selection as watched"),
+    (Action::Follow, "follow", "F", "Keep the selection in view"),
+    (Action::Io, "io", "O", "Show disk I/O columns"),
+    (Action::Columns, "columns", "o", "Pick columns"),
+    (
+        Action::FullCommand,
+        "full_command",
+        "ctrl-e",
+        "Show full command lines in place of names and back",
+    ),
+    (
+        Action::ScrollLeft,
+        "scroll_left",
+        "left",
+        "Scroll command lines to the left",
+    ),
+    (
+        Action::ScrollRight,
+        "scroll_right",
+        "right",
+        "Scroll command lines to the right",
+    ),
+    (Action::Renice, "renice", "R", "Renice processes by name"),
+    (Action::Sched, "sched", "S", "Pick a scheduling policy"),
+    (
+        Action::Ionice,
+        "ionice",
+        "ctrl-o",
+        "Pick an I/O scheduling class",
+    ),
+    (
+        Action::Priority,
+        "priority",
+        "E",
+        "Change priority and cores",
+    ),
+    (Action::Throttle, "throttle", "T", "Cap CPU usage"),
+    (Action::MemoryCap, "memory_cap", "B", "Cap memory"),
+    (Action::Port, "port", "P", "Find the process holding a port"),
+    (
+        Action::Command,
+        "command",
+        "!",
+        "Run a shell command on the selection",
+    ),
+    (
+        Action::CommandLine,
+        "command_line",
+        ":",
+        "Type a syskill command",
+    ),
+    (
+        Action::ReadOnly,
+        "read_only",
+        "ctrl-w",
+        "Stop or allow signalling and changing processes",
+    ),
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS
+            .iter()
+            .find(|&&(_, action, _, _)| action == name)
+            .map(|&(action, _, _, _)| action)
+    }
+}
+
+/// A key with the modifiers that tell it apart; Shift is part of the character.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+/// Names of keys that are not characters.
+const NAMED_KEYS: [(&str, KeyCode); 14] = [
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl Key {
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            modifiers: event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    /// E.g. `x`, `space`, `pageup` or `ctrl-d`.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            if let Some(key) = rest.strip_prefix("ctrl-").filter(|key| !key.is_empty()) {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = key;
+            } else if let Some(key) = rest.strip_prefix("alt-").filter(|key| !key.is_empty()) {
+                modifiers |= KeyModifiers::ALT;
+                rest = key;
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(key), None) => KeyCode::Char(key),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|&&(name, _)| name == rest)
+                .map(|&(_, code)| code)
+                .ok_or_else(|| format!("`{text}` is not a key"))?,
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(key) => write!(f, "{key}"),
+            code => match NAMED_KEYS.iter().find(|&&(_, named)| named == code) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+/// Keys separated by spaces, e.g. `g g`.
+fn parse_sequence(text: &str) -> Result<Vec<Key>, String> {
+    let keys = text
+        .split_whitespace()
+        .map(Key::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        // A lone space is the space key.
+        return if text.is_empty() {
+            Err("an empty key".to_string())
+        } else {
+            Ok(vec![Key::parse("space")?])
+        };
+    }
+    Ok(keys)
+}
+
+/// What a sequence of keys stands for.
+enum Lookup {
+    Action(Action),
+    /// The start of a longer sequence, and the action of the keys so far, if any.
+    Prefix(Option<Action>),
+    Nothing,
+}
+
+/// The bindings in effect: those of the config file, then the defaults of keys it leaves alone.
+#[derive(Default)]
+pub struct KeyMap {
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+impl KeyMap {
+    /// Binds the actions named in `keys`, the `[keys]` config table, and the saved filters with
+    /// a key in `filter_keys`, by name in the order of `[filters]`, in addition to the defaults.
+    pub fn new(
+        keys: &BTreeMap<String, String>,
+        filter_keys: &[(&str, Option<&str>)],
+    ) -> Result<Self, String> {
+        let mut bindings: Vec<(Vec<Key>, Action)> = Vec::new();
+        let named = keys.iter().map(|(name, sequence)| {
+            Action::from_name(name)
+                .map(|action| (name.as_str(), sequence.as_str(), action))
+                .ok_or_else(|| format!("unknown action `{name}` in [keys]"))
+        });
+        let filters = filter_keys
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &(name, key))| {
+                key.map(|key| Ok((name, key, Action::SavedFilter(index))))
+            });
+        for binding in named.chain(filters) {
+            let (name, sequence, action) = binding?;
+            let keys = parse_sequence(sequence).map_err(|e| format!("{e}, for {name}"))?;
+            if bindings.iter().any(|(bound, _)| *bound == keys) {
+                return Err(format!("`{sequence}` is bound to more than one action"));
+            }
+            bindings.push((keys, action));
+        }
+        for &(action, _, defaults, _) in &ACTIONS {
+            for sequence in defaults.split('|') {
+                let keys = parse_sequence(sequence).expect("default keys are valid");
+                if !bindings.iter().any(|(bound, _)| *bound == keys) {
+                    bindings.push((keys, action));
+                }
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    fn lookup(&self, keys: &[Key]) -> Lookup {
+        let exact = self
+            .bindings
+            .iter()
+            .find(|(bound, _)| bound == keys)
+            .map(|&(_, action)| action);
+        let longer = self
+            .bindings
+            .iter()
+            .any(|(bound, _)| bound.len() > keys.len() && bound.starts_with(keys));
+        match (exact, longer) {
+            (_, true) => Lookup::Prefix(exact),
+            (Some(action), false) => Lookup::Action(action),
+            (None, false) => Lookup::Nothing,
+        }
+    }
+
+    /// The keys of `action`, e.g. `g g, home`.
+    pub fn describe(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .filter(|&&(_, bound)| bound == action)
+            .map(|(keys, _)| {
+                keys.iter()
+                    .map(Key::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The key map, and the keys typed so far of a sequence.
+#[derive(Default)]
+pub struct Keys {
+    pub map: KeyMap,
+    pending: Vec<Key>,
+    since: Option<Instant>,
+}
+
+impl Keys {
+    pub fn new(map: KeyMap) -> Self {
+        Self {
+            map,
+            ..Self::default()
+        }
+    }
+
+    /// The actions `key` completes: usually one or none, two when it ends a sequence that ran
+    /// out and starts another action.
+    pub fn press(&mut self, key: Key, now: Instant) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if key.code == KeyCode::Esc && !self.pending.is_empty() {
+            self.pending.clear();
+            return actions;
+        }
+        self.pending.push(key);
+        loop {
+            match self.map.lookup(&self.pending) {
+                Lookup::Action(action) => {
+                    self.pending.clear();
+                    actions.push(action);
+                }
+                Lookup::Prefix(_) => self.since = Some(now),
+                Lookup::Nothing if self.pending.len() > 1 => {
+                    // The keys before run what they run on their own; this one starts over.
+                    let last = self.pending.pop().unwrap_or(key);
+                    if let Lookup::Prefix(Some(action)) = self.map.lookup(&self.pending) {
+                        actions.push(action);
+                    }
+                    self.pending = vec![last];
+                    continue;
+                }
+                Lookup::Nothing => self.pending.clear(),
+            }
+            return actions;
+        }
+    }
+
+    /// The action of a sequence left unfinished for `SEQUENCE_TIMEOUT`, like `g` waiting for a
+    /// second `g`.
+    pub fn expire(&mut self, now: Instant) -> Option<Action> {
+        let since = self.since?;
+        if self.pending.is_empty() || now - since < SEQUENCE_TIMEOUT {
+            return None;
+        }
+        self.since = None;
+        let pending = std::mem::take(&mut self.pending);
+        match self.map.lookup(&pending) {
+            Lookup::Prefix(action) => action,
+            _ => None,
+        }
+    }
+
+    /// The keys typed so far of a sequence, e.g. `g`.
+    pub fn pending(&self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| {
+            self.pending
+                .iter()
+                .map(Key::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! syskill: finding and terminating processes.
+//!
+//! The `syskill` binary is a thin wrapper around this library. [`ProcessSnapshot`] and
+//! [`KillRequest`] collect and signal processes without any terminal UI, while [`App`] holds the
+//! state of the interactive table drawn by [`ui`] and driven by [`input::run`].
+
+mod accessibility;
+mod affinity;
+mod alert;
+pub mod app;
+pub mod audit;
+mod breakdown;
+mod changes;
+pub mod cli;
+mod clipboard;
+mod cmdline;
+mod collector;
+pub mod config;
+mod connector;
+mod consumers;
+mod container;
+#[cfg(unix)]
+mod control;
+pub mod crash;
+mod dependents;
+mod details;
+pub mod diff;
+mod ephemeral;
+mod error;
+mod escalate;
+pub mod events;
+mod exits;
+pub mod export;
+mod fds;
+mod fuzzy;
+mod gpu;
+pub mod graphics;
+mod group;
+mod history;
+mod hugepages;
+mod hunt;
+pub mod hyperlink;
+pub mod i18n;
+pub mod input;
+mod ionice;
+mod keymap;
+mod limits;
+pub mod logging;
+pub mod metrics;
+mod numa;
+mod oom;
+mod orphans;
+mod outcomes;
+pub mod plugin;
+mod ports;
+mod power;
+mod pressure;
+pub mod proc;
+mod procfs;
+mod query;
+mod queue;
+mod redact;
+mod renice;
+pub mod replay;
+pub mod report;
+mod respawn;
+pub mod rpc;
+pub mod rules;
+mod sched;
+#[cfg(unix)]
+pub mod session;
+mod signal;
+pub mod snapshot;
+pub mod source;
+mod summary;
+mod suspects;
+pub mod template;
+mod terminal;
+mod threads;
+mod throttle;
+mod tools;
+mod tree;
+pub mod ui;
+pub mod units;
+pub mod update;
+pub mod users;
+mod watchdog;
+#[cfg(windows)]
+mod win32;
+
+pub use app::App;
+pub use proc::{KillRequest, ProcessKey, ProcessSnapshot};
+
+/// CPU usage (percent) at which a process is flagged as critical.
+pub const CPU_CRITICAL: f32 = 80.0;
+/// Change in CPU usage (percentage points) between refreshes that counts as rising or falling.
+pub const CPU_TREND: f32 = 5.0;
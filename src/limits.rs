@@ -0,0 +1,115 @@
+//! System-wide limits that cause outages without showing in CPU or memory usage: open files
+//! against `fs.file-max` and threads against `kernel.threads-max`. Linux only.
+
+use std::collections::HashMap;
+
+/// Processes holding at least this share of a system-wide limit are flagged in the table.
+pub const HEAVY_SHARE: f64 = 0.1;
+/// Share of a limit in use at which the summary line shows it as critical.
+pub const CRITICAL_SHARE: f64 = 0.8;
+
+#[derive(Clone, Copy)]
+pub struct Usage {
+    pub used: u64,
+    pub max: u64,
+}
+
+impl Usage {
+    pub fn share(self) -> f64 {
+        self.used as f64 / self.max.max(1) as f64
+    }
+
+    /// Whether the limit is too high to ever be reached, like the default `fs.file-max` of
+    /// 64-bit kernels.
+    pub fn unlimited(self) -> bool {
+        self.max >= 1 << 62
+    }
+}
+
+pub struct Limits {
+    pub open_files: Usage,
+    pub threads: Usage,
+}
+
+#[cfg(target_os = "linux")]
+pub fn read() -> Option<Limits> {
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    // Allocated handles, free handles and the maximum.
+    let file_nr = read("/proc/sys/fs/file-nr")?;
+    let mut file_nr = file_nr.split_whitespace().map(str::parse::<u64>);
+    let open_files = match (file_nr.next(), file_nr.next(), file_nr.next()) {
+        (Some(Ok(used)), Some(_), Some(Ok(max))) => Usage { used, max },
+        _ => return None,
+    };
+    // The fourth field is `running/total` scheduling entities, i.e. threads.
+    let threads = read("/proc/loadavg")?
+        .split_whitespace()
+        .nth(3)?
+        .split_once('/')?
+        .1
+        .parse()
+        .ok()?;
+    let threads_max = read("/proc/sys/kernel/threads-max")?.trim().parse().ok()?;
+    Some(Limits {
+        open_files,
+        threads: Usage {
+            used: threads,
+            max: threads_max,
+        },
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read() -> Option<Limits> {
+    None
+}
+
+/// Flags like `fds 12%` for the processes holding at least [`HEAVY_SHARE`] of a limit, by PID.
+#[cfg(target_os = "linux")]
+pub fn heavy_processes(limits: &Limits) -> HashMap<u32, Vec<String>> {
+    let count = |path: String| std::fs::read_dir(path).map_or(0, |entries| entries.count() as u64);
+    // No process can hold more files than `fs.nr_open`, so skip reading every descriptor table
+    // when that would not be enough.
+    let nr_open: u64 = std::fs::read_to_string("/proc/sys/fs/nr_open")
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(u64::MAX);
+    let check_files = (limits.open_files.max as f64 * HEAVY_SHARE) <= nr_open as f64;
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return HashMap::new();
+    };
+    let mut heavy = HashMap::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(pid) = name.to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        let mut flags = Vec::new();
+        for (label, dir, limit, check) in [
+            ("fds", "fd", limits.open_files, check_files),
+            ("threads", "task", limits.threads, true),
+        ] {
+            if !check {
+                continue;
+            }
+            let used = count(format!("/proc/{pid}/{dir}"));
+            let share = Usage {
+                used,
+                max: limit.max,
+            }
+            .share();
+            if share >= HEAVY_SHARE {
+                flags.push(format!("{label} {:.0}%", share * 100.0));
+            }
+        }
+        if !flags.is_empty() {
+            heavy.insert(pid, flags);
+        }
+    }
+    heavy
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn heavy_processes(_limits: &Limits) -> HashMap<u32, Vec<String>> {
+    HashMap::new()
+}
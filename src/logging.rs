@@ -0,0 +1,112 @@
+//! The log of what syskill did, for finding out what went wrong after a crash or odd behavior:
+//! how long refreshes took, the actions run, the signals sent and the errors shown.
+//!
+//! Events are written with `tracing` to `$XDG_STATE_HOME/syskill/syskill.<date>.log`
+//! (`~/.local/state/syskill` when unset), a new file every day of which the last week is kept.
+//! The latest lines are also kept in memory for the log pane of the table.
+
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// How many lines the log pane can show.
+const KEPT_LINES: usize = 1000;
+/// How many days of logs are kept.
+const KEPT_FILES: usize = 7;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static DIRECTORY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Parses a level as written in `log_level` and `--log-level`: `off`, `error`, `warn`, `info`,
+/// `debug` or `trace`.
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level.parse().map_err(|_| {
+        format!("unknown log level {level:?}, expected off, error, warn, info, debug or trace")
+    })
+}
+
+/// Starts logging events up to `level`. Only the log pane gets them when the log file cannot be
+/// created, and nothing does at `off`.
+pub fn init(level: LevelFilter) {
+    if level == LevelFilter::OFF {
+        return;
+    }
+    let file = directory().and_then(|directory| {
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("syskill")
+            .filename_suffix("log")
+            .max_log_files(KEPT_FILES)
+            .build(&directory)
+            .ok()?;
+        let _ = DIRECTORY.set(directory);
+        Some(appender)
+    });
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .with_writer(match file {
+            Some(file) => BoxMakeWriter::new(Recent.and(file)),
+            None => BoxMakeWriter::new(Recent),
+        })
+        .try_init();
+}
+
+/// `$XDG_STATE_HOME/syskill`, or `~/.local/state/syskill`, where syskill keeps its log files and
+/// the state it leaves behind.
+pub fn directory() -> Option<PathBuf> {
+    let state = match std::env::var_os("XDG_STATE_HOME") {
+        Some(state) if !state.is_empty() => PathBuf::from(state),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state.join("syskill"))
+}
+
+/// Where the log files are written; `None` while nothing is.
+pub fn file_directory() -> Option<&'static PathBuf> {
+    DIRECTORY.get()
+}
+
+/// The latest lines of the log, the oldest first.
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Writes the formatted events to [`RECENT`], every one in a single write.
+struct Recent;
+
+impl io::Write for Recent {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if recent.len() == KEPT_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MakeWriter<'_> for Recent {
+    type Writer = Recent;
+
+    fn make_writer(&self) -> Recent {
+        Recent
+    }
+}
@@ -1,464 +1,404 @@
-use crossterm::{
-    event::{read, Event, KeyCode, KeyEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode},
+use clap::Parser;
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
+use std::time::Duration;
+use sysinfo::System;
+use syskill::cli::{Cli, Command};
+use syskill::export::{self, ExportFormat};
+use syskill::report::{Report, ReportFormat};
+#[cfg(unix)]
+use syskill::session;
+use syskill::template::Template;
+use syskill::units::Units;
+use syskill::{
+    audit, config, crash, diff, events, input, logging, plugin, proc, replay, rpc, rules, snapshot,
+    update, users, CPU_CRITICAL,
 };
-use ratatui::{prelude::*, style::palette::tailwind, widgets::*, Terminal};
-use std::io::{self, Stdout};
-use sysinfo::{Pid, System};
 
-const PALETTES: [tailwind::Palette; 4] = [
-    tailwind::PURPLE,
-    tailwind::EMERALD,
-    tailwind::INDIGO,
-    tailwind::RED,
-];
-
-struct TableColors {
-    buffer_bg: Color,
-    header_bg: Color,
-    header_fg: Color,
-    row_fg: Color,
-    selected_style_fg: Color,
-    normal_row_color: Color,
-    alt_row_color: Color,
-    footer_border_color: Color,
-}
+fn main() -> ExitCode {
+    // Before `--sort` and the config file, which may name their columns.
+    plugin::register_built_in();
+    let cli = Cli::parse();
+    crash::install(cli.crash_include_names);
+
+    let user = match users::UserFilter::from_cli(cli.user.as_deref(), cli.mine) {
+        Ok(user) => user,
+        Err(e) => {
+            eprintln!("syskill: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
 
-impl TableColors {
-    const fn new(color: &tailwind::Palette) -> Self {
-        Self {
-            buffer_bg: tailwind::SLATE.c950,
-            header_bg: color.c900,
-            header_fg: tailwind::SLATE.c200,
-            row_fg: tailwind::SLATE.c200,
-            selected_style_fg: color.c400,
-            normal_row_color: tailwind::SLATE.c950,
-            alt_row_color: tailwind::SLATE.c900,
-            footer_border_color: color.c400,
+    let config = match config::Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("syskill: {e}");
+            return ExitCode::FAILURE;
         }
+    };
+    if let Some(Err(e)) = cli.profile.as_deref().map(|name| config.profile(name)) {
+        eprintln!("syskill: {e}");
+        return ExitCode::FAILURE;
     }
-}
-
-#[derive(Clone)]
-struct Data {
-    name: String,
-    pid: String,
-    cpu_usage: String,
-    memory: String,
-}
+    // Checked when the config file was loaded.
+    let level = cli
+        .log_level
+        .or_else(|| logging::parse_level(&config.log_level).ok())
+        .unwrap_or(tracing::level_filters::LevelFilter::INFO);
+    logging::init(level);
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "started");
+
+    let units = cli.units(&config);
+    let result = match cli.command {
+        None if cli.rpc => rpc::run(user, cli.read_only),
+        None if cli.kill.is_some() && cli.read_only && !cli.dry_run => {
+            eprintln!("syskill: --read-only does not send signals; add --dry-run to list them");
+            return ExitCode::FAILURE;
+        }
+        None if cli.kill.is_some() => {
+            let pattern = cli.kill.as_deref().unwrap_or_default();
+            return run_kill(pattern, cli.signal, cli.dry_run, cli.yes, user.as_ref())
+                .unwrap_or_else(|e| {
+                    eprintln!("syskill: {e}");
+                    ExitCode::FAILURE
+                });
+        }
+        None if cli.export.is_some() => {
+            let path = cli.export.as_deref().unwrap_or(std::path::Path::new("-"));
+            let format = cli
+                .export_format
+                .unwrap_or_else(|| ExportFormat::from_path(path));
+            run_export(path, format, cli.filter.as_deref(), user.as_ref(), units)
+        }
+        None if cli.events.as_deref() == Some(std::path::Path::new("-")) => {
+            let interval = cli.refresh_interval.unwrap_or(config.refresh_interval);
+            let interval = Duration::try_from_secs_f64(interval)
+                .ok()
+                .filter(|interval| !interval.is_zero())
+                .unwrap_or(Duration::from_secs(2));
+            events::run(std::path::Path::new("-"), interval, CPU_CRITICAL, user)
+        }
+        #[cfg(unix)]
+        None if cli.host.is_some() => {
+            let host = cli.host.as_deref().unwrap_or_default();
+            let interval = cli.refresh_interval.unwrap_or(config.refresh_interval);
+            let interval = Duration::try_from_secs_f64(interval)
+                .ok()
+                .filter(|interval| !interval.is_zero())
+                .unwrap_or(Duration::from_secs(2));
+            session::RemoteSession::ssh(host, interval)
+                .and_then(|remote| input::run(&cli, Some(remote), None, user, config))
+        }
+        None => input::run(&cli, None, None, user, config),
+        #[cfg(unix)]
+        Some(Command::Serve {
+            interval,
+            ref record,
+            stdio: true,
+            ..
+        }) => session::serve_stdio(interval, record.as_deref(), user.as_ref(), cli.read_only),
+        #[cfg(unix)]
+        Some(Command::Serve {
+            ref socket,
+            interval,
+            ref record,
+            ..
+        }) => session::serve(
+            &socket.clone().unwrap_or_else(session::default_socket),
+            interval,
+            record.as_deref(),
+            user.as_ref(),
+            cli.read_only,
+        ),
+        #[cfg(unix)]
+        Some(Command::Attach { ref socket }) => {
+            let socket = socket.clone().unwrap_or_else(session::default_socket);
+            session::RemoteSession::connect(&socket).and_then(|remote| {
+                remote.wait_for_snapshot(Duration::from_secs(5));
+                input::run(&cli, Some(remote), None, user, config)
+            })
+        }
+        Some(Command::Replay { ref log }) => replay::Replay::load(log)
+            .and_then(|replay| input::run(&cli, None, Some(replay), user, config)),
+        Some(Command::Version) => {
+            update::about_lines()
+                .iter()
+                .for_each(|line| println!("{line}"));
+            if cli.check_updates {
+                println!("{}", update::check().describe());
+            }
+            Ok(())
+        }
+        Some(Command::Record {
+            output,
+            interval,
+            count,
+        }) => snapshot::record(&output, interval, count, user.as_ref()),
+        Some(Command::Diff {
+            before,
+            after,
+            json,
+            min_cpu_delta,
+            min_memory_delta,
+        }) => run_diff(&before, &after, json, min_cpu_delta, min_memory_delta),
+        Some(Command::Report {
+            format,
+            output,
+            top,
+            template,
+        }) => {
+            let template = match template.map(|name| config.template(&name)).transpose() {
+                Ok(template) => template.map(|(template, _)| template),
+                Err(e) => {
+                    eprintln!("syskill: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            run_report(
+                format,
+                template.as_ref(),
+                output.as_deref(),
+                top,
+                user.as_ref(),
+                units,
+            )
+        }
+        Some(Command::History {
+            since,
+            pid,
+            ref name,
+            failed,
+            json,
+        }) => {
+            let filter = audit::Filter {
+                since: since.map(|age| snapshot::unix_now().saturating_sub(age)),
+                pid,
+                name: name.clone(),
+                owner: user.as_ref().map(|user| user.name.clone()),
+                failed,
+            };
+            run_history(&filter, json)
+        }
+        Some(Command::Check { rules }) => {
+            return run_check(&rules, user.as_ref()).unwrap_or_else(|e| {
+                eprintln!("syskill: {e}");
+                ExitCode::FAILURE
+            })
+        }
+    };
 
-impl Data {
-    const fn ref_array(&self) -> [&String; 4] {
-        [&self.name, &self.pid, &self.cpu_usage, &self.memory]
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("syskill: {e}");
+            ExitCode::FAILURE
+        }
     }
 }
 
-#[derive(Debug)]
-enum AppState {
-    ProcessMode,
-    SearchMode,
-}
-
-struct App {
-    state: TableState,
-    items: Vec<Data>,
-    scroll_state: ScrollbarState,
-    ctx: System,
-    colors: TableColors,
-    color_index: usize,
-    show_popup: bool,
-    mode: AppState,
-    input: String,
-    messages: Vec<String>,
-    character_index: usize,
+fn run_diff(
+    before: &std::path::Path,
+    after: &std::path::Path,
+    json: bool,
+    min_cpu_delta: f32,
+    min_memory_delta_pct: f64,
+) -> io::Result<()> {
+    let options = diff::DiffOptions {
+        min_cpu_delta,
+        min_memory_delta_pct,
+    };
+    let deltas = diff::compare(
+        &snapshot::read_session(before)?,
+        &snapshot::read_session(after)?,
+        &options,
+    );
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&deltas)?);
+    } else {
+        diff::print_table(&deltas);
+    }
+    Ok(())
 }
 
-const ITEM_HEIGHT: usize = 4;
-
-impl App {
-    fn new() -> Self {
-        Self {
-            state: TableState::default().with_selected(0),
-            scroll_state: ScrollbarState::default(),
-            items: Vec::new(),
-            ctx: System::new_all(),
-            colors: TableColors::new(&PALETTES[0]),
-            color_index: 0,
-            show_popup: false,
-            mode: AppState::ProcessMode,
-            input: String::new(),
-            messages: Vec::new(),
-            character_index: 0,
+fn run_history(filter: &audit::Filter, json: bool) -> io::Result<()> {
+    let Some(path) = audit::path() else {
+        return Err(io::Error::other("no audit log without a home directory"));
+    };
+    let mut stdout = io::stdout().lock();
+    for entry in audit::read(&path)?
+        .iter()
+        .filter(|entry| filter.matches(entry))
+    {
+        if json {
+            serde_json::to_writer(&mut stdout, entry)?;
+            writeln!(stdout)?;
+        } else {
+            writeln!(stdout, "{}", audit::describe(entry))?;
         }
     }
+    Ok(())
+}
 
-    pub fn clean(&mut self) {
-        self.items = Vec::new();
+/// Exits with failure if any rule is violated.
+fn run_check(
+    rules: &std::path::Path,
+    user: Option<&users::UserFilter>,
+) -> Result<ExitCode, String> {
+    let rules = rules::Rules::load(rules)?;
+    let mut system = System::new_all();
+    // CPU usage is computed from the difference between two refreshes.
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu();
+    system.refresh_processes();
+
+    let violations = rules.evaluate(&snapshot::Snapshot::capture(&system, user));
+    for violation in &violations {
+        println!("{}: {}", violation.rule, violation.message);
     }
-
-    pub fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.character_index.saturating_sub(1);
-        self.character_index = self.clamp_cursor(cursor_moved_left);
+    if violations.is_empty() {
+        println!("ok: {} rules passed", rules.rules.len());
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
     }
+}
 
-    pub fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.character_index.saturating_add(1);
-        self.character_index = self.clamp_cursor(cursor_moved_right);
+/// Exits with failure if no process matches or any of them could not be signalled.
+fn run_kill(
+    pattern: &str,
+    signal: sysinfo::Signal,
+    dry_run: bool,
+    yes: bool,
+    user: Option<&users::UserFilter>,
+) -> io::Result<ExitCode> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(users::refresh_kind());
+    let snapshot = snapshot::Snapshot::capture(&system, user);
+    let own_pid = std::process::id();
+    let targets: Vec<_> = proc::matching(&snapshot, pattern)
+        .into_iter()
+        .filter(|process| process.pid != own_pid)
+        .collect();
+    if targets.is_empty() {
+        eprintln!("syskill: no process matches {pattern:?}");
+        return Ok(ExitCode::FAILURE);
     }
 
-    pub fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.input.insert(index, new_char);
-        self.move_cursor_right();
+    for process in &targets {
+        println!("{:>8}  {}", process.pid, process.name);
     }
-
-    /// Returns the byte index based on the character position.
-    ///
-    /// Since each character in a string can be contain multiple bytes, it's necessary to calculate
-    /// the byte index based on the index of the character.
-    pub fn byte_index(&mut self) -> usize {
-        self.input
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.character_index)
-            .unwrap_or(self.input.len())
+    let count = targets.len();
+    let processes = if count == 1 { "process" } else { "processes" };
+    if dry_run {
+        println!("would send {signal} to {count} {processes}");
+        return Ok(ExitCode::SUCCESS);
     }
-
-    pub fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+    if !yes {
+        if !io::stdin().is_terminal() {
+            eprintln!("syskill: not signalling {count} {processes} without --yes");
+            return Ok(ExitCode::FAILURE);
+        }
+        print!("Send {signal} to {count} {processes}? [y/N] ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("nothing was sent");
+            return Ok(ExitCode::FAILURE);
         }
     }
 
-    pub fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.chars().count())
-    }
-
-    pub fn reset_cursor(&mut self) {
-        self.character_index = 0;
-    }
-
-    pub fn submit_message(&mut self) {
-        self.messages.push(self.input.clone());
-
-        // TODO (ozerova): Add search function
-        self.search();
-
-        self.input.clear();
-        self.reset_cursor();
-    }
-
-    pub fn search(&mut self) {
-        let msg = self.input.clone();
-        let procn = self.items.clone();
-
-        let mut parsed_processes = Vec::new();
-        procn.iter().for_each(|proc| {
-            if proc.name.contains(&msg) {
-                parsed_processes.push(proc.clone());
-            }
-        });
-
-        self.items = parsed_processes.clone();
-    }
-
-    pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.items.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+    let mut failed = false;
+    for process in targets {
+        let request = proc::KillRequest::new(process.pid, signal).expecting(&process.key());
+        let result = request.send(&mut system);
+        let outcome = match &result {
+            Ok(_) => audit::SENT,
+            Err(e) => e.message.as_str(),
         };
-        self.state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
-    }
-
-    pub fn previous(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.items.len() - 1
-                } else {
-                    i - 1
-                }
+        audit::record(process.pid, &process.name, &process.user, signal, outcome);
+        match result {
+            Ok(killed) => println!("{}", killed.message),
+            Err(e) if e.denied && cfg!(unix) => {
+                eprintln!("syskill: {e}; as root: {}", request.sudo_command());
+                failed = true;
+            }
+            Err(e) => {
+                eprintln!("syskill: {e}");
+                failed = true;
             }
-            None => 0,
-        };
-        self.state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
-    }
-
-    pub fn set_colors(&mut self) {
-        self.colors = TableColors::new(&PALETTES[self.color_index]);
-    }
-
-    pub fn set_scroll(&mut self) {
-        self.scroll_state = ScrollbarState::new((self.items.len() - 1) * ITEM_HEIGHT);
-    }
-
-    pub fn get_proc(&mut self) {
-        self.ctx.refresh_cpu();
-        let system = &self.ctx;
-        let processes = system.processes();
-        let mut data_vec = Vec::new();
-
-        for (_index, (pid, process)) in processes.iter().enumerate() {
-            let name = process.name();
-            let cpu_usage = process.cpu_usage().to_string();
-            let memory = process.memory().to_string();
-            let pid = pid.to_string();
-            self.items.push(Data {
-                name: name.to_string().clone(),
-                pid: pid.clone(),
-                cpu_usage: cpu_usage.clone(),
-                memory: memory.clone(),
-            });
-            data_vec.push(vec![name.to_string(), pid, cpu_usage, memory]);
-        }
-
-        self.items
-            .sort_by_key(|obj| obj.pid.parse::<i32>().unwrap());
-    }
-
-    pub fn delete_proc(&mut self) {
-        let row = &self.items[self.state.selected().unwrap() as usize].pid;
-        let s = System::new_all();
-        if let Some(process) = s.process(Pid::from(row.parse::<usize>().unwrap())) {
-            process.kill();
         }
-        self.refresh();
     }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
 
-    pub fn refresh(&mut self) {
-        self.ctx = System::new_all();
-        self.clean();
-        self.get_proc();
-        self.set_scroll();
+fn run_export(
+    path: &std::path::Path,
+    format: ExportFormat,
+    filter: Option<&str>,
+    user: Option<&users::UserFilter>,
+    units: Units,
+) -> io::Result<()> {
+    let mut system = System::new_all();
+    let units = Units {
+        cores: system.cpus().len(),
+        ..units
+    };
+    // CPU usage is computed from the difference between two refreshes.
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu();
+    system.refresh_processes();
+
+    let mut snapshot = snapshot::Snapshot::capture(&system, user);
+    snapshot.processes.sort_by_key(|p| p.pid);
+    if let Some(filter) = filter {
+        export::retain_matching(&mut snapshot, filter, units)
+            .map_err(|e| io::Error::other(format!("--filter: {e}")))?;
     }
-
-    pub fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) {
-        let widths = [
-            Constraint::Length(25),
-            Constraint::Length(5),
-            Constraint::Length(10),
-            Constraint::Length(10),
-        ];
-        let mut rows_pusher: Vec<Row> = Vec::new();
-        self.items.iter().for_each(|r| {
-            rows_pusher.push(Row::new(vec![
-                r.name.clone(),
-                r.pid.clone(),
-                r.cpu_usage.clone(),
-                r.memory.clone(),
-            ]))
-        });
-
-        let rows = self.items.iter().enumerate().map(|(i, data)| {
-            let color = match i % 2 {
-                0 => self.colors.normal_row_color,
-                _ => self.colors.alt_row_color,
-            };
-            let item = data.ref_array();
-            item.into_iter()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(self.colors.row_fg).bg(color))
-                .height(2)
-        });
-
-        let header_style = Style::default()
-            .fg(self.colors.header_fg)
-            .bg(self.colors.header_bg);
-        let selected_style = Style::default()
-            .add_modifier(Modifier::REVERSED)
-            .fg(self.colors.selected_style_fg);
-        let table = Table::new(rows, widths)
-            .block(Block::new().title("Processes"))
-            .highlight_style(selected_style)
-            .bg(self.colors.buffer_bg)
-            //.highlight_symbol(">>")
-            .block(Block::new())
-            .highlight_spacing(HighlightSpacing::Always)
-            .block(
-                Block::bordered()
-                    .border_type(BorderType::Double)
-                    .border_style(Style::new().fg(self.colors.footer_border_color)),
-            )
-            .header(
-                Row::new(vec![
-                    "NAME".to_string(),
-                    "PID".to_string(),
-                    "CPU USAGE".to_string(),
-                    "MEMORY".to_string(),
-                ])
-                .style(header_style),
-            );
-
-        terminal
-            .draw(|frame| {
-                let area = frame.size();
-
-                let vertical = Layout::vertical([
-                    Constraint::Length(1),
-                    Constraint::Min(3)
-                ]);
-                let [help_area, table_area] = vertical.areas(area);
-
-                frame.render_stateful_widget(table, table_area, &mut self.state.clone());
-                frame.set_cursor(0, 0);
-
-                let msg = vec![
-                    "\n".into(),
-                    "Use ".into(),
-                    "j".bold(),
-                    " and ".into(),
-                    "k".bold(),
-                    " to scroll up and down. Press ".into(),
-                    "r".bold(),
-                    " to refresh process list, and press ".into(),
-                    "d".bold(),
-                    " to delete selected process. ".into(),
-                    "Press ".into(),
-                    "/".bold(),
-                    " to toggle search, press ".into(),
-                    "enter".bold(),
-                    " to confirm search. Press ".into(),
-                    "q".bold(),
-                    " to exit.".into(),
-                ];
-
-                let text = Text::from(Line::from(msg));
-                frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }).style(Style::default()), help_area);
-
-                // Popup logic
-                if self.show_popup {
-                    let block = Block::bordered().title("Search");
-                    let area = centered_rect(60, 20, area);
-
-                    let input = Paragraph::new(self.input.as_str()).style(match self.mode {
-                        AppState::ProcessMode => Style::default(),
-                        AppState::SearchMode => Style::default().fg(Color::Yellow),
-                    });
-
-                    let inner_area = block.inner(area);
-
-                    frame.render_widget(Clear, area); //this clears out the background
-                    frame.render_widget(block, area);
-                    frame.render_widget(input, inner_area);
-                }
-            })
-            .unwrap();
+    let rendered = export::render(&snapshot, format).map_err(io::Error::other)?;
+    if path == std::path::Path::new("-") {
+        print!("{rendered}");
+        Ok(())
+    } else {
+        std::fs::write(path, rendered)
     }
 }
 
-fn main() {
-    enable_raw_mode().unwrap();
-    let stdout = io::stdout();
-    let backend = CrosstermBackend::new(stdout);
-    let options = TerminalOptions {
-        viewport: Viewport::Inline(16),
+fn run_report(
+    format: ReportFormat,
+    template: Option<&Template>,
+    output: Option<&std::path::Path>,
+    top: usize,
+    user: Option<&users::UserFilter>,
+    units: Units,
+) -> io::Result<()> {
+    let mut system = System::new_all();
+    let units = Units {
+        cores: system.cpus().len(),
+        ..units
     };
-    let mut terminal = Terminal::with_options(backend, options).unwrap();
-
-    let mut app = App::new();
-    app.set_colors();
-    app.get_proc();
-    app.set_scroll();
-
-    loop {
-        app.render(&mut terminal);
-
-        if let Ok(Event::Key(key_event)) = read() {
-            if key_event.kind == KeyEventKind::Press {
-                match app.mode {
-                    AppState::ProcessMode => match key_event.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('r') => {
-                            app.refresh();
-                        }
-                        KeyCode::Char('k') => {
-                            app.previous();
-                        }
-                        KeyCode::Char('j') => {
-                            app.next();
-                        }
-                        KeyCode::Char('d') => {
-                            app.delete_proc();
-                        }
-                        KeyCode::Char('/') => {
-                            app.mode = AppState::SearchMode;
-                            app.show_popup = !app.show_popup
-                        }
-                        _ => (),
-                    },
-                    AppState::SearchMode if key_event.kind == KeyEventKind::Press => {
-                        match key_event.code {
-                            KeyCode::Char('/') => {
-                                app.mode = AppState::ProcessMode;
-                                app.show_popup = !app.show_popup
-                            }
-                            KeyCode::Enter => {
-                                app.submit_message();
-                                app.mode = AppState::ProcessMode;
-                                app.show_popup = !app.show_popup
-                            }
-                            KeyCode::Char(to_insert) => {
-                                app.enter_char(to_insert);
-                            }
-                            KeyCode::Backspace => {
-                                app.delete_char();
-                            }
-                            KeyCode::Left => {
-                                app.move_cursor_left();
-                            }
-                            KeyCode::Right => {
-                                app.move_cursor_right();
-                            }
-                            _ => (),
-                        }
-                    }
-                    AppState::SearchMode => {}
-                }
-            }
+    // CPU usage is computed from the difference between two refreshes.
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu();
+    system.refresh_processes();
+
+    let report = Report::collect(&system, &[], None, top, user);
+    let rendered = match template {
+        Some(template) => report
+            .render_template(template, units)
+            .map_err(io::Error::other)?,
+        None => report.render(format, units),
+    };
+    match output {
+        Some(path) => std::fs::write(path, rendered),
+        None => {
+            print!("{rendered}");
+            Ok(())
         }
     }
-
-    disable_raw_mode().unwrap();
-    terminal.clear().unwrap();
-}
-
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
-
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(popup_layout[1])[1]
 }
@@ -0,0 +1,279 @@
+//! Prometheus metrics of the processes and the system shown in the table (`--metrics-port`).
+//!
+//! A small HTTP server on a thread of its own answers `GET /metrics` with the text exposition
+//! format, rendered from the last snapshot the table showed, so monitoring scrapes the same
+//! figures without a second agent collecting them.
+
+use crate::snapshot::Snapshot;
+use crate::summary::Summary;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Scrapers that stop sending mid-request are dropped after this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// The most read of a request, request line and headers together; a scrape takes a few hundred
+/// bytes.
+const MAX_REQUEST: u64 = 8 * 1024;
+
+/// Where to serve the metrics: a port on the loopback interface, or an address and port.
+#[derive(Clone, Copy, Debug)]
+pub struct MetricsAddress(pub SocketAddr);
+
+impl FromStr for MetricsAddress {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if let Ok(port) = text.parse::<u16>() {
+            return Ok(Self(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)));
+        }
+        text.parse().map(Self).map_err(|_| {
+            format!("expected a port or an address and port, e.g. 0.0.0.0:9100, not {text}")
+        })
+    }
+}
+
+/// The metrics last rendered, shared with the server thread.
+pub struct Exporter {
+    page: Arc<Mutex<String>>,
+}
+
+impl Exporter {
+    /// Starts serving on `address`; nothing is served until the first [`Exporter::update`].
+    pub fn serve(address: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let page = Arc::new(Mutex::new(String::new()));
+        let shared = Arc::clone(&page);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A scraper misbehaving, or slow, is no reason to keep the others waiting.
+                let page = Arc::clone(&shared);
+                thread::spawn(move || answer(stream, &page));
+            }
+        });
+        Ok(Self { page })
+    }
+
+    /// Renders the metrics of `snapshot`, and of the system if sampled, for the next scrapes.
+    pub fn update(&self, snapshot: &Snapshot, summary: Option<&Summary>) {
+        let page = render(snapshot, summary);
+        *self.page.lock().unwrap_or_else(|e| e.into_inner()) = page;
+    }
+}
+
+/// Reads one request from `stream` and answers it, closing the connection after.
+fn answer(mut stream: TcpStream, page: &Mutex<String>) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST));
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers are of no interest, but unread they could reset the connection.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut parts = request.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let page = page.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            if page.is_empty() {
+                ("503 Service Unavailable", "No snapshot yet\n".to_string())
+            } else {
+                ("200 OK", page)
+            }
+        }
+        (Some("GET"), Some("/")) => ("200 OK", "syskill metrics are at /metrics\n".to_string()),
+        (Some("GET"), _) => ("404 Not Found", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "Only GET is supported\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// The text exposition format of Prometheus.
+fn render(snapshot: &Snapshot, summary: Option<&Summary>) -> String {
+    let mut page = String::new();
+    let mut family =
+        |name: &str, kind: &str, help: &str, samples: &mut dyn Iterator<Item = String>| {
+            let _ = writeln!(page, "# HELP {name} {help}");
+            let _ = writeln!(page, "# TYPE {name} {kind}");
+            for sample in samples {
+                let _ = writeln!(page, "{name}{sample}");
+            }
+        };
+    let labels = |pid: u32, name: &str, user: &str| {
+        format!(
+            "{{pid=\"{pid}\",name=\"{}\",user=\"{}\"}}",
+            escape(name),
+            escape(user)
+        )
+    };
+    let processes = &snapshot.processes;
+
+    family(
+        "syskill_processes",
+        "gauge",
+        "Processes listed.",
+        &mut std::iter::once(format!(" {}", processes.len())),
+    );
+    family(
+        "syskill_process_cpu_percent",
+        "gauge",
+        "CPU usage of the process since the last refresh, in percent of one core.",
+        &mut processes.iter().map(|process| {
+            let labels = labels(process.pid, &process.name, &process.user);
+            format!("{labels} {}", process.cpu_usage)
+        }),
+    );
+    family(
+        "syskill_process_memory_bytes",
+        "gauge",
+        "Resident memory of the process.",
+        &mut processes.iter().map(|process| {
+            let labels = labels(process.pid, &process.name, &process.user);
+            format!("{labels} {}", process.memory)
+        }),
+    );
+    family(
+        "syskill_process_threads",
+        "gauge",
+        "Threads of the process, where known.",
+        &mut processes
+            .iter()
+            .filter(|process| process.threads > 0)
+            .map(|process| {
+                let labels = labels(process.pid, &process.name, &process.user);
+                format!("{labels} {}", process.threads)
+            }),
+    );
+    family(
+        "syskill_process_start_time_seconds",
+        "gauge",
+        "Start time of the process since the Unix epoch.",
+        &mut processes
+            .iter()
+            .filter(|process| process.start_time > 0)
+            .map(|process| {
+                let labels = labels(process.pid, &process.name, &process.user);
+                format!("{labels} {}", process.start_time)
+            }),
+    );
+    // Left out rather than reported as 0 when they were not read.
+    if !snapshot.without_disk {
+        family(
+            "syskill_process_disk_read_bytes_total",
+            "counter",
+            "Bytes the process read from storage since it started.",
+            &mut processes.iter().map(|process| {
+                let labels = labels(process.pid, &process.name, &process.user);
+                format!("{labels} {}", process.disk_read)
+            }),
+        );
+        family(
+            "syskill_process_disk_written_bytes_total",
+            "counter",
+            "Bytes the process wrote to storage since it started.",
+            &mut processes.iter().map(|process| {
+                let labels = labels(process.pid, &process.name, &process.user);
+                format!("{labels} {}", process.disk_written)
+            }),
+        );
+    }
+
+    if let Some(summary) = summary {
+        let gauge = |value: String| std::iter::once(format!(" {value}"));
+        family(
+            "syskill_cpu_percent",
+            "gauge",
+            "CPU usage of the whole machine, in percent of all cores.",
+            &mut gauge(summary.cpu().to_string()),
+        );
+        family(
+            "syskill_memory_used_bytes",
+            "gauge",
+            "Memory in use.",
+            &mut gauge(summary.memory_used.to_string()),
+        );
+        family(
+            "syskill_memory_total_bytes",
+            "gauge",
+            "Installed memory.",
+            &mut gauge(summary.memory_total.to_string()),
+        );
+        family(
+            "syskill_swap_used_bytes",
+            "gauge",
+            "Swap in use.",
+            &mut gauge(summary.swap_used.to_string()),
+        );
+        family(
+            "syskill_swap_total_bytes",
+            "gauge",
+            "Swap space.",
+            &mut gauge(summary.swap_total.to_string()),
+        );
+        family(
+            "syskill_load_average",
+            "gauge",
+            "Load average over the last 1, 5 and 15 minutes.",
+            &mut [
+                ("1m", summary.load.one),
+                ("5m", summary.load.five),
+                ("15m", summary.load.fifteen),
+            ]
+            .into_iter()
+            .map(|(period, load)| format!("{{period=\"{period}\"}} {load}")),
+        );
+        family(
+            "syskill_uptime_seconds",
+            "gauge",
+            "Time since boot.",
+            &mut gauge(summary.uptime.to_string()),
+        );
+    }
+    page
+}
+
+/// Escapes a label value: backslashes, double quotes and line feeds.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endless_requests_are_cut_short() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let page = Mutex::new("syskill_processes 1\n".to_string());
+        // As much as is read, without ever ending the line.
+        let mut request = "GET /metrics".to_string();
+        request.push_str(&" ".repeat(MAX_REQUEST as usize - request.len()));
+        client.write_all(request.as_bytes()).unwrap();
+        let started = std::time::Instant::now();
+        answer(server, &page).unwrap();
+        assert!(started.elapsed() < READ_TIMEOUT);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("syskill_processes 1\n"));
+    }
+}
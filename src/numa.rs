@@ -0,0 +1,100 @@
+//! NUMA topology and per-node memory placement of processes (Linux only).
+//!
+//! Nodes and their cores come from `/sys/devices/system/node`, the memory a process has on each
+//! node from `/proc/<pid>/numa_maps`. A process whose memory sits on one node while it runs on
+//! the cores of another pays for every access with cross-node traffic.
+
+use std::collections::BTreeMap;
+
+pub struct Node {
+    pub id: usize,
+    pub cores: Vec<usize>,
+}
+
+pub struct Topology {
+    pub nodes: Vec<Node>,
+}
+
+impl Topology {
+    /// Reads the topology; `None` when the system has a single node or it cannot be read.
+    #[cfg(target_os = "linux")]
+    pub fn detect() -> Option<Self> {
+        let mut nodes: Vec<Node> = std::fs::read_dir("/sys/devices/system/node")
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let id = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("node")?
+                    .parse()
+                    .ok()?;
+                let list = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+                let cores = crate::affinity::parse_cores(&list)?;
+                Some(Node { id, cores })
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.id);
+        (nodes.len() > 1).then_some(Self { nodes })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn detect() -> Option<Self> {
+        None
+    }
+
+    pub fn node_of_core(&self, core: usize) -> Option<usize> {
+        self.nodes
+            .iter()
+            .find(|node| node.cores.contains(&core))
+            .map(|node| node.id)
+    }
+}
+
+/// Bytes of memory `pid` has on each node, keyed by node id.
+pub fn memory_per_node(pid: u32) -> BTreeMap<usize, u64> {
+    let mut nodes = BTreeMap::new();
+    let Ok(maps) = std::fs::read_to_string(format!("/proc/{pid}/numa_maps")) else {
+        return nodes;
+    };
+    for line in maps.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let page_size = fields
+            .iter()
+            .find_map(|field| field.strip_prefix("kernelpagesize_kB="))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .unwrap_or(4)
+            * 1024;
+        for field in &fields {
+            let Some((node, pages)) = field.strip_prefix('N').and_then(|f| f.split_once('='))
+            else {
+                continue;
+            };
+            if let (Ok(node), Ok(pages)) = (node.parse(), pages.parse::<u64>()) {
+                *nodes.entry(node).or_insert(0) += pages * page_size;
+            }
+        }
+    }
+    nodes
+}
+
+/// The node holding most of the memory of a process.
+pub fn home_node(memory: &BTreeMap<usize, u64>) -> Option<usize> {
+    memory
+        .iter()
+        .max_by_key(|(_, &bytes)| bytes)
+        .map(|(&node, _)| node)
+}
+
+/// Suggests moving `pid` when its memory lives on a different node than the one it runs on.
+pub fn migration_hint(pid: u32, memory: &BTreeMap<usize, u64>, cpu_node: usize) -> Option<String> {
+    let home = home_node(memory)?;
+    if home == cpu_node {
+        return None;
+    }
+    Some(format!(
+        "Runs on node {cpu_node} but most memory is on node {home}. Move the memory with \
+         `migratepages {pid} {home} {cpu_node}` or pin the process with \
+         `taskset`/`numactl --cpunodebind={home}`."
+    ))
+}
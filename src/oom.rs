@@ -0,0 +1,105 @@
+//! Kills by the kernel's OOM killer, read from the kernel log (`/dev/kmsg`), so a process that
+//! vanished from the table is not mistaken for one a person killed. Linux only, and reading the
+//! kernel log usually takes root (see `kernel.dmesg_restrict`).
+
+#[cfg(target_os = "linux")]
+use crate::plugin::{ColumnProvider, Value};
+#[cfg(target_os = "linux")]
+use crate::snapshot::ProcessSample;
+use std::sync::mpsc::Receiver;
+
+pub struct OomKill {
+    pub pid: u32,
+    pub name: String,
+    /// Anonymous memory the process held when it was killed, in bytes.
+    pub anon_rss: Option<u64>,
+}
+
+/// Follows the kernel log from now on in the background, or `None` if it cannot be read.
+#[cfg(target_os = "linux")]
+pub fn watch() -> Option<Receiver<OomKill>> {
+    use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom};
+
+    let mut kmsg = std::fs::File::open("/dev/kmsg").ok()?;
+    // Only kills from now on; older ones are not about the processes in the table.
+    kmsg.seek(SeekFrom::End(0)).ok()?;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(kmsg);
+        let mut record = String::new();
+        loop {
+            record.clear();
+            match reader.read_line(&mut record) {
+                Ok(0) => break,
+                Ok(_) => {}
+                // Records were overwritten before they were read; carry on with the next one.
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => continue,
+                Err(_) => break,
+            }
+            // `priority,sequence,timestamp,flags;message`
+            let Some((_, message)) = record.split_once(';') else {
+                continue;
+            };
+            if let Some(kill) = parse(message) {
+                if sender.send(kill).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Some(receiver)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch() -> Option<Receiver<OomKill>> {
+    None
+}
+
+/// The `oom_score` column: how likely the OOM killer is to pick each process, from
+/// `/proc/<pid>/oom_score`, the highest first. Hidden until picked.
+#[cfg(target_os = "linux")]
+pub struct ScoreColumn;
+
+#[cfg(target_os = "linux")]
+impl ColumnProvider for ScoreColumn {
+    fn name(&self) -> &str {
+        "oom_score"
+    }
+
+    fn title(&self) -> &str {
+        "OOM SCORE"
+    }
+
+    fn width(&self) -> u16 {
+        11
+    }
+
+    fn shown(&self) -> bool {
+        false
+    }
+
+    fn value(&self, process: &ProcessSample) -> Option<Value> {
+        let score = std::fs::read_to_string(format!("/proc/{}/oom_score", process.pid)).ok()?;
+        Some(Value::Number(score.trim().parse().ok()?))
+    }
+}
+
+/// Reads messages like `Out of memory: Killed process 4242 (java) total-vm:…kB, anon-rss:…kB, …`,
+/// also logged for cgroups running out of memory.
+#[cfg(target_os = "linux")]
+fn parse(message: &str) -> Option<OomKill> {
+    let (_, rest) = message.split_once("Killed process ")?;
+    let (pid, rest) = rest.split_once(" (")?;
+    // Names may contain parentheses themselves.
+    let end = rest.find(") total-vm").or_else(|| rest.rfind(')'))?;
+    let anon_rss = rest[end..]
+        .split_once("anon-rss:")
+        .and_then(|(_, rss)| rss.split_once("kB"))
+        .and_then(|(kib, _)| kib.parse::<u64>().ok())
+        .map(|kib| kib * 1024);
+    Some(OomKill {
+        pid: pid.trim().parse().ok()?,
+        name: rest[..end].to_string(),
+        anon_rss,
+    })
+}
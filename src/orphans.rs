@@ -0,0 +1,70 @@
+//! Follow-up on the children of a killed process.
+//!
+//! Killing a parent rarely takes its workers with it: they are re-parented to init (or the
+//! closest subreaper) and keep running, often holding on to ports and files. [`OrphanWatch`]
+//! remembers the children at kill time and reports the ones that survived their parent.
+
+use crate::snapshot::ProcessKey;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessStatus, System};
+
+/// How long to wait for the parent to exit.
+const WATCH_FOR: Duration = Duration::from_secs(5);
+
+pub struct OrphanWatch {
+    parent_pid: Pid,
+    children: Vec<ProcessKey>,
+    started: Instant,
+}
+
+impl OrphanWatch {
+    /// Starts watching the current children of `parent`; `None` if it has none.
+    pub fn start(system: &System, parent: Pid) -> Option<Self> {
+        let children: Vec<ProcessKey> = system
+            .processes()
+            .iter()
+            .filter(|(_, process)| process.parent() == Some(parent))
+            .map(|(pid, process)| ProcessKey {
+                pid: pid.as_u32(),
+                start_time: process.start_time(),
+            })
+            .collect();
+        if children.is_empty() {
+            return None;
+        }
+        Some(Self {
+            parent_pid: parent,
+            children,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn expired(&self) -> bool {
+        self.started.elapsed() > WATCH_FOR
+    }
+
+    /// Once the parent has exited, returns the children that are still running (possibly none).
+    pub fn poll(&self, system: &mut System) -> Option<Vec<(ProcessKey, String)>> {
+        // An exited parent lingers as a zombie until its own parent reaps it.
+        let parent_alive = system.refresh_process(self.parent_pid)
+            && system
+                .process(self.parent_pid)
+                .is_some_and(|process| process.status() != ProcessStatus::Zombie);
+        if parent_alive {
+            return None;
+        }
+        let mut survivors = Vec::new();
+        for &key in &self.children {
+            let pid = Pid::from_u32(key.pid);
+            if !system.refresh_process(pid) {
+                continue;
+            }
+            if let Some(process) = system.process(pid) {
+                if process.start_time() == key.start_time {
+                    survivors.push((key, process.name().to_string()));
+                }
+            }
+        }
+        Some(survivors)
+    }
+}
@@ -0,0 +1,78 @@
+//! What the status line reported this session, such as the signals sent and the ones refused, so
+//! a result that was replaced by the next one before it was read can still be looked up.
+
+use std::collections::VecDeque;
+
+/// How many results the history keeps, the latest last.
+const KEPT_OUTCOMES: usize = 500;
+
+pub struct Outcome {
+    /// Seconds since the Unix epoch when it was first shown.
+    pub shown: u64,
+    pub message: String,
+    /// Whether it was shown as an error.
+    pub failed: bool,
+}
+
+#[derive(Default)]
+pub struct Outcomes {
+    log: VecDeque<Outcome>,
+    /// The notice and the error on the status line when it was last looked at.
+    notice: Option<String>,
+    error: Option<String>,
+}
+
+impl Outcomes {
+    /// Adds the notice and the error of the status line unless they were already there the last
+    /// time. An error is cleared by the next key press, so the same one raised again is added
+    /// again.
+    pub fn observe(&mut self, notice: Option<&str>, error: Option<String>, now: u64) {
+        if let Some(message) = notice.filter(|&notice| Some(notice) != self.notice.as_deref()) {
+            self.push(message.to_string(), false, now);
+        }
+        self.notice = notice.map(str::to_string);
+        if let Some(message) = error
+            .as_ref()
+            .filter(|&error| Some(error) != self.error.as_ref())
+        {
+            self.push(message.clone(), true, now);
+        }
+        self.error = error;
+    }
+
+    fn push(&mut self, message: String, failed: bool, now: u64) {
+        if message.trim().is_empty() {
+            return;
+        }
+        if failed {
+            tracing::warn!("{}", message.trim());
+        } else {
+            tracing::debug!("status: {}", message.trim());
+        }
+        if self.log.len() == KEPT_OUTCOMES {
+            self.log.pop_front();
+        }
+        self.log.push_back(Outcome {
+            shown: now,
+            message: message.trim().to_string(),
+            failed,
+        });
+    }
+
+    /// The results, the latest first.
+    pub fn latest_first(&self) -> impl Iterator<Item = &Outcome> {
+        self.log.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.log.iter().filter(|outcome| outcome.failed).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+}
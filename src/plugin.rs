@@ -0,0 +1,210 @@
+//! Columns added to the table from outside: by code using syskill as a library, or by optional
+//! parts of syskill itself. Each is a [`ColumnProvider`] computing a value per process, such as a
+//! metric read from a service's own endpoint, and is shown, hidden, moved, saved and sorted by
+//! like the built-in columns.
+//!
+//! Providers are registered with [`register`] at startup, before the command line and the config
+//! file are read, so that `--sort`, `columns` and `hidden_columns` can name them. Their values
+//! are computed on the collector thread at every refresh while their column is shown or sorted
+//! by, and travel with the snapshots into recordings and shared sessions.
+
+use crate::config::Column;
+use crate::snapshot::ProcessSample;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// The value of a custom column for one process.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    /// Shown with up to two decimals, and sorted by size.
+    Number(f64),
+    /// Shown as it is, and sorted alphabetically.
+    Text(String),
+}
+
+impl Value {
+    /// Numbers before text, as text sorts after them in the table.
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.cmp(b),
+            (Value::Number(_), Value::Text(_)) => Ordering::Less,
+            (Value::Text(_), Value::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(number) if number.fract() == 0.0 => write!(f, "{number:.0}"),
+            Value::Number(number) => write!(f, "{number:.2}"),
+            Value::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// A column computed by code outside the table, registered with [`register`]: its name, its
+/// header and the value of each process.
+pub trait ColumnProvider: Send + Sync {
+    /// As written in the config file and `--sort`, e.g. `queue_depth`.
+    fn name(&self) -> &str;
+
+    /// Header of the column, e.g. `QUEUE`.
+    fn title(&self) -> &str;
+
+    /// Cells the column takes, 10 unless overridden.
+    fn width(&self) -> u16 {
+        10
+    }
+
+    /// Whether the column is shown when the config file does not list the columns; it is
+    /// unless overridden.
+    fn shown(&self) -> bool {
+        true
+    }
+
+    /// The value for `process`, or `None` to leave its cell blank. Called for every process at
+    /// every refresh, so a slow source is best read in the background and cached.
+    fn value(&self, process: &ProcessSample) -> Option<Value>;
+}
+
+/// The registered providers, in the order of their columns. They live as long as the program.
+static PROVIDERS: RwLock<Vec<&'static dyn ColumnProvider>> = RwLock::new(Vec::new());
+
+/// Adds the column of `provider` after the built-in ones; an error if a column of that name
+/// exists already.
+pub fn register(provider: impl ColumnProvider + 'static) -> Result<(), String> {
+    let mut providers = PROVIDERS.write().unwrap_or_else(|e| e.into_inner());
+    add(&mut providers, provider)
+}
+
+/// [`register`] in `providers`.
+fn add(
+    providers: &mut Vec<&'static dyn ColumnProvider>,
+    provider: impl ColumnProvider + 'static,
+) -> Result<(), String> {
+    let name = provider.name();
+    let built_in = Column::ALL.iter().any(|column| column.name() == name);
+    if built_in || providers.iter().any(|known| known.name() == name) {
+        return Err(format!("there is a column `{name}` already"));
+    }
+    providers.push(Box::leak(Box::new(provider)));
+    Ok(())
+}
+
+/// Registers the columns syskill adds this way itself: the OOM score, on Linux.
+pub fn register_built_in() {
+    // Only fails if they are registered already.
+    #[cfg(target_os = "linux")]
+    let _ = register(crate::oom::ScoreColumn);
+}
+
+/// The registered providers, in the order they were registered. Taken once for a whole refresh,
+/// sort or drawing of the table, and passed on to what needs them.
+pub(crate) fn providers() -> Vec<&'static dyn ColumnProvider> {
+    PROVIDERS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// How many columns were registered.
+pub(crate) fn count() -> usize {
+    PROVIDERS.read().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+/// The provider of the custom column `index`.
+pub(crate) fn provider(index: u16) -> Option<&'static dyn ColumnProvider> {
+    let providers = PROVIDERS.read().unwrap_or_else(|e| e.into_inner());
+    providers.get(usize::from(index)).copied()
+}
+
+/// The values of the custom columns of `providers` for `process`, by column name.
+pub(crate) fn values(
+    providers: &[&dyn ColumnProvider],
+    process: &ProcessSample,
+) -> BTreeMap<String, Value> {
+    providers
+        .iter()
+        .filter_map(|provider| Some((provider.name().to_string(), provider.value(process)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{ProcessInfo, SortColumn, SortKey};
+
+    struct Threads;
+
+    impl ColumnProvider for Threads {
+        fn name(&self) -> &str {
+            "plugin_threads"
+        }
+
+        fn title(&self) -> &str {
+            "THR"
+        }
+
+        fn value(&self, process: &ProcessSample) -> Option<Value> {
+            (process.threads > 0).then_some(Value::Number(f64::from(process.threads)))
+        }
+    }
+
+    /// A column with nothing in it.
+    struct Named(&'static str);
+
+    impl ColumnProvider for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn title(&self) -> &str {
+            self.0
+        }
+
+        fn value(&self, _: &ProcessSample) -> Option<Value> {
+            None
+        }
+    }
+
+    #[test]
+    fn custom_columns_are_filled_in_and_sorted_by() {
+        // Kept out of the registry the rest of syskill uses.
+        let mut providers = Vec::new();
+        add(&mut providers, Threads).unwrap();
+        assert!(add(&mut providers, Threads).is_err());
+        assert!(add(&mut providers, Named("cpu")).is_err());
+
+        let process = ProcessSample {
+            threads: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            values(&providers, &process).get("plugin_threads"),
+            Some(&Value::Number(4.0))
+        );
+        assert!(!values(&providers, &ProcessSample::default()).contains_key("plugin_threads"));
+
+        let key = SortKey::new(SortColumn::Custom(0));
+        assert!(key.descending);
+        let [few, many, unknown] = [1, 8, 0].map(|threads| {
+            let mut sample = ProcessSample {
+                threads,
+                ..Default::default()
+            };
+            sample.custom = values(&providers, &sample);
+            ProcessInfo::from(sample)
+        });
+        assert_eq!(key.compare(&many, &few, &providers), Ordering::Less);
+        assert_eq!(key.compare(&unknown, &few, &providers), Ordering::Greater);
+
+        assert_eq!(Value::Number(4.0).to_string(), "4");
+        assert_eq!(Value::Number(0.128).to_string(), "0.13");
+        assert_eq!(
+            Value::Number(9.0).compare(&Value::Text("a".to_string())),
+            Ordering::Less
+        );
+    }
+}
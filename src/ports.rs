@@ -0,0 +1,218 @@
+//! Finding the processes listening on a port.
+//!
+//! On Linux the listening sockets are read from `/proc/net/{tcp,tcp6,udp,udp6}` and matched to
+//! processes through the socket inodes in `/proc/<pid>/fd`. Other platforms ask `lsof`.
+
+use std::fmt;
+use std::str::FromStr;
+
+pub struct Listener {
+    pub pid: u32,
+    pub protocol: &'static str,
+    pub address: String,
+}
+
+/// A port to look up as typed, e.g. `8080`, `:8080` like `ss` prints it, or `53/udp` for only
+/// one protocol.
+#[derive(Clone, Copy, Debug)]
+pub struct PortQuery {
+    pub port: u16,
+    /// `tcp` or `udp`; both when `None`.
+    pub protocol: Option<&'static str>,
+}
+
+impl PortQuery {
+    fn matches(&self, listener: &Listener) -> bool {
+        self.protocol
+            .is_none_or(|protocol| listener.protocol.starts_with(protocol))
+    }
+}
+
+impl FromStr for PortQuery {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let trimmed = input.trim();
+        let trimmed = trimmed.strip_prefix(':').unwrap_or(trimmed);
+        let (port, protocol) = match trimmed.split_once('/') {
+            Some((port, protocol)) => (port, Some(protocol.to_ascii_lowercase())),
+            None => (trimmed, None),
+        };
+        let protocol = match protocol.as_deref() {
+            None => None,
+            Some("tcp") => Some("tcp"),
+            Some("udp") => Some("udp"),
+            Some(protocol) => return Err(format!("Unknown protocol {protocol}, use tcp or udp")),
+        };
+        match port.parse() {
+            Ok(port) => Ok(PortQuery { port, protocol }),
+            Err(_) => Err(format!("Not a port number: {}", input.trim())),
+        }
+    }
+}
+
+impl fmt::Display for PortQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.protocol {
+            Some(protocol) => write!(f, "{}/{protocol}", self.port),
+            None => write!(f, "{}", self.port),
+        }
+    }
+}
+
+/// Processes with a listening TCP or bound UDP socket on `port`.
+#[cfg(target_os = "linux")]
+pub fn listeners(port: u16) -> Vec<Listener> {
+    use std::collections::HashMap;
+    use std::fs;
+
+    const TCP_LISTEN: &str = "0A";
+    const UDP_UNCONNECTED: &str = "07";
+
+    // Socket inode -> (protocol, local address).
+    let mut sockets: HashMap<String, (&'static str, String)> = HashMap::new();
+    for (protocol, file, state) in [
+        ("tcp", "/proc/net/tcp", TCP_LISTEN),
+        ("tcp6", "/proc/net/tcp6", TCP_LISTEN),
+        ("udp", "/proc/net/udp", UDP_UNCONNECTED),
+        ("udp6", "/proc/net/udp6", UDP_UNCONNECTED),
+    ] {
+        let Ok(table) = fs::read_to_string(file) else {
+            continue;
+        };
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (Some(local), Some(&socket_state), Some(inode)) =
+                (fields.get(1), fields.get(3), fields.get(9))
+            else {
+                continue;
+            };
+            let Some((address, local_port)) = local.split_once(':') else {
+                continue;
+            };
+            if socket_state == state && u16::from_str_radix(local_port, 16) == Ok(port) {
+                let address = format!("{}:{port}", decode_address(address));
+                sockets.insert(inode.to_string(), (protocol, address));
+            }
+        }
+    }
+    if sockets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut listeners = Vec::new();
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return listeners;
+    };
+    for process in processes.flatten() {
+        let Some(pid) = process
+            .file_name()
+            .to_str()
+            .and_then(|pid| pid.parse().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let target = target.to_string_lossy();
+            let Some(inode) = target
+                .strip_prefix("socket:[")
+                .and_then(|rest| rest.strip_suffix(']'))
+            else {
+                continue;
+            };
+            if let Some((protocol, address)) = sockets.get(inode) {
+                let duplicate = listeners.iter().any(|l: &Listener| {
+                    l.pid == pid && l.protocol == *protocol && l.address == *address
+                });
+                if !duplicate {
+                    listeners.push(Listener {
+                        pid,
+                        protocol,
+                        address: address.clone(),
+                    });
+                }
+            }
+        }
+    }
+    listeners
+}
+
+/// Decodes a hex address from `/proc/net`, printed as 32-bit words in host byte order.
+#[cfg(target_os = "linux")]
+pub(crate) fn decode_address(hex: &str) -> String {
+    let octets: Vec<u8> = (0..hex.len() / 8)
+        .filter_map(|i| u32::from_str_radix(&hex[i * 8..i * 8 + 8], 16).ok())
+        .flat_map(u32::to_ne_bytes)
+        .collect();
+    if let Ok(octets) = <[u8; 4]>::try_from(octets.as_slice()) {
+        std::net::Ipv4Addr::from(octets).to_string()
+    } else if let Ok(octets) = <[u8; 16]>::try_from(octets.as_slice()) {
+        format!("[{}]", std::net::Ipv6Addr::from(octets))
+    } else {
+        hex.to_string()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn listeners(port: u16) -> Vec<Listener> {
+    let tcp = [format!("-iTCP:{port}"), "-sTCP:LISTEN".to_string()];
+    let udp = [format!("-iUDP:{port}")];
+    let mut listeners = Vec::new();
+    for (protocol, filter) in [("tcp", &tcp[..]), ("udp", &udp[..])] {
+        let output = std::process::Command::new("lsof")
+            .args(["-nP", "-t"])
+            .args(filter)
+            .output();
+        let Ok(output) = output else {
+            continue;
+        };
+        listeners.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|pid| pid.trim().parse().ok())
+                .map(|pid| Listener {
+                    pid,
+                    protocol,
+                    address: format!("*:{port}"),
+                }),
+        );
+    }
+    listeners
+}
+
+/// A process holding a port, with the details needed to decide what to do with it.
+pub struct Holder {
+    pub listener: Listener,
+    pub name: String,
+    pub exe: String,
+    pub command: String,
+    pub memory: u64,
+}
+
+pub fn holders(system: &mut sysinfo::System, query: PortQuery) -> Vec<Holder> {
+    listeners(query.port)
+        .into_iter()
+        .filter(|listener| query.matches(listener))
+        .map(|listener| {
+            let pid = sysinfo::Pid::from_u32(listener.pid);
+            system.refresh_process(pid);
+            let process = system.process(pid);
+            Holder {
+                name: process.map(|p| p.name().to_string()).unwrap_or_default(),
+                exe: process
+                    .and_then(|p| p.exe())
+                    .map(|exe| exe.display().to_string())
+                    .unwrap_or_default(),
+                command: process.map(|p| p.cmd().join(" ")).unwrap_or_default(),
+                memory: process.map_or(0, |p| p.memory()),
+                listener,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,138 @@
+//! The state of the batteries of a laptop and the processes most likely draining them, for the
+//! battery pane.
+//!
+//! Batteries are read from `/sys/class/power_supply` on Linux; elsewhere none are found. How much
+//! power each process draws is not known, so the processes that used the most CPU over the last
+//! refreshes stand in for the ones to blame.
+
+use crate::app::ProcessInfo;
+use crate::history::HistoryStore;
+
+/// Refreshes the CPU usage of a process is averaged over.
+pub const WINDOW: usize = 30;
+/// Mean CPU usage, in percent of one core, below which a process is not worth naming.
+const NEGLIGIBLE_CPU: f32 = 0.5;
+
+pub struct Battery {
+    /// As the kernel calls it, e.g. `BAT0`.
+    pub name: String,
+    /// `Charging`, `Discharging`, `Full` or `Not charging`.
+    pub status: String,
+    /// Percent of a full charge.
+    pub capacity: Option<f64>,
+    /// Watts going out of the battery, or into it while charging.
+    pub power: Option<f64>,
+    /// Seconds until it is empty while discharging, or full while charging.
+    pub time_left: Option<u64>,
+}
+
+impl Battery {
+    pub fn discharging(&self) -> bool {
+        self.status == "Discharging"
+    }
+}
+
+#[derive(Default)]
+pub struct Power {
+    pub batteries: Vec<Battery>,
+    /// Whether a power supply is plugged in; `None` if the machine does not say.
+    pub on_mains: Option<bool>,
+}
+
+#[cfg(target_os = "linux")]
+pub fn read() -> Power {
+    let mut power = Power::default();
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return power;
+    };
+    let mut entries: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    entries.sort();
+    for path in entries {
+        let read = |file: &str| {
+            std::fs::read_to_string(path.join(file))
+                .ok()
+                .map(|text| text.trim().to_string())
+        };
+        match read("type").as_deref() {
+            Some("Battery") => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                power.batteries.push(battery(name, read));
+            }
+            Some("Mains") => {
+                let online = read("online").is_some_and(|online| online == "1");
+                power.on_mains = Some(power.on_mains.unwrap_or(false) || online);
+            }
+            _ => {}
+        }
+    }
+    power
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read() -> Power {
+    Power::default()
+}
+
+/// A battery from the files of its power supply directory. Batteries report either energy, in
+/// µWh with the power in µW, or charge, in µAh with the current in µA.
+#[cfg(target_os = "linux")]
+fn battery(name: String, read: impl Fn(&str) -> Option<String>) -> Battery {
+    let number = |file: &str| read(file).and_then(|text| text.parse::<f64>().ok());
+    let status = read("status").unwrap_or_else(|| "Unknown".to_string());
+    let voltage = number("voltage_now").map(|microvolts| microvolts / 1e6);
+    let current = number("current_now").map(|microamps| microamps.abs() / 1e6);
+    let power = number("power_now")
+        .map(|microwatts| microwatts.abs() / 1e6)
+        .or_else(|| Some(current? * voltage?));
+    // Left in and room for, in the unit of `rate`, and the rate itself.
+    let (now, full, rate) = match (number("energy_now"), number("energy_full")) {
+        (Some(now), Some(full)) => (now, full, number("power_now").map(f64::abs)),
+        _ => (
+            number("charge_now").unwrap_or_default(),
+            number("charge_full").unwrap_or_default(),
+            number("current_now").map(f64::abs),
+        ),
+    };
+    let hours = match (status.as_str(), rate) {
+        (_, None) => None,
+        (_, Some(rate)) if rate <= 0.0 => None,
+        ("Discharging", Some(rate)) => Some(now / rate),
+        ("Charging", Some(rate)) => Some((full - now).max(0.0) / rate),
+        _ => None,
+    };
+    let capacity = number("capacity")
+        .or_else(|| (full > 0.0).then(|| now / full * 100.0))
+        .map(|capacity| capacity.clamp(0.0, 100.0));
+    Battery {
+        name,
+        status,
+        capacity,
+        power,
+        time_left: hours.map(|hours| (hours * 3600.0) as u64),
+    }
+}
+
+/// The `count` processes that used the most CPU over the last [`WINDOW`] refreshes, with their
+/// mean usage in percent of one core, the highest first.
+pub fn drainers<'a>(
+    processes: &'a [ProcessInfo],
+    history: &HistoryStore,
+    count: usize,
+) -> Vec<(&'a ProcessInfo, f32)> {
+    let mut drainers: Vec<_> = processes
+        .iter()
+        .filter(|data| data.exited.is_none() && data.members == 0)
+        .filter_map(|data| {
+            let samples = &history.get(&data.key())?.cpu;
+            let recent = samples.iter().rev().take(WINDOW);
+            let mean = recent.clone().sum::<f32>() / recent.count().max(1) as f32;
+            (mean >= NEGLIGIBLE_CPU).then_some((data, mean))
+        })
+        .collect();
+    drainers.sort_by(|a, b| b.1.total_cmp(&a.1));
+    drainers.truncate(count);
+    drainers
+}
@@ -0,0 +1,48 @@
+//! How much the system is short of CPU and of memory, for focus mode.
+//!
+//! On Linux with pressure stall information this is the share of time tasks were stalled waiting
+//! for the resource over the last ten seconds (`some avg10` in `/proc/pressure/{cpu,memory}`).
+//! Elsewhere the utilization gauges stand in: overall CPU usage and the share of memory in use.
+
+use sysinfo::System;
+
+pub struct Pressure {
+    /// Percent, like the memory value.
+    pub cpu: f64,
+    pub memory: f64,
+    /// Whether the values are stall times rather than utilization.
+    pub stall: bool,
+}
+
+pub fn read(system: &mut System) -> Pressure {
+    #[cfg(target_os = "linux")]
+    if let (Some(cpu), Some(memory)) = (stall("cpu"), stall("memory")) {
+        return Pressure {
+            cpu,
+            memory,
+            stall: true,
+        };
+    }
+    system.refresh_cpu();
+    system.refresh_memory();
+    let memory = match system.total_memory() {
+        0 => 0.0,
+        total => system.used_memory() as f64 / total as f64 * 100.0,
+    };
+    Pressure {
+        cpu: f64::from(system.global_cpu_info().cpu_usage()),
+        memory,
+        stall: false,
+    }
+}
+
+/// `some avg10` of `/proc/pressure/<resource>`.
+#[cfg(target_os = "linux")]
+fn stall(resource: &str) -> Option<f64> {
+    let text = std::fs::read_to_string(format!("/proc/pressure/{resource}")).ok()?;
+    let some = text.lines().find(|line| line.starts_with("some "))?;
+    some.split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
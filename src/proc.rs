@@ -0,0 +1,397 @@
+//! Process collection and signalling, usable without the TUI.
+//!
+//! [`ProcessSnapshot::capture`] lists the processes of a refreshed [`sysinfo::System`];
+//! [`matching`] picks them like the search of the table does, and [`KillRequest`] delivers a
+//! signal to one of them and describes what happened.
+
+use std::fmt;
+use sysinfo::{Pid, ProcessStatus, Signal, System};
+
+pub use crate::snapshot::{ProcessKey, ProcessSample, Snapshot as ProcessSnapshot};
+
+/// A signal to deliver, by name or by number for signals sysinfo has no name for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KillSignal {
+    Named(Signal),
+    Number(i32),
+}
+
+/// A signal to deliver to one process.
+#[derive(Clone, Copy, Debug)]
+pub struct KillRequest {
+    pub pid: u32,
+    pub signal: KillSignal,
+    /// Start time (seconds since the epoch) the process must have. Set it when the PID was seen
+    /// a while ago, so a process that got the same PID in the meantime is not signalled.
+    pub start_time: Option<u64>,
+}
+
+/// A delivered signal.
+pub struct Killed {
+    /// Name of the process at the time it was signalled.
+    pub name: String,
+    pub message: String,
+}
+
+/// A signal that was not delivered.
+#[derive(Debug)]
+pub struct KillError {
+    pub message: String,
+    /// The process belongs to another user, who may refuse signals from this one (`EPERM`);
+    /// root may still send them, see [`KillRequest::sudo_command`].
+    pub denied: bool,
+}
+
+impl KillError {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            denied: false,
+        }
+    }
+
+    fn io(message: String, e: &std::io::Error) -> Self {
+        let denied = e.kind() == std::io::ErrorKind::PermissionDenied;
+        Self {
+            // `Operation not permitted (os error 1)` says less than it seems to.
+            message: if denied {
+                format!("{message}: permission denied")
+            } else {
+                format!("{message}: {e}")
+            },
+            denied,
+        }
+    }
+}
+
+impl fmt::Display for KillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for KillError {}
+
+impl From<KillError> for String {
+    fn from(e: KillError) -> Self {
+        e.message
+    }
+}
+
+impl KillRequest {
+    pub fn new(pid: u32, signal: Signal) -> Self {
+        Self {
+            pid,
+            signal: KillSignal::Named(signal),
+            start_time: None,
+        }
+    }
+
+    /// Only signals the process if it is still the one identified by `key`.
+    pub fn expecting(self, key: &ProcessKey) -> Self {
+        Self {
+            start_time: Some(key.start_time),
+            ..self
+        }
+    }
+
+    /// Parses a signal number as typed by the user.
+    pub fn number(pid: u32, number: &str) -> Result<Self, String> {
+        match number.parse() {
+            Ok(number) if number > 0 => Ok(Self {
+                pid,
+                signal: KillSignal::Number(number),
+                start_time: None,
+            }),
+            _ => Err(format!("Not a signal number: {number}")),
+        }
+    }
+
+    /// Sends the signal, refreshing the process in `system` first.
+    pub fn send(&self, system: &mut System) -> Result<Killed, KillError> {
+        let result = self.deliver(system);
+        match &result {
+            Ok(killed) => tracing::info!(pid = self.pid, "{}", killed.message),
+            Err(e) => tracing::debug!(pid = self.pid, "not sent: {e}"),
+        }
+        result
+    }
+
+    fn deliver(&self, system: &mut System) -> Result<Killed, KillError> {
+        let pid = Pid::from_u32(self.pid);
+        system.refresh_process(pid);
+        let process = system
+            .process(pid)
+            .ok_or_else(|| KillError::new(format!("no process with PID {pid}")))?;
+        let name = process.name().to_string();
+        if self
+            .start_time
+            .is_some_and(|start_time| start_time != process.start_time())
+        {
+            return Err(KillError::new(format!(
+                "PID {pid} now belongs to another process ({name}), nothing was sent"
+            )));
+        }
+        let signal = match self.signal {
+            KillSignal::Named(signal) => signal,
+            KillSignal::Number(number) => return send_number(pid, name, number),
+        };
+        let signal_name = crate::signal::describe(self.signal);
+        #[cfg(windows)]
+        if signal == Signal::Term {
+            return match crate::win32::close(self.pid) {
+                Ok(()) => Ok(Killed {
+                    message: format!("Asked {pid} ({name}) to close"),
+                    name,
+                }),
+                Err(e) => Err(KillError::io(format!("failed to close {pid} ({name})"), &e)),
+            };
+        }
+        // By number where possible, for the reason a signal is refused.
+        #[cfg(unix)]
+        if let Some(number) = crate::signal::number(signal) {
+            return match crate::signal::send_number(self.pid, number) {
+                Ok(()) => Ok(Killed {
+                    message: format!("Sent {signal_name} to {pid} ({name})"),
+                    name,
+                }),
+                Err(e) => Err(KillError::io(
+                    format!("failed to send {signal_name} to {pid} ({name})"),
+                    &e,
+                )),
+            };
+        }
+        match process.kill_with(signal) {
+            Some(true) => Ok(Killed {
+                message: format!("Sent {signal_name} to {pid} ({name})"),
+                name,
+            }),
+            Some(false) => Err(KillError::new(format!(
+                "failed to send {signal_name} to {pid} ({name})"
+            ))),
+            None => Err(KillError::new(format!(
+                "{signal_name} is not supported on this platform"
+            ))),
+        }
+    }
+
+    /// The command that sends the signal as root, for processes of other users, e.g.
+    /// `sudo kill -KILL 1234`.
+    pub fn sudo_command(&self) -> String {
+        format!("sudo kill -{} {}", self.signal_argument(), self.pid)
+    }
+
+    /// Sends the signal through `sudo -n`, which fails rather than ask for a password; it works
+    /// where `kill` may be run without one, or while sudo still remembers the last one typed.
+    /// Unlike [`KillRequest::send`] this does not check the start time.
+    pub fn send_with_sudo(&self) -> Result<(), String> {
+        let output = std::process::Command::new("sudo")
+            .args(["-n", "kill", &format!("-{}", self.signal_argument())])
+            .arg(self.pid.to_string())
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| format!("cannot run sudo: {e}"))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        // E.g. `sudo: a password is required`.
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().next().map_or_else(
+            || format!("sudo {}", output.status),
+            |line| line.trim_start_matches("sudo: ").to_string(),
+        ))
+    }
+
+    /// The signal as `kill` takes it, e.g. `KILL` or `10`.
+    fn signal_argument(&self) -> String {
+        match self.signal {
+            KillSignal::Named(signal) => crate::signal::name(signal),
+            KillSignal::Number(number) => number.to_string(),
+        }
+    }
+}
+
+/// Processes of `snapshot` whose name matches `pattern` like the search of the table, fzf-style,
+/// sorted by PID.
+pub fn matching<'a>(snapshot: &'a ProcessSnapshot, pattern: &str) -> Vec<&'a ProcessSample> {
+    let mut processes: Vec<&ProcessSample> = snapshot
+        .processes
+        .iter()
+        .filter(|process| {
+            let name = crate::query::Field::Name;
+            crate::query::find(name, &process.name, pattern).is_some()
+        })
+        .collect();
+    processes.sort_by_key(|process| process.pid);
+    processes
+}
+
+/// Name of a process state for the table, e.g. `Running` or `Zombie`.
+pub fn state_name(status: ProcessStatus) -> String {
+    match status {
+        ProcessStatus::Run => "Running".to_string(),
+        ProcessStatus::UninterruptibleDiskSleep => "Disk sleep".to_string(),
+        ProcessStatus::LockBlocked => "Blocked".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Threads of a process, the main one included; 0 where sysinfo does not list them.
+pub fn thread_count(process: &sysinfo::Process) -> u32 {
+    process.tasks().map_or(0, |tasks| tasks.len().max(1) as u32)
+}
+
+/// CPU time the process has used since it started, user and system together, in milliseconds;
+/// `None` where it cannot be read. Linux only.
+#[cfg(target_os = "linux")]
+pub fn cpu_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The name may contain spaces and parentheses; `utime` and `stime` are fields 14 and 15.
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 2..].split_whitespace().collect();
+    let user: u64 = fields.get(14 - 3)?.parse().ok()?;
+    let system: u64 = fields.get(15 - 3)?.parse().ok()?;
+    Some(((user + system) as f64 * 1000.0 / crate::threads::ticks_per_second()) as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cpu_time(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Bytes of resident memory shared with other processes, in file mappings and shared memory
+/// (`SHR` in `top`), and bytes swapped out; `None` where they cannot be read, as for kernel
+/// threads. Linux only.
+#[cfg(target_os = "linux")]
+pub fn shared_and_swap(pid: u32) -> Option<(u64, u64)> {
+    status_memory(&std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn shared_and_swap(_pid: u32) -> Option<(u64, u64)> {
+    None
+}
+
+/// The shared memory and swap of [`shared_and_swap`] from the text of `/proc/<pid>/status`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn status_memory(status: &str) -> Option<(u64, u64)> {
+    let bytes = |key: &str| {
+        let line = status.lines().find_map(|line| line.strip_prefix(key))?;
+        let kib: u64 = line.trim().strip_suffix("kB")?.trim().parse().ok()?;
+        Some(kib * 1024)
+    };
+    let shared = bytes("RssFile:")? + bytes("RssShmem:")?;
+    Some((shared, bytes("VmSwap:")?))
+}
+
+/// Whether a state (see [`state_name`]) usually needs a look: exited but not reaped by its
+/// parent, paused, traced, or stuck waiting for I/O.
+pub fn is_problem_state(state: &str) -> bool {
+    matches!(
+        state,
+        "Zombie" | "Stopped" | "Tracing" | "Dead" | "Disk sleep"
+    )
+}
+
+#[cfg(unix)]
+fn send_number(pid: Pid, name: String, number: i32) -> Result<Killed, KillError> {
+    match crate::signal::send_number(pid.as_u32(), number) {
+        Ok(()) => Ok(Killed {
+            message: format!("Sent signal {number} to {pid} ({name})"),
+            name,
+        }),
+        Err(e) => Err(KillError::io(
+            format!("failed to send signal {number} to {pid} ({name})"),
+            &e,
+        )),
+    }
+}
+
+#[cfg(not(unix))]
+fn send_number(pid: Pid, name: String, _number: i32) -> Result<Killed, KillError> {
+    Err(KillError::new(format!(
+        "signal numbers are not supported on this platform, cannot signal {pid} ({name})"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_memory_and_swap_are_read_from_the_status() {
+        let status = "Name:\tpostgres\nVmRSS:\t  20480 kB\nRssAnon:\t   4096 kB\n\
+                      RssFile:\t   8192 kB\nRssShmem:\t   8192 kB\nVmSwap:\t    512 kB\n";
+        assert_eq!(status_memory(status), Some((16384 * 1024, 512 * 1024)));
+        // Kernel threads have no memory of their own.
+        assert_eq!(
+            status_memory("Name:\tkthreadd\nState:\tS (sleeping)\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn matching_finds_processes_by_name_in_pid_order() {
+        let sample = |pid, name: &str| ProcessSample {
+            pid,
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let snapshot = ProcessSnapshot {
+            timestamp: 0,
+            processes: vec![sample(30, "node"), sample(10, "nginx"), sample(20, "bash")],
+            without_disk: false,
+        };
+        let pids: Vec<_> = matching(&snapshot, "ng")
+            .iter()
+            .map(|process| process.pid)
+            .collect();
+        assert_eq!(pids, [10]);
+        let pids: Vec<_> = matching(&snapshot, "n")
+            .iter()
+            .map(|process| process.pid)
+            .collect();
+        assert_eq!(pids, [10, 30]);
+    }
+
+    #[test]
+    fn kills_are_refused_unless_the_process_matches() {
+        let mut system = System::new();
+        // Above the highest PID Linux hands out.
+        let gone = KillRequest::new(5_000_000, Signal::Kill).send(&mut system);
+        assert!(gone
+            .map(|killed| killed.message)
+            .unwrap_err()
+            .message
+            .contains("no process with PID 5000000"));
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = Pid::from_u32(child.id());
+        system.refresh_process(pid);
+        let key = ProcessKey {
+            pid: child.id(),
+            start_time: system.process(pid).unwrap().start_time(),
+        };
+        // As if the process seen had exited and its PID had gone to the sleep started here.
+        let reused = ProcessKey {
+            start_time: key.start_time + 1,
+            ..key
+        };
+        let refused = KillRequest::new(key.pid, Signal::Kill)
+            .expecting(&reused)
+            .send(&mut system);
+        assert!(refused
+            .map(|killed| killed.message)
+            .unwrap_err()
+            .message
+            .contains("now belongs to another process"));
+        assert!(child.try_wait().unwrap().is_none());
+        let killed = KillRequest::new(key.pid, Signal::Kill)
+            .expecting(&key)
+            .send(&mut system)
+            .unwrap();
+        assert_eq!(killed.name, "sleep");
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+//! How much of `/proc` can be read. Mounted with `hidepid`, or in a restricted container, the
+//! processes of other users are hidden or their details cannot be read, which sysinfo reports as
+//! zeros and empty values. Those are shown as [`UNAVAILABLE`] instead, with a banner saying why.
+
+/// Shown in place of values that cannot be read.
+pub const UNAVAILABLE: &str = "—";
+
+/// What the `/proc` mount hides from this user, for the banner above the table; `None` when
+/// everything is visible. Checked once, as the mount does not change while the table runs.
+#[cfg(target_os = "linux")]
+pub fn restriction() -> Option<String> {
+    // SAFETY: geteuid(2) has no memory safety requirements.
+    if unsafe { libc::geteuid() } == 0 {
+        return None;
+    }
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+    // `id parent major:minor root mount-point options ... - type source super-options`; of
+    // mounts stacked on `/proc`, the last one is visible.
+    let options = mountinfo.lines().rev().find_map(|line| {
+        let (fields, filesystem) = line.split_once(" - ")?;
+        let mut filesystem = filesystem.split(' ');
+        let is_proc =
+            fields.split(' ').nth(4) == Some("/proc") && filesystem.next() == Some("proc");
+        is_proc.then(|| filesystem.nth(1).unwrap_or_default().to_string())
+    })?;
+    let hidepid = options
+        .split(',')
+        .find_map(|option| option.strip_prefix("hidepid="))?;
+    // With `noaccess` the directories of other users' processes remain, but sysinfo skips
+    // processes whose `stat` it cannot read, so both modes leave them out of the table.
+    matches!(hidepid, "1" | "2" | "noaccess" | "invisible").then(|| {
+        format!(
+            "/proc is mounted with hidepid={hidepid}: other users' processes cannot be read and \
+             are not listed"
+        )
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn restriction() -> Option<String> {
+    None
+}
+
+/// Whether `/proc/<pid>/<file>` can be read, e.g. `io`, which takes the same permissions as
+/// tracing the process. Elsewhere sysinfo reads the values through other means.
+pub fn readable(pid: u32, file: &str) -> bool {
+    !cfg!(target_os = "linux") || std::fs::File::open(format!("/proc/{pid}/{file}")).is_ok()
+}
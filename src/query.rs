@@ -0,0 +1,616 @@
+//! Filter expressions for the search, e.g. `cpu > 50 && name ~ chrome` or `mem > 1G`.
+//!
+//! A search containing one of `<`, `>`, `=`, `~`, `&` or `|` is an expression; anything else is
+//! matched against process names like before. An expression compares fields with `>`, `>=`, `<`,
+//! `<=`, `==` and `!=`, or with `~` and `!~` for names matching the way a plain search does, and
+//! combines comparisons with `&&`, `||`, `!` and parentheses. `&&` binds tighter than `||`.
+//!
+//! Words can also be prefixed with a field, e.g. `user:alex cmd:--config`, for processes that
+//! match in all of them: text fields as with `~`, numbers as with `==`. The other words of such a
+//! search are matched against names.
+
+use crate::app::ProcessInfo;
+use crate::fuzzy;
+use crate::units::Units;
+
+/// A parsed search.
+pub enum Query {
+    /// A plain search, matched against process names.
+    Name(String),
+    /// An expression, or words prefixed with fields and the rest matched against names as a
+    /// plain search.
+    Expression(Expr),
+}
+
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare { field: Field, op: Op, value: Value },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Exe,
+    User,
+    State,
+    Sched,
+    /// Container or systemd unit, e.g. `docker:web`.
+    Container,
+    /// Title of the window of the process, Windows only.
+    Window,
+    /// Controlling terminal, e.g. `pts/3`; empty for daemons.
+    Tty,
+    /// Command line; `~` looks for the text as typed rather than for its letters in order, which
+    /// long command lines would nearly always have.
+    Command,
+    Pid,
+    Parent,
+    Session,
+    /// In percent, per core if the table shows it so.
+    Cpu,
+    /// In bytes.
+    Memory,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Op {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    Matches,
+    NotMatches,
+}
+
+pub enum Value {
+    Text(String),
+    Number(f64),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(&'static str),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+/// The fields an expression can compare, by name and aliases, with what they hold.
+pub const FIELDS: [(&str, Field, &str); 14] = [
+    ("name", Field::Name, "process name"),
+    ("exe", Field::Exe, "path of the executable"),
+    ("user", Field::User, "owner"),
+    ("state", Field::State, "e.g. Sleeping or Zombie"),
+    (
+        "sched",
+        Field::Sched,
+        "scheduling policy, e.g. OTHER or FIFO 50",
+    ),
+    (
+        "container",
+        Field::Container,
+        "e.g. docker:web or nginx.service",
+    ),
+    ("window", Field::Window, "window title, on Windows"),
+    (
+        "tty",
+        Field::Tty,
+        "controlling terminal, e.g. pts/3; empty for daemons",
+    ),
+    (
+        "cmd|command",
+        Field::Command,
+        "command line; ~ finds the text as typed",
+    ),
+    ("pid", Field::Pid, "process ID"),
+    ("ppid|parent", Field::Parent, "parent PID"),
+    ("sid|session", Field::Session, "session ID"),
+    ("cpu", Field::Cpu, "CPU usage in percent, e.g. 50 or 50%"),
+    ("mem|memory", Field::Memory, "memory, e.g. 512M or 1.5G"),
+];
+
+/// The operators of an expression, with what they do.
+pub const OPERATORS: [(&str, &str); 5] = [
+    ("> >= < <=", "compare numbers"),
+    ("== !=", "equal, not equal"),
+    ("~ !~", "matches, or not, like a plain search"),
+    ("&& || !", "and, or, not; && binds tighter"),
+    ("( )", "group"),
+];
+
+/// Characters that make a search an expression.
+const OPERATOR_CHARS: [char; 6] = ['<', '>', '=', '~', '&', '|'];
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if let Some(query) = terms(input)? {
+            return Ok(query);
+        }
+        if !input.contains(OPERATOR_CHARS) {
+            return Ok(Query::Name(input.to_string()));
+        }
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.next) {
+            None => Ok(Query::Expression(expr)),
+            Some(token) => Err(format!("unexpected {}", describe(token))),
+        }
+    }
+
+    pub fn matches(&self, data: &ProcessInfo, units: Units) -> bool {
+        match self {
+            Query::Name(pattern) => find(Field::Name, &data.name, pattern).is_some(),
+            Query::Expression(expr) => expr.matches(data, units),
+        }
+    }
+
+    /// What is matched against `field` with `~`, or as the plain search matches names, to
+    /// highlight in it. Negated comparisons are left out, as they match where it is not found.
+    pub fn patterns(&self, field: Field) -> Vec<&str> {
+        let mut patterns = Vec::new();
+        match self {
+            Query::Name(pattern) if field == Field::Name => patterns.push(pattern.as_str()),
+            Query::Name(_) => {}
+            Query::Expression(expr) => expr.patterns(field, &mut patterns),
+        }
+        patterns.retain(|pattern| !pattern.is_empty());
+        patterns
+    }
+}
+
+impl Expr {
+    fn patterns<'a>(&'a self, wanted: Field, patterns: &mut Vec<&'a str>) {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.patterns(wanted, patterns);
+                right.patterns(wanted, patterns);
+            }
+            Expr::Not(_) => {}
+            Expr::Compare {
+                field,
+                op: Op::Matches,
+                value: Value::Text(value),
+            } if *field == wanted => patterns.push(value),
+            Expr::Compare { .. } => {}
+        }
+    }
+}
+
+/// Parses a search with words prefixed by fields, e.g. `nginx user:www cmd:"-c /etc"`, as the
+/// comparisons they stand for and a name search with the other words. `None` if no word has a
+/// prefix, or the search is an expression.
+fn terms(input: &str) -> Result<Option<Query>, String> {
+    let words = split_words(input)?;
+    let mut name = Vec::new();
+    let mut expr: Option<Expr> = None;
+    for word in &words {
+        let prefixed = word
+            .split_once(':')
+            .and_then(|(prefix, value)| Some((field_named(prefix)?, value)));
+        let Some((field, value)) = prefixed else {
+            name.push(word.as_str());
+            continue;
+        };
+        // Nothing typed after the prefix yet.
+        if value.is_empty() {
+            continue;
+        }
+        let op = if is_text(field) {
+            Op::Matches
+        } else {
+            Op::Equal
+        };
+        let value = self::value(field, op, value.to_string())?;
+        let compare = Expr::Compare { field, op, value };
+        expr = Some(match expr {
+            Some(expr) => Expr::And(Box::new(expr), Box::new(compare)),
+            None => compare,
+        });
+    }
+    if name.len() == words.len() {
+        return Ok(None);
+    }
+    let name = name.join(" ");
+    // An expression with a prefix-like value, e.g. `window == "user:alex"`.
+    if name.contains(OPERATOR_CHARS) {
+        return Ok(None);
+    }
+    let name_match = Expr::Compare {
+        field: Field::Name,
+        op: Op::Matches,
+        value: Value::Text(name.clone()),
+    };
+    let expr = match expr {
+        Some(expr) if name.is_empty() => expr,
+        Some(expr) => Expr::And(Box::new(name_match), Box::new(expr)),
+        None => name_match,
+    };
+    Ok(Some(Query::Expression(expr)))
+}
+
+/// Splits `input` at whitespace, but not inside double quotes, which are dropped.
+fn split_words(input: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if quoted {
+        return Err("unclosed quote".to_string());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// The field called `name` or one of its aliases, in any case.
+fn field_named(name: &str) -> Option<Field> {
+    let name = name.to_ascii_lowercase();
+    FIELDS
+        .iter()
+        .find(|(names, _, _)| names.split('|').any(|alias| alias == name))
+        .map(|&(_, field, _)| field)
+}
+
+/// Whether `field` holds text rather than a number.
+fn is_text(field: Field) -> bool {
+    matches!(
+        field,
+        Field::Name
+            | Field::Exe
+            | Field::User
+            | Field::State
+            | Field::Sched
+            | Field::Container
+            | Field::Window
+            | Field::Tty
+            | Field::Command
+    )
+}
+
+/// The value `text` compared with `field` by `op`.
+fn value(field: Field, op: Op, text: String) -> Result<Value, String> {
+    Ok(match field {
+        _ if is_text(field) => Value::Text(text),
+        _ if matches!(op, Op::Matches | Op::NotMatches) => {
+            return Err(
+                "~ only applies to name, exe, user, state, sched, container, window, tty and cmd"
+                    .to_string(),
+            )
+        }
+        Field::Memory => Value::Number(parse_size(&text)?),
+        Field::Cpu => Value::Number(parse_number(text.strip_suffix('%').unwrap_or(&text))?),
+        _ => Value::Number(parse_number(&text)?),
+    })
+}
+
+impl Expr {
+    fn matches(&self, data: &ProcessInfo, units: Units) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(data, units) && b.matches(data, units),
+            Expr::Or(a, b) => a.matches(data, units) || b.matches(data, units),
+            Expr::Not(expr) => !expr.matches(data, units),
+            Expr::Compare { field, op, value } => {
+                match (field, value) {
+                    (Field::Name, Value::Text(value)) => compare_text(&data.name, *op, value),
+                    (Field::Exe, Value::Text(value)) => compare_text(&data.exe, *op, value),
+                    (Field::User, Value::Text(value)) => compare_text(&data.user, *op, value),
+                    (Field::State, Value::Text(value)) => compare_text(&data.state, *op, value),
+                    (Field::Sched, Value::Text(value)) => compare_text(&data.sched, *op, value),
+                    (Field::Container, Value::Text(value)) => {
+                        compare_text(&data.container, *op, value)
+                    }
+                    (Field::Window, Value::Text(value)) => compare_text(&data.window, *op, value),
+                    (Field::Tty, Value::Text(value)) => compare_text(&data.tty, *op, value),
+                    (Field::Command, Value::Text(value)) => match op {
+                        Op::Matches => find(Field::Command, &data.command, value).is_some(),
+                        Op::NotMatches => find(Field::Command, &data.command, value).is_none(),
+                        _ => compare_text(&data.command, *op, value),
+                    },
+                    (Field::Pid, Value::Number(value)) => {
+                        compare_numbers(f64::from(data.pid), *op, *value)
+                    }
+                    (Field::Parent, Value::Number(value)) => {
+                        let parent = data.parent.map_or(0.0, f64::from);
+                        compare_numbers(parent, *op, *value)
+                    }
+                    (Field::Session, Value::Number(value)) => {
+                        let session = data.session.map_or(0.0, f64::from);
+                        compare_numbers(session, *op, *value)
+                    }
+                    (Field::Cpu, Value::Number(value)) => {
+                        let mut cpu = f64::from(data.cpu_usage);
+                        if units.per_core {
+                            cpu /= units.cores.max(1) as f64;
+                        }
+                        compare_numbers(cpu, *op, *value)
+                    }
+                    (Field::Memory, Value::Number(value)) => {
+                        compare_numbers(data.memory as f64, *op, *value)
+                    }
+                    // The parser gives text fields text values and the others numbers.
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn compare_text(text: &str, op: Op, value: &str) -> bool {
+    match op {
+        Op::Equal => text == value,
+        Op::NotEqual => text != value,
+        Op::Matches => fuzzy::find(value, text).is_some(),
+        Op::NotMatches => fuzzy::find(value, text).is_none(),
+        Op::Greater => text > value,
+        Op::GreaterOrEqual => text >= value,
+        Op::Less => text < value,
+        Op::LessOrEqual => text <= value,
+    }
+}
+
+/// Where `~` and the plain search find `pattern` in `text`, the value of `field`, by character
+/// position; `None` if they do not. Rows are matched and their matches highlighted with this
+/// alike: command lines have to contain the pattern, anything else only has to match it
+/// fuzzily (see [`fuzzy::find`]).
+pub fn find(field: Field, text: &str, pattern: &str) -> Option<Vec<usize>> {
+    match field {
+        Field::Command if pattern.is_empty() => Some(Vec::new()),
+        Field::Command => {
+            let found = occurrences(text, pattern);
+            (!found.is_empty()).then_some(found)
+        }
+        _ => fuzzy::find(pattern, text),
+    }
+}
+
+/// Character positions in `text` of every occurrence of `value`, ignoring case unless `value`
+/// has an uppercase letter, as the plain search does.
+pub fn occurrences(text: &str, value: &str) -> Vec<usize> {
+    let case_sensitive = value.chars().any(char::is_uppercase);
+    let normalize = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let text: Vec<char> = text.chars().map(normalize).collect();
+    let value: Vec<char> = value.chars().map(normalize).collect();
+    if value.is_empty() {
+        return Vec::new();
+    }
+    let mut positions = Vec::new();
+    for start in 0..text.len().saturating_sub(value.len() - 1) {
+        if text[start..start + value.len()] == value[..] {
+            positions.extend(
+                start.max(positions.last().map_or(0, |&last| last + 1))..start + value.len(),
+            );
+        }
+    }
+    positions
+}
+
+fn compare_numbers(number: f64, op: Op, value: f64) -> bool {
+    match op {
+        Op::Greater => number > value,
+        Op::GreaterOrEqual => number >= value,
+        Op::Less => number < value,
+        Op::LessOrEqual => number <= value,
+        Op::Equal => number == value,
+        Op::NotEqual => number != value,
+        // Rejected by the parser.
+        Op::Matches | Op::NotMatches => false,
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        chars.next();
+        let next = chars.peek().copied();
+        let token = match (c, next) {
+            ('(', _) => Token::Open,
+            (')', _) => Token::Close,
+            ('&', Some('&')) | ('|', Some('|')) => {
+                chars.next();
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            ('&' | '|', _) => return Err(format!("expected {c}{c}")),
+            ('>' | '<' | '=' | '!', Some('=')) => {
+                chars.next();
+                Token::Op(match c {
+                    '>' => ">=",
+                    '<' => "<=",
+                    '=' => "==",
+                    _ => "!=",
+                })
+            }
+            ('!', Some('~')) => {
+                chars.next();
+                Token::Op("!~")
+            }
+            ('>', _) => Token::Op(">"),
+            ('<', _) => Token::Op("<"),
+            ('=', _) => Token::Op("=="),
+            ('~', _) => Token::Op("~"),
+            ('!', _) => Token::Not,
+            ('"', _) => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => return Err("unclosed quote".to_string()),
+                    }
+                }
+                Token::Quoted(text)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()<>=!~&|\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("{word:?}"),
+        Token::Quoted(text) => format!("\"{text}\""),
+        Token::Op(op) => op.to_string(),
+        Token::And => "&&".to_string(),
+        Token::Or => "||".to_string(),
+        Token::Not => "!".to_string(),
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn take(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.next);
+        self.next += 1;
+        token
+    }
+
+    fn accept(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.next) == Some(token);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.accept(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.accept(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.accept(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.accept(&Token::Open) {
+            let expr = self.or()?;
+            if !self.accept(&Token::Close) {
+                return Err("expected )".to_string());
+            }
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let field = match self.take() {
+            Some(Token::Word(word)) => match field_named(word) {
+                Some(field) => field,
+                None => {
+                    return Err(format!(
+                        "unknown field {:?}, expected name, exe, user, state, sched, container, window, tty, cmd, pid, ppid, sid, cpu or mem",
+                        word.to_ascii_lowercase()
+                    ))
+                }
+            },
+            Some(token) => return Err(format!("expected a field, found {}", describe(token))),
+            None => return Err("expected a field".to_string()),
+        };
+        let op = match self.take() {
+            Some(Token::Op(op)) => match *op {
+                ">" => Op::Greater,
+                ">=" => Op::GreaterOrEqual,
+                "<" => Op::Less,
+                "<=" => Op::LessOrEqual,
+                "==" => Op::Equal,
+                "!=" => Op::NotEqual,
+                "~" => Op::Matches,
+                _ => Op::NotMatches,
+            },
+            Some(token) => return Err(format!("expected a comparison, found {}", describe(token))),
+            None => return Err("expected a comparison".to_string()),
+        };
+        let text = match self.take() {
+            Some(Token::Word(text) | Token::Quoted(text)) => text.clone(),
+            Some(token) => return Err(format!("expected a value, found {}", describe(token))),
+            None => return Err("expected a value".to_string()),
+        };
+        let value = value(field, op, text)?;
+        Ok(Expr::Compare { field, op, value })
+    }
+}
+
+fn parse_number(text: &str) -> Result<f64, String> {
+    text.parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite())
+        .ok_or_else(|| format!("not a number: {text}"))
+}
+
+/// Parses a memory size such as `512M`, `1.5G` or `2GiB`; units are powers of 1024.
+pub(crate) fn parse_size(text: &str) -> Result<f64, String> {
+    let upper = text.to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let factor: f64 = match &upper[digits.len()..] {
+        "" | "B" => 1.0,
+        "K" | "KB" | "KIB" => 1024.0,
+        "M" | "MB" | "MIB" => 1024.0 * 1024.0,
+        "G" | "GB" | "GIB" => 1024.0 * 1024.0 * 1024.0,
+        "T" | "TB" | "TIB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("not a memory size: {text}")),
+    };
+    Ok(parse_number(digits)? * factor)
+}
@@ -0,0 +1,136 @@
+//! Actions waiting to be carried out: the SIGKILL that follows the SIGTERM of an escalating kill,
+//! and kills scheduled for later from the confirmation. The queue is counted below the table and
+//! listed in a panel where each action can be called off, so nothing destructive happens out of
+//! sight.
+
+use crate::app::KillAction;
+use crate::proc::ProcessKey;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub enum Job {
+    /// The SIGKILL of an escalating kill, sent unless the process exits first. Setting the flag
+    /// calls it off.
+    Escalation(Arc<AtomicBool>),
+    /// An action requested for later.
+    Scheduled(KillAction),
+}
+
+pub struct Entry {
+    pub id: u64,
+    pub key: ProcessKey,
+    pub pid: String,
+    pub name: String,
+    pub due: Instant,
+    pub job: Job,
+}
+
+impl Entry {
+    /// E.g. `SIGKILL to java (4242) in 12s`.
+    pub fn describe(&self, now: Instant) -> String {
+        let what = match &self.job {
+            Job::Escalation(_) => "SIGKILL".to_string(),
+            Job::Scheduled(action) => action.describe().trim_end_matches(',').to_string(),
+        };
+        let left = self.due.saturating_duration_since(now).as_secs();
+        let unless = match self.job {
+            Job::Escalation(_) => " unless it exits",
+            Job::Scheduled(_) => "",
+        };
+        format!("{what} to {} ({}) in {left}s{unless}", self.name, self.pid)
+    }
+}
+
+#[derive(Default)]
+pub struct ActionQueue {
+    entries: Vec<Entry>,
+    next_id: u64,
+}
+
+impl ActionQueue {
+    /// Adds an action on the process of `key`; returns its id.
+    pub fn push(
+        &mut self,
+        key: ProcessKey,
+        pid: String,
+        name: String,
+        due: Instant,
+        job: Job,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push(Entry {
+            id,
+            key,
+            pid,
+            name,
+            due,
+            job,
+        });
+        self.entries.sort_by_key(|entry| entry.due);
+        id
+    }
+
+    /// Soonest first.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Calls off the `index`th action.
+    pub fn cancel(&mut self, index: usize) -> Option<Entry> {
+        if index >= self.entries.len() {
+            return None;
+        }
+        let entry = self.entries.remove(index);
+        if let Job::Escalation(cancelled) = &entry.job {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+        Some(entry)
+    }
+
+    /// Forgets an action that has been carried out.
+    pub fn remove(&mut self, id: u64) {
+        self.entries.retain(|entry| entry.id != id);
+    }
+
+    /// Takes the scheduled actions whose time has come, soonest first. Escalations finish on
+    /// their own.
+    pub fn take_due(&mut self, now: Instant) -> Vec<Entry> {
+        let (due, waiting) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| matches!(entry.job, Job::Scheduled(_)) && entry.due <= now);
+        self.entries = waiting;
+        due
+    }
+}
+
+/// Parses a delay as typed by the user: seconds, or a number with `s`, `m` or `h`, e.g. `90`,
+/// `30s` or `5m`.
+pub fn parse_delay(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let (number, unit) = match input.char_indices().last() {
+        Some((i, unit @ ('s' | 'm' | 'h'))) => (&input[..i], unit),
+        _ => (input, 's'),
+    };
+    let seconds = match unit {
+        'm' => 60.0,
+        'h' => 3600.0,
+        _ => 1.0,
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|number| Duration::try_from_secs_f64(number * seconds).ok())
+        .filter(|delay| !delay.is_zero())
+        .ok_or_else(|| format!("Not a delay: {input} (e.g. 30s or 5m)"))
+}
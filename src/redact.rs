@@ -0,0 +1,107 @@
+//! Hiding secrets in command lines and environment variables before they are shown, e.g.
+//! `--password=hunter2` as `--password=***`, so screenshots and reports do not give them away.
+//!
+//! Each pattern is a regular expression. When it has capture groups only those are hidden, so
+//! `--password[= ](\S+)` keeps the option name; otherwise the whole match is. Environment
+//! variables named like secrets, such as `API_TOKEN`, have their whole value hidden as well, unless
+//! there are no patterns at all.
+
+use regex::{Captures, Regex};
+
+/// What a hidden part is replaced with.
+const MASK: &str = "***";
+
+/// The patterns used when the config file has no `redact` list: password and token options,
+/// variables named like secrets, and passwords in URLs.
+pub const DEFAULT_PATTERNS: [&str; 3] = [
+    r"(?i)--?(?:password|passwd|pass|pwd|token|secret|api[-_]?key|auth)[= ](\S+)",
+    r"(?i)\b\w*(?:password|passwd|token|secret|api_key)\w*=(\S+)",
+    r"://[^/\s:@]+:([^/\s@]+)@",
+];
+
+/// Words of the names of environment variables whose values are hidden, e.g. `KEY` in
+/// `STRIPE_KEY`; a word ending in one of the longer ones counts too, as in `GITHUBTOKEN`.
+const SECRET_WORDS: [&str; 8] = [
+    "KEY",
+    "APIKEY",
+    "PASS",
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIALS",
+];
+
+#[derive(Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: &[impl AsRef<str>]) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern.as_ref())
+                    .map_err(|e| format!("redact pattern {:?}: {e}", pattern.as_ref()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// `text` with every match of every pattern hidden.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, mask).into_owned();
+        }
+        text
+    }
+
+    /// `NAME=value` with the whole value hidden if the name looks like that of a secret, and
+    /// otherwise what the patterns find.
+    pub fn apply_variable(&self, variable: &str) -> String {
+        match variable.split_once('=') {
+            Some((name, _)) if !self.patterns.is_empty() && secret_name(name) => {
+                format!("{name}={MASK}")
+            }
+            _ => self.apply(variable),
+        }
+    }
+}
+
+/// Whether an environment variable called `name` likely holds a secret.
+fn secret_name(name: &str) -> bool {
+    name.to_ascii_uppercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| {
+            SECRET_WORDS
+                .iter()
+                .any(|secret| word == *secret || (secret.len() > 4 && word.ends_with(secret)))
+        })
+}
+
+/// The match with its capture groups, or all of it without any, replaced by [`MASK`].
+fn mask(captures: &Captures) -> String {
+    let Some(whole) = captures.get(0) else {
+        return String::new();
+    };
+    if captures.len() == 1 {
+        return MASK.to_string();
+    }
+    let haystack = &whole.as_str();
+    let mut masked = String::new();
+    let mut end = 0;
+    // Nested groups are hidden with the group around them.
+    for group in captures.iter().skip(1).flatten() {
+        let start = group.start() - whole.start();
+        if start < end {
+            continue;
+        }
+        masked.push_str(&haystack[end..start]);
+        masked.push_str(MASK);
+        end = group.end() - whole.start();
+    }
+    masked.push_str(&haystack[end..]);
+    masked
+}
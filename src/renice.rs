@@ -0,0 +1,235 @@
+//! Changing the scheduling priority of several processes at once.
+//!
+//! A [`RenicePlan`] lists the current and the new nice value of every process before anything
+//! changes, so a search matching more than intended is caught in the preview. Applying it keeps
+//! the reason each process could not be reniced.
+//!
+//! Windows has priority classes rather than nice values. Each class stands for a nice value here
+//! (see [`CLASSES`]), the one it is read as, shown by its name and set as the nearest class.
+
+/// Nice values outside this range are clamped by the kernel anyway.
+pub const NICE_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// What the priority is called in the editor.
+#[cfg(not(windows))]
+pub const LABEL: &str = "Nice";
+#[cfg(windows)]
+pub const LABEL: &str = "Class";
+
+/// The priority classes of Windows by the nice value each stands for, highest priority first.
+#[cfg(windows)]
+const CLASSES: [(i32, u32, &str); 6] = {
+    use windows_sys::Win32::System::Threading::*;
+    [
+        (-20, REALTIME_PRIORITY_CLASS, "realtime"),
+        (-10, HIGH_PRIORITY_CLASS, "high"),
+        (-5, ABOVE_NORMAL_PRIORITY_CLASS, "above normal"),
+        (0, NORMAL_PRIORITY_CLASS, "normal"),
+        (10, BELOW_NORMAL_PRIORITY_CLASS, "below normal"),
+        (19, IDLE_PRIORITY_CLASS, "idle"),
+    ]
+};
+
+/// The class `nice` falls in; halfway between two, the lower priority one.
+#[cfg(windows)]
+fn class_of(nice: i32) -> (i32, u32, &'static str) {
+    CLASSES
+        .into_iter()
+        .rev()
+        .min_by_key(|(value, ..)| (value - nice).abs())
+        .unwrap_or(CLASSES[3])
+}
+
+/// The nice value that can actually be set closest to `nice`: itself, or on Windows that of its
+/// class.
+pub fn nearest(nice: i32) -> i32 {
+    #[cfg(windows)]
+    let nice = class_of(nice).0;
+    nice
+}
+
+/// `nice` as the editor shows it: the number, or the name of its class on Windows.
+pub fn describe(nice: i32) -> String {
+    #[cfg(windows)]
+    let text = class_of(nice).2.to_string();
+    #[cfg(not(windows))]
+    let text = nice.to_string();
+    text
+}
+
+/// The nice value `steps` up from `nice`, or down for negative steps; on Windows, classes are
+/// stepped through rather than values.
+pub fn step(nice: i32, steps: i32) -> i32 {
+    #[cfg(windows)]
+    let nice = {
+        let index = CLASSES
+            .iter()
+            .position(|class| class.0 == class_of(nice).0)
+            .unwrap_or(3);
+        let index = index
+            .saturating_add_signed(steps as isize)
+            .min(CLASSES.len() - 1);
+        CLASSES[index].0
+    };
+    #[cfg(not(windows))]
+    let nice = (nice + steps).clamp(*NICE_RANGE.start(), *NICE_RANGE.end());
+    nice
+}
+
+pub struct Entry {
+    pub pid: u32,
+    pub name: String,
+    /// Current nice value, or why it cannot be read.
+    pub from: Result<i32, String>,
+    pub to: i32,
+    /// Why setting the new value failed, once applied.
+    pub error: Option<String>,
+}
+
+pub struct RenicePlan {
+    pub delta: i32,
+    pub entries: Vec<Entry>,
+    pub applied: bool,
+}
+
+impl RenicePlan {
+    /// Reads the current nice value of each `(pid, name)`.
+    pub fn new(delta: i32, processes: impl IntoIterator<Item = (u32, String)>) -> Self {
+        let entries = processes
+            .into_iter()
+            .map(|(pid, name)| {
+                let from = nice(pid).map_err(|e| e.to_string());
+                let to = from.as_ref().map_or(0, |&from| {
+                    nearest((from + delta).clamp(*NICE_RANGE.start(), *NICE_RANGE.end()))
+                });
+                Entry {
+                    pid,
+                    name,
+                    from,
+                    to,
+                    error: None,
+                }
+            })
+            .collect();
+        Self {
+            delta,
+            entries,
+            applied: false,
+        }
+    }
+
+    /// Sets the new nice values; returns how many processes were reniced.
+    pub fn apply(&mut self) -> usize {
+        self.applied = true;
+        for entry in &mut self.entries {
+            entry.error = match &entry.from {
+                Ok(_) => set_nice(entry.pid, entry.to).err().map(|e| e.to_string()),
+                Err(e) => Some(e.clone()),
+            };
+        }
+        self.entries
+            .iter()
+            .filter(|entry| entry.error.is_none())
+            .count()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|entry| entry.error.is_some())
+    }
+}
+
+/// Parses a delta as typed by the user, e.g. `+5`, `-3` or `5`.
+pub fn parse_delta(input: &str) -> Result<i32, String> {
+    let input = input.trim();
+    input
+        .strip_prefix('+')
+        .unwrap_or(input)
+        .parse()
+        .ok()
+        .filter(|&delta: &i32| delta != 0 && delta.abs() <= 39)
+        .ok_or_else(|| format!("Not a priority change: {input}"))
+}
+
+#[cfg(unix)]
+pub fn nice(pid: u32) -> std::io::Result<i32> {
+    clear_errno();
+    // SAFETY: getpriority(2) has no memory safety requirements.
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+    // -1 is also a valid nice value, only errno tells them apart.
+    let error = std::io::Error::last_os_error();
+    if nice == -1 && error.raw_os_error().is_some_and(|errno| errno != 0) {
+        Err(error)
+    } else {
+        Ok(nice)
+    }
+}
+
+#[cfg(unix)]
+pub fn set_nice(pid: u32, nice: i32) -> std::io::Result<()> {
+    // SAFETY: setpriority(2) has no memory safety requirements.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(unix)]
+fn clear_errno() {
+    // SAFETY: the errno location is valid for the current thread.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        *libc::__errno_location() = 0;
+    }
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    unsafe {
+        *libc::__error() = 0;
+    }
+}
+
+#[cfg(windows)]
+pub fn nice(pid: u32) -> std::io::Result<i32> {
+    let class = crate::win32::priority_class(pid)?;
+    CLASSES
+        .into_iter()
+        .find(|&(_, known, _)| known == class)
+        .map(|(nice, ..)| nice)
+        .ok_or_else(|| std::io::Error::other(format!("unknown priority class {class:#x}")))
+}
+
+#[cfg(windows)]
+pub fn set_nice(pid: u32, nice: i32) -> std::io::Result<()> {
+    crate::win32::set_priority_class(pid, class_of(nice).1)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn nice(_pid: u32) -> std::io::Result<i32> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn set_nice(_pid: u32, _nice: i32) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nice_values_stand_for_priority_classes() {
+        assert_eq!(nearest(3), 0);
+        // Halfway between normal and below normal.
+        assert_eq!(nearest(5), 10);
+        assert_eq!(describe(-12), "high");
+        assert_eq!(step(0, 1), 10);
+        assert_eq!(step(19, 1), 19);
+        assert_eq!(step(-20, -1), -20);
+
+        let pid = std::process::id();
+        let before = nice(pid).unwrap();
+        set_nice(pid, 12).unwrap();
+        assert_eq!(nice(pid).unwrap(), 10);
+        set_nice(pid, before).unwrap();
+    }
+}
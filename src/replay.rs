@@ -0,0 +1,180 @@
+//! Session logs (`--session-log`) and their replay (`syskill replay`).
+//!
+//! A session log is a recording (see [`crate::snapshot`]) with the actions taken in the table in
+//! between its snapshots, as lines of their own tagged with `action`, e.g.
+//! `{"action":"signal","timestamp":1760623402,"pid":1234,"name":"nginx","signal":"SIGKILL"}`.
+//! Readers of recordings skip the actions, so `syskill diff` and `--baseline` take session logs
+//! too.
+
+use crate::snapshot::{self, Snapshot};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Something done in the table, as logged between the snapshots.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// A signal sent to a process, by hand or by the watchdog, or a kill by the OOM killer.
+    Signal {
+        timestamp: u64,
+        pid: u32,
+        name: String,
+        /// As the detail view lists it, e.g. `SIGKILL` or `signal 10`.
+        signal: String,
+    },
+    /// The search changed; empty when it was cleared.
+    Filter { timestamp: u64, filter: String },
+}
+
+impl Action {
+    pub fn describe(&self) -> String {
+        match self {
+            Action::Signal {
+                pid, name, signal, ..
+            } => format!("{signal} to {name} ({pid})"),
+            Action::Filter { filter, .. } if filter.is_empty() => "search cleared".to_string(),
+            Action::Filter { filter, .. } => format!("search {filter}"),
+        }
+    }
+}
+
+/// Whether a line of a session log is an action rather than a snapshot. The tag comes first.
+pub fn is_action(line: &str) -> bool {
+    line.starts_with("{\"action\":")
+}
+
+/// Appends snapshots and actions to a session log.
+pub struct SessionLog {
+    writer: BufWriter<File>,
+    /// The search last logged, so an unchanged one is not logged again.
+    filter: String,
+}
+
+impl SessionLog {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::options().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            filter: String::new(),
+        })
+    }
+
+    pub fn snapshot(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        snapshot::append(&mut self.writer, snapshot)
+    }
+
+    pub fn action(&mut self, action: &Action) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, action)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    /// Logs the search if it changed since it was last logged.
+    pub fn filter(&mut self, filter: &str) -> io::Result<()> {
+        if filter == self.filter {
+            return Ok(());
+        }
+        self.filter = filter.to_string();
+        self.action(&Action::Filter {
+            timestamp: snapshot::unix_now(),
+            filter: filter.to_string(),
+        })
+    }
+}
+
+/// A session log loaded for stepping through its snapshots.
+pub struct Replay {
+    pub path: PathBuf,
+    snapshots: Vec<Snapshot>,
+    /// The actions logged before each snapshot, since the one before it. Those after the last
+    /// snapshot go with the last one.
+    actions: Vec<Vec<Action>>,
+    /// Index into `snapshots` of the one shown.
+    pub position: usize,
+}
+
+impl Replay {
+    /// Reads the session log at `path`, or any other recording, starting at its first snapshot.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut snapshots = Vec::new();
+        let mut actions = Vec::new();
+        let mut pending = Vec::new();
+
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = |e: serde_json::Error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: {e}", path.display(), line_no + 1),
+                )
+            };
+            if is_action(&line) {
+                pending.push(serde_json::from_str(&line).map_err(invalid)?);
+            } else {
+                snapshots.push(serde_json::from_str(&line).map_err(invalid)?);
+                actions.push(std::mem::take(&mut pending));
+            }
+        }
+        let Some(last) = actions.last_mut() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: no snapshots", path.display()),
+            ));
+        };
+        last.append(&mut pending);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            snapshots,
+            actions,
+            position: 0,
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn current(&self) -> &Snapshot {
+        &self.snapshots[self.position]
+    }
+
+    /// The actions logged before the snapshot shown.
+    pub fn actions(&self) -> &[Action] {
+        &self.actions[self.position]
+    }
+
+    /// Moves `steps` snapshots on, or back for negative steps, stopping at either end. Whether
+    /// it moved.
+    pub fn step(&mut self, steps: isize) -> bool {
+        let position = self
+            .position
+            .saturating_add_signed(steps)
+            .min(self.count() - 1);
+        let moved = position != self.position;
+        self.position = position;
+        moved
+    }
+
+    /// Moves to the next snapshot with actions before it, or the previous one. Whether there was
+    /// one.
+    pub fn step_to_action(&mut self, forward: bool) -> bool {
+        let found = if forward {
+            (self.position + 1..self.count()).find(|&i| !self.actions[i].is_empty())
+        } else {
+            (0..self.position)
+                .rev()
+                .find(|&i| !self.actions[i].is_empty())
+        };
+        if let Some(position) = found {
+            self.position = position;
+        }
+        found.is_some()
+    }
+}
@@ -0,0 +1,502 @@
+//! Incident reports: a shareable Markdown or HTML summary of the current process table, or any
+//! other format written as a [`Template`].
+
+use crate::app::KillRecord;
+use crate::snapshot::{unix_now, ProcessSample, Snapshot};
+use crate::template::Template;
+use crate::units::Units;
+use crate::users::UserFilter;
+use crate::watchdog::{Trigger, Watchdog};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::fmt::Write;
+use sysinfo::System;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub const fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+pub struct SystemSummary {
+    pub host_name: String,
+    pub os: String,
+    pub kernel: String,
+    pub uptime: u64,
+    pub load_average: [f64; 3],
+    pub cpu_count: usize,
+    pub cpu_usage: f32,
+    pub used_memory: u64,
+    pub total_memory: u64,
+    pub used_swap: u64,
+    pub total_swap: u64,
+    pub process_count: usize,
+}
+
+/// How many of the latest watchdog triggers a report lists.
+const TRIGGERS_SHOWN: usize = 20;
+
+/// The watchdog of the table: its rules and what they did lately.
+pub struct WatchdogSummary {
+    pub dry_run: bool,
+    /// As the panel lists them, e.g. `cpu > 90 for 30s then SIGTERM`.
+    pub rules: Vec<String>,
+    /// The latest triggers, oldest first.
+    pub(crate) triggers: Vec<Trigger>,
+}
+
+impl WatchdogSummary {
+    pub(crate) fn collect(watchdog: &Watchdog) -> Self {
+        let log = watchdog.log();
+        Self {
+            dry_run: watchdog.dry_run,
+            rules: watchdog.rules.iter().map(|rule| rule.describe()).collect(),
+            triggers: log[log.len().saturating_sub(TRIGGERS_SHOWN)..].to_vec(),
+        }
+    }
+}
+
+impl SystemSummary {
+    pub fn collect(system: &System) -> Self {
+        let load = System::load_average();
+        Self {
+            host_name: System::host_name().unwrap_or_default(),
+            os: System::long_os_version().unwrap_or_default(),
+            kernel: System::kernel_version().unwrap_or_default(),
+            uptime: System::uptime(),
+            load_average: [load.one, load.five, load.fifteen],
+            cpu_count: system.cpus().len(),
+            cpu_usage: system.global_cpu_info().cpu_usage(),
+            used_memory: system.used_memory(),
+            total_memory: system.total_memory(),
+            used_swap: system.used_swap(),
+            total_swap: system.total_swap(),
+            process_count: system.processes().len(),
+        }
+    }
+}
+
+pub struct Report {
+    pub generated_at: u64,
+    pub system: SystemSummary,
+    pub top_cpu: Vec<ProcessSample>,
+    pub top_memory: Vec<ProcessSample>,
+    /// Every process, by PID; only used by templates.
+    pub processes: Vec<ProcessSample>,
+    pub recent_kills: Vec<KillRecord>,
+    /// `None` where no watchdog runs, as in `syskill report`.
+    pub watchdog: Option<WatchdogSummary>,
+}
+
+impl Report {
+    pub fn collect(
+        system: &System,
+        recent_kills: &[KillRecord],
+        watchdog: Option<WatchdogSummary>,
+        top: usize,
+        user: Option<&UserFilter>,
+    ) -> Self {
+        let processes = Snapshot::capture(system, user).processes;
+
+        let mut top_cpu = processes.clone();
+        top_cpu.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        top_cpu.truncate(top);
+
+        let mut top_memory = processes.clone();
+        top_memory.sort_by_key(|p| std::cmp::Reverse(p.memory));
+        top_memory.truncate(top);
+
+        Self {
+            generated_at: unix_now(),
+            system: SystemSummary::collect(system),
+            top_cpu,
+            top_memory,
+            processes,
+            recent_kills: recent_kills.to_vec(),
+            watchdog,
+        }
+    }
+
+    pub fn render_template(&self, template: &Template, units: Units) -> Result<String, String> {
+        template.render(&self.context(units))
+    }
+
+    /// Values available to templates. Numbers are raw (bytes, percent of one core, seconds);
+    /// the `_text` variants are formatted like the table.
+    pub fn context(&self, units: Units) -> Value {
+        let process = |p: &ProcessSample| {
+            json!({
+                "pid": p.pid,
+                "name": p.name,
+                "cpu_usage": p.cpu_usage,
+                "cpu_text": units.cpu(p.cpu_usage),
+                "memory": p.memory,
+                "memory_text": units.memory(p.memory),
+                "exe": p.exe,
+                "parent": p.parent,
+                "user": p.user,
+                "state": p.state,
+                "sched": p.sched,
+            })
+        };
+        let s = &self.system;
+        json!({
+            "generated_at": self.generated_at,
+            "system": {
+                "host_name": s.host_name,
+                "os": s.os,
+                "kernel": s.kernel,
+                "uptime": s.uptime,
+                "uptime_text": format_duration(s.uptime),
+                "load_average": s.load_average,
+                "cpu_count": s.cpu_count,
+                "cpu_usage": s.cpu_usage,
+                "used_memory": s.used_memory,
+                "total_memory": s.total_memory,
+                "used_swap": s.used_swap,
+                "total_swap": s.total_swap,
+                "process_count": s.process_count,
+            },
+            "top_cpu": self.top_cpu.iter().map(process).collect::<Vec<_>>(),
+            "top_memory": self.top_memory.iter().map(process).collect::<Vec<_>>(),
+            "processes": self.processes.iter().map(process).collect::<Vec<_>>(),
+            "recent_kills": self
+                .recent_kills
+                .iter()
+                .map(|kill| json!({"timestamp": kill.timestamp, "pid": kill.pid, "name": kill.name}))
+                .collect::<Vec<_>>(),
+            "watchdog": self.watchdog.as_ref().map(|watchdog| json!({
+                "dry_run": watchdog.dry_run,
+                "rules": watchdog.rules,
+                "triggers": watchdog
+                    .triggers
+                    .iter()
+                    .map(|trigger| json!({
+                        "timestamp": trigger.timestamp,
+                        "rule": trigger.rule,
+                        "pid": trigger.pid,
+                        "name": trigger.name,
+                        "outcome": trigger.outcome,
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+        })
+    }
+
+    pub fn render(&self, format: ReportFormat, units: Units) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_markdown(units),
+            ReportFormat::Html => self.to_html(units),
+        }
+    }
+
+    fn summary_rows(&self, units: Units) -> Vec<(&'static str, String)> {
+        let s = &self.system;
+        vec![
+            ("Host", s.host_name.clone()),
+            ("OS", s.os.clone()),
+            ("Kernel", s.kernel.clone()),
+            ("Uptime", format_duration(s.uptime)),
+            (
+                "Load average",
+                format!(
+                    "{:.2} {:.2} {:.2}",
+                    s.load_average[0], s.load_average[1], s.load_average[2]
+                ),
+            ),
+            (
+                "CPU",
+                format!("{:.1}% of {} cores", s.cpu_usage, s.cpu_count),
+            ),
+            (
+                "Memory",
+                format!(
+                    "{} / {}",
+                    units.memory(s.used_memory),
+                    units.memory(s.total_memory)
+                ),
+            ),
+            (
+                "Swap",
+                format!(
+                    "{} / {}",
+                    units.memory(s.used_swap),
+                    units.memory(s.total_swap)
+                ),
+            ),
+            ("Processes", s.process_count.to_string()),
+        ]
+    }
+
+    fn to_markdown(&self, units: Units) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# syskill incident report\n");
+        let _ = writeln!(out, "Generated at {} (Unix time).\n", self.generated_at);
+
+        let _ = writeln!(out, "## System summary\n");
+        let _ = writeln!(out, "| | |\n|---|---|");
+        for (key, value) in self.summary_rows(units) {
+            let _ = writeln!(out, "| {key} | {} |", escape_markdown(&value));
+        }
+
+        for (title, processes) in [
+            ("Top CPU consumers", &self.top_cpu),
+            ("Top memory consumers", &self.top_memory),
+        ] {
+            let _ = writeln!(out, "\n## {title}\n");
+            let _ = writeln!(out, "| NAME | PID | CPU | MEMORY |\n|---|---:|---:|---:|");
+            for p in processes {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} |",
+                    escape_markdown(&p.name),
+                    p.pid,
+                    units.cpu(p.cpu_usage),
+                    units.memory(p.memory)
+                );
+            }
+        }
+
+        let _ = writeln!(out, "\n## Recent kills\n");
+        if self.recent_kills.is_empty() {
+            let _ = writeln!(out, "No processes were killed this session.");
+        } else {
+            let _ = writeln!(out, "| TIME | NAME | PID |\n|---|---|---:|");
+            for kill in &self.recent_kills {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    kill.timestamp,
+                    escape_markdown(&kill.name),
+                    kill.pid
+                );
+            }
+        }
+
+        if let Some(watchdog) = &self.watchdog {
+            let _ = writeln!(out, "\n## Watchdog\n");
+            if watchdog.dry_run {
+                let _ = writeln!(out, "Dry run: the rules only log what they would do.\n");
+            }
+            if watchdog.rules.is_empty() {
+                let _ = writeln!(out, "No rules are set.");
+            } else {
+                let _ = writeln!(out, "| RULE |\n|---|");
+                for rule in &watchdog.rules {
+                    let _ = writeln!(out, "| {} |", escape_markdown(rule));
+                }
+            }
+            let _ = writeln!(out);
+            if watchdog.triggers.is_empty() {
+                let _ = writeln!(out, "No rule went off this session.");
+            } else {
+                let _ = writeln!(
+                    out,
+                    "| TIME | RULE | NAME | PID | OUTCOME |\n|---|---|---|---:|---|"
+                );
+                for trigger in &watchdog.triggers {
+                    let _ = writeln!(
+                        out,
+                        "| {} | {} | {} | {} | {} |",
+                        trigger.timestamp,
+                        escape_markdown(&trigger.rule),
+                        escape_markdown(&trigger.name),
+                        trigger.pid,
+                        escape_markdown(&trigger.outcome)
+                    );
+                }
+            }
+        }
+
+        out
+    }
+
+    fn to_html(&self, units: Units) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>syskill incident report</title>\n\
+             <style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             td, th {{ border: 1px solid #ccc; padding: 2px 8px; text-align: left; }}</style>\n\
+             </head>\n<body>"
+        );
+        let _ = writeln!(out, "<h1>syskill incident report</h1>");
+        let _ = writeln!(
+            out,
+            "<p>Generated at {} (Unix time).</p>",
+            self.generated_at
+        );
+
+        let _ = writeln!(out, "<h2>System summary</h2>\n<table>");
+        for (key, value) in self.summary_rows(units) {
+            let _ = writeln!(
+                out,
+                "<tr><th>{key}</th><td>{}</td></tr>",
+                escape_html(&value)
+            );
+        }
+        let _ = writeln!(out, "</table>");
+
+        for (title, processes) in [
+            ("Top CPU consumers", &self.top_cpu),
+            ("Top memory consumers", &self.top_memory),
+        ] {
+            let _ = writeln!(out, "<h2>{title}</h2>\n<table>");
+            let _ = writeln!(
+                out,
+                "<tr><th>NAME</th><th>PID</th><th>CPU</th><th>MEMORY</th></tr>"
+            );
+            for p in processes {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(&p.name),
+                    p.pid,
+                    units.cpu(p.cpu_usage),
+                    units.memory(p.memory)
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+
+        let _ = writeln!(out, "<h2>Recent kills</h2>");
+        if self.recent_kills.is_empty() {
+            let _ = writeln!(out, "<p>No processes were killed this session.</p>");
+        } else {
+            let _ = writeln!(
+                out,
+                "<table>\n<tr><th>TIME</th><th>NAME</th><th>PID</th></tr>"
+            );
+            for kill in &self.recent_kills {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    kill.timestamp,
+                    escape_html(&kill.name),
+                    kill.pid
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+
+        if let Some(watchdog) = &self.watchdog {
+            let _ = writeln!(out, "<h2>Watchdog</h2>");
+            if watchdog.dry_run {
+                let _ = writeln!(
+                    out,
+                    "<p>Dry run: the rules only log what they would do.</p>"
+                );
+            }
+            if watchdog.rules.is_empty() {
+                let _ = writeln!(out, "<p>No rules are set.</p>");
+            } else {
+                let _ = writeln!(out, "<table>\n<tr><th>RULE</th></tr>");
+                for rule in &watchdog.rules {
+                    let _ = writeln!(out, "<tr><td>{}</td></tr>", escape_html(rule));
+                }
+                let _ = writeln!(out, "</table>");
+            }
+            if watchdog.triggers.is_empty() {
+                let _ = writeln!(out, "<p>No rule went off this session.</p>");
+            } else {
+                let _ = writeln!(
+                    out,
+                    "<table>\n<tr><th>TIME</th><th>RULE</th><th>NAME</th><th>PID</th>\
+                     <th>OUTCOME</th></tr>"
+                );
+                for trigger in &watchdog.triggers {
+                    let _ = writeln!(
+                        out,
+                        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                        trigger.timestamp,
+                        escape_html(&trigger.rule),
+                        escape_html(&trigger.name),
+                        trigger.pid,
+                        escape_html(&trigger.outcome)
+                    );
+                }
+                let _ = writeln!(out, "</table>");
+            }
+        }
+
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+}
+
+pub(crate) fn format_duration(seconds: u64) -> String {
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    format!("{days}d {}h {}m", rest / 3600, (rest % 3600) / 60)
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_list_the_watchdog() {
+        let mut report = Report {
+            generated_at: 1_760_623_402,
+            system: SystemSummary {
+                host_name: "db-1".to_string(),
+                os: String::new(),
+                kernel: String::new(),
+                uptime: 0,
+                load_average: [0.0; 3],
+                cpu_count: 1,
+                cpu_usage: 0.0,
+                used_memory: 0,
+                total_memory: 0,
+                used_swap: 0,
+                total_swap: 0,
+                process_count: 0,
+            },
+            top_cpu: Vec::new(),
+            top_memory: Vec::new(),
+            processes: Vec::new(),
+            recent_kills: Vec::new(),
+            watchdog: Some(WatchdogSummary {
+                dry_run: true,
+                rules: vec!["cpu > 90 for 30s then SIGTERM".to_string()],
+                triggers: vec![Trigger {
+                    timestamp: 1_760_623_400,
+                    rule: "cpu > 90 for 30s then SIGTERM".to_string(),
+                    pid: 5_000_000,
+                    name: "<worker>".to_string(),
+                    outcome: "dry run".to_string(),
+                }],
+            }),
+        };
+        let markdown = report.render(ReportFormat::Markdown, Units::default());
+        assert!(markdown.contains("## Watchdog\n\nDry run"));
+        assert!(markdown.contains("| cpu > 90 for 30s then SIGTERM |\n"));
+        assert!(markdown.contains("| <worker> | 5000000 | dry run |"));
+        let html = report.render(ReportFormat::Html, Units::default());
+        assert!(html.contains("<td>&lt;worker&gt;</td><td>5000000</td><td>dry run</td>"));
+
+        report.watchdog = None;
+        let markdown = report.render(ReportFormat::Markdown, Units::default());
+        assert!(!markdown.contains("Watchdog"));
+    }
+}
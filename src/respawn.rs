@@ -0,0 +1,174 @@
+//! Killed processes that come right back, restarted by a supervisor such as systemd, a container
+//! runtime or a shell loop, so killing them again would only be whack-a-mole.
+//!
+//! A kill is watched for a few refreshes: a process started since, with the name of the killed
+//! one and the same executable where both are known, is taken for it respawned.
+
+use crate::app::ProcessInfo;
+use crate::proc::ProcessKey;
+use std::collections::HashSet;
+
+/// Refreshes after a kill during which a new process of the same name counts as a respawn.
+const WATCHED_REFRESHES: u32 = 5;
+
+struct Watch {
+    killed: ProcessKey,
+    name: String,
+    exe: String,
+    /// Seconds since the Unix epoch when it was killed.
+    at: u64,
+    /// The processes of the same name already running then, which are not respawns.
+    running: HashSet<ProcessKey>,
+    refreshes_left: u32,
+}
+
+pub struct Respawn {
+    pub killed: ProcessKey,
+    pub name: String,
+    pub pid: u32,
+    /// The parent of the new process, most likely what restarted it, with its name.
+    pub parent: Option<(u32, String)>,
+    /// E.g. `nginx.service`; empty if none or unknown.
+    pub container: String,
+}
+
+impl Respawn {
+    /// For the status line, e.g. `nginx (812) respawned as 1290, likely supervised by systemd
+    /// (1) in nginx.service`.
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} ({}) respawned as {}, likely supervised",
+            self.name, self.killed.pid, self.pid
+        );
+        if let Some((pid, name)) = &self.parent {
+            text.push_str(&format!(" by {name} ({pid})"));
+        }
+        if !self.container.is_empty() {
+            text.push_str(&format!(" in {}", self.container));
+        }
+        text
+    }
+}
+
+#[derive(Default)]
+pub struct Respawns {
+    watches: Vec<Watch>,
+}
+
+impl Respawns {
+    /// Starts watching for `killed` to come back, among `processes` as they were when it was
+    /// killed at `now`.
+    pub fn watch(&mut self, killed: &ProcessInfo, processes: &[ProcessInfo], now: u64) {
+        self.watches.retain(|watch| watch.killed != killed.key());
+        self.watches.push(Watch {
+            killed: killed.key(),
+            name: killed.name.clone(),
+            exe: killed.exe.clone(),
+            at: now,
+            running: processes
+                .iter()
+                .filter(|data| data.name == killed.name)
+                .map(ProcessInfo::key)
+                .collect(),
+            refreshes_left: WATCHED_REFRESHES,
+        });
+    }
+
+    /// The killed processes that came back since the last refresh, now that `processes` are
+    /// running. Each new process stands for one killed process at most, and a kill is watched no
+    /// more once its respawn was found or it ran out of refreshes.
+    pub fn observe(&mut self, processes: &[ProcessInfo]) -> Vec<Respawn> {
+        let mut claimed = HashSet::new();
+        let mut respawns = Vec::new();
+        self.watches.retain_mut(|watch| {
+            let found = processes.iter().find(|data| {
+                data.members == 0
+                    && data.exited.is_none()
+                    && data.name == watch.name
+                    && (watch.exe.is_empty() || data.exe.is_empty() || data.exe == watch.exe)
+                    // Start times are whole seconds and may round down across the kill.
+                    && data.start_time + 1 >= watch.at
+                    && !watch.running.contains(&data.key())
+                    && !claimed.contains(&data.key())
+            });
+            match found {
+                Some(data) => {
+                    claimed.insert(data.key());
+                    respawns.push(Respawn {
+                        killed: watch.killed,
+                        name: data.name.clone(),
+                        pid: data.pid,
+                        parent: data.parent.and_then(|ppid| {
+                            let parent = processes.iter().find(|parent| parent.pid == ppid)?;
+                            Some((ppid, parent.name.clone()))
+                        }),
+                        container: data.container.clone(),
+                    });
+                    false
+                }
+                None => {
+                    watch.refreshes_left -= 1;
+                    watch.refreshes_left > 0
+                }
+            }
+        });
+        respawns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::ProcessSample;
+
+    fn process(pid: u32, name: &str, parent: u32, start_time: u64) -> ProcessInfo {
+        ProcessInfo::from(ProcessSample {
+            pid,
+            name: name.to_string(),
+            exe: format!("/usr/bin/{name}"),
+            parent: Some(parent),
+            start_time,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn a_new_process_of_the_killed_name_is_a_respawn() {
+        let supervisor = process(5_000_000, "runsv", 1, 100);
+        let killed = process(5_000_001, "worker", 5_000_000, 100);
+        let sibling = process(5_000_002, "worker", 5_000_000, 100);
+        let mut respawns = Respawns::default();
+        respawns.watch(
+            &killed,
+            &[supervisor.clone(), killed.clone(), sibling.clone()],
+            200,
+        );
+        assert!(respawns
+            .observe(&[supervisor.clone(), sibling.clone()])
+            .is_empty());
+        let other = process(5_000_003, "editor", 5_000_000, 201);
+        let back = process(5_000_004, "worker", 5_000_000, 201);
+        let found = respawns.observe(&[supervisor.clone(), sibling, other, back]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pid, 5_000_004);
+        assert_eq!(
+            found[0].describe(),
+            "worker (5000001) respawned as 5000004, likely supervised by runsv (5000000)"
+        );
+        // Found once only.
+        let again = process(5_000_005, "worker", 5_000_000, 202);
+        assert!(respawns.observe(&[supervisor, again]).is_empty());
+    }
+
+    #[test]
+    fn kills_are_watched_for_a_few_refreshes() {
+        let killed = process(5_000_001, "worker", 1, 100);
+        let mut respawns = Respawns::default();
+        respawns.watch(&killed, std::slice::from_ref(&killed), 200);
+        for _ in 0..WATCHED_REFRESHES {
+            assert!(respawns.observe(&[]).is_empty());
+        }
+        let late = process(5_000_002, "worker", 1, 300);
+        assert!(respawns.observe(&[late]).is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+//! JSON-RPC 2.0 over stdio, for editor plugins that bring their own UI.
+//!
+//! `syskill --rpc` reads one request per line from stdin and writes one response per line to
+//! stdout. Methods:
+//!
+//! - `list` `{"filter"?: string}`: processes whose name matches the filter like the search of the
+//!   table (or the session filter set with `filter`), sorted by PID.
+//! - `filter` `{"pattern": string}`: sets the session filter; an empty pattern clears it.
+//! - `signal` `{"pid": number, "signal"?: string, "start_time"?: number}`: sends a signal
+//!   (default `TERM`) to a listed process, if it still has the start time given, unless started
+//!   with `--read-only`.
+//! - `subscribe` `{"interval"?: seconds}`: starts `update` notifications carrying a snapshot of
+//!   the filtered process list; `unsubscribe` stops them.
+
+use crate::audit;
+use crate::proc::{self, KillRequest, ProcessKey};
+use crate::snapshot::{ProcessSample, Snapshot};
+use crate::users::{self, UserFilter};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Signal, System};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Application error, e.g. a signal that could not be delivered.
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    /// Absent for notifications, which get no response.
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    filter: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FilterParams {
+    pattern: String,
+}
+
+#[derive(Deserialize)]
+struct SignalParams {
+    pid: u32,
+    signal: Option<String>,
+    /// Start time the process must have, as listed, so a process that got its PID since is not
+    /// signalled.
+    start_time: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    interval: Option<f64>,
+}
+
+struct Server {
+    system: System,
+    filter: String,
+    user: Option<UserFilter>,
+    /// Refuse to send signals (`--read-only`).
+    read_only: bool,
+    subscription: Option<Duration>,
+    next_update: Instant,
+}
+
+impl Server {
+    fn handle(&mut self, request: Request) -> Result<Value, RpcError> {
+        match request.method.as_str() {
+            "list" => {
+                let params: ListParams = params(request.params)?;
+                let filter = params.filter.unwrap_or_else(|| self.filter.clone());
+                self.refresh();
+                Ok(json!(self.processes(&filter)))
+            }
+            "filter" => {
+                let params: FilterParams = params(request.params)?;
+                self.filter = params.pattern;
+                self.refresh();
+                Ok(json!({ "matches": self.processes(&self.filter).len() }))
+            }
+            "signal" => {
+                let params: SignalParams = params(request.params)?;
+                let signal = match params.signal.as_deref() {
+                    Some(name) => crate::signal::parse(name).ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, format!("unknown signal {name}"))
+                    })?,
+                    None => Signal::Term,
+                };
+                self.signal(params, signal)
+            }
+            "subscribe" => {
+                let params: SubscribeParams = params(request.params)?;
+                let interval = Duration::try_from_secs_f64(params.interval.unwrap_or(1.0))
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                self.subscription = Some(interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL));
+                self.next_update = Instant::now();
+                Ok(json!(true))
+            }
+            "unsubscribe" => {
+                self.subscription = None;
+                Ok(json!(true))
+            }
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {other}"),
+            )),
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.system.refresh_cpu();
+        self.system
+            .refresh_processes_specifics(users::refresh_kind());
+    }
+
+    fn processes(&self, filter: &str) -> Vec<ProcessSample> {
+        let snapshot = Snapshot::capture(&self.system, self.user.as_ref());
+        proc::matching(&snapshot, filter)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    fn signal(&mut self, params: SignalParams, signal: Signal) -> Result<Value, RpcError> {
+        if self.read_only {
+            return Err(RpcError::new(
+                SERVER_ERROR,
+                "read-only: signals are not sent",
+            ));
+        }
+        let pid = Pid::from_u32(params.pid);
+        self.system
+            .refresh_process_specifics(pid, users::refresh_kind());
+        let process = self
+            .system
+            .process(pid)
+            .ok_or_else(|| RpcError::new(SERVER_ERROR, format!("no process with PID {pid}")))?;
+        // Only the processes that are listed can be signalled.
+        if let Some(user) = self.user.as_ref().filter(|user| !user.matches(process)) {
+            return Err(RpcError::new(
+                SERVER_ERROR,
+                format!(
+                    "PID {pid} does not belong to {}, nothing was sent",
+                    user.name
+                ),
+            ));
+        }
+        let name = process.name().to_string();
+        let mut request = KillRequest::new(params.pid, signal);
+        if let Some(start_time) = params.start_time {
+            request = request.expecting(&ProcessKey {
+                pid: params.pid,
+                start_time,
+            });
+        }
+        let result = request.send(&mut self.system);
+        let outcome = match &result {
+            Ok(_) => audit::SENT,
+            Err(e) => e.message.as_str(),
+        };
+        let owner = users::owner(pid).unwrap_or_default();
+        audit::record(params.pid, &name, &owner, signal, outcome);
+        match result {
+            Ok(killed) => Ok(json!({
+                "pid": params.pid,
+                "name": killed.name,
+                "signal": signal.to_string(),
+            })),
+            Err(e) => Err(RpcError::new(SERVER_ERROR, e.message)),
+        }
+    }
+
+    fn update(&mut self) -> Value {
+        self.refresh();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "update",
+            "params": Snapshot {
+                timestamp: crate::snapshot::unix_now(),
+                processes: self.processes(&self.filter),
+                without_disk: false,
+            },
+        })
+    }
+}
+
+/// Decodes `params`, treating omitted params like an empty object.
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    }
+}
+
+fn write(message: &Value) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, message)?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()
+}
+
+/// Serves requests from stdin until it is closed, only ever listing the processes of `user`, and
+/// refusing to send signals if `read_only`.
+pub fn run(user: Option<UserFilter>, read_only: bool) -> io::Result<()> {
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = Server {
+        system: System::new_all(),
+        filter: String::new(),
+        user,
+        read_only,
+        subscription: None,
+        next_update: Instant::now(),
+    };
+
+    loop {
+        let timeout = match server.subscription {
+            Some(_) => server.next_update.saturating_duration_since(Instant::now()),
+            None => Duration::from_secs(3600),
+        };
+        match lines.recv_timeout(timeout) {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => {
+                if let Some(reply) = handle_line(&mut server, &line) {
+                    write(&reply)?;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        if let Some(interval) = server.subscription {
+            if Instant::now() >= server.next_update {
+                write(&server.update())?;
+                server.next_update = Instant::now() + interval;
+            }
+        }
+    }
+}
+
+fn handle_line(server: &mut Server, line: &str) -> Option<Value> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, e.to_string());
+            return Some(response(Value::Null, Err(error)));
+        }
+    };
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(INVALID_REQUEST, e.to_string());
+            return Some(response(Value::Null, Err(error)));
+        }
+    };
+    let id = request.id.clone();
+    let result = server.handle(request);
+    id.map(|id| response(id, result))
+}
@@ -0,0 +1,119 @@
+//! Health rules: limits on the processes of a snapshot, read from a TOML file.
+//!
+//! ```toml
+//! [[rule]]
+//! process = "nginx"
+//! min_count = 1
+//!
+//! [[rule]]
+//! name = "java heap"
+//! process = "java*"
+//! max_cpu = 90
+//! max_memory_mib = 4096
+//! ```
+//!
+//! `process` is a name, or a prefix followed by `*`; without it a rule applies to every process.
+
+use crate::snapshot::{ProcessSample, Snapshot};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Shown in violations; defaults to the process pattern.
+    pub name: Option<String>,
+    pub process: Option<String>,
+    /// CPU usage in percent that no matching process may exceed.
+    pub max_cpu: Option<f32>,
+    /// Memory in MiB that no matching process may exceed.
+    pub max_memory_mib: Option<u64>,
+    /// Number of matching processes that must be running.
+    pub min_count: Option<usize>,
+    pub max_count: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+/// A rule that does not hold, with what was found.
+pub struct Violation {
+    pub rule: String,
+    pub message: String,
+}
+
+impl Rules {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Violations of all rules in `snapshot`, in rule order.
+    pub fn evaluate(&self, snapshot: &Snapshot) -> Vec<Violation> {
+        self.rules
+            .iter()
+            .flat_map(|rule| rule.evaluate(snapshot))
+            .collect()
+    }
+}
+
+impl Rule {
+    pub fn label(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.process.clone())
+            .unwrap_or_else(|| "all processes".to_string())
+    }
+
+    pub fn matches(&self, process: &ProcessSample) -> bool {
+        match self.process.as_deref() {
+            None => true,
+            Some(pattern) => match pattern.strip_suffix('*') {
+                Some(prefix) => process.name.starts_with(prefix),
+                None => process.name == pattern,
+            },
+        }
+    }
+
+    pub fn evaluate(&self, snapshot: &Snapshot) -> Vec<Violation> {
+        let matching: Vec<&ProcessSample> = snapshot
+            .processes
+            .iter()
+            .filter(|process| self.matches(process))
+            .collect();
+        let mut messages = Vec::new();
+        let count = matching.len();
+        if let Some(min) = self.min_count.filter(|&min| count < min) {
+            messages.push(format!("{count} running, at least {min} expected"));
+        }
+        if let Some(max) = self.max_count.filter(|&max| count > max) {
+            messages.push(format!("{count} running, at most {max} expected"));
+        }
+        for process in matching {
+            if let Some(max) = self.max_cpu.filter(|&max| process.cpu_usage > max) {
+                messages.push(format!(
+                    "{} ({}) uses {:.1}% CPU, limit {max}%",
+                    process.name, process.pid, process.cpu_usage
+                ));
+            }
+            let mib = process.memory / (1024 * 1024);
+            if let Some(max) = self.max_memory_mib.filter(|&max| mib > max) {
+                messages.push(format!(
+                    "{} ({}) uses {mib} MiB, limit {max} MiB",
+                    process.name, process.pid
+                ));
+            }
+        }
+        messages
+            .into_iter()
+            .map(|message| Violation {
+                rule: self.label(),
+                message,
+            })
+            .collect()
+    }
+}
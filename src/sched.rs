@@ -0,0 +1,119 @@
+//! Scheduling policies, shown in the `SCHED` column and changed like `chrt` does.
+//!
+//! Real-time policies (FIFO and RR) come with a static priority from 1 to 99; the others always
+//! have priority 0 and are tuned with nice values instead (see [`crate::renice`]). Linux only.
+
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Policy {
+    Other,
+    Fifo,
+    RoundRobin,
+    Batch,
+    Idle,
+}
+
+/// Policies offered by the picker, with a description.
+pub const PICKER: [(Policy, &str); 5] = [
+    (Policy::Other, "default time-sharing"),
+    (Policy::Batch, "CPU-bound work, preempted less often"),
+    (Policy::Idle, "only runs when nothing else wants the CPU"),
+    (Policy::Fifo, "real-time, runs until it blocks or yields"),
+    (Policy::RoundRobin, "real-time, with time slices"),
+];
+
+/// Static priorities of the real-time policies.
+pub const REALTIME_PRIORITIES: std::ops::RangeInclusive<i32> = 1..=99;
+
+impl Policy {
+    /// As `chrt` names it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Policy::Other => "OTHER",
+            Policy::Fifo => "FIFO",
+            Policy::RoundRobin => "RR",
+            Policy::Batch => "BATCH",
+            Policy::Idle => "IDLE",
+        }
+    }
+
+    pub const fn realtime(self) -> bool {
+        matches!(self, Policy::Fifo | Policy::RoundRobin)
+    }
+}
+
+/// Policy and priority of `pid` for the table, e.g. `OTHER` or `FIFO 50`; empty if unknown.
+pub fn describe(pid: u32) -> String {
+    match get(pid) {
+        Ok((policy, _)) if !policy.realtime() => policy.name().to_string(),
+        Ok((policy, priority)) => format!("{} {priority}", policy.name()),
+        Err(_) => String::new(),
+    }
+}
+
+/// Parses the priority typed into the picker. Only real-time policies have one; for the others
+/// the input is not shown and ignored.
+pub fn parse_priority(policy: Policy, input: &str) -> Result<i32, String> {
+    if !policy.realtime() {
+        return Ok(0);
+    }
+    input
+        .trim()
+        .parse()
+        .ok()
+        .filter(|priority| REALTIME_PRIORITIES.contains(priority))
+        .ok_or_else(|| format!("{} needs a priority from 1 to 99", policy.name()))
+}
+
+#[cfg(target_os = "linux")]
+pub fn get(pid: u32) -> io::Result<(Policy, i32)> {
+    // SAFETY: sched_getscheduler(2) has no memory safety requirements.
+    let policy = unsafe { libc::sched_getscheduler(pid as libc::pid_t) };
+    if policy == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut param = libc::sched_param { sched_priority: 0 };
+    // SAFETY: `param` is a valid sched_param to write to.
+    if unsafe { libc::sched_getparam(pid as libc::pid_t, &mut param) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let policy = match policy & !libc::SCHED_RESET_ON_FORK {
+        libc::SCHED_FIFO => Policy::Fifo,
+        libc::SCHED_RR => Policy::RoundRobin,
+        libc::SCHED_BATCH => Policy::Batch,
+        libc::SCHED_IDLE => Policy::Idle,
+        _ => Policy::Other,
+    };
+    Ok((policy, param.sched_priority))
+}
+
+#[cfg(target_os = "linux")]
+pub fn set(pid: u32, policy: Policy, priority: i32) -> io::Result<()> {
+    let policy = match policy {
+        Policy::Other => libc::SCHED_OTHER,
+        Policy::Fifo => libc::SCHED_FIFO,
+        Policy::RoundRobin => libc::SCHED_RR,
+        Policy::Batch => libc::SCHED_BATCH,
+        Policy::Idle => libc::SCHED_IDLE,
+    };
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: `param` is a valid sched_param to read from.
+    if unsafe { libc::sched_setscheduler(pid as libc::pid_t, policy, &param) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get(_pid: u32) -> io::Result<(Policy, i32)> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set(_pid: u32, _policy: Policy, _priority: i32) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
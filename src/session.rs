@@ -0,0 +1,417 @@
+//! Session multiplexing over a Unix socket.
+//!
+//! `syskill serve` runs a single headless collector (optionally recording to a file) and streams
+//! every snapshot to all clients connected to its socket. `syskill attach` runs the usual TUI on
+//! top of that stream, so any number of interactive sessions share one collector. Kills requested
+//! by attached clients are carried out by the server.
+//!
+//! With `--stdio` the server speaks to a single client on its stdin and stdout instead, which is
+//! how `syskill --host user@server` runs it on another machine over SSH.
+//!
+//! The protocol is JSON Lines in both directions, see [`ServerMessage`] and [`ClientMessage`].
+
+use crate::proc::KillRequest;
+use crate::snapshot::{self, ProcessKey, Snapshot};
+use crate::users::{self, UserFilter};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Signal, System};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Snapshot(Snapshot),
+    KillResult {
+        pid: u32,
+        name: String,
+        ok: bool,
+        /// The server was started with `--read-only` and did not try.
+        #[serde(default)]
+        read_only: bool,
+        /// Why the process was not killed, e.g. its PID went to another process.
+        #[serde(default)]
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Kills the process `pid` if it still has the start time the client saw, so that a
+    /// process that got the PID since is left alone.
+    Kill { pid: u32, start_time: u64 },
+}
+
+/// Socket used when none is given: `$XDG_RUNTIME_DIR/syskill.sock`, or a per-user file in the
+/// temporary directory.
+pub fn default_socket() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("syskill.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+            std::env::temp_dir().join(format!("syskill-{user}.sock"))
+        }
+    }
+}
+
+/// What the server said about a kill: the PID, the name, whether it was killed, whether the
+/// server is read-only and why it failed.
+pub type KillResult = (u32, String, bool, bool, Option<String>);
+
+/// Where the reply to a client's request goes.
+type Reply = Box<dyn Write + Send>;
+
+fn send(stream: &mut impl Write, message: &ServerMessage) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()
+}
+
+/// Runs the collector and serves it on `socket` until interrupted.
+pub fn serve(
+    socket: &Path,
+    interval: Duration,
+    record: Option<&Path>,
+    user: Option<&UserFilter>,
+    read_only: bool,
+) -> io::Result<()> {
+    if socket.exists() {
+        // A live server answers connections; anything else is a leftover from a crash.
+        if UnixStream::connect(socket).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!(
+                    "a syskill server is already listening on {}",
+                    socket.display()
+                ),
+            ));
+        }
+        std::fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    eprintln!("syskill: serving on {}", socket.display());
+
+    let clients: Arc<Mutex<Vec<UnixStream>>> = Arc::default();
+    let (commands, command_rx) = mpsc::channel();
+    {
+        let clients = Arc::clone(&clients);
+        thread::spawn(move || accept_clients(listener, clients, commands));
+    }
+
+    collect(interval, record, user, read_only, command_rx, |message| {
+        if let Ok(mut clients) = clients.lock() {
+            clients.retain_mut(|client| send(client, message).is_ok());
+        }
+        Ok(())
+    })
+}
+
+/// Runs the collector for the one client on stdin and stdout, until it goes away.
+pub fn serve_stdio(
+    interval: Duration,
+    record: Option<&Path>,
+    user: Option<&UserFilter>,
+    read_only: bool,
+) -> io::Result<()> {
+    let (commands, command_rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let Ok(message) = serde_json::from_str::<ClientMessage>(&line) else {
+                continue;
+            };
+            let reply: Reply = Box::new(io::stdout());
+            if commands.send((message, reply)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut stdout = io::stdout();
+    match collect(interval, record, user, read_only, command_rx, |message| {
+        send(&mut stdout, message)
+    }) {
+        // The client hung up.
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Takes a snapshot every `interval` and hands it to `broadcast`, carrying out the kills
+/// requested in between unless `read_only`, until `broadcast` fails.
+fn collect(
+    interval: Duration,
+    record: Option<&Path>,
+    user: Option<&UserFilter>,
+    read_only: bool,
+    command_rx: Receiver<(ClientMessage, Reply)>,
+    mut broadcast: impl FnMut(&ServerMessage) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut recorder = match record {
+        Some(path) => Some(BufWriter::new(
+            File::options().create(true).append(true).open(path)?,
+        )),
+        None => None,
+    };
+    let interval = interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    let mut system = System::new_all();
+
+    loop {
+        // Handle kill requests while waiting for the next refresh.
+        let deadline = Instant::now() + interval;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let (ClientMessage::Kill { pid, start_time }, mut reply) =
+                match command_rx.recv_timeout(remaining) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        thread::sleep(remaining);
+                        break;
+                    }
+                };
+            let key = ProcessKey { pid, start_time };
+            let sent = if read_only {
+                Err(None)
+            } else {
+                KillRequest::new(pid, Signal::Kill)
+                    .expecting(&key)
+                    .send(&mut system)
+                    .map_err(|e| Some(e.message))
+            };
+            let name = system.process(Pid::from_u32(pid));
+            let result = ServerMessage::KillResult {
+                pid,
+                name: name.map(|p| p.name().to_string()).unwrap_or_default(),
+                ok: sent.is_ok(),
+                read_only,
+                error: sent.err().flatten(),
+            };
+            let _ = send(&mut reply, &result);
+        }
+
+        system.refresh_cpu();
+        system.refresh_processes_specifics(users::refresh_kind());
+        let snapshot = Snapshot::capture(&system, user);
+
+        if let Some(writer) = recorder.as_mut() {
+            snapshot::append(writer, &snapshot)?;
+        }
+
+        broadcast(&ServerMessage::Snapshot(snapshot))?;
+    }
+}
+
+fn accept_clients(
+    listener: UnixListener,
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+    commands: Sender<(ClientMessage, Reply)>,
+) {
+    for stream in listener.incoming().flatten() {
+        let (Ok(reader), Ok(reply)) = (stream.try_clone(), stream.try_clone()) else {
+            continue;
+        };
+        if let Ok(mut clients) = clients.lock() {
+            clients.push(stream);
+        }
+
+        let commands = commands.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else { break };
+                let Ok(message) = serde_json::from_str::<ClientMessage>(&line) else {
+                    continue;
+                };
+                let Ok(reply) = reply.try_clone() else { break };
+                if commands.send((message, Box::new(reply))).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Client side of an attached session, or of one over SSH.
+pub struct RemoteSession {
+    writer: Box<dyn Write + Send>,
+    latest: Arc<Mutex<Option<Snapshot>>>,
+    kill_results: Arc<Mutex<Vec<KillResult>>>,
+    /// Set once the server stops sending.
+    closed: Arc<AtomicBool>,
+    /// Whether the closing has been reported, see [`RemoteSession::take_closed`].
+    closing_reported: bool,
+    /// The `ssh` running the server, for a session over SSH.
+    ssh: Option<Child>,
+}
+
+impl RemoteSession {
+    pub fn connect(socket: &Path) -> io::Result<Self> {
+        let stream = UnixStream::connect(socket).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("cannot attach to {}: {e}", socket.display()),
+            )
+        })?;
+        let reader = stream.try_clone()?;
+        Ok(Self::over(reader, Box::new(stream), None))
+    }
+
+    /// Runs `syskill serve --stdio` on `host` (`user@server`, or anything else `ssh` takes) and
+    /// waits for its first snapshot. `ssh` asks for passwords and host keys on the terminal
+    /// before the table takes it over.
+    pub fn ssh(host: &str, interval: Duration) -> io::Result<Self> {
+        // `ssh` would take it for an option, such as `-oProxyCommand=...`.
+        if host.starts_with('-') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("not a host: {host}"),
+            ));
+        }
+        let mut child = Command::new("ssh")
+            .args(["--", host])
+            .args(["syskill", "serve", "--stdio", "--interval"])
+            .arg(interval.as_secs_f64().to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot run ssh: {e}")))?;
+        let (Some(stdin), Some(stdout), Some(mut stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(io::Error::other("ssh has no pipes"));
+        };
+        // Read all along, so that a chatty server cannot block on a full pipe.
+        let errors = thread::spawn(move || {
+            let mut errors = String::new();
+            let _ = io::Read::read_to_string(&mut stderr, &mut errors);
+            errors
+        });
+        let session = Self::over(stdout, Box::new(stdin), Some(child));
+
+        while session.latest.lock().is_ok_and(|latest| latest.is_none()) {
+            if session.closed.load(Ordering::SeqCst) {
+                drop(session);
+                let errors = errors.join().unwrap_or_default();
+                let reason = errors
+                    .lines()
+                    .rfind(|line| !line.trim().is_empty())
+                    .unwrap_or("the connection closed");
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("cannot reach syskill on {host}: {reason}"),
+                ));
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        Ok(session)
+    }
+
+    /// Follows the server's messages on `reader`, sending requests to `writer`.
+    fn over(
+        reader: impl io::Read + Send + 'static,
+        writer: Box<dyn Write + Send>,
+        ssh: Option<Child>,
+    ) -> Self {
+        let latest: Arc<Mutex<Option<Snapshot>>> = Arc::default();
+        let kill_results: Arc<Mutex<Vec<KillResult>>> = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let reader = BufReader::new(reader);
+        {
+            let latest = Arc::clone(&latest);
+            let kill_results = Arc::clone(&kill_results);
+            let closed = Arc::clone(&closed);
+            thread::spawn(move || {
+                for line in reader.lines() {
+                    let Ok(line) = line else { break };
+                    match serde_json::from_str(&line) {
+                        Ok(ServerMessage::Snapshot(snapshot)) => {
+                            if let Ok(mut latest) = latest.lock() {
+                                *latest = Some(snapshot);
+                            }
+                        }
+                        Ok(ServerMessage::KillResult {
+                            pid,
+                            name,
+                            ok,
+                            read_only,
+                            error,
+                        }) => {
+                            if let Ok(mut results) = kill_results.lock() {
+                                results.push((pid, name, ok, read_only, error));
+                            }
+                        }
+                        Err(_) => continue,
+                    }
+                }
+                closed.store(true, Ordering::SeqCst);
+            });
+        }
+
+        Self {
+            writer,
+            latest,
+            kill_results,
+            closed,
+            closing_reported: false,
+            ssh,
+        }
+    }
+
+    /// Waits up to `timeout` for the first snapshot to arrive.
+    pub fn wait_for_snapshot(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if self.latest.lock().is_ok_and(|latest| latest.is_some()) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.latest.lock().ok().and_then(|latest| latest.clone())
+    }
+
+    /// Asks the server to kill `pid`, if it still has `start_time`.
+    pub fn kill(&mut self, pid: u32, start_time: u64) -> io::Result<()> {
+        let mut line = serde_json::to_vec(&ClientMessage::Kill { pid, start_time })?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()
+    }
+
+    /// Whether the server stopped sending since the last call; true once.
+    pub fn take_closed(&mut self) -> bool {
+        let report = self.closed.load(Ordering::SeqCst) && !self.closing_reported;
+        self.closing_reported |= report;
+        report
+    }
+
+    /// Kill results received since the last call.
+    pub fn take_kill_results(&self) -> Vec<KillResult> {
+        self.kill_results
+            .lock()
+            .map(|mut results| std::mem::take(&mut *results))
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for RemoteSession {
+    fn drop(&mut self) {
+        if let Some(ssh) = &mut self.ssh {
+            let _ = ssh.kill();
+            let _ = ssh.wait();
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Signal names accepted by the scripting interfaces, and the signals offered by the picker.
+
+use crate::proc::KillSignal;
+use sysinfo::Signal;
+
+/// Parses signal names, with or without the `SIG` prefix.
+pub fn parse(name: &str) -> Option<Signal> {
+    let upper = name.to_ascii_uppercase();
+    let signal = match upper.strip_prefix("SIG").unwrap_or(&upper) {
+        "HUP" => Signal::Hangup,
+        "INT" => Signal::Interrupt,
+        "QUIT" => Signal::Quit,
+        "KILL" => Signal::Kill,
+        "USR1" => Signal::User1,
+        "USR2" => Signal::User2,
+        "TERM" => Signal::Term,
+        "CONT" => Signal::Continue,
+        "STOP" => Signal::Stop,
+        _ => return None,
+    };
+    Some(signal)
+}
+
+/// Conventional name of `signal`, e.g. `TERM`.
+pub fn name(signal: Signal) -> String {
+    match signal {
+        Signal::Hangup => "HUP".to_string(),
+        Signal::Interrupt => "INT".to_string(),
+        Signal::Quit => "QUIT".to_string(),
+        Signal::Kill => "KILL".to_string(),
+        Signal::User1 => "USR1".to_string(),
+        Signal::User2 => "USR2".to_string(),
+        Signal::Term => "TERM".to_string(),
+        Signal::Continue => "CONT".to_string(),
+        Signal::Stop => "STOP".to_string(),
+        other => other.to_string().to_ascii_uppercase(),
+    }
+}
+
+/// `signal` as the detail view lists sent signals, e.g. `SIGKILL` or `signal 10`.
+pub fn describe(signal: KillSignal) -> String {
+    match signal {
+        KillSignal::Named(signal) => format!("SIG{}", name(signal)),
+        KillSignal::Number(number) => format!("signal {number}"),
+    }
+}
+
+/// Conventional name of the signal `number`, e.g. `SEGV`, or the number itself for signals
+/// without one.
+#[cfg(target_os = "linux")]
+pub fn name_of_number(number: i32) -> String {
+    let name = match number {
+        libc::SIGHUP => "HUP",
+        libc::SIGINT => "INT",
+        libc::SIGQUIT => "QUIT",
+        libc::SIGILL => "ILL",
+        libc::SIGTRAP => "TRAP",
+        libc::SIGABRT => "ABRT",
+        libc::SIGBUS => "BUS",
+        libc::SIGFPE => "FPE",
+        libc::SIGKILL => "KILL",
+        libc::SIGUSR1 => "USR1",
+        libc::SIGSEGV => "SEGV",
+        libc::SIGUSR2 => "USR2",
+        libc::SIGPIPE => "PIPE",
+        libc::SIGALRM => "ALRM",
+        libc::SIGTERM => "TERM",
+        libc::SIGXCPU => "XCPU",
+        libc::SIGXFSZ => "XFSZ",
+        libc::SIGSYS => "SYS",
+        _ => return number.to_string(),
+    };
+    name.to_string()
+}
+
+/// Number of `signal`, for the signals [`parse`] knows.
+#[cfg(unix)]
+pub fn number(signal: Signal) -> Option<i32> {
+    let number = match signal {
+        Signal::Hangup => libc::SIGHUP,
+        Signal::Interrupt => libc::SIGINT,
+        Signal::Quit => libc::SIGQUIT,
+        Signal::Kill => libc::SIGKILL,
+        Signal::User1 => libc::SIGUSR1,
+        Signal::User2 => libc::SIGUSR2,
+        Signal::Term => libc::SIGTERM,
+        Signal::Continue => libc::SIGCONT,
+        Signal::Stop => libc::SIGSTOP,
+        _ => return None,
+    };
+    Some(number)
+}
+
+#[cfg(not(unix))]
+pub fn number(_signal: Signal) -> Option<i32> {
+    None
+}
+
+/// Signals offered by the picker, with what they usually do.
+#[cfg(not(windows))]
+pub const PICKER: &[(Signal, &str)] = &[
+    (Signal::Term, "terminate gracefully"),
+    (Signal::Kill, "kill immediately"),
+    (Signal::Hangup, "hang up, often reloads configuration"),
+    (Signal::Interrupt, "interrupt, like Ctrl-C"),
+    (Signal::Stop, "pause"),
+    (Signal::Continue, "resume after a pause"),
+];
+
+/// Windows has no signals; these two stand for the requests of `taskkill` with and without `/F`,
+/// see [`crate::proc::KillRequest::send`].
+#[cfg(windows)]
+pub const PICKER: &[(Signal, &str)] = &[
+    (Signal::Term, "ask to close its windows, like taskkill"),
+    (Signal::Kill, "end it at once, like taskkill /F"),
+];
+
+/// Signals offered for a single thread: those a thread handles on its own. The default action
+/// of most others, such as ending or stopping, takes the whole process along.
+pub const THREAD_PICKER: &[(Signal, &str)] = &[
+    (Signal::User1, "user-defined, often dumps state"),
+    (Signal::User2, "user-defined"),
+];
+
+/// Sends a signal by number, for signals sysinfo has no name for.
+#[cfg(unix)]
+pub fn send_number(pid: u32, number: i32) -> std::io::Result<()> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    // SAFETY: kill(2) has no memory safety requirements.
+    if unsafe { libc::kill(pid, number) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// The process group of `pid`, such as the job a shell started it in; `None` if it is gone, or
+/// for kernel threads, which have none.
+#[cfg(unix)]
+pub fn process_group(pid: u32) -> Option<u32> {
+    let pid = libc::pid_t::try_from(pid).ok()?;
+    // SAFETY: getpgid(2) has no memory safety requirements.
+    let group = unsafe { libc::getpgid(pid) };
+    u32::try_from(group).ok().filter(|&group| group > 0)
+}
+
+#[cfg(not(unix))]
+pub fn process_group(_pid: u32) -> Option<u32> {
+    None
+}
+
+/// Sends a signal by number to every process in the process group `group`.
+#[cfg(unix)]
+pub fn send_to_group(group: u32, number: i32) -> std::io::Result<()> {
+    // 0 would be the group of syskill itself.
+    let group = libc::pid_t::try_from(group)
+        .ok()
+        .filter(|&group| group > 0)
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    // SAFETY: killpg(3) has no memory safety requirements.
+    if unsafe { libc::killpg(group, number) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
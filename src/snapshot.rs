@@ -0,0 +1,375 @@
+//! Session recordings.
+//!
+//! A recording is a JSON Lines file where every line is one [`Snapshot`] of the process table.
+//! They are produced by `syskill record`, `syskill serve --record` and tables started with a
+//! profile that records or with `--session-log`, and consumed by `syskill diff`, `--baseline`
+//! and `syskill replay`. Recordings of the table also log its actions, see [`crate::replay`].
+
+use crate::plugin;
+use crate::users::{self, UserFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{ProcessRefreshKind, System};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory: u64,
+    /// Bytes of address space, mapped or not; 0 in recordings made before it was added.
+    #[serde(default)]
+    pub virtual_memory: u64,
+    /// Bytes of `memory` shared with other processes, and bytes swapped out; `None` if unknown
+    /// or not read (see [`Fields`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_memory: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap: Option<u64>,
+    #[serde(default)]
+    pub exe: String,
+    /// The arguments the process was started with, separated by spaces; empty if unknown or not
+    /// read (see [`Fields`]).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u32>,
+    /// Seconds since the Unix epoch; with the PID it tells a process from a later one that got
+    /// the same PID. 0 in recordings made before it was added.
+    #[serde(default)]
+    pub start_time: u64,
+    /// Name of the owner, empty if unknown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub user: String,
+    /// E.g. `Running` or `Zombie`, see [`crate::proc::state_name`]; empty if unknown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub state: String,
+    /// Scheduling policy, e.g. `OTHER` or `FIFO 50`; empty if unknown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sched: String,
+    /// Bytes read from and written to storage since the process started.
+    #[serde(default)]
+    pub disk_read: u64,
+    #[serde(default)]
+    pub disk_written: u64,
+    /// 0 if unknown.
+    #[serde(default)]
+    pub threads: u32,
+    /// Open file descriptors; `None` if unknown or not read (see [`Fields`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fds: Option<u32>,
+    /// Controlling terminal, e.g. `pts/3`; empty if it has none, unknown or not read.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tty: String,
+    /// Session ID; `None` if unknown or not read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<u32>,
+    /// Container or systemd unit, e.g. `docker:web` or `nginx.service`, see
+    /// [`crate::container::of`]; empty if none, unknown or not read.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub container: String,
+    /// CPU time used since the process started, in milliseconds; `None` if unknown or not read
+    /// (see [`Fields`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_time: Option<u64>,
+    /// Title of the first visible window of the process; empty if it has none, or not read.
+    /// Windows only.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub window: String,
+    /// GPU utilization in percent, see [`crate::gpu`]; `None` for processes not using a GPU, or
+    /// not read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<f32>,
+    /// Bytes of GPU memory; `None` for processes not using a GPU, or not read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_memory: Option<u64>,
+    /// Values of the columns added with [`crate::plugin`], by column name; empty if not read.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, plugin::Value>,
+}
+
+/// Identifies a process across refreshes. The kernel hands out the PID of an exited process
+/// again, on a busy system within seconds, but not together with the same start time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProcessKey {
+    pub pid: u32,
+    /// Seconds since the Unix epoch; 0 if unknown.
+    pub start_time: u64,
+}
+
+impl ProcessSample {
+    pub fn key(&self) -> ProcessKey {
+        ProcessKey {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Seconds since the Unix epoch at which the snapshot was taken.
+    pub timestamp: u64,
+    pub processes: Vec<ProcessSample>,
+    /// The disk totals of the processes were not read (see [`Fields`]) and are 0.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub without_disk: bool,
+}
+
+/// Fields of the samples that take reads of their own for every process, or make snapshots much
+/// larger, so a collector can skip those nobody looks at.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Fields {
+    /// Bytes read and written, from `/proc/<pid>/io` on Linux.
+    pub disk: bool,
+    /// Scheduling policy, a system call per process.
+    pub sched: bool,
+    /// Open file descriptors, counted in `/proc/<pid>/fd` on Linux.
+    pub fds: bool,
+    /// Session and controlling terminal, from `/proc/<pid>/stat` on Linux.
+    pub session: bool,
+    /// Shared memory and swap, from `/proc/<pid>/status` on Linux.
+    pub memory_detail: bool,
+    /// Container, from `/proc/<pid>/cgroup` on Linux.
+    pub container: bool,
+    /// Window titles, from a walk over every window on Windows.
+    pub window: bool,
+    /// CPU time, from `/proc/<pid>/stat` on Linux.
+    pub cpu_time: bool,
+    /// Command lines, often longer than all the other fields together.
+    pub command: bool,
+    /// GPU usage, from NVML and `/proc/<pid>/fdinfo` on Linux.
+    pub gpu: bool,
+    /// Values of the custom columns, as costly as their providers make them.
+    pub custom: bool,
+}
+
+impl Fields {
+    pub const ALL: Fields = Fields {
+        disk: true,
+        sched: true,
+        fds: true,
+        session: true,
+        memory_detail: true,
+        container: true,
+        window: true,
+        cpu_time: true,
+        command: true,
+        gpu: true,
+        custom: true,
+    };
+    pub const NONE: Fields = Fields {
+        disk: false,
+        sched: false,
+        fds: false,
+        session: false,
+        memory_detail: false,
+        container: false,
+        window: false,
+        cpu_time: false,
+        command: false,
+        gpu: false,
+        custom: false,
+    };
+
+    /// What to refresh of each process for these fields.
+    pub fn refresh_kind(self) -> ProcessRefreshKind {
+        let kind = users::refresh_kind();
+        if self.disk {
+            kind
+        } else {
+            kind.without_disk_usage()
+        }
+    }
+}
+
+impl Snapshot {
+    /// Samples every process, or only those of `user`.
+    pub fn capture(system: &System, user: Option<&UserFilter>) -> Self {
+        Self::capture_fields(system, user, Fields::ALL)
+    }
+
+    /// Samples every process, or only those of `user`, leaving out the fields not in `fields`.
+    pub fn capture_fields(system: &System, user: Option<&UserFilter>, fields: Fields) -> Self {
+        let users = sysinfo::Users::new_with_refreshed_list();
+        let mut windows = if fields.window {
+            window_titles()
+        } else {
+            HashMap::new()
+        };
+        let gpus = if fields.gpu {
+            crate::gpu::usage()
+        } else {
+            HashMap::new()
+        };
+        let providers = if fields.custom {
+            plugin::providers()
+        } else {
+            Vec::new()
+        };
+        let processes = system
+            .processes()
+            .iter()
+            .filter(|(_, process)| user.is_none_or(|user| user.matches(process)))
+            .map(|(pid, process)| {
+                let (session, terminal) = fields
+                    .session
+                    .then(|| crate::terminal::session_and_terminal(pid.as_u32()))
+                    .flatten()
+                    .unzip();
+                let (shared_memory, swap) = fields
+                    .memory_detail
+                    .then(|| crate::proc::shared_and_swap(pid.as_u32()))
+                    .flatten()
+                    .unzip();
+                ProcessSample {
+                    pid: pid.as_u32(),
+                    name: process.name().to_string(),
+                    cpu_usage: process.cpu_usage(),
+                    memory: process.memory(),
+                    virtual_memory: process.virtual_memory(),
+                    shared_memory,
+                    swap,
+                    exe: process
+                        .exe()
+                        .map(|exe| exe.display().to_string())
+                        .unwrap_or_default(),
+                    command: if fields.command {
+                        process.cmd().join(" ")
+                    } else {
+                        String::new()
+                    },
+                    parent: process.parent().map(|parent| parent.as_u32()),
+                    start_time: process.start_time(),
+                    user: users::name(&users, process),
+                    state: crate::proc::state_name(process.status()),
+                    sched: if fields.sched {
+                        crate::sched::describe(pid.as_u32())
+                    } else {
+                        String::new()
+                    },
+                    // Without a refresh, sysinfo keeps the totals of the last one.
+                    disk_read: if fields.disk {
+                        process.disk_usage().total_read_bytes
+                    } else {
+                        0
+                    },
+                    disk_written: if fields.disk {
+                        process.disk_usage().total_written_bytes
+                    } else {
+                        0
+                    },
+                    threads: crate::proc::thread_count(process),
+                    fds: if fields.fds {
+                        crate::fds::count(pid.as_u32())
+                    } else {
+                        None
+                    },
+                    tty: terminal.unwrap_or_default(),
+                    session,
+                    container: if fields.container {
+                        crate::container::of(pid.as_u32())
+                    } else {
+                        String::new()
+                    },
+                    cpu_time: if fields.cpu_time {
+                        crate::proc::cpu_time(pid.as_u32())
+                    } else {
+                        None
+                    },
+                    window: windows.remove(&pid.as_u32()).unwrap_or_default(),
+                    gpu: gpus.get(&pid.as_u32()).and_then(|gpu| gpu.utilization),
+                    gpu_memory: gpus.get(&pid.as_u32()).map(|gpu| gpu.memory),
+                    custom: BTreeMap::new(),
+                }
+            })
+            .map(|mut sample| {
+                if fields.custom {
+                    sample.custom = plugin::values(&providers, &sample);
+                }
+                sample
+            })
+            .collect();
+
+        Self {
+            timestamp: unix_now(),
+            processes,
+            without_disk: !fields.disk,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn window_titles() -> HashMap<u32, String> {
+    crate::win32::window_titles()
+}
+
+#[cfg(not(windows))]
+fn window_titles() -> HashMap<u32, String> {
+    HashMap::new()
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Reads every snapshot of a recording, skipping blank lines and logged actions.
+pub fn read_session(path: &Path) -> io::Result<Vec<Snapshot>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut snapshots = Vec::new();
+
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || crate::replay::is_action(&line) {
+            continue;
+        }
+        let snapshot = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {e}", path.display(), line_no + 1),
+            )
+        })?;
+        snapshots.push(snapshot);
+    }
+
+    Ok(snapshots)
+}
+
+/// Writes `snapshot` as the next line of a recording.
+pub fn append(writer: &mut impl Write, snapshot: &Snapshot) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, snapshot)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+/// Appends a snapshot to `path` every `interval`, stopping after `count` snapshots if given.
+pub fn record(
+    path: &Path,
+    interval: Duration,
+    count: Option<usize>,
+    user: Option<&UserFilter>,
+) -> io::Result<()> {
+    let file = File::options().create(true).append(true).open(path)?;
+    let mut writer = BufWriter::new(file);
+    let mut system = System::new_all();
+
+    let mut taken = 0;
+    while count.is_none_or(|count| taken < count) {
+        // CPU usage is computed from the difference between two refreshes.
+        thread::sleep(interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL));
+        system.refresh_processes_specifics(users::refresh_kind());
+
+        append(&mut writer, &Snapshot::capture(&system, user))?;
+        taken += 1;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Where snapshots of the process table come from: the system itself for the collector, or
+//! snapshots made up beforehand for tests.
+
+use crate::collector::{readable, with_retry};
+use crate::snapshot::{Fields, Snapshot};
+use crate::users::UserFilter;
+use std::collections::VecDeque;
+use sysinfo::System;
+
+pub trait ProcessSource {
+    /// The processes as they are now, with only the costly fields in `fields` read; an error if
+    /// the process table cannot be read.
+    fn snapshot(&mut self, fields: Fields) -> Result<Snapshot, String>;
+}
+
+/// The processes of this machine, of one user's only with a filter.
+pub struct SystemSource {
+    /// Read in full on the first snapshot, which takes a while, and refreshed after.
+    system: Option<System>,
+    user: Option<UserFilter>,
+}
+
+impl SystemSource {
+    pub fn new(user: Option<UserFilter>) -> Self {
+        Self { system: None, user }
+    }
+}
+
+impl ProcessSource for SystemSource {
+    fn snapshot(&mut self, fields: Fields) -> Result<Snapshot, String> {
+        let read = match &mut self.system {
+            Some(system) => refresh(system, fields),
+            None => {
+                let system = self.system.insert(System::new_all());
+                readable(system)
+                    .then_some(())
+                    .or_else(|| refresh(system, Fields::ALL))
+            }
+        };
+        match (read, &self.system) {
+            (Some(()), Some(system)) => {
+                Ok(Snapshot::capture_fields(system, self.user.as_ref(), fields))
+            }
+            _ => Err("Cannot read the process table".to_string()),
+        }
+    }
+}
+
+fn refresh(system: &mut System, fields: Fields) -> Option<()> {
+    with_retry(|| {
+        system.refresh_cpu();
+        system.refresh_processes_specifics(fields.refresh_kind());
+        readable(system).then_some(())
+    })
+}
+
+/// Hands out the snapshots it was given in turn, then the last one again and again. The fields
+/// asked for are ignored.
+#[derive(Default)]
+pub struct MemorySource {
+    snapshots: VecDeque<Snapshot>,
+    last: Option<Snapshot>,
+}
+
+impl MemorySource {
+    pub fn new(snapshots: impl IntoIterator<Item = Snapshot>) -> Self {
+        Self {
+            snapshots: snapshots.into_iter().collect(),
+            last: None,
+        }
+    }
+
+    /// Queues `snapshot` after the ones not handed out yet.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        self.snapshots.push_back(snapshot);
+    }
+}
+
+impl ProcessSource for MemorySource {
+    fn snapshot(&mut self, _fields: Fields) -> Result<Snapshot, String> {
+        if let Some(snapshot) = self.snapshots.pop_front() {
+            self.last = Some(snapshot);
+        }
+        self.last
+            .clone()
+            .ok_or_else(|| "No snapshot to hand out".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::ProcessSample;
+
+    fn snapshot(timestamp: u64) -> Snapshot {
+        Snapshot {
+            timestamp,
+            processes: vec![ProcessSample {
+                pid: 1,
+                name: "init".to_string(),
+                ..Default::default()
+            }],
+            without_disk: false,
+        }
+    }
+
+    #[test]
+    fn memory_source_repeats_the_last_snapshot() {
+        let mut source = MemorySource::new([snapshot(1), snapshot(2)]);
+        let timestamps: Vec<_> = (0..4)
+            .map(|_| source.snapshot(Fields::ALL).unwrap().timestamp)
+            .collect();
+        assert_eq!(timestamps, [1, 2, 2, 2]);
+        source.push(snapshot(3));
+        assert_eq!(source.snapshot(Fields::NONE).unwrap().timestamp, 3);
+    }
+
+    #[test]
+    fn empty_memory_source_fails() {
+        assert!(MemorySource::default().snapshot(Fields::ALL).is_err());
+    }
+}
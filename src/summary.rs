@@ -0,0 +1,60 @@
+//! System-wide figures for the line above the table: CPU usage per core, memory, swap, load
+//! average, uptime, and open files and threads against their limits.
+
+use crate::limits::{self, Limits};
+use sysinfo::{LoadAvg, System};
+
+pub struct Summary {
+    /// Usage of each core, in percent.
+    pub cores: Vec<f32>,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub swap_used: u64,
+    pub swap_total: u64,
+    pub load: LoadAvg,
+    /// Seconds since boot.
+    pub uptime: u64,
+    /// Not available outside Linux.
+    pub limits: Option<Limits>,
+}
+
+impl Summary {
+    /// Usage of the whole machine, in percent.
+    pub fn cpu(&self) -> f32 {
+        self.cores.iter().sum::<f32>() / self.cores.len().max(1) as f32
+    }
+}
+
+/// Keeps its own [`System`], so that CPU usage is measured between consecutive samples no matter
+/// how often the process table is reloaded.
+pub struct Sampler {
+    system: System,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu();
+        Self { system }
+    }
+
+    pub fn sample(&mut self) -> Summary {
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+        Summary {
+            cores: self
+                .system
+                .cpus()
+                .iter()
+                .map(|cpu| cpu.cpu_usage())
+                .collect(),
+            memory_used: self.system.used_memory(),
+            memory_total: self.system.total_memory(),
+            swap_used: self.system.used_swap(),
+            swap_total: self.system.total_swap(),
+            load: System::load_average(),
+            uptime: System::uptime(),
+            limits: limits::read(),
+        }
+    }
+}
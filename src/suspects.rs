@@ -0,0 +1,185 @@
+//! A ranked shortlist of the processes most likely behind trouble, each with the reasons it is
+//! on it, as a starting point when it is not clear where to look.
+//!
+//! Every process gets a score from its CPU usage, how much its memory grew over the recorded
+//! history, its disk I/O, how long it has been stuck in disk sleep and how often a process of
+//! its name exited lately. Each part is scaled so that 1 means "clearly worth a look" and capped
+//! there, then weighted and summed.
+
+use crate::app::ProcessInfo;
+use crate::exits::{self, Exits};
+use crate::history::HistoryStore;
+use crate::proc::ProcessKey;
+use crate::units::Units;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How many suspects are listed.
+const LISTED: usize = 10;
+/// Processes scoring less are left out however few suspects there are.
+const MIN_SCORE: f64 = 0.25;
+
+/// Each part is weighted by how telling it is on its own.
+const CPU_WEIGHT: f64 = 1.0;
+const GROWTH_WEIGHT: f64 = 1.0;
+const IO_WEIGHT: f64 = 0.5;
+const DISK_SLEEP_WEIGHT: f64 = 1.0;
+const RESTART_WEIGHT: f64 = 1.0;
+
+/// What scores 1 in each part: all of a core, half again the memory over the history, this many
+/// bytes per second read and written, this long in disk sleep, and this many exits of the same
+/// name within [`RESTART_WINDOW`].
+const FULL_CPU: f64 = 100.0;
+const FULL_GROWTH: f64 = 0.5;
+const FULL_IO: f64 = 50.0 * 1024.0 * 1024.0;
+const FULL_DISK_SLEEP: Duration = Duration::from_secs(30);
+const FULL_RESTARTS: f64 = 5.0;
+const RESTART_WINDOW: u64 = 10 * 60;
+/// Fewer exits of a name are not taken for restarts; a shell or a cron job exits now and then.
+const MIN_RESTARTS: usize = 2;
+
+/// Memory has to grow by at least this much to count, so a small process doubling is no reason.
+const MIN_GROWTH: u64 = 16 * 1024 * 1024;
+/// Parts scoring less count for nothing, so a process is not listed for many small reasons.
+const MIN_REASON: f64 = 0.1;
+
+pub struct Suspect {
+    pub key: ProcessKey,
+    pub name: String,
+    pub user: String,
+    pub score: f64,
+    /// Why it is listed, the heaviest reason first.
+    pub reasons: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Suspects {
+    /// Since when each process has been seen in disk sleep without a break.
+    disk_sleep_since: HashMap<ProcessKey, Instant>,
+    /// The highest score first.
+    pub list: Vec<Suspect>,
+    /// Index into `list` of the chosen suspect.
+    pub choice: usize,
+}
+
+impl Suspects {
+    /// Scores the running processes of a new snapshot.
+    pub fn observe(
+        &mut self,
+        processes: &[ProcessInfo],
+        history: &HistoryStore,
+        exits: &Exits,
+        now: u64,
+        units: Units,
+    ) {
+        let running: Vec<&ProcessInfo> = processes
+            .iter()
+            .filter(|data| data.exited.is_none() && data.members == 0)
+            .collect();
+        let since = std::mem::take(&mut self.disk_sleep_since);
+        let seen = Instant::now();
+        for data in &running {
+            if data.state == "Disk sleep" {
+                let key = data.key();
+                let start = since.get(&key).copied().unwrap_or(seen);
+                self.disk_sleep_since.insert(key, start);
+            }
+        }
+        let mut restarts: HashMap<&str, usize> = HashMap::new();
+        for exit in exits.since(now.saturating_sub(RESTART_WINDOW)) {
+            *restarts.entry(exit.name.as_str()).or_default() += 1;
+        }
+
+        let mut list: Vec<Suspect> = running
+            .into_iter()
+            .filter_map(|data| {
+                let key = data.key();
+                let mut parts: Vec<(f64, String)> = Vec::new();
+                let cpu = data.cpu_usage;
+                parts.push((
+                    CPU_WEIGHT * (f64::from(cpu) / FULL_CPU).min(1.0),
+                    format!("using {} CPU", units.cpu(cpu)),
+                ));
+                let history = history.get(&key);
+                // Samples taken before the memory could be read are 0.
+                if let Some((first, last)) = history
+                    .and_then(|history| {
+                        let first = history.memory.iter().find(|&&memory| memory > 0)?;
+                        Some((*first, *history.memory.back()?))
+                    })
+                    .filter(|&(first, last)| last >= first.saturating_add(MIN_GROWTH))
+                {
+                    let growth = (last - first) as f64 / first as f64;
+                    parts.push((
+                        GROWTH_WEIGHT * (growth / FULL_GROWTH).min(1.0),
+                        format!(
+                            "memory grew {:.0}% from {} to {} over the last {} refreshes",
+                            growth * 100.0,
+                            units.memory(first),
+                            units.memory(last),
+                            history.map_or(0, |history| history.memory.len())
+                        ),
+                    ));
+                }
+                if let Some((read, written)) = history.and_then(|history| history.io_rate) {
+                    let rate = read.saturating_add(written);
+                    parts.push((
+                        IO_WEIGHT * (rate as f64 / FULL_IO).min(1.0),
+                        format!("reading and writing {}/s", units.memory(rate)),
+                    ));
+                }
+                if let Some(start) = self.disk_sleep_since.get(&key) {
+                    let stuck = seen.duration_since(*start);
+                    parts.push((
+                        DISK_SLEEP_WEIGHT
+                            * (stuck.as_secs_f64() / FULL_DISK_SLEEP.as_secs_f64()).min(1.0),
+                        format!(
+                            "in disk sleep for {}",
+                            exits::format_lifetime(stuck.as_secs())
+                        ),
+                    ));
+                }
+                if let Some(&count) = restarts
+                    .get(data.name.as_str())
+                    .filter(|&&count| count >= MIN_RESTARTS)
+                {
+                    parts.push((
+                        RESTART_WEIGHT * (count as f64 / FULL_RESTARTS).min(1.0),
+                        format!(
+                            "{count} processes of this name exited in the last {} minutes",
+                            RESTART_WINDOW / 60
+                        ),
+                    ));
+                }
+                parts.retain(|(score, _)| *score >= MIN_REASON);
+                let score: f64 = parts.iter().map(|(score, _)| score).sum();
+                if score < MIN_SCORE {
+                    return None;
+                }
+                parts.sort_by(|a, b| b.0.total_cmp(&a.0));
+                Some(Suspect {
+                    key,
+                    name: data.name.clone(),
+                    user: data.user.clone(),
+                    score,
+                    reasons: parts.into_iter().map(|(_, reason)| reason).collect(),
+                })
+            })
+            .collect();
+        list.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.key.cmp(&b.key)));
+        list.truncate(LISTED);
+        self.list = list;
+        self.choice = self.choice.min(self.list.len().saturating_sub(1));
+    }
+
+    pub fn chosen(&self) -> Option<&Suspect> {
+        self.list.get(self.choice)
+    }
+
+    pub fn move_choice(&mut self, step: isize) {
+        self.choice = self
+            .choice
+            .saturating_add_signed(step)
+            .min(self.list.len().saturating_sub(1));
+    }
+}
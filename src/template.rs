@@ -0,0 +1,283 @@
+//! A small template language for custom exports, a subset of Jinja (and so of minijinja and Tera).
+//!
+//! Supported are `{{ path }}` with an optional filter (`{{ process.name | csv }}`), `{% for x in
+//! path %}` with `loop.index`, `loop.first` and `loop.last`, `{% if path %}` with an optional `{%
+//! else %}`, and `{%-`/`-%}` to trim the whitespace before or after a tag. Paths are looked up in
+//! loop variables first and then in the JSON context, with `.` between field names.
+
+use serde_json::Value;
+
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+enum Node {
+    Text(String),
+    Print {
+        path: Vec<String>,
+        filter: Option<Filter>,
+    },
+    For {
+        name: String,
+        path: Vec<String>,
+        body: Vec<Node>,
+    },
+    If {
+        path: Vec<String>,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum Filter {
+    /// As a JSON value, e.g. a quoted and escaped string.
+    Json,
+    /// Quoted if it contains a comma, quote or line break.
+    Csv,
+    Html,
+    /// With `|` escaped, for Markdown tables.
+    Markdown,
+}
+
+/// A tag or expression between the text parts of the source.
+enum Token<'a> {
+    Text(&'a str),
+    Print(&'a str),
+    Tag(&'a str),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut tokens = tokens.into_iter();
+        let (nodes, end) = parse_nodes(&mut tokens)?;
+        match end {
+            None => Ok(Self { nodes }),
+            Some(tag) => Err(format!("unexpected {{% {tag} %}}")),
+        }
+    }
+
+    pub fn render(&self, context: &Value) -> Result<String, String> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, context, &mut Vec::new(), &mut out)?;
+        Ok(out)
+    }
+}
+
+fn tokenize(mut source: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut trim_next = false;
+    while !source.is_empty() {
+        let start = [source.find("{{"), source.find("{%")]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(source.len());
+        let mut text = &source[..start];
+        if trim_next {
+            text = text.trim_start();
+        }
+        source = &source[start..];
+        if source.is_empty() {
+            tokens.push(Token::Text(text));
+            break;
+        }
+        let close = if source.starts_with("{{") { "}}" } else { "%}" };
+        let end = source
+            .find(close)
+            .ok_or_else(|| format!("unclosed {}", &source[..2]))?;
+        let mut inner = &source[2..end];
+        if let Some(rest) = inner.strip_prefix('-') {
+            text = text.trim_end();
+            inner = rest;
+        }
+        trim_next = inner.ends_with('-');
+        let inner = inner.strip_suffix('-').unwrap_or(inner).trim();
+        tokens.push(Token::Text(text));
+        tokens.push(if close == "}}" {
+            Token::Print(inner)
+        } else {
+            Token::Tag(inner)
+        });
+        source = &source[end + 2..];
+    }
+    Ok(tokens)
+}
+
+/// Parses nodes up to the end of the input or a closing tag, which is returned.
+fn parse_nodes<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>,
+) -> Result<(Vec<Node>, Option<&'a str>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text.to_string())),
+            Token::Print(expression) => {
+                let (path, filter) = match expression.split_once('|') {
+                    Some((path, filter)) => (path, Some(parse_filter(filter.trim())?)),
+                    None => (expression, None),
+                };
+                nodes.push(Node::Print {
+                    path: parse_path(path)?,
+                    filter,
+                });
+            }
+            Token::Tag(tag) => {
+                let words: Vec<&str> = tag.split_whitespace().collect();
+                match words.as_slice() {
+                    ["for", name, "in", path] => {
+                        let (body, end) = parse_nodes(tokens)?;
+                        if end != Some("endfor") {
+                            return Err(format!("{{% for {name} %}} without {{% endfor %}}"));
+                        }
+                        nodes.push(Node::For {
+                            name: name.to_string(),
+                            path: parse_path(path)?,
+                            body,
+                        });
+                    }
+                    ["if", path] => {
+                        let (then, mut end) = parse_nodes(tokens)?;
+                        let mut otherwise = Vec::new();
+                        if end == Some("else") {
+                            (otherwise, end) = parse_nodes(tokens)?;
+                        }
+                        if end != Some("endif") {
+                            return Err(format!("{{% if {path} %}} without {{% endif %}}"));
+                        }
+                        nodes.push(Node::If {
+                            path: parse_path(path)?,
+                            then,
+                            otherwise,
+                        });
+                    }
+                    ["endfor"] | ["endif"] | ["else"] => return Ok((nodes, Some(tag))),
+                    _ => return Err(format!("unknown tag {{% {tag} %}}")),
+                }
+            }
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_path(path: &str) -> Result<Vec<String>, String> {
+    let path = path.trim();
+    let valid =
+        |field: &str| !field.is_empty() && field.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if !path.split('.').all(valid) {
+        return Err(format!("not a field name: {path:?}"));
+    }
+    Ok(path.split('.').map(str::to_string).collect())
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    match filter {
+        "json" => Ok(Filter::Json),
+        "csv" => Ok(Filter::Csv),
+        "html" => Ok(Filter::Html),
+        "markdown" => Ok(Filter::Markdown),
+        _ => Err(format!(
+            "unknown filter {filter:?}, expected json, csv, html or markdown"
+        )),
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    context: &Value,
+    scopes: &mut Vec<(String, Value)>,
+    out: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Print { path, filter } => {
+                let value = lookup(path, context, scopes)?;
+                out.push_str(&apply(*filter, &value));
+            }
+            Node::For { name, path, body } => {
+                let value = lookup(path, context, scopes)?;
+                let Value::Array(items) = value else {
+                    return Err(format!("{} is not a list", path.join(".")));
+                };
+                for (i, item) in items.iter().enumerate() {
+                    let index = serde_json::json!({
+                        "index": i + 1,
+                        "index0": i,
+                        "first": i == 0,
+                        "last": i + 1 == items.len(),
+                    });
+                    scopes.push(("loop".to_string(), index));
+                    scopes.push((name.clone(), item.clone()));
+                    let result = render_nodes(body, context, scopes, out);
+                    scopes.truncate(scopes.len() - 2);
+                    result?;
+                }
+            }
+            Node::If {
+                path,
+                then,
+                otherwise,
+            } => {
+                let branch = if truthy(&lookup(path, context, scopes)?) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, context, scopes, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lookup(path: &[String], context: &Value, scopes: &[(String, Value)]) -> Result<Value, String> {
+    let (first, rest) = path.split_first().expect("paths are never empty");
+    let mut value = scopes
+        .iter()
+        .rev()
+        .find(|(name, _)| name == first)
+        .map(|(_, value)| value)
+        .or_else(|| context.get(first))
+        .ok_or_else(|| format!("unknown field {first}"))?;
+    for field in rest {
+        value = value
+            .get(field)
+            .ok_or_else(|| format!("unknown field {}", path.join(".")))?;
+    }
+    Ok(value.clone())
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn apply(filter: Option<Filter>, value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    match filter {
+        None => text,
+        Some(Filter::Json) => value.to_string(),
+        Some(Filter::Csv) if text.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        }
+        Some(Filter::Csv) => text,
+        Some(Filter::Html) => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+        Some(Filter::Markdown) => text.replace('|', "\\|"),
+    }
+}
@@ -0,0 +1,96 @@
+//! The terminal session syskill was started from, to list only what was launched from the same
+//! shell, e.g. to clean up after a test run. Linux only, read from `/proc/<pid>/stat`.
+
+#[derive(Clone, Copy)]
+pub struct Terminal {
+    /// Session ID of the shell that started syskill.
+    session: i32,
+    /// Device number of its controlling terminal; 0 without one.
+    tty: i32,
+    shell: u32,
+}
+
+impl Terminal {
+    /// The session and terminal of the shell that started syskill, or `None` where they cannot
+    /// be read.
+    #[cfg(target_os = "linux")]
+    pub fn of_parent() -> Option<Self> {
+        let shell = std::os::unix::process::parent_id();
+        let (session, tty) = session_and_tty(shell)?;
+        Some(Self {
+            session,
+            tty,
+            shell,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn of_parent() -> Option<Self> {
+        None
+    }
+
+    /// Whether the process `pid` was started from this terminal: in the same session, or since
+    /// moved to a session of its own but still on the same terminal. The shell and syskill itself,
+    /// threads included, are left out.
+    pub fn started(&self, pid: u32) -> bool {
+        if pid == self.shell || std::path::Path::new(&format!("/proc/self/task/{pid}")).exists() {
+            return false;
+        }
+        session_and_tty(pid)
+            .is_some_and(|(session, tty)| session == self.session || tty != 0 && tty == self.tty)
+    }
+
+    pub fn session(&self) -> i32 {
+        self.session
+    }
+}
+
+/// Session ID and name of the controlling terminal of the process `pid`, e.g. `pts/3`, or an
+/// empty name for processes without one, such as daemons.
+pub fn session_and_terminal(pid: u32) -> Option<(u32, String)> {
+    let (session, tty) = session_and_tty(pid)?;
+    Some((u32::try_from(session).ok()?, tty_name(tty)))
+}
+
+/// The name `ps` gives the terminal with the device number `tty`, or its major and minor
+/// numbers for terminals without a usual name.
+fn tty_name(tty: i32) -> String {
+    if tty == 0 {
+        return String::new();
+    }
+    let major = (tty >> 8) & 0xfff;
+    let minor = (tty & 0xff) | ((tty >> 12) & 0xfff00);
+    match major {
+        // Pseudo-terminals, 256 to a major number.
+        136..=143 => format!("pts/{}", (major - 136) * 256 + minor),
+        4 if minor < 64 => format!("tty{minor}"),
+        4 => format!("ttyS{}", minor - 64),
+        _ => format!("{major}:{minor}"),
+    }
+}
+
+/// Session ID and controlling terminal of the process `pid`.
+fn session_and_tty(pid: u32) -> Option<(i32, i32)> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // `pid (comm) state ppid pgrp session tty_nr ...`; the command may contain anything.
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(3);
+    let session = fields.next()?.parse().ok()?;
+    let tty = fields.next()?.parse().ok()?;
+    Some((session, tty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminals_are_named_like_ps_does() {
+        assert_eq!(tty_name(0), "");
+        assert_eq!(tty_name(136 << 8 | 3), "pts/3");
+        // Minor numbers above 255 spill into the high bits.
+        assert_eq!(tty_name(136 << 8 | 1 << 20 | 4), "pts/260");
+        assert_eq!(tty_name(4 << 8 | 1), "tty1");
+        assert_eq!(tty_name(4 << 8 | 64), "ttyS0");
+        assert_eq!(tty_name(5 << 8 | 1), "5:1");
+    }
+}
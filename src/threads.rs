@@ -0,0 +1,169 @@
+//! The threads of one process with their CPU usage and state, for the detail view.
+//!
+//! Each thread has a `/proc/<pid>/task/<tid>/stat` of its own; CPU usage comes from how many
+//! clock ticks it ran for between two samples. Linux only.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Samples closer together than this measure CPU usage too coarsely to be shown.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Thread {
+    pub tid: u32,
+    pub name: String,
+    /// As the table shows states, e.g. `Running` or `Disk sleep`.
+    pub state: String,
+    /// In percent of one core since the last sample; `None` until there is one.
+    pub cpu: Option<f32>,
+}
+
+pub struct Threads {
+    pid: u32,
+    /// CPU time in clock ticks of each thread at the last sample.
+    ticks: HashMap<u32, u64>,
+    sampled: Option<Instant>,
+    /// By TID.
+    pub list: Vec<Thread>,
+    /// Index into `list` of the chosen thread.
+    pub choice: usize,
+}
+
+impl Threads {
+    pub fn new(pid: u32) -> Self {
+        let mut threads = Self {
+            pid,
+            ticks: HashMap::new(),
+            sampled: None,
+            list: Vec::new(),
+            choice: 0,
+        };
+        threads.sample();
+        threads
+    }
+
+    /// Lists the threads again, unless the last sample was less than [`MIN_INTERVAL`] ago.
+    pub fn sample(&mut self) {
+        let now = Instant::now();
+        let elapsed = self.sampled.map(|sampled| now.duration_since(sampled));
+        if elapsed.is_some_and(|elapsed| elapsed < MIN_INTERVAL) {
+            return;
+        }
+        let ticks_per_second = ticks_per_second();
+        let mut ticks = HashMap::new();
+        self.list = read(self.pid)
+            .into_iter()
+            .map(|(tid, name, state, used)| {
+                let cpu = elapsed.zip(self.ticks.get(&tid)).map(|(elapsed, &before)| {
+                    let seconds = used.saturating_sub(before) as f64 / ticks_per_second;
+                    (seconds * 100.0 / elapsed.as_secs_f64()) as f32
+                });
+                ticks.insert(tid, used);
+                Thread {
+                    tid,
+                    name,
+                    state: state_name(state),
+                    cpu,
+                }
+            })
+            .collect();
+        self.ticks = ticks;
+        self.sampled = Some(now);
+        self.choice = self.choice.min(self.list.len().saturating_sub(1));
+    }
+
+    pub fn chosen(&self) -> Option<&Thread> {
+        self.list.get(self.choice)
+    }
+
+    pub fn move_choice(&mut self, step: isize) {
+        self.choice = self
+            .choice
+            .saturating_add_signed(step)
+            .min(self.list.len().saturating_sub(1));
+    }
+}
+
+/// Name of a state letter of `stat`, as [`crate::proc::state_name`] names it.
+fn state_name(letter: char) -> String {
+    match letter {
+        'R' => "Running",
+        'S' => "Sleeping",
+        'D' => "Disk sleep",
+        'Z' => "Zombie",
+        'T' => "Stopped",
+        't' => "Tracing",
+        'X' | 'x' => "Dead",
+        'I' => "Idle",
+        'W' => "Waking",
+        'P' => "Parked",
+        _ => "Unknown",
+    }
+    .to_string()
+}
+
+/// TID, name, state letter and CPU time in clock ticks of every thread of `pid`, by TID.
+#[cfg(target_os = "linux")]
+fn read(pid: u32) -> Vec<(u32, String, char, u64)> {
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{pid}/task")) else {
+        return Vec::new();
+    };
+    let mut threads: Vec<_> = tasks
+        .flatten()
+        .filter_map(|task| {
+            let tid = task.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(task.path().join("stat")).ok()?;
+            // The name may contain spaces and parentheses, so it ends at the last parenthesis.
+            let name = &stat[stat.find('(')? + 1..stat.rfind(')')?];
+            // The remainder starts at field 3, the state; `utime` and `stime` are 14 and 15.
+            let fields: Vec<&str> = stat[stat.rfind(')')? + 2..].split_whitespace().collect();
+            let state = fields.first()?.chars().next()?;
+            let user: u64 = fields.get(14 - 3)?.parse().ok()?;
+            let system: u64 = fields.get(15 - 3)?.parse().ok()?;
+            Some((tid, name.to_string(), state, user + system))
+        })
+        .collect();
+    threads.sort_by_key(|thread| thread.0);
+    threads
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read(_pid: u32) -> Vec<(u32, String, char, u64)> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn ticks_per_second() -> f64 {
+    // SAFETY: sysconf(3) has no memory safety requirements.
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f64,
+        _ => 100.0,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn ticks_per_second() -> f64 {
+    100.0
+}
+
+/// Sends the signal `number` to the thread `tid` of `pid` alone, with `tgkill(2)`.
+#[cfg(target_os = "linux")]
+pub fn signal(pid: u32, tid: u32, number: i32) -> std::io::Result<()> {
+    let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidInput);
+    let pid = libc::pid_t::try_from(pid).map_err(|_| invalid())?;
+    let tid = libc::pid_t::try_from(tid).map_err(|_| invalid())?;
+    // SAFETY: tgkill(2) has no memory safety requirements.
+    if unsafe { libc::syscall(libc::SYS_tgkill, pid, tid, number) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn signal(_pid: u32, _tid: u32, _number: i32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "signalling single threads is only supported on Linux",
+    ))
+}
@@ -0,0 +1,371 @@
+//! Capping the CPU usage and the memory of processes with a cgroup, a middle ground between
+//! leaving a runaway process alone and killing it.
+//!
+//! The processes are moved into a cgroup of their own with a CPU quota or a memory limit, and back
+//! into the cgroups they came from when the limits are removed. Works with the `cpu` and `memory`
+//! controllers of cgroup v1 and with cgroup v2. Linux only, and moving processes between cgroups
+//! usually takes root.
+
+use std::io;
+use std::path::PathBuf;
+
+/// Length of a quota period; the cap is the share of it the processes may run.
+const PERIOD_US: u64 = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resource {
+    Cpu,
+    Memory,
+}
+
+impl Resource {
+    const fn controller(self) -> &'static str {
+        match self {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Limit {
+    /// Percent of one CPU.
+    Cpu(u32),
+    /// Bytes of memory, swap included.
+    Memory(u64),
+}
+
+impl Limit {
+    pub const fn resource(self) -> Resource {
+        match self {
+            Limit::Cpu(_) => Resource::Cpu,
+            Limit::Memory(_) => Resource::Memory,
+        }
+    }
+}
+
+/// Processes running under a CPU cap, a memory limit or both.
+pub struct Throttle {
+    /// The process the limits were put on; its children may have been moved along.
+    pub pid: u32,
+    /// Percent of one CPU the processes may use together.
+    pub percent: Option<u32>,
+    /// Bytes of memory the processes may use together.
+    pub memory: Option<u64>,
+    /// Every moved process.
+    pub moved: Vec<u32>,
+    /// One cgroup per hierarchy; with cgroup v1 the controllers can be mounted apart.
+    groups: Vec<Group>,
+}
+
+struct Group {
+    hierarchy: Hierarchy,
+    dir: PathBuf,
+    resources: Vec<Resource>,
+    /// Every moved process, with the `cgroup.procs` file of the cgroup it came from.
+    origins: Vec<(u32, PathBuf)>,
+}
+
+/// Parses a cap as typed, e.g. `50` or `50%`; above 100 allows more than one CPU.
+pub fn parse_percent(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    input
+        .strip_suffix('%')
+        .unwrap_or(input)
+        .trim()
+        .parse()
+        .ok()
+        .filter(|&percent| percent > 0)
+        .ok_or_else(|| format!("Not a CPU share in percent: {input}"))
+}
+
+/// Parses a memory limit as typed, e.g. `512M` or `2GiB`.
+pub fn parse_memory(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    crate::query::parse_size(input)
+        .ok()
+        .filter(|&bytes| bytes >= 1.0)
+        .map(|bytes| bytes as u64)
+        .ok_or_else(|| format!("Not a memory size: {input}"))
+}
+
+enum Hierarchy {
+    /// Mount point of a v1 controller.
+    V1(PathBuf),
+    /// Mount point of the unified hierarchy.
+    V2(PathBuf),
+}
+
+impl Hierarchy {
+    /// The v1 hierarchy of `controller` if there is one, as on hybrid systems the unified
+    /// hierarchy then has no controllers.
+    #[cfg(target_os = "linux")]
+    fn find(controller: &str) -> io::Result<Self> {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+        let mut unified = None;
+        // `id parent major:minor root mount-point options ... - type source super-options`
+        for line in mountinfo.lines() {
+            let Some((fields, filesystem)) = line.split_once(" - ") else {
+                continue;
+            };
+            let Some(mount_point) = fields.split(' ').nth(4) else {
+                continue;
+            };
+            let mut filesystem = filesystem.split(' ');
+            match (filesystem.next(), filesystem.nth(1)) {
+                (Some("cgroup"), Some(options)) if options.split(',').any(|o| o == controller) => {
+                    return Ok(Hierarchy::V1(mount_point.into()));
+                }
+                (Some("cgroup2"), _) => unified = Some(mount_point.into()),
+                _ => {}
+            }
+        }
+        unified.map(Hierarchy::V2).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no cgroup with a {controller} controller"),
+            )
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn find(_controller: &str) -> io::Result<Self> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn mount_point(&self) -> &PathBuf {
+        match self {
+            Hierarchy::V1(path) | Hierarchy::V2(path) => path,
+        }
+    }
+
+    /// The cgroup directory `pid` is in, from `/proc/<pid>/cgroup`.
+    fn cgroup_of(&self, pid: u32, controller: &str) -> io::Result<PathBuf> {
+        let cgroups = std::fs::read_to_string(format!("/proc/{pid}/cgroup"))?;
+        // `hierarchy-id:controllers:path`, with an empty controller list for v2.
+        let path = cgroups.lines().find_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+            let wanted = match self {
+                Hierarchy::V1(_) => controllers.split(',').any(|c| c == controller),
+                Hierarchy::V2(_) => controllers.is_empty(),
+            };
+            wanted.then_some(path)
+        });
+        let path = path.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("no cgroup for {pid}"))
+        })?;
+        Ok(self.mount_point().join(path.trim_start_matches('/')))
+    }
+}
+
+/// Moves `pids` into a new cgroup with `limit`. `pids` starts with the process the limit is put
+/// on, which has to be moved for the limit to count.
+pub fn apply(pids: &[u32], limit: Limit) -> io::Result<Throttle> {
+    let &[pid, ..] = pids else {
+        return Err(io::ErrorKind::InvalidInput.into());
+    };
+    let mut throttle = Throttle {
+        pid,
+        percent: None,
+        memory: None,
+        moved: pids.to_vec(),
+        groups: Vec::new(),
+    };
+    throttle.set(limit)?;
+    Ok(throttle)
+}
+
+/// Moves the processes back to where they came from and deletes the cgroups.
+pub fn remove(throttle: Throttle) -> io::Result<()> {
+    let mut result = Ok(());
+    for group in throttle.groups {
+        result = result.and(group.remove(throttle.pid));
+    }
+    result
+}
+
+impl Throttle {
+    /// Puts `limit` on the processes too, or changes it, moving them into the cgroup of another
+    /// hierarchy if its controller is mounted apart.
+    pub fn set(&mut self, limit: Limit) -> io::Result<()> {
+        let resource = limit.resource();
+        let hierarchy = Hierarchy::find(resource.controller())?;
+        let existing = self
+            .groups
+            .iter()
+            .position(|group| group.hierarchy.mount_point() == hierarchy.mount_point());
+        let i = match existing {
+            Some(i) => i,
+            None => {
+                let dir = hierarchy
+                    .mount_point()
+                    .join(format!("syskill-throttle-{}", self.pid));
+                std::fs::create_dir_all(&dir)?;
+                self.groups.push(Group {
+                    hierarchy,
+                    dir,
+                    resources: Vec::new(),
+                    origins: Vec::new(),
+                });
+                self.groups.len() - 1
+            }
+        };
+        let group = &mut self.groups[i];
+        let mut result = group.write(limit);
+        if result.is_ok() && group.origins.is_empty() {
+            result = group.enter(self.pid, &self.moved, resource.controller());
+        }
+        if let Err(e) = result {
+            if group.resources.is_empty() {
+                let _ = self.groups.remove(i).remove(self.pid);
+            }
+            return Err(e);
+        }
+        if !group.resources.contains(&resource) {
+            group.resources.push(resource);
+        }
+        // Children may exit before they are moved, so the first cgroup tells which were.
+        self.moved = self.groups[0].origins.iter().map(|&(pid, _)| pid).collect();
+        match limit {
+            Limit::Cpu(percent) => self.percent = Some(percent),
+            Limit::Memory(bytes) => self.memory = Some(bytes),
+        }
+        Ok(())
+    }
+
+    /// Removes the limit on `resource`, and the processes from a cgroup left without limits.
+    pub fn lift(&mut self, resource: Resource) -> io::Result<()> {
+        match resource {
+            Resource::Cpu => self.percent = None,
+            Resource::Memory => self.memory = None,
+        }
+        let Some(i) = self
+            .groups
+            .iter()
+            .position(|group| group.resources.contains(&resource))
+        else {
+            return Ok(());
+        };
+        let group = &mut self.groups[i];
+        group.resources.retain(|&other| other != resource);
+        if group.resources.is_empty() {
+            self.groups.remove(i).remove(self.pid)
+        } else {
+            group.unlimit(resource)
+        }
+    }
+
+    /// Whether no limit is left.
+    pub fn is_unlimited(&self) -> bool {
+        self.percent.is_none() && self.memory.is_none()
+    }
+
+    /// Whether every process under the limits has exited.
+    pub fn is_empty(&self) -> bool {
+        self.groups.iter().all(|group| {
+            std::fs::read_to_string(group.dir.join("cgroup.procs"))
+                .map_or(true, |procs| procs.trim().is_empty())
+        })
+    }
+}
+
+impl Group {
+    fn write(&self, limit: Limit) -> io::Result<()> {
+        use std::fs;
+
+        if let Hierarchy::V2(mount_point) = &self.hierarchy {
+            // The controller has to be enabled for the children of the root cgroup first.
+            let controller = limit.resource().controller();
+            fs::write(
+                mount_point.join("cgroup.subtree_control"),
+                format!("+{controller}"),
+            )?;
+        }
+        match (&self.hierarchy, limit) {
+            (Hierarchy::V1(_), Limit::Cpu(percent)) => {
+                let quota = u64::from(percent) * PERIOD_US / 100;
+                fs::write(self.dir.join("cpu.cfs_period_us"), PERIOD_US.to_string())?;
+                fs::write(self.dir.join("cpu.cfs_quota_us"), quota.to_string())
+            }
+            (Hierarchy::V2(_), Limit::Cpu(percent)) => {
+                let quota = u64::from(percent) * PERIOD_US / 100;
+                fs::write(self.dir.join("cpu.max"), format!("{quota} {PERIOD_US}"))
+            }
+            (Hierarchy::V1(_), Limit::Memory(bytes)) => {
+                // With swap accounting the limit has to cover swap too, or a leak is swapped out
+                // instead of killed; the limit without swap may not exceed the one with it.
+                let swap = self.dir.join("memory.memsw.limit_in_bytes");
+                let _ = fs::write(&swap, "-1");
+                fs::write(self.dir.join("memory.limit_in_bytes"), bytes.to_string())?;
+                let _ = fs::write(&swap, bytes.to_string());
+                Ok(())
+            }
+            (Hierarchy::V2(_), Limit::Memory(bytes)) => {
+                fs::write(self.dir.join("memory.max"), bytes.to_string())?;
+                let _ = fs::write(self.dir.join("memory.swap.max"), "0");
+                Ok(())
+            }
+        }
+    }
+
+    fn unlimit(&self, resource: Resource) -> io::Result<()> {
+        use std::fs;
+
+        match (&self.hierarchy, resource) {
+            (Hierarchy::V1(_), Resource::Cpu) => fs::write(self.dir.join("cpu.cfs_quota_us"), "-1"),
+            (Hierarchy::V2(_), Resource::Cpu) => fs::write(self.dir.join("cpu.max"), "max"),
+            (Hierarchy::V1(_), Resource::Memory) => {
+                let _ = fs::write(self.dir.join("memory.memsw.limit_in_bytes"), "-1");
+                fs::write(self.dir.join("memory.limit_in_bytes"), "-1")
+            }
+            (Hierarchy::V2(_), Resource::Memory) => {
+                let _ = fs::write(self.dir.join("memory.swap.max"), "max");
+                fs::write(self.dir.join("memory.max"), "max")
+            }
+        }
+    }
+
+    /// Moves `pids` in, all but `pid` itself on a best-effort basis.
+    fn enter(&mut self, pid: u32, pids: &[u32], controller: &str) -> io::Result<()> {
+        for &moving in pids {
+            let moved = self
+                .hierarchy
+                .cgroup_of(moving, controller)
+                .and_then(|origin| {
+                    std::fs::write(self.dir.join("cgroup.procs"), moving.to_string())?;
+                    Ok(origin.join("cgroup.procs"))
+                });
+            match moved {
+                Ok(origin) => self.origins.push((moving, origin)),
+                // Children may exit in the meantime, but without the process itself there is
+                // no limit.
+                Err(e) if moving == pid => return Err(e),
+                Err(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the processes back and deletes the cgroup. Processes started in the cgroup since go
+    /// to the cgroup of the process the limits were put on.
+    fn remove(self, pid: u32) -> io::Result<()> {
+        let Some(home) = self
+            .origins
+            .iter()
+            .find(|&&(moved, _)| moved == pid)
+            .map(|(_, origin)| origin.clone())
+        else {
+            return std::fs::remove_dir(&self.dir);
+        };
+        for (moved, origin) in &self.origins {
+            // Fails for processes that have exited, which left the cgroup anyway.
+            let _ = std::fs::write(origin, moved.to_string());
+        }
+        let remaining = std::fs::read_to_string(self.dir.join("cgroup.procs")).unwrap_or_default();
+        for pid in remaining.lines() {
+            let _ = std::fs::write(&home, pid);
+        }
+        std::fs::remove_dir(&self.dir)
+    }
+}
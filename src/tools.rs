@@ -0,0 +1,213 @@
+//! External tool actions for inspecting a process before deciding to kill it.
+//!
+//! Each [`ToolAction`] runs a diagnostic program (`jcmd`, `py-spy`, ...) against the selected
+//! process in the background. Actions are only offered when the process looks like the runtime
+//! they apply to and the program is installed. [`run_command`] runs whatever is typed at the
+//! command prompt instead.
+
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Runtime {
+    Jvm,
+    Python,
+}
+
+impl Runtime {
+    /// Guesses the runtime from the process name and executable path.
+    pub fn detect(name: &str, exe: &str) -> Option<Self> {
+        let program = Path::new(exe)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(name);
+        if program == "java" || name == "java" {
+            Some(Runtime::Jvm)
+        } else if program.starts_with("python") || name.starts_with("python") {
+            Some(Runtime::Python)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Runtime::Jvm => "JVM",
+            Runtime::Python => "CPython",
+        }
+    }
+}
+
+pub struct ToolAction {
+    pub label: &'static str,
+    pub runtime: Runtime,
+    pub program: &'static str,
+    /// Arguments; `{pid}` is replaced by the process id.
+    pub args: &'static [&'static str],
+}
+
+pub const ACTIONS: &[ToolAction] = &[
+    ToolAction {
+        label: "Heap usage",
+        runtime: Runtime::Jvm,
+        program: "jcmd",
+        args: &["{pid}", "GC.heap_info"],
+    },
+    ToolAction {
+        label: "Thread dump",
+        runtime: Runtime::Jvm,
+        program: "jcmd",
+        args: &["{pid}", "Thread.print"],
+    },
+    ToolAction {
+        label: "Thread dump",
+        runtime: Runtime::Python,
+        program: "py-spy",
+        args: &["dump", "--pid", "{pid}"],
+    },
+];
+
+/// Actions for `runtime` whose program is installed.
+pub fn available(runtime: Runtime) -> Vec<&'static ToolAction> {
+    ACTIONS
+        .iter()
+        .filter(|action| action.runtime == runtime && installed(action.program))
+        .collect()
+}
+
+fn installed(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// Output of a finished action.
+pub struct ToolOutput {
+    pub title: String,
+    pub text: String,
+}
+
+impl ToolAction {
+    /// Runs the action against `pid` on a background thread.
+    pub fn run(&'static self, pid: u32) -> Receiver<ToolOutput> {
+        let (sender, receiver) = mpsc::channel();
+        let pid = pid.to_string();
+        thread::spawn(move || {
+            let args: Vec<String> = self
+                .args
+                .iter()
+                .map(|arg| arg.replace("{pid}", &pid))
+                .collect();
+            let text = match Command::new(self.program).args(&args).output() {
+                Ok(output) => {
+                    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                    text.push_str(&String::from_utf8_lossy(&output.stderr));
+                    text
+                }
+                Err(e) => format!("cannot run {}: {e}", self.program),
+            };
+            let _ = sender.send(ToolOutput {
+                title: format!("{} ({} {})", self.label, self.program, args.join(" ")),
+                text,
+            });
+        });
+        receiver
+    }
+}
+
+/// Runs `template` through the shell on a background thread, with `{pid}` and `{name}` replaced
+/// by those of the selected process, see [`expand`].
+pub fn run_command(template: &str, pid: u32, name: &str) -> Receiver<ToolOutput> {
+    let (sender, receiver) = mpsc::channel();
+    let command = expand(template, pid, cfg!(windows));
+    let title = template
+        .replace("{pid}", &pid.to_string())
+        .replace("{name}", name);
+    let name = name.to_string();
+    thread::spawn(move || {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            // Only delayed expansion (`!...!`) happens after the command line is parsed.
+            shell.args(["/V:ON", "/C"]).arg(&command);
+            shell.env(NAME_VARIABLE, &name);
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c").arg(&command).arg("syskill").arg(&name);
+            shell
+        };
+        // The terminal is in raw mode and belongs to the table.
+        let output = shell.stdin(Stdio::null()).output();
+        let text = match output {
+            Ok(output) => {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                if !output.status.success() {
+                    text.push_str(&format!("\n{}", output.status));
+                } else if text.is_empty() {
+                    text.push_str("(no output)");
+                }
+                text
+            }
+            Err(e) => format!("cannot run the shell: {e}"),
+        };
+        let _ = sender.send(ToolOutput {
+            title: format!("$ {title}"),
+            text,
+        });
+    });
+    receiver
+}
+
+/// The variable holding the name of the process for `cmd`; `sh` gets it as `$1`.
+const NAME_VARIABLE: &str = "SYSKILL_NAME";
+
+/// `template` with `{pid}` replaced, and `{name}` by a reference to the name the shell is given
+/// apart from the command, for `cmd` if `windows` or else for `sh`, so that nothing in the name
+/// is run. Quotes put around the placeholder in the template are dropped; the placeholder is
+/// best left out of longer quoted strings, where `sh` splits the name at spaces and `'...'` keeps
+/// it from being expanded at all.
+fn expand(template: &str, pid: u32, windows: bool) -> String {
+    let (quoted, name): (&[&str], _) = if windows {
+        (&["\"{name}\""], format!("\"!{NAME_VARIABLE}!\""))
+    } else {
+        (&["'{name}'", "\"{name}\""], "\"$1\"".to_string())
+    };
+    let template = quoted
+        .iter()
+        .fold(template.to_string(), |template, quoted| {
+            template.replace(quoted, "{name}")
+        });
+    template
+        .replace("{pid}", &pid.to_string())
+        .replace("{name}", &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_passed_apart_from_the_command() {
+        let expand_sh = |template| expand(template, 42, false);
+        assert_eq!(expand_sh("kill {pid}"), "kill 42");
+        assert_eq!(expand_sh("pgrep {name}"), r#"pgrep "$1""#);
+        assert_eq!(expand_sh("pgrep '{name}'"), r#"pgrep "$1""#);
+        assert_eq!(expand_sh("pgrep \"{name}\""), r#"pgrep "$1""#);
+        assert_eq!(
+            expand("echo \"{name}\"", 42, true),
+            r#"echo "!SYSKILL_NAME!""#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_are_not_run() {
+        let name = "it's $HOME; echo `id` $(id)";
+        let output = run_command("echo {name}", 42, name).recv().unwrap();
+        assert_eq!(output.text, format!("{name}\n"));
+        assert_eq!(output.title, format!("$ echo {name}"));
+    }
+}
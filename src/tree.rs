@@ -0,0 +1,216 @@
+//! Process tree view: the flat process list arranged by parent PID.
+//!
+//! [`TreeView`] only remembers which processes are collapsed. Each time the list changes it is
+//! rearranged depth-first, children following their parent in the current sort order, and the
+//! descendants of collapsed processes are set aside until they are expanded again.
+
+use crate::app::ProcessInfo;
+use crate::snapshot::ProcessKey;
+use std::collections::{HashMap, HashSet};
+use sysinfo::{Pid, System};
+
+/// Tree details of a displayed process.
+#[derive(Clone, Copy)]
+pub struct TreeRow {
+    pub depth: usize,
+    pub has_children: bool,
+    pub collapsed: bool,
+}
+
+impl TreeRow {
+    /// Indentation and expand/collapse marker to put in front of the name.
+    pub fn prefix(self) -> String {
+        let marker = match (self.has_children, self.collapsed) {
+            (false, _) => "  ",
+            (true, false) => "▾ ",
+            (true, true) => "▸ ",
+        };
+        format!("{}{marker}", "  ".repeat(self.depth))
+    }
+}
+
+#[derive(Default)]
+pub struct TreeView {
+    collapsed: HashSet<ProcessKey>,
+}
+
+impl TreeView {
+    pub fn toggle(&mut self, key: ProcessKey) {
+        if !self.collapsed.remove(&key) {
+            self.collapsed.insert(key);
+        }
+    }
+
+    /// Orders `items` as a tree. Returns the displayed processes with their rows, and the hidden
+    /// descendants of collapsed processes. Processes whose parent is not listed are roots.
+    pub fn arrange(
+        &self,
+        items: Vec<ProcessInfo>,
+    ) -> (Vec<ProcessInfo>, Vec<TreeRow>, Vec<ProcessInfo>) {
+        let index: HashMap<u32, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(i, data)| (data.pid, i))
+            .collect();
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
+        let mut roots = Vec::new();
+        for (i, data) in items.iter().enumerate() {
+            match data.parent.and_then(|parent| index.get(&parent)) {
+                Some(&parent) if parent != i => children[parent].push(i),
+                _ => roots.push(i),
+            }
+        }
+
+        // Depth-first, keeping siblings in their sorted order.
+        let mut order = Vec::with_capacity(items.len());
+        let mut stack: Vec<(usize, usize, bool)> =
+            roots.into_iter().rev().map(|i| (i, 0, false)).collect();
+        while let Some((i, depth, hidden)) = stack.pop() {
+            let collapsed = self.collapsed.contains(&items[i].key());
+            let row = TreeRow {
+                depth,
+                has_children: !children[i].is_empty(),
+                collapsed,
+            };
+            order.push((i, row, hidden));
+            for &child in children[i].iter().rev() {
+                stack.push((child, depth + 1, hidden || collapsed));
+            }
+        }
+
+        let mut slots: Vec<Option<ProcessInfo>> = items.into_iter().map(Some).collect();
+        let (mut shown, mut rows, mut hidden_items) = (Vec::new(), Vec::new(), Vec::new());
+        for (i, row, hidden) in order {
+            let Some(data) = slots[i].take() else {
+                continue;
+            };
+            if hidden {
+                hidden_items.push(data);
+            } else {
+                shown.push(data);
+                rows.push(row);
+            }
+        }
+        // Parent loops (possible with reused PIDs) are never reached from a root.
+        for data in slots.into_iter().flatten() {
+            shown.push(data);
+            rows.push(TreeRow {
+                depth: 0,
+                has_children: false,
+                collapsed: false,
+            });
+        }
+        (shown, rows, hidden_items)
+    }
+}
+
+/// `root` and all its descendants, deepest first so children go before their parents.
+pub fn subtree(system: &System, root: Pid) -> Vec<Pid> {
+    let processes = system.processes().iter().map(|(&pid, process)| {
+        let parent = process.parent().map(Pid::as_u32);
+        (pid.as_u32(), parent, process.start_time())
+    });
+    subtree_of(processes, root.as_u32())
+        .into_iter()
+        .map(Pid::from_u32)
+        .collect()
+}
+
+/// [`subtree`] of the processes given by PID, parent PID and start time.
+pub fn subtree_of(
+    processes: impl IntoIterator<Item = (u32, Option<u32>, u64)>,
+    root: u32,
+) -> Vec<u32> {
+    let processes: Vec<_> = processes.into_iter().collect();
+    let started: HashMap<u32, u64> = processes
+        .iter()
+        .map(|&(pid, _, start_time)| (pid, start_time))
+        .collect();
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (pid, parent, start_time) in processes {
+        let Some(parent) = parent else {
+            continue;
+        };
+        // Windows keeps the PID of an exited parent, which a process started later may have got
+        // since; that one is no parent of processes older than itself.
+        if started
+            .get(&parent)
+            .is_some_and(|&parent_start| parent_start > start_time)
+        {
+            continue;
+        }
+        children.entry(parent).or_default().push(pid);
+    }
+    let mut pids = vec![root];
+    let mut seen = HashSet::from([root]);
+    let mut i = 0;
+    while i < pids.len() {
+        for &kid in children.get(&pids[i]).into_iter().flatten() {
+            if seen.insert(kid) {
+                pids.push(kid);
+            }
+        }
+        i += 1;
+    }
+    pids.reverse();
+    pids
+}
+
+/// The PIDs of a [`subtree_of`] that may be killed: all but init, which is never signalled.
+/// `None` if `own`, syskill itself, is among them, as its ancestors cannot be killed with their
+/// descendants without killing syskill halfway through.
+pub fn killable(pids: Vec<u32>, own: u32) -> Option<Vec<u32>> {
+    if pids.contains(&own) {
+        return None;
+    }
+    Some(pids.into_iter().filter(|&pid| pid != 1).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtree_puts_children_before_their_parents() {
+        let processes = [
+            (1, None, 100),
+            (10, Some(1), 110),
+            (11, Some(10), 120),
+            (12, Some(10), 130),
+            (13, Some(11), 140),
+            (20, Some(1), 150),
+        ];
+        assert_eq!(subtree_of(processes, 10), [13, 12, 11, 10]);
+        assert_eq!(subtree_of(processes, 13), [13]);
+    }
+
+    #[test]
+    fn subtree_skips_parents_younger_than_the_child() {
+        // 10 exited and its PID went to a process started after 11.
+        let processes = [(10, None, 200), (11, Some(10), 150), (12, Some(10), 250)];
+        assert_eq!(subtree_of(processes, 10), [12, 10]);
+    }
+
+    #[test]
+    fn killable_leaves_out_init_and_refuses_syskill() {
+        let processes = [
+            (1, None, 100),
+            (10, Some(1), 110),
+            (11, Some(10), 120),
+            (20, Some(1), 150),
+        ];
+        assert_eq!(killable(subtree_of(processes, 1), 20), None);
+        assert_eq!(killable(subtree_of(processes, 10), 20), Some(vec![11, 10]));
+        assert_eq!(killable(subtree_of(processes, 10), 11), None);
+        assert_eq!(
+            killable(subtree_of(processes, 1), 99),
+            Some(vec![11, 20, 10])
+        );
+    }
+
+    #[test]
+    fn subtree_survives_a_cycle() {
+        let processes = [(5, Some(6), 100), (6, Some(5), 100)];
+        assert_eq!(subtree_of(processes, 5), [6, 5]);
+    }
+}
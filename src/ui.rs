@@ -0,0 +1,4025 @@
+//! Drawing the interactive table, its side panes and popups.
+
+use crate::app::{App, AppState, KillAction, ProcessInfo, SidePane, SortColumn, SortKey, Tags};
+use crate::breakdown::Segment;
+use crate::config::{Column, ThemeSpec};
+use crate::plugin::ColumnProvider;
+use crate::throttle::Resource;
+use crate::{
+    affinity, audit, changes, details, exits, history, hugepages, hunt, hyperlink, i18n, ionice,
+    keymap, limits, logging, numa, plugin, power, proc, procfs, query, renice, replay, report,
+    sched, signal, snapshot, summary, tools, update, CPU_CRITICAL, CPU_TREND,
+};
+use clap::ValueEnum;
+use crossterm::event::KeyCode;
+use ratatui::{prelude::*, style::palette::tailwind, widgets::*, Terminal};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::io;
+use std::time::Instant;
+
+/// Terminals with fewer lines get the compact pager layout.
+const COMPACT_BELOW: u16 = 8;
+/// Narrowest the table gets next to the side panes; narrower terminals leave the panes out.
+const MIN_TABLE_WIDTH: u16 = 60;
+/// Smallest size of popups, unless the terminal itself is smaller.
+const MIN_POPUP_WIDTH: u16 = 40;
+const MIN_POPUP_HEIGHT: u16 = 5;
+/// Columns left out of a table too narrow for all of them, the first first. The name stays.
+const DROPPED_FIRST: [Column; 27] = [
+    Column::Command,
+    Column::Window,
+    Column::Container,
+    Column::GpuMemory,
+    Column::Gpu,
+    Column::Fds,
+    Column::CpuTime,
+    Column::Swap,
+    Column::Shared,
+    Column::Virtual,
+    Column::Started,
+    Column::Elapsed,
+    Column::Threads,
+    Column::Session,
+    Column::Tty,
+    Column::Ppid,
+    Column::Sched,
+    Column::Write,
+    Column::Read,
+    Column::Trend,
+    Column::Node,
+    Column::Tags,
+    Column::State,
+    Column::User,
+    Column::Memory,
+    Column::Pid,
+    Column::Cpu,
+];
+/// Samples shown in the CPU trend column.
+const TREND_WIDTH: usize = 8;
+/// Characters of command lines shown, in their column or in place of names.
+const COMMAND_WIDTH: usize = 50;
+/// Usage in percent above which a core of the machine counts as busy in the cores pane.
+const BUSY_CORE: f32 = 90.0;
+/// Catalog keys of the headers of the table columns, by [`Column`].
+const TITLES: [&str; 28] = [
+    "column.name",
+    "column.pid",
+    "column.cpu",
+    "column.memory",
+    "column.node",
+    "column.tags",
+    "column.trend",
+    "column.user",
+    "column.state",
+    "column.sched",
+    "column.read",
+    "column.write",
+    "column.virtual",
+    "column.shared",
+    "column.swap",
+    "column.ppid",
+    "column.tty",
+    "column.session",
+    "column.threads",
+    "column.started",
+    "column.elapsed",
+    "column.cputime",
+    "column.fds",
+    "column.container",
+    "column.window",
+    "column.gpu",
+    "column.gpumemory",
+    "column.command",
+];
+
+pub(crate) const PALETTES: [tailwind::Palette; 4] = [
+    tailwind::PURPLE,
+    tailwind::EMERALD,
+    tailwind::INDIGO,
+    tailwind::RED,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    #[default]
+    Purple,
+    Emerald,
+    Indigo,
+    Red,
+    /// Okabe-Ito colors, distinguishable with deuteranopia and protanopia.
+    Colorblind,
+    /// Black and white with severity also shown as symbols.
+    HighContrast,
+    /// No colors at all, only the terminal's own, with severity shown as symbols; the default
+    /// with `NO_COLOR` set or a dumb terminal.
+    Monochrome,
+}
+
+impl Palette {
+    pub(crate) const fn colors(self) -> TableColors {
+        match self {
+            Palette::Purple => TableColors::new(&PALETTES[0]),
+            Palette::Emerald => TableColors::new(&PALETTES[1]),
+            Palette::Indigo => TableColors::new(&PALETTES[2]),
+            Palette::Red => TableColors::new(&PALETTES[3]),
+            Palette::Colorblind => TableColors::colorblind(),
+            Palette::HighContrast => TableColors::high_contrast(),
+            Palette::Monochrome => TableColors::monochrome(),
+        }
+    }
+
+    /// As written after `--palette`, e.g. `high-contrast`.
+    fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// A built-in palette, or a theme of the config file's `[themes]` table by name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Theme {
+    Palette(Palette),
+    Custom(String),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::Palette(Palette::default())
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = std::convert::Infallible;
+
+    /// Names that are not those of a palette are taken for custom themes, which only the config
+    /// file can tell exist.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match Palette::from_str(name, true) {
+            Ok(palette) => Theme::Palette(palette),
+            Err(_) => Theme::Custom(name.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Theme::Palette(palette) => write!(f, "{}", palette.name()),
+            Theme::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Theme {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name.parse().unwrap_or_default())
+    }
+}
+
+impl Theme {
+    /// The palette when none is chosen: none at all with `NO_COLOR` set, as
+    /// <https://no-color.org> asks, or on a dumb terminal.
+    pub(crate) fn from_environment() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+        match no_color || dumb {
+            true => Theme::Palette(Palette::Monochrome),
+            false => Theme::default(),
+        }
+    }
+
+    /// The colors of the theme, with custom ones taken from `themes`.
+    pub(crate) fn colors(
+        &self,
+        themes: &BTreeMap<String, ThemeSpec>,
+    ) -> Result<TableColors, String> {
+        match self {
+            Theme::Palette(palette) => Ok(palette.colors()),
+            Theme::Custom(name) => match themes.get(name) {
+                Some(spec) => TableColors::custom(spec).map_err(|e| format!("theme {name}: {e}")),
+                None => {
+                    let mut names: Vec<String> = Palette::value_variants()
+                        .iter()
+                        .map(|palette| palette.name())
+                        .collect();
+                    names.extend(themes.keys().cloned());
+                    Err(format!(
+                        "unknown palette {name}, expected one of {}",
+                        names.join(", ")
+                    ))
+                }
+            },
+        }
+    }
+
+    /// The theme after this one: the palettes in their order, then the custom themes by name.
+    pub(crate) fn next(&self, themes: &BTreeMap<String, ThemeSpec>) -> Theme {
+        let all: Vec<Theme> = Palette::value_variants()
+            .iter()
+            .map(|&palette| Theme::Palette(palette))
+            .chain(themes.keys().map(|name| Theme::Custom(name.clone())))
+            .collect();
+        let next = all
+            .iter()
+            .position(|theme| theme == self)
+            .map_or(0, |i| i + 1);
+        all[next % all.len()].clone()
+    }
+}
+
+pub(crate) struct TableColors {
+    buffer_bg: Color,
+    header_bg: Color,
+    header_fg: Color,
+    row_fg: Color,
+    selected_style_fg: Color,
+    normal_row_color: Color,
+    alt_row_color: Color,
+    footer_border_color: Color,
+    rising_fg: Color,
+    critical_fg: Color,
+    /// Mark severity with ▲/▼/! in addition to color.
+    severity_symbols: bool,
+    /// Leave out every color, the fixed ones of popups and graphs too.
+    monochrome: bool,
+}
+
+impl TableColors {
+    pub(crate) const fn new(color: &tailwind::Palette) -> Self {
+        Self {
+            buffer_bg: tailwind::SLATE.c950,
+            header_bg: color.c900,
+            header_fg: tailwind::SLATE.c200,
+            row_fg: tailwind::SLATE.c200,
+            selected_style_fg: color.c400,
+            normal_row_color: tailwind::SLATE.c950,
+            alt_row_color: tailwind::SLATE.c900,
+            footer_border_color: color.c400,
+            rising_fg: tailwind::AMBER.c400,
+            critical_fg: tailwind::RED.c500,
+            severity_symbols: false,
+            monochrome: false,
+        }
+    }
+
+    /// Built from the Okabe-Ito palette: blue/orange/yellow stay distinct for deuteranopia and
+    /// protanopia, and red/green are never used to carry meaning.
+    const fn colorblind() -> Self {
+        Self {
+            buffer_bg: tailwind::SLATE.c950,
+            header_bg: Color::Rgb(0x00, 0x72, 0xB2),
+            header_fg: Color::Rgb(0xFF, 0xFF, 0xFF),
+            row_fg: tailwind::SLATE.c200,
+            selected_style_fg: Color::Rgb(0xF0, 0xE4, 0x42),
+            normal_row_color: tailwind::SLATE.c950,
+            alt_row_color: tailwind::SLATE.c900,
+            footer_border_color: Color::Rgb(0x56, 0xB4, 0xE9),
+            rising_fg: Color::Rgb(0x56, 0xB4, 0xE9),
+            critical_fg: Color::Rgb(0xE6, 0x9F, 0x00),
+            severity_symbols: false,
+            monochrome: false,
+        }
+    }
+
+    const fn high_contrast() -> Self {
+        Self {
+            buffer_bg: Color::Black,
+            header_bg: Color::White,
+            header_fg: Color::Black,
+            row_fg: Color::White,
+            selected_style_fg: Color::Yellow,
+            normal_row_color: Color::Black,
+            alt_row_color: Color::Black,
+            footer_border_color: Color::White,
+            rising_fg: Color::Yellow,
+            critical_fg: Color::LightRed,
+            severity_symbols: true,
+            monochrome: false,
+        }
+    }
+
+    const fn monochrome() -> Self {
+        Self {
+            buffer_bg: Color::Reset,
+            header_bg: Color::Reset,
+            header_fg: Color::Reset,
+            row_fg: Color::Reset,
+            selected_style_fg: Color::Reset,
+            normal_row_color: Color::Reset,
+            alt_row_color: Color::Reset,
+            footer_border_color: Color::Reset,
+            rising_fg: Color::Reset,
+            critical_fg: Color::Reset,
+            severity_symbols: true,
+            monochrome: true,
+        }
+    }
+
+    /// The colors of a `[themes]` table: those of its `base` palette, with the slots it sets
+    /// replaced.
+    fn custom(spec: &ThemeSpec) -> Result<Self, String> {
+        let mut colors = spec.base.colors();
+        let slots = [
+            (&mut colors.buffer_bg, &spec.buffer_bg),
+            (&mut colors.header_bg, &spec.header_bg),
+            (&mut colors.header_fg, &spec.header_fg),
+            (&mut colors.row_fg, &spec.row_fg),
+            (&mut colors.selected_style_fg, &spec.selected_style_fg),
+            (&mut colors.normal_row_color, &spec.normal_row_color),
+            (&mut colors.alt_row_color, &spec.alt_row_color),
+            (&mut colors.footer_border_color, &spec.footer_border_color),
+            (&mut colors.rising_fg, &spec.rising_fg),
+            (&mut colors.critical_fg, &spec.critical_fg),
+        ];
+        for (slot, value) in slots {
+            if let Some(value) = value {
+                // `#rrggbb`, a name such as `lightred`, or an index of the 256-color palette.
+                *slot = value.parse().map_err(|_| format!("not a color: {value}"))?;
+            }
+        }
+        if let Some(symbols) = spec.severity_symbols {
+            colors.severity_symbols = symbols;
+        }
+        Ok(colors)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Severity {
+    Normal,
+    Rising,
+    Falling,
+    Critical,
+}
+
+impl Severity {
+    fn of(cpu: f32, previous_cpu: Option<f32>) -> Self {
+        let trend = previous_cpu.map_or(0.0, |previous| cpu - previous);
+        if cpu >= CPU_CRITICAL {
+            Severity::Critical
+        } else if trend >= CPU_TREND {
+            Severity::Rising
+        } else if trend <= -CPU_TREND {
+            Severity::Falling
+        } else {
+            Severity::Normal
+        }
+    }
+
+    const fn symbol(self) -> &'static str {
+        match self {
+            Severity::Normal => " ",
+            Severity::Rising => "▲",
+            Severity::Falling => "▼",
+            Severity::Critical => "!",
+        }
+    }
+}
+
+/// Where the last frame drew what reacts to the mouse, in screen coordinates.
+#[derive(Default)]
+pub(crate) struct Clickable {
+    /// The rows of the table below its header.
+    pub(crate) rows: Rect,
+    /// Index of the first row shown.
+    pub(crate) first_row: usize,
+    pub(crate) row_height: u16,
+    /// Header cells of the columns the table can be sorted by.
+    pub(crate) headers: Vec<(Rect, SortColumn)>,
+    /// Buttons of the open popup, with the key each one stands for.
+    pub(crate) buttons: Vec<(Rect, KeyCode)>,
+    /// Counts of the breakdown line, by index.
+    pub(crate) segments: Vec<(Rect, usize)>,
+}
+
+/// Header of the logical column `column`, with the custom ones from `providers`.
+fn title<'a>(column: usize, providers: &[&'a dyn ColumnProvider]) -> &'a str {
+    match Column::from_index(column) {
+        Column::Custom(index) => providers
+            .get(usize::from(index))
+            .map_or("", |provider| provider.title()),
+        _ => i18n::text(TITLES[column]),
+    }
+}
+
+/// The sort order picked by clicking the header of a logical column, if it has one.
+fn sort_column(column: usize) -> Option<SortColumn> {
+    match Column::from_index(column) {
+        Column::Name => Some(SortColumn::Name),
+        Column::Pid => Some(SortColumn::Pid),
+        Column::Cpu => Some(SortColumn::Cpu),
+        Column::Memory => Some(SortColumn::Memory),
+        Column::User => Some(SortColumn::User),
+        Column::Started | Column::Elapsed => Some(SortColumn::Elapsed),
+        Column::CpuTime => Some(SortColumn::CpuTime),
+        Column::Custom(index) => Some(SortColumn::Custom(index)),
+        _ => None,
+    }
+}
+
+/// `command` in `width` characters, scrolled `scroll` characters to the right. Too long ones
+/// lose their middle, e.g. `java -Xmx2g -jar …/lib/app.jar`, so the program and its last
+/// arguments both stay in sight; once scrolled, `…` marks the parts out of sight instead.
+fn fit_command(command: &str, width: usize, scroll: usize) -> String {
+    let chars: Vec<char> = command.chars().collect();
+    let text = |chars: &[char]| chars.iter().collect::<String>();
+    let room = width.saturating_sub(1);
+    if scroll == 0 {
+        if chars.len() <= width {
+            return command.to_string();
+        }
+        let tail = room / 2;
+        let head = room - tail;
+        return format!(
+            "{}…{}",
+            text(&chars[..head]),
+            text(&chars[chars.len() - tail..])
+        );
+    }
+    let rest = &chars[scroll.min(chars.len())..];
+    if rest.len() <= room {
+        format!("…{}", text(rest))
+    } else {
+        format!("…{}…", text(&rest[..room.saturating_sub(1)]))
+    }
+}
+
+impl App {
+    /// Popup frame: bordered normally, a plain titled block in linear mode.
+    fn popup_block<'a>(&self, title: &'a str) -> Block<'a> {
+        if self.linear {
+            Block::new().title(title)
+        } else {
+            Block::bordered().title(title)
+        }
+    }
+
+    /// Draws `buttons` side by side, centered on the last line inside the border of `popup`, and
+    /// notes where they are for the mouse.
+    fn render_buttons(
+        &self,
+        frame: &mut Frame,
+        popup: Rect,
+        buttons: &[(&str, KeyCode)],
+        clickable: &mut Clickable,
+    ) {
+        let labels: Vec<String> = buttons
+            .iter()
+            .map(|(label, _)| format!("[ {label} ]"))
+            .collect();
+        let width: usize = labels.iter().map(|label| label.chars().count() + 2).sum();
+        let line = Rect {
+            y: popup.bottom().saturating_sub(2),
+            height: 1,
+            ..popup.inner(&Margin::new(1, 0))
+        };
+        let mut x = line.x + line.width.saturating_sub(width as u16) / 2;
+        for (label, &(_, code)) in labels.iter().zip(buttons) {
+            let width = (label.chars().count() as u16).min(line.right().saturating_sub(x));
+            let button = Rect::new(x, line.y, width, 1);
+            frame.render_widget(
+                Span::raw(label.as_str()).fg(self.colors.header_fg).bold(),
+                button,
+            );
+            clickable.buttons.push((button, code));
+            x += width + 2;
+        }
+    }
+
+    /// A name or command cell with the characters matched by the search filter, at `positions`
+    /// of `shown`, highlighted.
+    fn highlighted(&self, shown: &str, positions: &[usize], compact: bool) -> Text<'static> {
+        let mut spans: Vec<Span> = Vec::new();
+        let mut run = String::new();
+        let mut run_matched = false;
+        for (i, c) in shown.chars().enumerate() {
+            let matched = positions.contains(&i);
+            if matched != run_matched && !run.is_empty() {
+                let span = Span::raw(std::mem::take(&mut run));
+                spans.push(match run_matched {
+                    true => span.fg(self.colors.selected_style_fg).bold(),
+                    false => span,
+                });
+            }
+            run_matched = matched;
+            run.push(c);
+        }
+        let span = Span::raw(run);
+        spans.push(match run_matched {
+            true => span.fg(self.colors.selected_style_fg).bold(),
+            false => span,
+        });
+        let line = Line::from(spans);
+        if compact {
+            Text::from(line)
+        } else {
+            Text::from(vec![Line::default(), line, Line::default()])
+        }
+    }
+
+    /// Where the name or command cell `shown` of a process named `name` is matched by the search,
+    /// or `None` for other cells and when nothing in it is. Names are found in the name cell past
+    /// its tree or group indent, the text of command lines wherever the cell shows them.
+    fn matched_positions(
+        &self,
+        column: usize,
+        shown: &str,
+        name: &str,
+        name_patterns: &[&str],
+        command_patterns: &[&str],
+    ) -> Option<Vec<usize>> {
+        let mut positions = Vec::new();
+        if column == Column::Command.index() || (column == 0 && self.full_commands) {
+            for pattern in command_patterns {
+                let found = query::find(query::Field::Command, shown, pattern);
+                positions.extend(found.unwrap_or_default());
+            }
+        } else if column == 0 {
+            let offset = shown.chars().count().saturating_sub(name.chars().count());
+            for pattern in name_patterns {
+                let found = query::find(query::Field::Name, name, pattern).unwrap_or_default();
+                positions.extend(found.into_iter().map(|i| i + offset));
+            }
+        }
+        (!positions.is_empty()).then_some(positions)
+    }
+
+    /// CPU per core as a row of bars, memory, swap, load average and uptime.
+    fn summary_line(&self, summary: &summary::Summary) -> Line<'static> {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let label = |text: &'static str| text.fg(self.colors.header_fg).bold();
+        let cpu = summary.cpu();
+        let cores: String = summary
+            .cores
+            .iter()
+            .map(|&usage| BARS[((usage / 100.0 * 7.0).round() as usize).min(7)])
+            .collect();
+        let load = &summary.load;
+        let mut spans = vec![
+            label("CPU "),
+            Span::raw(format!("{cpu:.0}% ")).fg(if cpu >= CPU_CRITICAL {
+                self.colors.critical_fg
+            } else {
+                self.colors.row_fg
+            }),
+            Span::raw(cores).fg(self.colors.selected_style_fg),
+            label("  Mem "),
+            Span::raw(format!(
+                "{}/{}",
+                self.units.memory(summary.memory_used),
+                self.units.memory(summary.memory_total)
+            )),
+            label("  Swap "),
+            Span::raw(format!(
+                "{}/{}",
+                self.units.memory(summary.swap_used),
+                self.units.memory(summary.swap_total)
+            )),
+        ];
+        if let Some(limits) = &summary.limits {
+            for (text, usage) in [
+                ("  FDs ", limits.open_files),
+                ("  Threads ", limits.threads),
+            ] {
+                spans.push(label(text));
+                let span = if usage.unlimited() {
+                    Span::raw(usage.used.to_string())
+                } else {
+                    Span::raw(format!("{}/{}", usage.used, usage.max))
+                };
+                spans.push(if usage.share() >= limits::CRITICAL_SHARE {
+                    span.fg(self.colors.critical_fg).bold()
+                } else {
+                    span
+                });
+            }
+        }
+        spans.extend([
+            label("  Load "),
+            Span::raw(format!(
+                "{:.2} {:.2} {:.2}",
+                load.one, load.five, load.fifteen
+            )),
+            label("  Up "),
+            Span::raw(report::format_duration(summary.uptime)),
+        ]);
+        Line::from(spans)
+    }
+
+    /// The state and user counts, the one picked with the keyboard highlighted and the one
+    /// searched for underlined. Returns where each count was drawn.
+    fn render_breakdown(&self, frame: &mut Frame, area: Rect) -> Vec<(Rect, usize)> {
+        let breakdown = self.breakdown();
+        let mut spans = vec![Span::raw(format!("{} total: ", breakdown.total))
+            .fg(self.colors.header_fg)
+            .bold()];
+        let mut segments = Vec::new();
+        let mut x = area.x + spans[0].width() as u16;
+        let mut previous_user = None;
+        for (index, (segment, count)) in breakdown.segments.iter().enumerate() {
+            let user = matches!(segment, Segment::User(_));
+            let separator = match previous_user {
+                None => "",
+                Some(false) if user => " · ",
+                Some(_) => ", ",
+            };
+            previous_user = Some(user);
+            let text = if user {
+                format!("{} {count}", segment.label())
+            } else {
+                format!("{count} {}", segment.label())
+            };
+            let mut span = Span::raw(text);
+            if self.breakdown_choice == Some(index) {
+                span = span.reversed().fg(self.colors.selected_style_fg);
+            }
+            if self.filter == segment.search() {
+                span = span.underlined().bold();
+            }
+            let separator = Span::raw(separator);
+            x += separator.width() as u16;
+            let width = span.width() as u16;
+            segments.push((Rect::new(x, area.y, width, 1), index));
+            x += width;
+            spans.extend([separator, span]);
+        }
+        if breakdown.other_users > 0 {
+            spans.push(Span::raw(format!(", … {}", breakdown.other_users)));
+        }
+        frame.render_widget(Line::from(spans), area);
+        // Counts cut off at the edge cannot be clicked.
+        segments.retain(|(rect, _)| rect.right() <= area.right());
+        segments
+    }
+
+    /// Tags of a process, inherited ones dimmed.
+    fn tags_text(&self, tags: Option<&Tags>, compact: bool) -> Text<'static> {
+        let mut spans = Vec::new();
+        if let Some(tags) = tags {
+            for tag in &tags.own {
+                spans.push(Span::raw(format!("{tag} ")));
+            }
+            for tag in &tags.inherited {
+                spans.push(Span::raw(format!("{tag} ")).add_modifier(Modifier::DIM));
+            }
+        }
+        let line = Line::from(spans);
+        if compact {
+            Text::from(line)
+        } else {
+            Text::from(vec![Line::default(), line, Line::default()])
+        }
+    }
+
+    /// Indices of the columns to draw (see [`Column`]) in display order: those picked to be shown
+    /// that have something to show.
+    /// The panes to the right of the table that are switched on.
+    fn side_panes(&self) -> Vec<SidePane> {
+        [
+            (self.show_chart, SidePane::Chart),
+            (self.show_cores, SidePane::Cores),
+            (self.show_numa, SidePane::Numa),
+            (self.show_memory, SidePane::Memory),
+            (self.show_runtime, SidePane::Runtime),
+            (self.show_power, SidePane::Power),
+            (self.show_log, SidePane::Log),
+        ]
+        .into_iter()
+        .filter_map(|(shown, pane)| shown.then_some(pane))
+        .collect()
+    }
+
+    /// What the table says in place of rows when it has none.
+    fn empty_table_text(&self) -> Vec<Line<'static>> {
+        if let Some(e) = &self.filter_error {
+            return vec![
+                Line::from("The search is incomplete"),
+                Line::from(e.clone()),
+            ];
+        }
+        let searches: Vec<_> = self.filter_chain().collect();
+        if !searches.is_empty() {
+            return vec![
+                Line::from(format!("No process matches {}", searches.join(" › "))),
+                Line::from(format!(
+                    "{} drops the last search, {} all of them",
+                    self.keys.map.describe(keymap::Action::PopFilter),
+                    self.keys.map.describe(keymap::Action::ClearFilters)
+                )),
+            ];
+        }
+        if self.unfiltered.is_empty() {
+            return vec![Line::from("Reading the processes…")];
+        }
+        vec![Line::from(
+            "No process passes the owner, state and terminal filters",
+        )]
+    }
+
+    fn shown_columns(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .filter(|&&(column, shown)| {
+                shown
+                    && match column {
+                        Column::Node => self.numa.is_some(),
+                        Column::Tags => self.has_tags(),
+                        _ => true,
+                    }
+            })
+            .map(|&(column, _)| column.index())
+            .collect()
+    }
+
+    /// Switches to `theme`, keeping the current one if it is unknown or has a bad color.
+    pub(crate) fn set_theme(&mut self, theme: Theme) -> Result<(), String> {
+        self.colors = theme.colors(&self.themes)?;
+        self.theme = theme;
+        Ok(())
+    }
+
+    /// Switches to the next palette, or custom theme after the last.
+    pub(crate) fn next_theme(&mut self) {
+        let next = self.theme.next(&self.themes);
+        self.notice = Some(match self.set_theme(next) {
+            Ok(()) => format!("Palette: {}", self.theme),
+            Err(e) => e,
+        });
+    }
+
+    /// How many processes the searches and the owner and state filters leave, and the searches,
+    /// while there is one.
+    fn match_count(&self) -> Option<String> {
+        let searches: Vec<_> = self.filter_chain().collect();
+        if searches.is_empty()
+            && self.owner_filter.is_none()
+            && !self.problems_only
+            && self.terminal_filter.is_none()
+            || self.filter_error.is_some()
+        {
+            return None;
+        }
+        let total = self.unfiltered.len();
+        let mut count = format!(
+            "{} of {total} process{} match",
+            self.items.len(),
+            if total == 1 { "" } else { "es" }
+        );
+        if !searches.is_empty() {
+            count.push_str(&format!(" {}", searches.join(" › ")));
+        }
+        if let Some(user) = &self.owner_filter {
+            count.push_str(&format!(", owned by {user}"));
+        }
+        if self.problems_only {
+            count.push_str(", in problem states");
+        }
+        if self.terminal_filter.is_some() {
+            count.push_str(", started from this terminal");
+        }
+        Some(count)
+    }
+
+    /// Age of the displayed data, colored when updates are overdue or paused.
+    fn staleness(&self) -> Span<'static> {
+        if let Some(replay) = &self.replay {
+            return Span::raw(format!(
+                " {} of {}, {} ",
+                replay.position + 1,
+                replay.count(),
+                details::format_timestamp(replay.current().timestamp)
+            ))
+            .fg(self.colors.rising_fg);
+        }
+        let age = self.updated_at.elapsed();
+        let text = if age.as_secs() < 60 {
+            format!(" updated {:.1}s ago ", age.as_secs_f64())
+        } else {
+            format!(
+                " updated {}m{}s ago ",
+                age.as_secs() / 60,
+                age.as_secs() % 60
+            )
+        };
+        let overdue = self
+            .expected_interval
+            .map(|interval| age.as_secs_f64() / interval.as_secs_f64());
+        let (text, fg) = match overdue {
+            _ if self.paused => (format!(" paused,{text}"), self.colors.rising_fg),
+            _ if self.held.is_some() => (format!(" held,{text}"), self.colors.footer_border_color),
+            // Slowed down on purpose, so not overdue.
+            _ if self.idle.is_idle() => (format!(" idle,{text}"), self.colors.footer_border_color),
+            Some(ratio) if ratio > 5.0 => (format!(" stalled,{text}"), self.colors.critical_fg),
+            Some(ratio) if ratio > 2.0 => (text, self.colors.rising_fg),
+            _ => (text, self.colors.footer_border_color),
+        };
+        Span::raw(text).fg(fg)
+    }
+
+    /// The usage of every core by the whole machine, then where the selected process ran.
+    fn render_cores(&self, frame: &mut Frame, area: Rect) {
+        let selected = self.selected_process();
+        let title = match selected {
+            Some(data) => format!("Cores: {} ({})", data.name, data.pid),
+            None => "Cores".to_string(),
+        };
+        let block = self
+            .popup_block("")
+            .title(title)
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+        let bar_width = block.inner(area).width.saturating_sub(12) as usize;
+        let bar = |share: f64| {
+            let filled = ((share * bar_width as f64).round() as usize).min(bar_width);
+            format!("{}{}", "█".repeat(filled), "░".repeat(bar_width - filled))
+        };
+        let heading = |text: String| Line::from(text).fg(self.colors.header_fg).bold();
+
+        let mut lines = Vec::new();
+        if let Some(summary) = &self.summary {
+            lines.push(heading(format!("All processes, {:.0}%", summary.cpu())));
+            for (core, &usage) in summary.cores.iter().enumerate() {
+                let fg = if usage >= BUSY_CORE {
+                    self.colors.critical_fg
+                } else {
+                    self.colors.selected_style_fg
+                };
+                lines.push(
+                    Line::from(format!(
+                        "cpu{core:<3} {} {usage:>3.0}%",
+                        bar(f64::from(usage) / 100.0)
+                    ))
+                    .fg(fg),
+                );
+            }
+            // A single busy core hides in the average of many idle ones.
+            let busy: Vec<String> = (summary.cores.iter().enumerate())
+                .filter(|&(_, &usage)| usage >= BUSY_CORE)
+                .map(|(core, _)| format!("cpu{core}"))
+                .collect();
+            if !busy.is_empty() && busy.len() * 2 <= summary.cores.len() {
+                lines.push(Line::from(format!(
+                    "{} busy while the machine is at {:.0}%",
+                    busy.join(", "),
+                    summary.cpu()
+                )));
+            }
+        }
+        let Some(data) = selected else {
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+            return;
+        };
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(heading(format!(
+            "{} ({}), last ten seconds",
+            data.name, data.pid
+        )));
+
+        let cores = self.ctx.cpus().len();
+        let allowed = affinity::allowed_cores(data.pid);
+        lines.extend(
+            self.core_heat
+                .heat(cores)
+                .into_iter()
+                .enumerate()
+                .map(|(core, share)| {
+                    let fg = if allowed.as_ref().is_some_and(|a| !a.contains(&core)) {
+                        self.colors.footer_border_color
+                    } else if share >= 0.5 {
+                        self.colors.critical_fg
+                    } else {
+                        self.colors.selected_style_fg
+                    };
+                    Line::from(format!(
+                        "cpu{core:<3} {} {:>3.0}%",
+                        bar(share),
+                        share * 100.0
+                    ))
+                    .fg(fg)
+                }),
+        );
+
+        if let Some(allowed) = &allowed {
+            lines.push(Line::from(format!(
+                "Allowed: {} ({} of {cores})",
+                affinity::format_cores(allowed),
+                allowed.len()
+            )));
+        }
+        let cpu_usage = data.cpu_usage;
+        if let Some(core) = self.core_heat.saturated_core(cores, cpu_usage) {
+            if allowed.as_ref().is_some_and(|a| a.len() < cores) {
+                lines.push(
+                    Line::from(format!(
+                        "cpu{core} is saturated by this process. Press A to spread its affinity."
+                    ))
+                    .fg(self.colors.critical_fg),
+                );
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn render_numa(&self, frame: &mut Frame, area: Rect) {
+        let selected = self.selected_process();
+        let title = match selected {
+            Some(data) => format!("NUMA: {} ({})", data.name, data.pid),
+            None => "NUMA".to_string(),
+        };
+        let block = self
+            .popup_block("")
+            .title(title)
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+        let (Some(topology), Some(data)) = (&self.numa, selected) else {
+            let text = match self.numa {
+                Some(_) => "",
+                None => "This is not a NUMA system.",
+            };
+            frame.render_widget(Paragraph::new(text).block(block), area);
+            return;
+        };
+
+        let memory = numa::memory_per_node(data.pid);
+        let total = memory.values().sum::<u64>().max(1);
+        let bar_width = block.inner(area).width.saturating_sub(20) as usize;
+        let mut lines: Vec<Line> = Vec::new();
+        for node in &topology.nodes {
+            let bytes = memory.get(&node.id).copied().unwrap_or(0);
+            let filled = (bytes as f64 / total as f64 * bar_width as f64).round() as usize;
+            lines.push(Line::from(format!(
+                "node{:<3} {}{} {:>12}",
+                node.id,
+                "█".repeat(filled),
+                "░".repeat(bar_width - filled),
+                self.units.memory(bytes)
+            )));
+            lines.push(
+                Line::from(format!(
+                    "        cores {}",
+                    affinity::format_cores(&node.cores)
+                ))
+                .fg(self.colors.footer_border_color),
+            );
+        }
+
+        // The node whose cores the process was seen on most recently.
+        let heat = self.core_heat.heat(self.ctx.cpus().len());
+        let mut node_heat: HashMap<usize, f64> = HashMap::new();
+        for (core, share) in heat.into_iter().enumerate() {
+            if let Some(node) = topology.node_of_core(core) {
+                *node_heat.entry(node).or_insert(0.0) += share;
+            }
+        }
+        let cpu_node = node_heat
+            .into_iter()
+            .filter(|&(_, share)| share > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(node, _)| node);
+        if let Some(hint) = cpu_node.and_then(|node| numa::migration_hint(data.pid, &memory, node))
+        {
+            lines.push(Line::from(""));
+            lines.push(Line::from(hint).fg(self.colors.critical_fg));
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// CPU and memory of the last refreshes, for the detail view. Memory is drawn above its lowest
+    /// value so that slow growth stands out.
+    fn render_sparklines(&self, frame: &mut Frame, area: Rect, history: &history::History) {
+        let width = usize::from(area.width);
+        let cpu: Vec<u64> = history
+            .cpu
+            .iter()
+            .skip(history.cpu.len().saturating_sub(width))
+            .map(|&cpu| (cpu * 10.0) as u64)
+            .collect();
+        let memory: Vec<u64> = history
+            .memory
+            .iter()
+            .skip(history.memory.len().saturating_sub(width))
+            .copied()
+            .collect();
+        let low = memory.iter().copied().min().unwrap_or(0);
+        let high = memory.iter().copied().max().unwrap_or(0);
+        let peak = history.cpu.iter().copied().fold(0.0, f32::max);
+
+        let [cpu_label, cpu_area, memory_label, memory_area, _] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        frame.render_widget(
+            Line::from(vec![
+                "CPU ".fg(self.colors.header_fg).bold(),
+                format!(
+                    "now {}, peak {}",
+                    self.units.cpu(history.cpu.back().copied().unwrap_or(0.0)),
+                    self.units.cpu(peak)
+                )
+                .into(),
+            ]),
+            cpu_label,
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .data(&cpu)
+                .style(Style::new().fg(self.colors.rising_fg)),
+            cpu_area,
+        );
+        frame.render_widget(
+            Line::from(vec![
+                "Memory ".fg(self.colors.header_fg).bold(),
+                format!(
+                    "now {}, between {} and {}",
+                    self.units.memory(memory.last().copied().unwrap_or(0)),
+                    self.units.memory(low),
+                    self.units.memory(high)
+                )
+                .into(),
+            ]),
+            memory_label,
+        );
+        let above_low: Vec<u64> = memory.iter().map(|&bytes| bytes - low).collect();
+        frame.render_widget(
+            Sparkline::default()
+                .data(&above_low)
+                .style(Style::new().fg(self.colors.header_fg)),
+            memory_area,
+        );
+    }
+
+    fn render_runtime(&self, frame: &mut Frame, area: Rect) {
+        let selected = self.selected_process();
+        let title = match selected {
+            Some(data) => format!("Runtime: {} ({})", data.name, data.pid),
+            None => "Runtime".to_string(),
+        };
+        let block = self
+            .popup_block("")
+            .title(title)
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+
+        let mut lines: Vec<Line> = Vec::new();
+        match selected.and_then(|data| tools::Runtime::detect(&data.name, &data.exe)) {
+            Some(runtime) => {
+                lines.push(Line::from(format!("Detected {}", runtime.label())));
+                let actions = tools::available(runtime);
+                if actions.is_empty() {
+                    let programs: Vec<&str> = tools::ACTIONS
+                        .iter()
+                        .filter(|action| action.runtime == runtime)
+                        .map(|action| action.program)
+                        .collect();
+                    lines.push(Line::from(format!(
+                        "Install {} for runtime actions.",
+                        programs.join(" or ")
+                    )));
+                }
+                for (i, action) in actions.iter().enumerate() {
+                    lines.push(Line::from(vec![
+                        format!("{}", i + 1).bold(),
+                        format!(" {} ({})", action.label, action.program).into(),
+                    ]));
+                }
+            }
+            None => lines.push(Line::from("No JVM or CPython runtime detected.")),
+        }
+
+        if let Some(output) = &self.tool_output {
+            lines.push(Line::from(""));
+            lines.push(Line::from(output.title.as_str()).fg(self.colors.footer_border_color));
+            lines.extend(output.text.lines().map(Line::from));
+        }
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn render_memory(&self, frame: &mut Frame, area: Rect) {
+        let selected = self.selected_process();
+        let title = match selected {
+            Some(data) => format!("Memory: {} ({})", data.name, data.pid),
+            None => "Memory".to_string(),
+        };
+        let block = self
+            .popup_block("")
+            .title(title)
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+        let size = |bytes: u64| self.units.memory(bytes);
+
+        let mut lines: Vec<Line> = Vec::new();
+        if let Some(data) = selected {
+            lines.push(Line::from(format!(
+                "Resident:            {}",
+                size(data.memory)
+            )));
+            match hugepages::ProcessHugePages::read(data.pid) {
+                Some(huge) => {
+                    lines.push(Line::from(format!(
+                        "Transparent huge:    {}",
+                        size(huge.transparent)
+                    )));
+                    lines.push(Line::from(format!(
+                        "hugetlbfs:           {}",
+                        size(huge.explicit)
+                    )));
+                }
+                None => lines.push(Line::from("Huge page usage unavailable")),
+            }
+            lines.push(Line::from(""));
+        }
+
+        match hugepages::Pool::read() {
+            Some(pool) => {
+                lines.push(
+                    Line::from(format!("Huge page pool ({} pages)", size(pool.page_size)))
+                        .fg(self.colors.footer_border_color),
+                );
+                lines.push(Line::from(format!(
+                    "Total {}  Free {}  Reserved {}  Surplus {}",
+                    pool.total, pool.free, pool.reserved, pool.surplus
+                )));
+                lines.push(Line::from(format!(
+                    "In use:              {}",
+                    size(pool.used())
+                )));
+                lines.push(Line::from(""));
+                lines.push(
+                    Line::from(format!(
+                        "Transparent huge pages ({})",
+                        pool.transparent_mode.as_deref().unwrap_or("unknown")
+                    ))
+                    .fg(self.colors.footer_border_color),
+                );
+                lines.push(Line::from(format!(
+                    "Anonymous in use:    {}",
+                    size(pool.transparent)
+                )));
+            }
+            None => lines.push(Line::from("Huge page pool status unavailable")),
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// The charge and drain of every battery, and the processes that used the most CPU lately.
+    fn render_power(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block("")
+            .title("Battery")
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+        let bar_width = block.inner(area).width.saturating_sub(12) as usize;
+        let power = power::read();
+
+        let mut lines: Vec<Line> = Vec::new();
+        if power.batteries.is_empty() {
+            lines.push(Line::from(if cfg!(target_os = "linux") {
+                "No battery found"
+            } else {
+                "Batteries are only read on Linux"
+            }));
+        }
+        for battery in &power.batteries {
+            let mut text = format!("{} {}", battery.name, battery.status.to_lowercase());
+            if let Some(watts) = battery.power.filter(|&watts| watts > 0.0) {
+                text.push_str(&format!(", {watts:.1} W"));
+            }
+            if let Some(seconds) = battery.time_left {
+                let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+                text.push_str(&if battery.discharging() {
+                    format!(", {hours}h {minutes:02}m left")
+                } else {
+                    format!(", full in {hours}h {minutes:02}m")
+                });
+            }
+            lines.push(Line::from(text));
+            if let Some(capacity) = battery.capacity {
+                let filled =
+                    ((capacity / 100.0 * bar_width as f64).round() as usize).min(bar_width);
+                let fg = if battery.discharging() && capacity < 20.0 {
+                    self.colors.critical_fg
+                } else {
+                    self.colors.selected_style_fg
+                };
+                lines.push(
+                    Line::from(format!(
+                        "{}{} {capacity:>3.0}%",
+                        "█".repeat(filled),
+                        "░".repeat(bar_width - filled)
+                    ))
+                    .fg(fg),
+                );
+            }
+        }
+        match power.on_mains {
+            Some(true) => lines.push(Line::from("Plugged in")),
+            Some(false) => lines.push(Line::from("On battery")),
+            None => {}
+        }
+
+        lines.push(Line::from(""));
+        lines.push(
+            Line::from(format!(
+                "Most CPU over the last {} refreshes",
+                power::WINDOW
+            ))
+            .fg(self.colors.header_fg)
+            .bold(),
+        );
+        let drainers = power::drainers(&self.unfiltered, &self.history, 10);
+        if drainers.is_empty() {
+            lines.push(Line::from("Nothing busy"));
+        }
+        for (data, cpu) in drainers {
+            lines.push(Line::from(format!(
+                "{:>7}  {} ({})",
+                self.units.cpu(cpu),
+                data.name,
+                data.pid
+            )));
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// The latest lines of the log that fit, the time of day in front instead of the full
+    /// timestamp.
+    fn render_log(&self, frame: &mut Frame, area: Rect) {
+        let title = match logging::file_directory() {
+            Some(directory) => format!("Log, {}", directory.display()),
+            None => "Log, not written to a file".to_string(),
+        };
+        let block = self
+            .popup_block("")
+            .title(title)
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+        let height = usize::from(block.inner(area).height);
+        let recent = logging::recent();
+        let mut lines: Vec<Line> = recent
+            .iter()
+            .rev()
+            .take(height)
+            .rev()
+            .map(|line| {
+                let (timestamp, rest) = line.split_once(' ').unwrap_or(("", line));
+                let time = timestamp.get(11..19).unwrap_or(timestamp);
+                let rest = rest.trim_start();
+                let line = Line::from(format!("{time} {rest}"));
+                if rest.starts_with("WARN") || rest.starts_with("ERROR") {
+                    line.fg(self.colors.critical_fg)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("Nothing logged yet, see log_level"));
+        }
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn render_sched_picker(&self, frame: &mut Frame, area: Rect) {
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        let title = format!("Scheduling of {} ({})", data.name, data.pid);
+        let block = self.popup_block(&title);
+        let mut lines: Vec<Line> = sched::PICKER
+            .iter()
+            .enumerate()
+            .map(|(i, (policy, description))| {
+                let chosen = i == self.sched_choice;
+                let line = Line::from(format!(
+                    "{} {:<5} {description}",
+                    if chosen { ">" } else { " " },
+                    policy.name()
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        lines.push(Line::default());
+        if sched::PICKER[self.sched_choice].0.realtime() {
+            lines.push(Line::from(vec![
+                "  Priority (1-99): ".into(),
+                self.input.as_str().fg(Color::Yellow),
+            ]));
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(
+            "j/k to choose, type a priority for FIFO and RR, Enter to apply, Esc to cancel",
+        ));
+        let area = centered_rect(60, 100, area);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.y + (area.height - height) / 2,
+            height,
+            ..area
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn render_ionice_picker(&self, frame: &mut Frame, area: Rect) {
+        let Some(data) = self.selected_process() else {
+            return;
+        };
+        let title = format!("I/O priority of {} ({})", data.name, data.pid);
+        let block = self.popup_block(&title);
+        let mut lines: Vec<Line> = ionice::PICKER
+            .iter()
+            .enumerate()
+            .map(|(i, (class, description))| {
+                let chosen = i == self.ionice_choice;
+                let line = Line::from(format!(
+                    "{} {:<11} {description}",
+                    if chosen { ">" } else { " " },
+                    class.name()
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        lines.push(Line::default());
+        if ionice::PICKER[self.ionice_choice].0.has_level() {
+            lines.push(Line::from(vec![
+                "  Level (0-7, 0 first): ".into(),
+                self.input.as_str().fg(Color::Yellow),
+            ]));
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(
+            "j/k to choose, type a level for realtime and best-effort, Enter to apply, Esc to cancel",
+        ));
+        let area = centered_rect(60, 100, area);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.y + (area.height - height) / 2,
+            height,
+            ..area
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn render_priority_editor(&self, frame: &mut Frame, area: Rect) {
+        let (Some(data), Some(edit)) = (self.selected_process(), &self.priority) else {
+            return;
+        };
+        let title = format!("Priority of {} ({})", data.name, data.pid);
+        let block = self.popup_block(&title);
+        let nice = match &edit.from {
+            Ok(from) if *from == edit.nice => renice::describe(*from),
+            Ok(from) => format!(
+                "{} → {} ({} priority)",
+                renice::describe(*from),
+                renice::describe(edit.nice),
+                if edit.nice < *from { "higher" } else { "lower" }
+            ),
+            Err(e) => format!("cannot be read: {e}"),
+        };
+        let mut lines = vec![
+            Line::from(format!("  {:<7}{nice}", format!("{}:", renice::LABEL))),
+            Line::default(),
+        ];
+        lines.push(match &edit.cores {
+            Some(cores) => Line::from(vec![
+                "  Cores: ".into(),
+                self.input.as_str().fg(Color::Yellow),
+                format!("   (now {})", affinity::format_cores(cores)).into(),
+            ]),
+            None => Line::from("  Cores: not available here"),
+        });
+        lines.push(Line::default());
+        lines.push(Line::from(
+            "Up/Down for a higher or lower priority, edit the cores (e.g. 0-3,8), Enter to apply, \
+             Esc to cancel",
+        ));
+        let area = centered_rect(60, 100, area);
+        let height = (lines.len() as u16 + 3).min(area.height);
+        let area = Rect {
+            y: area.y + (area.height - height) / 2,
+            height,
+            ..area
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn render_column_picker(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block(i18n::text("popup.columns"))
+            .title_bottom(" j/k to choose, Space to show or hide, J/K to move, w to save ");
+        let providers = plugin::providers();
+        let lines: Vec<Line> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, &(column, shown))| {
+                let chosen = i == self.column_choice;
+                let line = Line::from(format!(
+                    "{} [{}] {}",
+                    if chosen { ">" } else { " " },
+                    if shown { "x" } else { " " },
+                    title(column.index(), &providers)
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let area = centered_rect(60, 100, area);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.y + (area.height - height) / 2,
+            height,
+            ..area
+        };
+        // Keep the cursor in view when the list is taller than the popup.
+        let visible = height.saturating_sub(2) as usize;
+        let scroll = self.column_choice.saturating_sub(visible.saturating_sub(1));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false })
+                .scroll((scroll as u16, 0)),
+            area,
+        );
+    }
+
+    fn render_queue(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block(i18n::text("popup.pending"))
+            .title_bottom(" j/k to choose, x to call off, Esc to close ");
+        let now = Instant::now();
+        let mut lines: Vec<Line> = self
+            .queue
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let chosen = i == self.queue_choice;
+                let line = Line::from(format!(
+                    "{} {}",
+                    if chosen { ">" } else { " " },
+                    entry.describe(now)
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        if self.queue.is_empty() {
+            lines.push(Line::from(
+                "Nothing pending. l in the kill confirmation schedules a kill.",
+            ));
+        }
+        let area = centered_rect(60, 100, area);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.y + (area.height - height) / 2,
+            height,
+            ..area
+        };
+        let visible = height.saturating_sub(2) as usize;
+        let scroll = self.queue_choice.saturating_sub(visible.saturating_sub(1));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false })
+                .scroll((scroll as u16, 0)),
+            area,
+        );
+    }
+
+    fn render_watchdog(&self, frame: &mut Frame, area: Rect) {
+        let title = if self.watchdog.dry_run {
+            "Watchdog (dry run)"
+        } else {
+            "Watchdog"
+        };
+        let block = self.popup_block(title).title_bottom(
+            " j/k to choose, a to add a rule, e to edit it, x to remove it, d for dry run, Esc to close ",
+        );
+        let mut lines: Vec<Line> = self
+            .watchdog
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let chosen = i == self.watchdog_choice;
+                let line = Line::from(format!(
+                    "{} {}",
+                    if chosen { ">" } else { " " },
+                    rule.describe()
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        if self.watchdog.rules.is_empty() {
+            lines.push(Line::from("No rules. Press a to add one."));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from("Log".fg(self.colors.header_fg).bold()));
+        let log = self.watchdog.log();
+        if log.is_empty() {
+            lines.push(Line::from("  Nothing triggered yet"));
+        }
+        // The latest first, as many as fit.
+        for trigger in log.iter().rev() {
+            lines.push(Line::from(format!(
+                "  {} {} ({}): {}, {}",
+                details::format_timestamp(trigger.timestamp),
+                trigger.name,
+                trigger.pid,
+                trigger.outcome,
+                trigger.rule
+            )));
+        }
+        let area = centered_rect(70, 60, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// What the status line said this session, the latest first, with failures in the critical
+    /// color.
+    fn render_outcomes(&self, frame: &mut Frame, area: Rect) {
+        let count = self.outcomes.len();
+        let title = format!(
+            "History, {count} message{}, {} failed",
+            if count == 1 { "" } else { "s" },
+            self.outcomes.failures()
+        );
+        let block = self
+            .popup_block(&title)
+            .title_bottom(" c to clear, Esc to close ");
+        let mut lines: Vec<Line> = self
+            .outcomes
+            .latest_first()
+            .map(|outcome| {
+                let line = Line::from(format!(
+                    "  {}  {}",
+                    details::format_timestamp(outcome.shown),
+                    outcome.message
+                ));
+                if outcome.failed {
+                    line.fg(self.colors.critical_fg)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("  Nothing has been done yet"));
+        }
+        let area = centered_rect(80, 60, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((self.outcomes_scroll, 0)),
+            area,
+        );
+    }
+
+    /// The audit log of the signals sent, the latest first.
+    fn render_audit(&self, entries: &[audit::Entry], frame: &mut Frame, area: Rect) {
+        let failed = entries.iter().filter(|entry| !entry.sent()).count();
+        let title = format!(
+            "Signals sent, {} logged, {failed} not delivered",
+            entries.len()
+        );
+        let block = self
+            .popup_block(&title)
+            .title_bottom(" j/k to scroll, Esc to close ");
+        let mut lines: Vec<Line> = entries
+            .iter()
+            .rev()
+            .map(|entry| {
+                let line = Line::from(format!("  {}", audit::describe(entry)));
+                if entry.sent() {
+                    line
+                } else {
+                    line.fg(self.colors.critical_fg)
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("  No signal has been sent yet"));
+        }
+        let area = centered_rect(80, 60, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((self.audit_scroll, 0)),
+            area,
+        );
+    }
+
+    /// The exit log as sorted and searched, one process per line.
+    fn render_exits(&self, frame: &mut Frame, area: Rect) {
+        let mut title = format!("Exited processes, {}", self.exits_sort.describe());
+        if !self.exits_filter.is_empty() {
+            title.push_str(&format!(", named {}", self.exits_filter));
+        }
+        let block = self
+            .popup_block(&title)
+            .title_bottom(" s to sort, / to search, c to clear, Esc to close ");
+        let listed = self.exits.listed(&self.exits_filter, self.exits_sort);
+        let mut lines = vec![Line::from(format!(
+            "  {:<23} {:<16} {:>8}  {:<10} {:>8} {:>10}  {}",
+            "FOUND GONE", "NAME", "PID", "USER", "LIVED", "PEAK CPU", "PEAK MEMORY"
+        ))
+        .fg(self.colors.header_fg)
+        .bold()];
+        if listed.is_empty() {
+            lines.push(Line::from(if self.exits.len() == 0 {
+                "  No process has exited yet"
+            } else {
+                "  No exited process has that name"
+            }));
+        }
+        for exit in listed {
+            let lifetime = exit
+                .lifetime
+                .map_or_else(|| "-".to_string(), exits::format_lifetime);
+            let mut line = format!(
+                "  {:<23} {:<16} {:>8}  {:<10} {:>8} {:>9.1}%  {}",
+                details::format_timestamp(exit.detected),
+                exit.name,
+                exit.key.pid,
+                exit.user,
+                lifetime,
+                exit.peak_cpu,
+                self.units.memory(exit.peak_memory)
+            );
+            if let Some(reason) = &exit.reason {
+                line.push_str(&format!(", {reason}"));
+            }
+            lines.push(Line::from(line));
+        }
+        let area = centered_rect(90, 70, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((self.exits_scroll, 0)),
+            area,
+        );
+    }
+
+    /// The Overview tab of the detail view: what sysinfo knows and the signals sent.
+    fn details_overview(&self, details: &details::Details) -> Vec<Line<'static>> {
+        let mut lines: Vec<Line> = details
+            .fields
+            .iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    format!("{label:<13}").fg(self.colors.header_fg).bold(),
+                    value.clone().into(),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from("Signals sent".fg(self.colors.header_fg).bold()));
+        let sent: Vec<_> = self
+            .sent_signals
+            .get(&details.key())
+            .into_iter()
+            .flatten()
+            .collect();
+        if sent.is_empty() {
+            lines.push(Line::from("  none this session"));
+        }
+        for sent in sent {
+            let ago = sent.at.elapsed().as_secs();
+            let mut spans = vec![Span::raw(format!(
+                "  {} at {}, {} ago",
+                sent.signal,
+                details::format_timestamp(sent.timestamp),
+                if ago < 60 {
+                    format!("{ago}s")
+                } else {
+                    format!("{}m {}s", ago / 60, ago % 60)
+                }
+            ))];
+            if let Some(due) = sent.kill_due {
+                let left = due.saturating_duration_since(Instant::now());
+                spans.push(
+                    format!(", SIGKILL follows in {}s unless it exits", left.as_secs())
+                        .fg(self.colors.rising_fg),
+                );
+            }
+            lines.push(Line::from(spans));
+        }
+        lines
+    }
+
+    /// The Environment tab of the detail view: the variables matching the search, secrets hidden
+    /// until revealed.
+    fn details_environment(&self, details: &details::Details) -> Vec<Line<'static>> {
+        if details.environ.is_empty() {
+            return vec![Line::from(
+                "Not readable; the environment of other users' processes takes root",
+            )];
+        }
+        let typing = matches!(self.mode, AppState::EnvSearchMode);
+        let search = if typing {
+            &self.input
+        } else {
+            &self.env_search
+        };
+        let needle = search.to_lowercase();
+        let shown: Vec<&str> = details
+            .environ
+            .iter()
+            .map(|variable| variable.shown(self.env_revealed))
+            .filter(|text| text.to_lowercase().contains(&needle))
+            .collect();
+        let hidden = details
+            .environ
+            .iter()
+            .filter(|variable| variable.hidden.is_some())
+            .count();
+        let mut summary = format!("{} of {} variables", shown.len(), details.environ.len());
+        match (hidden, self.env_revealed) {
+            (0, _) => {}
+            (_, false) => summary.push_str(&format!(", {hidden} with secrets hidden (r to show)")),
+            (_, true) => summary.push_str(&format!(", {hidden} with secrets shown (r to hide)")),
+        }
+        let search_line = match (typing, search.is_empty()) {
+            (true, _) => Line::from(format!("Search: {search}▏")).fg(Color::Yellow),
+            (false, true) => Line::from("/ to search"),
+            (false, false) => Line::from(format!("Search: {search} (/ to change)")),
+        };
+        let mut lines = vec![search_line, Line::from(summary), Line::from("")];
+        lines.extend(shown.into_iter().map(|text| {
+            let (name, value) = text.split_once('=').unwrap_or((text, ""));
+            Line::from(vec![
+                name.to_string().fg(self.colors.header_fg).bold(),
+                format!("={value}").into(),
+            ])
+        }));
+        lines
+    }
+
+    /// The Files tab of the detail view: the open file descriptors, with what sockets connect.
+    fn details_files(&self, details: &details::Details) -> Vec<Line<'static>> {
+        let Some(files) = &details.files else {
+            return vec![Line::from(if cfg!(target_os = "linux") {
+                "Not readable; the open files of other users' processes take root"
+            } else {
+                "Open files are only listed on Linux"
+            })];
+        };
+        let count = |label| {
+            files
+                .iter()
+                .filter(|descriptor| descriptor.target.label() == label)
+                .count()
+        };
+        let mut lines = vec![
+            Line::from(format!(
+                "{} open: {} files, {} sockets, {} pipes, {} other",
+                files.len(),
+                count("file"),
+                count("socket"),
+                count("pipe"),
+                count("other")
+            )),
+            Line::from(""),
+            Line::from(format!("{:>5}  {:<7} {}", "FD", "TYPE", "TARGET"))
+                .fg(self.colors.header_fg)
+                .bold(),
+        ];
+        lines.extend(files.iter().map(|descriptor| {
+            Line::from(format!(
+                "{:>5}  {:<7} {}",
+                descriptor.fd,
+                descriptor.target.label(),
+                descriptor.target.text()
+            ))
+        }));
+        lines
+    }
+
+    /// The Threads tab of the detail view: every thread with its CPU usage and state.
+    fn details_threads(&self, details: &details::Details) -> Vec<Line<'static>> {
+        let threads = &details.threads;
+        if threads.list.is_empty() {
+            return vec![Line::from(if cfg!(target_os = "linux") {
+                "No threads; the process has exited"
+            } else {
+                "Threads are only listed on Linux"
+            })];
+        }
+        let mut lines = vec![
+            Line::from(format!(
+                "{} threads, j/k to choose, s to signal the chosen one",
+                threads.list.len()
+            )),
+            Line::from(""),
+            Line::from(format!(
+                "{:>8}  {:<16} {:>7}  {}",
+                "TID", "NAME", "CPU", "STATE"
+            ))
+            .fg(self.colors.header_fg)
+            .bold(),
+        ];
+        lines.extend(threads.list.iter().enumerate().map(|(i, thread)| {
+            let cpu = thread
+                .cpu
+                .map_or_else(|| "-".to_string(), |cpu| self.units.cpu(cpu));
+            let line = Line::from(format!(
+                "{:>8}  {:<16} {cpu:>7}  {}",
+                thread.tid, thread.name, thread.state
+            ));
+            match i == threads.choice {
+                true => line.add_modifier(Modifier::REVERSED),
+                false => line,
+            }
+        }));
+        lines
+    }
+
+    /// The suspects, the most likely first, each with the reasons below it.
+    fn render_suspects(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block(i18n::text("popup.suspects"))
+            .title_bottom(" j/k to choose, Enter to select the process, Esc to close ");
+        let suspects = &self.suspects;
+        let mut lines = Vec::new();
+        let mut chosen_line = 0;
+        if suspects.list.is_empty() {
+            lines.push(Line::from(
+                "  Nothing stands out: no process is busy, growing, stuck in disk sleep or \
+                 restarting",
+            ));
+        }
+        for (i, suspect) in suspects.list.iter().enumerate() {
+            let chosen = i == suspects.choice;
+            if chosen {
+                chosen_line = lines.len();
+            }
+            let line = Line::from(format!(
+                "{} {:>2}. {} ({}), {}, score {:.2}",
+                if chosen { ">" } else { " " },
+                i + 1,
+                suspect.name,
+                suspect.key.pid,
+                suspect.user,
+                suspect.score
+            ))
+            .bold();
+            lines.push(if chosen {
+                line.add_modifier(Modifier::REVERSED)
+            } else {
+                line
+            });
+            lines.extend(
+                suspect
+                    .reasons
+                    .iter()
+                    .map(|reason| Line::from(format!("       {reason}"))),
+            );
+        }
+        let area = centered_rect(80, 70, area);
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = chosen_line.saturating_sub(visible.saturating_sub(4));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((scroll as u16, 0)),
+            area,
+        );
+    }
+
+    /// The commands of the processes that came and went between refreshes, the most frequent first.
+    fn render_ephemeral(&self, frame: &mut Frame, area: Rect) {
+        let ephemeral = &self.ephemeral;
+        let title = format!(
+            "{}, {} in the last minute",
+            i18n::text("popup.short_lived"),
+            self.units.numbers.integer(ephemeral.total as u64)
+        );
+        let block = self
+            .popup_block(&title)
+            .title_bottom(" j/k to scroll, Esc to close ");
+        let now = Instant::now();
+        let mut lines = vec![Line::from(format!(
+            "  {:>7}  {:>7}  {:>9}  {:>6}  COMMAND",
+            "RUNS", "PER SEC", "LIFETIME", "LAST"
+        ))
+        .fg(self.colors.header_fg)
+        .bold()];
+        lines.extend(ephemeral.commands.iter().map(|command| {
+            Line::from(format!(
+                "  {:>7}  {:>7}  {:>7}ms  {:>5}s  {}",
+                self.units.numbers.integer(command.count as u64),
+                self.units.numbers.format(command.rate, 2),
+                self.units
+                    .numbers
+                    .integer(command.average_lifetime.as_millis() as u64),
+                now.duration_since(command.last_exited).as_secs(),
+                command.command
+            ))
+        }));
+        if ephemeral.since.is_none() {
+            lines = vec![Line::from(
+                "  Only seen through the proc connector: on Linux, as root (CAP_NET_ADMIN)",
+            )];
+        } else if ephemeral.commands.is_empty() {
+            lines.push(Line::from("  None yet"));
+        }
+        let area = centered_rect(80, 70, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((ephemeral.scroll as u16, 0)),
+            area,
+        );
+    }
+
+    /// The users and names using the most CPU and memory, with how many processes they run.
+    fn render_consumers(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block(i18n::text("popup.consumers"))
+            .title_bottom(" j/k to choose, Enter to search for its processes, Esc to close ");
+        let consumers = &self.consumers;
+        let width = consumers
+            .users
+            .iter()
+            .chain(&consumers.names)
+            .map(|total| total.label.chars().count())
+            .max()
+            .unwrap_or_default()
+            .clamp(8, 32);
+        let mut lines = Vec::new();
+        let mut chosen_line = 0;
+        for (i, (heading, totals)) in [("USER", &consumers.users), ("NAME", &consumers.names)]
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                lines.push(Line::default());
+            }
+            lines.push(
+                Line::from(format!(
+                    "  {heading:width$}  {:>8}  {:>11}  {:>9}",
+                    "CPU", "MEMORY", "PROCESSES"
+                ))
+                .fg(self.colors.header_fg)
+                .bold(),
+            );
+            let first = if i == 0 { 0 } else { consumers.users.len() };
+            for (j, total) in totals.iter().enumerate() {
+                let chosen = first + j == consumers.choice;
+                if chosen {
+                    chosen_line = lines.len();
+                }
+                let label: String = total.label.chars().take(width).collect();
+                let line = Line::from(format!(
+                    "{} {label:width$}  {:>8}  {:>11}  {:>9}",
+                    if chosen { ">" } else { " " },
+                    self.units.cpu(total.cpu),
+                    self.units.memory(total.memory),
+                    self.units.numbers.integer(total.count as u64),
+                ));
+                lines.push(if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                });
+            }
+        }
+        if consumers.is_empty() {
+            lines = vec![Line::from("  No processes yet")];
+        }
+        let area = centered_rect(70, 70, area);
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = chosen_line.saturating_sub(visible.saturating_sub(2));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((scroll as u16, 0)),
+            area,
+        );
+    }
+
+    /// The processes started, exited and changed since the baseline, one per line.
+    fn render_changes(&self, frame: &mut Frame, area: Rect) {
+        let changes = &self.changes;
+        let since = match &changes.baseline {
+            changes::Baseline::Previous => "the last refresh".to_string(),
+            changes::Baseline::Pinned(at) => details::format_timestamp(*at),
+            changes::Baseline::File(path, at) => {
+                format!("{} ({})", path.display(), details::format_timestamp(*at))
+            }
+        };
+        let title = format!(
+            "Since {since}: {} started, {} exited, {} changed",
+            changes.count(changes::Kind::Started),
+            changes.count(changes::Kind::Exited),
+            changes.count(changes::Kind::Changed)
+        );
+        let pin = match changes.baseline {
+            changes::Baseline::Previous => "b to compare with this refresh",
+            _ => "b to compare with the last refresh",
+        };
+        let block = self.popup_block(&title).title_bottom(format!(
+            " j/k to choose, Enter to select the process, {pin}, Esc to close "
+        ));
+        let mut lines = vec![Line::from(format!(
+            "  {:<8} {:>8} {:<16} {:<10} {:>15}  {}",
+            "", "PID", "NAME", "USER", "CPU", "MEMORY"
+        ))
+        .fg(self.colors.header_fg)
+        .bold()];
+        if changes.list.is_empty() {
+            lines.push(Line::from("  Nothing started, exited or changed much"));
+        }
+        for (i, change) in changes.list.iter().enumerate() {
+            let (cpu, memory) = match change.kind {
+                changes::Kind::Started => (
+                    format!("{:.1}%", change.cpu.1),
+                    self.units.memory(change.memory.1),
+                ),
+                changes::Kind::Exited => (
+                    format!("{:.1}%", change.cpu.0),
+                    self.units.memory(change.memory.0),
+                ),
+                changes::Kind::Changed => (
+                    format!("{:.1}% → {:.1}%", change.cpu.0, change.cpu.1),
+                    format!(
+                        "{} → {}",
+                        self.units.memory(change.memory.0),
+                        self.units.memory(change.memory.1)
+                    ),
+                ),
+            };
+            let chosen = i == changes.choice;
+            let line = Line::from(format!(
+                "{} {:<8} {:>8} {:<16} {:<10} {:>15}  {}",
+                if chosen { ">" } else { " " },
+                change.kind.label(),
+                change.key.pid,
+                change.name,
+                change.user,
+                cpu,
+                memory
+            ));
+            let line = match change.kind {
+                changes::Kind::Started => line.fg(self.colors.rising_fg),
+                changes::Kind::Exited => line.fg(self.colors.footer_border_color),
+                changes::Kind::Changed => line,
+            };
+            lines.push(if chosen {
+                line.add_modifier(Modifier::REVERSED)
+            } else {
+                line
+            });
+        }
+        let area = centered_rect(90, 70, area);
+        let visible = area.height.saturating_sub(3) as usize;
+        let scroll = changes.choice.saturating_sub(visible.saturating_sub(1));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((scroll as u16, 0)),
+            area,
+        );
+    }
+
+    fn render_hunt(&self, hunt: &hunt::Hunt, frame: &mut Frame, area: Rect) {
+        let title = match &hunt.hits {
+            None => format!("Looking for {:?}…", hunt.needle),
+            Some(hits) if hunt.truncated => {
+                format!("{:?}: the first {} places", hunt.needle, hits.len())
+            }
+            Some(hits) => format!("{:?}: {} places", hunt.needle, hits.len()),
+        };
+        let block = self.popup_block(&title).title_bottom(
+            " j/k to choose, Enter to select the process, / to find again, Esc to close ",
+        );
+        let mut lines = vec![Line::from(format!(
+            "  {:>8} {:<16} {:<12} {}",
+            "PID", "NAME", "WHERE", "WHAT"
+        ))
+        .fg(self.colors.header_fg)
+        .bold()];
+        match &hunt.hits {
+            None => lines.push(Line::from("  Reading every process…")),
+            Some(hits) if hits.is_empty() => lines.push(Line::from("  Found nowhere")),
+            Some(hits) => lines.extend(hits.iter().enumerate().map(|(i, hit)| {
+                let chosen = i == hunt.choice;
+                let line = Line::from(format!(
+                    "{} {:>8} {:<16} {:<12} {}",
+                    if chosen { ">" } else { " " },
+                    hit.pid,
+                    hit.name,
+                    hit.place.label(),
+                    hit.text
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })),
+        }
+        let area = centered_rect(90, 70, area);
+        let visible = area.height.saturating_sub(3) as usize;
+        let scroll = hunt.choice.saturating_sub(visible.saturating_sub(1));
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .scroll((scroll as u16, 0)),
+            area,
+        );
+    }
+
+    /// The line above the table, with the keys to find the others; taken from the key map, so it
+    /// shows rebound keys and leaves out unbound ones.
+    fn hint(&self) -> Vec<Span<'static>> {
+        const HINTED: [(keymap::Action, &str); 6] = [
+            (keymap::Action::Help, "hint.help"),
+            (keymap::Action::Search, "hint.search"),
+            (keymap::Action::Kill, "hint.kill"),
+            (keymap::Action::Signal, "hint.signal"),
+            (keymap::Action::CommandLine, "hint.command_line"),
+            (keymap::Action::Quit, "hint.quit"),
+        ];
+        let mut spans = vec![Span::raw(i18n::text("hint.start"))];
+        for (action, key) in HINTED {
+            let keys = self.keys.map.describe(action);
+            if keys.is_empty() {
+                continue;
+            }
+            if spans.len() > 1 {
+                spans.push(Span::raw(i18n::text("hint.separator")));
+            }
+            spans.push(Span::raw(keys).bold());
+            spans.push(Span::raw(i18n::text(key)));
+        }
+        spans.push(Span::raw(i18n::text("hint.end")));
+        spans
+    }
+
+    /// Every action with the keys it is bound to, as the config file left them, then the search
+    /// syntax and the sort keys.
+    fn render_help(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block(i18n::text("popup.help"))
+            .title_bottom(" j/k to scroll, Esc to close ");
+        let heading = |text: &'static str| Line::from(text).fg(self.colors.header_fg).bold();
+        let entry = |left: String, right: String| {
+            Line::from(vec![
+                Span::raw(format!("  {left:<16}"))
+                    .fg(self.colors.header_fg)
+                    .bold(),
+                Span::raw(right),
+            ])
+        };
+        let mut lines = vec![heading("Keys")];
+        lines.extend(
+            keymap::ACTIONS
+                .iter()
+                .map(|&(action, name, _, description)| {
+                    let mut line = entry(self.keys.map.describe(action), format!("{description} "));
+                    line.push_span(
+                        Span::raw(format!("({name})")).fg(self.colors.footer_border_color),
+                    );
+                    line
+                }),
+        );
+        lines.push(Line::from(""));
+        lines.push(heading("Search"));
+        lines.push(Line::from(
+            "  A plain word matches process names, skipping letters. With an operator it is an expression, e.g. cpu > 50 && name ~ chrome:",
+        ));
+        lines.extend(query::FIELDS.iter().map(|&(names, _, description)| {
+            entry(names.replace('|', ", "), description.to_string())
+        }));
+        lines.extend(query::OPERATORS.iter().map(|&(operators, description)| {
+            entry(operators.to_string(), description.to_string())
+        }));
+        lines.push(Line::from(
+            "  A field before a word matches that field instead of the name, e.g. pid:1234, user:alex or cmd:--config.",
+        ));
+        lines.push(Line::from(
+            "  Up and Down in the search box go through earlier searches.",
+        ));
+        if !self.config.filters.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(heading("Saved filters (:filter @name)"));
+            lines.extend(
+                self.config
+                    .filters
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (name, filter))| {
+                        let keys = self.keys.map.describe(keymap::Action::SavedFilter(index));
+                        entry(keys, format!("{name}: {}", filter.search))
+                    }),
+            );
+        }
+        lines.push(Line::from(""));
+        lines.push(heading("Sort"));
+        let custom = (0..plugin::count()).map(|index| SortColumn::Custom(index as u16));
+        lines.extend(SortColumn::ALL.into_iter().chain(custom).map(|column| {
+            let order = if SortKey::new(column).descending {
+                "highest first"
+            } else {
+                "ascending"
+            };
+            let keys = column.action().map_or_else(
+                || format!(":sort {}", column.name()),
+                |action| self.keys.map.describe(action),
+            );
+            entry(
+                keys,
+                format!("{}, {order}; again to reverse", column.name()),
+            )
+        }));
+        lines.push(Line::from(
+            "  The key pressed last sorts first, the earlier ones break ties. --sort and :sort take e.g. cpu:desc,name.",
+        ));
+        let area = centered_rect(70, 80, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false })
+                .scroll((self.help_scroll, 0)),
+            area,
+        );
+    }
+
+    fn render_alerts(&self, frame: &mut Frame, area: Rect) {
+        let block = self
+            .popup_block(i18n::text("popup.alerts"))
+            .title_bottom(" c to clear the alerts, Esc to close ");
+        let mut lines: Vec<Line> = self
+            .alerts
+            .thresholds
+            .iter()
+            .map(|threshold| {
+                Line::from(format!(
+                    "  {}: {}",
+                    threshold.describe(),
+                    threshold.limits()
+                ))
+            })
+            .collect();
+        if self.alerts.thresholds.is_empty() {
+            lines.push(Line::from(
+                "No thresholds. Add them to the config file as [[alert]] tables.",
+            ));
+        }
+        lines.push(Line::from(""));
+        let log = self.alerts.log();
+        if log.is_empty() {
+            lines.push(Line::from("  Nothing went above a threshold yet"));
+        }
+        // The latest first, as many as fit; processes still above stand out.
+        for alert in log.iter().rev() {
+            let line = Line::from(format!(
+                "  {} {} ({}): {}",
+                details::format_timestamp(alert.timestamp),
+                alert.name,
+                alert.key.pid,
+                alert.reason
+            ));
+            lines.push(if self.alerts.is_above(&alert.key) {
+                line.fg(self.colors.critical_fg)
+            } else {
+                line
+            });
+        }
+        let area = centered_rect(70, 60, area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn render_signal_picker(&self, frame: &mut Frame, area: Rect) {
+        let title = match (self.signal_thread, &self.details) {
+            (Some(tid), Some(details)) => format!(
+                "Send signal to thread {tid} of {} ({})",
+                details.name, details.pid
+            ),
+            _ => match self.selected_process() {
+                Some(data) => format!("Send signal to {} ({})", data.name, data.pid),
+                None => return,
+            },
+        };
+        let block = self.popup_block(&title);
+        let mut lines: Vec<Line> = self
+            .picker_signals()
+            .iter()
+            .enumerate()
+            .map(|(i, (signal, description))| {
+                // A typed number takes precedence over the highlighted signal.
+                let chosen = i == self.signal_choice && self.input.is_empty();
+                let line = Line::from(format!(
+                    "{} SIG{:<5} {description}",
+                    if chosen { ">" } else { " " },
+                    signal::name(*signal)
+                ));
+                if chosen {
+                    line.add_modifier(Modifier::REVERSED)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::from(vec![
+            "  Number: ".into(),
+            self.input.as_str().fg(Color::Yellow),
+        ]));
+        lines.push(Line::default());
+        lines.push(Line::from(
+            "j/k to choose, type a number for any other signal, Enter to send, Esc to cancel",
+        ));
+        // As tall as the list, so it fits even in small terminals.
+        let area = centered_rect(60, 100, area);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.y + (area.height - height) / 2,
+            height,
+            ..area
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// Draws the CPU history of the selected process. With an image protocol only the frame is
+    /// drawn and the area left for the image is returned.
+    fn render_chart(&self, frame: &mut Frame, area: Rect) -> Option<Rect> {
+        let selected = self.selected_process();
+        let samples: Vec<f64> = selected
+            .and_then(|data| self.history.get(&data.key()))
+            .map(|history| history.cpu.iter().map(|&cpu| f64::from(cpu)).collect())
+            .unwrap_or_default();
+        let max = samples.iter().copied().fold(100.0, f64::max);
+        let title = match selected {
+            Some(data) => format!("CPU history: {} ({})", data.name, data.pid),
+            None => "CPU history".to_string(),
+        };
+        let block = self
+            .popup_block("")
+            .title(title)
+            .border_style(Style::new().fg(self.colors.footer_border_color));
+
+        if self.graphics.is_some() {
+            let inner = block.inner(area);
+            frame.render_widget(Clear, area);
+            frame.render_widget(block, area);
+            return Some(inner);
+        }
+
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &cpu)| (i as f64, cpu))
+            .collect();
+        let dataset = Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::new().fg(self.colors.selected_style_fg))
+            .data(&points);
+        let chart = Chart::new(vec![dataset])
+            .block(block)
+            .x_axis(Axis::default().bounds([0.0, history::HISTORY_LEN as f64 - 1.0]))
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, max])
+                    .labels(vec!["0%".into(), format!("{max:.0}%").into()]),
+            );
+        frame.render_widget(chart, area);
+        None
+    }
+
+    /// Places the chart image over `area` (or clears old kitty placements when there is none).
+    fn render_image(&mut self, area: Option<Rect>) -> io::Result<()> {
+        let Some(protocol) = self.graphics else {
+            return Ok(());
+        };
+        let samples: Vec<f64> = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .and_then(|data| self.history.get(&data.key()))
+            .map(|history| history.cpu.iter().map(|&cpu| f64::from(cpu)).collect())
+            .unwrap_or_default();
+        let color = match self.colors.selected_style_fg {
+            Color::Rgb(r, g, b) => [r, g, b],
+            _ => [255, 255, 255],
+        };
+        self.image.update(protocol, area, &samples, color)
+    }
+
+    /// Draws the table and whatever is open over it. Chart images, where enabled, go to stdout
+    /// whatever the backend.
+    pub fn render<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        let mut widths = vec![
+            Constraint::Length(25),
+            Constraint::Length(7),
+            Constraint::Length(12),
+            Constraint::Length(11),
+            Constraint::Length(5),
+            Constraint::Min(10),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(11),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(17),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(6),
+            Constraint::Length(20),
+            Constraint::Length(30),
+            Constraint::Length(6),
+            Constraint::Length(11),
+            Constraint::Length(COMMAND_WIDTH as u16),
+        ];
+        let providers = plugin::providers();
+        widths.extend(
+            providers
+                .iter()
+                .map(|provider| Constraint::Length(provider.width())),
+        );
+        let size = terminal.size().unwrap_or_default();
+        let compact = !self.linear && size.height < COMPACT_BELOW;
+        let panes = self.side_panes();
+        let [beside_panes, _] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(Rect::new(0, 0, size.width, 1));
+        let with_panes = !panes.is_empty() && !compact && beside_panes.width >= MIN_TABLE_WIDTH;
+        let table_width = if with_panes {
+            beside_panes.width
+        } else {
+            size.width
+        };
+        // The border, or the selection marker of linear mode.
+        let available = if compact {
+            table_width
+        } else {
+            table_width.saturating_sub(2)
+        };
+        let shown = fit_columns(
+            self.shown_columns(),
+            |column| match column {
+                0 if self.full_commands => constraint_width(widths[Column::Command.index()]),
+                column => constraint_width(widths[column]),
+            },
+            available,
+        );
+        let widths: Vec<Constraint> = shown
+            .iter()
+            .map(|&column| match column {
+                0 if self.full_commands => widths[Column::Command.index()],
+                column => widths[column],
+            })
+            .collect();
+        let column_widths = widths.clone();
+        // Row and column of the copy mode cursor. The path is not a table column, and the user is
+        // the last one.
+        let copy_cursor = matches!(self.mode, AppState::CopyMode)
+            .then(|| (self.state.selected().unwrap_or(0), self.copy_column))
+            .and_then(|(row, column)| match column {
+                0..=3 => Some((row, column)),
+                5 => Some((row, 7)),
+                _ => None,
+            });
+        let tags = self.tags();
+        // The parts of the search matched against names and command lines, to highlight in them.
+        let query = query::Query::parse(&self.filter).ok();
+        let patterns = |field| {
+            query
+                .as_ref()
+                .map(|query| query.patterns(field))
+                .unwrap_or_default()
+        };
+        let name_patterns = patterns(query::Field::Name);
+        let command_patterns = patterns(query::Field::Command);
+        let no_tags = String::new();
+        let unavailable = procfs::UNAVAILABLE.to_string();
+        // Replays show the time elapsed by the snapshot.
+        let now = self
+            .replay
+            .as_ref()
+            .map_or_else(snapshot::unix_now, |replay| replay.current().timestamp);
+        let followed = self.follow.as_ref().and_then(|(key, _)| {
+            self.items
+                .iter()
+                .position(|data| data.key() == *key && data.exited.is_none())
+        });
+        // Only the rows in sight are built, see `row_window`.
+        let row = |i: usize, data: &ProcessInfo| {
+            // Groups have no PID of their own.
+            let pid = match data.members {
+                0 => data.pid.to_string(),
+                _ => String::new(),
+            };
+            let cpu = self.units.cpu(data.cpu_usage);
+            let memory = self.units.memory(data.memory);
+            let mut item = vec![&data.name, &pid, &cpu, &memory];
+            let tree_prefix = self.tree_rows.get(i).map(|row| row.prefix());
+            let group_prefix = self.group_rows.get(i).map(|row| row.prefix());
+            // Processes without arguments, such as kernel threads, keep their names.
+            let command_name = self.full_commands.then(|| {
+                let command = match data.command.as_str() {
+                    "" => &data.name,
+                    _ => &data.command,
+                };
+                let indent = tree_prefix
+                    .as_deref()
+                    .or(group_prefix)
+                    .map_or(0, |prefix| prefix.chars().count());
+                fit_command(
+                    command,
+                    COMMAND_WIDTH.saturating_sub(indent),
+                    self.command_offset,
+                )
+            });
+            if let Some(name) = &command_name {
+                item[0] = name;
+            }
+            let name = item[0].clone();
+            let tree_name = tree_prefix.map(|prefix| format!("{prefix}{name}"));
+            if let Some(name) = &tree_name {
+                item[0] = name;
+            }
+            let group_name = group_prefix.map(|prefix| match data.members {
+                0 => format!("{prefix}{name}"),
+                members => format!("{prefix}{name} ({members})"),
+            });
+            if let Some(name) = &group_name {
+                item[0] = name;
+            }
+            // Linear mode has no colors to grey out exited processes with, or to set stopped ones
+            // apart.
+            let stopped = data.exited.is_none() && data.state == "Stopped";
+            let marked_name = match (self.linear, data.exited.is_some(), stopped) {
+                (true, true, _) => Some(format!("{} (exited)", item[0])),
+                (true, _, true) => Some(format!("{} (stopped)", item[0])),
+                (true, _, _) if followed == Some(i) => Some(format!("{} (followed)", item[0])),
+                (true, _, _) if self.alerts.is_above(&data.key()) => {
+                    Some(format!("{} (alert)", item[0]))
+                }
+                _ => None,
+            };
+            if let Some(name) = &marked_name {
+                item[0] = name;
+            }
+            item.push(&data.node);
+            let described_tags = tags.get(&data.pid).map(Tags::describe);
+            item.push(described_tags.as_ref().unwrap_or(&no_tags));
+            let trend = self
+                .history
+                .get(&data.key())
+                .map(|history| history.cpu_trend(TREND_WIDTH))
+                .unwrap_or_default();
+            item.push(&trend);
+            // Empty when `/proc` does not let us read them, or when the members of a group
+            // differ in them.
+            for value in [&data.user, &data.state, &data.sched] {
+                item.push(if value.is_empty() && data.members == 0 {
+                    &unavailable
+                } else {
+                    value
+                });
+            }
+            let (read, written) = self
+                .history
+                .get(&data.key())
+                .and_then(|history| history.io_rate)
+                .filter(|_| data.exited.is_none())
+                .map(|(read, written)| {
+                    let rate = |bytes| format!("{}/s", self.units.memory(bytes));
+                    (rate(read), rate(written))
+                })
+                .unwrap_or_default();
+            item.push(&read);
+            item.push(&written);
+            let virtual_memory = match data.virtual_memory {
+                0 if data.members > 0 => String::new(),
+                0 => unavailable.clone(),
+                bytes => self.units.memory(bytes),
+            };
+            item.push(&virtual_memory);
+            let [shared, swap] = [data.shared_memory, data.swap].map(|bytes| match bytes {
+                None if data.members > 0 => String::new(),
+                None => unavailable.clone(),
+                Some(bytes) => self.units.memory(bytes),
+            });
+            item.push(&shared);
+            item.push(&swap);
+            let parent = data
+                .parent
+                .map(|parent| parent.to_string())
+                .unwrap_or_default();
+            item.push(&parent);
+            // Most processes have no terminal, which is left blank like a missing parent.
+            item.push(&data.tty);
+            let session = data
+                .session
+                .map(|session| session.to_string())
+                .unwrap_or_default();
+            item.push(&session);
+            let threads = match data.threads {
+                0 => unavailable.clone(),
+                threads => threads.to_string(),
+            };
+            item.push(&threads);
+            let started = match data.start_time {
+                0 if data.members > 0 => String::new(),
+                0 => unavailable.clone(),
+                start_time => details::format_minute(start_time),
+            };
+            item.push(&started);
+            let elapsed = match data.start_time {
+                0 if data.members > 0 => String::new(),
+                0 => unavailable.clone(),
+                start_time => details::format_elapsed(now.saturating_sub(start_time)),
+            };
+            item.push(&elapsed);
+            let cpu_time = match data.cpu_time {
+                None if data.members > 0 => String::new(),
+                None => unavailable.clone(),
+                Some(cpu_time) => details::format_elapsed(cpu_time / 1000),
+            };
+            item.push(&cpu_time);
+            let fds = match data.fds {
+                None if data.members > 0 => String::new(),
+                None => unavailable.clone(),
+                Some(fds) => fds.to_string(),
+            };
+            item.push(&fds);
+            item.push(&data.container);
+            item.push(&data.window);
+            // Most processes use no GPU, and are left blank rather than marked unknown.
+            let gpu = match (data.gpu, data.gpu_memory) {
+                (Some(percent), _) => format!("{percent:.0}%"),
+                (None, Some(_)) => "0%".to_string(),
+                (None, None) => String::new(),
+            };
+            item.push(&gpu);
+            let gpu_memory = data
+                .gpu_memory
+                .map(|bytes| self.units.memory(bytes))
+                .unwrap_or_default();
+            item.push(&gpu_memory);
+            let command = fit_command(&data.command, COMMAND_WIDTH, self.command_offset);
+            item.push(&command);
+            let custom: Vec<String> = providers
+                .iter()
+                .map(|provider| {
+                    data.custom
+                        .get(provider.name())
+                        .map(ToString::to_string)
+                        .unwrap_or_default()
+                })
+                .collect();
+            item.extend(&custom);
+            if self.linear {
+                return shown
+                    .iter()
+                    .map(|&column| (column, item[column]))
+                    .map(|(column, content)| {
+                        let cell = Cell::from(content.clone());
+                        if copy_cursor == Some((i, column)) {
+                            cell.add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect::<Row>();
+            }
+            let color = match i % 2 {
+                0 => self.colors.normal_row_color,
+                _ => self.colors.alt_row_color,
+            };
+            let severity =
+                Severity::of(data.cpu_usage, self.previous_cpu.get(&data.key()).copied());
+            let severity_fg = match severity {
+                _ if data.exited.is_some() => self.colors.footer_border_color,
+                Severity::Normal | Severity::Falling => self.colors.row_fg,
+                Severity::Rising => self.colors.rising_fg,
+                Severity::Critical => self.colors.critical_fg,
+            };
+            let pad = |content: &str| {
+                if compact {
+                    Text::from(content.to_string())
+                } else {
+                    Text::from(format!("\n{content}\n"))
+                }
+            };
+            shown
+                .iter()
+                .map(|&column| (column, item[column]))
+                .map(|(column, content)| {
+                    let positions = self.matched_positions(
+                        column,
+                        content,
+                        &data.name,
+                        &name_patterns,
+                        &command_patterns,
+                    );
+                    let cell = if let Some(positions) = positions {
+                        Cell::from(self.highlighted(content, &positions, compact))
+                    } else if column == 5 {
+                        Cell::from(self.tags_text(tags.get(&data.pid), compact))
+                    } else if column == 8 {
+                        let cell = Cell::from(pad(content));
+                        match data.state.as_str() {
+                            _ if data.exited.is_some() => cell,
+                            "Zombie" => cell.fg(self.colors.critical_fg).bold(),
+                            state if proc::is_problem_state(state) => {
+                                cell.fg(self.colors.rising_fg).bold()
+                            }
+                            _ => cell,
+                        }
+                    } else if column != 2 {
+                        Cell::from(pad(content))
+                    } else {
+                        let content = if self.colors.severity_symbols {
+                            format!("{} {content}", severity.symbol())
+                        } else {
+                            content.clone()
+                        };
+                        Cell::from(pad(&content)).fg(severity_fg)
+                    };
+                    if copy_cursor == Some((i, column)) {
+                        cell.add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
+                    } else {
+                        cell
+                    }
+                })
+                .collect::<Row>()
+                .style(if data.exited.is_some() {
+                    Style::new()
+                        .fg(self.colors.footer_border_color)
+                        .bg(color)
+                        .add_modifier(Modifier::DIM)
+                } else if stopped {
+                    Style::new()
+                        .fg(self.colors.rising_fg)
+                        .bg(color)
+                        .add_modifier(Modifier::ITALIC)
+                } else if followed == Some(i) {
+                    Style::new()
+                        .fg(self.colors.header_fg)
+                        .bg(color)
+                        .add_modifier(Modifier::BOLD)
+                } else if self.alerts.is_above(&data.key()) {
+                    Style::new()
+                        .fg(self.colors.critical_fg)
+                        .bg(color)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::new().fg(self.colors.row_fg).bg(color)
+                })
+                .height(if compact { 1 } else { 2 })
+        };
+
+        let header = shown.iter().map(|&column| {
+            let title = match column {
+                0 if self.full_commands => title(Column::Command.index(), &providers),
+                column => title(column, &providers),
+            };
+            let column = sort_column(column);
+            let Some(level) = self.sort.iter().position(|key| Some(key.column) == column) else {
+                return title.to_string();
+            };
+            let arrow = if self.sort[level].descending {
+                "▼"
+            } else {
+                "▲"
+            };
+            // Tie-breaking keys are numbered by their place in the sort order.
+            if level == 0 {
+                format!("{title} {arrow}")
+            } else {
+                format!("{title} {arrow}{}", level + 1)
+            }
+        });
+        let header = Row::new(header.collect::<Vec<String>>());
+        let notice = match &self.error {
+            Some(e) => Line::from(format!(" {e} ").bold()).fg(self.colors.critical_fg),
+            None => Line::from(self.notice.clone().unwrap_or_default()),
+        };
+        // Kills still to come stay in sight until they happen or are called off.
+        let pending = match self.queue.len() {
+            0 => String::new(),
+            count => format!(" {count} pending (Q) "),
+        };
+        let alerting = match self.alerts.active() {
+            0 => String::new(),
+            count => format!(" {count} above threshold (G) "),
+        };
+        // The start of a key sequence, waiting for the rest.
+        let typed = match self.keys.pending() {
+            Some(keys) => format!(" {keys}… "),
+            None => String::new(),
+        };
+        let match_count = Line::from(vec![
+            typed.fg(self.colors.header_fg).bold(),
+            alerting.fg(self.colors.critical_fg),
+            pending.fg(self.colors.rising_fg),
+            self.match_count()
+                .map(|count| format!(" {count} "))
+                .unwrap_or_default()
+                .into(),
+        ])
+        .alignment(Alignment::Right);
+        let warning = self.collect_error.as_ref().map(|e| {
+            Line::from(format!(" {e}, showing the last data ").bold()).fg(self.colors.critical_fg)
+        });
+        // What was done just before the replayed snapshot, or else which log it is.
+        let replaying = self.replay.as_ref().map(|replay| match replay.actions() {
+            [] => format!(
+                "Replaying {}, [ and ] to step",
+                replay
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            ),
+            actions => format!(
+                "Just before: {}",
+                actions
+                    .iter()
+                    .map(replay::Action::describe)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+        // Centered, clear of the executable and the staleness on the top border.
+        let top_title = warning.is_some() || self.restriction.is_some() || replaying.is_some();
+        let read_only = self
+            .read_only
+            .then(|| "Read-only: no signals or changes".to_string());
+        let banner = replaying
+            .as_ref()
+            .or(read_only.as_ref())
+            .or(self.restriction.as_ref())
+            .map(|banner| {
+                Line::from(format!(" {banner} "))
+                    .fg(self.colors.rising_fg)
+                    .alignment(Alignment::Center)
+            });
+
+        let table = |rows: Vec<Row<'static>>| {
+            if self.linear {
+                return Table::new(rows, widths)
+                    .highlight_symbol("> ")
+                    .highlight_spacing(HighlightSpacing::Always)
+                    .block(match warning.or(banner) {
+                        // An empty title would still take up a line.
+                        Some(warning) => Block::new()
+                            .title(warning)
+                            .title_bottom(notice)
+                            .title_bottom(match_count),
+                        None => Block::new().title_bottom(notice).title_bottom(match_count),
+                    })
+                    .header(header);
+            }
+            let header_style = Style::default()
+                .fg(self.colors.header_fg)
+                .bg(self.colors.header_bg);
+            let selected_style = Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(self.colors.selected_style_fg);
+            let table = Table::new(rows, widths)
+                .block(Block::new().title("Processes"))
+                .highlight_style(selected_style)
+                .bg(self.colors.buffer_bg)
+                //.highlight_symbol(">>")
+                .block(Block::new())
+                .highlight_spacing(HighlightSpacing::Always)
+                .header(header.style(header_style));
+            if compact {
+                // Every line counts in a tiny pane, so there is no border.
+                table
+            } else {
+                table.block(
+                    Block::bordered()
+                        .border_type(BorderType::Double)
+                        .border_style(Style::new().fg(self.colors.footer_border_color))
+                        .title(warning.unwrap_or_default())
+                        .title(banner.unwrap_or_default())
+                        .title_bottom(notice)
+                        .title_bottom(match_count),
+                )
+            }
+        };
+        let announcement = self.announcement();
+        let staleness = self.staleness();
+        let selected_exe = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|data| data.exe.clone())
+            .filter(|exe| !exe.is_empty());
+
+        let mut image_area = None;
+        let mut clickable = Clickable::default();
+        terminal
+            .draw(|frame| {
+                let area = frame.size();
+
+                let vertical = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Length(u16::from(self.summary.is_some())),
+                    Constraint::Length(u16::from(self.show_breakdown)),
+                    Constraint::Min(3),
+                ]);
+                let [mut help_area, mut summary_area, mut breakdown_area, mut table_area] =
+                    vertical.areas(area);
+                if compact {
+                    // No help line either; the page indicator sits in the header.
+                    (help_area, summary_area, breakdown_area, table_area) =
+                        (Rect::default(), Rect::default(), Rect::default(), area);
+                }
+                if let Some(summary) = &self.summary {
+                    frame.render_widget(self.summary_line(summary), summary_area);
+                }
+                if self.show_breakdown && !compact {
+                    clickable.segments = self.render_breakdown(frame, breakdown_area);
+                }
+
+                if with_panes {
+                    let horizontal = Layout::horizontal([
+                        Constraint::Percentage(60),
+                        Constraint::Percentage(40),
+                    ]);
+                    let [left, side_area] = horizontal.areas(table_area);
+                    table_area = left;
+                    let pane_areas = Layout::vertical(vec![
+                        Constraint::Ratio(1, panes.len() as u32);
+                        panes.len()
+                    ])
+                    .split(side_area);
+                    for (pane, &pane_area) in panes.iter().zip(pane_areas.iter()) {
+                        match pane {
+                            SidePane::Chart => image_area = self.render_chart(frame, pane_area),
+                            SidePane::Cores => self.render_cores(frame, pane_area),
+                            SidePane::Numa => self.render_numa(frame, pane_area),
+                            SidePane::Memory => self.render_memory(frame, pane_area),
+                            SidePane::Runtime => self.render_runtime(frame, pane_area),
+                            SidePane::Power => self.render_power(frame, pane_area),
+                            SidePane::Log => self.render_log(frame, pane_area),
+                        }
+                    }
+                }
+
+                // The same layout as the table's own, inside its border or titles.
+                let inner = if compact {
+                    table_area
+                } else if self.linear {
+                    let top = u16::from(top_title);
+                    Rect {
+                        y: table_area.y + top,
+                        height: table_area.height.saturating_sub(top + 1),
+                        ..table_area
+                    }
+                } else {
+                    table_area.inner(&Margin::new(1, 1))
+                };
+                let row_height: u16 = if compact || self.linear { 1 } else { 2 };
+                let visible = usize::from(inner.height.saturating_sub(1) / row_height).max(1);
+                let len = self.items.len();
+                let selected = self
+                    .state
+                    .selected()
+                    .filter(|_| len > 0)
+                    .map(|i| i.min(len - 1));
+                let offset = if compact {
+                    // Page through the list instead of scrolling it line by line.
+                    selected.unwrap_or(0) / visible * visible
+                } else if let Some(followed) = followed {
+                    // The table still scrolls to a selection moved away from the followed process.
+                    row_window(followed.saturating_sub(visible / 2), selected, visible, len)
+                } else {
+                    row_window(self.state.offset(), selected, visible, len)
+                };
+                let shown_rows = offset.min(len)..(offset + visible).min(len);
+                let rows = shown_rows
+                    .clone()
+                    .zip(&self.items[shown_rows])
+                    .map(|(i, data)| row(i, data))
+                    .collect();
+                let mut state = TableState::default().with_selected(selected.map(|i| i - offset));
+                frame.render_stateful_widget(table(rows), table_area, &mut state);
+                // Where the shown rows are among all of them, on the right border. Counted in
+                // lines, so the thumb is as long as the share of the rows on screen.
+                if !compact && !self.linear && len > visible {
+                    let lines = |rows: usize| rows * usize::from(row_height);
+                    let mut scrollbar = ScrollbarState::new(lines(len - visible) + 1)
+                        .position(lines(offset))
+                        .viewport_content_length(lines(visible));
+                    frame.render_stateful_widget(
+                        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                            .begin_symbol(None)
+                            .end_symbol(None)
+                            .track_symbol(Some("║"))
+                            .track_style(Style::new().fg(self.colors.footer_border_color))
+                            .thumb_style(Style::new().fg(self.colors.header_fg)),
+                        Rect {
+                            y: inner.y + 1,
+                            height: inner.height.saturating_sub(1),
+                            ..table_area
+                        },
+                        &mut scrollbar,
+                    );
+                }
+                if len == 0 {
+                    let text = self.empty_table_text();
+                    let rows_area = Rect {
+                        y: inner.y + 1,
+                        height: inner.height.saturating_sub(1),
+                        ..inner
+                    };
+                    let [_, message_area] = Layout::vertical([
+                        Constraint::Length(rows_area.height.saturating_sub(text.len() as u16) / 2),
+                        Constraint::Fill(1),
+                    ])
+                    .areas(rows_area);
+                    frame.render_widget(
+                        Paragraph::new(text)
+                            .alignment(Alignment::Center)
+                            .fg(self.colors.footer_border_color)
+                            .wrap(Wrap { trim: true }),
+                        message_area,
+                    );
+                }
+                if compact {
+                    let indicator = format!(
+                        " {}/{} ",
+                        offset / visible + 1,
+                        len.div_ceil(visible).max(1)
+                    );
+                    let width = (indicator.len() as u16).min(table_area.width);
+                    frame.render_widget(
+                        Span::raw(indicator).fg(self.colors.header_fg),
+                        Rect::new(table_area.right() - width, table_area.y, width, 1),
+                    );
+                }
+                let selection_width = if self.linear { 2 } else { 0 };
+                let [_, columns_area] =
+                    Layout::horizontal([Constraint::Length(selection_width), Constraint::Fill(0)])
+                        .areas(inner);
+                let cells = Layout::horizontal(column_widths)
+                    .spacing(1)
+                    .split(columns_area);
+                clickable.headers = shown
+                    .iter()
+                    .zip(cells.iter())
+                    .filter_map(|(&column, &cell)| {
+                        Some((Rect { height: 1, ..cell }, sort_column(column)?))
+                    })
+                    .collect();
+                clickable.rows = Rect {
+                    y: inner.y + 1,
+                    height: inner.height.saturating_sub(1),
+                    ..inner
+                };
+                clickable.first_row = offset;
+                clickable.row_height = row_height;
+
+                // Executable of the selected process on the top border, clickable where supported.
+                if let (Some(exe), false) = (&selected_exe, self.linear || compact) {
+                    let label = " EXE: ";
+                    let x = table_area.x + 1;
+                    let width = table_area.width.saturating_sub(2);
+                    let label_width = (label.len() as u16).min(width);
+                    frame.render_widget(
+                        Span::raw(label).fg(self.colors.footer_border_color),
+                        Rect::new(x, table_area.y, label_width, 1),
+                    );
+                    let path_area = Rect::new(
+                        x + label_width,
+                        table_area.y,
+                        (exe.chars().count() as u16).min(width - label_width),
+                        1,
+                    );
+                    if self.hyperlinks {
+                        frame.render_widget(
+                            hyperlink::Hyperlink {
+                                text: exe,
+                                url: hyperlink::url_for_path(&self.exe_url, exe),
+                            },
+                            path_area,
+                        );
+                    } else {
+                        frame.render_widget(
+                            Span::raw(exe.as_str()).fg(self.colors.footer_border_color),
+                            path_area,
+                        );
+                    }
+                }
+                if !self.linear && !compact {
+                    let width = (staleness.width() as u16).min(table_area.width.saturating_sub(2));
+                    frame.render_widget(
+                        staleness,
+                        Rect::new(
+                            table_area.right().saturating_sub(width + 1),
+                            table_area.y,
+                            width,
+                            1,
+                        ),
+                    );
+                }
+                frame.set_cursor(0, 0);
+
+                let msg = self.hint();
+
+                let text = if self.linear {
+                    // Screen readers follow the top line, so keep the selection there.
+                    Text::from(announcement)
+                } else {
+                    Text::from(Line::from(msg))
+                };
+                frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }).style(Style::default()), help_area);
+
+                // Popup logic
+                if self.show_popup && matches!(self.mode, AppState::SignalMode) {
+                    // The picker of a thread goes over the detail view, drawn below.
+                    if self.signal_thread.is_none() {
+                        self.render_signal_picker(frame, area);
+                    }
+                } else if self.show_popup && matches!(self.mode, AppState::SchedMode) {
+                    self.render_sched_picker(frame, area);
+                } else if self.show_popup && matches!(self.mode, AppState::IoniceMode) {
+                    self.render_ionice_picker(frame, area);
+                } else if self.show_popup && matches!(self.mode, AppState::PriorityMode) {
+                    self.render_priority_editor(frame, area);
+                } else if self.show_popup && matches!(self.mode, AppState::ColumnMode) {
+                    self.render_column_picker(frame, area);
+                } else if self.show_popup {
+                    let title = match self.mode {
+                        AppState::PortMode => "Port (e.g. 8080 or 53/udp)".to_string(),
+                        AppState::JumpMode => "Jump to PID".to_string(),
+                        AppState::FindMode => {
+                            "Find in names, command lines, environments, files and connections"
+                                .to_string()
+                        }
+                        AppState::ThrottleMode => {
+                            let selected = self.selected_process();
+                            let (what, unit) = match self.throttle_resource {
+                                Resource::Cpu => ("CPU usage", "% of one CPU"),
+                                Resource::Memory => ("memory", "e.g. 512M or 2G"),
+                            };
+                            // Under the other cap already, the processes stay as they are.
+                            let capped = selected
+                                .and_then(|data| self.throttle_of(data.pid))
+                                .is_some();
+                            format!(
+                                "Cap the {what} of {}{} at ({unit}{})",
+                                selected
+                                    .map(|data| format!("{} ({})", data.name, data.pid))
+                                    .unwrap_or_default(),
+                                if self.throttle_tree && !capped {
+                                    " and its children"
+                                } else {
+                                    ""
+                                },
+                                match (capped, self.throttle_tree) {
+                                    (true, _) => "",
+                                    (false, true) => ", Tab: alone",
+                                    (false, false) => ", Tab: with children",
+                                },
+                            )
+                        }
+                        AppState::CommandMode => {
+                            "Command ({pid} and {name} are those of the selection)".to_string()
+                        }
+                        AppState::CommandLineMode => {
+                            ":command (e.g. kill 1234, sort cpu, filter nginx, theme red or tree)"
+                                .to_string()
+                        }
+                        AppState::ScheduleMode => match &self.scheduling {
+                            Some(pending) => format!(
+                                "Send {} to {} ({}) in (e.g. 30s or 5m)",
+                                pending.action.describe().trim_end_matches(','),
+                                pending.name,
+                                pending.pid
+                            ),
+                            None => String::new(),
+                        },
+                        AppState::WatchdogMode if self.watchdog_editing.is_some() => {
+                            "Edit watchdog rule".to_string()
+                        }
+                        AppState::WatchdogMode => {
+                            "Watchdog rule (e.g. name ~ java && cpu > 90 for 30s then TERM)"
+                                .to_string()
+                        }
+                        AppState::ExitFilterMode => {
+                            "Show exited processes named (empty for all)".to_string()
+                        }
+                        AppState::ReniceMode => format!(
+                            "Change the priority of {} processes by (e.g. +5)",
+                            self.items.len()
+                        ),
+                        _ => match (&self.filter_error, self.match_count()) {
+                            (Some(e), _) => format!("Search: {e}"),
+                            (None, Some(count)) => format!("Search: {count}"),
+                            (None, None) => "Search".to_string(),
+                        },
+                    };
+                    let block = self.popup_block(&title);
+                    let area = centered_rect(60, 20, area);
+
+                    let input = Paragraph::new(self.input.as_str()).style(match self.mode {
+                        AppState::ProcessMode
+                        | AppState::SignalMode
+                        | AppState::SchedMode
+                        | AppState::IoniceMode
+                        | AppState::PriorityMode
+                        | AppState::ColumnMode
+                        | AppState::EnvSearchMode
+                        | AppState::CopyMode => Style::default(),
+                        AppState::SearchMode
+                        | AppState::PortMode
+                        | AppState::JumpMode
+                        | AppState::FindMode
+                        | AppState::CommandMode
+                        | AppState::CommandLineMode
+                        | AppState::ThrottleMode
+                        | AppState::ScheduleMode
+                        | AppState::WatchdogMode
+                        | AppState::ExitFilterMode
+                        | AppState::ReniceMode => Style::default().fg(Color::Yellow),
+                    });
+
+                    let inner_area = block.inner(area);
+
+                    frame.render_widget(Clear, area); //this clears out the background
+                    frame.render_widget(block, area);
+                    frame.render_widget(input, inner_area);
+                }
+
+                if let Some((port, holders)) = &self.port_holders {
+                    let title = format!("Port {port}");
+                    let block = self.popup_block(&title);
+                    let area = centered_rect(70, 60, area);
+                    let mut lines = Vec::new();
+                    for holder in holders {
+                        lines.push(Line::from(format!(
+                            "{} ({}) listening on {} {}",
+                            holder.name,
+                            holder.listener.pid,
+                            holder.listener.protocol,
+                            holder.listener.address
+                        )));
+                        lines.push(Line::from(format!("  Executable: {}", holder.exe)));
+                        lines.push(Line::from(format!("  Command:    {}", holder.command)));
+                        lines.push(Line::from(format!(
+                            "  Memory:     {}",
+                            self.units.memory(holder.memory)
+                        )));
+                        lines.push(Line::from(""));
+                    }
+
+                    frame.render_widget(Clear, area);
+                    frame.render_widget(
+                        Paragraph::new(lines)
+                            .block(block)
+                            .wrap(Wrap { trim: false }),
+                        area,
+                    );
+                    self.render_buttons(
+                        frame,
+                        area,
+                        &[
+                            ("h reload (HUP)", KeyCode::Char('h')),
+                            ("t stop gracefully (TERM)", KeyCode::Char('t')),
+                            ("K kill (KILL)", KeyCode::Char('K')),
+                            ("Esc close", KeyCode::Esc),
+                        ],
+                        &mut clickable,
+                    );
+                }
+
+                if let Some(output) = &self.command_output {
+                    let block = self
+                        .popup_block(&output.title)
+                        .title_bottom(" j/k to scroll, ! for another command, Esc to close ");
+                    let area = centered_rect(80, 70, area);
+                    frame.render_widget(Clear, area);
+                    frame.render_widget(
+                        Paragraph::new(output.text.as_str())
+                            .block(block)
+                            .wrap(Wrap { trim: false })
+                            .scroll((self.command_scroll, 0)),
+                        area,
+                    );
+                }
+
+                if let Some(pending) = &self.pending_kill {
+                    let block = self.popup_block(i18n::text("popup.confirm"));
+                    let matching = matches!(pending.action, KillAction::Matching { .. });
+                    let label = match matching {
+                        true => i18n::text("confirm.search"),
+                        false => i18n::text("confirm.name"),
+                    };
+                    let (pid, user) = (i18n::text("confirm.pid"), i18n::text("confirm.user"));
+                    let width = [label, pid, user]
+                        .iter()
+                        .map(|label| label.chars().count() + 1)
+                        .max()
+                        .unwrap_or_default();
+                    let action = pending.action.describe();
+                    let mut lines = vec![
+                        Line::from(i18n::fill("confirm.send", &[("action", &action)])),
+                        Line::from(""),
+                        Line::from(format!("  {label:<width$}{}", pending.name)),
+                        Line::from(format!("  {pid:<width$}{}", pending.pid)),
+                        Line::from(format!("  {user:<width$}{}", pending.user)),
+                    ];
+                    let taken_along = |what: String| {
+                        let count = pending.affected.len();
+                        let key = match count {
+                            1 => "confirm.taken_along_one",
+                            _ => "confirm.taken_along",
+                        };
+                        let args: [(&str, &dyn Display); 2] = [("count", &count), ("what", &what)];
+                        format!("  {}", i18n::fill(key, &args))
+                    };
+                    let scope = match (&pending.action, pending.process_group) {
+                        (KillAction::Subtree, _) if pending.affected.is_empty() => {
+                            Some(format!("  {}", i18n::text("confirm.no_descendants")))
+                        }
+                        (KillAction::Subtree, _) => {
+                            Some(taken_along(i18n::text("confirm.descendants").to_string()))
+                        }
+                        (KillAction::ProcessGroup, None) => {
+                            Some(format!("  {}", i18n::text("confirm.no_group")))
+                        }
+                        (KillAction::ProcessGroup, Some(group)) if pending.affected.is_empty() => {
+                            let alone = i18n::fill("confirm.alone_in_group", &[("group", &group)]);
+                            Some(format!("  {alone}"))
+                        }
+                        (KillAction::ProcessGroup, Some(group)) => Some(taken_along(i18n::fill(
+                            "confirm.group_members",
+                            &[("group", &group)],
+                        ))),
+                        (KillAction::Matching { .. }, _) => {
+                            Some(format!("  {}", i18n::text("confirm.matching")))
+                        }
+                        _ => None,
+                    };
+                    if let Some(scope) = scope {
+                        // All of them when there is room: they are the whole of the kill.
+                        let shown = if matching {
+                            (area.height as usize).saturating_sub(11).max(6)
+                        } else {
+                            6
+                        };
+                        lines.push(Line::from(scope));
+                        for (pid, name) in pending.affected.iter().take(shown) {
+                            lines.push(Line::from(format!("    {name} ({pid})")));
+                        }
+                        if pending.affected.len() > shown {
+                            let more = pending.affected.len() - shown;
+                            let more = i18n::fill("confirm.more", &[("count", &more)]);
+                            lines.push(Line::from(format!("    {more}")));
+                        }
+                    }
+                    if matches!(pending.action, KillAction::Group) {
+                        lines.push(Line::from(format!("  {}", i18n::text("confirm.group"))));
+                    }
+                    if matches!(pending.action, KillAction::Container) {
+                        lines.push(Line::from(format!("  {}", i18n::text("confirm.container"))));
+                    }
+                    if !pending.dependents.is_empty() {
+                        const SHOWN: usize = 4;
+                        lines.push(Line::from(""));
+                        lines.push(Line::from(
+                            i18n::text("confirm.dependents").fg(self.colors.rising_fg),
+                        ));
+                        for dependent in pending.dependents.iter().take(SHOWN) {
+                            lines.push(Line::from(format!(
+                                "  {} ({}), {}",
+                                dependent.name,
+                                dependent.pid,
+                                dependent.reason.describe()
+                            )));
+                        }
+                        if pending.dependents.len() > SHOWN {
+                            let more = pending.dependents.len() - SHOWN;
+                            let more = i18n::fill("confirm.more", &[("count", &more)]);
+                            lines.push(Line::from(format!("  {more}")));
+                        }
+                    }
+                    // Room for the buttons.
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(""));
+                    let height = (lines.len() as u16 + 2).min(area.height);
+                    let popup = centered_rect(50, 100, area);
+                    let popup = Rect {
+                        y: popup.y + (popup.height - height) / 2,
+                        height,
+                        ..popup
+                    };
+                    frame.render_widget(Clear, popup);
+                    frame.render_widget(
+                        Paragraph::new(lines)
+                            .block(block)
+                            .wrap(Wrap { trim: false }),
+                        popup,
+                    );
+                    let mut buttons = vec![
+                        (i18n::text("button.confirm"), KeyCode::Char('y')),
+                        (i18n::text("button.cancel"), KeyCode::Char('n')),
+                        (i18n::text("button.later"), KeyCode::Char('l')),
+                    ];
+                    // See `App::cycle_kill_scope`.
+                    if matches!(
+                        pending.action,
+                        KillAction::Kill | KillAction::Subtree | KillAction::ProcessGroup
+                    ) {
+                        buttons.push((i18n::text("button.scope"), KeyCode::Char('a')));
+                    }
+                    self.render_buttons(frame, popup, &buttons, &mut clickable);
+                }
+
+                if self.show_queue {
+                    self.render_queue(frame, area);
+                }
+
+                // Out of the way of the input popup while a rule is typed.
+                if self.show_watchdog && !matches!(self.mode, AppState::WatchdogMode) {
+                    self.render_watchdog(frame, area);
+                }
+
+                if self.show_alerts {
+                    self.render_alerts(frame, area);
+                }
+
+                if let Some(hunt) = &self.hunt {
+                    if !matches!(self.mode, AppState::FindMode) {
+                        self.render_hunt(hunt, frame, area);
+                    }
+                }
+                if self.show_exits && !matches!(self.mode, AppState::ExitFilterMode) {
+                    self.render_exits(frame, area);
+                }
+                if self.show_outcomes {
+                    self.render_outcomes(frame, area);
+                }
+                if let Some(entries) = &self.audit_entries {
+                    self.render_audit(entries, frame, area);
+                }
+                if self.show_changes {
+                    self.render_changes(frame, area);
+                }
+                if self.show_suspects {
+                    self.render_suspects(frame, area);
+                }
+                if self.show_consumers {
+                    self.render_consumers(frame, area);
+                }
+                if self.show_ephemeral {
+                    self.render_ephemeral(frame, area);
+                }
+
+                if self.show_help {
+                    self.render_help(frame, area);
+                }
+
+                if !self.orphans.is_empty() {
+                    let block = self.popup_block(i18n::text("popup.orphans"));
+                    let area = centered_rect(60, 50, area);
+                    let parent = self
+                        .kills
+                        .last()
+                        .map(|kill| format!("{} ({})", kill.name, kill.pid))
+                        .unwrap_or_default();
+                    let mut lines = vec![
+                        Line::from(i18n::fill("orphans.exited", &[("parent", &parent)])),
+                        Line::from(""),
+                    ];
+                    lines.extend(
+                        self.orphans
+                            .iter()
+                            .map(|(key, name)| Line::from(format!("  {name} ({})", key.pid))),
+                    );
+
+                    frame.render_widget(Clear, area);
+                    frame.render_widget(
+                        Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+                        area,
+                    );
+                    self.render_buttons(
+                        frame,
+                        area,
+                        &[
+                            (i18n::text("button.kill_orphans"), KeyCode::Char('y')),
+                            (i18n::text("button.leave_orphans"), KeyCode::Char('n')),
+                        ],
+                        &mut clickable,
+                    );
+                }
+
+                if let Some(denied) = &self.denied_kill {
+                    let block = self.popup_block(i18n::text("popup.permission_denied"));
+                    let area = centered_rect(60, 30, area);
+                    let owner = match denied.owner.as_str() {
+                        "" => i18n::text("denied.another_user"),
+                        owner => owner,
+                    };
+                    let process = format!("{} ({})", denied.name, denied.request.pid);
+                    let signal = signal::describe(denied.request.signal);
+                    let args: [(&str, &dyn Display); 3] = [
+                        ("process", &process),
+                        ("owner", &owner),
+                        ("signal", &signal),
+                    ];
+                    let mut lines = vec![
+                        Line::from(i18n::fill("denied.owner", &args)),
+                        Line::from(""),
+                        Line::from(i18n::text("denied.as_root")),
+                        Line::from(format!("  {}", denied.request.sudo_command())).bold(),
+                    ];
+                    if let Some(e) = &denied.sudo_error {
+                        lines.push(Line::from(""));
+                        lines.push(
+                            Line::from(i18n::fill("denied.sudo_failed", &[("error", e)]))
+                                .fg(self.colors.critical_fg),
+                        );
+                        lines.push(Line::from(i18n::text("denied.copy")));
+                    }
+
+                    frame.render_widget(Clear, area);
+                    frame.render_widget(
+                        Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+                        area,
+                    );
+                    self.render_buttons(
+                        frame,
+                        area,
+                        &[
+                            (i18n::text("button.sudo"), KeyCode::Char('y')),
+                            (i18n::text("button.copy"), KeyCode::Char('c')),
+                            (i18n::text("button.cancel"), KeyCode::Char('n')),
+                        ],
+                        &mut clickable,
+                    );
+                }
+
+                if let Some(plan) = &self.renice {
+                    let title = format!("Renice by {:+}", plan.delta);
+                    let block = self.popup_block(&title);
+                    let area = centered_rect(60, 60, area);
+                    let mut lines = Vec::new();
+                    if plan.applied {
+                        lines.push(Line::from("These processes could not be reniced:"));
+                        lines.push(Line::from(""));
+                        lines.extend(plan.failures().map(|entry| {
+                            Line::from(format!(
+                                "  {} ({}): {}",
+                                entry.name,
+                                entry.pid,
+                                entry.error.as_deref().unwrap_or_default()
+                            ))
+                        }));
+                    } else {
+                        lines.extend(plan.entries.iter().map(|entry| {
+                            Line::from(match &entry.from {
+                                Ok(from) => format!(
+                                    "  {} ({}): {} {} → {}",
+                                    entry.name,
+                                    entry.pid,
+                                    renice::LABEL.to_lowercase(),
+                                    renice::describe(*from),
+                                    renice::describe(entry.to)
+                                ),
+                                Err(e) => format!("  {} ({}): {e}", entry.name, entry.pid),
+                            })
+                        }));
+                    }
+
+                    frame.render_widget(Clear, area);
+                    frame.render_widget(
+                        Paragraph::new(lines)
+                            .block(block)
+                            .wrap(Wrap { trim: false }),
+                        area,
+                    );
+                    let buttons: &[_] = if plan.applied {
+                        &[("Esc close", KeyCode::Esc)]
+                    } else {
+                        &[
+                            ("y apply", KeyCode::Char('y')),
+                            ("n cancel", KeyCode::Char('n')),
+                        ]
+                    };
+                    self.render_buttons(frame, area, buttons, &mut clickable);
+                }
+
+                if let Some(details) = &self.details {
+                    let tabs: Vec<String> = details::Tab::ALL
+                        .into_iter()
+                        .map(|tab| match tab == self.details_tab {
+                            true => format!("[{}]", tab.name()),
+                            false => tab.name().to_string(),
+                        })
+                        .collect();
+                    let title = format!(
+                        "{} ({}) - {} - Tab to switch, j/k to scroll, Esc to close",
+                        details.name,
+                        details.pid,
+                        tabs.join(" ")
+                    );
+                    let block = self.popup_block(&title);
+                    let area = centered_rect(80, 80, area);
+                    let lines = match self.details_tab {
+                        details::Tab::Overview => self.details_overview(details),
+                        details::Tab::Environment => self.details_environment(details),
+                        details::Tab::Files => self.details_files(details),
+                        details::Tab::Threads => self.details_threads(details),
+                    };
+
+                    frame.render_widget(Clear, area);
+                    let inner = block.inner(area);
+                    frame.render_widget(block, area);
+                    let history = self
+                        .history
+                        .get(&details.key())
+                        .filter(|_| self.details_tab == details::Tab::Overview);
+                    let [graphs_area, text_area] = Layout::vertical([
+                        Constraint::Length(if history.is_some() { 5 } else { 0 }),
+                        Constraint::Min(0),
+                    ])
+                    .areas(inner);
+                    if let Some(history) = history {
+                        self.render_sparklines(frame, graphs_area, history);
+                    }
+                    frame.render_widget(
+                        Paragraph::new(lines)
+                            .wrap(Wrap { trim: false })
+                            .scroll((self.details_scroll, 0)),
+                        text_area,
+                    );
+                    if self.show_popup && self.signal_thread.is_some() {
+                        self.render_signal_picker(frame, area);
+                    }
+                }
+
+                if self.show_about {
+                    let block = self.popup_block(i18n::text("popup.about"));
+                    let area = centered_rect(60, 50, area);
+                    let mut lines: Vec<Line> =
+                        update::about_lines().into_iter().map(Line::from).collect();
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(self.update_status.describe()));
+
+                    frame.render_widget(Clear, area);
+                    frame.render_widget(
+                        Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+                        area,
+                    );
+                }
+
+                // Many popups and graphs have fixed colors; the bold, reversed and the rest stay.
+                if self.colors.monochrome {
+                    for cell in &mut frame.buffer_mut().content {
+                        cell.fg = Color::Reset;
+                        cell.bg = Color::Reset;
+                    }
+                }
+            })
+            .map(|_frame| ())?;
+        // Scrolled only as far as the selection needs from here on.
+        *self.state.offset_mut() = clickable.first_row;
+        self.clickable = clickable;
+
+        // Popups are drawn over the chart, so hide the image while one is open.
+        if self.popup_open() {
+            image_area = None;
+        }
+        let _ = self.render_image(image_area);
+        Ok(())
+    }
+}
+
+/// The first of `visible` rows in sight out of `len`: `offset`, moved as little as it takes to
+/// bring the selection into sight, and back up where it would leave rows below it unused.
+fn row_window(offset: usize, selected: Option<usize>, visible: usize, len: usize) -> usize {
+    let mut offset = offset.min(len.saturating_sub(visible));
+    if let Some(selected) = selected {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + visible {
+            offset = selected + 1 - visible;
+        }
+    }
+    offset
+}
+
+/// `percent_x` by `percent_y` of `r` in its middle, but at least [`MIN_POPUP_WIDTH`] by
+/// [`MIN_POPUP_HEIGHT`] where `r` has the room.
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+    let popup = Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1];
+    let width = popup.width.max(MIN_POPUP_WIDTH).min(r.width);
+    let height = popup.height.max(MIN_POPUP_HEIGHT).min(r.height);
+    Rect::new(
+        r.x + (r.width - width) / 2,
+        r.y + (r.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Cells a column of the table takes.
+fn constraint_width(constraint: Constraint) -> u16 {
+    match constraint {
+        Constraint::Length(width) | Constraint::Min(width) => width,
+        _ => 0,
+    }
+}
+
+/// `shown` without the custom columns, the last first, and then the columns of
+/// [`DROPPED_FIRST`], in its order, that leave it wider than `available` cells with a cell between
+/// columns.
+fn fit_columns(mut shown: Vec<usize>, width: impl Fn(usize) -> u16, available: u16) -> Vec<usize> {
+    let total = |shown: &[usize]| {
+        shown
+            .iter()
+            .map(|&column| width(column) + 1)
+            .sum::<u16>()
+            .saturating_sub(1)
+    };
+    let mut custom: Vec<usize> = shown
+        .iter()
+        .copied()
+        .filter(|&column| column >= Column::ALL.len())
+        .collect();
+    custom.sort_unstable_by(|a, b| b.cmp(a));
+    let dropped = custom.into_iter().chain(DROPPED_FIRST.map(Column::index));
+    for column in dropped {
+        if total(&shown) <= available {
+            break;
+        }
+        shown.retain(|&shown| shown != column);
+    }
+    shown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::{ProcessSample, Snapshot};
+    use ratatui::backend::TestBackend;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.show_snapshot(Snapshot {
+            timestamp: 1_760_623_402,
+            processes: vec![ProcessSample {
+                pid: 5_000_000,
+                name: "bash".to_string(),
+                user: "root".to_string(),
+                state: "Sleeping".to_string(),
+                ..Default::default()
+            }],
+            without_disk: true,
+        });
+        app
+    }
+
+    /// The lines drawn by `app` on a terminal of `width` by `height`.
+    fn draw(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        app.render(&mut terminal).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer.get(x, y).symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn narrow_table_drops_the_last_columns_first() {
+        // Name, PID, CPU, memory, user and state.
+        let width = |column| match column {
+            0 => 25,
+            1 => 7,
+            2 => 12,
+            3 => 10,
+            7 => 12,
+            _ => 11,
+        };
+        let all = vec![0, 1, 2, 3, 7, 8];
+        assert_eq!(fit_columns(all.clone(), width, 200), all);
+        // The state goes before the user, who goes before the memory.
+        assert_eq!(fit_columns(all.clone(), width, 70), [0, 1, 2, 3, 7]);
+        assert_eq!(fit_columns(all.clone(), width, 50), [0, 1, 2]);
+        assert_eq!(fit_columns(all, width, 10), [0]);
+    }
+
+    #[test]
+    fn scrollbar_thumb_follows_the_rows_shown() {
+        let mut app = App::new();
+        app.show_snapshot(Snapshot {
+            timestamp: 1_760_623_402,
+            processes: (5_000_000..5_000_060)
+                .map(|pid| ProcessSample {
+                    pid,
+                    name: format!("worker-{pid}"),
+                    ..Default::default()
+                })
+                .collect(),
+            without_disk: true,
+        });
+        // The lines of the scrollbar holding the thumb, and how many lines it has: the right
+        // border below the header row.
+        let mut thumb = |selected| {
+            app.state.select(Some(selected));
+            let border: Vec<String> = draw(&mut app, 80, 24)
+                .iter()
+                .map(|line| line.chars().last().unwrap_or_default().to_string())
+                .collect();
+            let top = border.iter().position(|symbol| symbol == "╗").unwrap();
+            let bottom = border.iter().position(|symbol| symbol == "╝").unwrap();
+            let track = &border[top + 2..bottom];
+            assert!(track.iter().all(|symbol| symbol == "█" || symbol == "║"));
+            let on_thumb: Vec<usize> = (0..track.len())
+                .filter(|&line| track[line] == "█")
+                .collect();
+            (on_thumb, track.len())
+        };
+        let (top, track) = thumb(0);
+        assert_eq!(top.first(), Some(&0));
+        let (middle, _) = thumb(30);
+        assert!(middle.first() > Some(&0) && middle.last() < Some(&(track - 1)));
+        let (end, _) = thumb(59);
+        assert_eq!(end.last(), Some(&(track - 1)));
+        assert_eq!((top.len(), end.len()), (middle.len(), middle.len()));
+    }
+
+    #[test]
+    fn search_matches_are_found_in_names_and_command_lines() {
+        let app = app();
+        let query = query::Query::parse("jav cmd:-JAR").unwrap();
+        let names = query.patterns(query::Field::Name);
+        let commands = query.patterns(query::Field::Command);
+        assert_eq!(names, ["jav"]);
+        assert_eq!(commands, ["-JAR"]);
+        let name = app.matched_positions(0, "├ java", "java", &names, &[]);
+        assert_eq!(name, Some(vec![2, 3, 4]));
+        let command = Column::Command.index();
+        let found = app.matched_positions(command, "java -jar a.jar -JAR", "java", &[], &commands);
+        assert_eq!(found, Some(vec![16, 17, 18, 19]));
+        let expression = query::Query::parse("name ~ a && (cmd ~ ab || !(cmd ~ x))").unwrap();
+        assert_eq!(expression.patterns(query::Field::Name), ["a"]);
+        assert_eq!(expression.patterns(query::Field::Command), ["ab"]);
+        assert_eq!(query::occurrences("abab ABA", "ab"), [0, 1, 2, 3, 5, 6]);
+        // Names match fuzzily, command lines only where they contain the pattern.
+        assert_eq!(
+            query::find(query::Field::Name, "java", "jv"),
+            Some(vec![0, 2])
+        );
+        assert_eq!(query::find(query::Field::Command, "java", "jv"), None);
+        assert_eq!(
+            app.matched_positions(1, "42", "java", &names, &commands),
+            None
+        );
+    }
+
+    #[test]
+    fn popups_keep_a_usable_size() {
+        let popup = centered_rect(60, 20, Rect::new(0, 0, 50, 12));
+        assert_eq!((popup.width, popup.height), (40, 5));
+        assert_eq!((popup.x, popup.y), (5, 3));
+        let popup = centered_rect(60, 20, Rect::new(0, 0, 30, 3));
+        assert_eq!(popup, Rect::new(0, 0, 30, 3));
+    }
+
+    #[test]
+    fn empty_search_says_so() {
+        let mut app = app();
+        app.input = "nothing like it".to_string();
+        app.update_search();
+        let lines = draw(&mut app, 70, 20);
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("No process matches nothing like it")));
+    }
+
+    #[test]
+    fn narrow_terminal_keeps_the_name_and_leaves_the_panes_out() {
+        let mut app = app();
+        app.show_cores = true;
+        let lines = draw(&mut app, 50, 20);
+        assert!(lines.iter().any(|line| line.contains("bash")));
+        assert!(!lines.iter().any(|line| line.contains("USER")));
+        for (width, height) in [(1, 1), (10, 2), (20, 5)] {
+            draw(&mut app, width, height);
+        }
+    }
+}
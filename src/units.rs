@@ -0,0 +1,68 @@
+//! Human-readable memory sizes and CPU usage, shared by the table, its panes and the reports.
+
+use crate::i18n::Numbers;
+use serde::Deserialize;
+
+const KIB: f64 = 1024.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryUnit {
+    /// The largest unit that keeps the value at 1 or more.
+    #[default]
+    Auto,
+    Bytes,
+    Kib,
+    Mib,
+    Gib,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Units {
+    pub memory: MemoryUnit,
+    /// Divide CPU usage by the number of cores, so a process using every core shows 100%.
+    pub per_core: bool,
+    pub cores: usize,
+    /// Decimal mark and thousands separator, plain unless the locale is applied.
+    pub numbers: Numbers,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            memory: MemoryUnit::Auto,
+            per_core: false,
+            cores: 1,
+            numbers: Numbers::PLAIN,
+        }
+    }
+}
+
+impl Units {
+    pub fn memory(self, bytes: u64) -> String {
+        let value = bytes as f64;
+        let unit = match self.memory {
+            MemoryUnit::Auto if value >= KIB.powi(3) => MemoryUnit::Gib,
+            MemoryUnit::Auto if value >= KIB.powi(2) => MemoryUnit::Mib,
+            MemoryUnit::Auto if value >= KIB => MemoryUnit::Kib,
+            MemoryUnit::Auto => MemoryUnit::Bytes,
+            unit => unit,
+        };
+        let numbers = self.numbers;
+        match unit {
+            MemoryUnit::Kib => format!("{} KiB", numbers.format(value / KIB, 1)),
+            MemoryUnit::Mib => format!("{} MiB", numbers.format(value / KIB.powi(2), 1)),
+            MemoryUnit::Gib => format!("{} GiB", numbers.format(value / KIB.powi(3), 1)),
+            MemoryUnit::Auto | MemoryUnit::Bytes => format!("{} B", numbers.integer(bytes)),
+        }
+    }
+
+    /// CPU usage in percent of one core, as reported by the system.
+    pub fn cpu(self, percent: f32) -> String {
+        let percent = match self.per_core {
+            true => percent / self.cores.max(1) as f32,
+            false => percent,
+        };
+        format!("{}%", self.numbers.format(f64::from(percent), 1))
+    }
+}
@@ -0,0 +1,95 @@
+//! Version information and the opt-in update check against GitHub releases.
+//!
+//! The check only compares version numbers and reports the result; nothing is ever downloaded.
+//! Builds without the `update-check` feature leave out the HTTP client and cannot check.
+
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+#[cfg(feature = "update-check")]
+use std::time::Duration;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const REPOSITORY: &str = "https://github.com/alexei-ozerov/syskill";
+#[cfg(feature = "update-check")]
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/alexei-ozerov/syskill/releases/latest";
+
+#[derive(Clone, Debug)]
+pub enum UpdateStatus {
+    NotChecked,
+    Checking,
+    UpToDate,
+    Available(String),
+    Failed(String),
+}
+
+impl UpdateStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            UpdateStatus::NotChecked => "Update check disabled (run with --check-updates)".into(),
+            UpdateStatus::Checking => "Checking for updates...".into(),
+            UpdateStatus::UpToDate => "You are running the latest release".into(),
+            UpdateStatus::Available(tag) => {
+                format!("syskill {tag} is available at {REPOSITORY}/releases")
+            }
+            UpdateStatus::Failed(e) => format!("Update check failed: {e}"),
+        }
+    }
+}
+
+/// Lines shown on the about screen and printed by `syskill version`.
+pub fn about_lines() -> Vec<String> {
+    vec![
+        format!("syskill {VERSION}"),
+        format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+        REPOSITORY.to_string(),
+    ]
+}
+
+/// Fetches the tag name of the latest GitHub release.
+#[cfg(feature = "update-check")]
+pub fn latest_release() -> Result<String, String> {
+    let body = ureq::get(LATEST_RELEASE_URL)
+        .set("User-Agent", concat!("syskill/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+
+    let release: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    release["tag_name"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "release has no tag name".to_string())
+}
+
+#[cfg(not(feature = "update-check"))]
+pub fn latest_release() -> Result<String, String> {
+    Err("this build has no update check (the `update-check` feature)".to_string())
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+pub fn check() -> UpdateStatus {
+    match latest_release() {
+        Ok(tag) if parse_version(&tag) > parse_version(VERSION) => UpdateStatus::Available(tag),
+        Ok(_) => UpdateStatus::UpToDate,
+        Err(e) => UpdateStatus::Failed(e),
+    }
+}
+
+/// Runs [`check`] on a background thread so the UI is not held up by the network.
+pub fn check_in_background() -> Receiver<UpdateStatus> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(check());
+    });
+    receiver
+}
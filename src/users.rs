@@ -0,0 +1,84 @@
+//! Restricting the process list to one user (`--user NAME` / `--mine`).
+
+use sysinfo::{Process, ProcessRefreshKind, System, Uid, UpdateKind, Users};
+
+#[derive(Clone)]
+pub struct UserFilter {
+    uid: Uid,
+    pub name: String,
+}
+
+impl UserFilter {
+    /// Builds the filter requested on the command line, if any.
+    pub fn from_cli(user: Option<&str>, mine: bool) -> Result<Option<Self>, String> {
+        let users = Users::new_with_refreshed_list();
+        if let Some(name) = user {
+            return users
+                .iter()
+                .find(|user| user.name() == name)
+                .map(|user| {
+                    Some(Self {
+                        uid: user.id().clone(),
+                        name: name.to_string(),
+                    })
+                })
+                .ok_or_else(|| format!("no such user: {name}"));
+        }
+        if !mine {
+            return Ok(None);
+        }
+
+        let pid = sysinfo::get_current_pid()?;
+        let mut system = System::new();
+        system.refresh_process_specifics(
+            pid,
+            ProcessRefreshKind::new().with_user(UpdateKind::Always),
+        );
+        let uid = system
+            .process(pid)
+            .and_then(Process::user_id)
+            .cloned()
+            .ok_or("cannot determine the current user")?;
+        let name = users
+            .get_user_by_id(&uid)
+            .map_or_else(|| uid.to_string(), |user| user.name().to_string());
+        Ok(Some(Self { uid, name }))
+    }
+
+    pub fn matches(&self, process: &Process) -> bool {
+        process.user_id() == Some(&self.uid)
+    }
+}
+
+/// What to refresh about processes; like `refresh_processes` but also picks up the owner of
+/// processes started since the last refresh.
+pub fn refresh_kind() -> ProcessRefreshKind {
+    ProcessRefreshKind::new()
+        .with_memory()
+        .with_cpu()
+        .with_disk_usage()
+        .with_exe(UpdateKind::OnlyIfNotSet)
+        .with_user(UpdateKind::OnlyIfNotSet)
+}
+
+/// Name of the user owning `process`, its numeric id if the user is unknown, or an empty string
+/// if the owner cannot be read.
+pub fn name(users: &Users, process: &Process) -> String {
+    process.user_id().map_or_else(String::new, |uid| {
+        users
+            .get_user_by_id(uid)
+            .map_or_else(|| uid.to_string(), |user| user.name().to_string())
+    })
+}
+
+/// Name of the user owning `pid`, or its numeric id if the user is unknown.
+pub fn owner(pid: sysinfo::Pid) -> Option<String> {
+    let mut system = System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new().with_user(UpdateKind::Always));
+    let uid = system.process(pid)?.user_id()?.clone();
+    Some(
+        Users::new_with_refreshed_list()
+            .get_user_by_id(&uid)
+            .map_or_else(|| uid.to_string(), |user| user.name().to_string()),
+    )
+}
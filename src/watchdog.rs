@@ -0,0 +1,332 @@
+//! Watchdog: rules that signal processes on their own, e.g. SIGTERM to any `java` above 90% CPU
+//! for 30 seconds, so syskill can be left running to keep a machine in check.
+//!
+//! ```toml
+//! [[watchdog]]
+//! name = "runaway java"
+//! when = "name ~ java && cpu > 90"
+//! for = "30s"
+//! signal = "TERM"
+//! ```
+//!
+//! `when` is a search of the table (see [`crate::query`]), `for` how long a process has to match
+//! it without a break, and `signal` what it is then sent, `TERM` if left out. Rules can also be
+//! added and edited in the table as `<when> for <delay> then <signal>`, which saves them to the
+//! config file. Every trigger is logged; in dry-run mode the log is all that happens.
+
+use crate::app::ProcessInfo;
+use crate::proc::ProcessKey;
+use crate::query::Query;
+use crate::units::Units;
+use crate::{queue, signal};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use sysinfo::Signal;
+
+/// How many triggers the log keeps, the latest last.
+const KEPT_TRIGGERS: usize = 100;
+
+/// A rule as written in the config file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    /// Shown in the log; defaults to the rule itself.
+    pub name: Option<String>,
+    pub when: String,
+    /// E.g. `30s` or `5m`; right away if left out.
+    #[serde(rename = "for")]
+    pub sustain: Option<String>,
+    /// E.g. `TERM` or `SIGKILL`.
+    pub signal: Option<String>,
+}
+
+pub struct Rule {
+    name: Option<String>,
+    when: String,
+    query: Query,
+    sustain: Duration,
+    pub signal: Signal,
+}
+
+impl Rule {
+    pub fn from_spec(spec: &RuleSpec) -> Result<Self, String> {
+        Self::new(
+            spec.name.clone(),
+            &spec.when,
+            spec.sustain.as_deref(),
+            spec.signal.as_deref(),
+        )
+    }
+
+    /// Parses a rule as typed in the table, e.g. `name ~ java && cpu > 90 for 30s then KILL`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (rest, signal) = split_at(input, " then ", |signal| {
+            signal::parse(signal.trim()).is_some()
+        });
+        let (when, sustain) =
+            split_at(rest, " for ", |sustain| queue::parse_delay(sustain).is_ok());
+        Self::new(None, when, sustain, signal)
+    }
+
+    fn new(
+        name: Option<String>,
+        when: &str,
+        sustain: Option<&str>,
+        signal: Option<&str>,
+    ) -> Result<Self, String> {
+        let when = when.trim();
+        if when.is_empty() {
+            return Err("A rule needs a condition, e.g. cpu > 90".to_string());
+        }
+        let query = Query::parse(when).map_err(|e| format!("{when}: {e}"))?;
+        let sustain = match sustain {
+            Some(sustain) => queue::parse_delay(sustain)?,
+            None => Duration::ZERO,
+        };
+        let signal = match signal.map(str::trim) {
+            Some(name) => signal::parse(name).ok_or_else(|| format!("Unknown signal {name}"))?,
+            None => Signal::Term,
+        };
+        Ok(Self {
+            name,
+            when: when.to_string(),
+            query,
+            sustain,
+            signal,
+        })
+    }
+
+    /// The search processes have to match.
+    pub fn condition(&self) -> &str {
+        &self.when
+    }
+
+    /// E.g. `cpu > 90 for 30s then SIGTERM`, or the name of the rule.
+    pub fn describe(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.typed(),
+        }
+    }
+
+    /// The rule as typed in the table, e.g. `cpu > 90 for 30s then SIGTERM`.
+    pub fn typed(&self) -> String {
+        let signal = signal::name(self.signal);
+        match self.sustain() {
+            Some(sustain) => format!("{} for {sustain} then SIG{signal}", self.when),
+            None => format!("{} then SIG{signal}", self.when),
+        }
+    }
+
+    /// E.g. `30s`; `None` for right away.
+    fn sustain(&self) -> Option<String> {
+        (!self.sustain.is_zero()).then(|| format!("{}s", self.sustain.as_secs_f64()))
+    }
+
+    /// The rule as written in the config file.
+    pub fn spec(&self) -> RuleSpec {
+        RuleSpec {
+            name: self.name.clone(),
+            when: self.when.clone(),
+            sustain: self.sustain(),
+            signal: Some(signal::name(self.signal)),
+        }
+    }
+
+    /// Takes over the name of the rule it replaces, which cannot be typed.
+    pub fn named(self, name: Option<String>) -> Self {
+        Self { name, ..self }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// Splits `input` at the last `keyword` outside quotes that `follows` accepts what comes after,
+/// or else at the last one outside quotes, so that a keyword within a quoted condition, e.g.
+/// `cmd ~ "sleep for 30s"`, stays there, and one that is not in it reports what follows.
+fn split_at<'a>(
+    input: &'a str,
+    keyword: &str,
+    follows: impl Fn(&str) -> bool,
+) -> (&'a str, Option<&'a str>) {
+    let splits: Vec<(&str, &str)> = input
+        .rmatch_indices(keyword)
+        .map(|(i, _)| (&input[..i], &input[i + keyword.len()..]))
+        .filter(|(condition, _)| condition.matches('"').count() % 2 == 0)
+        .collect();
+    splits
+        .iter()
+        .find(|(_, rest)| follows(rest))
+        .or(splits.first())
+        .map_or((input, None), |&(condition, rest)| (condition, Some(rest)))
+}
+
+/// A rule that went off.
+#[derive(Clone)]
+pub struct Trigger {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub rule: String,
+    pub pid: u32,
+    pub name: String,
+    /// What was done, or why nothing was.
+    pub outcome: String,
+}
+
+#[derive(Default)]
+pub struct Watchdog {
+    pub rules: Vec<Rule>,
+    /// Only log what the rules would do.
+    pub dry_run: bool,
+    log: Vec<Trigger>,
+    /// Since when each process has matched each rule, by rule index.
+    matching: HashMap<(usize, ProcessKey), Instant>,
+    /// Processes a rule went off for, until they stop matching it.
+    fired: HashSet<(usize, ProcessKey)>,
+}
+
+impl Watchdog {
+    /// The rules that went off in `processes`, with the key and name of the process they went off
+    /// for. A rule goes off once per process for as long as the process keeps matching it, and
+    /// never for init or syskill itself, which a broad rule would otherwise take down.
+    pub fn check(
+        &mut self,
+        processes: &[ProcessInfo],
+        units: Units,
+        now: Instant,
+    ) -> Vec<(usize, ProcessKey, String)> {
+        let mut due = Vec::new();
+        let mut still = HashSet::new();
+        let spared = [1, std::process::id()];
+        for (index, rule) in self.rules.iter().enumerate() {
+            for data in processes {
+                if data.exited.is_some()
+                    || data.members > 0
+                    || spared.contains(&data.pid)
+                    || !rule.query.matches(data, units)
+                {
+                    continue;
+                }
+                let key = (index, data.key());
+                still.insert(key);
+                let since = *self.matching.entry(key).or_insert(now);
+                if now - since >= rule.sustain && self.fired.insert(key) {
+                    due.push((index, key.1, data.name.clone()));
+                }
+            }
+        }
+        self.matching.retain(|key, _| still.contains(key));
+        self.fired.retain(|key| still.contains(key));
+        due
+    }
+
+    /// Switches dry-run mode, letting the rules go off again for processes they only went off
+    /// for in the other mode.
+    pub fn toggle_dry_run(&mut self) {
+        self.dry_run = !self.dry_run;
+        self.fired.clear();
+    }
+
+    pub fn record(&mut self, trigger: Trigger) {
+        if self.log.len() == KEPT_TRIGGERS {
+            self.log.remove(0);
+        }
+        self.log.push(trigger);
+    }
+
+    /// Oldest first.
+    pub fn log(&self) -> &[Trigger] {
+        &self.log
+    }
+
+    /// Puts `rule` in the place of the `index`th rule, which starts over for every process.
+    pub fn replace(&mut self, index: usize, rule: Rule) {
+        if index >= self.rules.len() {
+            return;
+        }
+        self.rules[index] = rule;
+        self.matching.retain(|&(rule, _), _| rule != index);
+        self.fired.retain(|&(rule, _)| rule != index);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.rules.len() {
+            return;
+        }
+        self.rules.remove(index);
+        // Later rules move up by one.
+        let moved = |(rule, key): (usize, ProcessKey)| {
+            (rule != index).then(|| (rule - usize::from(rule > index), key))
+        };
+        self.matching = std::mem::take(&mut self.matching)
+            .into_iter()
+            .filter_map(|(key, since)| Some((moved(key)?, since)))
+            .collect();
+        self.fired = std::mem::take(&mut self.fired)
+            .into_iter()
+            .filter_map(moved)
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::ProcessSample;
+
+    #[test]
+    fn rules_never_go_off_for_init_or_syskill() {
+        let processes = [1, std::process::id(), 5_000_000].map(|pid| {
+            ProcessInfo::from(ProcessSample {
+                pid,
+                name: "worker".to_string(),
+                start_time: 1_760_623_402,
+                ..Default::default()
+            })
+        });
+        let mut watchdog = Watchdog {
+            rules: vec![Rule::parse("pid > 0 then KILL").unwrap()],
+            ..Default::default()
+        };
+        let due = watchdog.check(&processes, Units::default(), Instant::now());
+        let pids: Vec<_> = due.iter().map(|(_, key, _)| key.pid).collect();
+        assert_eq!(pids, [5_000_000]);
+    }
+
+    #[test]
+    fn keywords_in_quoted_conditions_stay_there() {
+        let typed = |input| Rule::parse(input).map(|rule| rule.typed());
+        assert_eq!(
+            typed("name ~ java && cpu > 90 for 30s then KILL").unwrap(),
+            "name ~ java && cpu > 90 for 30s then SIGKILL"
+        );
+        assert_eq!(
+            typed(r#"cmd ~ "sleep for 30s""#).unwrap(),
+            r#"cmd ~ "sleep for 30s" then SIGTERM"#
+        );
+        assert_eq!(
+            typed(r#"cmd ~ "wait then KILL" for 1m"#).unwrap(),
+            r#"cmd ~ "wait then KILL" for 60s then SIGTERM"#
+        );
+        assert_eq!(
+            typed(r#"cmd ~ "a for 5s then HUP" for 5s then HUP"#).unwrap(),
+            r#"cmd ~ "a for 5s then HUP" for 5s then SIGHUP"#
+        );
+        // Unquoted, the last keyword followed by a delay or a signal is the one.
+        assert_eq!(
+            typed("name ~ then then KILL").unwrap(),
+            "name ~ then then SIGKILL"
+        );
+        assert_eq!(
+            typed("cpu > 90 then NOPE").unwrap_err(),
+            "Unknown signal NOPE"
+        );
+        assert_eq!(
+            typed("cpu > 90 for soon").unwrap_err(),
+            "Not a delay: soon (e.g. 30s or 5m)"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! What Windows has in place of signals, nice values and `/proc`: window titles, priority classes
+//! and the close requests of `taskkill`. Windows only.
+
+use std::collections::HashMap;
+use std::io;
+use windows_sys::Win32::Foundation::{CloseHandle, BOOL, HANDLE, HWND, LPARAM};
+use windows_sys::Win32::System::Threading::{
+    GetPriorityClass, OpenProcess, SetPriorityClass, PROCESS_ACCESS_RIGHTS,
+    PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+};
+
+/// Title of the first visible window of each process that has one, by PID.
+pub fn window_titles() -> HashMap<u32, String> {
+    let mut titles = HashMap::new();
+    // SAFETY: the callback only runs during the call, while `titles` is borrowed.
+    unsafe { EnumWindows(Some(add_title), &mut titles as *mut _ as LPARAM) };
+    titles
+}
+
+unsafe extern "system" fn add_title(window: HWND, titles: LPARAM) -> BOOL {
+    // SAFETY: `window_titles` passes a map that outlives the enumeration.
+    let titles = unsafe { &mut *(titles as *mut HashMap<u32, String>) };
+    // SAFETY: the window handles come from EnumWindows, the buffer is as long as passed.
+    unsafe {
+        let length = GetWindowTextLengthW(window);
+        if IsWindowVisible(window) == 0 || length <= 0 {
+            return 1;
+        }
+        let mut pid = 0;
+        GetWindowThreadProcessId(window, &mut pid);
+        let mut text = vec![0u16; length as usize + 1];
+        let copied = GetWindowTextW(window, text.as_mut_ptr(), text.len() as i32);
+        if copied > 0 {
+            titles
+                .entry(pid)
+                .or_insert_with(|| String::from_utf16_lossy(&text[..copied as usize]));
+        }
+    }
+    // Carry on with the next window.
+    1
+}
+
+/// An open handle to a process, closed when dropped.
+struct Process(HANDLE);
+
+impl Process {
+    fn open(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> io::Result<Self> {
+        // SAFETY: OpenProcess has no memory safety requirements.
+        match unsafe { OpenProcess(access, 0, pid) } {
+            0 => Err(io::Error::last_os_error()),
+            handle => Ok(Self(handle)),
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // SAFETY: the handle is open and closed only here.
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// The priority class of `pid`, e.g. `NORMAL_PRIORITY_CLASS`.
+pub fn priority_class(pid: u32) -> io::Result<u32> {
+    let process = Process::open(pid, PROCESS_QUERY_LIMITED_INFORMATION)?;
+    // SAFETY: the handle is open.
+    match unsafe { GetPriorityClass(process.0) } {
+        0 => Err(io::Error::last_os_error()),
+        class => Ok(class),
+    }
+}
+
+pub fn set_priority_class(pid: u32, class: u32) -> io::Result<()> {
+    let process = Process::open(pid, PROCESS_SET_INFORMATION)?;
+    // SAFETY: the handle is open.
+    if unsafe { SetPriorityClass(process.0, class) } == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Asks `pid` to close its windows, as `taskkill /PID <pid>` without `/F` does: programs get to
+/// save their work, and those without windows, such as console programs, are left running.
+pub fn close(pid: u32) -> io::Result<()> {
+    let output = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    // E.g. `ERROR: The process with PID 1234 could not be terminated.` and the reason below.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .map(|line| line.trim().trim_start_matches("ERROR: "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Err(io::Error::other(if reason.is_empty() {
+        format!("taskkill {}", output.status)
+    } else {
+        reason
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows_sys::Win32::System::Threading::BELOW_NORMAL_PRIORITY_CLASS;
+
+    #[test]
+    fn priority_class_is_set_and_read_back() {
+        let pid = std::process::id();
+        let class = priority_class(pid).unwrap();
+        set_priority_class(pid, BELOW_NORMAL_PRIORITY_CLASS).unwrap();
+        assert_eq!(priority_class(pid).unwrap(), BELOW_NORMAL_PRIORITY_CLASS);
+        set_priority_class(pid, class).unwrap();
+        assert_eq!(priority_class(pid).unwrap(), class);
+    }
+
+    #[test]
+    fn missing_processes_are_reported() {
+        // The System Idle Process cannot be opened.
+        assert!(priority_class(0).is_err());
+        let e = close(0xFFFF_FFF0).unwrap_err();
+        assert!(!e.to_string().is_empty());
+    }
+
+    #[test]
+    fn window_titles_leave_out_processes_without_windows() {
+        // The test runs in a console it does not own.
+        let titles = window_titles();
+        assert!(!titles.contains_key(&std::process::id()));
+        assert!(titles.values().all(|title| !title.is_empty()));
+    }
+}